@@ -0,0 +1,3 @@
+module uk.co.palmr.classfileparser {
+    exports uk.co.palmr.classfileparser;
+}
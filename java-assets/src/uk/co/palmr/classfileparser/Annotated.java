@@ -0,0 +1,23 @@
+package uk.co.palmr.classfileparser;
+
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Annotated.Info(id = 1, tags = {"a", "b"}, type = String.class, kind = ElementType.TYPE)
+@Deprecated
+public class Annotated {
+    @Retention(RetentionPolicy.RUNTIME)
+    public @interface Info {
+        int id();
+        String[] tags() default {};
+        Class<?> type() default Object.class;
+        ElementType kind() default ElementType.METHOD;
+        Retention nested() default @Retention(RetentionPolicy.CLASS);
+    }
+
+    @Info(id = 2, nested = @Retention(RetentionPolicy.SOURCE))
+    public void annotated(@Info(id = 3) int param, long other) {}
+
+    public void plain() {}
+}
@@ -0,0 +1,15 @@
+package uk.co.palmr.classfileparser;
+
+public class Constants {
+    public static final int ANSWER = 42;
+    public static final boolean ENABLED = true;
+    public static final long BIG = 1L << 40;
+    public static final float HALF = 0.5f;
+    public static final double RATE = 2.5;
+    public static final String GREETING = "Hello";
+
+    // Not compile-time constants
+    public static final String COMPUTED = String.valueOf(ANSWER);
+    public static int counter = 7;
+    public final int instance = 3;
+}
@@ -0,0 +1,32 @@
+package uk.co.palmr.classfileparser;
+
+import java.util.List;
+import java.util.Map;
+
+public class Generics<T extends Number, U> {
+    public List<String> names;
+    public T value;
+    public U[] others;
+    public Map<String, List<T>> nested;
+    public int plain;
+
+    public Generics(T value) {
+        this.value = value;
+    }
+
+    public <E extends Comparable<E>> E max(List<? extends E> items) {
+        return null;
+    }
+
+    public <A extends B, B extends Runnable> void chained(A a, B b) {
+    }
+
+    public Map.Entry<T, U> entry() throws IllegalStateException {
+        return null;
+    }
+
+    public class Inner {
+        public Inner(List<T> values) {
+        }
+    }
+}
@@ -0,0 +1,23 @@
+package uk.co.palmr.classfileparser;
+
+public class Handlers {
+    public static class Failure extends Exception {
+    }
+
+    public static void check(int value) throws Failure {
+        if (value < 0) {
+            throw new Failure();
+        }
+    }
+
+    public static int attempt(int value) {
+        try {
+            check(value);
+            return 0;
+        } catch (Failure e) {
+            return 1;
+        } finally {
+            System.out.println(value);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+package uk.co.palmr.classfileparser;
+
+public interface Modifiers {
+    int LIMIT = 10;
+
+    void run();
+
+    default String name() {
+        return helper();
+    }
+
+    static Modifiers create() {
+        return null;
+    }
+
+    private String helper() {
+        return "modifiers";
+    }
+
+    enum Level {
+        LOW, HIGH;
+
+        private final int weight;
+
+        Level() {
+            weight = ordinal();
+        }
+
+        public synchronized int weight() {
+            return weight;
+        }
+    }
+
+    abstract class Base<T> implements Comparable<T> {
+        protected transient volatile int count;
+
+        protected abstract void step();
+
+        static native void poke();
+    }
+}
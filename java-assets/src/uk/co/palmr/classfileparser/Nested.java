@@ -0,0 +1,22 @@
+package uk.co.palmr.classfileparser;
+
+public class Nested {
+    public class Inner {
+        public class Deeper {}
+    }
+
+    public static class StaticMember {}
+
+    public Runnable make() {
+        class Local implements Runnable {
+            public void run() {}
+        }
+        return new Local();
+    }
+
+    public Runnable anonymous() {
+        return new Runnable() {
+            public void run() {}
+        };
+    }
+}
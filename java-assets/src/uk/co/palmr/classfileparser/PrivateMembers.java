@@ -0,0 +1,32 @@
+package uk.co.palmr.classfileparser;
+
+import java.io.Serializable;
+import java.util.function.Supplier;
+
+public class PrivateMembers implements Serializable {
+    private static final long serialVersionUID = 1L;
+
+    private int used = 1;
+    private int unused;
+
+    public PrivateMembers() {
+    }
+
+    private PrivateMembers(int ignored) {
+    }
+
+    public int get() {
+        return used + helper();
+    }
+
+    public Supplier<String> supplier() {
+        return () -> "lambda";
+    }
+
+    private int helper() {
+        return 2;
+    }
+
+    private void neverCalled() {
+    }
+}
@@ -0,0 +1,17 @@
+package uk.co.palmr.classfileparser;
+
+import java.util.concurrent.TimeUnit;
+
+public class SwitchMap {
+  public static String describe(TimeUnit unit) {
+    switch (unit) {
+      case SECONDS: return "s";
+      case MINUTES: return "m";
+      default: return "?";
+    }
+  }
+
+  public static Runnable lambda() {
+    return () -> System.out.println("hi");
+  }
+}
@@ -0,0 +1,166 @@
+//! The externally visible surface of a class, for comparing two versions of a library.
+//!
+//! Only the public and protected members are kept, and everything refers to names rather than
+//! constant pool indices, so a [`ClassApi`] doesn't depend on the class file data and two of them
+//! can be compared directly.
+
+use std::borrow::Cow;
+
+use crate::attribute_info::{ExceptionsAttribute, HasAttributes, SignatureAttribute};
+use crate::constant_info::{ClassConstant, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::field_info::FieldAccessFlags;
+use crate::method_info::MethodAccessFlags;
+use crate::{ClassAccessFlags, ClassFile, LoadError};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassApi {
+    /// The internal name of the class
+    pub name: String,
+    pub access_flags: ClassAccessFlags,
+    /// The internal name of the superclass, which is None for `java/lang/Object` and modules
+    pub super_class: Option<String>,
+    /// The internal names of the directly implemented interfaces, sorted
+    pub interfaces: Vec<String>,
+    pub signature: Option<String>,
+    /// The public and protected fields, sorted by name and descriptor
+    pub fields: Vec<FieldApi>,
+    /// The public and protected methods, sorted by name and descriptor
+    pub methods: Vec<MethodApi>,
+}
+impl ClassApi {
+    pub fn field(&self, name: &str, descriptor: &str) -> Option<&FieldApi> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name && field.descriptor == descriptor)
+    }
+
+    pub fn method(&self, name: &str, descriptor: &str) -> Option<&MethodApi> {
+        self.methods
+            .iter()
+            .find(|method| method.name == name && method.descriptor == descriptor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FieldApi {
+    pub name: String,
+    pub descriptor: String,
+    pub signature: Option<String>,
+    pub access_flags: FieldAccessFlags,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MethodApi {
+    pub name: String,
+    pub descriptor: String,
+    pub signature: Option<String>,
+    pub access_flags: MethodAccessFlags,
+    /// The internal names of the checked exceptions in the throws clause, sorted
+    pub exceptions: Vec<String>,
+}
+
+fn utf8(
+    pool: &ConstantPool,
+    data: &[u8],
+    index: ConstantPoolIndexRaw<Utf8Constant>,
+) -> Result<String, LoadError> {
+    pool.get_utf8_text(index, data)
+        .map(Cow::into_owned)
+        .ok_or(LoadError::Unknown)
+}
+
+fn class_name(
+    pool: &ConstantPool,
+    data: &[u8],
+    index: ConstantPoolIndexRaw<ClassConstant>,
+) -> Result<String, LoadError> {
+    pool.get_class_name(index, data)
+        .map(Cow::into_owned)
+        .ok_or(LoadError::Unknown)
+}
+
+fn signature(
+    owner: &impl HasAttributes,
+    pool: &ConstantPool,
+    data: &[u8],
+) -> Result<Option<String>, LoadError> {
+    owner
+        .find_attribute::<SignatureAttribute>(pool, data)?
+        .map(|attr| utf8(pool, data, attr.signature_index))
+        .transpose()
+}
+
+impl ClassFile {
+    /// Extract the externally visible surface of the class: its own names and flags, and the
+    /// public and protected fields and methods.
+    /// Errors if a name or one of the attributes it reads is malformed.
+    pub fn api(&self, data: &[u8]) -> Result<ClassApi, LoadError> {
+        let pool = &self.const_pool;
+
+        let super_class = if self.super_class.is_zero() {
+            None
+        } else {
+            Some(class_name(pool, data, self.super_class)?)
+        };
+        let mut interfaces = self
+            .interfaces
+            .iter()
+            .map(|&index| class_name(pool, data, index))
+            .collect::<Result<Vec<_>, _>>()?;
+        interfaces.sort();
+
+        let mut fields = Vec::new();
+        for field in self.fields.iter() {
+            let flags = field.access_flags;
+            if !flags.intersects(FieldAccessFlags::PUBLIC | FieldAccessFlags::PROTECTED) {
+                continue;
+            }
+
+            fields.push(FieldApi {
+                name: utf8(pool, data, field.name_index)?,
+                descriptor: utf8(pool, data, field.descriptor_index)?,
+                signature: signature(field, pool, data)?,
+                access_flags: flags,
+            });
+        }
+        fields.sort();
+
+        let mut methods = Vec::new();
+        for method in self.methods.iter() {
+            let flags = method.access_flags;
+            if !flags.intersects(MethodAccessFlags::PUBLIC | MethodAccessFlags::PROTECTED) {
+                continue;
+            }
+
+            let mut exceptions = match method.find_attribute::<ExceptionsAttribute>(pool, data)? {
+                Some(attr) => attr
+                    .exception_table
+                    .iter()
+                    .map(|&index| class_name(pool, data, index))
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => Vec::new(),
+            };
+            exceptions.sort();
+
+            methods.push(MethodApi {
+                name: utf8(pool, data, method.name_index)?,
+                descriptor: utf8(pool, data, method.descriptor_index)?,
+                signature: signature(method, pool, data)?,
+                access_flags: flags,
+                exceptions,
+            });
+        }
+        methods.sort();
+
+        Ok(ClassApi {
+            name: class_name(pool, data, self.this_class)?,
+            access_flags: self.access_flags,
+            super_class,
+            interfaces,
+            signature: signature(self, pool, data)?,
+            fields,
+            methods,
+        })
+    }
+}
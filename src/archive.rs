@@ -0,0 +1,410 @@
+//! Reading class files out of archives without extracting them to disk.
+//!
+//! This supports jars, which are zip archives with their classes stored by path, and `.jmod` files,
+//! which are zip archives with a small header in front and with their classes stored under the
+//! `classes/` directory.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::error::ParseError;
+use crate::{ClassFile, ClassFileOpt, ClassFileVersion, ParseOptions};
+
+/// The magic bytes and version that start every jmod file
+pub const JMOD_HEADER: &[u8] = &[b'J', b'M', 0x01, 0x00];
+/// The directory inside of a jmod that the class files are stored under
+pub const JMOD_CLASSES_PREFIX: &str = "classes/";
+/// The directory inside of a jar that holds its metadata, including the classes for other Java
+/// versions in multi-release jars
+pub const JAR_META_INF_PREFIX: &str = "META-INF/";
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    Zip(ZipError),
+    /// The file did not start with the jmod header
+    NotJmod,
+    /// The entry was read from the archive but could not be parsed as a class file
+    Parse {
+        entry: String,
+        error: ParseError,
+    },
+}
+impl From<io::Error> for ArchiveError {
+    fn from(err: io::Error) -> Self {
+        ArchiveError::Io(err)
+    }
+}
+impl From<ZipError> for ArchiveError {
+    fn from(err: ZipError) -> Self {
+        ArchiveError::Zip(err)
+    }
+}
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::Io(err) => write!(f, "io error: {}", err),
+            ArchiveError::Zip(err) => write!(f, "zip error: {}", err),
+            ArchiveError::NotJmod => f.write_str("missing jmod header"),
+            ArchiveError::Parse { entry, error } => {
+                write!(f, "failed to parse class entry {}: {}", entry, error)
+            }
+        }
+    }
+}
+impl std::error::Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArchiveError::Io(err) => Some(err),
+            ArchiveError::Zip(err) => Some(err),
+            ArchiveError::NotJmod => None,
+            ArchiveError::Parse { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Convert the path of an entry in a jmod to the internal name of the class it holds
+/// Returns None if the entry is not a class file.
+pub fn jmod_entry_class_name(entry: &str) -> Option<&str> {
+    entry
+        .strip_prefix(JMOD_CLASSES_PREFIX)?
+        .strip_suffix(".class")
+}
+
+/// Convert the path of an entry in a jar to the internal name of the class it holds
+/// Returns None if the entry is not a class file, or is under `META-INF/`, such as the versioned
+/// classes of a multi-release jar.
+pub fn jar_entry_class_name(entry: &str) -> Option<&str> {
+    if entry.starts_with(JAR_META_INF_PREFIX) {
+        return None;
+    }
+    entry.strip_suffix(".class")
+}
+
+/// A reader which hides the jmod header from the zip reader, so that the offsets stored in the
+/// zip are relative to the start of the reader.
+struct SkipHeader<R> {
+    inner: R,
+}
+const JMOD_HEADER_LEN: u64 = JMOD_HEADER.len() as u64;
+
+impl<R: Read + Seek> SkipHeader<R> {
+    fn new(mut inner: R) -> Result<SkipHeader<R>, ArchiveError> {
+        let mut header = [0u8; JMOD_HEADER.len()];
+        inner.seek(SeekFrom::Start(0))?;
+        inner.read_exact(&mut header).map_err(|err| {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                ArchiveError::NotJmod
+            } else {
+                ArchiveError::Io(err)
+            }
+        })?;
+        if header != JMOD_HEADER {
+            return Err(ArchiveError::NotJmod);
+        }
+
+        Ok(SkipHeader { inner })
+    }
+}
+impl<R: Read> Read for SkipHeader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+impl<R: Seek> Seek for SkipHeader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => SeekFrom::Start(offset + JMOD_HEADER_LEN),
+            pos => pos,
+        };
+        let actual = self.inner.seek(pos)?;
+        actual.checked_sub(JMOD_HEADER_LEN).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seeked into the jmod header")
+        })
+    }
+}
+
+/// A class file read out of an archive, along with the bytes that it was parsed from.
+#[derive(Debug, Clone)]
+pub struct ClassEntry {
+    /// The internal name of the class, derived from the entry path
+    pub name: String,
+    pub data: Vec<u8>,
+}
+impl ClassEntry {
+    pub fn parse(&self, options: &ParseOptions) -> Result<ClassFile, ArchiveError> {
+        ClassFile::parse(&self.data, options).map_err(|error| self.parse_error(error))
+    }
+
+    pub fn parse_opt(&self, options: &ParseOptions) -> Result<ClassFileOpt, ArchiveError> {
+        ClassFileOpt::parse(&self.data, options).map_err(|error| self.parse_error(error))
+    }
+
+    fn parse_error(&self, error: ParseError) -> ArchiveError {
+        ArchiveError::Parse {
+            entry: self.name.clone(),
+            error,
+        }
+    }
+}
+
+/// Iterates over the class files within a jmod
+pub struct JmodClassReader<R: Read + Seek> {
+    archive: ZipArchive<SkipHeader<R>>,
+}
+impl JmodClassReader<File> {
+    pub fn open(path: impl AsRef<Path>) -> Result<JmodClassReader<File>, ArchiveError> {
+        JmodClassReader::new(File::open(path)?)
+    }
+}
+impl<R: Read + Seek> JmodClassReader<R> {
+    pub fn new(reader: R) -> Result<JmodClassReader<R>, ArchiveError> {
+        let archive = ZipArchive::new(SkipHeader::new(reader)?)?;
+        Ok(JmodClassReader { archive })
+    }
+
+    /// The internal names of every class within the jmod, in no particular order
+    pub fn class_names(&self) -> impl Iterator<Item = &str> {
+        self.archive.file_names().filter_map(jmod_entry_class_name)
+    }
+
+    /// Read the class with the given internal name, if it exists
+    pub fn read_class(&mut self, name: &str) -> Result<Option<ClassEntry>, ArchiveError> {
+        let path = format!("{}{}.class", JMOD_CLASSES_PREFIX, name);
+        read_class_entry(&mut self.archive, &path, name)
+    }
+
+    /// Iterate over every class entry, reading each one as it is reached
+    pub fn classes(&mut self) -> ClassEntries<'_, R> {
+        ClassEntries {
+            archive: &mut self.archive,
+            index: 0,
+        }
+    }
+}
+
+/// An iterator over the class entries of an archive, see [`JmodClassReader::classes`]
+pub struct ClassEntries<'a, R: Read + Seek> {
+    archive: &'a mut ZipArchive<SkipHeader<R>>,
+    index: usize,
+}
+impl<'a, R: Read + Seek> Iterator for ClassEntries<'a, R> {
+    type Item = Result<ClassEntry, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        next_class_entry(self.archive, &mut self.index, jmod_entry_class_name)
+    }
+}
+
+/// Iterates over the class files within a jar
+pub struct JarClassReader<R: Read + Seek> {
+    archive: ZipArchive<R>,
+}
+impl JarClassReader<File> {
+    pub fn open(path: impl AsRef<Path>) -> Result<JarClassReader<File>, ArchiveError> {
+        JarClassReader::new(File::open(path)?)
+    }
+}
+impl<R: Read + Seek> JarClassReader<R> {
+    pub fn new(reader: R) -> Result<JarClassReader<R>, ArchiveError> {
+        let archive = ZipArchive::new(reader)?;
+        Ok(JarClassReader { archive })
+    }
+
+    /// The internal names of every class within the jar, in no particular order
+    pub fn class_names(&self) -> impl Iterator<Item = &str> {
+        self.archive.file_names().filter_map(jar_entry_class_name)
+    }
+
+    /// Read the class with the given internal name, if it exists
+    pub fn read_class(&mut self, name: &str) -> Result<Option<ClassEntry>, ArchiveError> {
+        let path = format!("{}.class", name);
+        read_class_entry(&mut self.archive, &path, name)
+    }
+
+    /// Iterate over every class entry, reading each one as it is reached
+    pub fn classes(&mut self) -> JarClassEntries<'_, R> {
+        JarClassEntries {
+            archive: &mut self.archive,
+            index: 0,
+        }
+    }
+}
+
+/// An iterator over the class entries of a jar, see [`JarClassReader::classes`]
+pub struct JarClassEntries<'a, R: Read + Seek> {
+    archive: &'a mut ZipArchive<R>,
+    index: usize,
+}
+impl<'a, R: Read + Seek> Iterator for JarClassEntries<'a, R> {
+    type Item = Result<ClassEntry, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        next_class_entry(self.archive, &mut self.index, jar_entry_class_name)
+    }
+}
+
+fn read_class_entry<A: Read + Seek>(
+    archive: &mut ZipArchive<A>,
+    path: &str,
+    name: &str,
+) -> Result<Option<ClassEntry>, ArchiveError> {
+    let mut file = match archive.by_name(path) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    // The size comes from the archive, so it isn't trusted for preallocating
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    Ok(Some(ClassEntry {
+        name: name.to_string(),
+        data,
+    }))
+}
+
+/// Read the first class entry at or after `index`, leaving `index` after it
+fn next_class_entry<A: Read + Seek>(
+    archive: &mut ZipArchive<A>,
+    index: &mut usize,
+    entry_class_name: fn(&str) -> Option<&str>,
+) -> Option<Result<ClassEntry, ArchiveError>> {
+    while *index < archive.len() {
+        let current = *index;
+        *index += 1;
+
+        let mut file = match archive.by_index(current) {
+            Ok(file) => file,
+            Err(err) => return Some(Err(err.into())),
+        };
+        let name = match entry_class_name(file.name()) {
+            Some(name) if file.is_file() => name.to_string(),
+            _ => continue,
+        };
+
+        let mut data = Vec::new();
+        if let Err(err) = file.read_to_end(&mut data) {
+            return Some(Err(err.into()));
+        }
+
+        return Some(Ok(ClassEntry { name, data }));
+    }
+
+    None
+}
+
+/// A fingerprint of the bytes of a class, for telling apart copies of a class with the same name.
+/// This is the 64-bit FNV-1a hash, which is fast but not cryptographic.
+pub fn class_fingerprint(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Where a copy of a class was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassOrigin {
+    /// The archive or directory that the class was in, as named by the caller
+    pub source: String,
+    /// The path of the class within its source
+    pub entry: String,
+    /// The version from the class file's header, or None if the data is too short to have one
+    pub version: Option<ClassFileVersion>,
+    /// See [`class_fingerprint`]
+    pub fingerprint: u64,
+}
+
+/// A class name that was found more than once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateClass {
+    /// The internal name of the class
+    pub name: String,
+    /// Every copy of the class, in the order they were added
+    pub origins: Vec<ClassOrigin>,
+}
+impl DuplicateClass {
+    /// Whether every copy has the same bytes, which usually makes the duplication harmless
+    pub fn is_identical(&self) -> bool {
+        self.origins
+            .windows(2)
+            .all(|pair| pair[0].fingerprint == pair[1].fingerprint)
+    }
+}
+
+/// Collects the classes of several archives, such as the jars on a classpath, to find the class
+/// names which appear more than once.
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateClassDetector {
+    classes: HashMap<String, Vec<ClassOrigin>>,
+}
+impl DuplicateClassDetector {
+    pub fn new() -> DuplicateClassDetector {
+        DuplicateClassDetector::default()
+    }
+
+    /// Record a class which was read from somewhere else, such as a directory
+    pub fn add_class(&mut self, name: &str, source: &str, entry: &str, data: &[u8]) {
+        let version = (data.len() >= 8).then(|| ClassFileVersion {
+            minor: u16::from_be_bytes([data[4], data[5]]),
+            major: u16::from_be_bytes([data[6], data[7]]),
+        });
+        self.classes
+            .entry(name.to_string())
+            .or_default()
+            .push(ClassOrigin {
+                source: source.to_string(),
+                entry: entry.to_string(),
+                version,
+                fingerprint: class_fingerprint(data),
+            });
+    }
+
+    /// Record every class in the jmod, which is named `source` in the reports
+    pub fn add_jmod<R: Read + Seek>(
+        &mut self,
+        source: &str,
+        reader: &mut JmodClassReader<R>,
+    ) -> Result<(), ArchiveError> {
+        for entry in reader.classes() {
+            let entry = entry?;
+            let path = format!("{}{}.class", JMOD_CLASSES_PREFIX, entry.name);
+            self.add_class(&entry.name, source, &path, &entry.data);
+        }
+        Ok(())
+    }
+
+    /// Record every class in the jar, which is named `source` in the reports
+    pub fn add_jar<R: Read + Seek>(
+        &mut self,
+        source: &str,
+        reader: &mut JarClassReader<R>,
+    ) -> Result<(), ArchiveError> {
+        for entry in reader.classes() {
+            let entry = entry?;
+            let path = format!("{}.class", entry.name);
+            self.add_class(&entry.name, source, &path, &entry.data);
+        }
+        Ok(())
+    }
+
+    /// Every class name which was recorded more than once, sorted by name
+    pub fn duplicates(&self) -> Vec<DuplicateClass> {
+        let mut duplicates: Vec<_> = self
+            .classes
+            .iter()
+            .filter(|(_, origins)| origins.len() > 1)
+            .map(|(name, origins)| DuplicateClass {
+                name: name.clone(),
+                origins: origins.clone(),
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.name.cmp(&b.name));
+        duplicates
+    }
+}
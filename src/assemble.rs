@@ -0,0 +1,287 @@
+//! Decoding code into instructions with absolute branch targets, and encoding it again with
+//! branches that refer to labels. The instructions themselves come from
+//! [`code_iter`](crate::instructions::code_iter).
+//! This is shared by the transforms which change the length of code, since every branch and
+//! switch in the code has to be placed again when they do.
+
+use crate::instructions::{code_iter, Instruction, WideInstruction};
+
+pub(crate) const ACONST_NULL: u8 = 0x01;
+pub(crate) const LDC: u8 = 0x12;
+pub(crate) const LDC_W: u8 = 0x13;
+pub(crate) const GOTO: u8 = 0xa7;
+pub(crate) const JSR: u8 = 0xa8;
+pub(crate) const GOTO_W: u8 = 0xc8;
+pub(crate) const JSR_W: u8 = 0xc9;
+
+/// An instruction, with the operands that may have to change
+#[derive(Debug, Clone)]
+pub(crate) enum Op {
+    /// Copied unchanged
+    Other,
+    /// A conditional branch or a goto, with `goto_w` treated as `goto`
+    Branch {
+        opcode: u8,
+        target: usize,
+    },
+    Jsr {
+        target: usize,
+    },
+    Ret,
+    TableSwitch {
+        default: usize,
+        low: i32,
+        targets: Vec<usize>,
+    },
+    LookupSwitch {
+        default: usize,
+        pairs: Vec<(i32, usize)>,
+    },
+}
+impl Op {
+    /// The operands of the instruction at the offset that may have to change, with its branch
+    /// targets made absolute. None if a target is before the start of the code.
+    fn new(offset: usize, instruction: &Instruction) -> Option<Op> {
+        let target = |relative: i32| usize::try_from(offset as i64 + i64::from(relative)).ok();
+        Some(match instruction {
+            Instruction::Jsr(_) | Instruction::JsrW(_) => Op::Jsr {
+                target: target(instruction.branch_targets()[0])?,
+            },
+            Instruction::GotoW(relative) => Op::Branch {
+                opcode: GOTO,
+                target: target(*relative)?,
+            },
+            Instruction::Ret(_) | Instruction::Wide(WideInstruction::Ret(_)) => Op::Ret,
+            Instruction::Tableswitch {
+                default,
+                low,
+                offsets,
+                ..
+            } => Op::TableSwitch {
+                default: target(*default)?,
+                low: *low,
+                targets: offsets
+                    .iter()
+                    .map(|&relative| target(relative))
+                    .collect::<Option<_>>()?,
+            },
+            Instruction::Lookupswitch { default, pairs } => Op::LookupSwitch {
+                default: target(*default)?,
+                pairs: pairs
+                    .iter()
+                    .map(|&(key, relative)| Some((key, target(relative)?)))
+                    .collect::<Option<_>>()?,
+            },
+            _ => match instruction.branch_targets().as_slice() {
+                &[relative] => Op::Branch {
+                    opcode: instruction.opcode(),
+                    target: target(relative)?,
+                },
+                _ => Op::Other,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Insn {
+    pub offset: usize,
+    pub len: usize,
+    pub instruction: Instruction,
+    pub op: Op,
+}
+impl Insn {
+    /// Whether the instruction is a `goto_w` or `jsr_w`
+    pub fn is_wide_jump(&self) -> bool {
+        matches!(
+            self.instruction,
+            Instruction::GotoW(_) | Instruction::JsrW(_)
+        )
+    }
+}
+
+/// Decode the code into its instructions, returning None if it is malformed
+pub(crate) fn decode(code: &[u8]) -> Option<Vec<Insn>> {
+    let mut insns: Vec<Insn> = Vec::new();
+    for result in code_iter(code) {
+        let (offset, instruction) = result.ok()?;
+        let offset = usize::from(offset);
+        if let Some(last) = insns.last_mut() {
+            last.len = offset - last.offset;
+        }
+        insns.push(Insn {
+            offset,
+            len: code.len() - offset,
+            op: Op::new(offset, &instruction)?,
+            instruction,
+        });
+    }
+    Some(insns)
+}
+
+/// The encoded instructions, with branches referring to labels
+#[derive(Debug, Clone)]
+pub(crate) enum Item {
+    Bind(usize),
+    /// The original offset of the instruction which is emitted next
+    Origin(usize),
+    Copy(usize),
+    Byte(u8),
+    /// A conditional branch, `goto`, or `jsr`
+    Jump {
+        opcode: u8,
+        target: usize,
+        wide: bool,
+    },
+    TableSwitch {
+        default: usize,
+        low: i32,
+        targets: Vec<usize>,
+    },
+    LookupSwitch {
+        default: usize,
+        pairs: Vec<(i32, usize)>,
+    },
+}
+
+/// The inverse of a conditional branch
+fn invert(opcode: u8) -> u8 {
+    match opcode {
+        0xc6 => 0xc7,
+        0xc7 => 0xc6,
+        // The conditions come in pairs, starting with ifeq and ifne
+        _ if opcode % 2 == 1 => opcode + 1,
+        _ => opcode - 1,
+    }
+}
+
+fn switch_padding(pos: usize) -> usize {
+    (4 - (pos + 1) % 4) % 4
+}
+
+/// Place the items, widening branches whose targets are too far away, returning the position of
+/// each label and of each item
+pub(crate) fn layout(
+    items: &mut [Item],
+    insns: &[Insn],
+    label_count: usize,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut labels = vec![0; label_count];
+    let mut positions = vec![0; items.len()];
+    loop {
+        let mut pos = 0;
+        for (item, position) in items.iter().zip(positions.iter_mut()) {
+            *position = pos;
+            pos += match item {
+                Item::Bind(label) => {
+                    labels[*label] = pos;
+                    0
+                }
+                Item::Origin(_) => 0,
+                Item::Copy(i) => insns[*i].len,
+                Item::Byte(_) => 1,
+                Item::Jump { wide: false, .. } => 3,
+                Item::Jump {
+                    opcode: GOTO | JSR, ..
+                } => 5,
+                // An inverted branch over a goto_w
+                Item::Jump { .. } => 8,
+                Item::TableSwitch { targets, .. } => {
+                    1 + switch_padding(pos) + 12 + 4 * targets.len()
+                }
+                Item::LookupSwitch { pairs, .. } => 1 + switch_padding(pos) + 8 + 8 * pairs.len(),
+            };
+        }
+
+        let mut changed = false;
+        for (item, &position) in items.iter_mut().zip(positions.iter()) {
+            if let Item::Jump {
+                target,
+                wide: wide @ false,
+                ..
+            } = item
+            {
+                let relative = labels[*target] as i64 - position as i64;
+                if i16::try_from(relative).is_err() {
+                    *wide = true;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return (labels, positions);
+        }
+    }
+}
+
+fn relative(target: usize, from: usize) -> i32 {
+    (target as i64 - from as i64) as i32
+}
+
+/// Encode the placed items, returning the code and the new position of each original offset
+pub(crate) fn encode(
+    items: &[Item],
+    insns: &[Insn],
+    code: &[u8],
+    labels: &[usize],
+    positions: &[usize],
+) -> (Vec<u8>, Vec<(usize, usize)>) {
+    let mut out = Vec::new();
+    let mut origins = Vec::new();
+    for (item, &pos) in items.iter().zip(positions.iter()) {
+        match item {
+            Item::Bind(_) => {}
+            Item::Origin(offset) => origins.push((pos, *offset)),
+            Item::Copy(i) => {
+                let insn = &insns[*i];
+                out.extend_from_slice(&code[insn.offset..insn.offset + insn.len]);
+            }
+            Item::Byte(byte) => out.push(*byte),
+            &Item::Jump {
+                opcode,
+                target,
+                wide,
+            } => {
+                let target = labels[target];
+                if !wide {
+                    out.push(opcode);
+                    out.extend_from_slice(&(relative(target, pos) as i16).to_be_bytes());
+                } else if opcode == GOTO || opcode == JSR {
+                    out.push(if opcode == GOTO { GOTO_W } else { JSR_W });
+                    out.extend_from_slice(&relative(target, pos).to_be_bytes());
+                } else {
+                    out.push(invert(opcode));
+                    out.extend_from_slice(&8i16.to_be_bytes());
+                    out.push(GOTO_W);
+                    out.extend_from_slice(&relative(target, pos + 3).to_be_bytes());
+                }
+            }
+            Item::TableSwitch {
+                default,
+                low,
+                targets,
+            } => {
+                out.push(0xaa);
+                out.resize(out.len() + switch_padding(pos), 0);
+                out.extend_from_slice(&relative(labels[*default], pos).to_be_bytes());
+                out.extend_from_slice(&low.to_be_bytes());
+                let high = *low as i64 + targets.len() as i64 - 1;
+                out.extend_from_slice(&(high as i32).to_be_bytes());
+                for &target in targets {
+                    out.extend_from_slice(&relative(labels[target], pos).to_be_bytes());
+                }
+            }
+            Item::LookupSwitch { default, pairs } => {
+                out.push(0xab);
+                out.resize(out.len() + switch_padding(pos), 0);
+                out.extend_from_slice(&relative(labels[*default], pos).to_be_bytes());
+                out.extend_from_slice(&(pairs.len() as i32).to_be_bytes());
+                for &(key, target) in pairs {
+                    out.extend_from_slice(&key.to_be_bytes());
+                    out.extend_from_slice(&relative(labels[target], pos).to_be_bytes());
+                }
+            }
+        }
+    }
+    (out, origins)
+}
@@ -0,0 +1,185 @@
+//! Encoding annotations as the info of their attributes.
+//! The counts are taken from the lengths of the lists, rather than the count fields, so that
+//! annotations can be added and removed without keeping them in sync. A list too long for its
+//! count fails with [`WriteError::TooManyItems`].
+
+use crate::attribute_info::{
+    Annotation, AnnotationDefaultAttribute, AnnotationsAttribute, ElementValue,
+    ParameterAnnotationsAttribute, TargetInfo, TypeAnnotation, TypeAnnotationsAttribute, TypePath,
+};
+use crate::writer::{count, WriteError};
+
+fn write_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+/// A count which is a single byte, like [`count`]
+fn count_u8(len: usize) -> Result<u8, WriteError> {
+    u8::try_from(len).map_err(|_| WriteError::TooManyItems)
+}
+
+fn write_annotations(out: &mut Vec<u8>, annotations: &[Annotation]) -> Result<(), WriteError> {
+    out.extend_from_slice(&count(annotations.len())?);
+    for annotation in annotations {
+        annotation.write(out)?;
+    }
+    Ok(())
+}
+
+impl Annotation {
+    pub fn write(&self, out: &mut Vec<u8>) -> Result<(), WriteError> {
+        write_u16(out, self.type_index.0);
+        out.extend_from_slice(&count(self.element_value_pairs.len())?);
+        for pair in self.element_value_pairs.iter() {
+            write_u16(out, pair.element_name_index.0);
+            pair.value.write(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl ElementValue {
+    pub fn write(&self, out: &mut Vec<u8>) -> Result<(), WriteError> {
+        match self {
+            ElementValue::Const {
+                tag,
+                const_value_index,
+            } => {
+                out.push(*tag);
+                write_u16(out, const_value_index.0);
+            }
+            ElementValue::Enum {
+                type_name_index,
+                const_name_index,
+            } => {
+                out.push(b'e');
+                write_u16(out, type_name_index.0);
+                write_u16(out, const_name_index.0);
+            }
+            ElementValue::Class { class_info_index } => {
+                out.push(b'c');
+                write_u16(out, class_info_index.0);
+            }
+            ElementValue::Annotation(annotation) => {
+                out.push(b'@');
+                annotation.write(out)?;
+            }
+            ElementValue::Array { values, .. } => {
+                out.push(b'[');
+                out.extend_from_slice(&count(values.len())?);
+                for value in values {
+                    value.write(out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TargetInfo {
+    pub fn write(&self, out: &mut Vec<u8>) -> Result<(), WriteError> {
+        match self {
+            TargetInfo::TypeParameter {
+                type_parameter_index,
+            } => out.push(*type_parameter_index),
+            TargetInfo::Supertype { supertype_index } => write_u16(out, *supertype_index),
+            TargetInfo::TypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            } => {
+                out.push(*type_parameter_index);
+                out.push(*bound_index);
+            }
+            TargetInfo::Empty => {}
+            TargetInfo::FormalParameter {
+                formal_parameter_index,
+            } => out.push(*formal_parameter_index),
+            TargetInfo::Throws { throws_type_index } => write_u16(out, *throws_type_index),
+            TargetInfo::Localvar { table, .. } => {
+                out.extend_from_slice(&count(table.len())?);
+                for entry in table {
+                    write_u16(out, entry.start_pc);
+                    write_u16(out, entry.length);
+                    write_u16(out, entry.index);
+                }
+            }
+            TargetInfo::Catch {
+                exception_table_index,
+            } => write_u16(out, *exception_table_index),
+            TargetInfo::Offset { offset } => write_u16(out, *offset),
+            TargetInfo::TypeArgument {
+                offset,
+                type_argument_index,
+            } => {
+                write_u16(out, *offset);
+                out.push(*type_argument_index);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TypePath {
+    pub fn write(&self, out: &mut Vec<u8>) -> Result<(), WriteError> {
+        out.push(count_u8(self.path.len())?);
+        for entry in self.path.iter() {
+            out.push(entry.type_path_kind);
+            out.push(entry.type_argument_index);
+        }
+        Ok(())
+    }
+}
+
+impl TypeAnnotation {
+    /// Write the annotation. The target info must be the form that the target type has, which
+    /// isn't checked.
+    pub fn write(&self, out: &mut Vec<u8>) -> Result<(), WriteError> {
+        out.push(self.target_type);
+        self.target_info.write(out)?;
+        self.target_path.write(out)?;
+        self.annotation.write(out)
+    }
+}
+
+impl AnnotationsAttribute {
+    /// Encode the annotations as the info of a RuntimeVisibleAnnotations or
+    /// RuntimeInvisibleAnnotations attribute
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WriteError> {
+        let mut out = Vec::new();
+        write_annotations(&mut out, &self.annotations)?;
+        Ok(out)
+    }
+}
+
+impl ParameterAnnotationsAttribute {
+    /// Encode the annotations as the info of a RuntimeVisibleParameterAnnotations or
+    /// RuntimeInvisibleParameterAnnotations attribute
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WriteError> {
+        let mut out = vec![count_u8(self.parameter_annotations.len())?];
+        for parameter in self.parameter_annotations.iter() {
+            write_annotations(&mut out, &parameter.annotations)?;
+        }
+        Ok(out)
+    }
+}
+
+impl TypeAnnotationsAttribute {
+    /// Encode the annotations as the info of a RuntimeVisibleTypeAnnotations or
+    /// RuntimeInvisibleTypeAnnotations attribute
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WriteError> {
+        let mut out = count(self.annotations.len())?.to_vec();
+        for annotation in self.annotations.iter() {
+            annotation.write(&mut out)?;
+        }
+        Ok(out)
+    }
+}
+
+impl AnnotationDefaultAttribute {
+    /// Encode the default value as the info of an AnnotationDefault attribute
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WriteError> {
+        let mut out = Vec::new();
+        self.default_value.write(&mut out)?;
+        Ok(out)
+    }
+}
@@ -0,0 +1,236 @@
+use std::ops::Range;
+
+use smallvec::SmallVec;
+
+use crate::attribute_info::{AttributeInfo, CodeAttribute, ExceptionEntry, InstructionIndex};
+use crate::constant_info::{ClassConstant, Utf8Constant};
+use crate::constant_pool::ConstantPoolIndexRaw;
+
+/// A position in the code which may not be known yet, see [`CodeAttributeBuilder::new_label`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label(usize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeBuilderError {
+    /// The label was used but never bound to a position
+    UnboundLabel(Label),
+    /// The label was created by a different builder
+    UnknownLabel(Label),
+    /// The code is longer than the 65535 bytes that the JVM allows
+    CodeTooLong,
+    /// An exception handler's range was empty or backwards
+    EmptyHandlerRange {
+        start_pc: u16,
+        end_pc: u16,
+    },
+    TooManyExceptionHandlers,
+    TooManyAttributes,
+    /// The nested attribute's info was longer than its length field can represent
+    AttributeTooLong,
+}
+
+#[derive(Debug, Clone)]
+struct HandlerSpec {
+    start: Label,
+    end: Label,
+    handler: Label,
+    catch_type: ConstantPoolIndexRaw<ClassConstant>,
+}
+
+/// Builds the info of a Code attribute, keeping the lengths and counts in sync with the content.
+///
+/// The instructions are given as already encoded bytes.
+/// Exception handlers refer to labels, which can be bound after the handler is declared.
+/// The exception table keeps the order that the handlers are added in, which is the order that
+/// the JVM tries them, so a handler should be added before any handler whose range encloses it.
+#[derive(Debug, Clone)]
+pub struct CodeAttributeBuilder {
+    max_stack: u16,
+    max_locals: u16,
+    code: Vec<u8>,
+    labels: Vec<Option<u16>>,
+    handlers: Vec<HandlerSpec>,
+    attributes: Vec<(ConstantPoolIndexRaw<Utf8Constant>, Vec<u8>)>,
+}
+impl CodeAttributeBuilder {
+    pub fn new(max_stack: u16, max_locals: u16) -> CodeAttributeBuilder {
+        CodeAttributeBuilder {
+            max_stack,
+            max_locals,
+            code: Vec::new(),
+            labels: Vec::new(),
+            handlers: Vec::new(),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn set_max_stack(&mut self, max_stack: u16) -> &mut Self {
+        self.max_stack = max_stack;
+        self
+    }
+
+    pub fn set_max_locals(&mut self, max_locals: u16) -> &mut Self {
+        self.max_locals = max_locals;
+        self
+    }
+
+    /// Append encoded instructions to the code
+    pub fn emit(&mut self, instructions: &[u8]) -> &mut Self {
+        self.code.extend_from_slice(instructions);
+        self
+    }
+
+    /// The position that the next emitted instruction will be at
+    pub fn position(&self) -> usize {
+        self.code.len()
+    }
+
+    /// Create a new label, which must be bound before building
+    pub fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    /// Bind the label to the current position
+    pub fn bind(&mut self, label: Label) -> Result<&mut Self, CodeBuilderError> {
+        let pos = u16::try_from(self.position()).map_err(|_| CodeBuilderError::CodeTooLong)?;
+        let slot = self
+            .labels
+            .get_mut(label.0)
+            .ok_or(CodeBuilderError::UnknownLabel(label))?;
+        *slot = Some(pos);
+        Ok(self)
+    }
+
+    /// Create a label bound to the current position
+    pub fn label_here(&mut self) -> Result<Label, CodeBuilderError> {
+        let label = self.new_label();
+        self.bind(label)?;
+        Ok(label)
+    }
+
+    /// Add an exception handler active from `start` up to (but not including) `end`, which jumps to
+    /// `handler`. A zero `catch_type` catches all exceptions.
+    pub fn exception_handler(
+        &mut self,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_type: ConstantPoolIndexRaw<ClassConstant>,
+    ) -> &mut Self {
+        self.handlers.push(HandlerSpec {
+            start,
+            end,
+            handler,
+            catch_type,
+        });
+        self
+    }
+
+    /// Add a nested attribute, such as a LineNumberTable
+    pub fn attribute(
+        &mut self,
+        name_index: ConstantPoolIndexRaw<Utf8Constant>,
+        info: Vec<u8>,
+    ) -> &mut Self {
+        self.attributes.push((name_index, info));
+        self
+    }
+
+    fn resolve(&self, label: Label) -> Result<InstructionIndex, CodeBuilderError> {
+        self.labels
+            .get(label.0)
+            .ok_or(CodeBuilderError::UnknownLabel(label))?
+            .map(InstructionIndex)
+            .ok_or(CodeBuilderError::UnboundLabel(label))
+    }
+
+    fn exception_table(&self) -> Result<SmallVec<[ExceptionEntry; 6]>, CodeBuilderError> {
+        self.handlers
+            .iter()
+            .map(|spec| {
+                let entry = ExceptionEntry {
+                    start_pc: self.resolve(spec.start)?,
+                    end_pc: self.resolve(spec.end)?,
+                    handler_pc: self.resolve(spec.handler)?,
+                    catch_type: spec.catch_type,
+                };
+                if entry.start_pc >= entry.end_pc {
+                    return Err(CodeBuilderError::EmptyHandlerRange {
+                        start_pc: entry.start_pc.0,
+                        end_pc: entry.end_pc.0,
+                    });
+                }
+                Ok(entry)
+            })
+            .collect()
+    }
+
+    /// Append the encoded info of the Code attribute to `data`, returning the range it was written
+    /// to and the parsed form of it, whose ranges refer into `data`.
+    pub fn build(
+        &self,
+        data: &mut Vec<u8>,
+    ) -> Result<(Range<usize>, CodeAttribute), CodeBuilderError> {
+        let code_length =
+            u16::try_from(self.code.len()).map_err(|_| CodeBuilderError::CodeTooLong)?;
+        let exception_table = self.exception_table()?;
+        let exception_table_length = u16::try_from(exception_table.len())
+            .map_err(|_| CodeBuilderError::TooManyExceptionHandlers)?;
+        let attributes_count = u16::try_from(self.attributes.len())
+            .map_err(|_| CodeBuilderError::TooManyAttributes)?;
+        if self
+            .attributes
+            .iter()
+            .any(|(_, info)| u32::try_from(info.len()).is_err())
+        {
+            return Err(CodeBuilderError::AttributeTooLong);
+        }
+
+        // Everything has been checked, so `data` is never left partially written
+        let start = data.len();
+        data.extend_from_slice(&self.max_stack.to_be_bytes());
+        data.extend_from_slice(&self.max_locals.to_be_bytes());
+        data.extend_from_slice(&u32::from(code_length).to_be_bytes());
+        let code_start = data.len();
+        data.extend_from_slice(&self.code);
+        let code = code_start..data.len();
+
+        data.extend_from_slice(&exception_table_length.to_be_bytes());
+        for entry in exception_table.iter() {
+            data.extend_from_slice(&entry.start_pc.0.to_be_bytes());
+            data.extend_from_slice(&entry.end_pc.0.to_be_bytes());
+            data.extend_from_slice(&entry.handler_pc.0.to_be_bytes());
+            data.extend_from_slice(&entry.catch_type.0.to_be_bytes());
+        }
+
+        data.extend_from_slice(&attributes_count.to_be_bytes());
+        let mut attributes = SmallVec::with_capacity(self.attributes.len());
+        for (name_index, info) in self.attributes.iter() {
+            let attribute_length = info.len() as u32;
+            data.extend_from_slice(&name_index.0.to_be_bytes());
+            data.extend_from_slice(&attribute_length.to_be_bytes());
+            let info_start = data.len();
+            data.extend_from_slice(info);
+            attributes.push(AttributeInfo {
+                attribute_name_index: *name_index,
+                attribute_length,
+                info: info_start..data.len(),
+            });
+        }
+
+        Ok((
+            start..data.len(),
+            CodeAttribute {
+                max_stack: self.max_stack,
+                max_locals: self.max_locals,
+                code_length: u32::from(code_length),
+                code,
+                exception_table_length,
+                exception_table,
+                attributes_count,
+                attributes,
+            },
+        ))
+    }
+}
@@ -1,15 +1,64 @@
+#[cfg(feature = "annotations")]
+mod annotation;
+mod builder;
+pub mod names;
 mod parser;
+mod remove;
+mod replace;
+#[cfg(feature = "stackmap")]
+mod stack_map;
+mod typed;
 mod types;
+mod version;
+mod visitor;
 
+pub use self::builder::{CodeAttributeBuilder, CodeBuilderError, Label};
+#[cfg(feature = "stackmap")]
+pub use self::stack_map::{FrameState, StackMapError};
+pub use self::typed::{parse_attribute, Attribute, AttributeData, TypedAttributes};
 pub use self::types::*;
+pub use self::version::{attribute_min_major_version, AttributeOwner, AttributeVersionViolation};
+pub(crate) use self::visitor::{nesting_too_deep, type_annotation_targets};
+pub use self::visitor::{
+    visit_annotations, visit_element_value, visit_parameter_annotations, visit_type_annotations,
+    AnnotationVisitor,
+};
 
+#[cfg(feature = "annotations")]
+pub use self::parser::annotation_default_attribute_parser;
+#[cfg(feature = "annotations")]
+pub use self::parser::annotation_parser;
+#[cfg(feature = "annotations")]
+pub use self::parser::annotations_attribute_parser;
 pub use self::parser::attribute_parser;
 pub use self::parser::bootstrap_methods_attribute_parser;
 pub use self::parser::code_attribute_opt_parser;
 pub use self::parser::code_attribute_parser;
 pub use self::parser::constant_value_attribute_parser;
+#[cfg(feature = "annotations")]
+pub use self::parser::element_value_parser;
+pub use self::parser::enclosing_method_attribute_parser;
 pub use self::parser::exception_entry_parser;
 pub use self::parser::exceptions_attribute_parser;
+pub use self::parser::inner_classes_attribute_parser;
+pub use self::parser::method_parameters_attribute_parser;
+#[cfg(feature = "modules")]
+pub use self::parser::module_attribute_parser;
+#[cfg(feature = "modules")]
+pub use self::parser::module_main_class_attribute_parser;
+#[cfg(feature = "modules")]
+pub use self::parser::module_packages_attribute_parser;
+pub use self::parser::nest_host_attribute_parser;
+pub use self::parser::nest_members_attribute_parser;
+#[cfg(feature = "annotations")]
+pub use self::parser::parameter_annotations_attribute_parser;
+pub use self::parser::record_attribute_parser;
+pub use self::parser::signature_attribute_parser;
 pub use self::parser::skip_attribute_parser;
 pub use self::parser::sourcefile_attribute_parser;
+#[cfg(feature = "stackmap")]
 pub use self::parser::stack_map_table_attribute_parser;
+#[cfg(feature = "annotations")]
+pub use self::parser::type_annotation_parser;
+#[cfg(feature = "annotations")]
+pub use self::parser::type_annotations_attribute_parser;
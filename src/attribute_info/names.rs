@@ -0,0 +1,188 @@
+//! The names of the attributes defined by the JVM specification.
+//! [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7-300)
+
+pub const CONSTANT_VALUE: &str = "ConstantValue";
+pub const CODE: &str = "Code";
+pub const STACK_MAP_TABLE: &str = "StackMapTable";
+pub const BOOTSTRAP_METHODS: &str = "BootstrapMethods";
+pub const NEST_HOST: &str = "NestHost";
+pub const NEST_MEMBERS: &str = "NestMembers";
+pub const PERMITTED_SUBCLASSES: &str = "PermittedSubclasses";
+pub const EXCEPTIONS: &str = "Exceptions";
+pub const INNER_CLASSES: &str = "InnerClasses";
+pub const ENCLOSING_METHOD: &str = "EnclosingMethod";
+pub const SYNTHETIC: &str = "Synthetic";
+pub const SIGNATURE: &str = "Signature";
+pub const RECORD: &str = "Record";
+pub const SOURCE_FILE: &str = "SourceFile";
+pub const LINE_NUMBER_TABLE: &str = "LineNumberTable";
+pub const LOCAL_VARIABLE_TABLE: &str = "LocalVariableTable";
+pub const LOCAL_VARIABLE_TYPE_TABLE: &str = "LocalVariableTypeTable";
+pub const SOURCE_DEBUG_EXTENSION: &str = "SourceDebugExtension";
+pub const DEPRECATED: &str = "Deprecated";
+pub const RUNTIME_VISIBLE_ANNOTATIONS: &str = "RuntimeVisibleAnnotations";
+pub const RUNTIME_INVISIBLE_ANNOTATIONS: &str = "RuntimeInvisibleAnnotations";
+pub const RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS: &str = "RuntimeVisibleParameterAnnotations";
+pub const RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS: &str = "RuntimeInvisibleParameterAnnotations";
+pub const RUNTIME_VISIBLE_TYPE_ANNOTATIONS: &str = "RuntimeVisibleTypeAnnotations";
+pub const RUNTIME_INVISIBLE_TYPE_ANNOTATIONS: &str = "RuntimeInvisibleTypeAnnotations";
+pub const ANNOTATION_DEFAULT: &str = "AnnotationDefault";
+pub const METHOD_PARAMETERS: &str = "MethodParameters";
+pub const MODULE: &str = "Module";
+pub const MODULE_PACKAGES: &str = "ModulePackages";
+pub const MODULE_MAIN_CLASS: &str = "ModuleMainClass";
+
+/// One of the attributes defined by the JVM specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeName {
+    ConstantValue,
+    Code,
+    StackMapTable,
+    BootstrapMethods,
+    NestHost,
+    NestMembers,
+    PermittedSubclasses,
+    Exceptions,
+    InnerClasses,
+    EnclosingMethod,
+    Synthetic,
+    Signature,
+    Record,
+    SourceFile,
+    LineNumberTable,
+    LocalVariableTable,
+    LocalVariableTypeTable,
+    SourceDebugExtension,
+    Deprecated,
+    RuntimeVisibleAnnotations,
+    RuntimeInvisibleAnnotations,
+    RuntimeVisibleParameterAnnotations,
+    RuntimeInvisibleParameterAnnotations,
+    RuntimeVisibleTypeAnnotations,
+    RuntimeInvisibleTypeAnnotations,
+    AnnotationDefault,
+    MethodParameters,
+    Module,
+    ModulePackages,
+    ModuleMainClass,
+}
+impl AttributeName {
+    /// Every attribute name, in the order the specification lists them
+    pub const ALL: [AttributeName; 30] = [
+        AttributeName::ConstantValue,
+        AttributeName::Code,
+        AttributeName::StackMapTable,
+        AttributeName::BootstrapMethods,
+        AttributeName::NestHost,
+        AttributeName::NestMembers,
+        AttributeName::PermittedSubclasses,
+        AttributeName::Exceptions,
+        AttributeName::InnerClasses,
+        AttributeName::EnclosingMethod,
+        AttributeName::Synthetic,
+        AttributeName::Signature,
+        AttributeName::Record,
+        AttributeName::SourceFile,
+        AttributeName::LineNumberTable,
+        AttributeName::LocalVariableTable,
+        AttributeName::LocalVariableTypeTable,
+        AttributeName::SourceDebugExtension,
+        AttributeName::Deprecated,
+        AttributeName::RuntimeVisibleAnnotations,
+        AttributeName::RuntimeInvisibleAnnotations,
+        AttributeName::RuntimeVisibleParameterAnnotations,
+        AttributeName::RuntimeInvisibleParameterAnnotations,
+        AttributeName::RuntimeVisibleTypeAnnotations,
+        AttributeName::RuntimeInvisibleTypeAnnotations,
+        AttributeName::AnnotationDefault,
+        AttributeName::MethodParameters,
+        AttributeName::Module,
+        AttributeName::ModulePackages,
+        AttributeName::ModuleMainClass,
+    ];
+
+    /// Get the attribute with the name, or None if it is not a predefined attribute
+    pub fn from_name(name: &str) -> Option<AttributeName> {
+        Some(match name {
+            CONSTANT_VALUE => AttributeName::ConstantValue,
+            CODE => AttributeName::Code,
+            STACK_MAP_TABLE => AttributeName::StackMapTable,
+            BOOTSTRAP_METHODS => AttributeName::BootstrapMethods,
+            NEST_HOST => AttributeName::NestHost,
+            NEST_MEMBERS => AttributeName::NestMembers,
+            PERMITTED_SUBCLASSES => AttributeName::PermittedSubclasses,
+            EXCEPTIONS => AttributeName::Exceptions,
+            INNER_CLASSES => AttributeName::InnerClasses,
+            ENCLOSING_METHOD => AttributeName::EnclosingMethod,
+            SYNTHETIC => AttributeName::Synthetic,
+            SIGNATURE => AttributeName::Signature,
+            RECORD => AttributeName::Record,
+            SOURCE_FILE => AttributeName::SourceFile,
+            LINE_NUMBER_TABLE => AttributeName::LineNumberTable,
+            LOCAL_VARIABLE_TABLE => AttributeName::LocalVariableTable,
+            LOCAL_VARIABLE_TYPE_TABLE => AttributeName::LocalVariableTypeTable,
+            SOURCE_DEBUG_EXTENSION => AttributeName::SourceDebugExtension,
+            DEPRECATED => AttributeName::Deprecated,
+            RUNTIME_VISIBLE_ANNOTATIONS => AttributeName::RuntimeVisibleAnnotations,
+            RUNTIME_INVISIBLE_ANNOTATIONS => AttributeName::RuntimeInvisibleAnnotations,
+            RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS => {
+                AttributeName::RuntimeVisibleParameterAnnotations
+            }
+            RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS => {
+                AttributeName::RuntimeInvisibleParameterAnnotations
+            }
+            RUNTIME_VISIBLE_TYPE_ANNOTATIONS => AttributeName::RuntimeVisibleTypeAnnotations,
+            RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => AttributeName::RuntimeInvisibleTypeAnnotations,
+            ANNOTATION_DEFAULT => AttributeName::AnnotationDefault,
+            METHOD_PARAMETERS => AttributeName::MethodParameters,
+            MODULE => AttributeName::Module,
+            MODULE_PACKAGES => AttributeName::ModulePackages,
+            MODULE_MAIN_CLASS => AttributeName::ModuleMainClass,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AttributeName::ConstantValue => CONSTANT_VALUE,
+            AttributeName::Code => CODE,
+            AttributeName::StackMapTable => STACK_MAP_TABLE,
+            AttributeName::BootstrapMethods => BOOTSTRAP_METHODS,
+            AttributeName::NestHost => NEST_HOST,
+            AttributeName::NestMembers => NEST_MEMBERS,
+            AttributeName::PermittedSubclasses => PERMITTED_SUBCLASSES,
+            AttributeName::Exceptions => EXCEPTIONS,
+            AttributeName::InnerClasses => INNER_CLASSES,
+            AttributeName::EnclosingMethod => ENCLOSING_METHOD,
+            AttributeName::Synthetic => SYNTHETIC,
+            AttributeName::Signature => SIGNATURE,
+            AttributeName::Record => RECORD,
+            AttributeName::SourceFile => SOURCE_FILE,
+            AttributeName::LineNumberTable => LINE_NUMBER_TABLE,
+            AttributeName::LocalVariableTable => LOCAL_VARIABLE_TABLE,
+            AttributeName::LocalVariableTypeTable => LOCAL_VARIABLE_TYPE_TABLE,
+            AttributeName::SourceDebugExtension => SOURCE_DEBUG_EXTENSION,
+            AttributeName::Deprecated => DEPRECATED,
+            AttributeName::RuntimeVisibleAnnotations => RUNTIME_VISIBLE_ANNOTATIONS,
+            AttributeName::RuntimeInvisibleAnnotations => RUNTIME_INVISIBLE_ANNOTATIONS,
+            AttributeName::RuntimeVisibleParameterAnnotations => {
+                RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS
+            }
+            AttributeName::RuntimeInvisibleParameterAnnotations => {
+                RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS
+            }
+            AttributeName::RuntimeVisibleTypeAnnotations => RUNTIME_VISIBLE_TYPE_ANNOTATIONS,
+            AttributeName::RuntimeInvisibleTypeAnnotations => RUNTIME_INVISIBLE_TYPE_ANNOTATIONS,
+            AttributeName::AnnotationDefault => ANNOTATION_DEFAULT,
+            AttributeName::MethodParameters => METHOD_PARAMETERS,
+            AttributeName::Module => MODULE,
+            AttributeName::ModulePackages => MODULE_PACKAGES,
+            AttributeName::ModuleMainClass => MODULE_MAIN_CLASS,
+        }
+    }
+}
+impl std::fmt::Display for AttributeName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
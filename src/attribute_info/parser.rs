@@ -1,16 +1,27 @@
 use nom::bytes::complete::take;
+#[cfg(any(feature = "annotations", feature = "stackmap"))]
 use nom::error::ErrorKind;
 use nom::multi::count;
 use nom::number::complete::{be_u16, be_u32, be_u8};
-use nom::{Err, IResult, Slice};
+#[cfg(any(feature = "annotations", feature = "stackmap"))]
+use nom::Err;
+use nom::IResult;
+#[cfg(feature = "stackmap")]
+use nom::Slice;
 
+#[cfg(feature = "stackmap")]
 use crate::attribute_info::types::StackMapFrame::*;
 use crate::attribute_info::*;
 
 use crate::constant_info::ConstantInfo;
+use crate::parser::combinators::{count_sv, skip_count};
 use crate::parser::ParseData;
-use crate::util::{constant_pool_index_raw, count_sv, skip_count};
+#[cfg(feature = "annotations")]
+use crate::parser::DEFAULT_MAX_NESTING_DEPTH;
+use crate::util::constant_pool_index_raw;
+use crate::LoadError;
 
+/// Skip over an attribute, including its header, without reading its info
 pub fn skip_attribute_parser(i: ParseData) -> IResult<ParseData, ()> {
     let (i, _) = constant_pool_index_raw::<ConstantInfo>(i)?;
     let (i, attribute_length) = be_u32(i)?;
@@ -108,10 +119,12 @@ pub fn code_attribute_opt_parser(i: ParseData) -> IResult<ParseData, CodeAttribu
     ))
 }
 
+#[cfg(feature = "stackmap")]
 fn same_frame_parser(input: ParseData, frame_type: u8) -> IResult<ParseData, StackMapFrame> {
     value!(input, SameFrame { frame_type })
 }
 
+#[cfg(feature = "stackmap")]
 fn verification_type_parser(input: ParseData) -> IResult<ParseData, VerificationTypeInfo> {
     use self::VerificationTypeInfo::*;
     let v = input.data()[0];
@@ -133,6 +146,7 @@ fn verification_type_parser(input: ParseData) -> IResult<ParseData, Verification
     }
 }
 
+#[cfg(feature = "stackmap")]
 fn same_locals_1_stack_item_frame_parser(
     input: ParseData,
     frame_type: u8,
@@ -143,6 +157,7 @@ fn same_locals_1_stack_item_frame_parser(
     )
 }
 
+#[cfg(feature = "stackmap")]
 fn same_locals_1_stack_item_frame_extended_parser(
     input: ParseData,
     frame_type: u8,
@@ -159,6 +174,7 @@ fn same_locals_1_stack_item_frame_extended_parser(
     )
 }
 
+#[cfg(feature = "stackmap")]
 fn chop_frame_parser(input: ParseData, frame_type: u8) -> IResult<ParseData, StackMapFrame> {
     do_parse!(
         input,
@@ -170,6 +186,7 @@ fn chop_frame_parser(input: ParseData, frame_type: u8) -> IResult<ParseData, Sta
     )
 }
 
+#[cfg(feature = "stackmap")]
 fn same_frame_extended_parser(
     input: ParseData,
     frame_type: u8,
@@ -184,6 +201,7 @@ fn same_frame_extended_parser(
     )
 }
 
+#[cfg(feature = "stackmap")]
 fn append_frame_parser(i: ParseData, frame_type: u8) -> IResult<ParseData, StackMapFrame> {
     let (i, offset_delta) = be_u16(i)?;
     let (i, locals) = count_sv(verification_type_parser, (frame_type - 251) as usize)(i)?;
@@ -197,6 +215,7 @@ fn append_frame_parser(i: ParseData, frame_type: u8) -> IResult<ParseData, Stack
     ))
 }
 
+#[cfg(feature = "stackmap")]
 fn full_frame_parser(i: ParseData, frame_type: u8) -> IResult<ParseData, StackMapFrame> {
     let (i, offset_delta) = be_u16(i)?;
     let (i, number_of_locals) = be_u16(i)?;
@@ -216,6 +235,7 @@ fn full_frame_parser(i: ParseData, frame_type: u8) -> IResult<ParseData, StackMa
     ))
 }
 
+#[cfg(feature = "stackmap")]
 fn stack_frame_parser(input: ParseData, frame_type: u8) -> IResult<ParseData, StackMapFrame> {
     match frame_type {
         0..=63 => same_frame_parser(input, frame_type),
@@ -229,11 +249,13 @@ fn stack_frame_parser(input: ParseData, frame_type: u8) -> IResult<ParseData, St
     }
 }
 
+#[cfg(feature = "stackmap")]
 fn stack_map_frame_entry_parser(i: ParseData) -> IResult<ParseData, StackMapFrame> {
     let (i, frame_type) = be_u8(i)?;
     stack_frame_parser(i, frame_type)
 }
 
+#[cfg(feature = "stackmap")]
 pub fn stack_map_table_attribute_parser(
     input: ParseData,
 ) -> IResult<ParseData, StackMapTableAttribute> {
@@ -323,3 +345,727 @@ pub fn sourcefile_attribute_parser(input: ParseData) -> IResult<ParseData, Sourc
             })
     )
 }
+
+pub fn signature_attribute_parser(i: ParseData) -> IResult<ParseData, SignatureAttribute> {
+    let (i, signature_index) = constant_pool_index_raw(i)?;
+    Ok((i, SignatureAttribute { signature_index }))
+}
+
+fn inner_class_info_parser(i: ParseData) -> IResult<ParseData, InnerClassInfo> {
+    let (i, inner_class_info_index) = constant_pool_index_raw(i)?;
+    let (i, outer_class_info_index) = constant_pool_index_raw(i)?;
+    let (i, inner_name_index) = constant_pool_index_raw(i)?;
+    let (i, inner_class_access_flags) = be_u16(i)?;
+    Ok((
+        i,
+        InnerClassInfo {
+            inner_class_info_index,
+            outer_class_info_index,
+            inner_name_index,
+            inner_class_access_flags,
+        },
+    ))
+}
+
+pub fn inner_classes_attribute_parser(i: ParseData) -> IResult<ParseData, InnerClassesAttribute> {
+    let (i, number_of_classes) = be_u16(i)?;
+    let (i, classes) = count(inner_class_info_parser, usize::from(number_of_classes))(i)?;
+    Ok((
+        i,
+        InnerClassesAttribute {
+            number_of_classes,
+            classes,
+        },
+    ))
+}
+
+pub fn enclosing_method_attribute_parser(
+    i: ParseData,
+) -> IResult<ParseData, EnclosingMethodAttribute> {
+    let (i, class_index) = constant_pool_index_raw(i)?;
+    let (i, method_index) = constant_pool_index_raw(i)?;
+    Ok((
+        i,
+        EnclosingMethodAttribute {
+            class_index,
+            method_index,
+        },
+    ))
+}
+
+pub fn nest_host_attribute_parser(i: ParseData) -> IResult<ParseData, NestHostAttribute> {
+    let (i, host_class_index) = constant_pool_index_raw(i)?;
+    Ok((i, NestHostAttribute { host_class_index }))
+}
+
+pub fn nest_members_attribute_parser(i: ParseData) -> IResult<ParseData, NestMembersAttribute> {
+    let (i, number_of_classes) = be_u16(i)?;
+    let (i, classes) = count(constant_pool_index_raw, usize::from(number_of_classes))(i)?;
+    Ok((
+        i,
+        NestMembersAttribute {
+            number_of_classes,
+            classes,
+        },
+    ))
+}
+
+#[cfg(feature = "modules")]
+fn module_requires_parser(i: ParseData) -> IResult<ParseData, ModuleRequires> {
+    let (i, requires_index) = constant_pool_index_raw(i)?;
+    let (i, requires_flags) = be_u16(i)?;
+    let (i, requires_version_index) = constant_pool_index_raw(i)?;
+    Ok((
+        i,
+        ModuleRequires {
+            requires_index,
+            requires_flags,
+            requires_version_index,
+        },
+    ))
+}
+
+#[cfg(feature = "modules")]
+/// Parses an entry of either the exports or the opens of a module, which have the same layout
+fn module_exports_parser(i: ParseData) -> IResult<ParseData, ModuleExports> {
+    let (i, package_index) = constant_pool_index_raw(i)?;
+    let (i, raw_flags) = be_u16(i)?;
+    let (i, to_count) = be_u16(i)?;
+    let (i, to_index) = count(constant_pool_index_raw, usize::from(to_count))(i)?;
+    Ok((
+        i,
+        ModuleExports {
+            package_index,
+            raw_flags,
+            to_count,
+            to_index,
+        },
+    ))
+}
+
+#[cfg(feature = "modules")]
+fn module_provides_parser(i: ParseData) -> IResult<ParseData, ModuleProvides> {
+    let (i, provides_index) = constant_pool_index_raw(i)?;
+    let (i, provides_with_count) = be_u16(i)?;
+    let (i, provides_with_index) =
+        count(constant_pool_index_raw, usize::from(provides_with_count))(i)?;
+    Ok((
+        i,
+        ModuleProvides {
+            provides_index,
+            provides_with_count,
+            provides_with_index,
+        },
+    ))
+}
+
+#[cfg(feature = "modules")]
+pub fn module_attribute_parser(i: ParseData) -> IResult<ParseData, ModuleAttribute> {
+    let (i, module_name_index) = constant_pool_index_raw(i)?;
+    let (i, module_flags) = be_u16(i)?;
+    let (i, module_version_index) = constant_pool_index_raw(i)?;
+    let (i, requires_count) = be_u16(i)?;
+    let (i, requires) = count(module_requires_parser, usize::from(requires_count))(i)?;
+    let (i, exports_count) = be_u16(i)?;
+    let (i, exports) = count(module_exports_parser, usize::from(exports_count))(i)?;
+    let (i, opens_count) = be_u16(i)?;
+    let (i, opens) = count(module_exports_parser, usize::from(opens_count))(i)?;
+    let (i, uses_count) = be_u16(i)?;
+    let (i, uses_index) = count(constant_pool_index_raw, usize::from(uses_count))(i)?;
+    let (i, provides_count) = be_u16(i)?;
+    let (i, provides) = count(module_provides_parser, usize::from(provides_count))(i)?;
+    Ok((
+        i,
+        ModuleAttribute {
+            module_name_index,
+            module_flags,
+            module_version_index,
+            requires_count,
+            requires,
+            exports_count,
+            exports,
+            opens_count,
+            opens,
+            uses_count,
+            uses_index,
+            provides_count,
+            provides,
+        },
+    ))
+}
+
+#[cfg(feature = "modules")]
+pub fn module_packages_attribute_parser(
+    i: ParseData,
+) -> IResult<ParseData, ModulePackagesAttribute> {
+    let (i, package_count) = be_u16(i)?;
+    let (i, package_index) = count(constant_pool_index_raw, usize::from(package_count))(i)?;
+    Ok((
+        i,
+        ModulePackagesAttribute {
+            package_count,
+            package_index,
+        },
+    ))
+}
+
+#[cfg(feature = "modules")]
+pub fn module_main_class_attribute_parser(
+    i: ParseData,
+) -> IResult<ParseData, ModuleMainClassAttribute> {
+    let (i, main_class_index) = constant_pool_index_raw(i)?;
+    Ok((i, ModuleMainClassAttribute { main_class_index }))
+}
+
+fn method_parameter_parser(i: ParseData) -> IResult<ParseData, MethodParameter> {
+    let (i, name_index) = constant_pool_index_raw(i)?;
+    let (i, access_flags) = be_u16(i)?;
+    Ok((
+        i,
+        MethodParameter {
+            name_index,
+            access_flags,
+        },
+    ))
+}
+
+pub fn method_parameters_attribute_parser(
+    i: ParseData,
+) -> IResult<ParseData, MethodParametersAttribute> {
+    let (i, parameters_count) = be_u8(i)?;
+    let (i, parameters) = count(method_parameter_parser, usize::from(parameters_count))(i)?;
+    Ok((
+        i,
+        MethodParametersAttribute {
+            parameters_count,
+            parameters,
+        },
+    ))
+}
+
+fn record_component_info_parser(i: ParseData) -> IResult<ParseData, RecordComponentInfo> {
+    let (i, name_index) = constant_pool_index_raw(i)?;
+    let (i, descriptor_index) = constant_pool_index_raw(i)?;
+    let (i, attributes_count) = be_u16(i)?;
+    let (i, attributes) = count_sv(attribute_parser, usize::from(attributes_count))(i)?;
+    Ok((
+        i,
+        RecordComponentInfo {
+            name_index,
+            descriptor_index,
+            attributes_count,
+            attributes,
+        },
+    ))
+}
+
+pub fn record_attribute_parser(i: ParseData) -> IResult<ParseData, RecordAttribute> {
+    let (i, components_count) = be_u16(i)?;
+    let (i, components) = count(record_component_info_parser, usize::from(components_count))(i)?;
+    Ok((
+        i,
+        RecordAttribute {
+            components_count,
+            components,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+/// `budget` is how many more levels of element values may be nested
+fn annotation_depth_parser(i: ParseData, budget: usize) -> IResult<ParseData, Annotation> {
+    let (i, type_index) = constant_pool_index_raw(i)?;
+    let (mut i, num_element_value_pairs) = be_u16(i)?;
+    // A pair takes at least 5 bytes, so a count that the input can't hold doesn't allocate for it
+    let capacity = usize::from(num_element_value_pairs).min(i.len() / 5);
+    let mut element_value_pairs = Vec::with_capacity(capacity);
+    for _ in 0..num_element_value_pairs {
+        let (rest, element_name_index) = constant_pool_index_raw(i)?;
+        let (rest, value) = element_value_depth_parser(rest, budget)?;
+        element_value_pairs.push(ElementValuePair {
+            element_name_index,
+            value,
+        });
+        i = rest;
+    }
+    Ok((
+        i,
+        Annotation {
+            type_index,
+            num_element_value_pairs,
+            element_value_pairs,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+fn element_value_depth_parser(i: ParseData, budget: usize) -> IResult<ParseData, ElementValue> {
+    // Arrays and annotations nest element values, so limit how deep they go to keep deeply
+    // nested data from overflowing the stack
+    let budget = match budget.checked_sub(1) {
+        Some(budget) => budget,
+        None => return Err(Err::Failure(error_position!(i, ErrorKind::TooLarge))),
+    };
+    let (i, tag) = be_u8(i)?;
+    match tag {
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' => {
+            let (i, const_value_index) = constant_pool_index_raw(i)?;
+            Ok((
+                i,
+                ElementValue::Const {
+                    tag,
+                    const_value_index,
+                },
+            ))
+        }
+        b'e' => {
+            let (i, type_name_index) = constant_pool_index_raw(i)?;
+            let (i, const_name_index) = constant_pool_index_raw(i)?;
+            Ok((
+                i,
+                ElementValue::Enum {
+                    type_name_index,
+                    const_name_index,
+                },
+            ))
+        }
+        b'c' => {
+            let (i, class_info_index) = constant_pool_index_raw(i)?;
+            Ok((i, ElementValue::Class { class_info_index }))
+        }
+        b'@' => {
+            let (i, annotation) = annotation_depth_parser(i, budget)?;
+            Ok((i, ElementValue::Annotation(annotation)))
+        }
+        b'[' => {
+            let (mut i, num_values) = be_u16(i)?;
+            // An element value takes at least 3 bytes
+            let mut values = Vec::with_capacity(usize::from(num_values).min(i.len() / 3));
+            for _ in 0..num_values {
+                let (rest, value) = element_value_depth_parser(i, budget)?;
+                values.push(value);
+                i = rest;
+            }
+            Ok((i, ElementValue::Array { num_values, values }))
+        }
+        _ => Err(Err::Error(error_position!(i, ErrorKind::Tag))),
+    }
+}
+
+#[cfg(feature = "annotations")]
+/// Parse an annotation, failing if its element values nest deeper than
+/// [`DEFAULT_MAX_NESTING_DEPTH`](crate::parser::DEFAULT_MAX_NESTING_DEPTH)
+pub fn annotation_parser(i: ParseData) -> IResult<ParseData, Annotation> {
+    annotation_depth_parser(i, DEFAULT_MAX_NESTING_DEPTH)
+}
+
+#[cfg(feature = "annotations")]
+/// Parse an element value, failing if it nests deeper than
+/// [`DEFAULT_MAX_NESTING_DEPTH`](crate::parser::DEFAULT_MAX_NESTING_DEPTH)
+pub fn element_value_parser(i: ParseData) -> IResult<ParseData, ElementValue> {
+    element_value_depth_parser(i, DEFAULT_MAX_NESTING_DEPTH)
+}
+
+#[cfg(feature = "annotations")]
+/// Parse the info of a RuntimeVisibleAnnotations or RuntimeInvisibleAnnotations attribute
+pub fn annotations_attribute_parser(i: ParseData) -> IResult<ParseData, AnnotationsAttribute> {
+    let (i, num_annotations) = be_u16(i)?;
+    let (i, annotations) = count(annotation_parser, usize::from(num_annotations))(i)?;
+    Ok((
+        i,
+        AnnotationsAttribute {
+            num_annotations,
+            annotations,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+fn parameter_annotations_parser(i: ParseData) -> IResult<ParseData, ParameterAnnotations> {
+    let (i, num_annotations) = be_u16(i)?;
+    let (i, annotations) = count(annotation_parser, usize::from(num_annotations))(i)?;
+    Ok((
+        i,
+        ParameterAnnotations {
+            num_annotations,
+            annotations,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+/// Parse the info of a RuntimeVisibleParameterAnnotations or RuntimeInvisibleParameterAnnotations
+/// attribute
+pub fn parameter_annotations_attribute_parser(
+    i: ParseData,
+) -> IResult<ParseData, ParameterAnnotationsAttribute> {
+    let (i, num_parameters) = be_u8(i)?;
+    let (i, parameter_annotations) =
+        count(parameter_annotations_parser, usize::from(num_parameters))(i)?;
+    Ok((
+        i,
+        ParameterAnnotationsAttribute {
+            num_parameters,
+            parameter_annotations,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+fn local_var_target_entry_parser(i: ParseData) -> IResult<ParseData, LocalVarTargetEntry> {
+    let (i, start_pc) = be_u16(i)?;
+    let (i, length) = be_u16(i)?;
+    let (i, index) = be_u16(i)?;
+    Ok((
+        i,
+        LocalVarTargetEntry {
+            start_pc,
+            length,
+            index,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+/// Parse the target info, whose form depends on the target type
+fn target_info_parser(i: ParseData, target_type: u8) -> IResult<ParseData, TargetInfo> {
+    let kind = match TargetInfoKind::from_target_type(target_type) {
+        Some(kind) => kind,
+        None => return Err(Err::Error(error_position!(i, ErrorKind::Tag))),
+    };
+    match kind {
+        TargetInfoKind::TypeParameter => {
+            let (i, type_parameter_index) = be_u8(i)?;
+            Ok((
+                i,
+                TargetInfo::TypeParameter {
+                    type_parameter_index,
+                },
+            ))
+        }
+        TargetInfoKind::Supertype => {
+            let (i, supertype_index) = be_u16(i)?;
+            Ok((i, TargetInfo::Supertype { supertype_index }))
+        }
+        TargetInfoKind::TypeParameterBound => {
+            let (i, type_parameter_index) = be_u8(i)?;
+            let (i, bound_index) = be_u8(i)?;
+            Ok((
+                i,
+                TargetInfo::TypeParameterBound {
+                    type_parameter_index,
+                    bound_index,
+                },
+            ))
+        }
+        TargetInfoKind::Empty => Ok((i, TargetInfo::Empty)),
+        TargetInfoKind::FormalParameter => {
+            let (i, formal_parameter_index) = be_u8(i)?;
+            Ok((
+                i,
+                TargetInfo::FormalParameter {
+                    formal_parameter_index,
+                },
+            ))
+        }
+        TargetInfoKind::Throws => {
+            let (i, throws_type_index) = be_u16(i)?;
+            Ok((i, TargetInfo::Throws { throws_type_index }))
+        }
+        TargetInfoKind::Localvar => {
+            let (i, table_length) = be_u16(i)?;
+            let (i, table) = count(local_var_target_entry_parser, usize::from(table_length))(i)?;
+            Ok((
+                i,
+                TargetInfo::Localvar {
+                    table_length,
+                    table,
+                },
+            ))
+        }
+        TargetInfoKind::Catch => {
+            let (i, exception_table_index) = be_u16(i)?;
+            Ok((
+                i,
+                TargetInfo::Catch {
+                    exception_table_index,
+                },
+            ))
+        }
+        TargetInfoKind::Offset => {
+            let (i, offset) = be_u16(i)?;
+            Ok((i, TargetInfo::Offset { offset }))
+        }
+        TargetInfoKind::TypeArgument => {
+            let (i, offset) = be_u16(i)?;
+            let (i, type_argument_index) = be_u8(i)?;
+            Ok((
+                i,
+                TargetInfo::TypeArgument {
+                    offset,
+                    type_argument_index,
+                },
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "annotations")]
+fn type_path_entry_parser(i: ParseData) -> IResult<ParseData, TypePathEntry> {
+    let (i, type_path_kind) = be_u8(i)?;
+    let (i, type_argument_index) = be_u8(i)?;
+    Ok((
+        i,
+        TypePathEntry {
+            type_path_kind,
+            type_argument_index,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+fn type_path_parser(i: ParseData) -> IResult<ParseData, TypePath> {
+    let (i, path_length) = be_u8(i)?;
+    let (i, path) = count(type_path_entry_parser, usize::from(path_length))(i)?;
+    Ok((i, TypePath { path_length, path }))
+}
+
+#[cfg(feature = "annotations")]
+/// Parse a type annotation, failing if the target type is unknown or its element values nest
+/// deeper than [`DEFAULT_MAX_NESTING_DEPTH`](crate::parser::DEFAULT_MAX_NESTING_DEPTH)
+pub fn type_annotation_parser(i: ParseData) -> IResult<ParseData, TypeAnnotation> {
+    let (i, target_type) = be_u8(i)?;
+    let (i, target_info) = target_info_parser(i, target_type)?;
+    let (i, target_path) = type_path_parser(i)?;
+    let (i, annotation) = annotation_parser(i)?;
+    Ok((
+        i,
+        TypeAnnotation {
+            target_type,
+            target_info,
+            target_path,
+            annotation,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+/// Parse the info of a RuntimeVisibleTypeAnnotations or RuntimeInvisibleTypeAnnotations attribute
+pub fn type_annotations_attribute_parser(
+    i: ParseData,
+) -> IResult<ParseData, TypeAnnotationsAttribute> {
+    let (i, num_annotations) = be_u16(i)?;
+    let (i, annotations) = count(type_annotation_parser, usize::from(num_annotations))(i)?;
+    Ok((
+        i,
+        TypeAnnotationsAttribute {
+            num_annotations,
+            annotations,
+        },
+    ))
+}
+
+#[cfg(feature = "annotations")]
+pub fn annotation_default_attribute_parser(
+    i: ParseData,
+) -> IResult<ParseData, AnnotationDefaultAttribute> {
+    let (i, default_value) = element_value_parser(i)?;
+    Ok((i, AnnotationDefaultAttribute { default_value }))
+}
+
+fn parse_info_with<'a, T>(
+    info: &AttributeInfo,
+    class_file_data: &'a [u8],
+    parser: impl FnOnce(ParseData<'a>) -> IResult<ParseData<'a>, T>,
+) -> Result<T, LoadError> {
+    let i = ParseData::from_range(class_file_data, info.info.clone());
+    parser(i).map(|(_, v)| v).map_err(|_| LoadError::Unknown)
+}
+
+impl KnownAttribute for CodeAttribute {
+    const NAME: &'static str = names::CODE;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, code_attribute_parser)
+    }
+}
+
+#[cfg(feature = "stackmap")]
+impl KnownAttribute for StackMapTableAttribute {
+    const NAME: &'static str = names::STACK_MAP_TABLE;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, stack_map_table_attribute_parser)
+    }
+}
+
+impl KnownAttribute for CodeAttributeOpt {
+    const NAME: &'static str = names::CODE;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, code_attribute_opt_parser)
+    }
+}
+
+impl KnownAttribute for ExceptionsAttribute {
+    const NAME: &'static str = names::EXCEPTIONS;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, exceptions_attribute_parser)
+    }
+}
+
+impl KnownAttribute for ConstantValueAttribute {
+    const NAME: &'static str = names::CONSTANT_VALUE;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, constant_value_attribute_parser)
+    }
+}
+
+impl KnownAttribute for BootstrapMethodsAttribute {
+    const NAME: &'static str = names::BOOTSTRAP_METHODS;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, bootstrap_methods_attribute_parser)
+    }
+}
+
+impl KnownAttribute for SourceFileAttribute {
+    const NAME: &'static str = names::SOURCE_FILE;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        // The sourcefile parser expects the attribute header, which we have already parsed
+        let (_, sourcefile_index) =
+            constant_pool_index_raw(ParseData::from_range(class_file_data, info.info.clone()))
+                .map_err(|_| LoadError::Unknown)?;
+        Ok(SourceFileAttribute {
+            attribute_name_index: info.attribute_name_index.0,
+            attribute_length: info.attribute_length,
+            sourcefile_index,
+        })
+    }
+}
+
+impl KnownAttribute for SignatureAttribute {
+    const NAME: &'static str = names::SIGNATURE;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, signature_attribute_parser)
+    }
+}
+
+impl KnownAttribute for InnerClassesAttribute {
+    const NAME: &'static str = names::INNER_CLASSES;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, inner_classes_attribute_parser)
+    }
+}
+
+impl KnownAttribute for EnclosingMethodAttribute {
+    const NAME: &'static str = names::ENCLOSING_METHOD;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, enclosing_method_attribute_parser)
+    }
+}
+
+impl KnownAttribute for NestHostAttribute {
+    const NAME: &'static str = names::NEST_HOST;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, nest_host_attribute_parser)
+    }
+}
+
+impl KnownAttribute for NestMembersAttribute {
+    const NAME: &'static str = names::NEST_MEMBERS;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, nest_members_attribute_parser)
+    }
+}
+
+#[cfg(feature = "modules")]
+impl KnownAttribute for ModuleAttribute {
+    const NAME: &'static str = names::MODULE;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, module_attribute_parser)
+    }
+}
+
+#[cfg(feature = "modules")]
+impl KnownAttribute for ModulePackagesAttribute {
+    const NAME: &'static str = names::MODULE_PACKAGES;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, module_packages_attribute_parser)
+    }
+}
+
+#[cfg(feature = "modules")]
+impl KnownAttribute for ModuleMainClassAttribute {
+    const NAME: &'static str = names::MODULE_MAIN_CLASS;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, module_main_class_attribute_parser)
+    }
+}
+
+impl KnownAttribute for MethodParametersAttribute {
+    const NAME: &'static str = names::METHOD_PARAMETERS;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, method_parameters_attribute_parser)
+    }
+}
+
+impl KnownAttribute for RecordAttribute {
+    const NAME: &'static str = names::RECORD;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, record_attribute_parser)
+    }
+}
+
+#[cfg(feature = "annotations")]
+impl KnownAttribute for AnnotationDefaultAttribute {
+    const NAME: &'static str = names::ANNOTATION_DEFAULT;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, annotation_default_attribute_parser)
+    }
+}
+
+#[cfg(feature = "annotations")]
+impl AnnotationsAttribute {
+    /// Parse the info of the attribute, which may be either a RuntimeVisibleAnnotations or a
+    /// RuntimeInvisibleAnnotations attribute
+    pub fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, annotations_attribute_parser)
+    }
+}
+
+#[cfg(feature = "annotations")]
+impl ParameterAnnotationsAttribute {
+    /// Parse the info of the attribute, which may be either a RuntimeVisibleParameterAnnotations
+    /// or a RuntimeInvisibleParameterAnnotations attribute
+    pub fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(
+            info,
+            class_file_data,
+            parameter_annotations_attribute_parser,
+        )
+    }
+}
+
+#[cfg(feature = "annotations")]
+impl TypeAnnotationsAttribute {
+    /// Parse the info of the attribute, which may be either a RuntimeVisibleTypeAnnotations or a
+    /// RuntimeInvisibleTypeAnnotations attribute
+    pub fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError> {
+        parse_info_with(info, class_file_data, type_annotations_attribute_parser)
+    }
+}
@@ -0,0 +1,225 @@
+use smallvec::{Array, SmallVec};
+
+use crate::attribute_info::{
+    attribute_parser, names, AttributeInfo, AttributeOwner, CodeAttributeOpt, KnownAttribute,
+};
+use crate::constant_info::Utf8Constant;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::field_info::FieldInfo;
+use crate::method_info::MethodInfo;
+use crate::parser::ParseData;
+use crate::stale::StaleRanges;
+use crate::{ClassFile, LoadError};
+
+fn has_name(attr: &AttributeInfo, pool: &ConstantPool, data: &[u8], name: &str) -> bool {
+    pool.get_t(attr.attribute_name_index)
+        .map(|attr_name| attr_name.as_text(data) == name)
+        .unwrap_or(false)
+}
+
+/// Remove the attributes with the name, returning those removed
+fn remove_named<A: Array<Item = AttributeInfo>>(
+    attributes: &mut SmallVec<A>,
+    attributes_count: &mut u16,
+    pool: &ConstantPool,
+    data: &[u8],
+    name: &str,
+) -> Vec<AttributeInfo> {
+    let mut removed = Vec::new();
+    attributes.retain(|attr| {
+        let matches = has_name(attr, pool, data, name);
+        if matches {
+            removed.push(attr.clone());
+        }
+        !matches
+    });
+    *attributes_count = attributes.len() as u16;
+    removed
+}
+
+/// Mark the info of the removed attributes as stale, returning their name indices
+fn mark_removed(
+    stale_ranges: &mut StaleRanges,
+    removed: Vec<AttributeInfo>,
+) -> Vec<ConstantPoolIndexRaw<Utf8Constant>> {
+    removed
+        .into_iter()
+        .map(|attr| {
+            stale_ranges.mark(attr.info);
+            attr.attribute_name_index
+        })
+        .collect()
+}
+
+impl FieldInfo {
+    /// Remove every attribute of the field with the name, returning how many were removed.
+    /// The info of each removed attribute is marked in `stale_ranges`, which should be the
+    /// [`ClassFile::stale_ranges`] of the class that the field is in.
+    pub fn remove_attribute(
+        &mut self,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+        name: &str,
+        stale_ranges: &mut StaleRanges,
+    ) -> usize {
+        let removed = remove_named(
+            &mut self.attributes,
+            &mut self.attributes_count,
+            pool,
+            class_file_data,
+            name,
+        );
+        mark_removed(stale_ranges, removed).len()
+    }
+}
+
+impl MethodInfo {
+    /// Remove every attribute of the method with the name, returning how many were removed.
+    /// The info of each removed attribute is marked in `stale_ranges`, like
+    /// [`FieldInfo::remove_attribute`].
+    /// This doesn't look inside the Code attribute, see [`ClassFile::remove_attribute`] for that.
+    pub fn remove_attribute(
+        &mut self,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+        name: &str,
+        stale_ranges: &mut StaleRanges,
+    ) -> usize {
+        let removed = remove_named(
+            &mut self.attributes,
+            &mut self.attributes_count,
+            pool,
+            class_file_data,
+            name,
+        );
+        mark_removed(stale_ranges, removed).len()
+    }
+}
+
+impl ClassFile {
+    /// Remove every attribute with the name from the owner, returning how many were removed.
+    ///
+    /// Removing from the Code attribute of a method writes the new Code attribute to the end of
+    /// `data`, with its range and length updated to match.
+    /// The old info is recorded in [`ClassFile::stale_ranges`].
+    /// If `drop_unused_name` is true, the Utf8 constants that named the removed attributes are also
+    /// removed from the pool when nothing else refers to them, which renumbers the pool as
+    /// [`ClassFile::remove_unused_constant`] does.
+    /// Errors if the owner doesn't exist, or if its Code attribute is missing or can't be parsed.
+    pub fn remove_attribute(
+        &mut self,
+        data: &mut Vec<u8>,
+        owner: AttributeOwner,
+        name: &str,
+        drop_unused_name: bool,
+    ) -> Result<usize, LoadError> {
+        let pool = &self.const_pool;
+        let removed = match owner {
+            AttributeOwner::Class => {
+                let removed = remove_named(
+                    &mut self.attributes,
+                    &mut self.attributes_count,
+                    pool,
+                    data,
+                    name,
+                );
+                mark_removed(&mut self.stale_ranges, removed)
+            }
+            AttributeOwner::Field(index) => {
+                let field = self.fields.get_mut(index).ok_or(LoadError::Unknown)?;
+                let removed = remove_named(
+                    &mut field.attributes,
+                    &mut field.attributes_count,
+                    pool,
+                    data,
+                    name,
+                );
+                mark_removed(&mut self.stale_ranges, removed)
+            }
+            AttributeOwner::Method(index) => {
+                let method = self.methods.get_mut(index).ok_or(LoadError::Unknown)?;
+                let removed = remove_named(
+                    &mut method.attributes,
+                    &mut method.attributes_count,
+                    pool,
+                    data,
+                    name,
+                );
+                mark_removed(&mut self.stale_ranges, removed)
+            }
+            AttributeOwner::Code(index) => {
+                let method = self.methods.get_mut(index).ok_or(LoadError::Unknown)?;
+                let code = method
+                    .attributes
+                    .iter_mut()
+                    .find(|attr| has_name(attr, pool, data, names::CODE))
+                    .ok_or(LoadError::Unknown)?;
+                let old = code.info.clone();
+                let removed = remove_code_attribute(code, pool, data, name)?;
+                if !removed.is_empty() {
+                    self.stale_ranges.mark(old);
+                }
+                removed
+            }
+        };
+
+        let count = removed.len();
+        if count > 0 {
+            self.typed_attributes = None;
+        }
+        if drop_unused_name {
+            let mut names = removed;
+            names.sort_by_key(|index| index.0);
+            names.dedup();
+            // Remove from the end so that the earlier indices stay valid
+            for index in names.into_iter().rev() {
+                // The name is kept if it's still used, or if the pool can't be renumbered
+                let _ = self.remove_unused_constant(data, index.into_generic());
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+/// Rewrite the Code attribute without the nested attributes with the name, returning the name
+/// indices of those removed
+fn remove_code_attribute(
+    code: &mut AttributeInfo,
+    pool: &ConstantPool,
+    data: &mut Vec<u8>,
+    name: &str,
+) -> Result<Vec<ConstantPoolIndexRaw<Utf8Constant>>, LoadError> {
+    let opt = CodeAttributeOpt::parse_info(code, data)?;
+
+    let mut input = ParseData::from_pos(data, opt.attributes_start);
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+    for _ in 0..opt.attributes_count {
+        let start = input.pos();
+        let (rest, attr) = attribute_parser(input).map_err(|_| LoadError::Unknown)?;
+        if has_name(&attr, pool, data, name) {
+            removed.push(attr.attribute_name_index);
+        } else {
+            kept.push(start..rest.pos());
+        }
+        input = rest;
+    }
+    if removed.is_empty() {
+        return Ok(removed);
+    }
+
+    // Everything before the attributes count is unchanged
+    let mut info = data[code.info.start..opt.attributes_start - 2].to_vec();
+    info.extend_from_slice(&(kept.len() as u16).to_be_bytes());
+    for range in kept {
+        info.extend_from_slice(&data[range]);
+    }
+
+    let start = data.len();
+    data.extend_from_slice(&info);
+    code.info = start..data.len();
+    code.attribute_length = info.len() as u32;
+
+    Ok(removed)
+}
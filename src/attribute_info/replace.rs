@@ -0,0 +1,146 @@
+use crate::attribute_info::{
+    attribute_parser, names, AttributeInfo, AttributeOwner, CodeAttributeOpt, KnownAttribute,
+};
+use crate::constant_pool::ConstantPool;
+use crate::parser::ParseData;
+use crate::stale::StaleRanges;
+use crate::{ClassFile, LoadError};
+
+impl AttributeInfo {
+    /// Replace the info of the attribute, writing it to the end of `data` and updating the range
+    /// and length to match.
+    /// The old info is marked in `stale_ranges`, which should be the
+    /// [`ClassFile::stale_ranges`] of the class that the attribute is in.
+    /// Errors if the info is too long for an attribute.
+    pub fn set_info(
+        &mut self,
+        data: &mut Vec<u8>,
+        info: &[u8],
+        stale_ranges: &mut StaleRanges,
+    ) -> Result<(), LoadError> {
+        let attribute_length = u32::try_from(info.len()).map_err(|_| LoadError::Unknown)?;
+        let start = data.len();
+        data.extend_from_slice(info);
+        let old = std::mem::replace(&mut self.info, start..data.len());
+        stale_ranges.mark(old);
+        self.attribute_length = attribute_length;
+        Ok(())
+    }
+}
+
+fn has_name(attr: &AttributeInfo, pool: &ConstantPool, data: &[u8], name: &str) -> bool {
+    pool.get_t(attr.attribute_name_index)
+        .map(|attr_name| attr_name.as_text(data) == name)
+        .unwrap_or(false)
+}
+
+impl ClassFile {
+    /// Replace the info of the first attribute with the name in the owner, returning whether there
+    /// was one to replace.
+    /// The attribute keeps its place among the owner's attributes, so only its info changes.
+    ///
+    /// Replacing in the Code attribute of a method writes the new Code attribute to the end of
+    /// `data`, like [`ClassFile::remove_attribute`] does.
+    /// Errors if the owner doesn't exist, if its Code attribute is missing or can't be parsed, or
+    /// if the info is too long.
+    pub fn replace_attribute(
+        &mut self,
+        data: &mut Vec<u8>,
+        owner: AttributeOwner,
+        name: &str,
+        info: &[u8],
+    ) -> Result<bool, LoadError> {
+        let pool = &self.const_pool;
+        let attributes = match owner {
+            AttributeOwner::Class => &mut self.attributes[..],
+            AttributeOwner::Field(index) => {
+                &mut self
+                    .fields
+                    .get_mut(index)
+                    .ok_or(LoadError::Unknown)?
+                    .attributes[..]
+            }
+            AttributeOwner::Method(index) => {
+                &mut self
+                    .methods
+                    .get_mut(index)
+                    .ok_or(LoadError::Unknown)?
+                    .attributes[..]
+            }
+            AttributeOwner::Code(index) => {
+                let method = self.methods.get_mut(index).ok_or(LoadError::Unknown)?;
+                let code = method
+                    .attributes
+                    .iter_mut()
+                    .find(|attr| has_name(attr, pool, data, names::CODE))
+                    .ok_or(LoadError::Unknown)?;
+                let stale_ranges = &mut self.stale_ranges;
+                let replaced = replace_code_attribute(code, pool, data, name, info, stale_ranges)?;
+                if replaced {
+                    self.typed_attributes = None;
+                }
+                return Ok(replaced);
+            }
+        };
+
+        let replaced = match attributes
+            .iter_mut()
+            .find(|attr| has_name(attr, pool, data, name))
+        {
+            Some(attr) => {
+                attr.set_info(data, info, &mut self.stale_ranges)?;
+                true
+            }
+            None => false,
+        };
+        if replaced {
+            self.typed_attributes = None;
+        }
+        Ok(replaced)
+    }
+}
+
+/// Rewrite the Code attribute with the info of the first nested attribute with the name replaced,
+/// returning whether there was one
+fn replace_code_attribute(
+    code: &mut AttributeInfo,
+    pool: &ConstantPool,
+    data: &mut Vec<u8>,
+    name: &str,
+    info: &[u8],
+    stale_ranges: &mut StaleRanges,
+) -> Result<bool, LoadError> {
+    let attribute_length = u32::try_from(info.len()).map_err(|_| LoadError::Unknown)?;
+    let opt = CodeAttributeOpt::parse_info(code, data)?;
+
+    let mut input = ParseData::from_pos(data, opt.attributes_start);
+    let mut nested = Vec::with_capacity(usize::from(opt.attributes_count));
+    for _ in 0..opt.attributes_count {
+        let start = input.pos();
+        let (rest, attr) = attribute_parser(input).map_err(|_| LoadError::Unknown)?;
+        nested.push((start..rest.pos(), attr));
+        input = rest;
+    }
+    let target = match nested
+        .iter()
+        .position(|(_, attr)| has_name(attr, pool, data, name))
+    {
+        Some(target) => target,
+        None => return Ok(false),
+    };
+
+    // Everything before the attributes is unchanged
+    let mut new_info = data[code.info.start..opt.attributes_start].to_vec();
+    for (i, (range, attr)) in nested.into_iter().enumerate() {
+        if i == target {
+            new_info.extend_from_slice(&attr.attribute_name_index.0.to_be_bytes());
+            new_info.extend_from_slice(&attribute_length.to_be_bytes());
+            new_info.extend_from_slice(info);
+        } else {
+            new_info.extend_from_slice(&data[range]);
+        }
+    }
+
+    code.set_info(data, &new_info, stale_ranges)?;
+    Ok(true)
+}
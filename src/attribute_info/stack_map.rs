@@ -0,0 +1,388 @@
+use smallvec::SmallVec;
+
+use crate::attribute_info::{StackMapFrame, StackMapTableAttribute, VerificationTypeInfo};
+
+/// The types of the locals and the stack at an offset in the code, which is what a full frame
+/// describes.
+/// Long and Double take one entry, like in the StackMapTable, rather than two local slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameState {
+    pub offset: u16,
+    pub locals: Vec<VerificationTypeInfo>,
+    pub stack: Vec<VerificationTypeInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackMapError {
+    /// The frame at the offset isn't after the frame before it
+    UnorderedOffset(u16),
+    /// There are more frames than fit in the table's count
+    TooManyFrames,
+}
+
+fn write_verification_type(out: &mut Vec<u8>, ty: &VerificationTypeInfo) {
+    match *ty {
+        VerificationTypeInfo::Top => out.push(0),
+        VerificationTypeInfo::Integer => out.push(1),
+        VerificationTypeInfo::Float => out.push(2),
+        VerificationTypeInfo::Double => out.push(3),
+        VerificationTypeInfo::Long => out.push(4),
+        VerificationTypeInfo::Null => out.push(5),
+        VerificationTypeInfo::UninitializedThis => out.push(6),
+        VerificationTypeInfo::Object { class } => {
+            out.push(7);
+            out.extend_from_slice(&class.0.to_be_bytes());
+        }
+        VerificationTypeInfo::Uninitialized { offset } => {
+            out.push(8);
+            out.extend_from_slice(&offset.to_be_bytes());
+        }
+    }
+}
+
+/// The most compact frame which turns the previous locals into the state, picked the same way
+/// that javac does
+fn compact_frame(
+    previous: &[VerificationTypeInfo],
+    state: &FrameState,
+    offset_delta: u16,
+) -> StackMapFrame {
+    let locals = &state.locals[..];
+    let short = offset_delta < 64;
+    match state.stack.len() {
+        0 if locals == previous => {
+            if short {
+                StackMapFrame::SameFrame {
+                    frame_type: offset_delta as u8,
+                }
+            } else {
+                StackMapFrame::SameFrameExtended {
+                    frame_type: 251,
+                    offset_delta,
+                }
+            }
+        }
+        1 if locals == previous => {
+            let stack = state.stack[0];
+            if short {
+                StackMapFrame::SameLocals1StackItemFrame {
+                    frame_type: 64 + offset_delta as u8,
+                    stack,
+                }
+            } else {
+                StackMapFrame::SameLocals1StackItemFrameExtended {
+                    frame_type: 247,
+                    offset_delta,
+                    stack,
+                }
+            }
+        }
+        0 if locals.len() < previous.len()
+            && previous.len() - locals.len() <= 3
+            && previous.starts_with(locals) =>
+        {
+            StackMapFrame::ChopFrame {
+                frame_type: 251 - (previous.len() - locals.len()) as u8,
+                offset_delta,
+            }
+        }
+        0 if locals.len() > previous.len()
+            && locals.len() - previous.len() <= 3
+            && locals.starts_with(previous) =>
+        {
+            StackMapFrame::AppendFrame {
+                frame_type: 251 + (locals.len() - previous.len()) as u8,
+                offset_delta,
+                locals: locals[previous.len()..].iter().copied().collect(),
+            }
+        }
+        _ => StackMapFrame::FullFrame {
+            frame_type: 255,
+            offset_delta,
+            number_of_locals: locals.len() as u16,
+            locals: locals.iter().copied().collect(),
+            number_of_stack_items: state.stack.len() as u16,
+            stack: state.stack.iter().copied().collect(),
+        },
+    }
+}
+
+impl StackMapTableAttribute {
+    /// Build the table from the full state at each frame, which must be in order of their
+    /// offsets. Each frame is encoded in the most compact kind that describes it, such as a
+    /// `same_frame` when nothing changed, so the table matches what javac produces.
+    /// The initial locals are the ones implied by the method's descriptor, which come before the
+    /// first frame.
+    pub fn from_states(
+        initial_locals: &[VerificationTypeInfo],
+        states: &[FrameState],
+    ) -> Result<StackMapTableAttribute, StackMapError> {
+        let number_of_entries =
+            u16::try_from(states.len()).map_err(|_| StackMapError::TooManyFrames)?;
+        let mut entries = Vec::with_capacity(states.len());
+        let mut previous: (&[VerificationTypeInfo], Option<u16>) = (initial_locals, None);
+        for state in states {
+            // The first frame's delta is its offset, and each frame after is at least one later
+            let offset_delta = match previous.1 {
+                None => state.offset,
+                Some(offset) if state.offset > offset => state.offset - offset - 1,
+                Some(_) => return Err(StackMapError::UnorderedOffset(state.offset)),
+            };
+            entries.push(compact_frame(previous.0, state, offset_delta));
+            previous = (&state.locals, Some(state.offset));
+        }
+
+        Ok(StackMapTableAttribute {
+            number_of_entries,
+            entries,
+        })
+    }
+
+    /// Expand the frames into the full state at each of them, the inverse of
+    /// [`StackMapTableAttribute::from_states`].
+    /// Returns None if a frame chops more locals than there are, or is past the end of any code.
+    pub fn states(&self, initial_locals: &[VerificationTypeInfo]) -> Option<Vec<FrameState>> {
+        let mut states: Vec<FrameState> = Vec::with_capacity(self.entries.len());
+        let mut locals = initial_locals.to_vec();
+        for frame in self.entries.iter() {
+            let mut stack = Vec::new();
+            let offset_delta = match frame {
+                StackMapFrame::SameFrame { frame_type } => u16::from(*frame_type),
+                StackMapFrame::SameLocals1StackItemFrame {
+                    frame_type,
+                    stack: item,
+                } => {
+                    stack.push(*item);
+                    u16::from(*frame_type - 64)
+                }
+                StackMapFrame::SameLocals1StackItemFrameExtended {
+                    offset_delta,
+                    stack: item,
+                    ..
+                } => {
+                    stack.push(*item);
+                    *offset_delta
+                }
+                StackMapFrame::ChopFrame {
+                    frame_type,
+                    offset_delta,
+                } => {
+                    let chopped = usize::from(251 - *frame_type);
+                    locals.truncate(locals.len().checked_sub(chopped)?);
+                    *offset_delta
+                }
+                StackMapFrame::SameFrameExtended { offset_delta, .. } => *offset_delta,
+                StackMapFrame::AppendFrame {
+                    offset_delta,
+                    locals: appended,
+                    ..
+                } => {
+                    locals.extend_from_slice(appended);
+                    *offset_delta
+                }
+                StackMapFrame::FullFrame {
+                    offset_delta,
+                    locals: full_locals,
+                    stack: full_stack,
+                    ..
+                } => {
+                    locals = full_locals.to_vec();
+                    stack = full_stack.to_vec();
+                    *offset_delta
+                }
+            };
+            let offset = match states.last() {
+                None => offset_delta,
+                Some(last) => last.offset.checked_add(offset_delta)?.checked_add(1)?,
+            };
+            states.push(FrameState {
+                offset,
+                locals: locals.clone(),
+                stack,
+            });
+        }
+        Some(states)
+    }
+
+    /// Move the frames, and the offsets of uninitialized objects, to the new offsets given by
+    /// `position` for each old offset in the code. Frames keep their kind, except that a short
+    /// frame whose offset delta no longer fits in its frame type is changed to the extended form.
+    /// Returns None if an offset has no new position, or the frames would no longer be in order.
+    pub(crate) fn moved(
+        &self,
+        position: impl Fn(usize) -> Option<usize>,
+    ) -> Option<StackMapTableAttribute> {
+        let move_type = |ty: &VerificationTypeInfo| match *ty {
+            VerificationTypeInfo::Uninitialized { offset } => {
+                let offset = u16::try_from(position(usize::from(offset))?).ok()?;
+                Some(VerificationTypeInfo::Uninitialized { offset })
+            }
+            ty => Some(ty),
+        };
+
+        let mut entries = Vec::with_capacity(self.entries.len());
+        // The old and new offsets of the previous frame
+        let mut previous: Option<(usize, usize)> = None;
+        for frame in self.entries.iter() {
+            let delta = usize::from(frame.offset_delta());
+            let offset = match previous {
+                Some((offset, _)) => offset + delta + 1,
+                None => delta,
+            };
+            let new = position(offset)?;
+            let offset_delta = match previous {
+                Some((_, new_previous)) => new.checked_sub(new_previous + 1)?,
+                None => new,
+            };
+            let offset_delta = u16::try_from(offset_delta).ok()?;
+            previous = Some((offset, new));
+
+            let short = offset_delta < 64;
+            entries.push(match frame {
+                StackMapFrame::SameFrame { .. } if short => StackMapFrame::SameFrame {
+                    frame_type: offset_delta as u8,
+                },
+                StackMapFrame::SameFrame { .. } => StackMapFrame::SameFrameExtended {
+                    frame_type: 251,
+                    offset_delta,
+                },
+                StackMapFrame::SameLocals1StackItemFrame { stack, .. } if short => {
+                    StackMapFrame::SameLocals1StackItemFrame {
+                        frame_type: 64 + offset_delta as u8,
+                        stack: move_type(stack)?,
+                    }
+                }
+                StackMapFrame::SameLocals1StackItemFrame { stack, .. }
+                | StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } => {
+                    StackMapFrame::SameLocals1StackItemFrameExtended {
+                        frame_type: 247,
+                        offset_delta,
+                        stack: move_type(stack)?,
+                    }
+                }
+                StackMapFrame::ChopFrame { frame_type, .. } => StackMapFrame::ChopFrame {
+                    frame_type: *frame_type,
+                    offset_delta,
+                },
+                StackMapFrame::SameFrameExtended { frame_type, .. } => {
+                    StackMapFrame::SameFrameExtended {
+                        frame_type: *frame_type,
+                        offset_delta,
+                    }
+                }
+                StackMapFrame::AppendFrame {
+                    frame_type, locals, ..
+                } => StackMapFrame::AppendFrame {
+                    frame_type: *frame_type,
+                    offset_delta,
+                    locals: locals.iter().map(move_type).collect::<Option<_>>()?,
+                },
+                StackMapFrame::FullFrame {
+                    frame_type,
+                    number_of_locals,
+                    locals,
+                    number_of_stack_items,
+                    stack,
+                    ..
+                } => StackMapFrame::FullFrame {
+                    frame_type: *frame_type,
+                    offset_delta,
+                    number_of_locals: *number_of_locals,
+                    locals: locals.iter().map(move_type).collect::<Option<_>>()?,
+                    number_of_stack_items: *number_of_stack_items,
+                    stack: stack.iter().map(move_type).collect::<Option<_>>()?,
+                },
+            });
+        }
+
+        Some(StackMapTableAttribute {
+            number_of_entries: self.number_of_entries,
+            entries,
+        })
+    }
+
+    /// Encode the table as the info of a StackMapTable attribute
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.entries.len() as u16).to_be_bytes());
+        for frame in self.entries.iter() {
+            match frame {
+                StackMapFrame::SameFrame { frame_type } => out.push(*frame_type),
+                StackMapFrame::SameLocals1StackItemFrame { frame_type, stack } => {
+                    out.push(*frame_type);
+                    write_verification_type(&mut out, stack);
+                }
+                StackMapFrame::SameLocals1StackItemFrameExtended {
+                    frame_type,
+                    offset_delta,
+                    stack,
+                } => {
+                    out.push(*frame_type);
+                    out.extend_from_slice(&offset_delta.to_be_bytes());
+                    write_verification_type(&mut out, stack);
+                }
+                StackMapFrame::ChopFrame {
+                    frame_type,
+                    offset_delta,
+                }
+                | StackMapFrame::SameFrameExtended {
+                    frame_type,
+                    offset_delta,
+                } => {
+                    out.push(*frame_type);
+                    out.extend_from_slice(&offset_delta.to_be_bytes());
+                }
+                StackMapFrame::AppendFrame {
+                    frame_type,
+                    offset_delta,
+                    locals,
+                } => {
+                    out.push(*frame_type);
+                    out.extend_from_slice(&offset_delta.to_be_bytes());
+                    for local in locals.iter() {
+                        write_verification_type(&mut out, local);
+                    }
+                }
+                StackMapFrame::FullFrame {
+                    frame_type,
+                    offset_delta,
+                    locals,
+                    stack,
+                    ..
+                } => {
+                    out.push(*frame_type);
+                    out.extend_from_slice(&offset_delta.to_be_bytes());
+                    write_types(&mut out, locals);
+                    write_types(&mut out, stack);
+                }
+            }
+        }
+        out
+    }
+}
+
+impl StackMapFrame {
+    /// How far the frame is from the previous one, which is encoded in the frame type of the
+    /// short frames
+    fn offset_delta(&self) -> u16 {
+        match *self {
+            StackMapFrame::SameFrame { frame_type } => u16::from(frame_type),
+            StackMapFrame::SameLocals1StackItemFrame { frame_type, .. } => {
+                u16::from(frame_type - 64)
+            }
+            StackMapFrame::SameLocals1StackItemFrameExtended { offset_delta, .. }
+            | StackMapFrame::ChopFrame { offset_delta, .. }
+            | StackMapFrame::SameFrameExtended { offset_delta, .. }
+            | StackMapFrame::AppendFrame { offset_delta, .. }
+            | StackMapFrame::FullFrame { offset_delta, .. } => offset_delta,
+        }
+    }
+}
+
+/// Write the types preceded by their count
+fn write_types<const N: usize>(out: &mut Vec<u8>, types: &SmallVec<[VerificationTypeInfo; N]>) {
+    out.extend_from_slice(&(types.len() as u16).to_be_bytes());
+    for ty in types.iter() {
+        write_verification_type(out, ty);
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[cfg(feature = "stackmap")]
+use crate::attribute_info::StackMapTableAttribute;
+use crate::attribute_info::{
+    names, AttributeInfo, AttributeOwner, BootstrapMethodsAttribute, CodeAttribute,
+    ConstantValueAttribute, EnclosingMethodAttribute, ExceptionsAttribute, InnerClassesAttribute,
+    KnownAttribute, MethodParametersAttribute, NestHostAttribute, NestMembersAttribute,
+    SignatureAttribute, SourceFileAttribute,
+};
+#[cfg(feature = "annotations")]
+use crate::attribute_info::{
+    AnnotationDefaultAttribute, AnnotationsAttribute, ParameterAnnotationsAttribute,
+    TypeAnnotationsAttribute,
+};
+#[cfg(feature = "modules")]
+use crate::attribute_info::{ModuleAttribute, ModuleMainClassAttribute, ModulePackagesAttribute};
+use crate::constant_pool::ConstantPool;
+use crate::error::Malformation;
+use crate::{ClassFile, LoadError, ParseError};
+
+/// An attribute parsed into the type for its name
+#[derive(Clone, Debug)]
+pub enum AttributeData {
+    /// Boxed since it is much larger than the others
+    Code(Box<CodeAttribute>),
+    #[cfg(feature = "stackmap")]
+    StackMapTable(StackMapTableAttribute),
+    Exceptions(ExceptionsAttribute),
+    ConstantValue(ConstantValueAttribute),
+    BootstrapMethods(BootstrapMethodsAttribute),
+    SourceFile(SourceFileAttribute),
+    Signature(SignatureAttribute),
+    #[cfg(feature = "annotations")]
+    RuntimeVisibleAnnotations(AnnotationsAttribute),
+    #[cfg(feature = "annotations")]
+    RuntimeInvisibleAnnotations(AnnotationsAttribute),
+    #[cfg(feature = "annotations")]
+    RuntimeVisibleParameterAnnotations(ParameterAnnotationsAttribute),
+    #[cfg(feature = "annotations")]
+    RuntimeInvisibleParameterAnnotations(ParameterAnnotationsAttribute),
+    #[cfg(feature = "annotations")]
+    RuntimeVisibleTypeAnnotations(TypeAnnotationsAttribute),
+    #[cfg(feature = "annotations")]
+    RuntimeInvisibleTypeAnnotations(TypeAnnotationsAttribute),
+    #[cfg(feature = "annotations")]
+    AnnotationDefault(AnnotationDefaultAttribute),
+    InnerClasses(InnerClassesAttribute),
+    EnclosingMethod(EnclosingMethodAttribute),
+    NestHost(NestHostAttribute),
+    NestMembers(NestMembersAttribute),
+    #[cfg(feature = "modules")]
+    Module(ModuleAttribute),
+    #[cfg(feature = "modules")]
+    ModulePackages(ModulePackagesAttribute),
+    #[cfg(feature = "modules")]
+    ModuleMainClass(ModuleMainClassAttribute),
+    MethodParameters(MethodParametersAttribute),
+}
+impl AttributeData {
+    /// Parse the attribute into the type for its name, returning None if the name isn't one
+    /// that has a type, or if its type is left out by a disabled feature
+    pub fn parse(
+        info: &AttributeInfo,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+    ) -> Result<Option<AttributeData>, LoadError> {
+        let name = pool
+            .get_t(info.attribute_name_index)
+            .ok_or(LoadError::Unknown)?
+            .as_text(class_file_data);
+        let data = match name.as_ref() {
+            names::CODE => {
+                AttributeData::Code(Box::new(CodeAttribute::parse_info(info, class_file_data)?))
+            }
+            #[cfg(feature = "stackmap")]
+            names::STACK_MAP_TABLE => AttributeData::StackMapTable(
+                StackMapTableAttribute::parse_info(info, class_file_data)?,
+            ),
+            names::EXCEPTIONS => {
+                AttributeData::Exceptions(ExceptionsAttribute::parse_info(info, class_file_data)?)
+            }
+            names::CONSTANT_VALUE => AttributeData::ConstantValue(
+                ConstantValueAttribute::parse_info(info, class_file_data)?,
+            ),
+            names::BOOTSTRAP_METHODS => AttributeData::BootstrapMethods(
+                BootstrapMethodsAttribute::parse_info(info, class_file_data)?,
+            ),
+            names::SOURCE_FILE => {
+                AttributeData::SourceFile(SourceFileAttribute::parse_info(info, class_file_data)?)
+            }
+            names::SIGNATURE => {
+                AttributeData::Signature(SignatureAttribute::parse_info(info, class_file_data)?)
+            }
+            #[cfg(feature = "annotations")]
+            names::RUNTIME_VISIBLE_ANNOTATIONS => AttributeData::RuntimeVisibleAnnotations(
+                AnnotationsAttribute::parse_info(info, class_file_data)?,
+            ),
+            #[cfg(feature = "annotations")]
+            names::RUNTIME_INVISIBLE_ANNOTATIONS => AttributeData::RuntimeInvisibleAnnotations(
+                AnnotationsAttribute::parse_info(info, class_file_data)?,
+            ),
+            #[cfg(feature = "annotations")]
+            names::RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS => {
+                AttributeData::RuntimeVisibleParameterAnnotations(
+                    ParameterAnnotationsAttribute::parse_info(info, class_file_data)?,
+                )
+            }
+            #[cfg(feature = "annotations")]
+            names::RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS => {
+                AttributeData::RuntimeInvisibleParameterAnnotations(
+                    ParameterAnnotationsAttribute::parse_info(info, class_file_data)?,
+                )
+            }
+            #[cfg(feature = "annotations")]
+            names::RUNTIME_VISIBLE_TYPE_ANNOTATIONS => {
+                AttributeData::RuntimeVisibleTypeAnnotations(TypeAnnotationsAttribute::parse_info(
+                    info,
+                    class_file_data,
+                )?)
+            }
+            #[cfg(feature = "annotations")]
+            names::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
+                AttributeData::RuntimeInvisibleTypeAnnotations(
+                    TypeAnnotationsAttribute::parse_info(info, class_file_data)?,
+                )
+            }
+            #[cfg(feature = "annotations")]
+            names::ANNOTATION_DEFAULT => AttributeData::AnnotationDefault(
+                AnnotationDefaultAttribute::parse_info(info, class_file_data)?,
+            ),
+            names::INNER_CLASSES => AttributeData::InnerClasses(InnerClassesAttribute::parse_info(
+                info,
+                class_file_data,
+            )?),
+            names::ENCLOSING_METHOD => AttributeData::EnclosingMethod(
+                EnclosingMethodAttribute::parse_info(info, class_file_data)?,
+            ),
+            names::NEST_HOST => {
+                AttributeData::NestHost(NestHostAttribute::parse_info(info, class_file_data)?)
+            }
+            names::NEST_MEMBERS => {
+                AttributeData::NestMembers(NestMembersAttribute::parse_info(info, class_file_data)?)
+            }
+            #[cfg(feature = "modules")]
+            names::MODULE => {
+                AttributeData::Module(ModuleAttribute::parse_info(info, class_file_data)?)
+            }
+            #[cfg(feature = "modules")]
+            names::MODULE_PACKAGES => AttributeData::ModulePackages(
+                ModulePackagesAttribute::parse_info(info, class_file_data)?,
+            ),
+            #[cfg(feature = "modules")]
+            names::MODULE_MAIN_CLASS => AttributeData::ModuleMainClass(
+                ModuleMainClassAttribute::parse_info(info, class_file_data)?,
+            ),
+            names::METHOD_PARAMETERS => AttributeData::MethodParameters(
+                MethodParametersAttribute::parse_info(info, class_file_data)?,
+            ),
+            _ => return Ok(None),
+        };
+        Ok(Some(data))
+    }
+}
+
+/// An attribute parsed by its name, see [`parse_attribute`]
+#[derive(Clone, Debug)]
+pub enum Attribute {
+    Typed(AttributeData),
+    /// An attribute whose name has no type, with the range of its info in the class file data
+    Raw(Range<usize>),
+}
+impl Attribute {
+    pub fn typed(&self) -> Option<&AttributeData> {
+        match self {
+            Attribute::Typed(data) => Some(data),
+            Attribute::Raw(_) => None,
+        }
+    }
+}
+
+/// Parse the attribute into the type for its name, or keep its info as is if the name has no
+/// type.
+/// Errors if the name can't be found or the attribute is malformed.
+pub fn parse_attribute(
+    info: &AttributeInfo,
+    class_file_data: &[u8],
+    pool: &ConstantPool,
+) -> Result<Attribute, LoadError> {
+    Ok(match AttributeData::parse(info, pool, class_file_data)? {
+        Some(data) => Attribute::Typed(data),
+        None => Attribute::Raw(info.info.clone()),
+    })
+}
+
+/// The attributes of a class file which have types, parsed ahead of time, see
+/// [`ClassFile::parse_typed_attributes`].
+/// Entries are keyed by the owner of the attribute and its index in the owner's attributes.
+#[derive(Clone, Debug, Default)]
+pub struct TypedAttributes {
+    entries: HashMap<(AttributeOwner, usize), AttributeData>,
+}
+impl TypedAttributes {
+    pub fn get(&self, owner: AttributeOwner, index: usize) -> Option<&AttributeData> {
+        self.entries.get(&(owner, index))
+    }
+
+    /// Iterate over the parsed attributes, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (AttributeOwner, usize, &AttributeData)> + '_ {
+        self.entries
+            .iter()
+            .map(|(&(owner, index), data)| (owner, index, data))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn parse_all(
+        &mut self,
+        attributes: &[AttributeInfo],
+        owner: AttributeOwner,
+        pool: &ConstantPool,
+        data: &[u8],
+    ) -> Result<(), ParseError> {
+        for (index, attr) in attributes.iter().enumerate() {
+            let parsed = AttributeData::parse(attr, pool, data).map_err(|_| {
+                ParseError::Malformed {
+                    offset: attr.info.start,
+                    item: None,
+                    reason: Malformation::Invalid,
+                }
+                .with_item(data)
+            })?;
+            if let Some(parsed) = parsed {
+                self.entries.insert((owner, index), parsed);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ClassFile {
+    /// Parse every attribute which has a type, including those nested in Code attributes, and
+    /// keep them in [`ClassFile::typed_attributes`].
+    /// Errors if any of them is malformed, in which case the typed attributes are left unchanged.
+    ///
+    /// Edits which add, remove, or rewrite attributes clear the typed attributes, since they would
+    /// no longer match.
+    pub fn parse_typed_attributes(&mut self, data: &[u8]) -> Result<(), ParseError> {
+        let pool = &self.const_pool;
+        let mut typed = TypedAttributes::default();
+        typed.parse_all(&self.attributes, AttributeOwner::Class, pool, data)?;
+        for (i, field) in self.fields.iter().enumerate() {
+            typed.parse_all(&field.attributes, AttributeOwner::Field(i), pool, data)?;
+        }
+        for (i, method) in self.methods.iter().enumerate() {
+            typed.parse_all(&method.attributes, AttributeOwner::Method(i), pool, data)?;
+        }
+
+        // The nested attributes are only known once the Code attributes are parsed
+        let mut nested = Vec::new();
+        for (owner, _, attr) in typed.iter() {
+            if let (AttributeOwner::Method(i), AttributeData::Code(code)) = (owner, attr) {
+                nested.push((i, code.attributes.clone()));
+            }
+        }
+        for (i, attributes) in nested {
+            typed.parse_all(&attributes, AttributeOwner::Code(i), pool, data)?;
+        }
+
+        self.typed_attributes = Some(typed);
+        Ok(())
+    }
+}
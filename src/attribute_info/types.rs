@@ -2,11 +2,58 @@ use std::ops::Range;
 
 use smallvec::SmallVec;
 
+use crate::attribute_info::exception_entry_parser;
+#[cfg(feature = "modules")]
+use crate::constant_info::{ModuleConstant, PackageConstant};
+use crate::method_info::attributes_search_parser;
+use crate::parser::ParseData;
 use crate::{
-    constant_info::{ClassConstant, ConstantInfo, MethodHandleConstant, Utf8Constant},
-    constant_pool::ConstantPoolIndexRaw,
+    constant_info::{
+        ClassConstant, ConstantInfo, MethodHandleConstant, NameAndTypeConstant, Utf8Constant,
+    },
+    constant_pool::{ConstantPool, ConstantPoolIndexRaw},
+    LoadError,
 };
 
+/// An attribute type with a standard name, which can be parsed from the info of an
+/// [`AttributeInfo`] with that name.
+pub trait KnownAttribute: Sized {
+    /// The name of the attribute as it appears in the constant pool
+    const NAME: &'static str;
+
+    fn parse_info(info: &AttributeInfo, class_file_data: &[u8]) -> Result<Self, LoadError>;
+}
+
+/// Anything that owns a list of attributes: the class itself, fields, methods, and code.
+pub trait HasAttributes {
+    fn attributes(&self) -> &[AttributeInfo];
+
+    /// Find the first attribute with the given name
+    fn find_attribute_info(
+        &self,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+        name: &str,
+    ) -> Option<&AttributeInfo> {
+        self.attributes().iter().find(|attr| {
+            pool.get_t(attr.attribute_name_index)
+                .map(|attr_name| attr_name.as_text(class_file_data) == name)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Find the first attribute named [`KnownAttribute::NAME`] and parse it
+    fn find_attribute<T: KnownAttribute>(
+        &self,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+    ) -> Result<Option<T>, LoadError> {
+        self.find_attribute_info(pool, class_file_data, T::NAME)
+            .map(|info| T::parse_info(info, class_file_data))
+            .transpose()
+    }
+}
+
 /// An index into the code that should be an index
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct InstructionIndex(pub u16);
@@ -42,6 +89,12 @@ pub struct CodeAttribute {
     pub attributes: SmallVec<[AttributeInfo; 6]>,
 }
 
+impl HasAttributes for CodeAttribute {
+    fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct CodeAttributeOpt {
     pub max_stack: u16,
@@ -52,8 +105,72 @@ pub struct CodeAttributeOpt {
     pub attributes_count: u16,
     pub attributes_start: usize,
 }
+impl CodeAttributeOpt {
+    /// Iterate over the entries of the exception table, parsing each as it is reached.
+    /// Stops early if an entry can't be parsed, which only happens if the data is not the data
+    /// that the attribute was parsed from.
+    pub fn exception_entries_iter<'a>(&self, class_file_data: &'a [u8]) -> ExceptionEntryIter<'a> {
+        // Each entry is four u16s
+        let range = self.exception_table_start
+            ..self.exception_table_start + usize::from(self.exception_table_length) * 8;
+        let input = (range.end <= class_file_data.len())
+            .then(|| ParseData::from_range(class_file_data, range));
+        ExceptionEntryIter { input }
+    }
+
+    /// Find the first of the code's attributes with the given name, such as LineNumberTable,
+    /// returning the range of its info.
+    /// Errors with [`LoadError::OutOfRange`] if the attributes start past the end of the data.
+    pub fn find_attribute(
+        &self,
+        class_file_data: &[u8],
+        pool: &ConstantPool,
+        name: &str,
+    ) -> Result<Option<Range<usize>>, LoadError> {
+        if self.attributes_start > class_file_data.len() {
+            return Err(LoadError::OutOfRange {
+                index: self.attributes_start,
+                len: class_file_data.len(),
+            });
+        }
+        let input = ParseData::from_pos(class_file_data, self.attributes_start);
+        let (_, info) =
+            attributes_search_parser(input, class_file_data, pool, name, self.attributes_count)
+                .map_err(|_| LoadError::Unknown)?;
+
+        Ok(info.map(|(_, range)| range))
+    }
+}
+
+/// Iterates over the entries of a Code attribute's exception table, see
+/// [`CodeAttributeOpt::exception_entries_iter`]
+#[derive(Debug, Clone)]
+pub struct ExceptionEntryIter<'a> {
+    /// None once an entry fails to parse
+    input: Option<ParseData<'a>>,
+}
+impl<'a> Iterator for ExceptionEntryIter<'a> {
+    type Item = ExceptionEntry;
+
+    fn next(&mut self) -> Option<ExceptionEntry> {
+        let input = self.input.take()?;
+        if input.is_empty() {
+            return None;
+        }
 
-#[derive(Clone, Copy, Debug)]
+        let (rest, entry) = exception_entry_parser(input).ok()?;
+        self.input = Some(rest);
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.input.as_ref().map_or(0, |input| input.len() / 8);
+        (0, Some(len))
+    }
+}
+
+#[cfg(feature = "stackmap")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum VerificationTypeInfo {
     Top,
     Integer,
@@ -73,6 +190,7 @@ pub enum VerificationTypeInfo {
     },
 }
 
+#[cfg(feature = "stackmap")]
 #[derive(Clone, Debug)]
 pub enum StackMapFrame {
     SameFrame {
@@ -110,6 +228,7 @@ pub enum StackMapFrame {
     },
 }
 
+#[cfg(feature = "stackmap")]
 #[derive(Clone, Debug)]
 pub struct StackMapTableAttribute {
     pub number_of_entries: u16,
@@ -156,3 +275,476 @@ pub struct SourceFileAttribute {
     /// The constant_pool entry at that index must be a CONSTANT_Utf8_info structure representing a string.
     pub sourcefile_index: ConstantPoolIndexRaw<Utf8Constant>,
 }
+
+/// The Signature attribute records the generic signature of a class, method, or field.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.9)
+#[derive(Clone, Debug)]
+pub struct SignatureAttribute {
+    pub signature_index: ConstantPoolIndexRaw<Utf8Constant>,
+}
+
+bitflags! {
+    /// The flags of a nested class as declared in the source, which unlike the flags of the class
+    /// file itself can be private, protected, and static
+    pub struct InnerClassAccessFlags: u16 {
+        const PUBLIC = 0x0001;
+        const PRIVATE = 0x0002;
+        const PROTECTED = 0x0004;
+        const STATIC = 0x0008;
+        const FINAL = 0x0010;
+        const INTERFACE = 0x0200;
+        const ABSTRACT = 0x0400;
+        const SYNTHETIC = 0x1000;
+        const ANNOTATION = 0x2000;
+        const ENUM = 0x4000;
+    }
+}
+
+/// An entry of the InnerClasses attribute, for a nested class which the class refers to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InnerClassInfo {
+    pub inner_class_info_index: ConstantPoolIndexRaw<ClassConstant>,
+    /// Zero if the class is not a member of another class, such as local and anonymous classes
+    pub outer_class_info_index: ConstantPoolIndexRaw<ClassConstant>,
+    /// Zero if the class is anonymous
+    pub inner_name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    /// The flags as they were in the class file, including bits which have no defined meaning
+    pub inner_class_access_flags: u16,
+}
+impl InnerClassInfo {
+    pub fn access_flags(&self) -> InnerClassAccessFlags {
+        InnerClassAccessFlags::from_bits_truncate(self.inner_class_access_flags)
+    }
+}
+
+/// The InnerClasses attribute records the nested classes that a class refers to, and how they
+/// are declared.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.6)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InnerClassesAttribute {
+    pub number_of_classes: u16,
+    pub classes: Vec<InnerClassInfo>,
+}
+
+/// The EnclosingMethod attribute is on local and anonymous classes, and records where they are
+/// declared.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.7)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnclosingMethodAttribute {
+    pub class_index: ConstantPoolIndexRaw<ClassConstant>,
+    /// Zero if the class is declared in an initializer rather than a method
+    pub method_index: ConstantPoolIndexRaw<NameAndTypeConstant>,
+}
+
+/// The NestHost attribute records the host of the nest that the class is a member of.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.28)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NestHostAttribute {
+    pub host_class_index: ConstantPoolIndexRaw<ClassConstant>,
+}
+
+/// The NestMembers attribute is on the host of a nest, and records the other members of it.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.29)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NestMembersAttribute {
+    pub number_of_classes: u16,
+    pub classes: Vec<ConstantPoolIndexRaw<ClassConstant>>,
+}
+
+#[cfg(feature = "modules")]
+bitflags! {
+    pub struct ModuleFlags: u16 {
+        /// The module is open, so every package is opened to every module
+        const OPEN = 0x0020;
+        const SYNTHETIC = 0x1000;
+        /// The module was implicitly declared
+        const MANDATED = 0x8000;
+    }
+}
+
+#[cfg(feature = "modules")]
+bitflags! {
+    pub struct RequiresFlags: u16 {
+        /// Modules which read this module also read the required module
+        const TRANSITIVE = 0x0020;
+        /// The required module is only needed at compile time
+        const STATIC_PHASE = 0x0040;
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+#[cfg(feature = "modules")]
+bitflags! {
+    /// The flags of an `exports` or `opens` entry of a module
+    pub struct ExportsFlags: u16 {
+        const SYNTHETIC = 0x1000;
+        const MANDATED = 0x8000;
+    }
+}
+
+#[cfg(feature = "modules")]
+/// A module that the module depends on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModuleRequires {
+    pub requires_index: ConstantPoolIndexRaw<ModuleConstant>,
+    /// The flags as they were in the class file, including bits which have no defined meaning
+    pub requires_flags: u16,
+    /// Zero if the version of the required module at compile time is not recorded
+    pub requires_version_index: ConstantPoolIndexRaw<Utf8Constant>,
+}
+#[cfg(feature = "modules")]
+impl ModuleRequires {
+    pub fn flags(&self) -> RequiresFlags {
+        RequiresFlags::from_bits_truncate(self.requires_flags)
+    }
+}
+
+#[cfg(feature = "modules")]
+/// A package that the module exports or opens, either to every module or only to those listed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleExports {
+    pub package_index: ConstantPoolIndexRaw<PackageConstant>,
+    /// The flags as they were in the class file, including bits which have no defined meaning
+    pub raw_flags: u16,
+    pub to_count: u16,
+    /// The modules that the package is exported or opened to, which is empty if it is to every
+    /// module
+    pub to_index: Vec<ConstantPoolIndexRaw<ModuleConstant>>,
+}
+#[cfg(feature = "modules")]
+impl ModuleExports {
+    pub fn flags(&self) -> ExportsFlags {
+        ExportsFlags::from_bits_truncate(self.raw_flags)
+    }
+
+    /// Whether every module can use the package, rather than only the modules listed
+    pub fn is_unqualified(&self) -> bool {
+        self.to_index.is_empty()
+    }
+}
+
+#[cfg(feature = "modules")]
+/// A service that the module provides, along with the classes which implement it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleProvides {
+    pub provides_index: ConstantPoolIndexRaw<ClassConstant>,
+    pub provides_with_count: u16,
+    pub provides_with_index: Vec<ConstantPoolIndexRaw<ClassConstant>>,
+}
+
+#[cfg(feature = "modules")]
+/// The Module attribute is on `module-info` classes, and records the module's dependencies, the
+/// packages it makes available to other modules, and the services it uses and provides.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.25)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleAttribute {
+    pub module_name_index: ConstantPoolIndexRaw<ModuleConstant>,
+    /// The flags as they were in the class file, including bits which have no defined meaning
+    pub module_flags: u16,
+    /// Zero if the module has no version
+    pub module_version_index: ConstantPoolIndexRaw<Utf8Constant>,
+    pub requires_count: u16,
+    pub requires: Vec<ModuleRequires>,
+    pub exports_count: u16,
+    pub exports: Vec<ModuleExports>,
+    pub opens_count: u16,
+    pub opens: Vec<ModuleExports>,
+    pub uses_count: u16,
+    /// The services that the module uses
+    pub uses_index: Vec<ConstantPoolIndexRaw<ClassConstant>>,
+    pub provides_count: u16,
+    pub provides: Vec<ModuleProvides>,
+}
+#[cfg(feature = "modules")]
+impl ModuleAttribute {
+    pub fn flags(&self) -> ModuleFlags {
+        ModuleFlags::from_bits_truncate(self.module_flags)
+    }
+}
+
+#[cfg(feature = "modules")]
+/// The ModulePackages attribute is on `module-info` classes, and records every package of the
+/// module, including those it doesn't export or open.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.26)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModulePackagesAttribute {
+    pub package_count: u16,
+    pub package_index: Vec<ConstantPoolIndexRaw<PackageConstant>>,
+}
+
+#[cfg(feature = "modules")]
+/// The ModuleMainClass attribute is on `module-info` classes, and records the main class of the
+/// module.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.27)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModuleMainClassAttribute {
+    pub main_class_index: ConstantPoolIndexRaw<ClassConstant>,
+}
+
+bitflags! {
+    pub struct MethodParameterAccessFlags: u16 {
+        const FINAL = 0x0010;
+        /// The parameter is not declared in the source, and is not implied by the language
+        const SYNTHETIC = 0x1000;
+        /// The parameter is implied by the language, such as the outer instance of an inner
+        /// class's constructor
+        const MANDATED = 0x8000;
+    }
+}
+
+/// An entry of the MethodParameters attribute, for one of the parameters of the method
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MethodParameter {
+    /// Zero if the parameter has no name
+    pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    /// The flags as they were in the class file, including bits which have no defined meaning
+    pub access_flags: u16,
+}
+impl MethodParameter {
+    pub fn flags(&self) -> MethodParameterAccessFlags {
+        MethodParameterAccessFlags::from_bits_truncate(self.access_flags)
+    }
+}
+
+/// The MethodParameters attribute records the names and flags of the parameters of a method,
+/// which javac only emits when given `-parameters` or for the parameters of some generated
+/// methods.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.24)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodParametersAttribute {
+    pub parameters_count: u8,
+    pub parameters: Vec<MethodParameter>,
+}
+
+/// A component of a record, which is one of the parameters in the record's header
+#[derive(Clone, Debug)]
+pub struct RecordComponentInfo {
+    pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    pub descriptor_index: ConstantPoolIndexRaw<Utf8Constant>,
+    pub attributes_count: u16,
+    pub attributes: SmallVec<[AttributeInfo; 2]>,
+}
+
+impl HasAttributes for RecordComponentInfo {
+    fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+}
+
+/// The Record attribute marks a class as a record, and lists its components.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.30)
+#[derive(Clone, Debug)]
+pub struct RecordAttribute {
+    pub components_count: u16,
+    pub components: Vec<RecordComponentInfo>,
+}
+
+#[cfg(feature = "annotations")]
+/// An annotation, as found in the annotation attributes and nested in element values.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.16)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Annotation {
+    /// The field descriptor of the annotation's type, such as `Ljava/lang/Deprecated;`
+    pub type_index: ConstantPoolIndexRaw<Utf8Constant>,
+    pub num_element_value_pairs: u16,
+    pub element_value_pairs: Vec<ElementValuePair>,
+}
+
+#[cfg(feature = "annotations")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ElementValuePair {
+    pub element_name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    pub value: ElementValue,
+}
+
+#[cfg(feature = "annotations")]
+/// The value of an element of an annotation, or the default value of an annotation method
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ElementValue {
+    /// A primitive or string value, where the tag is one of `BCDFIJSZs`.
+    /// Strings refer to a Utf8 entry rather than a String entry.
+    Const {
+        tag: u8,
+        const_value_index: ConstantPoolIndexRaw<ConstantInfo>,
+    },
+    Enum {
+        /// The field descriptor of the enum type
+        type_name_index: ConstantPoolIndexRaw<Utf8Constant>,
+        const_name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    },
+    /// A class literal, given by its return descriptor, such as `Ljava/lang/String;` or `V`
+    Class {
+        class_info_index: ConstantPoolIndexRaw<Utf8Constant>,
+    },
+    Annotation(Annotation),
+    Array {
+        num_values: u16,
+        values: Vec<ElementValue>,
+    },
+}
+
+#[cfg(feature = "annotations")]
+/// The info of a RuntimeVisibleAnnotations or RuntimeInvisibleAnnotations attribute
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnnotationsAttribute {
+    pub num_annotations: u16,
+    pub annotations: Vec<Annotation>,
+}
+
+#[cfg(feature = "annotations")]
+/// The annotations on one parameter of a method
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterAnnotations {
+    pub num_annotations: u16,
+    pub annotations: Vec<Annotation>,
+}
+
+#[cfg(feature = "annotations")]
+/// The info of a RuntimeVisibleParameterAnnotations or RuntimeInvisibleParameterAnnotations
+/// attribute.
+/// The parameters may not line up with those of the descriptor, since javac leaves out synthetic
+/// and implicit parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterAnnotationsAttribute {
+    pub num_parameters: u8,
+    pub parameter_annotations: Vec<ParameterAnnotations>,
+}
+
+#[cfg(feature = "annotations")]
+/// An entry of a local variable target, which is where the local is live
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalVarTargetEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16,
+}
+
+#[cfg(feature = "annotations")]
+/// Which type in a declaration or expression a type annotation is on, the form of which depends on
+/// the target type.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.20.1)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TargetInfo {
+    /// 0x00 and 0x01, a type parameter of a class or method
+    TypeParameter { type_parameter_index: u8 },
+    /// 0x10, the superclass if the index is 65535, otherwise the interface with the index
+    Supertype { supertype_index: u16 },
+    /// 0x11 and 0x12, a bound of a type parameter of a class or method
+    TypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8,
+    },
+    /// 0x13 to 0x15, the type of a field or record component, the return type, or the receiver
+    Empty,
+    /// 0x16, the type of a formal parameter
+    FormalParameter { formal_parameter_index: u8 },
+    /// 0x17, a type in the throws clause
+    Throws { throws_type_index: u16 },
+    /// 0x40 and 0x41, the type of a local variable or resource variable
+    Localvar {
+        table_length: u16,
+        table: Vec<LocalVarTargetEntry>,
+    },
+    /// 0x42, the type in the exception parameter of a catch clause
+    Catch { exception_table_index: u16 },
+    /// 0x43 to 0x46, the type in an instanceof, new, or method reference expression
+    Offset { offset: u16 },
+    /// 0x47 to 0x4B, a type argument of a cast, constructor call, method call, or method reference
+    TypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+}
+
+/// The form of a type annotation's target info, which its target type decides.
+/// This is the one table of target types, shared by the parser, the visitor and index remapping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TargetInfoKind {
+    TypeParameter,
+    Supertype,
+    TypeParameterBound,
+    Empty,
+    FormalParameter,
+    Throws,
+    Localvar,
+    Catch,
+    Offset,
+    TypeArgument,
+}
+impl TargetInfoKind {
+    pub(crate) fn from_target_type(target_type: u8) -> Option<TargetInfoKind> {
+        Some(match target_type {
+            0x00 | 0x01 => TargetInfoKind::TypeParameter,
+            0x10 => TargetInfoKind::Supertype,
+            0x11 | 0x12 => TargetInfoKind::TypeParameterBound,
+            0x13..=0x15 => TargetInfoKind::Empty,
+            0x16 => TargetInfoKind::FormalParameter,
+            0x17 => TargetInfoKind::Throws,
+            0x40 | 0x41 => TargetInfoKind::Localvar,
+            0x42 => TargetInfoKind::Catch,
+            0x43..=0x46 => TargetInfoKind::Offset,
+            0x47..=0x4b => TargetInfoKind::TypeArgument,
+            _ => return None,
+        })
+    }
+
+    /// The length of the target info in bytes, or None for a local variable target, which is a
+    /// u16 count of 6 byte entries
+    pub(crate) fn fixed_len(self) -> Option<usize> {
+        Some(match self {
+            TargetInfoKind::Empty => 0,
+            TargetInfoKind::TypeParameter | TargetInfoKind::FormalParameter => 1,
+            TargetInfoKind::Supertype
+            | TargetInfoKind::TypeParameterBound
+            | TargetInfoKind::Throws
+            | TargetInfoKind::Catch
+            | TargetInfoKind::Offset => 2,
+            TargetInfoKind::TypeArgument => 3,
+            TargetInfoKind::Localvar => return None,
+        })
+    }
+}
+
+#[cfg(feature = "annotations")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TypePathEntry {
+    /// 0 for deeper in an array type, 1 for deeper in a nested type, 2 for the bound of a wildcard,
+    /// and 3 for a type argument
+    pub type_path_kind: u8,
+    pub type_argument_index: u8,
+}
+
+#[cfg(feature = "annotations")]
+/// Where in the target type the annotation is, such as on the element type of an array
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypePath {
+    pub path_length: u8,
+    pub path: Vec<TypePathEntry>,
+}
+
+#[cfg(feature = "annotations")]
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.20)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeAnnotation {
+    pub target_type: u8,
+    pub target_info: TargetInfo,
+    pub target_path: TypePath,
+    pub annotation: Annotation,
+}
+
+#[cfg(feature = "annotations")]
+/// The info of a RuntimeVisibleTypeAnnotations or RuntimeInvisibleTypeAnnotations attribute
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeAnnotationsAttribute {
+    pub num_annotations: u16,
+    pub annotations: Vec<TypeAnnotation>,
+}
+
+#[cfg(feature = "annotations")]
+/// The default value of the element that an annotation type's method declares
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.22)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnnotationDefaultAttribute {
+    pub default_value: ElementValue,
+}
@@ -0,0 +1,145 @@
+use crate::attribute_info::names::AttributeName;
+use crate::attribute_info::{AttributeInfo, CodeAttribute, HasAttributes, KnownAttribute};
+use crate::constant_pool::ConstantPool;
+use crate::ClassFile;
+
+/// The first class file major version that the JVM recognizes the attribute in, or None if the
+/// attribute is not one of the predefined attributes.
+/// Attributes that appear in earlier versions are silently ignored by the JVM.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7-310)
+pub fn attribute_min_major_version(name: &str) -> Option<u16> {
+    AttributeName::from_name(name).map(AttributeName::min_major_version)
+}
+
+impl AttributeName {
+    /// The first class file major version that the JVM recognizes the attribute in
+    pub fn min_major_version(self) -> u16 {
+        use AttributeName::*;
+        match self {
+            ConstantValue | Code | Exceptions | SourceFile | LineNumberTable
+            | LocalVariableTable | InnerClasses | Synthetic | Deprecated => 45,
+            EnclosingMethod
+            | Signature
+            | SourceDebugExtension
+            | LocalVariableTypeTable
+            | RuntimeVisibleAnnotations
+            | RuntimeInvisibleAnnotations
+            | RuntimeVisibleParameterAnnotations
+            | RuntimeInvisibleParameterAnnotations
+            | AnnotationDefault => 49,
+            StackMapTable => 50,
+            BootstrapMethods => 51,
+            RuntimeVisibleTypeAnnotations | RuntimeInvisibleTypeAnnotations | MethodParameters => {
+                52
+            }
+            Module | ModulePackages | ModuleMainClass => 53,
+            NestHost | NestMembers => 55,
+            Record => 60,
+            PermittedSubclasses => 61,
+        }
+    }
+}
+
+/// What an attribute is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttributeOwner {
+    Class,
+    /// The index of the field
+    Field(usize),
+    /// The index of the method
+    Method(usize),
+    /// The Code attribute of the method at the index
+    Code(usize),
+}
+
+/// A predefined attribute that appeared in a class file version which predates it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeVersionViolation {
+    pub name: String,
+    pub owner: AttributeOwner,
+    /// The first major version that the attribute is recognized in
+    pub min_major: u16,
+    /// The offset of the attribute's info in the class file
+    pub offset: usize,
+}
+
+fn check_attributes(
+    attributes: &[AttributeInfo],
+    owner: AttributeOwner,
+    major: u16,
+    pool: &ConstantPool,
+    data: &[u8],
+    out: &mut Vec<AttributeVersionViolation>,
+) {
+    for attr in attributes {
+        let name = match pool.get_t(attr.attribute_name_index) {
+            Some(name) => name.as_text(data),
+            None => continue,
+        };
+        if let Some(min_major) = attribute_min_major_version(&name) {
+            if major < min_major {
+                out.push(AttributeVersionViolation {
+                    name: name.into_owned(),
+                    owner,
+                    min_major,
+                    offset: attr.info.start,
+                });
+            }
+        }
+    }
+}
+
+impl ClassFile {
+    /// Find every predefined attribute, including those nested in Code attributes, which appears
+    /// in a class file version that predates it.
+    /// Code attributes which fail to parse are not searched.
+    pub fn attribute_version_violations(&self, data: &[u8]) -> Vec<AttributeVersionViolation> {
+        let major = self.version.major;
+        let pool = &self.const_pool;
+        let mut out = Vec::new();
+
+        check_attributes(
+            &self.attributes,
+            AttributeOwner::Class,
+            major,
+            pool,
+            data,
+            &mut out,
+        );
+        for (i, field) in self.fields.iter().enumerate() {
+            check_attributes(
+                &field.attributes,
+                AttributeOwner::Field(i),
+                major,
+                pool,
+                data,
+                &mut out,
+            );
+        }
+        for (i, method) in self.methods.iter().enumerate() {
+            check_attributes(
+                &method.attributes,
+                AttributeOwner::Method(i),
+                major,
+                pool,
+                data,
+                &mut out,
+            );
+
+            if let Some(code) = method.find_attribute_info(pool, data, CodeAttribute::NAME) {
+                if let Ok(code) = CodeAttribute::parse_info(code, data) {
+                    check_attributes(
+                        &code.attributes,
+                        AttributeOwner::Code(i),
+                        major,
+                        pool,
+                        data,
+                        &mut out,
+                    );
+                }
+            }
+        }
+
+        out
+    }
+}
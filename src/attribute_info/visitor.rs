@@ -0,0 +1,308 @@
+use nom::bytes::complete::take;
+use nom::error::ErrorKind;
+use nom::number::complete::{be_u16, be_u8};
+use nom::{Err, IResult};
+
+use crate::attribute_info::{names, AttributeInfo, TargetInfoKind};
+use crate::constant_info::{ConstantInfo, Utf8Constant};
+use crate::constant_pool::ConstantPoolIndexRaw;
+use crate::parser::{ParseData, DEFAULT_MAX_NESTING_DEPTH};
+use crate::util::constant_pool_index_raw;
+use crate::LoadError;
+
+/// Callbacks for walking over the raw bytes of annotations, without building them.
+/// Every method does nothing by default, so only the interesting parts need to be implemented.
+///
+/// See [`visit_annotations`], [`visit_parameter_annotations`], [`visit_type_annotations`], and
+/// [`visit_element_value`].
+pub trait AnnotationVisitor {
+    /// The start of an annotation, which is nested inside an element value if `depth` is above
+    /// zero.
+    /// Returning false skips the elements of the annotation, and its end is not visited.
+    fn annotation_start(
+        &mut self,
+        _type_index: ConstantPoolIndexRaw<Utf8Constant>,
+        _depth: usize,
+    ) -> bool {
+        true
+    }
+
+    fn annotation_end(&mut self, _depth: usize) {}
+
+    /// The name of the element whose value is visited next
+    fn element_name(&mut self, _name_index: ConstantPoolIndexRaw<Utf8Constant>) {}
+
+    /// A primitive or string value, where the tag is one of `BCDFIJSZs`
+    fn const_value(&mut self, _tag: u8, _value_index: ConstantPoolIndexRaw<ConstantInfo>) {}
+
+    fn enum_value(
+        &mut self,
+        _type_name_index: ConstantPoolIndexRaw<Utf8Constant>,
+        _const_name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    ) {
+    }
+
+    /// A class literal, given by its return descriptor, such as `Ljava/lang/String;` or `V`
+    fn class_value(&mut self, _class_info_index: ConstantPoolIndexRaw<Utf8Constant>) {}
+
+    /// The start of an array with `len` element values, which are visited before its end
+    fn array_start(&mut self, _len: u16) {}
+
+    fn array_end(&mut self) {}
+
+    /// The start of the annotations on the parameter, for parameter annotations
+    fn parameter_start(&mut self, _index: u8) {}
+
+    /// What a type annotation is on, which is visited before its annotation.
+    /// The target info and type path are the raw bytes after the target type, with the type path
+    /// including its length.
+    fn type_annotation_target(&mut self, _target_type: u8, _target_info: &[u8], _type_path: &[u8]) {
+    }
+
+    /// How deeply element values may be nested inside arrays and annotations before visiting
+    /// fails, which keeps deeply nested data from overflowing the stack
+    fn max_depth(&self) -> usize {
+        DEFAULT_MAX_NESTING_DEPTH
+    }
+}
+
+/// Walks over the bytes without calling anything
+struct SkipVisitor;
+impl AnnotationVisitor for SkipVisitor {}
+
+/// `budget` is how many more levels of element values may be nested
+fn annotation_visit<'a, V: AnnotationVisitor>(
+    i: ParseData<'a>,
+    visitor: &mut V,
+    depth: usize,
+    budget: usize,
+) -> IResult<ParseData<'a>, ()> {
+    let (i, type_index) = constant_pool_index_raw(i)?;
+    if visitor.annotation_start(type_index, depth) {
+        let (i, _) = element_value_pairs_visit(i, visitor, depth, budget)?;
+        visitor.annotation_end(depth);
+        Ok((i, ()))
+    } else {
+        element_value_pairs_visit(i, &mut SkipVisitor, depth, budget)
+    }
+}
+
+fn element_value_pairs_visit<'a, V: AnnotationVisitor>(
+    i: ParseData<'a>,
+    visitor: &mut V,
+    depth: usize,
+    budget: usize,
+) -> IResult<ParseData<'a>, ()> {
+    let (mut i, num_element_value_pairs) = be_u16(i)?;
+    for _ in 0..num_element_value_pairs {
+        let (rest, name_index) = constant_pool_index_raw(i)?;
+        visitor.element_name(name_index);
+        let (rest, _) = element_value_visit(rest, visitor, depth, budget)?;
+        i = rest;
+    }
+    Ok((i, ()))
+}
+
+fn element_value_visit<'a, V: AnnotationVisitor>(
+    i: ParseData<'a>,
+    visitor: &mut V,
+    depth: usize,
+    budget: usize,
+) -> IResult<ParseData<'a>, ()> {
+    let budget = match budget.checked_sub(1) {
+        Some(budget) => budget,
+        None => return Err(Err::Failure(error_position!(i, ErrorKind::TooLarge))),
+    };
+    let (i, tag) = be_u8(i)?;
+    match tag {
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' => {
+            let (i, value_index) = constant_pool_index_raw(i)?;
+            visitor.const_value(tag, value_index);
+            Ok((i, ()))
+        }
+        b'e' => {
+            let (i, type_name_index) = constant_pool_index_raw(i)?;
+            let (i, const_name_index) = constant_pool_index_raw(i)?;
+            visitor.enum_value(type_name_index, const_name_index);
+            Ok((i, ()))
+        }
+        b'c' => {
+            let (i, class_info_index) = constant_pool_index_raw(i)?;
+            visitor.class_value(class_info_index);
+            Ok((i, ()))
+        }
+        b'@' => annotation_visit(i, visitor, depth + 1, budget),
+        b'[' => {
+            let (mut i, num_values) = be_u16(i)?;
+            visitor.array_start(num_values);
+            for _ in 0..num_values {
+                i = element_value_visit(i, visitor, depth, budget)?.0;
+            }
+            visitor.array_end();
+            Ok((i, ()))
+        }
+        _ => Err(Err::Error(error_position!(i, ErrorKind::Tag))),
+    }
+}
+
+fn annotations_visit<'a, V: AnnotationVisitor>(
+    i: ParseData<'a>,
+    visitor: &mut V,
+) -> IResult<ParseData<'a>, ()> {
+    let (mut i, num_annotations) = be_u16(i)?;
+    for _ in 0..num_annotations {
+        let budget = visitor.max_depth();
+        i = annotation_visit(i, visitor, 0, budget)?.0;
+    }
+    Ok((i, ()))
+}
+
+fn type_annotation_visit<'a, V: AnnotationVisitor>(
+    i: ParseData<'a>,
+    visitor: &mut V,
+) -> IResult<ParseData<'a>, ()> {
+    let (rest, target_type) = be_u8(i.clone())?;
+    let kind = match TargetInfoKind::from_target_type(target_type) {
+        Some(kind) => kind,
+        None => return Err(Err::Error(error_position!(i, ErrorKind::Tag))),
+    };
+    let target_info_len = match kind.fixed_len() {
+        Some(len) => len,
+        // localvar_target, a table of 6 byte entries
+        None => {
+            let (_, table_length) = be_u16(rest.clone())?;
+            2 + 6 * usize::from(table_length)
+        }
+    };
+    let (rest, target_info) = take(target_info_len)(rest)?;
+    let (_, path_length) = be_u8(rest.clone())?;
+    let (rest, type_path) = take(1 + 2 * usize::from(path_length))(rest)?;
+    visitor.type_annotation_target(target_type, target_info.data(), type_path.data());
+    let budget = visitor.max_depth();
+    annotation_visit(rest, visitor, 0, budget)
+}
+
+fn type_annotations_visit<'a, V: AnnotationVisitor>(
+    i: ParseData<'a>,
+    visitor: &mut V,
+) -> IResult<ParseData<'a>, ()> {
+    let (mut i, num_annotations) = be_u16(i)?;
+    for _ in 0..num_annotations {
+        i = type_annotation_visit(i, visitor)?.0;
+    }
+    Ok((i, ()))
+}
+
+/// Find the target type of each type annotation in the info of a type annotations attribute,
+/// along with where its target info starts in `info`.
+/// Returns None if the attribute is malformed, or nests its element values too deeply.
+pub(crate) fn type_annotation_targets(info: &[u8]) -> Option<Vec<(u8, usize)>> {
+    let (mut i, num_annotations) = be_u16::<_, ()>(ParseData::new(info)).ok()?;
+    let mut targets = Vec::new();
+    for _ in 0..num_annotations {
+        let target_type = *i.data().first()?;
+        targets.push((target_type, i.pos() + 1));
+        i = type_annotation_visit(i, &mut SkipVisitor).ok()?.0;
+    }
+    Some(targets)
+}
+
+fn parameter_annotations_visit<'a, V: AnnotationVisitor>(
+    i: ParseData<'a>,
+    visitor: &mut V,
+) -> IResult<ParseData<'a>, ()> {
+    let (mut i, num_parameters) = be_u8(i)?;
+    for index in 0..num_parameters {
+        visitor.parameter_start(index);
+        i = annotations_visit(i, visitor)?.0;
+    }
+    Ok((i, ()))
+}
+
+fn info_data<'a>(info: &AttributeInfo, class_file_data: &'a [u8]) -> ParseData<'a> {
+    ParseData::from_range(class_file_data, info.info.clone())
+}
+
+/// Visit the annotations of a RuntimeVisibleAnnotations or RuntimeInvisibleAnnotations attribute
+pub fn visit_annotations<V: AnnotationVisitor>(
+    info: &AttributeInfo,
+    class_file_data: &[u8],
+    visitor: &mut V,
+) -> Result<(), LoadError> {
+    annotations_visit(info_data(info, class_file_data), visitor)
+        .map(|_| ())
+        .map_err(|_| LoadError::Unknown)
+}
+
+/// Visit the annotations of a RuntimeVisibleTypeAnnotations or RuntimeInvisibleTypeAnnotations
+/// attribute
+pub fn visit_type_annotations<V: AnnotationVisitor>(
+    info: &AttributeInfo,
+    class_file_data: &[u8],
+    visitor: &mut V,
+) -> Result<(), LoadError> {
+    type_annotations_visit(info_data(info, class_file_data), visitor)
+        .map(|_| ())
+        .map_err(|_| LoadError::Unknown)
+}
+
+/// Visit the annotations of a RuntimeVisibleParameterAnnotations or
+/// RuntimeInvisibleParameterAnnotations attribute
+pub fn visit_parameter_annotations<V: AnnotationVisitor>(
+    info: &AttributeInfo,
+    class_file_data: &[u8],
+    visitor: &mut V,
+) -> Result<(), LoadError> {
+    parameter_annotations_visit(info_data(info, class_file_data), visitor)
+        .map(|_| ())
+        .map_err(|_| LoadError::Unknown)
+}
+
+/// Visit the element value of an AnnotationDefault attribute
+pub fn visit_element_value<V: AnnotationVisitor>(
+    info: &AttributeInfo,
+    class_file_data: &[u8],
+    visitor: &mut V,
+) -> Result<(), LoadError> {
+    let budget = visitor.max_depth();
+    element_value_visit(info_data(info, class_file_data), visitor, 0, budget)
+        .map(|_| ())
+        .map_err(|_| LoadError::Unknown)
+}
+
+/// Only limits the depth
+struct DepthLimit(usize);
+impl AnnotationVisitor for DepthLimit {
+    fn max_depth(&self) -> usize {
+        self.0
+    }
+}
+
+/// Find where the element values of an attribute with annotations nest deeper than `max_depth`,
+/// returning the offset of the element value past the limit.
+/// Attributes which are malformed in other ways are not reported.
+pub(crate) fn nesting_too_deep(
+    name: &str,
+    info: &AttributeInfo,
+    class_file_data: &[u8],
+    max_depth: usize,
+) -> Option<usize> {
+    let i = info_data(info, class_file_data);
+    let visitor = &mut DepthLimit(max_depth);
+    let result = match name {
+        names::RUNTIME_VISIBLE_ANNOTATIONS | names::RUNTIME_INVISIBLE_ANNOTATIONS => {
+            annotations_visit(i, visitor)
+        }
+        names::RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS
+        | names::RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS => parameter_annotations_visit(i, visitor),
+        names::RUNTIME_VISIBLE_TYPE_ANNOTATIONS | names::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
+            type_annotations_visit(i, visitor)
+        }
+        names::ANNOTATION_DEFAULT => element_value_visit(i, visitor, 0, max_depth),
+        _ => return None,
+    };
+    match result {
+        Err(Err::Failure(err)) if err.code == ErrorKind::TooLarge => Some(err.input.pos()),
+        _ => None,
+    }
+}
@@ -0,0 +1,442 @@
+//! Building class files from scratch, such as for test fixtures.
+//!
+//! The builders produce a [`ClassFile`] along with the data that its ranges refer to, which holds
+//! the bytes of the Utf8 constants and attributes in the order they were added. Together they work
+//! like a parsed class file and its data, and [`ClassFile::to_bytes`] writes them out as a class
+//! file.
+
+use std::collections::HashMap;
+
+use smallvec::SmallVec;
+
+use crate::attribute_info::AttributeInfo;
+use crate::constant_info::*;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::field_info::{FieldAccessFlags, FieldInfo};
+use crate::method_info::{MethodBuilder, MethodBuilderError, MethodInfo};
+use crate::stale::StaleRanges;
+use crate::writer::write_constant;
+use crate::{ClassAccessFlags, ClassFile, ClassFileVersion};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassBuilderError {
+    /// The constant pool has no room for another constant
+    PoolTooLarge,
+    /// The text is longer than a Utf8 constant can hold
+    Utf8TooLong,
+    /// The class already has a field with the same name and descriptor
+    DuplicateField,
+    /// There are more interfaces, fields, or attributes than a class file can hold
+    TooManyItems,
+    Method(MethodBuilderError),
+}
+impl From<MethodBuilderError> for ClassBuilderError {
+    fn from(err: MethodBuilderError) -> Self {
+        ClassBuilderError::Method(err)
+    }
+}
+
+/// Builds a constant pool and the data that its Utf8 constants refer to.
+/// Adding a constant that the pool already has returns the index of the existing one.
+#[derive(Debug, Clone, Default)]
+pub struct ConstantPoolBuilder {
+    pool: ConstantPool,
+    data: Vec<u8>,
+    /// The index of each entry, by its class file form
+    entries: HashMap<Vec<u8>, u16>,
+}
+impl ConstantPoolBuilder {
+    pub fn new() -> ConstantPoolBuilder {
+        ConstantPoolBuilder::default()
+    }
+
+    /// Continue building an existing pool, such as a parsed class file's, with the data that its
+    /// Utf8 constants refer to. Adding a constant that the pool already has returns the index of
+    /// its first copy.
+    pub fn from_pool(pool: ConstantPool, data: Vec<u8>) -> ConstantPoolBuilder {
+        let mut entries = HashMap::new();
+        for (index, entry) in pool.iter_indexed() {
+            let mut key = Vec::new();
+            if write_constant(entry, index.0, &data, &mut key).is_ok() {
+                entries.entry(key).or_insert(index.0);
+            }
+        }
+        ConstantPoolBuilder {
+            pool,
+            data,
+            entries,
+        }
+    }
+
+    /// Get the index of an entry equal to the given one, adding it if the pool has none.
+    /// A Utf8 entry must refer to text in [`Self::data`], so new text should be added with
+    /// [`Self::utf8`] instead.
+    pub fn add(
+        &mut self,
+        entry: ConstantInfo,
+    ) -> Result<ConstantPoolIndexRaw<ConstantInfo>, ClassBuilderError> {
+        let mut key = Vec::new();
+        write_constant(&entry, 0, &self.data, &mut key)
+            .map_err(|_| ClassBuilderError::Utf8TooLong)?;
+        if let Some(&index) = self.entries.get(&key) {
+            return Ok(ConstantPoolIndexRaw::new(index));
+        }
+        let index = self
+            .pool
+            .push(entry)
+            .ok_or(ClassBuilderError::PoolTooLarge)?;
+        self.entries.insert(key, index.0);
+        Ok(index)
+    }
+
+    /// Add constants to a pool and the data that it refers to through a builder, such as for
+    /// editing a parsed class file. The pool and data are put back with whatever was added, even
+    /// if `f` fails, so a caller that gives up should restore its own copies.
+    pub fn with_pool<T>(
+        pool: &mut ConstantPool,
+        data: &mut Vec<u8>,
+        f: impl FnOnce(&mut ConstantPoolBuilder) -> T,
+    ) -> T {
+        let mut constants =
+            ConstantPoolBuilder::from_pool(std::mem::take(pool), std::mem::take(data));
+        let result = f(&mut constants);
+        (*pool, *data) = constants.finish();
+        result
+    }
+
+    /// Add a Utf8 constant with the text, which is written as modified UTF-8
+    pub fn utf8(
+        &mut self,
+        text: &str,
+    ) -> Result<ConstantPoolIndexRaw<Utf8Constant>, ClassBuilderError> {
+        self.utf8_bytes(&cesu8::to_java_cesu8(text))
+    }
+
+    /// Add a Utf8 constant with the bytes as they appear in the class file, which are modified
+    /// UTF-8 rather than UTF-8 for text with nul or supplementary characters
+    pub fn utf8_bytes(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<ConstantPoolIndexRaw<Utf8Constant>, ClassBuilderError> {
+        let len = u16::try_from(bytes.len()).map_err(|_| ClassBuilderError::Utf8TooLong)?;
+        let mut key = vec![1];
+        key.extend_from_slice(&len.to_be_bytes());
+        key.extend_from_slice(bytes);
+        if let Some(&index) = self.entries.get(&key) {
+            return Ok(ConstantPoolIndexRaw::new(index));
+        }
+
+        let start = self.data.len();
+        self.data.extend_from_slice(bytes);
+        let text = Utf8Constant::new(start..self.data.len());
+        match self.pool.push(ConstantInfo::Utf8(text)) {
+            Some(index) => {
+                self.entries.insert(key, index.0);
+                Ok(ConstantPoolIndexRaw::new(index.0))
+            }
+            None => {
+                self.data.truncate(start);
+                Err(ClassBuilderError::PoolTooLarge)
+            }
+        }
+    }
+
+    /// Add a Class constant with the internal name, such as `java/lang/String`
+    pub fn class(
+        &mut self,
+        name: &str,
+    ) -> Result<ConstantPoolIndexRaw<ClassConstant>, ClassBuilderError> {
+        let name_index = self.utf8(name)?;
+        let index = self.add(ConstantInfo::Class(ClassConstant { name_index }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn string(
+        &mut self,
+        text: &str,
+    ) -> Result<ConstantPoolIndexRaw<StringConstant>, ClassBuilderError> {
+        let string_index = self.utf8(text)?;
+        let index = self.add(ConstantInfo::String(StringConstant { string_index }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn integer(
+        &mut self,
+        value: i32,
+    ) -> Result<ConstantPoolIndexRaw<IntegerConstant>, ClassBuilderError> {
+        let index = self.add(ConstantInfo::Integer(IntegerConstant { value }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn float(
+        &mut self,
+        value: f32,
+    ) -> Result<ConstantPoolIndexRaw<FloatConstant>, ClassBuilderError> {
+        let index = self.add(ConstantInfo::Float(FloatConstant { value }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn long(
+        &mut self,
+        value: i64,
+    ) -> Result<ConstantPoolIndexRaw<LongConstant>, ClassBuilderError> {
+        let index = self.add(ConstantInfo::Long(LongConstant { value }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn double(
+        &mut self,
+        value: f64,
+    ) -> Result<ConstantPoolIndexRaw<DoubleConstant>, ClassBuilderError> {
+        let index = self.add(ConstantInfo::Double(DoubleConstant { value }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn name_and_type(
+        &mut self,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndexRaw<NameAndTypeConstant>, ClassBuilderError> {
+        let name_index = self.utf8(name)?;
+        let descriptor_index = self.utf8(descriptor)?;
+        let index = self.add(ConstantInfo::NameAndType(NameAndTypeConstant {
+            name_index,
+            descriptor_index,
+        }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn field_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndexRaw<FieldRefConstant>, ClassBuilderError> {
+        let class_index = self.class(class)?;
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        let index = self.add(ConstantInfo::FieldRef(FieldRefConstant {
+            class_index,
+            name_and_type_index,
+        }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn method_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndexRaw<MethodRefConstant>, ClassBuilderError> {
+        let class_index = self.class(class)?;
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        let index = self.add(ConstantInfo::MethodRef(MethodRefConstant {
+            class_index,
+            name_and_type_index,
+        }))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn interface_method_ref(
+        &mut self,
+        class: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ConstantPoolIndexRaw<InterfaceMethodRefConstant>, ClassBuilderError> {
+        let class_index = self.class(class)?;
+        let name_and_type_index = self.name_and_type(name, descriptor)?;
+        let index = self.add(ConstantInfo::InterfaceMethodRef(
+            InterfaceMethodRefConstant {
+                class_index,
+                name_and_type_index,
+            },
+        ))?;
+        Ok(ConstantPoolIndexRaw::new(index.0))
+    }
+
+    pub fn pool(&self) -> &ConstantPool {
+        &self.pool
+    }
+
+    /// The data that the Utf8 constants refer to
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The data, for writing attributes that refer to the pool's constants
+    pub(crate) fn data_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.data
+    }
+
+    pub fn finish(self) -> (ConstantPool, Vec<u8>) {
+        (self.pool, self.data)
+    }
+}
+
+/// Builds a class file from scratch.
+/// The constants that the class and its members need are added to the pool as they are added, and
+/// instructions can refer to other constants by adding them through [`Self::constants`] first.
+///
+/// Names are internal names, such as `java/lang/Object`, and are written to the pool as is.
+#[derive(Debug, Clone)]
+pub struct ClassFileBuilder {
+    version: ClassFileVersion,
+    access_flags: ClassAccessFlags,
+    constants: ConstantPoolBuilder,
+    this_class: ConstantPoolIndexRaw<ClassConstant>,
+    super_class: ConstantPoolIndexRaw<ClassConstant>,
+    interfaces: SmallVec<[ConstantPoolIndexRaw<ClassConstant>; 4]>,
+    fields: SmallVec<[FieldInfo; 6]>,
+    methods: Vec<MethodBuilder>,
+    attributes: SmallVec<[AttributeInfo; 4]>,
+}
+impl ClassFileBuilder {
+    /// Start a public class for Java 8 with the name and superclass, which should only be None for
+    /// `java/lang/Object` and modules
+    pub fn new(
+        name: &str,
+        super_class: Option<&str>,
+    ) -> Result<ClassFileBuilder, ClassBuilderError> {
+        let mut constants = ConstantPoolBuilder::new();
+        let this_class = constants.class(name)?;
+        let super_class = match super_class {
+            Some(name) => constants.class(name)?,
+            None => ConstantPoolIndexRaw::new(0),
+        };
+        Ok(ClassFileBuilder {
+            version: ClassFileVersion {
+                major: 52,
+                minor: 0,
+            },
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            constants,
+            this_class,
+            super_class,
+            interfaces: SmallVec::new(),
+            fields: SmallVec::new(),
+            methods: Vec::new(),
+            attributes: SmallVec::new(),
+        })
+    }
+
+    pub fn version(&mut self, version: ClassFileVersion) -> &mut Self {
+        self.version = version;
+        self
+    }
+
+    pub fn access_flags(&mut self, access_flags: ClassAccessFlags) -> &mut Self {
+        self.access_flags = access_flags;
+        self
+    }
+
+    /// The pool that the class is built with, for adding the constants that instructions refer to
+    pub fn constants(&mut self) -> &mut ConstantPoolBuilder {
+        &mut self.constants
+    }
+
+    pub fn interface(&mut self, name: &str) -> Result<&mut Self, ClassBuilderError> {
+        if self.interfaces.len() >= usize::from(u16::MAX) {
+            return Err(ClassBuilderError::TooManyItems);
+        }
+        let index = self.constants.class(name)?;
+        self.interfaces.push(index);
+        Ok(self)
+    }
+
+    /// Add a field without attributes
+    pub fn field(
+        &mut self,
+        access_flags: FieldAccessFlags,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<&mut Self, ClassBuilderError> {
+        if self.fields.len() >= usize::from(u16::MAX) {
+            return Err(ClassBuilderError::TooManyItems);
+        }
+        let name_index = self.constants.utf8(name)?;
+        let descriptor_index = self.constants.utf8(descriptor)?;
+        let duplicate = self.fields.iter().any(|field| {
+            field.name_index == name_index && field.descriptor_index == descriptor_index
+        });
+        if duplicate {
+            return Err(ClassBuilderError::DuplicateField);
+        }
+
+        self.fields.push(FieldInfo {
+            access_flags,
+            raw_access_flags: access_flags.bits(),
+            name_index,
+            descriptor_index,
+            attributes_count: 0,
+            attributes: SmallVec::new(),
+        });
+        Ok(self)
+    }
+
+    /// Add a method, which is built when the class is
+    pub fn method(&mut self, method: MethodBuilder) -> &mut Self {
+        self.methods.push(method);
+        self
+    }
+
+    /// Add a class attribute with the name and info
+    pub fn attribute(&mut self, name: &str, info: &[u8]) -> Result<&mut Self, ClassBuilderError> {
+        if self.attributes.len() >= usize::from(u16::MAX) {
+            return Err(ClassBuilderError::TooManyItems);
+        }
+        let attribute_length =
+            u32::try_from(info.len()).map_err(|_| ClassBuilderError::TooManyItems)?;
+        let attribute_name_index = self.constants.utf8(name)?;
+        let data = self.constants.data_mut();
+        let start = data.len();
+        data.extend_from_slice(info);
+        self.attributes.push(AttributeInfo {
+            attribute_name_index,
+            attribute_length,
+            info: start..data.len(),
+        });
+        Ok(self)
+    }
+
+    /// Build the class and its methods, returning it with the data that it refers to
+    pub fn build(mut self) -> Result<(ClassFile, Vec<u8>), ClassBuilderError> {
+        let mut methods: Vec<MethodInfo> = Vec::with_capacity(self.methods.len());
+        for method in self.methods.iter() {
+            if methods.len() >= usize::from(u16::MAX) {
+                return Err(MethodBuilderError::TooManyMethods.into());
+            }
+            let method = method.write(&mut self.constants)?;
+            // The pool builder gives equal names the same index
+            let duplicate = methods.iter().any(|other| {
+                other.name_index == method.name_index
+                    && other.descriptor_index == method.descriptor_index
+            });
+            if duplicate {
+                return Err(MethodBuilderError::DuplicateMethod.into());
+            }
+            methods.push(method);
+        }
+
+        let (const_pool, data) = self.constants.finish();
+        let class_file = ClassFile {
+            version: self.version,
+            const_pool_size: const_pool.len() + 1,
+            const_pool,
+            access_flags: self.access_flags,
+            raw_access_flags: self.access_flags.bits(),
+            this_class: self.this_class,
+            super_class: self.super_class,
+            interfaces_count: self.interfaces.len() as u16,
+            interfaces: self.interfaces,
+            fields_count: self.fields.len() as u16,
+            fields: self.fields,
+            methods_count: methods.len() as u16,
+            methods: SmallVec::from_vec(methods),
+            attributes_count: self.attributes.len() as u16,
+            attributes: self.attributes,
+            descriptor_cache: None,
+            typed_attributes: None,
+            stale_ranges: StaleRanges::new(),
+        };
+        Ok((class_file, data))
+    }
+}
@@ -0,0 +1,106 @@
+//! Heuristics for recognizing classes and methods that were generated by the compiler rather than
+//! written in the source, so that analyses can filter them out.
+//! These are based on the flags, naming patterns, and attributes that javac produces, and so can be
+//! fooled by other compilers or by deliberately misleading names.
+
+use crate::attribute_info::{names, HasAttributes};
+use crate::constant_pool::ConstantPool;
+use crate::field_info::FieldAccessFlags;
+use crate::method_info::{MethodAccessFlags, MethodInfo};
+use crate::{ClassAccessFlags, ClassFile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticClassKind {
+    /// A class spun by the `LambdaMetafactory` to implement a lambda or method reference, such as
+    /// `Foo$$Lambda$12`. These normally only exist at runtime, but show up in class dumps.
+    LambdaProxy,
+    /// A class holding the `$SwitchMap$` arrays used to switch on enums from another class
+    SwitchMap,
+    /// An empty class used by older javac versions to give private constructors a distinct
+    /// signature for their synthetic accessor
+    AccessConstructorTag,
+    /// Marked as synthetic, but not matching any of the more specific patterns
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticMethodKind {
+    /// The body of a lambda, such as `lambda$main$0`
+    LambdaBody,
+    /// A bridge method generated for generic or covariant overriding
+    Bridge,
+    /// An accessor for private members used by nested classes, such as `access$000`
+    Accessor,
+    /// Marked as synthetic, but not matching any of the more specific patterns
+    Other,
+}
+
+fn has_synthetic_attribute(owner: &impl HasAttributes, pool: &ConstantPool, data: &[u8]) -> bool {
+    owner
+        .find_attribute_info(pool, data, names::SYNTHETIC)
+        .is_some()
+}
+
+/// Classify the class as compiler generated, returning None if it appears to be from the source.
+pub fn classify_class(class_file: &ClassFile, data: &[u8]) -> Option<SyntheticClassKind> {
+    let pool = &class_file.const_pool;
+    let name = pool
+        .get_t(class_file.this_class)
+        .and_then(|class| pool.get_t(class.name_index))
+        .map(|name| name.as_text(data));
+
+    if let Some(name) = &name {
+        if name.contains("$$Lambda$") || name.contains("$$Lambda/") {
+            return Some(SyntheticClassKind::LambdaProxy);
+        }
+    }
+
+    let is_synthetic = class_file
+        .access_flags
+        .contains(ClassAccessFlags::SYNTHETIC)
+        || has_synthetic_attribute(class_file, pool, data);
+    if !is_synthetic {
+        return None;
+    }
+
+    let has_switch_map = class_file.fields.iter().any(|field| {
+        field.access_flags.contains(FieldAccessFlags::STATIC)
+            && pool
+                .get_t(field.name_index)
+                .map(|name| name.as_text(data).starts_with("$SwitchMap$"))
+                .unwrap_or(false)
+    });
+    if has_switch_map {
+        return Some(SyntheticClassKind::SwitchMap);
+    }
+
+    if class_file.fields.is_empty() && class_file.methods.is_empty() {
+        return Some(SyntheticClassKind::AccessConstructorTag);
+    }
+
+    Some(SyntheticClassKind::Other)
+}
+
+/// Classify the method as compiler generated, returning None if it appears to be from the source.
+pub fn classify_method(
+    method: &MethodInfo,
+    pool: &ConstantPool,
+    data: &[u8],
+) -> Option<SyntheticMethodKind> {
+    if method.access_flags.contains(MethodAccessFlags::BRIDGE) {
+        return Some(SyntheticMethodKind::Bridge);
+    }
+
+    let is_synthetic = method.access_flags.contains(MethodAccessFlags::SYNTHETIC)
+        || has_synthetic_attribute(method, pool, data);
+    if !is_synthetic {
+        return None;
+    }
+
+    let name = pool.get_t(method.name_index).map(|name| name.as_text(data));
+    match name.as_deref() {
+        Some(name) if name.starts_with("lambda$") => Some(SyntheticMethodKind::LambdaBody),
+        Some(name) if name.starts_with("access$") => Some(SyntheticMethodKind::Accessor),
+        _ => Some(SyntheticMethodKind::Other),
+    }
+}
@@ -0,0 +1,430 @@
+//! Classifying the changes between two versions of a class's [`ClassApi`] by whether existing
+//! code keeps working, following the binary compatibility rules in chapter 13 of the JLS.
+//!
+//! Only the api is compared, so the checks are conservative where the answer depends on classes
+//! that aren't given, such as a changed superclass which may still have the old one as an ancestor.
+
+use std::collections::BTreeMap;
+
+use crate::api::{ClassApi, FieldApi, MethodApi};
+use crate::field_info::FieldAccessFlags;
+use crate::method_info::MethodAccessFlags;
+use crate::ClassAccessFlags;
+
+/// How a change affects existing code, ordered from least to most severe, so the overall
+/// compatibility of several changes is their maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Compatibility {
+    Compatible,
+    /// Existing binaries still link, but some existing sources no longer compile
+    SourceBreaking,
+    /// Existing binaries may fail to link or fail at runtime
+    BinaryBreaking,
+}
+
+/// The part of a class that changed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ApiElement {
+    Class,
+    Field { name: String, descriptor: String },
+    Method { name: String, descriptor: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    Added,
+    /// Removed, or made package private or private for a member
+    Removed,
+    AccessReduced,
+    AccessWidened,
+    MadeFinal,
+    NoLongerFinal,
+    MadeAbstract,
+    NoLongerAbstract,
+    MadeStatic,
+    NoLongerStatic,
+    /// A class became an interface, or an interface became a class
+    KindChanged,
+    SuperclassChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    InterfaceAdded(String),
+    InterfaceRemoved(String),
+    /// The generic signature changed, which doesn't change the erased types
+    SignatureChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    ExceptionAdded(String),
+    ExceptionRemoved(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApiChange {
+    /// The internal name of the class
+    pub class: String,
+    pub element: ApiElement,
+    pub kind: ChangeKind,
+    pub compatibility: Compatibility,
+}
+
+/// The access level of a member, from private to public
+fn access_level(public: bool, protected: bool) -> u8 {
+    match (public, protected) {
+        (true, _) => 2,
+        (false, true) => 1,
+        (false, false) => 0,
+    }
+}
+
+fn signature_compatibility(old: &Option<String>) -> Compatibility {
+    // Adding generics to a raw type is compatible by design, anything else can break sources
+    if old.is_none() {
+        Compatibility::Compatible
+    } else {
+        Compatibility::SourceBreaking
+    }
+}
+
+struct Changes<'a> {
+    class: &'a str,
+    changes: Vec<ApiChange>,
+}
+impl<'a> Changes<'a> {
+    fn push(&mut self, element: &ApiElement, kind: ChangeKind, compatibility: Compatibility) {
+        self.changes.push(ApiChange {
+            class: self.class.to_string(),
+            element: element.clone(),
+            kind,
+            compatibility,
+        });
+    }
+
+    /// Record the flag being set or cleared, when it changed
+    fn flag(
+        &mut self,
+        element: &ApiElement,
+        (old, new): (bool, bool),
+        set: (ChangeKind, Compatibility),
+        cleared: (ChangeKind, Compatibility),
+    ) {
+        match (old, new) {
+            (false, true) => self.push(element, set.0, set.1),
+            (true, false) => self.push(element, cleared.0, cleared.1),
+            _ => {}
+        }
+    }
+
+    fn access(&mut self, element: &ApiElement, old: u8, new: u8) {
+        if new < old {
+            self.push(
+                element,
+                ChangeKind::AccessReduced,
+                Compatibility::BinaryBreaking,
+            );
+        } else if new > old {
+            self.push(
+                element,
+                ChangeKind::AccessWidened,
+                Compatibility::Compatible,
+            );
+        }
+    }
+
+    fn signature(&mut self, element: &ApiElement, old: &Option<String>, new: &Option<String>) {
+        if old != new {
+            let compatibility = signature_compatibility(old);
+            let kind = ChangeKind::SignatureChanged {
+                old: old.clone(),
+                new: new.clone(),
+            };
+            self.push(element, kind, compatibility);
+        }
+    }
+
+    fn class(&mut self, old: &ClassApi, new: &ClassApi) {
+        use self::ChangeKind::*;
+        use self::Compatibility::*;
+
+        let element = &ApiElement::Class;
+        let flag = |flag| {
+            (
+                old.access_flags.contains(flag),
+                new.access_flags.contains(flag),
+            )
+        };
+        let (old_public, new_public) = flag(ClassAccessFlags::PUBLIC);
+        self.access(
+            element,
+            access_level(old_public, false),
+            access_level(new_public, false),
+        );
+        if old.access_flags.contains(ClassAccessFlags::INTERFACE)
+            != new.access_flags.contains(ClassAccessFlags::INTERFACE)
+        {
+            self.push(element, KindChanged, BinaryBreaking);
+        }
+        self.flag(
+            element,
+            flag(ClassAccessFlags::FINAL),
+            (MadeFinal, BinaryBreaking),
+            (NoLongerFinal, Compatible),
+        );
+        // Interfaces are always abstract
+        if !new.access_flags.contains(ClassAccessFlags::INTERFACE) {
+            self.flag(
+                element,
+                flag(ClassAccessFlags::ABSTRACT),
+                (MadeAbstract, BinaryBreaking),
+                (NoLongerAbstract, Compatible),
+            );
+        }
+
+        if old.super_class != new.super_class {
+            let kind = SuperclassChanged {
+                old: old.super_class.clone(),
+                new: new.super_class.clone(),
+            };
+            self.push(element, kind, BinaryBreaking);
+        }
+        for interface in old.interfaces.iter() {
+            if !new.interfaces.contains(interface) {
+                self.push(element, InterfaceRemoved(interface.clone()), BinaryBreaking);
+            }
+        }
+        for interface in new.interfaces.iter() {
+            if !old.interfaces.contains(interface) {
+                self.push(element, InterfaceAdded(interface.clone()), Compatible);
+            }
+        }
+        self.signature(element, &old.signature, &new.signature);
+    }
+
+    fn field(&mut self, old: Option<&FieldApi>, new: Option<&FieldApi>) {
+        use self::ChangeKind::*;
+        use self::Compatibility::*;
+
+        let (old, new) = match (old, new) {
+            (Some(old), Some(new)) => (old, new),
+            (Some(field), None) | (None, Some(field)) => {
+                let element = ApiElement::Field {
+                    name: field.name.clone(),
+                    descriptor: field.descriptor.clone(),
+                };
+                if new.is_some() {
+                    self.push(&element, Added, Compatible);
+                } else {
+                    self.push(&element, Removed, BinaryBreaking);
+                }
+                return;
+            }
+            (None, None) => return,
+        };
+
+        let element = &ApiElement::Field {
+            name: new.name.clone(),
+            descriptor: new.descriptor.clone(),
+        };
+        let flag = |flag| {
+            (
+                old.access_flags.contains(flag),
+                new.access_flags.contains(flag),
+            )
+        };
+        let (old_public, new_public) = flag(FieldAccessFlags::PUBLIC);
+        let (old_protected, new_protected) = flag(FieldAccessFlags::PROTECTED);
+        self.access(
+            element,
+            access_level(old_public, old_protected),
+            access_level(new_public, new_protected),
+        );
+        // Assignments from other classes fail once the field is final
+        self.flag(
+            element,
+            flag(FieldAccessFlags::FINAL),
+            (MadeFinal, BinaryBreaking),
+            (NoLongerFinal, Compatible),
+        );
+        self.flag(
+            element,
+            flag(FieldAccessFlags::STATIC),
+            (MadeStatic, BinaryBreaking),
+            (NoLongerStatic, BinaryBreaking),
+        );
+        self.signature(element, &old.signature, &new.signature);
+    }
+
+    fn method(&mut self, old: Option<&MethodApi>, new: Option<&MethodApi>, new_class: &ClassApi) {
+        use self::ChangeKind::*;
+        use self::Compatibility::*;
+
+        let (old, new) = match (old, new) {
+            (Some(old), Some(new)) => (old, new),
+            (Some(method), None) | (None, Some(method)) => {
+                let element = ApiElement::Method {
+                    name: method.name.clone(),
+                    descriptor: method.descriptor.clone(),
+                };
+                if new.is_none() {
+                    self.push(&element, Removed, BinaryBreaking);
+                } else if method.access_flags.contains(MethodAccessFlags::ABSTRACT) {
+                    // Existing subclasses no longer compile, but only fail at runtime if the new
+                    // method is called on them
+                    self.push(&element, Added, SourceBreaking);
+                } else {
+                    self.push(&element, Added, Compatible);
+                }
+                return;
+            }
+            (None, None) => return,
+        };
+
+        let element = &ApiElement::Method {
+            name: new.name.clone(),
+            descriptor: new.descriptor.clone(),
+        };
+        let flag = |flag| {
+            (
+                old.access_flags.contains(flag),
+                new.access_flags.contains(flag),
+            )
+        };
+        let (old_public, new_public) = flag(MethodAccessFlags::PUBLIC);
+        let (old_protected, new_protected) = flag(MethodAccessFlags::PROTECTED);
+        self.access(
+            element,
+            access_level(old_public, old_protected),
+            access_level(new_public, new_protected),
+        );
+        // Nothing can override a method of a final class anyway
+        let made_final = if new_class.access_flags.contains(ClassAccessFlags::FINAL) {
+            Compatible
+        } else {
+            BinaryBreaking
+        };
+        self.flag(
+            element,
+            flag(MethodAccessFlags::FINAL),
+            (MadeFinal, made_final),
+            (NoLongerFinal, Compatible),
+        );
+        self.flag(
+            element,
+            flag(MethodAccessFlags::ABSTRACT),
+            (MadeAbstract, BinaryBreaking),
+            (NoLongerAbstract, Compatible),
+        );
+        self.flag(
+            element,
+            flag(MethodAccessFlags::STATIC),
+            (MadeStatic, BinaryBreaking),
+            (NoLongerStatic, BinaryBreaking),
+        );
+        self.signature(element, &old.signature, &new.signature);
+
+        // The throws clause isn't checked when linking, but callers may have to catch more, or
+        // may be catching an exception that can no longer be thrown
+        for exception in old.exceptions.iter() {
+            if !new.exceptions.contains(exception) {
+                self.push(element, ExceptionRemoved(exception.clone()), SourceBreaking);
+            }
+        }
+        for exception in new.exceptions.iter() {
+            if !old.exceptions.contains(exception) {
+                self.push(element, ExceptionAdded(exception.clone()), SourceBreaking);
+            }
+        }
+    }
+}
+
+impl ClassApi {
+    /// Classify every change from this version of the class to `new`.
+    /// If this version isn't public then nothing outside its package can use it, so no changes
+    /// are reported.
+    pub fn compare(&self, new: &ClassApi) -> Vec<ApiChange> {
+        let mut changes = Changes {
+            class: &new.name,
+            changes: Vec::new(),
+        };
+        if !self.access_flags.contains(ClassAccessFlags::PUBLIC) {
+            return changes.changes;
+        }
+
+        changes.class(self, new);
+
+        let mut fields = BTreeMap::new();
+        for field in self.fields.iter() {
+            fields
+                .entry((&field.name, &field.descriptor))
+                .or_insert((None, None))
+                .0 = Some(field);
+        }
+        for field in new.fields.iter() {
+            fields
+                .entry((&field.name, &field.descriptor))
+                .or_insert((None, None))
+                .1 = Some(field);
+        }
+        for (old, new) in fields.into_values() {
+            changes.field(old, new);
+        }
+
+        let mut methods = BTreeMap::new();
+        for method in self.methods.iter() {
+            methods
+                .entry((&method.name, &method.descriptor))
+                .or_insert((None, None))
+                .0 = Some(method);
+        }
+        for method in new.methods.iter() {
+            methods
+                .entry((&method.name, &method.descriptor))
+                .or_insert((None, None))
+                .1 = Some(method);
+        }
+        for (old, new_method) in methods.into_values() {
+            changes.method(old, new_method, new);
+        }
+
+        changes.changes
+    }
+}
+
+/// Classify the changes between two versions of a set of classes, such as the classes in two
+/// versions of a jar, matching the classes by name.
+/// The changes are ordered by class name. Only the removal of public classes is breaking.
+pub fn compare_classes<'a>(
+    old: impl IntoIterator<Item = &'a ClassApi>,
+    new: impl IntoIterator<Item = &'a ClassApi>,
+) -> Vec<ApiChange> {
+    let mut classes = BTreeMap::new();
+    for class in old {
+        classes.entry(class.name.as_str()).or_insert((None, None)).0 = Some(class);
+    }
+    for class in new {
+        classes.entry(class.name.as_str()).or_insert((None, None)).1 = Some(class);
+    }
+
+    let mut changes = Vec::new();
+    for (name, versions) in classes {
+        let (kind, compatibility) = match versions {
+            (Some(old), Some(new)) => {
+                changes.extend(old.compare(new));
+                continue;
+            }
+            (Some(old), None) if old.access_flags.contains(ClassAccessFlags::PUBLIC) => {
+                (ChangeKind::Removed, Compatibility::BinaryBreaking)
+            }
+            (Some(_), None) => (ChangeKind::Removed, Compatibility::Compatible),
+            (None, _) => (ChangeKind::Added, Compatibility::Compatible),
+        };
+        changes.push(ApiChange {
+            class: name.to_string(),
+            element: ApiElement::Class,
+            kind,
+            compatibility,
+        });
+    }
+    changes
+}
@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+
+use crate::constant_info::*;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::ClassFileVersion;
+
+/// The instructions which load a constant from the pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LdcKind {
+    /// `ldc`, with a one byte index
+    Ldc,
+    /// `ldc_w`, with a two byte index
+    LdcW,
+    /// `ldc2_w`, which loads a Long or Double, or a Dynamic constant of either
+    Ldc2W,
+}
+impl LdcKind {
+    pub fn from_opcode(opcode: u8) -> Option<LdcKind> {
+        match opcode {
+            0x12 => Some(LdcKind::Ldc),
+            0x13 => Some(LdcKind::LdcW),
+            0x14 => Some(LdcKind::Ldc2W),
+            _ => None,
+        }
+    }
+
+    /// Whether the instruction loads a value that takes up two stack slots
+    pub fn is_wide(self) -> bool {
+        self == LdcKind::Ldc2W
+    }
+}
+
+/// A constant that an `ldc` instruction can push onto the stack
+#[derive(Debug, Clone)]
+pub enum LoadableConstant<'a> {
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(Cow<'a, StringConstant>),
+    /// Loads the `java.lang.Class` for the class
+    Class(Cow<'a, ClassConstant>),
+    MethodType(Cow<'a, MethodTypeConstant>),
+    MethodHandle(Cow<'a, MethodHandleConstant>),
+    Dynamic(Cow<'a, DynamicConstant>),
+}
+impl<'a> LoadableConstant<'a> {
+    /// Copy the entry out of the constant pool, so that the constant can be kept without keeping
+    /// the pool borrowed
+    pub fn to_owned<'b>(self) -> LoadableConstant<'b> {
+        match self {
+            LoadableConstant::Integer(v) => LoadableConstant::Integer(v),
+            LoadableConstant::Float(v) => LoadableConstant::Float(v),
+            LoadableConstant::Long(v) => LoadableConstant::Long(v),
+            LoadableConstant::Double(v) => LoadableConstant::Double(v),
+            LoadableConstant::String(c) => LoadableConstant::String(Cow::Owned(c.into_owned())),
+            LoadableConstant::Class(c) => LoadableConstant::Class(Cow::Owned(c.into_owned())),
+            LoadableConstant::MethodType(c) => {
+                LoadableConstant::MethodType(Cow::Owned(c.into_owned()))
+            }
+            LoadableConstant::MethodHandle(c) => {
+                LoadableConstant::MethodHandle(Cow::Owned(c.into_owned()))
+            }
+            LoadableConstant::Dynamic(c) => LoadableConstant::Dynamic(Cow::Owned(c.into_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LdcError {
+    /// The index does not refer to an entry of the pool
+    InvalidIndex,
+    /// The index is given for an `ldc`, but doesn't fit in its one byte operand
+    IndexTooLarge,
+    /// The constant is not one which can be loaded, such as a utf8 or field reference
+    NotLoadable,
+    /// A Long or Double was loaded with `ldc`/`ldc_w`, or anything else with `ldc2_w`
+    WrongWidth,
+    /// The constant can only be loaded in class files with at least this major version
+    UnsupportedVersion { min_major: u16 },
+}
+
+/// Resolve the operand of an `ldc`, `ldc_w`, or `ldc2_w` instruction to the constant it loads,
+/// checking that the instruction can load it in a class file of the version.
+/// An `ldc` can only refer to the first 255 entries of the pool.
+pub fn resolve_ldc<'a>(
+    pool: &'a ConstantPool,
+    kind: LdcKind,
+    index: u16,
+    version: ClassFileVersion,
+    class_file_data: &[u8],
+) -> Result<LoadableConstant<'a>, LdcError> {
+    if kind == LdcKind::Ldc && index > u16::from(u8::MAX) {
+        return Err(LdcError::IndexTooLarge);
+    }
+    let entry = pool
+        .get(ConstantPoolIndexRaw::<ConstantInfo>::new(index))
+        .ok_or(LdcError::InvalidIndex)?;
+    let (constant, min_major, wide) = match entry {
+        ConstantInfo::Integer(c) => (LoadableConstant::Integer(c.value), 45, false),
+        ConstantInfo::Float(c) => (LoadableConstant::Float(c.value), 45, false),
+        ConstantInfo::Long(c) => (LoadableConstant::Long(c.value), 45, true),
+        ConstantInfo::Double(c) => (LoadableConstant::Double(c.value), 45, true),
+        ConstantInfo::String(c) => (LoadableConstant::String(Cow::Borrowed(c)), 45, false),
+        ConstantInfo::Class(c) => (LoadableConstant::Class(Cow::Borrowed(c)), 49, false),
+        ConstantInfo::MethodType(c) => (LoadableConstant::MethodType(Cow::Borrowed(c)), 51, false),
+        ConstantInfo::MethodHandle(c) => {
+            (LoadableConstant::MethodHandle(Cow::Borrowed(c)), 51, false)
+        }
+        ConstantInfo::Dynamic(c) => {
+            let wide = dynamic_is_wide(pool, c, class_file_data).ok_or(LdcError::InvalidIndex)?;
+            (LoadableConstant::Dynamic(Cow::Borrowed(c)), 55, wide)
+        }
+        _ => return Err(LdcError::NotLoadable),
+    };
+
+    if wide != kind.is_wide() {
+        return Err(LdcError::WrongWidth);
+    }
+    if version.major < min_major {
+        return Err(LdcError::UnsupportedVersion { min_major });
+    }
+
+    Ok(constant)
+}
+
+/// Whether the dynamic constant is a long or double, from its field descriptor
+fn dynamic_is_wide(
+    pool: &ConstantPool,
+    constant: &DynamicConstant,
+    class_file_data: &[u8],
+) -> Option<bool> {
+    let nat = pool.get_t(constant.name_and_type_index)?;
+    let descriptor = pool.get_t(nat.descriptor_index)?;
+    Some(matches!(descriptor.as_bytes(class_file_data), b"J" | b"D"))
+}
@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+
+use crate::constant_info::*;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::descriptor::{DescriptorError, ParsedDescriptor};
+use crate::resolved::{Borrowed, TextMode};
+
+/// Which kind of reference constant a member reference is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberRefKind {
+    Field,
+    Method,
+    InterfaceMethod,
+}
+impl MemberRefKind {
+    pub fn is_field(self) -> bool {
+        self == MemberRefKind::Field
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemberRefError {
+    /// The entry, or an index of it or its NameAndType, does not refer to an entry of the right
+    /// type
+    InvalidIndex(ConstantPoolIndexRaw<ConstantInfo>),
+    /// The descriptor of the entry could not be parsed
+    Descriptor(ConstantPoolIndexRaw<ConstantInfo>, DescriptorError),
+    /// A field reference has a method descriptor, or a method reference has a field descriptor
+    WrongDescriptor(ConstantPoolIndexRaw<ConstantInfo>),
+}
+
+/// A field or method that the class refers to, see [`resolve_member_ref`].
+/// The text is borrowed from the class file data unless it was resolved with
+/// [`crate::resolved::Owned`] text, see [`resolve_member_ref_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberRef<T = String> {
+    /// The index of the FieldRef, MethodRef, or InterfaceMethodRef entry
+    pub index: ConstantPoolIndexRaw<ConstantInfo>,
+    pub kind: MemberRefKind,
+    /// The internal name of the class that the member is in, which is an array descriptor for
+    /// methods called on arrays, such as `clone`
+    pub class_name: T,
+    pub name: T,
+    pub descriptor: T,
+    /// The descriptor, which is a field descriptor for field references and a method descriptor
+    /// otherwise
+    pub parsed_descriptor: ParsedDescriptor,
+}
+impl<'a> MemberRef<Cow<'a, str>> {
+    /// Copy the names out of the class file data, so that the reference can be kept without
+    /// keeping the data alive
+    pub fn to_owned(self) -> MemberRef<String> {
+        MemberRef {
+            index: self.index,
+            kind: self.kind,
+            class_name: self.class_name.into_owned(),
+            name: self.name.into_owned(),
+            descriptor: self.descriptor.into_owned(),
+            parsed_descriptor: self.parsed_descriptor,
+        }
+    }
+}
+
+/// Resolve the FieldRef, MethodRef, or InterfaceMethodRef entry at the index into the class, name,
+/// and descriptor of the member it refers to, borrowing them from the data
+pub fn resolve_member_ref<'a>(
+    pool: &ConstantPool,
+    index: ConstantPoolIndexRaw<ConstantInfo>,
+    class_file_data: &'a [u8],
+) -> Result<MemberRef<Cow<'a, str>>, MemberRefError> {
+    resolve_member_ref_with(pool, index, class_file_data, Borrowed)
+}
+
+/// Resolve the entry at the index, holding its text as the mode decides, see
+/// [`resolve_member_ref`]
+pub fn resolve_member_ref_with<'a, M: TextMode<'a>>(
+    pool: &ConstantPool,
+    index: ConstantPoolIndexRaw<ConstantInfo>,
+    class_file_data: &'a [u8],
+    mode: M,
+) -> Result<MemberRef<M::Text>, MemberRefError> {
+    let invalid = || MemberRefError::InvalidIndex(index);
+    let (kind, class_index, nat_index) = match pool.get(index).ok_or_else(invalid)? {
+        ConstantInfo::FieldRef(r) => (MemberRefKind::Field, r.class_index, r.name_and_type_index),
+        ConstantInfo::MethodRef(r) => (MemberRefKind::Method, r.class_index, r.name_and_type_index),
+        ConstantInfo::InterfaceMethodRef(r) => (
+            MemberRefKind::InterfaceMethod,
+            r.class_index,
+            r.name_and_type_index,
+        ),
+        _ => return Err(invalid()),
+    };
+
+    let class_name = pool
+        .get_class_name(class_index, class_file_data)
+        .ok_or_else(invalid)?;
+    let (name, descriptor) = pool.get_name_and_type(nat_index).ok_or_else(invalid)?;
+
+    let parsed_descriptor = ParsedDescriptor::parse(descriptor.as_bytes(class_file_data))
+        .map_err(|err| MemberRefError::Descriptor(index, err))?;
+    if kind.is_field() != matches!(parsed_descriptor, ParsedDescriptor::Field(_)) {
+        return Err(MemberRefError::WrongDescriptor(index));
+    }
+
+    Ok(MemberRef {
+        index,
+        kind,
+        class_name: mode.text(class_name),
+        name: mode.text(name.as_text(class_file_data)),
+        descriptor: mode.text(descriptor.as_text(class_file_data)),
+        parsed_descriptor,
+    })
+}
+
+/// Resolve every FieldRef, MethodRef, and InterfaceMethodRef entry in the pool, in the order of
+/// the pool.
+/// Errors on the first entry that can't be resolved.
+pub fn referenced_members<'a>(
+    pool: &ConstantPool,
+    class_file_data: &'a [u8],
+) -> Result<Vec<MemberRef<Cow<'a, str>>>, MemberRefError> {
+    referenced_members_with(pool, class_file_data, Borrowed)
+}
+
+/// Resolve every member reference in the pool, holding their text as the mode decides, see
+/// [`referenced_members`]
+pub fn referenced_members_with<'a, M: TextMode<'a>>(
+    pool: &ConstantPool,
+    class_file_data: &'a [u8],
+    mode: M,
+) -> Result<Vec<MemberRef<M::Text>>, MemberRefError> {
+    pool.iter_indexed()
+        .filter(|(_, entry)| {
+            matches!(
+                entry,
+                ConstantInfo::FieldRef(_)
+                    | ConstantInfo::MethodRef(_)
+                    | ConstantInfo::InterfaceMethodRef(_)
+            )
+        })
+        .map(|(index, _)| resolve_member_ref_with(pool, index, class_file_data, mode))
+        .collect()
+}
@@ -0,0 +1,264 @@
+use std::borrow::Cow;
+
+use crate::constant_info::*;
+use crate::constant_pool::ConstantPool;
+use crate::descriptor::method::MethodDescriptor;
+use crate::descriptor::DescriptorType;
+use crate::names;
+use crate::resolved::{Borrowed, TextMode};
+use crate::ClassFileVersion;
+
+/// The kind of a method handle, which decides how it behaves when invoked.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-5.html#jvms-5.4.3.5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    GetField = 1,
+    GetStatic = 2,
+    PutField = 3,
+    PutStatic = 4,
+    InvokeVirtual = 5,
+    InvokeStatic = 6,
+    InvokeSpecial = 7,
+    /// Creates a new instance and calls the `<init>` method on it
+    NewInvokeSpecial = 8,
+    InvokeInterface = 9,
+}
+impl ReferenceKind {
+    pub fn from_u8(kind: u8) -> Option<ReferenceKind> {
+        Some(match kind {
+            1 => ReferenceKind::GetField,
+            2 => ReferenceKind::GetStatic,
+            3 => ReferenceKind::PutField,
+            4 => ReferenceKind::PutStatic,
+            5 => ReferenceKind::InvokeVirtual,
+            6 => ReferenceKind::InvokeStatic,
+            7 => ReferenceKind::InvokeSpecial,
+            8 => ReferenceKind::NewInvokeSpecial,
+            9 => ReferenceKind::InvokeInterface,
+            _ => return None,
+        })
+    }
+
+    /// The name that javap gives the kind, such as `REF_invokeStatic`
+    pub fn name(self) -> &'static str {
+        match self {
+            ReferenceKind::GetField => "REF_getField",
+            ReferenceKind::GetStatic => "REF_getStatic",
+            ReferenceKind::PutField => "REF_putField",
+            ReferenceKind::PutStatic => "REF_putStatic",
+            ReferenceKind::InvokeVirtual => "REF_invokeVirtual",
+            ReferenceKind::InvokeStatic => "REF_invokeStatic",
+            ReferenceKind::InvokeSpecial => "REF_invokeSpecial",
+            ReferenceKind::NewInvokeSpecial => "REF_newInvokeSpecial",
+            ReferenceKind::InvokeInterface => "REF_invokeInterface",
+        }
+    }
+
+    /// Whether the handle reads or writes a field, rather than invoking a method
+    pub fn is_field(self) -> bool {
+        matches!(
+            self,
+            ReferenceKind::GetField
+                | ReferenceKind::GetStatic
+                | ReferenceKind::PutField
+                | ReferenceKind::PutStatic
+        )
+    }
+
+    /// Whether the handle writes a field
+    pub fn is_put(self) -> bool {
+        matches!(self, ReferenceKind::PutField | ReferenceKind::PutStatic)
+    }
+
+    /// Whether the handle takes no receiver
+    pub fn is_static(self) -> bool {
+        matches!(
+            self,
+            ReferenceKind::GetStatic
+                | ReferenceKind::PutStatic
+                | ReferenceKind::InvokeStatic
+                | ReferenceKind::NewInvokeSpecial
+        )
+    }
+
+    /// The opcode of the instruction that the handle behaves like.
+    /// A `NewInvokeSpecial` handle behaves like `new` and `dup` followed by `invokespecial`.
+    pub fn opcode(self) -> u8 {
+        match self {
+            ReferenceKind::GetField => 0xb4,
+            ReferenceKind::GetStatic => 0xb2,
+            ReferenceKind::PutField => 0xb5,
+            ReferenceKind::PutStatic => 0xb3,
+            ReferenceKind::InvokeVirtual => 0xb6,
+            ReferenceKind::InvokeStatic => 0xb8,
+            ReferenceKind::InvokeSpecial | ReferenceKind::NewInvokeSpecial => 0xb7,
+            ReferenceKind::InvokeInterface => 0xb9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodHandleError {
+    /// The reference kind is not between 1 and 9
+    InvalidKind(u8),
+    /// An index of the handle or its reference does not refer to an entry of the right type
+    InvalidIndex,
+    /// The reference is not a type of constant that the kind allows.
+    /// Static and special handles can only refer to interface methods from Java 8.
+    WrongReferenceType,
+    /// `NewInvokeSpecial` handles must refer to a `<init>` method, and other method handles must
+    /// not refer to `<init>` or `<clinit>`
+    InvalidMethodName,
+    /// The descriptor of the reference does not fit the kind, such as a method descriptor for a
+    /// field handle or a constructor which doesn't return void
+    InvalidDescriptor,
+}
+
+/// A method handle with its reference resolved, see [`resolve_method_handle`].
+/// The text is borrowed from the class file data unless it was resolved with
+/// [`crate::resolved::Owned`] text, see [`resolve_method_handle_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedMethodHandle<T = String> {
+    pub kind: ReferenceKind,
+    /// The internal name of the class that the member is in
+    pub class_name: T,
+    pub name: T,
+    pub descriptor: T,
+    /// Whether the reference is to an interface method
+    pub is_interface: bool,
+}
+impl<T: AsRef<str>> ResolvedMethodHandle<T> {
+    /// The method descriptor of the handle's type, which is how it is invoked.
+    /// Instance handles take the receiver as their first parameter, field getters return the field
+    /// type, field setters take the value, and `NewInvokeSpecial` handles return the new instance.
+    /// Errors with [`MethodHandleError::InvalidDescriptor`] if a method handle's descriptor doesn't
+    /// start with its parameters, which can't happen for one from [`resolve_method_handle`].
+    pub fn handle_type(&self) -> Result<String, MethodHandleError> {
+        let class_name = self.class_name.as_ref();
+        let descriptor = self.descriptor.as_ref();
+        if !self.kind.is_field() && !descriptor.starts_with('(') {
+            return Err(MethodHandleError::InvalidDescriptor);
+        }
+        let owner = if class_name.starts_with('[') {
+            class_name.to_string()
+        } else {
+            format!("L{};", class_name)
+        };
+        let receiver = if self.kind.is_static() { "" } else { &owner };
+
+        Ok(match self.kind {
+            ReferenceKind::GetField | ReferenceKind::GetStatic => {
+                format!("({}){}", receiver, descriptor)
+            }
+            ReferenceKind::PutField | ReferenceKind::PutStatic => {
+                format!("({}{})V", receiver, descriptor)
+            }
+            ReferenceKind::NewInvokeSpecial => {
+                let end = descriptor
+                    .rfind(')')
+                    .ok_or(MethodHandleError::InvalidDescriptor)?;
+                format!("{}){}", &descriptor[..end], owner)
+            }
+            _ => format!("({}{}", receiver, &descriptor[1..]),
+        })
+    }
+}
+impl<'a> ResolvedMethodHandle<Cow<'a, str>> {
+    /// Copy the names out of the class file data, so that the handle can be kept without keeping
+    /// the data alive
+    pub fn to_owned(self) -> ResolvedMethodHandle<String> {
+        ResolvedMethodHandle {
+            kind: self.kind,
+            class_name: self.class_name.into_owned(),
+            name: self.name.into_owned(),
+            descriptor: self.descriptor.into_owned(),
+            is_interface: self.is_interface,
+        }
+    }
+}
+
+/// Resolve the member that the method handle refers to, checking that the kind and reference
+/// agree as the JVM requires. The names are borrowed from the data.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.4.8)
+pub fn resolve_method_handle<'a>(
+    pool: &ConstantPool,
+    handle: &MethodHandleConstant,
+    version: ClassFileVersion,
+    class_file_data: &'a [u8],
+) -> Result<ResolvedMethodHandle<Cow<'a, str>>, MethodHandleError> {
+    resolve_method_handle_with(pool, handle, version, class_file_data, Borrowed)
+}
+
+/// Resolve the member that the method handle refers to, holding its names as the mode decides,
+/// see [`resolve_method_handle`]
+pub fn resolve_method_handle_with<'a, M: TextMode<'a>>(
+    pool: &ConstantPool,
+    handle: &MethodHandleConstant,
+    version: ClassFileVersion,
+    class_file_data: &'a [u8],
+    mode: M,
+) -> Result<ResolvedMethodHandle<M::Text>, MethodHandleError> {
+    let kind = ReferenceKind::from_u8(handle.reference_kind)
+        .ok_or(MethodHandleError::InvalidKind(handle.reference_kind))?;
+    let reference = pool
+        .get(handle.reference_index)
+        .ok_or(MethodHandleError::InvalidIndex)?;
+    let (class_index, nat_index, is_field, is_interface) = match reference {
+        ConstantInfo::FieldRef(r) => (r.class_index, r.name_and_type_index, true, false),
+        ConstantInfo::MethodRef(r) => (r.class_index, r.name_and_type_index, false, false),
+        ConstantInfo::InterfaceMethodRef(r) => (r.class_index, r.name_and_type_index, false, true),
+        _ => return Err(MethodHandleError::WrongReferenceType),
+    };
+
+    let allowed = match kind {
+        _ if kind.is_field() => is_field,
+        ReferenceKind::InvokeVirtual | ReferenceKind::NewInvokeSpecial => {
+            !is_field && !is_interface
+        }
+        ReferenceKind::InvokeStatic | ReferenceKind::InvokeSpecial => {
+            !is_field && (!is_interface || version.major >= 52)
+        }
+        _ => is_interface,
+    };
+    if !allowed {
+        return Err(MethodHandleError::WrongReferenceType);
+    }
+
+    let class_name = pool
+        .get_class_name(class_index, class_file_data)
+        .ok_or(MethodHandleError::InvalidIndex)?;
+    let (name, descriptor) = pool
+        .get_name_and_type(nat_index)
+        .ok_or(MethodHandleError::InvalidIndex)?;
+    let name = name.as_text(class_file_data);
+    let descriptor = descriptor.as_text(class_file_data);
+
+    if !kind.is_field() {
+        let is_init = names::is_constructor(&name);
+        if (kind == ReferenceKind::NewInvokeSpecial) != is_init
+            || names::is_static_initializer(&name)
+        {
+            return Err(MethodHandleError::InvalidMethodName);
+        }
+    }
+
+    let descriptor_fits = if kind.is_field() {
+        matches!(DescriptorType::parse(descriptor.as_bytes()), Ok((_, rest)) if rest.is_empty())
+    } else {
+        match MethodDescriptor::parse(descriptor.as_bytes()) {
+            Ok(method) => kind != ReferenceKind::NewInvokeSpecial || method.return_type.is_none(),
+            Err(_) => false,
+        }
+    };
+    if !descriptor_fits {
+        return Err(MethodHandleError::InvalidDescriptor);
+    }
+
+    Ok(ResolvedMethodHandle {
+        kind,
+        class_name: mode.text(class_name),
+        name: mode.text(name),
+        descriptor: mode.text(descriptor),
+        is_interface,
+    })
+}
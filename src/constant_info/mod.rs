@@ -1,5 +1,18 @@
+mod loadable;
+mod member_ref;
+mod method_handle;
 mod parser;
 mod types;
 
-pub use self::parser::constant_parser;
+pub use self::loadable::{resolve_ldc, LdcError, LdcKind, LoadableConstant};
+pub use self::member_ref::{
+    referenced_members, referenced_members_with, resolve_member_ref, resolve_member_ref_with,
+    MemberRef, MemberRefError, MemberRefKind,
+};
+pub use self::method_handle::{
+    resolve_method_handle, resolve_method_handle_with, MethodHandleError, ReferenceKind,
+    ResolvedMethodHandle,
+};
+pub(crate) use self::parser::{constant_offsets_parser, is_constant_tag, single_constant_parser};
+pub use self::parser::{constant_parser, skip_constant_pool_parser};
 pub use self::types::*;
@@ -1,3 +1,4 @@
+use nom::bytes::complete::take;
 use nom::error::ErrorKind;
 use nom::number::complete::{be_f32, be_f64, be_i32, be_i64, be_u16, be_u8};
 use nom::{Err, IResult};
@@ -141,6 +142,35 @@ named!(const_invoke_dynamic<ParseData, ConstantInfo>, do_parse!(
     ))
 ));
 
+named!(const_dynamic<ParseData, ConstantInfo>, do_parse!(
+    bootstrap_method_attr_index: be_u16 >>
+    name_and_type_index: constant_pool_index_raw >>
+    (ConstantInfo::Dynamic(
+        DynamicConstant {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        }
+    ))
+));
+
+named!(const_module<ParseData, ConstantInfo>, do_parse!(
+    name_index: constant_pool_index_raw >>
+    (ConstantInfo::Module(
+        ModuleConstant {
+            name_index,
+        }
+    ))
+));
+
+named!(const_package<ParseData, ConstantInfo>, do_parse!(
+    name_index: constant_pool_index_raw >>
+    (ConstantInfo::Package(
+        PackageConstant {
+            name_index,
+        }
+    ))
+));
+
 fn const_block_parser(input: ParseData, const_type: u8) -> IResult<ParseData, ConstantInfo> {
     match const_type {
         1 => const_utf8(input),
@@ -156,12 +186,20 @@ fn const_block_parser(input: ParseData, const_type: u8) -> IResult<ParseData, Co
         12 => const_name_and_type(input),
         15 => const_method_handle(input),
         16 => const_method_type(input),
+        17 => const_dynamic(input),
         18 => const_invoke_dynamic(input),
+        19 => const_module(input),
+        20 => const_package(input),
         _ => Result::Err(Err::Error(error_position!(input, ErrorKind::Alt))),
     }
 }
 
-fn single_constant_parser(i: ParseData) -> IResult<ParseData, ConstantInfo> {
+/// Whether the tag is one of the defined kinds of constant
+pub(crate) fn is_constant_tag(tag: u8) -> bool {
+    matches!(tag, 1 | 3..=12 | 15..=20)
+}
+
+pub(crate) fn single_constant_parser(i: ParseData) -> IResult<ParseData, ConstantInfo> {
     let (i, const_type) = be_u8(i)?;
     let (i, const_block) = const_block_parser(i, const_type)?;
     Ok((i, const_block))
@@ -190,6 +228,9 @@ pub fn constant_parser(
                 input = i;
                 index += 1;
             }
+            Result::Err(Err::Error(e)) if e.code == ErrorKind::Eof => {
+                return Result::Err(Err::Error(e));
+            }
             _ => {
                 return Result::Err(Err::Error(nom::error::Error::new(input, ErrorKind::Alt)));
             }
@@ -197,3 +238,54 @@ pub fn constant_parser(
     }
     Ok((input, res))
 }
+
+/// Skip over one constant pool entry without parsing it, returning the number of slots it takes
+fn skip_constant_parser(input: ParseData) -> IResult<ParseData, usize> {
+    let (i, const_type) = be_u8(input.clone())?;
+    Ok(match const_type {
+        1 => {
+            let (i, length) = be_u16(i)?;
+            (take(length)(i)?.0, 1)
+        }
+        7 | 8 | 16 | 19 | 20 => (take(2usize)(i)?.0, 1),
+        15 => (take(3usize)(i)?.0, 1),
+        3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => (take(4usize)(i)?.0, 1),
+        5 | 6 => (take(8usize)(i)?.0, 2),
+        _ => return Result::Err(Err::Error(nom::error::Error::new(input, ErrorKind::Alt))),
+    })
+}
+
+/// Skip over the constant pool entries, without parsing them.
+/// The size is the number of slots, so Long and Double entries count as two, like the
+/// `constant_pool_count` - 1 of the class file.
+pub fn skip_constant_pool_parser(i: ParseData, const_pool_size: usize) -> IResult<ParseData, ()> {
+    let mut index = 0;
+    let mut input = i;
+    while index < const_pool_size {
+        let (i, slots) = skip_constant_parser(input)?;
+        input = i;
+        index += slots;
+    }
+    Ok((input, ()))
+}
+
+/// Skip over the constant pool entries like [`skip_constant_pool_parser`], recording the offset
+/// that each slot starts at, which is None for the unusable slot after a Long or Double entry
+pub(crate) fn constant_offsets_parser(
+    i: ParseData,
+    const_pool_size: usize,
+) -> IResult<ParseData, Vec<Option<u32>>> {
+    let mut offsets = Vec::with_capacity(const_pool_size);
+    let mut input = i;
+    while offsets.len() < const_pool_size {
+        let offset = u32::try_from(input.pos())
+            .map_err(|_| Err::Failure(error_position!(input.clone(), ErrorKind::TooLarge)))?;
+        let (i, slots) = skip_constant_parser(input)?;
+        offsets.push(Some(offset));
+        if slots == 2 {
+            offsets.push(None);
+        }
+        input = i;
+    }
+    Ok((input, offsets))
+}
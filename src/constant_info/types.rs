@@ -1,6 +1,10 @@
 use std::{borrow::Cow, ops::Range};
 
-use crate::{constant_pool::ConstantPoolIndexRaw, impl_from_try_reverse, parser::ParseData};
+use crate::{
+    constant_pool::{ConstantPool, ConstantPoolIndexRaw, PoolRef},
+    impl_from_try_reverse,
+    parser::ParseData,
+};
 
 #[derive(Clone, Debug)]
 pub enum ConstantInfo {
@@ -18,6 +22,9 @@ pub enum ConstantInfo {
     MethodHandle(MethodHandleConstant),
     MethodType(MethodTypeConstant),
     InvokeDynamic(InvokeDynamicConstant),
+    Dynamic(DynamicConstant),
+    Module(ModuleConstant),
+    Package(PackageConstant),
     /// The unusuable variant appears right after the Double/Long types
     /// This is technically not in the actual file, but it represents the latter
     /// 4 bytes of the variant. It still has its own index, and so it is represented
@@ -43,9 +50,12 @@ impl_from_try_reverse!(enum NameAndTypeConstant => ConstantInfo::NameAndType; In
 impl_from_try_reverse!(enum MethodHandleConstant => ConstantInfo::MethodHandle; IncorrectConstant);
 impl_from_try_reverse!(enum MethodTypeConstant => ConstantInfo::MethodType; IncorrectConstant);
 impl_from_try_reverse!(enum InvokeDynamicConstant => ConstantInfo::InvokeDynamic; IncorrectConstant);
+impl_from_try_reverse!(enum DynamicConstant => ConstantInfo::Dynamic; IncorrectConstant);
+impl_from_try_reverse!(enum ModuleConstant => ConstantInfo::Module; IncorrectConstant);
+impl_from_try_reverse!(enum PackageConstant => ConstantInfo::Package; IncorrectConstant);
 // TODO: From Unusuable?
 
-pub fn to_text(bytes: &[u8]) -> Cow<str> {
+pub fn to_text(bytes: &[u8]) -> Cow<'_, str> {
     cesu8::from_java_cesu8(bytes).unwrap_or_else(|_| String::from_utf8_lossy(bytes))
 }
 
@@ -67,6 +77,11 @@ impl Utf8Constant {
         self.data.is_empty()
     }
 
+    /// The range of the string's bytes in the class file data, after its length
+    pub fn range(&self) -> Range<usize> {
+        self.data.clone()
+    }
+
     pub fn as_bytes<'a>(&self, class_file_data: &'a [u8]) -> &'a [u8] {
         let i = ParseData::from_range(class_file_data, self.data.clone());
         i.data()
@@ -160,3 +175,67 @@ pub struct InvokeDynamicConstant {
     pub bootstrap_method_attr_index: u16,
     pub name_and_type_index: ConstantPoolIndexRaw<NameAndTypeConstant>,
 }
+
+/// A dynamically-computed constant, whose value is produced by a bootstrap method.
+/// The descriptor of the name and type is a field descriptor.
+#[derive(Clone, Debug)]
+pub struct DynamicConstant {
+    pub bootstrap_method_attr_index: u16,
+    pub name_and_type_index: ConstantPoolIndexRaw<NameAndTypeConstant>,
+}
+
+/// A module, which is only in the pool of `module-info` classes
+#[derive(Clone, Debug)]
+pub struct ModuleConstant {
+    /// The name of the module, such as `java.base`
+    pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
+}
+
+/// A package exported or opened by a module, which is only in the pool of `module-info` classes
+#[derive(Clone, Debug)]
+pub struct PackageConstant {
+    /// The name of the package in internal form, such as `java/lang`
+    pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
+}
+
+/// The value of a loadable constant which holds a value directly, such as the value of a
+/// ConstantValue attribute
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstantValue {
+    /// Also used for boolean, byte, char, and short values
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(String),
+}
+impl ConstantValue {
+    /// Resolve the constant at the index into its value.
+    /// Returns None if the index is invalid or refers to a constant that does not hold a value.
+    pub fn resolve(
+        pool: &ConstantPool,
+        index: ConstantPoolIndexRaw<ConstantInfo>,
+        class_file_data: &[u8],
+    ) -> Option<ConstantValue> {
+        ConstantValue::resolve_in(PoolRef::Loaded(pool), index, class_file_data)
+    }
+
+    /// Like [`ConstantValue::resolve`], but also works with a lazy constant pool
+    pub(crate) fn resolve_in(
+        pool: PoolRef,
+        index: ConstantPoolIndexRaw<ConstantInfo>,
+        class_file_data: &[u8],
+    ) -> Option<ConstantValue> {
+        Some(match pool.get(index, class_file_data)?.as_ref() {
+            ConstantInfo::Integer(c) => ConstantValue::Integer(c.value),
+            ConstantInfo::Float(c) => ConstantValue::Float(c.value),
+            ConstantInfo::Long(c) => ConstantValue::Long(c.value),
+            ConstantInfo::Double(c) => ConstantValue::Double(c.value),
+            ConstantInfo::String(c) => {
+                let text = pool.get_utf8_text(c.string_index, class_file_data)?;
+                ConstantValue::String(text.into_owned())
+            }
+            _ => return None,
+        })
+    }
+}
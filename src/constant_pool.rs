@@ -1,15 +1,21 @@
 use std::{
+    borrow::Cow,
     convert::{TryFrom, TryInto},
     hash::Hash,
     marker::PhantomData,
-    rc::Rc,
 };
 
-use crate::constant_info::ConstantInfo;
+use crate::constant_info::{
+    single_constant_parser, ClassConstant, ConstantInfo, NameAndTypeConstant, ReferenceKind,
+    Utf8Constant,
+};
+use crate::parser::ParseData;
+use crate::util::Shared;
+use crate::LoadError;
 
 /// An index into the constant pool that hasn't been offset by -1
 #[derive(Debug)]
-pub struct ConstantPoolIndexRaw<T>(pub u16, PhantomData<*const T>);
+pub struct ConstantPoolIndexRaw<T>(pub u16, PhantomData<fn() -> T>);
 impl<T> ConstantPoolIndexRaw<T> {
     pub fn new(i: u16) -> Self {
         Self(i, PhantomData)
@@ -18,6 +24,24 @@ impl<T> ConstantPoolIndexRaw<T> {
     pub fn is_zero(&self) -> bool {
         self.0 == 0
     }
+
+    /// Get the index of the entry after this one, stepping over the unusable slot that follows a
+    /// Long or Double entry.
+    /// The zero index steps to the first entry.
+    /// Returns None if there is no entry after this one.
+    pub fn next_index(self, pool: &ConstantPool) -> Option<ConstantPoolIndexRaw<ConstantInfo>> {
+        let step = if self.is_zero() {
+            1
+        } else {
+            pool.slot_width(self.0 - 1)?
+        };
+        let next = self.0.checked_add(step)?;
+        if next > pool.len() {
+            return None;
+        }
+
+        Some(ConstantPoolIndexRaw::new(next))
+    }
 }
 impl<T> Eq for ConstantPoolIndexRaw<T> {}
 impl<T> PartialEq for ConstantPoolIndexRaw<T> {
@@ -32,7 +56,7 @@ impl<T> Hash for ConstantPoolIndexRaw<T> {
 }
 impl<T> Clone for ConstantPoolIndexRaw<T> {
     fn clone(&self) -> Self {
-        Self::new(self.0)
+        *self
     }
 }
 impl<T> Copy for ConstantPoolIndexRaw<T> {}
@@ -44,15 +68,27 @@ impl<T: TryFrom<ConstantInfo>> ConstantPoolIndexRaw<T> {
 
 /// A constant pool index that has already been offset by -1
 #[derive(Debug)]
-pub struct ConstantPoolIndex<T>(pub u16, PhantomData<*const T>);
+pub struct ConstantPoolIndex<T>(pub u16, PhantomData<fn() -> T>);
 impl<T> ConstantPoolIndex<T> {
     pub fn new(i: u16) -> Self {
         Self(i, PhantomData)
     }
+
+    /// Get the index of the entry after this one, stepping over the unusable slot that follows a
+    /// Long or Double entry.
+    /// Returns None if there is no entry after this one.
+    pub fn next_index(self, pool: &ConstantPool) -> Option<ConstantPoolIndex<ConstantInfo>> {
+        let next = self.0.checked_add(pool.slot_width(self.0)?)?;
+        if next >= pool.len() {
+            return None;
+        }
+
+        Some(ConstantPoolIndex::new(next))
+    }
 }
 impl<T> Clone for ConstantPoolIndex<T> {
     fn clone(&self) -> Self {
-        Self::new(self.0)
+        *self
     }
 }
 impl<T> Copy for ConstantPoolIndex<T> {}
@@ -102,12 +138,44 @@ impl TryFrom<u16> for ConstantPoolIndex<ConstantInfo> {
     }
 }
 
+/// Why looking up an entry in the constant pool failed, see [`ConstantPool::try_get`].
+/// The indices are raw, as they appear in the class file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstantPoolLookupError {
+    /// Index zero never refers to an entry
+    ZeroIndex,
+    OutOfBounds(u16),
+    /// The index is the unusable second slot of the Long or Double entry just before it
+    UnusableSlot(u16),
+    /// The entry isn't of the expected type
+    WrongType(u16),
+}
+impl std::fmt::Display for ConstantPoolLookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConstantPoolLookupError::ZeroIndex => write!(f, "constant pool index is zero"),
+            ConstantPoolLookupError::OutOfBounds(i) => {
+                write!(f, "constant pool index #{} is out of bounds", i)
+            }
+            ConstantPoolLookupError::UnusableSlot(i) => write!(
+                f,
+                "constant pool index #{} is the second slot of a Long or Double",
+                i
+            ),
+            ConstantPoolLookupError::WrongType(i) => {
+                write!(f, "constant pool entry #{} has the wrong type", i)
+            }
+        }
+    }
+}
+impl std::error::Error for ConstantPoolLookupError {}
+
 /// A wrapper structure around Vec to provide access
 #[derive(Clone, Debug)]
 pub struct ConstantPool {
     /// In the jvm, the constant pool starts at 1, so the indices start at one.
     /// But this is indexed starting at zero.
-    pool: Rc<[ConstantInfo]>,
+    pool: Shared<Vec<ConstantInfo>>,
 }
 impl ConstantPool {
     // Note: The casts from u16 to usize are always fine if the invariant holds,
@@ -117,7 +185,7 @@ impl ConstantPool {
     pub(crate) fn new(pool: Vec<ConstantInfo>) -> Self {
         assert!(pool.len() <= (u16::MAX as usize));
         Self {
-            pool: Rc::from(pool),
+            pool: Shared::from(pool),
         }
     }
 
@@ -143,10 +211,338 @@ impl ConstantPool {
         <&'a T>::try_from(v).ok()
     }
 
-    pub fn iter(&self) -> std::slice::Iter<ConstantInfo> {
+    /// Like [`ConstantPool::get`], but saying why there is no entry, and treating the unusable
+    /// slots after Long and Double entries as errors rather than returning them
+    pub fn try_get<T>(
+        &self,
+        i: ConstantPoolIndexRaw<T>,
+    ) -> Result<&ConstantInfo, ConstantPoolLookupError> {
+        if i.is_zero() {
+            return Err(ConstantPoolLookupError::ZeroIndex);
+        }
+        match self.pool.get(usize::from(i.0 - 1)) {
+            Some(ConstantInfo::Unusable) => Err(ConstantPoolLookupError::UnusableSlot(i.0)),
+            Some(entry) => Ok(entry),
+            None => Err(ConstantPoolLookupError::OutOfBounds(i.0)),
+        }
+    }
+
+    /// Like [`ConstantPool::get_t`], but saying why there is no entry of the type
+    pub fn try_get_t<'a, T>(
+        &'a self,
+        i: ConstantPoolIndexRaw<T>,
+    ) -> Result<&'a T, ConstantPoolLookupError>
+    where
+        &'a T: TryFrom<&'a ConstantInfo>,
+    {
+        let entry = self.try_get(i)?;
+        <&'a T>::try_from(entry).map_err(|_| ConstantPoolLookupError::WrongType(i.0))
+    }
+
+    /// Get the text of the Utf8 entry at the index, decoded from the class file data
+    pub fn get_utf8_text<'a>(
+        &self,
+        i: impl TryInto<ConstantPoolIndex<Utf8Constant>>,
+        class_file_data: &'a [u8],
+    ) -> Option<Cow<'a, str>> {
+        self.get_t(i).map(|text| text.as_text(class_file_data))
+    }
+
+    /// Get the internal name of the Class entry at the index, such as `java/lang/Object`, or an
+    /// array descriptor for array classes
+    pub fn get_class_name<'a>(
+        &self,
+        i: impl TryInto<ConstantPoolIndex<ClassConstant>>,
+        class_file_data: &'a [u8],
+    ) -> Option<Cow<'a, str>> {
+        let class = self.get_t(i)?;
+        self.get_utf8_text(class.name_index, class_file_data)
+    }
+
+    /// Get the name and descriptor entries of the NameAndType entry at the index
+    pub fn get_name_and_type(
+        &self,
+        i: impl TryInto<ConstantPoolIndex<NameAndTypeConstant>>,
+    ) -> Option<(&Utf8Constant, &Utf8Constant)> {
+        let nat = self.get_t(i)?;
+        Some((
+            self.get_t(nat.name_index)?,
+            self.get_t(nat.descriptor_index)?,
+        ))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ConstantInfo> {
         self.pool.iter()
     }
+
+    /// Get mutable access to the entries, copying them first if the pool is shared with any
+    /// clones, so that changes are never seen through other class files.
+    /// Long and Double entries must stay followed by an [`ConstantInfo::Unusable`] slot.
+    pub fn make_mut(&mut self) -> &mut [ConstantInfo] {
+        Shared::make_mut(&mut self.pool).as_mut_slice()
+    }
+
+    /// Get mutable access to an entry, copying the pool first if it is shared, see
+    /// [`ConstantPool::make_mut`]
+    pub fn get_mut<T>(
+        &mut self,
+        i: impl TryInto<ConstantPoolIndex<T>>,
+    ) -> Option<&mut ConstantInfo> {
+        let i: ConstantPoolIndex<T> = i.try_into().ok()?;
+        if usize::from(i.0) >= self.pool.len() {
+            return None;
+        }
+        self.make_mut().get_mut(usize::from(i.0))
+    }
+
+    /// Add an entry to the end of the pool, followed by an unusable slot if it is a Long or
+    /// Double, returning its raw index.
+    /// Returns None if the pool has no room for it.
+    /// This copies the entries first if the pool is shared, so clones of the pool don't see the
+    /// new entry, see [`ConstantPool::make_mut`].
+    /// The `const_pool_size` of the class file that owns the pool is not updated.
+    pub fn push(&mut self, entry: ConstantInfo) -> Option<ConstantPoolIndexRaw<ConstantInfo>> {
+        let wide = matches!(entry, ConstantInfo::Long(_) | ConstantInfo::Double(_));
+        let width = if wide { 2 } else { 1 };
+        // The constant_pool_count is one more than the number of slots, and must fit in a u16
+        if self.pool.len() + width >= usize::from(u16::MAX) {
+            return None;
+        }
+
+        let index = ConstantPoolIndexRaw::new(self.len() + 1);
+        let entries = Shared::make_mut(&mut self.pool);
+        entries.push(entry);
+        if wide {
+            entries.push(ConstantInfo::Unusable);
+        }
+        Some(index)
+    }
+
+    /// Iterate over the raw indices of every usable entry, skipping the unusable slots after
+    /// Long/Double entries.
+    pub fn indices(&self) -> impl Iterator<Item = ConstantPoolIndexRaw<ConstantInfo>> + '_ {
+        std::iter::successors(
+            ConstantPoolIndexRaw::<ConstantInfo>::new(0).next_index(self),
+            move |i| i.next_index(self),
+        )
+    }
+
+    /// Iterate over every usable entry alongside its raw index
+    pub fn iter_indexed(
+        &self,
+    ) -> impl Iterator<Item = (ConstantPoolIndexRaw<ConstantInfo>, &ConstantInfo)> + '_ {
+        self.indices()
+            .filter_map(move |i| self.get(i).map(|entry| (i, entry)))
+    }
+
+    /// Iterate over every Utf8 entry alongside its raw index, with its text decoded from the
+    /// class file data
+    pub fn utf8_iter<'a>(
+        &'a self,
+        class_file_data: &'a [u8],
+    ) -> impl Iterator<Item = (ConstantPoolIndexRaw<Utf8Constant>, Cow<'a, str>)> + 'a {
+        self.iter_indexed()
+            .filter_map(move |(i, entry)| match entry {
+                ConstantInfo::Utf8(text) => Some((
+                    ConstantPoolIndexRaw::new(i.0),
+                    text.as_text(class_file_data),
+                )),
+                _ => None,
+            })
+    }
+
+    /// Write out every entry in the format that `javap -v` uses, such as
+    /// `#5 = Methodref #3.#12 // java/lang/Object."<init>":()V`, with the entries that they
+    /// refer to resolved in a comment.
+    pub fn dump(&self, class_file_data: &[u8]) -> String {
+        let mut out = String::new();
+        for (index, entry) in self.iter_indexed() {
+            let (kind, operands, comment) = dump_entry(self, entry, class_file_data);
+            let index = format!("#{}", index.0);
+            let line = match comment {
+                Some(comment) => format!(
+                    "{:>5} = {:<18} {:<14} // {}",
+                    index, kind, operands, comment
+                ),
+                None => format!("{:>5} = {:<18} {}", index, kind, operands),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The number of slots that the entry at the (already offset) index takes up
+    fn slot_width(&self, i: u16) -> Option<u16> {
+        match self.pool.get(i as usize)? {
+            ConstantInfo::Long(_) | ConstantInfo::Double(_) => Some(2),
+            _ => Some(1),
+        }
+    }
+}
+/// A constant pool which only records where each entry starts in the class file data, and parses
+/// an entry each time it is asked for, see [`crate::ParseOptions::lazy_constant_pool`].
+/// This is cheaper than a [`ConstantPool`] when only a few entries are looked at, and
+/// [`ConstantPoolOpt::to_pool`] parses every entry for when that is no longer the case.
+#[derive(Clone, Debug, Default)]
+pub struct ConstantPoolOpt {
+    /// The offset of each slot, indexed starting at zero like [`ConstantPool`].
+    /// The unusable slots after Long and Double entries have no offset.
+    offsets: Shared<[Option<u32>]>,
 }
+impl ConstantPoolOpt {
+    /// The offsets hold at most u16 elements, like the entries of [`ConstantPool::new`]
+    pub(crate) fn new(offsets: Vec<Option<u32>>) -> Self {
+        assert!(offsets.len() <= (u16::MAX as usize));
+        Self {
+            offsets: Shared::from(offsets),
+        }
+    }
+
+    pub fn len(&self) -> u16 {
+        self.offsets.len() as u16
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the offset in the class file data of the tag of the entry at the index.
+    /// Returns None for the unusable slots after Long and Double entries.
+    pub fn offset<T>(&self, i: impl TryInto<ConstantPoolIndex<T>>) -> Option<usize> {
+        let i: ConstantPoolIndex<T> = i.try_into().ok()?;
+        let offset = (*self.offsets.get(usize::from(i.0))?)?;
+        Some(offset as usize)
+    }
+
+    /// Parse the entry at the index out of the class file data, which must be the data that the
+    /// pool was parsed from.
+    /// Like [`ConstantPool::get`], the unusable slots are [`ConstantInfo::Unusable`].
+    pub fn get<T>(
+        &self,
+        i: impl TryInto<ConstantPoolIndex<T>>,
+        class_file_data: &[u8],
+    ) -> Option<ConstantInfo> {
+        let i: ConstantPoolIndex<T> = i.try_into().ok()?;
+        match *self.offsets.get(usize::from(i.0))? {
+            Some(offset) => parse_entry(offset, class_file_data).ok(),
+            None => Some(ConstantInfo::Unusable),
+        }
+    }
+
+    pub fn get_t<T>(
+        &self,
+        i: impl TryInto<ConstantPoolIndex<T>>,
+        class_file_data: &[u8],
+    ) -> Option<T>
+    where
+        T: TryFrom<ConstantInfo>,
+    {
+        T::try_from(self.get(i, class_file_data)?).ok()
+    }
+
+    /// See [`ConstantPool::get_utf8_text`]
+    pub fn get_utf8_text<'a>(
+        &self,
+        i: impl TryInto<ConstantPoolIndex<Utf8Constant>>,
+        class_file_data: &'a [u8],
+    ) -> Option<Cow<'a, str>> {
+        self.get_t::<Utf8Constant>(i, class_file_data)
+            .map(|text| text.as_text(class_file_data))
+    }
+
+    /// See [`ConstantPool::get_class_name`]
+    pub fn get_class_name<'a>(
+        &self,
+        i: impl TryInto<ConstantPoolIndex<ClassConstant>>,
+        class_file_data: &'a [u8],
+    ) -> Option<Cow<'a, str>> {
+        let class = self.get_t::<ClassConstant>(i, class_file_data)?;
+        self.get_utf8_text(class.name_index, class_file_data)
+    }
+
+    /// Parse every entry into a [`ConstantPool`].
+    /// Errors with [`LoadError::InvalidIndex`] for the first entry that is malformed.
+    pub fn to_pool(&self, class_file_data: &[u8]) -> Result<ConstantPool, LoadError> {
+        let entries = self
+            .offsets
+            .iter()
+            .enumerate()
+            .map(|(i, offset)| match *offset {
+                Some(offset) => parse_entry(offset, class_file_data)
+                    .map_err(|_| LoadError::InvalidIndex(i as u16 + 1)),
+                None => Ok(ConstantInfo::Unusable),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ConstantPool::new(entries))
+    }
+}
+
+/// A constant pool which is either loaded or lazy, for the lookups that work the same on both
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PoolRef<'p> {
+    Loaded(&'p ConstantPool),
+    Lazy(&'p ConstantPoolOpt),
+}
+impl<'p> PoolRef<'p> {
+    pub(crate) fn get(
+        self,
+        i: ConstantPoolIndexRaw<ConstantInfo>,
+        class_file_data: &[u8],
+    ) -> Option<Cow<'p, ConstantInfo>> {
+        match self {
+            PoolRef::Loaded(pool) => pool.get(i).map(Cow::Borrowed),
+            PoolRef::Lazy(pool) => pool.get(i, class_file_data).map(Cow::Owned),
+        }
+    }
+
+    pub(crate) fn get_utf8_text<'a>(
+        self,
+        i: ConstantPoolIndexRaw<Utf8Constant>,
+        class_file_data: &'a [u8],
+    ) -> Option<Cow<'a, str>> {
+        match self {
+            PoolRef::Loaded(pool) => pool.get_utf8_text(i, class_file_data),
+            PoolRef::Lazy(pool) => pool.get_utf8_text(i, class_file_data),
+        }
+    }
+
+    pub(crate) fn get_class_name<'a>(
+        self,
+        i: ConstantPoolIndexRaw<ClassConstant>,
+        class_file_data: &'a [u8],
+    ) -> Option<Cow<'a, str>> {
+        match self {
+            PoolRef::Loaded(pool) => pool.get_class_name(i, class_file_data),
+            PoolRef::Lazy(pool) => pool.get_class_name(i, class_file_data),
+        }
+    }
+
+    /// Whether the entry at the index is a Utf8 entry with the text
+    pub(crate) fn is_utf8(
+        self,
+        i: ConstantPoolIndexRaw<Utf8Constant>,
+        class_file_data: &[u8],
+        text: &str,
+    ) -> bool {
+        self.get_utf8_text(i, class_file_data)
+            .is_some_and(|utf8| utf8 == text)
+    }
+}
+
+fn parse_entry(offset: u32, class_file_data: &[u8]) -> Result<ConstantInfo, LoadError> {
+    let offset = offset as usize;
+    if offset > class_file_data.len() {
+        return Err(LoadError::OutOfRange {
+            index: offset,
+            len: class_file_data.len(),
+        });
+    }
+    let input = ParseData::from_range(class_file_data, offset..class_file_data.len());
+    let (_, entry) = single_constant_parser(input)?;
+    Ok(entry)
+}
+
 /// This is primarily for swapping it out
 impl Default for ConstantPool {
     fn default() -> Self {
@@ -154,4 +550,139 @@ impl Default for ConstantPool {
     }
 }
 
+/// The kind, operands, and resolved comment of an entry for [`ConstantPool::dump`]
+fn dump_entry(
+    pool: &ConstantPool,
+    entry: &ConstantInfo,
+    data: &[u8],
+) -> (&'static str, String, Option<String>) {
+    let utf8 = |index: ConstantPoolIndexRaw<Utf8Constant>| {
+        pool.get_utf8_text(index, data).map(Cow::into_owned)
+    };
+    let class = |index: ConstantPoolIndexRaw<ClassConstant>| {
+        let name = pool.get_class_name(index, data)?.into_owned();
+        // Array classes are quoted, since they aren't names
+        Some(quote_if(name, '['))
+    };
+    let nat_text = |nat: &NameAndTypeConstant| {
+        let name = quote_if(utf8(nat.name_index)?, '<');
+        Some(format!("{}:{}", name, utf8(nat.descriptor_index)?))
+    };
+    let name_and_type =
+        |index: ConstantPoolIndexRaw<NameAndTypeConstant>| nat_text(pool.get_t(index)?);
+    let member = |class_index, nat_index| {
+        Some(format!(
+            "{}.{}",
+            class(class_index)?,
+            name_and_type(nat_index)?
+        ))
+    };
+    let pair = |a: u16, b: u16, sep: char| format!("#{}{}#{}", a, sep, b);
+
+    match entry {
+        ConstantInfo::Utf8(c) => ("Utf8", escape(&c.as_text(data)), None),
+        ConstantInfo::Integer(c) => ("Integer", c.value.to_string(), None),
+        ConstantInfo::Float(c) => ("Float", format!("{:?}f", c.value), None),
+        ConstantInfo::Long(c) => ("Long", format!("{}l", c.value), None),
+        ConstantInfo::Double(c) => ("Double", format!("{:?}d", c.value), None),
+        ConstantInfo::Class(c) => (
+            "Class",
+            format!("#{}", c.name_index.0),
+            utf8(c.name_index).map(|name| quote_if(name, '[')),
+        ),
+        ConstantInfo::String(c) => (
+            "String",
+            format!("#{}", c.string_index.0),
+            utf8(c.string_index).map(|text| escape(&text)),
+        ),
+        ConstantInfo::FieldRef(c) => (
+            "Fieldref",
+            pair(c.class_index.0, c.name_and_type_index.0, '.'),
+            member(c.class_index, c.name_and_type_index),
+        ),
+        ConstantInfo::MethodRef(c) => (
+            "Methodref",
+            pair(c.class_index.0, c.name_and_type_index.0, '.'),
+            member(c.class_index, c.name_and_type_index),
+        ),
+        ConstantInfo::InterfaceMethodRef(c) => (
+            "InterfaceMethodref",
+            pair(c.class_index.0, c.name_and_type_index.0, '.'),
+            member(c.class_index, c.name_and_type_index),
+        ),
+        ConstantInfo::NameAndType(c) => (
+            "NameAndType",
+            pair(c.name_index.0, c.descriptor_index.0, ':'),
+            nat_text(c),
+        ),
+        // Only member references are followed, since the reference could be another handle
+        ConstantInfo::MethodHandle(c) => {
+            let reference = match pool.get(c.reference_index) {
+                Some(ConstantInfo::FieldRef(r)) => member(r.class_index, r.name_and_type_index),
+                Some(ConstantInfo::MethodRef(r)) => member(r.class_index, r.name_and_type_index),
+                Some(ConstantInfo::InterfaceMethodRef(r)) => {
+                    member(r.class_index, r.name_and_type_index)
+                }
+                _ => None,
+            };
+            let comment = ReferenceKind::from_u8(c.reference_kind)
+                .zip(reference)
+                .map(|(kind, reference)| format!("{} {}", kind.name(), reference));
+            (
+                "MethodHandle",
+                format!("{}:#{}", c.reference_kind, c.reference_index.0),
+                comment,
+            )
+        }
+        // javap puts an extra space before the descriptor
+        ConstantInfo::MethodType(c) => (
+            "MethodType",
+            format!("#{}", c.descriptor_index.0),
+            utf8(c.descriptor_index).map(|descriptor| format!(" {}", descriptor)),
+        ),
+        ConstantInfo::InvokeDynamic(c) => (
+            "InvokeDynamic",
+            pair(c.bootstrap_method_attr_index, c.name_and_type_index.0, ':'),
+            name_and_type(c.name_and_type_index)
+                .map(|nat| format!("#{}:{}", c.bootstrap_method_attr_index, nat)),
+        ),
+        ConstantInfo::Dynamic(c) => (
+            "Dynamic",
+            pair(c.bootstrap_method_attr_index, c.name_and_type_index.0, ':'),
+            name_and_type(c.name_and_type_index)
+                .map(|nat| format!("#{}:{}", c.bootstrap_method_attr_index, nat)),
+        ),
+        ConstantInfo::Module(c) => ("Module", format!("#{}", c.name_index.0), utf8(c.name_index)),
+        ConstantInfo::Package(c) => (
+            "Package",
+            format!("#{}", c.name_index.0),
+            utf8(c.name_index),
+        ),
+        ConstantInfo::Unusable => ("Unusable", String::new(), None),
+    }
+}
+
+fn quote_if(text: String, first: char) -> String {
+    if text.starts_with(first) {
+        format!("\"{}\"", text)
+    } else {
+        text
+    }
+}
+
+/// Escape control characters like javap does, so that each entry stays on one line
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
 // TODO: Implementing Index{Mut,} would be useful, but I failed to make it work properly
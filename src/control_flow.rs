@@ -0,0 +1,319 @@
+//! Building the control flow graph of a method's code, with its subroutines.
+//!
+//! The code is split into basic blocks, which are only entered at their first instruction and only
+//! left after their last one, or by an exception.
+//!
+//! Before Java 6, javac compiled `finally` blocks into subroutines, which are called with `jsr` and
+//! return with `ret` to the instruction after the call. The block ending in a `jsr` has a
+//! [`EdgeKind::Call`] edge to the subroutine, and each `ret` has a [`EdgeKind::Return`] edge to the
+//! instruction after every `jsr` that calls a subroutine which it belongs to.
+//! [see more](https://docs.oracle.com/javase/specs/jvms/se6/html/Compiling.doc.html#13789)
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::attribute_info::{CodeAttribute, ExceptionEntry};
+use crate::instructions::{code_iter, Instruction, InstructionError, WideInstruction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// A branch, goto, or switch
+    Jump,
+    /// Continuing to the next block
+    FallThrough,
+    /// To an exception handler that covers the block
+    Exception,
+    /// A `jsr` to the start of a subroutine
+    Call,
+    /// A `ret` to the instruction after a `jsr`
+    Return,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub kind: EdgeKind,
+    /// The index of the block that the edge goes to
+    pub target: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The offset of the first instruction
+    pub start: usize,
+    /// The offset just past the last instruction
+    pub end: usize,
+    /// The offset of the last instruction
+    pub last: usize,
+    pub edges: Vec<Edge>,
+}
+
+/// A subroutine, with its blocks given by their indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subroutine {
+    /// The block that `jsr` jumps to
+    pub entry: usize,
+    /// The blocks which end in a `jsr` to the subroutine
+    pub callers: Vec<usize>,
+    /// The blocks reachable from the entry without following calls or returns, along with the
+    /// handlers for any of them, in order of their offsets. A nested call continues at the
+    /// instruction after it.
+    pub blocks: Vec<usize>,
+    /// The blocks in `blocks` which end in a `ret`
+    pub rets: Vec<usize>,
+}
+
+/// The basic blocks of some code in order of their offsets, and its subroutines in order of their
+/// entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    pub subroutines: Vec<Subroutine>,
+}
+impl ControlFlowGraph {
+    /// The index of the block containing the offset
+    pub fn block_at(&self, offset: usize) -> Option<usize> {
+        let index = self.blocks.partition_point(|block| block.end <= offset);
+        self.blocks
+            .get(index)
+            .filter(|block| block.start <= offset)
+            .map(|_| index)
+    }
+
+    /// The subroutine that starts at the block
+    pub fn subroutine(&self, entry: usize) -> Option<&Subroutine> {
+        self.subroutines
+            .iter()
+            .find(|subroutine| subroutine.entry == entry)
+    }
+}
+
+/// Why the control flow graph of some code couldn't be built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlowError {
+    Instruction(InstructionError),
+    /// A branch or switch target which isn't the start of an instruction in the code
+    BadTarget {
+        offset: usize,
+        target: i64,
+    },
+    /// An exception handler whose range or handler isn't at the start of an instruction
+    BadHandler {
+        handler_pc: u16,
+    },
+    /// The instruction at the offset can continue past the end of the code
+    FallsOffEnd {
+        offset: usize,
+    },
+    /// The `ret` at the offset isn't in any subroutine
+    UnmatchedRet {
+        offset: usize,
+    },
+}
+
+fn is_jsr(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Jsr(_) | Instruction::JsrW(_))
+}
+
+fn is_ret(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Ret(_) | Instruction::Wide(WideInstruction::Ret(_))
+    )
+}
+
+/// Build the control flow graph of the code.
+/// The code must be the whole of the code of a method, and the exception table the one that goes
+/// with it. Blocks are split at the bounds of each handler's range, so that every instruction in a
+/// block is covered by the same handlers.
+pub fn control_flow_graph(
+    code: &[u8],
+    exception_table: &[ExceptionEntry],
+) -> Result<ControlFlowGraph, ControlFlowError> {
+    let mut instructions = Vec::new();
+    for result in code_iter(code) {
+        let (offset, instruction) = result.map_err(ControlFlowError::Instruction)?;
+        instructions.push((usize::from(offset), instruction));
+    }
+    let is_start = |offset: usize| {
+        instructions
+            .binary_search_by_key(&offset, |&(start, _)| start)
+            .is_ok()
+    };
+
+    // The offsets which start a block
+    let mut leaders = BTreeSet::new();
+    if !instructions.is_empty() {
+        leaders.insert(0);
+    }
+    for (index, (offset, instruction)) in instructions.iter().enumerate() {
+        let targets = instruction.branch_targets();
+        for &relative in targets.iter() {
+            let target = *offset as i64 + i64::from(relative);
+            match usize::try_from(target) {
+                Ok(target) if is_start(target) => leaders.insert(target),
+                _ => {
+                    return Err(ControlFlowError::BadTarget {
+                        offset: *offset,
+                        target,
+                    })
+                }
+            };
+        }
+        if !targets.is_empty() || !instruction.falls_through() {
+            if let Some((next, _)) = instructions.get(index + 1) {
+                leaders.insert(*next);
+            }
+        }
+    }
+    for entry in exception_table {
+        let handler_pc = entry.handler_pc.0;
+        let (start, end) = (usize::from(entry.start_pc.0), usize::from(entry.end_pc.0));
+        let handler = usize::from(handler_pc);
+        // The range can end at the end of the code
+        if !is_start(start) || !(is_start(end) || end == code.len()) || !is_start(handler) {
+            return Err(ControlFlowError::BadHandler { handler_pc });
+        }
+        leaders.extend(
+            [start, end, handler]
+                .into_iter()
+                .filter(|&at| at < code.len()),
+        );
+    }
+
+    let starts: Vec<usize> = leaders.into_iter().collect();
+    let blocks_by_start: HashMap<usize, usize> = starts
+        .iter()
+        .enumerate()
+        .map(|(index, &start)| (start, index))
+        .collect();
+    let mut blocks = Vec::with_capacity(starts.len());
+    // The instruction that ends each block
+    let mut lasts = Vec::with_capacity(starts.len());
+    for (index, &start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).copied().unwrap_or(code.len());
+        let last = instructions.partition_point(|&(offset, _)| offset < end) - 1;
+        lasts.push(last);
+        blocks.push(BasicBlock {
+            start,
+            end,
+            last: instructions[last].0,
+            edges: Vec::new(),
+        });
+    }
+
+    for (index, block) in blocks.iter_mut().enumerate() {
+        let (offset, instruction) = &instructions[lasts[index]];
+        let kind = if is_jsr(instruction) {
+            EdgeKind::Call
+        } else {
+            EdgeKind::Jump
+        };
+        for relative in instruction.branch_targets() {
+            let target = (*offset as i64 + i64::from(relative)) as usize;
+            block.edges.push(Edge {
+                kind,
+                target: blocks_by_start[&target],
+            });
+        }
+        if instruction.falls_through() {
+            if block.end >= code.len() {
+                return Err(ControlFlowError::FallsOffEnd { offset: *offset });
+            }
+            // The instruction after a `jsr` is reached by the subroutine's `ret`
+            if !is_jsr(instruction) {
+                block.edges.push(Edge {
+                    kind: EdgeKind::FallThrough,
+                    target: index + 1,
+                });
+            }
+        }
+        for entry in exception_table {
+            let range = usize::from(entry.start_pc.0)..usize::from(entry.end_pc.0);
+            if range.contains(&block.start) {
+                block.edges.push(Edge {
+                    kind: EdgeKind::Exception,
+                    target: blocks_by_start[&usize::from(entry.handler_pc.0)],
+                });
+            }
+        }
+    }
+
+    // Each distinct target of a `jsr` starts a subroutine
+    let mut entries: BTreeSet<usize> = BTreeSet::new();
+    let mut callers: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, block) in blocks.iter().enumerate() {
+        for edge in block
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Call)
+        {
+            entries.insert(edge.target);
+            callers.entry(edge.target).or_default().push(index);
+        }
+    }
+    let mut subroutines = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let mut reached = vec![false; blocks.len()];
+        let mut pending = vec![entry];
+        while let Some(index) = pending.pop() {
+            if std::mem::replace(&mut reached[index], true) {
+                continue;
+            }
+            for edge in blocks[index].edges.iter() {
+                match edge.kind {
+                    EdgeKind::Call => pending.push(index + 1),
+                    _ => pending.push(edge.target),
+                }
+            }
+        }
+        let members: Vec<usize> = (0..blocks.len()).filter(|&index| reached[index]).collect();
+        let rets = members
+            .iter()
+            .copied()
+            .filter(|&index| is_ret(&instructions[lasts[index]].1))
+            .collect();
+        subroutines.push(Subroutine {
+            entry,
+            callers: callers.remove(&entry).unwrap_or_default(),
+            blocks: members,
+            rets,
+        });
+    }
+
+    // A `ret` returns after every call to each subroutine that it's in
+    for (index, &last) in lasts.iter().enumerate() {
+        let (offset, instruction) = &instructions[last];
+        if !is_ret(instruction) {
+            continue;
+        }
+        let mut returns: Vec<usize> = subroutines
+            .iter()
+            .filter(|subroutine| subroutine.rets.contains(&index))
+            .flat_map(|subroutine| subroutine.callers.iter().map(|caller| caller + 1))
+            .collect();
+        if returns.is_empty() {
+            return Err(ControlFlowError::UnmatchedRet { offset: *offset });
+        }
+        returns.sort_unstable();
+        returns.dedup();
+        let edges = returns.into_iter().map(|target| Edge {
+            kind: EdgeKind::Return,
+            target,
+        });
+        blocks[index].edges.extend(edges);
+    }
+
+    Ok(ControlFlowGraph {
+        blocks,
+        subroutines,
+    })
+}
+
+impl CodeAttribute {
+    /// Build the control flow graph of the code, see [`control_flow_graph`]
+    pub fn control_flow_graph(
+        &self,
+        class_file_data: &[u8],
+    ) -> Result<ControlFlowGraph, ControlFlowError> {
+        control_flow_graph(&class_file_data[self.code.clone()], &self.exception_table)
+    }
+}
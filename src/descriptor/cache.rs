@@ -0,0 +1,108 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    constant_info::Utf8Constant,
+    constant_pool::{ConstantPool, ConstantPoolIndexRaw},
+};
+
+use super::{
+    method::{MethodDescriptor, MethodDescriptorError},
+    DescriptorType, DescriptorTypeError,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedDescriptor {
+    Field(DescriptorType<'static>),
+    Method(MethodDescriptor<'static>),
+}
+impl ParsedDescriptor {
+    /// Parse either a field or method descriptor, deciding by the opening character
+    pub fn parse(text: &[u8]) -> Result<ParsedDescriptor, DescriptorError> {
+        if text.starts_with(b"(") {
+            let desc = MethodDescriptor::parse(text).map_err(DescriptorError::Method)?;
+            Ok(ParsedDescriptor::Method(desc.to_owned()))
+        } else {
+            let (desc, rest) = DescriptorType::parse(text).map_err(DescriptorError::Field)?;
+            if !rest.is_empty() {
+                return Err(DescriptorError::RemainingData);
+            }
+            Ok(ParsedDescriptor::Field(desc.to_owned()))
+        }
+    }
+
+    pub fn as_field(&self) -> Option<&DescriptorType<'static>> {
+        match self {
+            ParsedDescriptor::Field(desc) => Some(desc),
+            ParsedDescriptor::Method(_) => None,
+        }
+    }
+
+    pub fn as_method(&self) -> Option<&MethodDescriptor<'static>> {
+        match self {
+            ParsedDescriptor::Field(_) => None,
+            ParsedDescriptor::Method(desc) => Some(desc),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DescriptorError {
+    /// The index did not refer to a utf8 constant
+    InvalidIndex,
+    Field(DescriptorTypeError),
+    Method(MethodDescriptorError),
+    /// There was data after the field descriptor
+    RemainingData,
+}
+
+/// A cache of parsed descriptors, keyed by the index of the utf8 constant that holds them.
+/// Descriptors tend to be shared by many members and instructions, so this avoids parsing the same
+/// one repeatedly.
+#[derive(Debug, Clone, Default)]
+pub struct DescriptorCache {
+    entries: RefCell<HashMap<u16, Rc<ParsedDescriptor>>>,
+}
+impl DescriptorCache {
+    pub fn new() -> DescriptorCache {
+        DescriptorCache::default()
+    }
+
+    /// Get the parsed descriptor at the index, parsing and caching it if it has not been already.
+    /// Failures are not cached.
+    pub fn get_or_parse(
+        &self,
+        pool: &ConstantPool,
+        index: ConstantPoolIndexRaw<Utf8Constant>,
+        class_file_data: &[u8],
+    ) -> Result<Rc<ParsedDescriptor>, DescriptorError> {
+        if let Some(desc) = self.entries.borrow().get(&index.0) {
+            return Ok(desc.clone());
+        }
+
+        let desc = Rc::new(parse_at(pool, index, class_file_data)?);
+        self.entries.borrow_mut().insert(index.0, desc.clone());
+        Ok(desc)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+}
+
+/// Parse the descriptor held by the utf8 constant at the index, without any caching
+pub fn parse_at(
+    pool: &ConstantPool,
+    index: ConstantPoolIndexRaw<Utf8Constant>,
+    class_file_data: &[u8],
+) -> Result<ParsedDescriptor, DescriptorError> {
+    let text = pool.get_t(index).ok_or(DescriptorError::InvalidIndex)?;
+    ParsedDescriptor::parse(text.as_bytes(class_file_data))
+}
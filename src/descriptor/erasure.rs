@@ -0,0 +1,288 @@
+//! Erasing generic signatures to the descriptors that they correspond to.
+//! [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.9.1)
+
+use crate::attribute_info::{AttributeOwner, HasAttributes, SignatureAttribute};
+use crate::constant_pool::ConstantPool;
+use crate::descriptor::method::MethodDescriptor;
+use crate::descriptor::signature::{
+    ClassSignature, MethodSignature, ReferenceTypeSignature, SignatureError, TypeParameter,
+    TypeSignature,
+};
+use crate::names;
+use crate::ClassFile;
+
+/// How deeply type variable bounds may refer to other type variables, which stops cycles like
+/// `<A:TB;B:TA;>` from looping forever
+const MAX_BOUND_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErasureError {
+    Malformed,
+    /// A type variable which is not declared by the method or class, such as one from an
+    /// enclosing class. The erasure can't be known without loading that class.
+    UnknownTypeVariable,
+}
+impl From<SignatureError> for ErasureError {
+    fn from(_: SignatureError) -> Self {
+        ErasureError::Malformed
+    }
+}
+
+/// Type parameters in scope, where those declared closer come first and so shadow those further
+/// out, like a method's shadow its class's
+type TypeVariables<'s, 'a> = Vec<&'s TypeParameter<'a>>;
+
+const OBJECT: &[u8] = b"Ljava/lang/Object;";
+
+fn erase_type(
+    typ: &TypeSignature,
+    variables: &TypeVariables,
+    depth: usize,
+    out: &mut Vec<u8>,
+) -> Result<(), ErasureError> {
+    match typ {
+        TypeSignature::Base(base) => {
+            out.push(base.to_char());
+            Ok(())
+        }
+        TypeSignature::Reference(typ) => erase_reference_type(typ, variables, depth, out),
+    }
+}
+
+/// Erase a reference type, where the erasure of `Outer<T>.Inner` is `Outer$Inner`
+fn erase_reference_type(
+    typ: &ReferenceTypeSignature,
+    variables: &TypeVariables,
+    depth: usize,
+    out: &mut Vec<u8>,
+) -> Result<(), ErasureError> {
+    match typ {
+        ReferenceTypeSignature::Class(class) => {
+            out.push(b'L');
+            out.extend_from_slice(&class.binary_name());
+            out.push(b';');
+            Ok(())
+        }
+        ReferenceTypeSignature::TypeVariable(name) => erase_variable(name, variables, depth, out),
+        ReferenceTypeSignature::Array(component) => {
+            out.push(b'[');
+            erase_type(component, variables, depth, out)
+        }
+    }
+}
+
+/// Erase a type variable to the erasure of its first bound
+fn erase_variable(
+    name: &[u8],
+    variables: &TypeVariables,
+    depth: usize,
+    out: &mut Vec<u8>,
+) -> Result<(), ErasureError> {
+    if depth > MAX_BOUND_DEPTH {
+        return Err(ErasureError::Malformed);
+    }
+    let parameter = variables
+        .iter()
+        .find(|parameter| *parameter.name == *name)
+        .ok_or(ErasureError::UnknownTypeVariable)?;
+
+    // The class bound may be left out, leaving only interface bounds
+    match parameter
+        .class_bound
+        .as_ref()
+        .or_else(|| parameter.interface_bounds.first())
+    {
+        Some(bound) => erase_reference_type(bound, variables, depth + 1, out),
+        None => {
+            out.extend_from_slice(OBJECT);
+            Ok(())
+        }
+    }
+}
+
+fn erase_field_signature(
+    signature: &[u8],
+    variables: &TypeVariables,
+) -> Result<Vec<u8>, ErasureError> {
+    let typ = ReferenceTypeSignature::parse(signature)?;
+    let mut out = Vec::new();
+    erase_reference_type(&typ, variables, 0, &mut out)?;
+    Ok(out)
+}
+
+/// The erased parameters and return type of a method signature
+type ErasedMethod = (Vec<Vec<u8>>, Vec<u8>);
+
+fn erase_method_signature(
+    signature: &[u8],
+    class_variables: &TypeVariables,
+) -> Result<ErasedMethod, ErasureError> {
+    let signature = MethodSignature::parse(signature)?;
+    let variables: TypeVariables = signature
+        .type_parameters
+        .iter()
+        .chain(class_variables.iter().copied())
+        .collect();
+
+    let mut parameters = Vec::with_capacity(signature.parameter_types.len());
+    for parameter in signature.parameter_types.iter() {
+        let mut erased = Vec::new();
+        erase_type(parameter, &variables, 0, &mut erased)?;
+        parameters.push(erased);
+    }
+    let mut return_type = Vec::new();
+    match &signature.return_type {
+        Some(typ) => erase_type(typ, &variables, 0, &mut return_type)?,
+        None => return_type.push(b'V'),
+    }
+    Ok((parameters, return_type))
+}
+
+/// Whether the erased method signature agrees with the descriptor.
+/// Constructors can have parameters that the compiler adds but leaves out of the signature, such
+/// as the outer instance of an inner class or the captured variables of a local class, so their
+/// signature's parameters only have to appear somewhere in the descriptor's.
+fn method_matches(erased: &ErasedMethod, descriptor: &[u8], constructor: bool) -> bool {
+    let descriptor = match MethodDescriptor::parse(descriptor) {
+        Ok(descriptor) => descriptor,
+        Err(_) => return false,
+    };
+    let (parameters, return_type) = erased;
+
+    let descriptor_return = match &descriptor.return_type {
+        Some(return_type) => return_type.to_descriptor(),
+        None => b"V".to_vec(),
+    };
+    if *return_type != descriptor_return {
+        return false;
+    }
+
+    let descriptor_parameters: Vec<Vec<u8>> = descriptor
+        .parameter_types
+        .iter()
+        .map(|parameter| parameter.to_descriptor())
+        .collect();
+    if constructor {
+        parameters.is_empty()
+            || descriptor_parameters
+                .windows(parameters.len())
+                .any(|window| window == parameters.as_slice())
+    } else {
+        *parameters == descriptor_parameters
+    }
+}
+
+fn write_method(erased: &ErasedMethod) -> Vec<u8> {
+    let (parameters, return_type) = erased;
+    let mut out = vec![b'('];
+    for parameter in parameters {
+        out.extend_from_slice(parameter);
+    }
+    out.push(b')');
+    out.extend_from_slice(return_type);
+    out
+}
+
+/// A field or method whose Signature attribute does not erase to its descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureMismatch {
+    /// The field or method that the Signature attribute is on
+    pub owner: AttributeOwner,
+    pub signature: String,
+    /// The descriptor that the signature erases to, or None if the signature is malformed
+    pub erased: Option<String>,
+    pub descriptor: String,
+}
+
+fn signature_text<'a, T: HasAttributes>(
+    member: &T,
+    pool: &ConstantPool,
+    data: &'a [u8],
+) -> Option<&'a [u8]> {
+    let attr: SignatureAttribute = member.find_attribute(pool, data).ok()??;
+    Some(pool.get_t(attr.signature_index)?.as_bytes(data))
+}
+
+impl ClassFile {
+    /// Find the fields and methods whose Signature attribute does not erase to their descriptor,
+    /// which is a common result of rewriting a descriptor while leaving the signature alone.
+    ///
+    /// Members whose signature uses a type variable that is not declared by the member or class,
+    /// such as one from an enclosing class, can't be checked and are skipped.
+    /// A malformed class Signature is treated as declaring no type variables.
+    pub fn signature_mismatches(&self, data: &[u8]) -> Vec<SignatureMismatch> {
+        let pool = &self.const_pool;
+        let class_signature =
+            signature_text(self, pool, data).and_then(|text| ClassSignature::parse(text).ok());
+        let class_variables: TypeVariables = class_signature
+            .iter()
+            .flat_map(|signature| signature.type_parameters.iter())
+            .collect();
+
+        let mut out = Vec::new();
+        let mut check = |owner, signature: &[u8], descriptor: &[u8], erased, matches| {
+            let erased: Result<Vec<u8>, ErasureError> = erased;
+            let erased = match erased {
+                Ok(_) if matches => return,
+                Ok(erased) => Some(String::from_utf8_lossy(&erased).into_owned()),
+                Err(ErasureError::UnknownTypeVariable) => return,
+                Err(ErasureError::Malformed) => None,
+            };
+            out.push(SignatureMismatch {
+                owner,
+                signature: String::from_utf8_lossy(signature).into_owned(),
+                erased,
+                descriptor: String::from_utf8_lossy(descriptor).into_owned(),
+            });
+        };
+
+        for (i, field) in self.fields.iter().enumerate() {
+            let signature = match signature_text(field, pool, data) {
+                Some(signature) => signature,
+                None => continue,
+            };
+            let descriptor = match pool.get_t(field.descriptor_index) {
+                Some(descriptor) => descriptor.as_bytes(data),
+                None => continue,
+            };
+            let erased = erase_field_signature(signature, &class_variables);
+            let matches = erased.as_deref() == Ok(descriptor);
+            check(
+                AttributeOwner::Field(i),
+                signature,
+                descriptor,
+                erased,
+                matches,
+            );
+        }
+
+        for (i, method) in self.methods.iter().enumerate() {
+            let signature = match signature_text(method, pool, data) {
+                Some(signature) => signature,
+                None => continue,
+            };
+            let (name, descriptor) = match (
+                pool.get_t(method.name_index),
+                pool.get_t(method.descriptor_index),
+            ) {
+                (Some(name), Some(descriptor)) => (name.as_bytes(data), descriptor.as_bytes(data)),
+                _ => continue,
+            };
+            let erased = erase_method_signature(signature, &class_variables);
+            let matches = erased
+                .as_ref()
+                .map(|erased| method_matches(erased, descriptor, name == names::INIT.as_bytes()))
+                .unwrap_or(false);
+            let erased = erased.map(|erased| write_method(&erased));
+            check(
+                AttributeOwner::Method(i),
+                signature,
+                descriptor,
+                erased,
+                matches,
+            );
+        }
+
+        out
+    }
+}
@@ -46,6 +46,27 @@ impl<'a> MethodDescriptor<'a> {
         MethodDescriptorParserIterator::new(text)
     }
 
+    /// Write the descriptor form of the method, such as `(ILjava/lang/String;)V`
+    pub fn write_descriptor(&self, out: &mut Vec<u8>) {
+        out.push(b'(');
+        for param in self.parameter_types.iter() {
+            param.write_descriptor(out);
+        }
+        out.push(b')');
+
+        match &self.return_type {
+            Some(ret) => ret.write_descriptor(out),
+            None => out.push(b'V'),
+        }
+    }
+
+    /// Get the descriptor form of the method
+    pub fn to_descriptor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_descriptor(&mut out);
+        out
+    }
+
     pub fn to_owned<'b>(self) -> MethodDescriptor<'b> {
         MethodDescriptor {
             parameter_types: self
@@ -243,4 +264,16 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn round_trip() {
+        for text in [
+            &b"()V"[..],
+            b"(IDJ)V",
+            b"([[ILjava/lang/String;Z)[Ljava/lang/Object;",
+        ] {
+            let desc = MethodDescriptor::parse(text).unwrap();
+            assert_eq!(desc.to_descriptor(), text);
+        }
+    }
 }
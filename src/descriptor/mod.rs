@@ -1,4 +1,9 @@
+mod cache;
+mod erasure;
 mod types;
 pub mod method;
+pub mod signature;
 
-pub use types::*;
\ No newline at end of file
+pub use cache::*;
+pub use erasure::SignatureMismatch;
+pub use types::*;
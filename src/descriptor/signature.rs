@@ -0,0 +1,844 @@
+//! Parsing the generic signatures of Signature attributes.
+//! [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.7.9.1)
+
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use crate::attribute_info::SignatureAttribute;
+use crate::constant_pool::ConstantPool;
+use crate::parser::DEFAULT_MAX_NESTING_DEPTH;
+use crate::LoadError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The signature ended in the middle of a type
+    UnexpectedEnd,
+    /// The byte at the position can't start or continue what was being read
+    Unexpected(usize),
+    /// There was no identifier at the position
+    EmptyIdentifier(usize),
+    /// There was more after the signature, starting at the position
+    RemainingData(usize),
+    /// Types were nested in each other too deeply
+    TooDeep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+}
+impl BaseType {
+    fn from_char(c: u8) -> Option<BaseType> {
+        Some(match c {
+            b'B' => BaseType::Byte,
+            b'C' => BaseType::Char,
+            b'D' => BaseType::Double,
+            b'F' => BaseType::Float,
+            b'I' => BaseType::Int,
+            b'J' => BaseType::Long,
+            b'S' => BaseType::Short,
+            b'Z' => BaseType::Boolean,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn to_char(self) -> u8 {
+        match self {
+            BaseType::Byte => b'B',
+            BaseType::Char => b'C',
+            BaseType::Double => b'D',
+            BaseType::Float => b'F',
+            BaseType::Int => b'I',
+            BaseType::Long => b'J',
+            BaseType::Short => b'S',
+            BaseType::Boolean => b'Z',
+        }
+    }
+}
+impl Display for BaseType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BaseType::Byte => "byte",
+            BaseType::Char => "char",
+            BaseType::Double => "double",
+            BaseType::Float => "float",
+            BaseType::Int => "int",
+            BaseType::Long => "long",
+            BaseType::Short => "short",
+            BaseType::Boolean => "boolean",
+        })
+    }
+}
+
+/// A type which may be a primitive, such as a parameter or return type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeSignature<'a> {
+    Base(BaseType),
+    Reference(ReferenceTypeSignature<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceTypeSignature<'a> {
+    /// L*class type*;
+    Class(ClassTypeSignature<'a>),
+    /// T*name*; a use of a type variable
+    TypeVariable(Cow<'a, [u8]>),
+    /// [*component*
+    Array(Box<TypeSignature<'a>>),
+}
+
+/// A class type, such as `Ljava/util/Map<TK;TV;>.Entry;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassTypeSignature<'a> {
+    /// The outermost class, whose name includes its package, followed by each inner class
+    /// written with `.` after it.
+    /// There is always at least one.
+    pub classes: Vec<SimpleClassTypeSignature<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleClassTypeSignature<'a> {
+    pub name: Cow<'a, [u8]>,
+    pub type_arguments: Vec<TypeArgument<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeArgument<'a> {
+    /// `*`, any type
+    Any,
+    Exact(ReferenceTypeSignature<'a>),
+    /// `+`, the type or a subtype of it
+    Extends(ReferenceTypeSignature<'a>),
+    /// `-`, the type or a supertype of it
+    Super(ReferenceTypeSignature<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParameter<'a> {
+    pub name: Cow<'a, [u8]>,
+    /// None if the parameter only has interface bounds, as in `<T::Ljava/lang/Runnable;>`
+    pub class_bound: Option<ReferenceTypeSignature<'a>>,
+    pub interface_bounds: Vec<ReferenceTypeSignature<'a>>,
+}
+
+/// The signature of a class, such as `<T:Ljava/lang/Object;>Ljava/lang/Object;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSignature<'a> {
+    pub type_parameters: Vec<TypeParameter<'a>>,
+    pub super_class: ClassTypeSignature<'a>,
+    pub interfaces: Vec<ClassTypeSignature<'a>>,
+}
+
+/// The signature of a method, such as `<T:Ljava/lang/Object;>(TT;)Ljava/util/List<TT;>;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSignature<'a> {
+    pub type_parameters: Vec<TypeParameter<'a>>,
+    pub parameter_types: Vec<TypeSignature<'a>>,
+    /// If this is None, then the return type was void
+    pub return_type: Option<TypeSignature<'a>>,
+    /// The exceptions after `^`, which are classes or type variables
+    pub throws: Vec<ReferenceTypeSignature<'a>>,
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+    /// How many types are being read, which are nested in each other
+    nesting: usize,
+}
+impl<'a> Parser<'a> {
+    fn new(text: &'a [u8]) -> Parser<'a> {
+        Parser {
+            text,
+            pos: 0,
+            nesting: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, SignatureError> {
+        let byte = self.peek().ok_or(SignatureError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), SignatureError> {
+        if self.next()? == byte {
+            Ok(())
+        } else {
+            Err(SignatureError::Unexpected(self.pos - 1))
+        }
+    }
+
+    fn finish(&self) -> Result<(), SignatureError> {
+        if self.pos == self.text.len() {
+            Ok(())
+        } else {
+            Err(SignatureError::RemainingData(self.pos))
+        }
+    }
+
+    /// Read up to the next character which can't be in an identifier.
+    /// With `slashes`, `/` is also allowed, for the package of a class name.
+    fn identifier(&mut self, slashes: bool) -> Result<&'a [u8], SignatureError> {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if matches!(byte, b'.' | b';' | b'[' | b'<' | b'>' | b':') || (byte == b'/' && !slashes)
+            {
+                break;
+            }
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(SignatureError::EmptyIdentifier(start));
+        }
+        Ok(&self.text[start..self.pos])
+    }
+
+    fn java_type(&mut self) -> Result<TypeSignature<'a>, SignatureError> {
+        match self.peek().and_then(BaseType::from_char) {
+            Some(base) => {
+                self.pos += 1;
+                Ok(TypeSignature::Base(base))
+            }
+            None => Ok(TypeSignature::Reference(self.reference_type()?)),
+        }
+    }
+
+    fn reference_type(&mut self) -> Result<ReferenceTypeSignature<'a>, SignatureError> {
+        if self.nesting == DEFAULT_MAX_NESTING_DEPTH {
+            return Err(SignatureError::TooDeep);
+        }
+        let byte = self.next()?;
+        self.nesting += 1;
+        let result = match byte {
+            b'L' => self.class_type().map(ReferenceTypeSignature::Class),
+            b'T' => self.identifier(false).and_then(|name| {
+                self.expect(b';')?;
+                Ok(ReferenceTypeSignature::TypeVariable(Cow::Borrowed(name)))
+            }),
+            b'[' => self
+                .java_type()
+                .map(|component| ReferenceTypeSignature::Array(Box::new(component))),
+            _ => Err(SignatureError::Unexpected(self.pos - 1)),
+        };
+        self.nesting -= 1;
+        result
+    }
+
+    /// Read a class type after its `L`
+    fn class_type(&mut self) -> Result<ClassTypeSignature<'a>, SignatureError> {
+        let mut classes = Vec::new();
+        loop {
+            // Only the outermost class has a package
+            let name = self.identifier(classes.is_empty())?;
+            let mut type_arguments = Vec::new();
+            if self.peek() == Some(b'<') {
+                self.pos += 1;
+                type_arguments = self.type_arguments()?;
+            }
+            classes.push(SimpleClassTypeSignature {
+                name: Cow::Borrowed(name),
+                type_arguments,
+            });
+
+            match self.next()? {
+                b'.' => {}
+                b';' => return Ok(ClassTypeSignature { classes }),
+                _ => return Err(SignatureError::Unexpected(self.pos - 1)),
+            }
+        }
+    }
+
+    /// Read type arguments after their `<`
+    fn type_arguments(&mut self) -> Result<Vec<TypeArgument<'a>>, SignatureError> {
+        let mut arguments = Vec::new();
+        loop {
+            let argument = match self.peek() {
+                Some(b'*') => {
+                    self.pos += 1;
+                    TypeArgument::Any
+                }
+                Some(b'+') => {
+                    self.pos += 1;
+                    TypeArgument::Extends(self.reference_type()?)
+                }
+                Some(b'-') => {
+                    self.pos += 1;
+                    TypeArgument::Super(self.reference_type()?)
+                }
+                _ => TypeArgument::Exact(self.reference_type()?),
+            };
+            arguments.push(argument);
+            if self.peek() == Some(b'>') {
+                self.pos += 1;
+                return Ok(arguments);
+            }
+        }
+    }
+
+    /// Read the type parameters, if there are any
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter<'a>>, SignatureError> {
+        let mut parameters = Vec::new();
+        if self.peek() != Some(b'<') {
+            return Ok(parameters);
+        }
+        self.pos += 1;
+
+        loop {
+            let name = self.identifier(false)?;
+            self.expect(b':')?;
+            // The class bound may be left out, leaving only interface bounds
+            let class_bound = match self.peek() {
+                Some(b'L' | b'T' | b'[') => Some(self.reference_type()?),
+                _ => None,
+            };
+            let mut interface_bounds = Vec::new();
+            while self.peek() == Some(b':') {
+                self.pos += 1;
+                interface_bounds.push(self.reference_type()?);
+            }
+            parameters.push(TypeParameter {
+                name: Cow::Borrowed(name),
+                class_bound,
+                interface_bounds,
+            });
+
+            if self.peek() == Some(b'>') {
+                self.pos += 1;
+                return Ok(parameters);
+            }
+        }
+    }
+
+    fn class_type_signature(&mut self) -> Result<ClassTypeSignature<'a>, SignatureError> {
+        self.expect(b'L')?;
+        self.class_type()
+    }
+}
+
+impl<'a> ClassSignature<'a> {
+    pub fn parse(text: &'a [u8]) -> Result<ClassSignature<'a>, SignatureError> {
+        let mut parser = Parser::new(text);
+        let type_parameters = parser.type_parameters()?;
+        let super_class = parser.class_type_signature()?;
+        let mut interfaces = Vec::new();
+        while parser.peek().is_some() {
+            interfaces.push(parser.class_type_signature()?);
+        }
+        Ok(ClassSignature {
+            type_parameters,
+            super_class,
+            interfaces,
+        })
+    }
+
+    /// Write the signature form of the class
+    pub fn write_signature(&self, out: &mut Vec<u8>) {
+        write_type_parameters(&self.type_parameters, out);
+        self.super_class.write_signature(out);
+        for interface in self.interfaces.iter() {
+            interface.write_signature(out);
+        }
+    }
+
+    pub fn to_signature(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_signature(&mut out);
+        out
+    }
+
+    pub fn to_owned<'b>(self) -> ClassSignature<'b> {
+        ClassSignature {
+            type_parameters: self
+                .type_parameters
+                .into_iter()
+                .map(TypeParameter::to_owned)
+                .collect(),
+            super_class: self.super_class.to_owned(),
+            interfaces: self
+                .interfaces
+                .into_iter()
+                .map(ClassTypeSignature::to_owned)
+                .collect(),
+        }
+    }
+}
+
+impl<'a> MethodSignature<'a> {
+    pub fn parse(text: &'a [u8]) -> Result<MethodSignature<'a>, SignatureError> {
+        let mut parser = Parser::new(text);
+        let type_parameters = parser.type_parameters()?;
+
+        parser.expect(b'(')?;
+        let mut parameter_types = Vec::new();
+        while parser.peek() != Some(b')') {
+            parameter_types.push(parser.java_type()?);
+        }
+        parser.pos += 1;
+
+        let return_type = if parser.peek() == Some(b'V') {
+            parser.pos += 1;
+            None
+        } else {
+            Some(parser.java_type()?)
+        };
+
+        let mut throws = Vec::new();
+        while parser.peek().is_some() {
+            parser.expect(b'^')?;
+            throws.push(parser.reference_type()?);
+        }
+        Ok(MethodSignature {
+            type_parameters,
+            parameter_types,
+            return_type,
+            throws,
+        })
+    }
+
+    /// Write the signature form of the method
+    pub fn write_signature(&self, out: &mut Vec<u8>) {
+        write_type_parameters(&self.type_parameters, out);
+        out.push(b'(');
+        for parameter in self.parameter_types.iter() {
+            parameter.write_signature(out);
+        }
+        out.push(b')');
+        match &self.return_type {
+            Some(return_type) => return_type.write_signature(out),
+            None => out.push(b'V'),
+        }
+        for throws in self.throws.iter() {
+            out.push(b'^');
+            throws.write_signature(out);
+        }
+    }
+
+    pub fn to_signature(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_signature(&mut out);
+        out
+    }
+
+    pub fn to_owned<'b>(self) -> MethodSignature<'b> {
+        MethodSignature {
+            type_parameters: self
+                .type_parameters
+                .into_iter()
+                .map(TypeParameter::to_owned)
+                .collect(),
+            parameter_types: self
+                .parameter_types
+                .into_iter()
+                .map(TypeSignature::to_owned)
+                .collect(),
+            return_type: self.return_type.map(TypeSignature::to_owned),
+            throws: self
+                .throws
+                .into_iter()
+                .map(ReferenceTypeSignature::to_owned)
+                .collect(),
+        }
+    }
+}
+
+fn write_type_parameters(parameters: &[TypeParameter], out: &mut Vec<u8>) {
+    if parameters.is_empty() {
+        return;
+    }
+    out.push(b'<');
+    for parameter in parameters {
+        out.extend_from_slice(&parameter.name);
+        out.push(b':');
+        if let Some(bound) = &parameter.class_bound {
+            bound.write_signature(out);
+        }
+        for bound in parameter.interface_bounds.iter() {
+            out.push(b':');
+            bound.write_signature(out);
+        }
+    }
+    out.push(b'>');
+}
+
+impl<'a> TypeSignature<'a> {
+    /// Parse a type which may be a primitive, with nothing after it
+    pub fn parse(text: &'a [u8]) -> Result<TypeSignature<'a>, SignatureError> {
+        let mut parser = Parser::new(text);
+        let typ = parser.java_type()?;
+        parser.finish()?;
+        Ok(typ)
+    }
+
+    pub fn write_signature(&self, out: &mut Vec<u8>) {
+        match self {
+            TypeSignature::Base(base) => out.push(base.to_char()),
+            TypeSignature::Reference(reference) => reference.write_signature(out),
+        }
+    }
+
+    pub fn to_owned<'b>(self) -> TypeSignature<'b> {
+        match self {
+            TypeSignature::Base(base) => TypeSignature::Base(base),
+            TypeSignature::Reference(reference) => TypeSignature::Reference(reference.to_owned()),
+        }
+    }
+}
+
+impl<'a> ReferenceTypeSignature<'a> {
+    /// Parse the signature of a field, which is a reference type with nothing after it
+    pub fn parse(text: &'a [u8]) -> Result<ReferenceTypeSignature<'a>, SignatureError> {
+        let mut parser = Parser::new(text);
+        let typ = parser.reference_type()?;
+        parser.finish()?;
+        Ok(typ)
+    }
+
+    pub fn write_signature(&self, out: &mut Vec<u8>) {
+        match self {
+            ReferenceTypeSignature::Class(class) => class.write_signature(out),
+            ReferenceTypeSignature::TypeVariable(name) => {
+                out.push(b'T');
+                out.extend_from_slice(name);
+                out.push(b';');
+            }
+            ReferenceTypeSignature::Array(component) => {
+                out.push(b'[');
+                component.write_signature(out);
+            }
+        }
+    }
+
+    pub fn to_signature(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_signature(&mut out);
+        out
+    }
+
+    pub fn to_owned<'b>(self) -> ReferenceTypeSignature<'b> {
+        match self {
+            ReferenceTypeSignature::Class(class) => ReferenceTypeSignature::Class(class.to_owned()),
+            ReferenceTypeSignature::TypeVariable(name) => {
+                ReferenceTypeSignature::TypeVariable(Cow::Owned(name.into_owned()))
+            }
+            ReferenceTypeSignature::Array(component) => {
+                ReferenceTypeSignature::Array(Box::new((*component).to_owned()))
+            }
+        }
+    }
+}
+
+impl<'a> ClassTypeSignature<'a> {
+    /// The binary name of the class that the type erases to, such as `java/util/Map$Entry`
+    pub fn binary_name(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (i, class) in self.classes.iter().enumerate() {
+            if i != 0 {
+                out.push(b'$');
+            }
+            out.extend_from_slice(&class.name);
+        }
+        out
+    }
+
+    pub fn write_signature(&self, out: &mut Vec<u8>) {
+        out.push(b'L');
+        for (i, class) in self.classes.iter().enumerate() {
+            if i != 0 {
+                out.push(b'.');
+            }
+            out.extend_from_slice(&class.name);
+            if !class.type_arguments.is_empty() {
+                out.push(b'<');
+                for argument in class.type_arguments.iter() {
+                    argument.write_signature(out);
+                }
+                out.push(b'>');
+            }
+        }
+        out.push(b';');
+    }
+
+    pub fn to_owned<'b>(self) -> ClassTypeSignature<'b> {
+        ClassTypeSignature {
+            classes: self
+                .classes
+                .into_iter()
+                .map(|class| SimpleClassTypeSignature {
+                    name: Cow::Owned(class.name.into_owned()),
+                    type_arguments: class
+                        .type_arguments
+                        .into_iter()
+                        .map(TypeArgument::to_owned)
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> TypeArgument<'a> {
+    pub fn write_signature(&self, out: &mut Vec<u8>) {
+        match self {
+            TypeArgument::Any => out.push(b'*'),
+            TypeArgument::Exact(typ) => typ.write_signature(out),
+            TypeArgument::Extends(typ) => {
+                out.push(b'+');
+                typ.write_signature(out);
+            }
+            TypeArgument::Super(typ) => {
+                out.push(b'-');
+                typ.write_signature(out);
+            }
+        }
+    }
+
+    pub fn to_owned<'b>(self) -> TypeArgument<'b> {
+        match self {
+            TypeArgument::Any => TypeArgument::Any,
+            TypeArgument::Exact(typ) => TypeArgument::Exact(typ.to_owned()),
+            TypeArgument::Extends(typ) => TypeArgument::Extends(typ.to_owned()),
+            TypeArgument::Super(typ) => TypeArgument::Super(typ.to_owned()),
+        }
+    }
+}
+
+impl<'a> TypeParameter<'a> {
+    pub fn to_owned<'b>(self) -> TypeParameter<'b> {
+        TypeParameter {
+            name: Cow::Owned(self.name.into_owned()),
+            class_bound: self.class_bound.map(ReferenceTypeSignature::to_owned),
+            interface_bounds: self
+                .interface_bounds
+                .into_iter()
+                .map(ReferenceTypeSignature::to_owned)
+                .collect(),
+        }
+    }
+}
+
+fn write_name(f: &mut std::fmt::Formatter<'_>, name: &[u8]) -> std::fmt::Result {
+    match std::str::from_utf8(name) {
+        Ok(name) => f.write_str(&name.replace('/', ".")),
+        Err(_) => f.write_str("[non-utf8 name]"),
+    }
+}
+
+fn write_list<T: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    items: &[T],
+    separator: &str,
+) -> std::fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i != 0 {
+            f.write_str(separator)?;
+        }
+        item.fmt(f)?;
+    }
+    Ok(())
+}
+
+/// Displays the type as it would be written in Java, such as `java.util.List<? extends T>[]`
+impl Display for TypeSignature<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeSignature::Base(base) => base.fmt(f),
+            TypeSignature::Reference(reference) => reference.fmt(f),
+        }
+    }
+}
+impl Display for ReferenceTypeSignature<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferenceTypeSignature::Class(class) => class.fmt(f),
+            ReferenceTypeSignature::TypeVariable(name) => write_name(f, name),
+            ReferenceTypeSignature::Array(component) => write!(f, "{}[]", component),
+        }
+    }
+}
+impl Display for ClassTypeSignature<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_list(f, &self.classes, ".")
+    }
+}
+impl Display for SimpleClassTypeSignature<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_name(f, &self.name)?;
+        if !self.type_arguments.is_empty() {
+            f.write_str("<")?;
+            write_list(f, &self.type_arguments, ", ")?;
+            f.write_str(">")?;
+        }
+        Ok(())
+    }
+}
+impl Display for TypeArgument<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeArgument::Any => f.write_str("?"),
+            TypeArgument::Exact(typ) => typ.fmt(f),
+            TypeArgument::Extends(typ) => write!(f, "? extends {}", typ),
+            TypeArgument::Super(typ) => write!(f, "? super {}", typ),
+        }
+    }
+}
+impl Display for TypeParameter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_name(f, &self.name)?;
+        let bounds: Vec<_> = self
+            .class_bound
+            .iter()
+            .chain(self.interface_bounds.iter())
+            .collect();
+        if !bounds.is_empty() {
+            f.write_str(" extends ")?;
+            write_list(f, &bounds, " & ")?;
+        }
+        Ok(())
+    }
+}
+
+impl SignatureAttribute {
+    fn text<'a>(&self, pool: &ConstantPool, class_file_data: &'a [u8]) -> Option<&'a [u8]> {
+        Some(pool.get_t(self.signature_index)?.as_bytes(class_file_data))
+    }
+
+    /// Parse the signature as that of a class.
+    /// Errors if the index is bad or the signature is malformed.
+    pub fn class_signature<'a>(
+        &self,
+        pool: &ConstantPool,
+        class_file_data: &'a [u8],
+    ) -> Result<ClassSignature<'a>, LoadError> {
+        let text = self.text(pool, class_file_data).ok_or(LoadError::Unknown)?;
+        ClassSignature::parse(text).map_err(|_| LoadError::Unknown)
+    }
+
+    /// Parse the signature as that of a method.
+    /// Errors if the index is bad or the signature is malformed.
+    pub fn method_signature<'a>(
+        &self,
+        pool: &ConstantPool,
+        class_file_data: &'a [u8],
+    ) -> Result<MethodSignature<'a>, LoadError> {
+        let text = self.text(pool, class_file_data).ok_or(LoadError::Unknown)?;
+        MethodSignature::parse(text).map_err(|_| LoadError::Unknown)
+    }
+
+    /// Parse the signature as that of a field or record component.
+    /// Errors if the index is bad or the signature is malformed.
+    pub fn field_signature<'a>(
+        &self,
+        pool: &ConstantPool,
+        class_file_data: &'a [u8],
+    ) -> Result<ReferenceTypeSignature<'a>, LoadError> {
+        let text = self.text(pool, class_file_data).ok_or(LoadError::Unknown)?;
+        ReferenceTypeSignature::parse(text).map_err(|_| LoadError::Unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{
+        BaseType, ClassSignature, MethodSignature, ReferenceTypeSignature, SignatureError,
+        TypeArgument, TypeSignature,
+    };
+
+    #[test]
+    fn parsing() {
+        let typ = ReferenceTypeSignature::parse(b"Ljava/util/Map<TK;+[I>.Entry<*-TV;>;").unwrap();
+        let class = match &typ {
+            ReferenceTypeSignature::Class(class) => class,
+            _ => panic!("not a class type"),
+        };
+        assert_eq!(class.classes.len(), 2);
+        assert_eq!(class.classes[0].name, Cow::Borrowed(&b"java/util/Map"[..]));
+        assert_eq!(
+            class.classes[0].type_arguments,
+            [
+                TypeArgument::Exact(ReferenceTypeSignature::TypeVariable(Cow::Borrowed(b"K"))),
+                TypeArgument::Extends(ReferenceTypeSignature::Array(Box::new(
+                    TypeSignature::Base(BaseType::Int)
+                ))),
+            ]
+        );
+        assert_eq!(class.binary_name(), b"java/util/Map$Entry");
+        assert_eq!(
+            typ.to_string(),
+            "java.util.Map<K, ? extends int[]>.Entry<?, ? super V>"
+        );
+
+        let method =
+            MethodSignature::parse(b"<T::Ljava/lang/Runnable;>([TT;J)V^TE;^Ljava/io/IOException;")
+                .unwrap();
+        assert_eq!(method.type_parameters.len(), 1);
+        assert_eq!(method.type_parameters[0].class_bound, None);
+        assert_eq!(
+            method.type_parameters[0].to_string(),
+            "T extends java.lang.Runnable"
+        );
+        assert_eq!(method.parameter_types.len(), 2);
+        assert_eq!(method.return_type, None);
+        assert_eq!(method.throws.len(), 2);
+
+        let class = ClassSignature::parse(
+            b"<T:Ljava/lang/Number;U:Ljava/lang/Object;>Ljava/lang/Object;Ljava/lang/Comparable<TT;>;",
+        )
+        .unwrap();
+        assert_eq!(class.type_parameters.len(), 2);
+        assert_eq!(class.interfaces.len(), 1);
+
+        assert_eq!(
+            ReferenceTypeSignature::parse(b"I"),
+            Err(SignatureError::Unexpected(0))
+        );
+        assert_eq!(
+            ReferenceTypeSignature::parse(b"Ljava/lang/Object;I"),
+            Err(SignatureError::RemainingData(18))
+        );
+        assert_eq!(
+            ReferenceTypeSignature::parse(b"Ljava/util/List<"),
+            Err(SignatureError::UnexpectedEnd)
+        );
+        assert_eq!(
+            ReferenceTypeSignature::parse(b"T;"),
+            Err(SignatureError::EmptyIdentifier(1))
+        );
+        assert_eq!(
+            MethodSignature::parse(b"()V^I"),
+            Err(SignatureError::Unexpected(4))
+        );
+        let deep = [b'['; 1000];
+        assert_eq!(TypeSignature::parse(&deep), Err(SignatureError::TooDeep));
+    }
+
+    #[test]
+    fn round_trip() {
+        for text in [
+            &b"<T:Ljava/lang/Object;>(TT;[[ILjava/util/List<+TT;>;)Ljava/util/Map<TT;*>.Entry<-TT;>;^TE;"[..],
+            b"()V",
+            b"(IJ)[Ljava/lang/String;",
+        ] {
+            assert_eq!(MethodSignature::parse(text).unwrap().to_signature(), text);
+        }
+        for text in [
+            &b"<T::Ljava/lang/Runnable;:Ljava/io/Closeable;>Ljava/lang/Object;"[..],
+            b"Ljava/lang/Enum<LFoo;>;Ljava/lang/Comparable<LFoo;>;",
+        ] {
+            assert_eq!(ClassSignature::parse(text).unwrap().to_signature(), text);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, fmt::Display, num::NonZeroUsize};
+use std::{borrow::Cow, fmt::Display, num::NonZeroUsize, str::Utf8Error};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DescriptorTypeError {
@@ -14,6 +14,20 @@ pub enum DescriptorTypeError {
     TooManyNestedArrays,
 }
 
+/// A class name which is not valid UTF-8.
+/// Display shows a placeholder for these, so this keeps the actual bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonUtf8ClassName<'a> {
+    pub name: &'a [u8],
+    pub error: Utf8Error,
+}
+impl Display for NonUtf8ClassName<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "class name is not valid utf8: {}", self.error)
+    }
+}
+impl std::error::Error for NonUtf8ClassName<'_> {}
+
 /// Non-recursive types for descriptor type
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DescriptorTypeBasic<'a> {
@@ -60,6 +74,24 @@ impl<'a> DescriptorType<'a> {
         )
     }
 
+    /// Write the descriptor form of the type, such as `[[I`
+    pub fn write_descriptor(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Basic(x) => x.write_descriptor(out),
+            Self::Array { level, component } => {
+                out.resize(out.len() + level.get(), b'[');
+                component.write_descriptor(out);
+            }
+        }
+    }
+
+    /// Get the descriptor form of the type
+    pub fn to_descriptor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_descriptor(&mut out);
+        out
+    }
+
     pub fn to_owned<'b>(self) -> DescriptorType<'b> {
         match self {
             Self::Basic(x) => DescriptorType::Basic(x.to_owned()),
@@ -70,6 +102,15 @@ impl<'a> DescriptorType<'a> {
         }
     }
 
+    /// Whether the type is a long or double, which take up two local variable slots and count as
+    /// two parameters
+    pub fn is_wide(&self) -> bool {
+        matches!(
+            self,
+            Self::Basic(DescriptorTypeBasic::Long) | Self::Basic(DescriptorTypeBasic::Double)
+        )
+    }
+
     pub fn parse(
         mut text: &'a [u8],
     ) -> Result<(DescriptorType<'a>, &'a [u8]), DescriptorTypeError> {
@@ -159,7 +200,7 @@ impl Display for DescriptorTypeBasic<'_> {
             DescriptorTypeBasic::Int => f.write_str("int"),
             DescriptorTypeBasic::Long => f.write_str("long"),
             DescriptorTypeBasic::ClassName(path) => {
-                if let Ok(path) = std::str::from_utf8(&path) {
+                if let Ok(path) = std::str::from_utf8(path) {
                     f.write_str(path)
                 } else {
                     f.write_str("[non-utf8 class name]")
@@ -185,7 +226,77 @@ impl Display for DescriptorType<'_> {
     }
 }
 
+impl DescriptorType<'_> {
+    /// The raw bytes of the class name, if this is a class or an array of a class
+    pub fn class_name(&self) -> Option<&[u8]> {
+        match self {
+            Self::Basic(x) => x.class_name(),
+            Self::Array { component, .. } => component.class_name(),
+        }
+    }
+
+    /// The same text as Display, but erroring with the bytes of a class name which is not valid
+    /// UTF-8 rather than using a placeholder
+    pub fn try_to_string(&self) -> Result<String, NonUtf8ClassName<'_>> {
+        match self {
+            Self::Basic(x) => x.to_str().map(str::to_owned),
+            Self::Array { level, component } => {
+                let mut text = component.to_str()?.to_owned();
+                for _ in 0..level.get() {
+                    text.push_str("[]");
+                }
+                Ok(text)
+            }
+        }
+    }
+}
+
 impl<'a> DescriptorTypeBasic<'a> {
+    /// The raw bytes of the class name, if this is a class
+    pub fn class_name(&self) -> Option<&[u8]> {
+        match self {
+            DescriptorTypeBasic::ClassName(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The same text as Display, but erroring with the bytes of a class name which is not valid
+    /// UTF-8 rather than using a placeholder
+    pub fn to_str(&self) -> Result<&str, NonUtf8ClassName<'_>> {
+        match self {
+            DescriptorTypeBasic::ClassName(name) => {
+                std::str::from_utf8(name).map_err(|error| NonUtf8ClassName { name, error })
+            }
+            DescriptorTypeBasic::Byte => Ok("byte"),
+            DescriptorTypeBasic::Char => Ok("char"),
+            DescriptorTypeBasic::Double => Ok("double"),
+            DescriptorTypeBasic::Float => Ok("float"),
+            DescriptorTypeBasic::Int => Ok("int"),
+            DescriptorTypeBasic::Long => Ok("long"),
+            DescriptorTypeBasic::Short => Ok("short"),
+            DescriptorTypeBasic::Boolean => Ok("boolean"),
+        }
+    }
+
+    /// Write the descriptor form of the type, such as `I` or `Ljava/lang/String;`
+    pub fn write_descriptor(&self, out: &mut Vec<u8>) {
+        match self {
+            DescriptorTypeBasic::Byte => out.push(b'B'),
+            DescriptorTypeBasic::Char => out.push(b'C'),
+            DescriptorTypeBasic::Double => out.push(b'D'),
+            DescriptorTypeBasic::Float => out.push(b'F'),
+            DescriptorTypeBasic::Int => out.push(b'I'),
+            DescriptorTypeBasic::Long => out.push(b'J'),
+            DescriptorTypeBasic::ClassName(name) => {
+                out.push(b'L');
+                out.extend_from_slice(name);
+                out.push(b';');
+            }
+            DescriptorTypeBasic::Short => out.push(b'S'),
+            DescriptorTypeBasic::Boolean => out.push(b'Z'),
+        }
+    }
+
     pub fn to_owned<'b>(self) -> DescriptorTypeBasic<'b> {
         match self {
             DescriptorTypeBasic::ClassName(x) => {
@@ -331,4 +442,29 @@ mod tests {
         );
         Ok(())
     }
+    #[test]
+    fn non_utf8_class_names() {
+        let name: &[u8] = b"a/\xFF";
+        let basic = DescriptorTypeBasic::ClassName(Cow::Borrowed(name));
+        assert_eq!(basic.to_string(), "[non-utf8 class name]");
+        assert_eq!(basic.class_name(), Some(name));
+        let err = basic.to_str().unwrap_err();
+        assert_eq!(err.name, name);
+        assert_eq!(err.error.valid_up_to(), 2);
+
+        let array = DescriptorType::Array {
+            level: NonZeroUsize::new(2).unwrap(),
+            component: basic,
+        };
+        assert_eq!(array.class_name(), Some(name));
+        assert_eq!(array.try_to_string().unwrap_err().name, name);
+
+        let valid = DescriptorType::Array {
+            level: NonZeroUsize::new(1).unwrap(),
+            component: DescriptorTypeBasic::ClassName(Cow::Borrowed(b"a/B")),
+        };
+        assert_eq!(valid.try_to_string().unwrap(), "a/B[]");
+        assert_eq!(DescriptorTypeBasic::Int.to_str(), Ok("int"));
+        assert_eq!(DescriptorTypeBasic::Int.class_name(), None);
+    }
 }
@@ -0,0 +1,125 @@
+//! Cryptographic digests of class files, for inventories of the classes in an archive.
+//!
+//! Each class gets two digests: one of its raw bytes, which changes whenever the file does, and
+//! one of its structure, which only covers what the class declares. Recompiling a class without
+//! changing its declarations, or compiling it with another compiler, changes the constant pool
+//! layout and the code but usually keeps the structural digest the same.
+
+use std::fmt;
+
+use md5::Md5;
+use sha2::{Digest as _, Sha256};
+
+use crate::constant_info::{ClassConstant, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::{ClassFile, LoadError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Md5,
+}
+impl DigestAlgorithm {
+    fn hash(self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            DigestAlgorithm::Sha256 => Sha256::digest(bytes).to_vec(),
+            DigestAlgorithm::Md5 => Md5::digest(bytes).to_vec(),
+        }
+    }
+}
+
+/// The bytes of a digest, which display as lowercase hex
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Digest(pub Vec<u8>);
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassDigest {
+    pub algorithm: DigestAlgorithm,
+    /// The digest of the class file's bytes
+    pub raw: Digest,
+    /// The digest of the class's access flags and names, its superclass and interfaces, and the
+    /// access flags, names and descriptors of its fields and methods, in the order they are
+    /// declared. The version, the constant pool layout, the code, and other attributes don't
+    /// affect it.
+    pub structural: Digest,
+}
+
+/// Writes the parts of the structure in a form where no two structures give the same bytes
+struct Structure<'a> {
+    pool: &'a ConstantPool,
+    data: &'a [u8],
+    out: Vec<u8>,
+}
+impl<'a> Structure<'a> {
+    fn u16(&mut self, value: u16) {
+        self.out.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn utf8(&mut self, index: ConstantPoolIndexRaw<Utf8Constant>) -> Result<(), LoadError> {
+        let text = self.pool.get_t(index).ok_or(LoadError::Unknown)?;
+        let bytes = text.as_bytes(self.data);
+        self.out
+            .extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        self.out.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Write the name of the class, where a zero index is written as a name of length zero
+    fn class(&mut self, index: ConstantPoolIndexRaw<ClassConstant>) -> Result<(), LoadError> {
+        if index.is_zero() {
+            self.out.extend_from_slice(&0u32.to_be_bytes());
+            return Ok(());
+        }
+        let class = self.pool.get_t(index).ok_or(LoadError::Unknown)?;
+        self.utf8(class.name_index)
+    }
+}
+
+impl ClassFile {
+    /// Compute the digests of the raw bytes and of the structure of the class.
+    /// Errors if a name that the structure includes is missing from the constant pool.
+    pub fn digest(
+        &self,
+        data: &[u8],
+        algorithm: DigestAlgorithm,
+    ) -> Result<ClassDigest, LoadError> {
+        let mut structure = Structure {
+            pool: &self.const_pool,
+            data,
+            out: Vec::new(),
+        };
+        structure.u16(self.raw_flags());
+        structure.class(self.this_class)?;
+        structure.class(self.super_class)?;
+        structure.u16(self.interfaces.len() as u16);
+        for &interface in self.interfaces.iter() {
+            structure.class(interface)?;
+        }
+        structure.u16(self.fields.len() as u16);
+        for field in self.fields.iter() {
+            structure.u16(field.raw_flags());
+            structure.utf8(field.name_index)?;
+            structure.utf8(field.descriptor_index)?;
+        }
+        structure.u16(self.methods.len() as u16);
+        for method in self.methods.iter() {
+            structure.u16(method.raw_flags());
+            structure.utf8(method.name_index)?;
+            structure.utf8(method.descriptor_index)?;
+        }
+
+        Ok(ClassDigest {
+            algorithm,
+            raw: Digest(algorithm.hash(data)),
+            structural: Digest(algorithm.hash(&structure.out)),
+        })
+    }
+}
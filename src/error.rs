@@ -0,0 +1,198 @@
+use crate::constant_info::is_constant_tag;
+use crate::parser::ParseData;
+use crate::scan;
+
+/// The kinds of item in the tables of a class file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Constant,
+    Interface,
+    Field,
+    Method,
+    /// An attribute of the class itself
+    Attribute,
+}
+impl std::fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ItemKind::Constant => "constant",
+            ItemKind::Interface => "interface",
+            ItemKind::Field => "field",
+            ItemKind::Method => "method",
+            ItemKind::Attribute => "attribute",
+        })
+    }
+}
+
+/// Which item of a table in the class file something is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemLocation {
+    pub kind: ItemKind,
+    /// The index of the item in its table.
+    /// For constants this is the index into the constant pool, which starts at 1.
+    pub index: usize,
+    /// The offset that the item starts at
+    pub start: usize,
+}
+
+/// Why part of a class file could not be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Malformation {
+    /// The data ended in the middle of the item
+    Truncated,
+    /// A constant had a tag which is not one of the defined constant kinds
+    InvalidConstantTag(u8),
+    /// Some value was invalid, such as a length or count that does not fit its item
+    Invalid,
+}
+impl std::fmt::Display for Malformation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Malformation::Truncated => f.write_str("data ends early"),
+            Malformation::InvalidConstantTag(tag) => write!(f, "invalid constant tag {}", tag),
+            Malformation::Invalid => f.write_str("invalid data"),
+        }
+    }
+}
+
+/// An error from parsing a class file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The class file could not be read
+    Io {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+    /// The data did not start with `0xCAFEBABE`
+    BadMagic,
+    /// The `constant_pool_count` was 0, when it is always at least 1 as index 0 is never used
+    EmptyConstantPool,
+    /// The data could not be parsed starting at the given offset.
+    /// The item is the constant, interface, field, method, or class attribute that the offset is
+    /// inside, if it is inside one.
+    Malformed {
+        offset: usize,
+        item: Option<ItemLocation>,
+        reason: Malformation,
+    },
+    /// There was data after the end of the class file, see
+    /// [`crate::parser::ParseOptions::reject_trailing_bytes`]
+    TrailingBytes { offset: usize, len: usize },
+    /// A predefined attribute appeared before the class file version that introduced it, see
+    /// [`crate::parser::ParseOptions::reject_attributes_before_version`]
+    AttributeBeforeVersion {
+        name: String,
+        offset: usize,
+        min_major: u16,
+    },
+    /// An annotation or signature was nested deeper than allowed, see
+    /// [`crate::parser::ParseOptions::max_nesting_depth`].
+    /// The offset is where the nesting went too deep in an annotation, or the start of the
+    /// Signature attribute for a signature.
+    TooDeeplyNested { offset: usize, max_depth: usize },
+}
+impl ParseError {
+    /// Find the item that a malformed offset is inside of, see [`crate::scan::item_at`]
+    pub(crate) fn with_item(self, data: &[u8]) -> ParseError {
+        match self {
+            ParseError::Malformed {
+                offset,
+                item: None,
+                reason,
+            } => {
+                let item = scan::item_at(data, offset);
+                let reason = match (item, reason) {
+                    // The tag is all that is checked before the rest of the constant is parsed
+                    (Some(item), Malformation::Invalid) if item.kind == ItemKind::Constant => {
+                        match data.get(item.start) {
+                            Some(&tag) if !is_constant_tag(tag) => {
+                                Malformation::InvalidConstantTag(tag)
+                            }
+                            _ => reason,
+                        }
+                    }
+                    _ => reason,
+                };
+                ParseError::Malformed {
+                    offset,
+                    item,
+                    reason,
+                }
+            }
+            err => err,
+        }
+    }
+}
+impl From<std::io::Error> for ParseError {
+    fn from(err: std::io::Error) -> Self {
+        ParseError::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+impl<'a> From<nom::Err<nom::error::Error<ParseData<'a>>>> for ParseError {
+    fn from(err: nom::Err<nom::error::Error<ParseData<'a>>>) -> Self {
+        match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => ParseError::Malformed {
+                offset: e.input.pos(),
+                item: None,
+                reason: match e.code {
+                    nom::error::ErrorKind::Eof => Malformation::Truncated,
+                    _ => Malformation::Invalid,
+                },
+            },
+            // We only use complete parsers, so this only happens if a parser was misused
+            nom::Err::Incomplete(_) => ParseError::Malformed {
+                offset: 0,
+                item: None,
+                reason: Malformation::Truncated,
+            },
+        }
+    }
+}
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Io { message, .. } => write!(f, "could not read class file: {}", message),
+            ParseError::BadMagic => f.write_str("not a class file, bad magic"),
+            ParseError::EmptyConstantPool => f.write_str("constant pool count of 0"),
+            ParseError::Malformed {
+                offset,
+                item: None,
+                reason,
+            } => write!(
+                f,
+                "malformed class file at offset {:#x}: {}",
+                offset, reason
+            ),
+            ParseError::Malformed {
+                offset,
+                item: Some(item),
+                reason,
+            } => write!(
+                f,
+                "malformed {} {} at {:#x}, at offset {:#x}: {}",
+                item.kind, item.index, item.start, offset, reason
+            ),
+            ParseError::TrailingBytes { offset, len } => {
+                write!(f, "{} trailing bytes at offset {:#x}", len, offset)
+            }
+            ParseError::AttributeBeforeVersion {
+                name,
+                offset,
+                min_major,
+            } => write!(
+                f,
+                "{} attribute at offset {:#x} requires class file version {}",
+                name, offset, min_major
+            ),
+            ParseError::TooDeeplyNested { offset, max_depth } => write!(
+                f,
+                "nested deeper than {} levels at offset {:#x}",
+                max_depth, offset
+            ),
+        }
+    }
+}
+impl std::error::Error for ParseError {}
@@ -1,5 +1,6 @@
 mod parser;
 mod types;
 
-pub use self::parser::{field_parser, skip_field_parser, field_opt_parser, field_opt_value_parser};
+pub(crate) use self::parser::field_opt_value_parser_with;
+pub use self::parser::{field_opt_parser, field_opt_value_parser, field_parser, skip_field_parser};
 pub use self::types::*;
@@ -1,18 +1,20 @@
 use nom::number::complete::be_u16;
 use nom::IResult;
 
-use crate::attribute_info::{attribute_parser, skip_attribute_parser, constant_value_attribute_parser};
+use crate::attribute_info::{attribute_parser, skip_attribute_parser, constant_value_attribute_parser, names};
 
 use crate::constant_info::ConstantInfo;
-use crate::constant_pool::{ConstantPoolIndexRaw, ConstantPool};
+use crate::constant_pool::{ConstantPoolIndexRaw, ConstantPool, PoolRef};
 use crate::field_info::{FieldAccessFlags, FieldInfo};
 
-use crate::method_info::attributes_search_parser;
+use crate::method_info::attributes_search_by;
+use crate::parser::combinators::{count_sv, skip_count};
 use crate::parser::ParseData;
-use crate::util::{constant_pool_index_raw, count_sv, skip_count};
+use crate::util::constant_pool_index_raw;
 
 use super::FieldInfoOpt;
 
+/// Skip over a field and its attributes
 pub fn skip_field_parser(i: ParseData) -> IResult<ParseData, ()> {
     let (i, _) = be_u16(i)?;
     let (i, _) = constant_pool_index_raw::<ConstantInfo>(i)?;
@@ -32,6 +34,7 @@ pub fn field_parser(i: ParseData) -> IResult<ParseData, FieldInfo> {
         i,
         FieldInfo {
             access_flags: FieldAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
             name_index,
             descriptor_index,
             attributes_count,
@@ -51,6 +54,7 @@ pub fn field_opt_parser(i: ParseData) -> IResult<ParseData, FieldInfoOpt> {
         i,
         FieldInfoOpt {
             access_flags: FieldAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
             name_index,
             descriptor_index,
             attributes_count,
@@ -61,12 +65,23 @@ pub fn field_opt_parser(i: ParseData) -> IResult<ParseData, FieldInfoOpt> {
 /// Parse the field opt and search for a constant value initializer, returning the index to that as
 /// well, if it exists.
 pub fn field_opt_value_parser<'a>(i: ParseData<'a>, class_file_data: &'a [u8], constant_pool: &ConstantPool) -> IResult<ParseData<'a>, (FieldInfoOpt, Option<ConstantPoolIndexRaw<ConstantInfo>>)> {
+    field_opt_value_parser_with(i, class_file_data, PoolRef::Loaded(constant_pool))
+}
+
+/// Like [`field_opt_value_parser`], but also works with a lazy constant pool
+pub(crate) fn field_opt_value_parser_with<'a>(
+    i: ParseData<'a>,
+    class_file_data: &'a [u8],
+    constant_pool: PoolRef,
+) -> IResult<ParseData<'a>, (FieldInfoOpt, Option<ConstantPoolIndexRaw<ConstantInfo>>)> {
     let (i, access_flags) = be_u16(i)?;
     let (i, name_index) = constant_pool_index_raw(i)?;
     let (i, descriptor_index) = constant_pool_index_raw(i)?;
     let (i, attributes_count) = be_u16(i)?;
     let before_attr_i = i.clone();
-    let (_, attr) = attributes_search_parser(i, class_file_data, constant_pool, "ConstantValue", attributes_count)?;
+    let (_, attr) = attributes_search_by(i, attributes_count, |name_index| {
+        constant_pool.is_utf8(name_index, class_file_data, names::CONSTANT_VALUE)
+    })?;
 
     let attr = if let Some((_, info_range)) = attr {
         let i = ParseData::from_range(class_file_data, info_range);
@@ -83,6 +98,7 @@ pub fn field_opt_value_parser<'a>(i: ParseData<'a>, class_file_data: &'a [u8], c
         i,
         (FieldInfoOpt {
             access_flags: FieldAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
             name_index,
             descriptor_index,
             attributes_count,
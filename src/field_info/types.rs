@@ -1,24 +1,48 @@
 use smallvec::SmallVec;
 
-use crate::attribute_info::AttributeInfo;
+use crate::attribute_info::{AttributeInfo, HasAttributes};
 
+use crate::util::AccessFlags;
 use crate::{constant_info::Utf8Constant, constant_pool::ConstantPoolIndexRaw};
 
 #[derive(Clone, Debug)]
 pub struct FieldInfo {
     pub access_flags: FieldAccessFlags,
+    /// The access flags as they were in the class file, including bits which have no defined meaning
+    pub raw_access_flags: u16,
     pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
     pub descriptor_index: ConstantPoolIndexRaw<Utf8Constant>,
     pub attributes_count: u16,
     pub attributes: SmallVec<[AttributeInfo; 2]>,
 }
 
+impl FieldInfo {
+    /// The access flags as a raw value, including any bits that have no defined meaning
+    pub fn raw_flags(&self) -> u16 {
+        self.access_flags.with_undefined(self.raw_access_flags)
+    }
+}
+
+impl HasAttributes for FieldInfo {
+    fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FieldInfoOpt {
     pub access_flags: FieldAccessFlags,
+    /// The access flags as they were in the class file, including bits which have no defined meaning
+    pub raw_access_flags: u16,
     pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
     pub descriptor_index: ConstantPoolIndexRaw<Utf8Constant>,
-    pub attributes_count: u16
+    pub attributes_count: u16,
+}
+impl FieldInfoOpt {
+    /// The access flags as a raw value, including any bits that have no defined meaning
+    pub fn raw_flags(&self) -> u16 {
+        self.access_flags.with_undefined(self.raw_access_flags)
+    }
 }
 
 bitflags! {
@@ -35,3 +59,10 @@ bitflags! {
         const ENUM = 0x4000;       // 	Declared as an element of an enum.
     }
 }
+impl AccessFlags for FieldAccessFlags {
+    const DEFINED: u16 = FieldAccessFlags::all().bits();
+
+    fn defined_bits(self) -> u16 {
+        self.bits()
+    }
+}
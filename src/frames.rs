@@ -0,0 +1,995 @@
+//! Computing the StackMapTable of code, by inferring the types of its locals and operand stack.
+//!
+//! Classes from version 50 are checked by the type checking verifier, which needs a frame at every
+//! branch target, exception handler, and instruction after an unconditional jump. Transforms which
+//! write new code, such as inlining subroutines, use this to give the code its frames again.
+//!
+//! Where paths with different classes in the same slot meet, the frame holds their closest common
+//! superclass, which is found by loading the classes from a [`ClassProvider`]. Interfaces are
+//! treated like `java/lang/Object`, as the verifier does.
+//! [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.10.1)
+
+use std::collections::{HashMap, HashSet};
+
+use crate::attribute_info::{
+    ExceptionEntry, FrameState, StackMapError, StackMapTableAttribute, VerificationTypeInfo,
+};
+use crate::builder::ConstantPoolBuilder;
+use crate::constant_info::{
+    resolve_ldc, resolve_member_ref, ClassConstant, LdcKind, LoadableConstant,
+};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::descriptor::{DescriptorType, ParsedDescriptor};
+use crate::instructions::{code_iter, Instruction, InstructionError, WideInstruction};
+use crate::method_info::{MethodAccessFlags, MethodInfo};
+use crate::names::{CLASS, INIT, OBJECT, STRING, THROWABLE};
+use crate::provider::ClassProvider;
+use crate::ClassFile;
+
+/// The type of a local or stack slot, like a [`VerificationTypeInfo`] but with classes given by
+/// their internal name rather than by an entry in the constant pool
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FrameType {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    /// An object created by the `new` instruction at the offset, whose constructor hasn't been
+    /// called yet
+    Uninitialized(u16),
+    /// An object of the class, or an array with the descriptor such as `[I`
+    Object(String),
+}
+impl FrameType {
+    /// Whether the type takes up two slots, which are a long or double followed by a Top
+    fn is_wide(&self) -> bool {
+        matches!(self, FrameType::Long | FrameType::Double)
+    }
+
+    fn is_reference(&self) -> bool {
+        matches!(self, FrameType::Null | FrameType::Object(_))
+    }
+
+    /// The type of a value with the field descriptor
+    fn of_descriptor(ty: &DescriptorType) -> FrameType {
+        let descriptor = ty.to_descriptor();
+        match descriptor[0] {
+            b'F' => FrameType::Float,
+            b'J' => FrameType::Long,
+            b'D' => FrameType::Double,
+            b'L' => FrameType::Object(
+                String::from_utf8_lossy(&descriptor[1..descriptor.len() - 1]).into_owned(),
+            ),
+            b'[' => FrameType::Object(String::from_utf8_lossy(&descriptor).into_owned()),
+            _ => FrameType::Integer,
+        }
+    }
+}
+
+/// The types at an offset in the code, where longs and doubles take one entry like in a
+/// StackMapTable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub offset: u16,
+    pub locals: Vec<FrameType>,
+    pub stack: Vec<FrameType>,
+}
+
+/// The frames that a method's code needs, see [`compute_frames`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frames {
+    /// The locals implied by the method's descriptor, which come before the first frame
+    pub initial_locals: Vec<FrameType>,
+    /// The frames in order of their offsets
+    pub frames: Vec<Frame>,
+}
+impl Frames {
+    /// Build the StackMapTable of the frames, see [`StackMapTableAttribute::from_states`].
+    /// A Class entry is added to the pool for each class named by the frames or initial locals
+    /// which it doesn't have yet, with the names appended to `data`. On error, some entries may
+    /// already have been added.
+    pub fn to_stack_map_table(
+        &self,
+        pool: &mut ConstantPool,
+        data: &mut Vec<u8>,
+    ) -> Result<StackMapTableAttribute, FrameError> {
+        // Without frames, the initial locals don't need their classes
+        if self.frames.is_empty() {
+            return Ok(StackMapTableAttribute {
+                number_of_entries: 0,
+                entries: Vec::new(),
+            });
+        }
+        ConstantPoolBuilder::with_pool(pool, data, |constants| {
+            let mut types = |types: &[FrameType]| -> Result<Vec<VerificationTypeInfo>, FrameError> {
+                types
+                    .iter()
+                    .map(|ty| {
+                        Ok(match ty {
+                            FrameType::Top => VerificationTypeInfo::Top,
+                            FrameType::Integer => VerificationTypeInfo::Integer,
+                            FrameType::Float => VerificationTypeInfo::Float,
+                            FrameType::Long => VerificationTypeInfo::Long,
+                            FrameType::Double => VerificationTypeInfo::Double,
+                            FrameType::Null => VerificationTypeInfo::Null,
+                            FrameType::UninitializedThis => VerificationTypeInfo::UninitializedThis,
+                            &FrameType::Uninitialized(offset) => {
+                                VerificationTypeInfo::Uninitialized { offset }
+                            }
+                            FrameType::Object(name) => VerificationTypeInfo::Object {
+                                class: constants.class(name).map_err(|_| FrameError::PoolFull)?,
+                            },
+                        })
+                    })
+                    .collect()
+            };
+
+            let initial_locals = types(&self.initial_locals)?;
+            let mut states = Vec::with_capacity(self.frames.len());
+            for frame in self.frames.iter() {
+                states.push(FrameState {
+                    offset: frame.offset,
+                    locals: types(&frame.locals)?,
+                    stack: types(&frame.stack)?,
+                });
+            }
+            StackMapTableAttribute::from_states(&initial_locals, &states)
+                .map_err(FrameError::StackMap)
+        })
+    }
+}
+
+/// Why the frames of some code couldn't be computed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    Instruction(InstructionError),
+    /// The method's descriptor couldn't be read
+    InvalidDescriptor,
+    /// The instruction at the offset refers to a constant of the wrong kind, or to a member with
+    /// a descriptor that can't be parsed
+    InvalidConstant {
+        offset: usize,
+    },
+    /// The instruction at the offset pops more than is on the stack
+    Underflow {
+        offset: usize,
+    },
+    /// The instruction at the offset uses a local past the method's `max_locals`
+    InvalidLocal {
+        offset: usize,
+    },
+    /// The instruction at the offset is reached with stacks that can't be merged
+    InconsistentStack {
+        offset: usize,
+    },
+    /// A branch or switch target which isn't the start of an instruction in the code
+    BadTarget {
+        offset: usize,
+        target: i64,
+    },
+    /// An exception handler which isn't the start of an instruction in the code
+    BadHandler {
+        handler_pc: u16,
+    },
+    /// The instruction at the offset is the last, and execution can continue past it
+    FallsOffEnd {
+        offset: usize,
+    },
+    /// The instruction at the offset calls or returns from a subroutine, which frames can't
+    /// describe, so the subroutines have to be inlined first
+    Subroutine {
+        offset: usize,
+    },
+    /// The instruction at the offset can't be reached, so there are no types for its frame
+    Unreachable {
+        offset: usize,
+    },
+    /// The class, or one of its superclasses, isn't available from the provider
+    UnknownClass(String),
+    /// The constant pool has no room for a class that a frame refers to
+    PoolFull,
+    StackMap(StackMapError),
+}
+
+/// The types of every slot of the locals and the stack before an instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct State {
+    locals: Vec<FrameType>,
+    stack: Vec<FrameType>,
+}
+
+/// Turn slots into the entries of a frame, where a long or double is one entry
+fn entries(slots: &[FrameType]) -> Vec<FrameType> {
+    let mut out = Vec::with_capacity(slots.len());
+    let mut i = 0;
+    while i < slots.len() {
+        out.push(slots[i].clone());
+        i += if slots[i].is_wide() { 2 } else { 1 };
+    }
+    out
+}
+
+/// The locals of a frame, without the Top slots at the end
+fn local_entries(slots: &[FrameType]) -> Vec<FrameType> {
+    let mut out = entries(slots);
+    while out.last() == Some(&FrameType::Top) {
+        out.pop();
+    }
+    out
+}
+
+/// The internal name of an array's element class, or the descriptor of an element array
+fn element_class(descriptor: &str) -> Option<&str> {
+    match descriptor.strip_prefix('[')? {
+        element if element.starts_with('[') => Some(element),
+        element => element.strip_prefix('L')?.strip_suffix(';'),
+    }
+}
+
+/// The descriptor of an array of the class, which is itself a descriptor if it is an array
+fn array_of(class: &str) -> String {
+    if class.starts_with('[') {
+        format!("[{}", class)
+    } else {
+        format!("[L{};", class)
+    }
+}
+
+struct Analysis<'a> {
+    class_file: &'a ClassFile,
+    data: &'a [u8],
+    provider: &'a dyn ClassProvider,
+    this_name: String,
+    max_locals: usize,
+    /// The class of the object created by the `new` at each offset
+    new_classes: HashMap<u16, String>,
+}
+impl<'a> Analysis<'a> {
+    fn pool(&self) -> &'a ConstantPool {
+        &self.class_file.const_pool
+    }
+
+    fn class_name(&self, index: ConstantPoolIndexRaw<ClassConstant>) -> Option<String> {
+        Some(self.pool().get_class_name(index, self.data)?.into_owned())
+    }
+
+    /// The superclass of the class, or None for `java/lang/Object`
+    fn superclass(&self, name: &str) -> Result<Option<String>, FrameError> {
+        let unknown = || FrameError::UnknownClass(name.to_owned());
+        if name == self.this_name {
+            let super_class = self.class_file.super_class;
+            if super_class.0 == 0 {
+                return Ok(None);
+            }
+            return self.class_name(super_class).map(Some).ok_or_else(unknown);
+        }
+
+        let class = self.provider.load_class(name).ok_or_else(unknown)?;
+        let super_class = class.class_file.super_class;
+        if super_class.0 == 0 {
+            return Ok(None);
+        }
+        let name = class
+            .class_file
+            .const_pool
+            .get_class_name(super_class, &class.data)
+            .ok_or_else(unknown)?;
+        Ok(Some(name.into_owned()))
+    }
+
+    /// The class and each of its superclasses in turn
+    fn superclasses(&self, name: &str) -> Result<Vec<String>, FrameError> {
+        let mut chain = vec![name.to_owned()];
+        let mut seen = HashSet::new();
+        loop {
+            let last = chain.last().unwrap();
+            if last == OBJECT || !seen.insert(last.clone()) {
+                return Ok(chain);
+            }
+            match self.superclass(last)? {
+                Some(superclass) => chain.push(superclass),
+                None => return Ok(chain),
+            }
+        }
+    }
+
+    /// The closest class that both classes or arrays can be assigned to
+    fn common_class(&self, a: &str, b: &str) -> Result<String, FrameError> {
+        if a == b {
+            return Ok(a.to_owned());
+        }
+        match (a.starts_with('['), b.starts_with('[')) {
+            (true, true) => match (element_class(a), element_class(b)) {
+                (Some(a), Some(b)) => Ok(array_of(&self.common_class(a, b)?)),
+                // Arrays of different primitives
+                _ => Ok(OBJECT.to_owned()),
+            },
+            (false, false) => {
+                let b_chain = self.superclasses(b)?;
+                let common = self
+                    .superclasses(a)?
+                    .into_iter()
+                    .find(|class| b_chain.contains(class));
+                Ok(common.unwrap_or_else(|| OBJECT.to_owned()))
+            }
+            _ => Ok(OBJECT.to_owned()),
+        }
+    }
+
+    /// Merge two values in the same slot, returning None if they can't be merged
+    fn merge_value(&self, a: &FrameType, b: &FrameType) -> Result<Option<FrameType>, FrameError> {
+        Ok(match (a, b) {
+            _ if a == b => Some(a.clone()),
+            (FrameType::Null, other) | (other, FrameType::Null) if other.is_reference() => {
+                Some(other.clone())
+            }
+            (FrameType::Object(a), FrameType::Object(b)) => {
+                Some(FrameType::Object(self.common_class(a, b)?))
+            }
+            _ => None,
+        })
+    }
+
+    /// Merge the incoming state into the state of an instruction, returning whether it changed
+    fn merge(
+        &self,
+        state: &mut State,
+        incoming: &State,
+        offset: usize,
+    ) -> Result<bool, FrameError> {
+        if state.stack.len() != incoming.stack.len() {
+            return Err(FrameError::InconsistentStack { offset });
+        }
+        let mut changed = false;
+        for (slot, other) in state.locals.iter_mut().zip(incoming.locals.iter()) {
+            let merged = self.merge_value(slot, other)?.unwrap_or(FrameType::Top);
+            if merged != *slot {
+                *slot = merged;
+                changed = true;
+            }
+        }
+        for (slot, other) in state.stack.iter_mut().zip(incoming.stack.iter()) {
+            let merged = self
+                .merge_value(slot, other)?
+                .ok_or(FrameError::InconsistentStack { offset })?;
+            if merged != *slot {
+                *slot = merged;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// The type of the constant loaded by `ldc`, `ldc_w`, or `ldc2_w`, see [`resolve_ldc`]
+    fn constant_type(&self, kind: LdcKind, index: u16) -> Option<FrameType> {
+        let object = |name: &str| Some(FrameType::Object(name.to_owned()));
+        let version = self.class_file.version;
+        match resolve_ldc(self.pool(), kind, index, version, self.data).ok()? {
+            LoadableConstant::Integer(_) => Some(FrameType::Integer),
+            LoadableConstant::Float(_) => Some(FrameType::Float),
+            LoadableConstant::Long(_) => Some(FrameType::Long),
+            LoadableConstant::Double(_) => Some(FrameType::Double),
+            LoadableConstant::String(_) => object(STRING),
+            LoadableConstant::Class(_) => object(CLASS),
+            LoadableConstant::MethodType(_) => object("java/lang/invoke/MethodType"),
+            LoadableConstant::MethodHandle(_) => object("java/lang/invoke/MethodHandle"),
+            LoadableConstant::Dynamic(dynamic) => {
+                let (_, descriptor) = self.pool().get_name_and_type(dynamic.name_and_type_index)?;
+                match ParsedDescriptor::parse(descriptor.as_bytes(self.data)).ok()? {
+                    ParsedDescriptor::Field(ty) => Some(FrameType::of_descriptor(&ty)),
+                    ParsedDescriptor::Method(_) => None,
+                }
+            }
+        }
+    }
+
+    /// Apply the instruction at the offset to the state
+    fn execute(
+        &self,
+        offset: usize,
+        instruction: &Instruction,
+        state: &mut State,
+    ) -> Result<(), FrameError> {
+        use FrameType::*;
+        use Instruction::*;
+
+        let invalid = || FrameError::InvalidConstant { offset };
+        let underflow = || FrameError::Underflow { offset };
+        let stack = &mut state.stack;
+        let locals = &mut state.locals;
+        let pop = |stack: &mut Vec<FrameType>, slots: usize| -> Result<(), FrameError> {
+            let len = stack.len().checked_sub(slots).ok_or_else(underflow)?;
+            stack.truncate(len);
+            Ok(())
+        };
+        let push = |stack: &mut Vec<FrameType>, ty: FrameType| {
+            let wide = ty.is_wide();
+            stack.push(ty);
+            if wide {
+                stack.push(Top);
+            }
+        };
+        let load = |locals: &[FrameType], stack: &mut Vec<FrameType>, index: usize| {
+            let ty = locals
+                .get(index)
+                .cloned()
+                .ok_or(FrameError::InvalidLocal { offset })?;
+            push(stack, ty);
+            Ok(())
+        };
+        let store = |locals: &mut Vec<FrameType>, index: usize, ty: FrameType| {
+            let end = index + if ty.is_wide() { 2 } else { 1 };
+            if end > locals.len() {
+                return Err(FrameError::InvalidLocal { offset });
+            }
+            // Overwriting the upper half of a long or double makes the whole of it unusable
+            if index > 0 && locals[index - 1].is_wide() {
+                locals[index - 1] = Top;
+            }
+            if ty.is_wide() {
+                locals[index + 1] = Top;
+            }
+            locals[index] = ty;
+            Ok(())
+        };
+        let pop_store = |locals: &mut Vec<FrameType>,
+                         stack: &mut Vec<FrameType>,
+                         index: usize,
+                         ty: Option<FrameType>| {
+            let slots = ty.as_ref().map_or(1, |ty| if ty.is_wide() { 2 } else { 1 });
+            let len = stack.len().checked_sub(slots).ok_or_else(underflow)?;
+            let value = stack[len].clone();
+            stack.truncate(len);
+            store(locals, index, ty.unwrap_or(value))
+        };
+        // Copy the top `count` slots below the `depth` slots under them
+        let dup = |stack: &mut Vec<FrameType>, count: usize, depth: usize| {
+            let len = stack.len();
+            let start = len.checked_sub(count + depth).ok_or_else(underflow)?;
+            let copied: Vec<_> = stack[len - count..].to_vec();
+            stack.splice(start..start, copied);
+            Ok(())
+        };
+
+        match instruction {
+            Nop
+            | Goto(_)
+            | GotoW(_)
+            | Return
+            | Iinc { .. }
+            | Wide(WideInstruction::Iinc { .. }) => {}
+            AconstNull => push(stack, Null),
+            IconstM1 | Iconst0 | Iconst1 | Iconst2 | Iconst3 | Iconst4 | Iconst5 | Bipush(_)
+            | Sipush(_) => push(stack, Integer),
+            Lconst0 | Lconst1 => push(stack, Long),
+            Fconst0 | Fconst1 | Fconst2 => push(stack, Float),
+            Dconst0 | Dconst1 => push(stack, Double),
+            Ldc(index) => {
+                let ty = self.constant_type(LdcKind::Ldc, u16::from(*index));
+                push(stack, ty.ok_or_else(invalid)?);
+            }
+            LdcW(index) => push(
+                stack,
+                self.constant_type(LdcKind::LdcW, index.0)
+                    .ok_or_else(invalid)?,
+            ),
+            Ldc2W(index) => push(
+                stack,
+                self.constant_type(LdcKind::Ldc2W, index.0)
+                    .ok_or_else(invalid)?,
+            ),
+            Iload(index) | Lload(index) | Fload(index) | Dload(index) | Aload(index) => {
+                load(locals, stack, usize::from(*index))?
+            }
+            Iload0 | Lload0 | Fload0 | Dload0 | Aload0 => load(locals, stack, 0)?,
+            Iload1 | Lload1 | Fload1 | Dload1 | Aload1 => load(locals, stack, 1)?,
+            Iload2 | Lload2 | Fload2 | Dload2 | Aload2 => load(locals, stack, 2)?,
+            Iload3 | Lload3 | Fload3 | Dload3 | Aload3 => load(locals, stack, 3)?,
+            Wide(
+                WideInstruction::Iload(index)
+                | WideInstruction::Lload(index)
+                | WideInstruction::Fload(index)
+                | WideInstruction::Dload(index)
+                | WideInstruction::Aload(index),
+            ) => load(locals, stack, usize::from(*index))?,
+            Iaload | Baload | Caload | Saload => {
+                pop(stack, 2)?;
+                push(stack, Integer);
+            }
+            Laload => {
+                pop(stack, 2)?;
+                push(stack, Long);
+            }
+            Faload => {
+                pop(stack, 2)?;
+                push(stack, Float);
+            }
+            Daload => {
+                pop(stack, 2)?;
+                push(stack, Double);
+            }
+            Aaload => {
+                pop(stack, 1)?;
+                let array = stack.pop().ok_or_else(underflow)?;
+                let element = match array {
+                    Object(descriptor) => element_class(&descriptor)
+                        .map(|class| Object(class.to_owned()))
+                        .unwrap_or(Top),
+                    _ => Null,
+                };
+                push(stack, element);
+            }
+            Istore(index) => pop_store(locals, stack, usize::from(*index), Some(Integer))?,
+            Lstore(index) => pop_store(locals, stack, usize::from(*index), Some(Long))?,
+            Fstore(index) => pop_store(locals, stack, usize::from(*index), Some(Float))?,
+            Dstore(index) => pop_store(locals, stack, usize::from(*index), Some(Double))?,
+            Astore(index) => pop_store(locals, stack, usize::from(*index), None)?,
+            Wide(WideInstruction::Istore(index)) => {
+                pop_store(locals, stack, usize::from(*index), Some(Integer))?
+            }
+            Wide(WideInstruction::Lstore(index)) => {
+                pop_store(locals, stack, usize::from(*index), Some(Long))?
+            }
+            Wide(WideInstruction::Fstore(index)) => {
+                pop_store(locals, stack, usize::from(*index), Some(Float))?
+            }
+            Wide(WideInstruction::Dstore(index)) => {
+                pop_store(locals, stack, usize::from(*index), Some(Double))?
+            }
+            Wide(WideInstruction::Astore(index)) => {
+                pop_store(locals, stack, usize::from(*index), None)?
+            }
+            Istore0 => pop_store(locals, stack, 0, Some(Integer))?,
+            Istore1 => pop_store(locals, stack, 1, Some(Integer))?,
+            Istore2 => pop_store(locals, stack, 2, Some(Integer))?,
+            Istore3 => pop_store(locals, stack, 3, Some(Integer))?,
+            Lstore0 => pop_store(locals, stack, 0, Some(Long))?,
+            Lstore1 => pop_store(locals, stack, 1, Some(Long))?,
+            Lstore2 => pop_store(locals, stack, 2, Some(Long))?,
+            Lstore3 => pop_store(locals, stack, 3, Some(Long))?,
+            Fstore0 => pop_store(locals, stack, 0, Some(Float))?,
+            Fstore1 => pop_store(locals, stack, 1, Some(Float))?,
+            Fstore2 => pop_store(locals, stack, 2, Some(Float))?,
+            Fstore3 => pop_store(locals, stack, 3, Some(Float))?,
+            Dstore0 => pop_store(locals, stack, 0, Some(Double))?,
+            Dstore1 => pop_store(locals, stack, 1, Some(Double))?,
+            Dstore2 => pop_store(locals, stack, 2, Some(Double))?,
+            Dstore3 => pop_store(locals, stack, 3, Some(Double))?,
+            Astore0 => pop_store(locals, stack, 0, None)?,
+            Astore1 => pop_store(locals, stack, 1, None)?,
+            Astore2 => pop_store(locals, stack, 2, None)?,
+            Astore3 => pop_store(locals, stack, 3, None)?,
+            Iastore | Fastore | Aastore | Bastore | Castore | Sastore => pop(stack, 3)?,
+            Lastore | Dastore => pop(stack, 4)?,
+            Pop => pop(stack, 1)?,
+            Pop2 => pop(stack, 2)?,
+            Dup => dup(stack, 1, 0)?,
+            DupX1 => dup(stack, 1, 1)?,
+            DupX2 => dup(stack, 1, 2)?,
+            Dup2 => dup(stack, 2, 0)?,
+            Dup2X1 => dup(stack, 2, 1)?,
+            Dup2X2 => dup(stack, 2, 2)?,
+            Swap => {
+                let len = stack.len();
+                if len < 2 {
+                    return Err(underflow());
+                }
+                stack.swap(len - 1, len - 2);
+            }
+            Iadd | Isub | Imul | Idiv | Irem | Ishl | Ishr | Iushr | Iand | Ior | Ixor | Fcmpl
+            | Fcmpg => {
+                pop(stack, 2)?;
+                push(stack, Integer);
+            }
+            Ladd | Lsub | Lmul | Ldiv | Lrem | Land | Lor | Lxor => {
+                pop(stack, 4)?;
+                push(stack, Long);
+            }
+            Lshl | Lshr | Lushr => {
+                pop(stack, 3)?;
+                push(stack, Long);
+            }
+            Fadd | Fsub | Fmul | Fdiv | Frem => {
+                pop(stack, 2)?;
+                push(stack, Float);
+            }
+            Dadd | Dsub | Dmul | Ddiv | Drem => {
+                pop(stack, 4)?;
+                push(stack, Double);
+            }
+            Ineg | F2i | I2b | I2c | I2s | Arraylength | Instanceof(_) => {
+                pop(stack, 1)?;
+                push(stack, Integer);
+            }
+            Lneg | D2l => {
+                pop(stack, 2)?;
+                push(stack, Long);
+            }
+            Fneg | I2f => {
+                pop(stack, 1)?;
+                push(stack, Float);
+            }
+            Dneg | L2d => {
+                pop(stack, 2)?;
+                push(stack, Double);
+            }
+            I2l | F2l => {
+                pop(stack, 1)?;
+                push(stack, Long);
+            }
+            I2d | F2d => {
+                pop(stack, 1)?;
+                push(stack, Double);
+            }
+            L2i | D2i | Lcmp | Dcmpl | Dcmpg => {
+                let slots = if matches!(instruction, L2i | D2i) {
+                    2
+                } else {
+                    4
+                };
+                pop(stack, slots)?;
+                push(stack, Integer);
+            }
+            L2f | D2f => {
+                pop(stack, 2)?;
+                push(stack, Float);
+            }
+            Ifeq(_)
+            | Ifne(_)
+            | Iflt(_)
+            | Ifge(_)
+            | Ifgt(_)
+            | Ifle(_)
+            | Ifnull(_)
+            | Ifnonnull(_)
+            | Tableswitch { .. }
+            | Lookupswitch { .. }
+            | Ireturn
+            | Freturn
+            | Areturn
+            | Athrow
+            | Monitorenter
+            | Monitorexit => pop(stack, 1)?,
+            IfIcmpeq(_) | IfIcmpne(_) | IfIcmplt(_) | IfIcmpge(_) | IfIcmpgt(_) | IfIcmple(_)
+            | IfAcmpeq(_) | IfAcmpne(_) | Lreturn | Dreturn => pop(stack, 2)?,
+            Jsr(_) | JsrW(_) | Ret(_) | Wide(WideInstruction::Ret(_)) => {
+                return Err(FrameError::Subroutine { offset })
+            }
+            Getstatic(index) | Putstatic(index) | Getfield(index) | Putfield(index) => {
+                let member =
+                    resolve_member_ref(self.pool(), ConstantPoolIndexRaw::new(index.0), self.data)
+                        .map_err(|_| invalid())?;
+                let ty = match member.parsed_descriptor {
+                    ParsedDescriptor::Field(ty) => FrameType::of_descriptor(&ty),
+                    ParsedDescriptor::Method(_) => return Err(invalid()),
+                };
+                let slots = if ty.is_wide() { 2 } else { 1 };
+                match instruction {
+                    Getstatic(_) => push(stack, ty),
+                    Putstatic(_) => pop(stack, slots)?,
+                    Getfield(_) => {
+                        pop(stack, 1)?;
+                        push(stack, ty);
+                    }
+                    _ => pop(stack, slots + 1)?,
+                }
+            }
+            Invokevirtual(_)
+            | Invokespecial(_)
+            | Invokestatic(_)
+            | Invokeinterface { .. }
+            | Invokedynamic(_) => self.invoke(offset, instruction, locals, stack)?,
+            New(_) => {
+                let offset = u16::try_from(offset).map_err(|_| invalid())?;
+                push(stack, Uninitialized(offset));
+            }
+            Newarray(atype) => {
+                let descriptor = match atype {
+                    4 => "[Z",
+                    5 => "[C",
+                    6 => "[F",
+                    7 => "[D",
+                    8 => "[B",
+                    9 => "[S",
+                    10 => "[I",
+                    11 => "[J",
+                    _ => return Err(invalid()),
+                };
+                pop(stack, 1)?;
+                push(stack, Object(descriptor.to_owned()));
+            }
+            Anewarray(index) => {
+                let class = self.class_name(*index).ok_or_else(invalid)?;
+                pop(stack, 1)?;
+                push(stack, Object(array_of(&class)));
+            }
+            Checkcast(index) => {
+                let class = self.class_name(*index).ok_or_else(invalid)?;
+                pop(stack, 1)?;
+                push(stack, Object(class));
+            }
+            Multianewarray { index, dimensions } => {
+                let class = self.class_name(*index).ok_or_else(invalid)?;
+                pop(stack, usize::from(*dimensions))?;
+                push(stack, Object(class));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply a call to the state, which initializes the object that a constructor is called on
+    fn invoke(
+        &self,
+        offset: usize,
+        instruction: &Instruction,
+        locals: &mut [FrameType],
+        stack: &mut Vec<FrameType>,
+    ) -> Result<(), FrameError> {
+        let invalid = || FrameError::InvalidConstant { offset };
+        let (name, descriptor, receiver) = match instruction {
+            Instruction::Invokedynamic(index) => {
+                let call_site = self.pool().get_t(*index).ok_or_else(invalid)?;
+                let (_, descriptor) = self
+                    .pool()
+                    .get_name_and_type(call_site.name_and_type_index)
+                    .ok_or_else(invalid)?;
+                let descriptor = ParsedDescriptor::parse(descriptor.as_bytes(self.data))
+                    .map_err(|_| invalid())?;
+                (None, descriptor, false)
+            }
+            _ => {
+                let index = match instruction {
+                    Instruction::Invokevirtual(index) => ConstantPoolIndexRaw::new(index.0),
+                    Instruction::Invokeinterface { index, .. } => {
+                        ConstantPoolIndexRaw::new(index.0)
+                    }
+                    Instruction::Invokespecial(index) | Instruction::Invokestatic(index) => *index,
+                    _ => unreachable!("only calls are invoked"),
+                };
+                let member =
+                    resolve_member_ref(self.pool(), index, self.data).map_err(|_| invalid())?;
+                let receiver = !matches!(instruction, Instruction::Invokestatic(_));
+                (Some(member.name), member.parsed_descriptor, receiver)
+            }
+        };
+        let descriptor = match descriptor {
+            ParsedDescriptor::Method(descriptor) => descriptor,
+            ParsedDescriptor::Field(_) => return Err(invalid()),
+        };
+
+        let underflow = || FrameError::Underflow { offset };
+        let parameters: usize = descriptor
+            .parameter_types
+            .iter()
+            .map(|ty| if ty.is_wide() { 2 } else { 1 })
+            .sum();
+        let len = stack.len().checked_sub(parameters).ok_or_else(underflow)?;
+        stack.truncate(len);
+        if receiver {
+            let receiver = stack.pop().ok_or_else(underflow)?;
+            let constructor = matches!(instruction, Instruction::Invokespecial(_))
+                && name.as_deref() == Some(INIT);
+            if constructor {
+                let initialized = match receiver {
+                    FrameType::UninitializedThis => Some(self.this_name.clone()),
+                    FrameType::Uninitialized(new) => self.new_classes.get(&new).cloned(),
+                    _ => None,
+                };
+                if let Some(class) = initialized {
+                    let initialized = FrameType::Object(class);
+                    for slot in locals.iter_mut().chain(stack.iter_mut()) {
+                        if *slot == receiver {
+                            *slot = initialized.clone();
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(ty) = descriptor.return_type {
+            let ty = FrameType::of_descriptor(&ty);
+            let wide = ty.is_wide();
+            stack.push(ty);
+            if wide {
+                stack.push(FrameType::Top);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Infer the types at every instruction of the method's code, and return the frames that the
+/// type checking verifier needs: one at each branch and switch target, each exception handler,
+/// and each instruction after an unconditional jump, return, or throw.
+///
+/// The code must be the whole of the code of the method, and the exception table and
+/// `max_locals` the ones that go with it. The code can't call subroutines, and every instruction
+/// must be reachable.
+pub fn compute_frames(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    code: &[u8],
+    exception_table: &[ExceptionEntry],
+    max_locals: u16,
+    class_file_data: &[u8],
+    provider: &dyn ClassProvider,
+) -> Result<Frames, FrameError> {
+    let pool = &class_file.const_pool;
+    let this_name = pool
+        .get_class_name(class_file.this_class, class_file_data)
+        .ok_or(FrameError::InvalidDescriptor)?
+        .into_owned();
+    let name = pool
+        .get_utf8_text(method.name_index, class_file_data)
+        .ok_or(FrameError::InvalidDescriptor)?;
+    let descriptor = pool
+        .get_t(method.descriptor_index)
+        .map(|descriptor| ParsedDescriptor::parse(descriptor.as_bytes(class_file_data)));
+    let descriptor = match descriptor {
+        Some(Ok(ParsedDescriptor::Method(descriptor))) => descriptor,
+        _ => return Err(FrameError::InvalidDescriptor),
+    };
+
+    let mut instructions = Vec::new();
+    for result in code_iter(code) {
+        let (offset, instruction) = result.map_err(FrameError::Instruction)?;
+        instructions.push((usize::from(offset), instruction));
+    }
+    let indices: HashMap<usize, usize> = instructions
+        .iter()
+        .enumerate()
+        .map(|(index, &(offset, _))| (offset, index))
+        .collect();
+    let mut new_classes = HashMap::new();
+    for (offset, instruction) in instructions.iter() {
+        if let Instruction::New(index) = instruction {
+            let class = pool
+                .get_class_name(*index, class_file_data)
+                .ok_or(FrameError::InvalidConstant { offset: *offset })?;
+            new_classes.insert(*offset as u16, class.into_owned());
+        }
+    }
+
+    let analysis = Analysis {
+        class_file,
+        data: class_file_data,
+        provider,
+        this_name,
+        max_locals: usize::from(max_locals),
+        new_classes,
+    };
+
+    let mut locals = Vec::with_capacity(analysis.max_locals);
+    if !method.access_flags.contains(MethodAccessFlags::STATIC) {
+        let constructor = name == INIT && analysis.this_name != OBJECT;
+        locals.push(if constructor {
+            FrameType::UninitializedThis
+        } else {
+            FrameType::Object(analysis.this_name.clone())
+        });
+    }
+    for parameter in descriptor.parameter_types.iter() {
+        let ty = FrameType::of_descriptor(parameter);
+        let wide = ty.is_wide();
+        locals.push(ty);
+        if wide {
+            locals.push(FrameType::Top);
+        }
+    }
+    if locals.len() > analysis.max_locals {
+        return Err(FrameError::InvalidDescriptor);
+    }
+    let initial_locals = local_entries(&locals);
+    locals.resize(analysis.max_locals, FrameType::Top);
+
+    let mut handlers = Vec::with_capacity(exception_table.len());
+    for entry in exception_table {
+        let handler_pc = entry.handler_pc.0;
+        let handler = *indices
+            .get(&usize::from(handler_pc))
+            .ok_or(FrameError::BadHandler { handler_pc })?;
+        let class = match entry.catch_type.0 {
+            0 => THROWABLE.to_owned(),
+            _ => analysis
+                .class_name(entry.catch_type)
+                .ok_or(FrameError::BadHandler { handler_pc })?,
+        };
+        let range = usize::from(entry.start_pc.0)..usize::from(entry.end_pc.0);
+        handlers.push((range, handler, FrameType::Object(class)));
+    }
+
+    // The instructions which need a frame
+    let mut targets = HashSet::new();
+    let mut states: Vec<Option<State>> = vec![None; instructions.len()];
+    let mut pending = Vec::new();
+    let enter = |states: &mut [Option<State>],
+                 pending: &mut Vec<usize>,
+                 index: usize,
+                 incoming: State|
+     -> Result<(), FrameError> {
+        let changed = match &mut states[index] {
+            Some(state) => analysis.merge(state, &incoming, instructions[index].0)?,
+            slot @ None => {
+                *slot = Some(incoming);
+                true
+            }
+        };
+        if changed && !pending.contains(&index) {
+            pending.push(index);
+        }
+        Ok(())
+    };
+    if !instructions.is_empty() {
+        let stack = Vec::new();
+        enter(&mut states, &mut pending, 0, State { locals, stack })?;
+    }
+    for (_, handler, _) in handlers.iter() {
+        targets.insert(*handler);
+    }
+
+    while let Some(index) = pending.pop() {
+        let (offset, instruction) = &instructions[index];
+        let offset = *offset;
+        let before = states[index]
+            .clone()
+            .expect("pending instructions have a state");
+
+        for (range, handler, class) in handlers.iter() {
+            if range.contains(&offset) {
+                let incoming = State {
+                    locals: before.locals.clone(),
+                    stack: vec![class.clone()],
+                };
+                enter(&mut states, &mut pending, *handler, incoming)?;
+            }
+        }
+
+        let mut after = before;
+        analysis.execute(offset, instruction, &mut after)?;
+        for relative in instruction.branch_targets() {
+            let target = offset as i64 + i64::from(relative);
+            let target_index = usize::try_from(target)
+                .ok()
+                .and_then(|target| indices.get(&target))
+                .copied()
+                .ok_or(FrameError::BadTarget { offset, target })?;
+            targets.insert(target_index);
+            enter(&mut states, &mut pending, target_index, after.clone())?;
+        }
+        if instruction.falls_through() {
+            if index + 1 >= instructions.len() {
+                return Err(FrameError::FallsOffEnd { offset });
+            }
+            enter(&mut states, &mut pending, index + 1, after)?;
+        } else if index + 1 < instructions.len() {
+            targets.insert(index + 1);
+        }
+    }
+
+    let mut targets: Vec<usize> = targets.into_iter().collect();
+    targets.sort_unstable();
+    let mut frames = Vec::with_capacity(targets.len());
+    for index in targets {
+        let offset = instructions[index].0;
+        let state = states[index]
+            .as_ref()
+            .ok_or(FrameError::Unreachable { offset })?;
+        frames.push(Frame {
+            offset: offset as u16,
+            locals: local_entries(&state.locals),
+            stack: entries(&state.stack),
+        });
+    }
+    if let Some(index) = states.iter().position(Option::is_none) {
+        return Err(FrameError::Unreachable {
+            offset: instructions[index].0,
+        });
+    }
+
+    Ok(Frames {
+        initial_locals,
+        frames,
+    })
+}
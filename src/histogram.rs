@@ -0,0 +1,213 @@
+//! Counting the instructions in the code of methods, as a summary of what the code does.
+//!
+//! Each method gets a histogram of its opcodes and a count of its call sites by kind and by the
+//! constant that they call, and a class gets the same summed over all of its methods.
+
+use std::collections::BTreeMap;
+
+use crate::attribute_info::{CodeAttributeOpt, HasAttributes};
+use crate::constant_pool::ConstantPool;
+use crate::instructions::{code_iter, opcode_mnemonic, Instruction, InstructionError};
+use crate::method_info::MethodInfo;
+use crate::{ClassFile, LoadError};
+
+/// The number of times each opcode appears.
+/// `wide` is counted as its own opcode rather than as the instruction that it modifies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpcodeHistogram {
+    counts: [u32; 256],
+}
+impl Default for OpcodeHistogram {
+    fn default() -> Self {
+        OpcodeHistogram { counts: [0; 256] }
+    }
+}
+impl OpcodeHistogram {
+    pub fn new() -> OpcodeHistogram {
+        OpcodeHistogram::default()
+    }
+
+    pub fn add(&mut self, instruction: &Instruction) {
+        let count = &mut self.counts[usize::from(instruction.opcode())];
+        *count = count.saturating_add(1);
+    }
+
+    pub fn merge(&mut self, other: &OpcodeHistogram) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count = count.saturating_add(*other);
+        }
+    }
+
+    pub fn count(&self, opcode: u8) -> u32 {
+        self.counts[usize::from(opcode)]
+    }
+
+    /// The number of times the instruction with the mnemonic appears, such as `invokevirtual`
+    pub fn count_mnemonic(&self, mnemonic: &str) -> u32 {
+        self.iter()
+            .find(|&(opcode, _)| opcode_mnemonic(opcode) == Some(mnemonic))
+            .map_or(0, |(_, count)| count)
+    }
+
+    /// The number of instructions counted
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|&count| u64::from(count)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|&count| count == 0)
+    }
+
+    /// Iterate over the opcodes which appear along with their counts, in order of opcode
+    pub fn iter(&self) -> impl Iterator<Item = (u8, u32)> + '_ {
+        (0..=u8::MAX)
+            .zip(self.counts.iter().copied())
+            .filter(|&(_, count)| count != 0)
+    }
+
+    /// The opcodes which appear along with their counts, most common first.
+    /// Opcodes with the same count are in order of opcode.
+    pub fn most_common(&self) -> Vec<(u8, u32)> {
+        let mut counts: Vec<_> = self.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+/// The number of `invoke*` instructions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallSites {
+    pub invokevirtual: u32,
+    pub invokespecial: u32,
+    pub invokestatic: u32,
+    pub invokeinterface: u32,
+    pub invokedynamic: u32,
+    /// The number of call sites of each constant that is called, by its raw index.
+    /// These are method refs, or InvokeDynamic constants for `invokedynamic`.
+    pub targets: BTreeMap<u16, u32>,
+}
+impl CallSites {
+    pub fn new() -> CallSites {
+        CallSites::default()
+    }
+
+    /// Count the instruction if it is a call
+    pub fn add(&mut self, instruction: &Instruction) {
+        let (count, index) = match instruction {
+            Instruction::Invokevirtual(index) => (&mut self.invokevirtual, index.0),
+            Instruction::Invokespecial(index) => (&mut self.invokespecial, index.0),
+            Instruction::Invokestatic(index) => (&mut self.invokestatic, index.0),
+            Instruction::Invokeinterface { index, .. } => (&mut self.invokeinterface, index.0),
+            Instruction::Invokedynamic(index) => (&mut self.invokedynamic, index.0),
+            _ => return,
+        };
+        *count = count.saturating_add(1);
+        let target = self.targets.entry(index).or_insert(0);
+        *target = target.saturating_add(1);
+    }
+
+    pub fn merge(&mut self, other: &CallSites) {
+        self.invokevirtual = self.invokevirtual.saturating_add(other.invokevirtual);
+        self.invokespecial = self.invokespecial.saturating_add(other.invokespecial);
+        self.invokestatic = self.invokestatic.saturating_add(other.invokestatic);
+        self.invokeinterface = self.invokeinterface.saturating_add(other.invokeinterface);
+        self.invokedynamic = self.invokedynamic.saturating_add(other.invokedynamic);
+        for (&index, &count) in other.targets.iter() {
+            let target = self.targets.entry(index).or_insert(0);
+            *target = target.saturating_add(count);
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        [
+            self.invokevirtual,
+            self.invokespecial,
+            self.invokestatic,
+            self.invokeinterface,
+            self.invokedynamic,
+        ]
+        .iter()
+        .map(|&count| u64::from(count))
+        .sum()
+    }
+}
+
+/// The opcodes and call sites of some code
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstructionStats {
+    pub opcodes: OpcodeHistogram,
+    pub call_sites: CallSites,
+}
+impl InstructionStats {
+    pub fn new() -> InstructionStats {
+        InstructionStats::default()
+    }
+
+    /// Count the instructions of the code, which must be the whole of the code of a method.
+    /// Errors if any of it can't be decoded.
+    pub fn from_code(code: &[u8]) -> Result<InstructionStats, InstructionError> {
+        let mut stats = InstructionStats::new();
+        for result in code_iter(code) {
+            let (_, instruction) = result?;
+            stats.add(&instruction);
+        }
+        Ok(stats)
+    }
+
+    pub fn add(&mut self, instruction: &Instruction) {
+        self.opcodes.add(instruction);
+        self.call_sites.add(instruction);
+    }
+
+    pub fn merge(&mut self, other: &InstructionStats) {
+        self.opcodes.merge(&other.opcodes);
+        self.call_sites.merge(&other.call_sites);
+    }
+}
+
+/// The instruction counts of each method of a class, and of the class as a whole
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassInstructionStats {
+    /// The counts of each method, in the order of the class's methods.
+    /// Methods without code, such as abstract and native methods, have None.
+    pub methods: Vec<Option<InstructionStats>>,
+    /// The counts of all of the methods together
+    pub total: InstructionStats,
+}
+
+impl MethodInfo {
+    /// Count the instructions of the method's code.
+    /// Returns None if the method has no code.
+    /// Errors if the Code attribute can't be parsed or its code can't be decoded.
+    pub fn instruction_stats(
+        &self,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+    ) -> Result<Option<InstructionStats>, LoadError> {
+        let code = match self.find_attribute::<CodeAttributeOpt>(pool, class_file_data)? {
+            Some(code) => code,
+            None => return Ok(None),
+        };
+        let bytes = class_file_data
+            .get(code.code_range)
+            .ok_or(LoadError::Unknown)?;
+        InstructionStats::from_code(bytes)
+            .map(Some)
+            .map_err(|_| LoadError::Unknown)
+    }
+}
+
+impl ClassFile {
+    /// Count the instructions of every method of the class, see [`MethodInfo::instruction_stats`]
+    pub fn instruction_stats(&self, data: &[u8]) -> Result<ClassInstructionStats, LoadError> {
+        let mut stats = ClassInstructionStats::default();
+        for method in self.methods.iter() {
+            let method_stats = method.instruction_stats(&self.const_pool, data)?;
+            if let Some(method_stats) = &method_stats {
+                stats.total.merge(method_stats);
+            }
+            stats.methods.push(method_stats);
+        }
+        Ok(stats)
+    }
+}
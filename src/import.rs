@@ -0,0 +1,528 @@
+//! Copying fields and methods from one class file into another.
+//!
+//! The constants that a member refers to, through its name and descriptor, its attributes, and
+//! the instructions of its code, are added to the pool of the class that it is copied into. Any
+//! equal constant that the pool already has is used instead of adding another. The bootstrap
+//! methods of copied `invokedynamic` instructions and dynamic constants are added to the class's
+//! BootstrapMethods attribute in the same way.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::attribute_info::{
+    names, AttributeInfo, BootstrapMethod, BootstrapMethodsAttribute, CodeAttribute, HasAttributes,
+    KnownAttribute,
+};
+use crate::constant_info::*;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::field_info::FieldInfo;
+use crate::ldc::fit_ldc;
+use crate::method_info::MethodInfo;
+use crate::remap::{copy_attributes, IndexMapping, IndexRemap, RemapError};
+use crate::writer::write_constant;
+use crate::ClassFile;
+
+/// How deeply constants may refer to each other before the copy gives up, which is more than a
+/// well formed pool needs and stops cycles through the arguments of bootstrap methods
+const MAX_IMPORT_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// The class already has a member with the same name and descriptor
+    DuplicateMember,
+    /// The class already has as many fields or methods as a class file can hold
+    TooManyMembers,
+    /// The member's constants or attributes could not be copied
+    Remap(RemapError),
+}
+impl From<RemapError> for ImportError {
+    fn from(err: RemapError) -> Self {
+        ImportError::Remap(err)
+    }
+}
+
+/// Records every index that the attributes refer to, leaving them unchanged
+#[derive(Default)]
+struct Collector {
+    used: RefCell<BTreeSet<u16>>,
+}
+impl IndexMapping for Collector {
+    fn map_index(&self, index: u16) -> Result<u16, RemapError> {
+        self.used.borrow_mut().insert(index);
+        Ok(index)
+    }
+}
+
+/// Adds the constants of a class file that is copied from to the pool of the one copied into
+struct Importer<'a> {
+    from_pool: &'a ConstantPool,
+    from_data: &'a [u8],
+    from_bootstrap_methods: Vec<BootstrapMethod>,
+    pool: ConstantPool,
+    /// Where `out` will be placed in the data of the class file copied into
+    base: usize,
+    /// The bytes of new Utf8 constants and attributes, which are appended to the data
+    out: Vec<u8>,
+    /// The index of each entry of the new pool, by its class file form
+    entries: HashMap<Vec<u8>, u16>,
+    /// The new index of each old index that has been imported
+    imported: HashMap<u16, u16>,
+    bootstrap_methods: Vec<BootstrapMethod>,
+    bootstrap_methods_changed: bool,
+}
+impl<'a> Importer<'a> {
+    fn new(
+        class_file: &ClassFile,
+        data: &[u8],
+        from: &'a ClassFile,
+        from_data: &'a [u8],
+    ) -> Result<Importer<'a>, RemapError> {
+        let bootstrap_methods = |class_file: &ClassFile, data: &[u8]| {
+            class_file
+                .find_attribute::<BootstrapMethodsAttribute>(&class_file.const_pool, data)
+                .map(|attr| attr.map_or_else(Vec::new, |attr| attr.bootstrap_methods))
+                .map_err(|_| RemapError::Malformed)
+        };
+
+        let mut entries = HashMap::new();
+        for (index, entry) in class_file.const_pool.iter_indexed() {
+            let mut key = Vec::new();
+            if write_constant(entry, index.0, data, &mut key).is_ok() {
+                entries.entry(key).or_insert(index.0);
+            }
+        }
+
+        Ok(Importer {
+            from_pool: &from.const_pool,
+            from_data,
+            from_bootstrap_methods: bootstrap_methods(from, from_data)?,
+            pool: class_file.const_pool.clone(),
+            base: data.len(),
+            out: Vec::new(),
+            entries,
+            imported: HashMap::new(),
+            bootstrap_methods: bootstrap_methods(class_file, data)?,
+            bootstrap_methods_changed: false,
+        })
+    }
+
+    /// Get the index of an entry equal to the given one, adding it if the pool has none
+    fn add(&mut self, entry: ConstantInfo) -> Result<u16, RemapError> {
+        let mut key = Vec::new();
+        write_constant(&entry, 0, &[], &mut key).map_err(|_| RemapError::Malformed)?;
+        if let Some(&index) = self.entries.get(&key) {
+            return Ok(index);
+        }
+        let index = self.pool.push(entry).ok_or(RemapError::PoolTooLarge)?;
+        self.entries.insert(key, index.0);
+        Ok(index.0)
+    }
+
+    fn utf8(&mut self, bytes: &[u8]) -> Result<u16, RemapError> {
+        let len = u16::try_from(bytes.len()).map_err(|_| RemapError::Malformed)?;
+        let mut key = vec![1];
+        key.extend_from_slice(&len.to_be_bytes());
+        key.extend_from_slice(bytes);
+        if let Some(&index) = self.entries.get(&key) {
+            return Ok(index);
+        }
+
+        let start = self.base + self.out.len();
+        self.out.extend_from_slice(bytes);
+        let text = Utf8Constant::new(start..self.base + self.out.len());
+        let index = self
+            .pool
+            .push(ConstantInfo::Utf8(text))
+            .ok_or(RemapError::PoolTooLarge)?;
+        self.entries.insert(key, index.0);
+        Ok(index.0)
+    }
+
+    fn index<T>(
+        &mut self,
+        index: ConstantPoolIndexRaw<T>,
+        depth: usize,
+    ) -> Result<ConstantPoolIndexRaw<T>, RemapError> {
+        self.import(index.0, depth).map(ConstantPoolIndexRaw::new)
+    }
+
+    /// Add the entry at the old index, and every entry that it refers to, to the new pool
+    fn import(&mut self, index: u16, depth: usize) -> Result<u16, RemapError> {
+        if let Some(&new) = self.imported.get(&index) {
+            return Ok(new);
+        }
+        if depth >= MAX_IMPORT_DEPTH {
+            return Err(RemapError::Malformed);
+        }
+        let depth = depth + 1;
+
+        let from_pool = self.from_pool;
+        let entry = match from_pool.get(ConstantPoolIndexRaw::<ConstantInfo>::new(index)) {
+            Some(ConstantInfo::Unusable) | None => return Err(RemapError::InvalidIndex(index)),
+            Some(entry) => entry,
+        };
+        let new = match entry {
+            ConstantInfo::Utf8(text) => self.utf8(text.as_bytes(self.from_data))?,
+            ConstantInfo::Integer(_)
+            | ConstantInfo::Float(_)
+            | ConstantInfo::Long(_)
+            | ConstantInfo::Double(_)
+            | ConstantInfo::Unusable => self.add(entry.clone())?,
+            ConstantInfo::Class(c) => {
+                let entry = ClassConstant {
+                    name_index: self.index(c.name_index, depth)?,
+                };
+                self.add(ConstantInfo::Class(entry))?
+            }
+            ConstantInfo::String(c) => {
+                let entry = StringConstant {
+                    string_index: self.index(c.string_index, depth)?,
+                };
+                self.add(ConstantInfo::String(entry))?
+            }
+            ConstantInfo::FieldRef(c) => {
+                let entry = FieldRefConstant {
+                    class_index: self.index(c.class_index, depth)?,
+                    name_and_type_index: self.index(c.name_and_type_index, depth)?,
+                };
+                self.add(ConstantInfo::FieldRef(entry))?
+            }
+            ConstantInfo::MethodRef(c) => {
+                let entry = MethodRefConstant {
+                    class_index: self.index(c.class_index, depth)?,
+                    name_and_type_index: self.index(c.name_and_type_index, depth)?,
+                };
+                self.add(ConstantInfo::MethodRef(entry))?
+            }
+            ConstantInfo::InterfaceMethodRef(c) => {
+                let entry = InterfaceMethodRefConstant {
+                    class_index: self.index(c.class_index, depth)?,
+                    name_and_type_index: self.index(c.name_and_type_index, depth)?,
+                };
+                self.add(ConstantInfo::InterfaceMethodRef(entry))?
+            }
+            ConstantInfo::NameAndType(c) => {
+                let entry = NameAndTypeConstant {
+                    name_index: self.index(c.name_index, depth)?,
+                    descriptor_index: self.index(c.descriptor_index, depth)?,
+                };
+                self.add(ConstantInfo::NameAndType(entry))?
+            }
+            ConstantInfo::MethodHandle(c) => {
+                let entry = MethodHandleConstant {
+                    reference_kind: c.reference_kind,
+                    reference_index: self.index(c.reference_index, depth)?,
+                };
+                self.add(ConstantInfo::MethodHandle(entry))?
+            }
+            ConstantInfo::MethodType(c) => {
+                let entry = MethodTypeConstant {
+                    descriptor_index: self.index(c.descriptor_index, depth)?,
+                };
+                self.add(ConstantInfo::MethodType(entry))?
+            }
+            ConstantInfo::InvokeDynamic(c) => {
+                let entry = InvokeDynamicConstant {
+                    bootstrap_method_attr_index: self
+                        .bootstrap_method(c.bootstrap_method_attr_index, depth)?,
+                    name_and_type_index: self.index(c.name_and_type_index, depth)?,
+                };
+                self.add(ConstantInfo::InvokeDynamic(entry))?
+            }
+            ConstantInfo::Dynamic(c) => {
+                let entry = DynamicConstant {
+                    bootstrap_method_attr_index: self
+                        .bootstrap_method(c.bootstrap_method_attr_index, depth)?,
+                    name_and_type_index: self.index(c.name_and_type_index, depth)?,
+                };
+                self.add(ConstantInfo::Dynamic(entry))?
+            }
+            ConstantInfo::Module(c) => {
+                let entry = ModuleConstant {
+                    name_index: self.index(c.name_index, depth)?,
+                };
+                self.add(ConstantInfo::Module(entry))?
+            }
+            ConstantInfo::Package(c) => {
+                let entry = PackageConstant {
+                    name_index: self.index(c.name_index, depth)?,
+                };
+                self.add(ConstantInfo::Package(entry))?
+            }
+        };
+
+        self.imported.insert(index, new);
+        Ok(new)
+    }
+
+    /// Add the bootstrap method at the old index to the new BootstrapMethods attribute, unless it
+    /// already has an equal one, returning its new index
+    fn bootstrap_method(&mut self, index: u16, depth: usize) -> Result<u16, RemapError> {
+        let method = self
+            .from_bootstrap_methods
+            .get(usize::from(index))
+            .ok_or(RemapError::Malformed)?
+            .clone();
+        let bootstrap_method_ref = self.index(method.bootstrap_method_ref, depth)?;
+        let bootstrap_arguments = method
+            .bootstrap_arguments
+            .iter()
+            .map(|&argument| self.index(argument, depth))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let existing = self.bootstrap_methods.iter().position(|method| {
+            method.bootstrap_method_ref == bootstrap_method_ref
+                && method.bootstrap_arguments == bootstrap_arguments
+        });
+        let new = match existing {
+            Some(new) => new,
+            None => {
+                self.bootstrap_methods.push(BootstrapMethod {
+                    bootstrap_method_ref,
+                    num_bootstrap_arguments: method.num_bootstrap_arguments,
+                    bootstrap_arguments,
+                });
+                self.bootstrap_methods_changed = true;
+                self.bootstrap_methods.len() - 1
+            }
+        };
+        u16::try_from(new).map_err(|_| RemapError::Malformed)
+    }
+
+    /// Import everything that the member refers to, then copy its attributes to `out`, returning
+    /// the new indices of its name and descriptor
+    fn member(
+        &mut self,
+        name_index: ConstantPoolIndexRaw<Utf8Constant>,
+        descriptor_index: ConstantPoolIndexRaw<Utf8Constant>,
+        attributes: &mut [AttributeInfo],
+    ) -> Result<
+        (
+            ConstantPoolIndexRaw<Utf8Constant>,
+            ConstantPoolIndexRaw<Utf8Constant>,
+        ),
+        RemapError,
+    > {
+        let collector = Collector::default();
+        copy_attributes(
+            &collector,
+            self.from_pool,
+            self.from_data,
+            &mut attributes.to_vec(),
+            &mut Vec::new(),
+        )?;
+        for index in collector.used.into_inner() {
+            self.import(index, 0)?;
+        }
+        let name_index = self.index(name_index, 0)?;
+        let descriptor_index = self.index(descriptor_index, 0)?;
+
+        let mut map = vec![0; usize::from(self.from_pool.len()) + 1];
+        for (&old, &new) in self.imported.iter() {
+            map[usize::from(old)] = new;
+        }
+        let remap = IndexRemap::from_map(map, self.pool.len());
+
+        // Constants whose new index is past 255 can't be loaded with `ldc`, so the code is
+        // rewritten to use `ldc_w` first, after the data copied from
+        let mut from_data = Cow::Borrowed(self.from_data);
+        for attr in attributes.iter_mut() {
+            let is_code = self
+                .from_pool
+                .get_t(attr.attribute_name_index)
+                .is_some_and(|name| name.as_bytes(self.from_data) == names::CODE.as_bytes());
+            if !is_code {
+                continue;
+            }
+            let code = CodeAttribute::parse_info(attr, self.from_data)
+                .map_err(|_| RemapError::Malformed)?;
+            if let Some(info) = fit_ldc(&code, self.from_pool, self.from_data, &remap)? {
+                let data = from_data.to_mut();
+                let start = data.len();
+                data.extend_from_slice(&info);
+                attr.info = start..data.len();
+                attr.attribute_length =
+                    u32::try_from(info.len()).map_err(|_| RemapError::CodeTooLong)?;
+            }
+        }
+
+        copy_attributes(
+            &remap,
+            self.from_pool,
+            &from_data,
+            attributes,
+            &mut self.out,
+        )?;
+        let base = self.base;
+        for attr in attributes.iter_mut() {
+            attr.info = (attr.info.start + base)..(attr.info.end + base);
+        }
+
+        Ok((name_index, descriptor_index))
+    }
+
+    /// Write the new pool, and the BootstrapMethods attribute if it changed, to the class file
+    fn finish(mut self, class_file: &mut ClassFile, data: &mut Vec<u8>) -> Result<(), RemapError> {
+        if self.bootstrap_methods_changed {
+            let mut info = Vec::new();
+            let count =
+                u16::try_from(self.bootstrap_methods.len()).map_err(|_| RemapError::Malformed)?;
+            info.extend_from_slice(&count.to_be_bytes());
+            for method in self.bootstrap_methods.iter() {
+                let arguments = u16::try_from(method.bootstrap_arguments.len())
+                    .map_err(|_| RemapError::Malformed)?;
+                info.extend_from_slice(&method.bootstrap_method_ref.0.to_be_bytes());
+                info.extend_from_slice(&arguments.to_be_bytes());
+                for argument in method.bootstrap_arguments.iter() {
+                    info.extend_from_slice(&argument.0.to_be_bytes());
+                }
+            }
+
+            let name_index =
+                ConstantPoolIndexRaw::new(self.utf8(names::BOOTSTRAP_METHODS.as_bytes())?);
+            let start = self.base + self.out.len();
+            self.out.extend_from_slice(&info);
+            let attr = AttributeInfo {
+                attribute_name_index: name_index,
+                attribute_length: info.len() as u32,
+                info: start..self.base + self.out.len(),
+            };
+            let pool = &class_file.const_pool;
+            let existing = class_file.attributes.iter_mut().find(|attr| {
+                pool.get_t(attr.attribute_name_index)
+                    .is_some_and(|name| name.as_bytes(data) == names::BOOTSTRAP_METHODS.as_bytes())
+            });
+            match existing {
+                Some(existing) => *existing = attr,
+                None => {
+                    class_file.attributes.push(attr);
+                    class_file.attributes_count = class_file.attributes.len() as u16;
+                }
+            }
+        }
+
+        data.extend_from_slice(&self.out);
+        class_file.const_pool_size = self.pool.len() + 1;
+        class_file.const_pool = self.pool;
+        class_file.typed_attributes = None;
+        Ok(())
+    }
+}
+
+/// Whether any of the members has the name and descriptor
+fn has_member(
+    pool: &ConstantPool,
+    data: &[u8],
+    mut members: impl Iterator<
+        Item = (
+            ConstantPoolIndexRaw<Utf8Constant>,
+            ConstantPoolIndexRaw<Utf8Constant>,
+        ),
+    >,
+    name: &[u8],
+    descriptor: &[u8],
+) -> bool {
+    let text = |index| {
+        pool.get_t(index)
+            .map(|text: &Utf8Constant| text.as_bytes(data))
+    };
+    members.any(|(name_index, descriptor_index)| {
+        text(name_index) == Some(name) && text(descriptor_index) == Some(descriptor)
+    })
+}
+
+impl ClassFile {
+    /// Copy the method of `from` into this class, along with its attributes and every constant
+    /// that they refer to, returning the index of the new method.
+    /// The new constants, attributes and bootstrap methods are written to the end of `data`.
+    /// Errors if the class already has a method with the same name and descriptor, or if the
+    /// method's attributes can't be copied, such as when they include one whose layout isn't
+    /// known. On error, neither the class file nor `data` is modified.
+    pub fn import_method(
+        &mut self,
+        data: &mut Vec<u8>,
+        from: &ClassFile,
+        from_data: &[u8],
+        method: &MethodInfo,
+    ) -> Result<usize, ImportError> {
+        if self.methods.len() >= usize::from(u16::MAX) {
+            return Err(ImportError::TooManyMembers);
+        }
+        let text = |index: ConstantPoolIndexRaw<Utf8Constant>| {
+            from.const_pool
+                .get_t(index)
+                .map(|text| text.as_bytes(from_data))
+                .ok_or(RemapError::InvalidIndex(index.0))
+        };
+        let members = self
+            .methods
+            .iter()
+            .map(|method| (method.name_index, method.descriptor_index));
+        let name = text(method.name_index)?;
+        let descriptor = text(method.descriptor_index)?;
+        if has_member(&self.const_pool, data, members, name, descriptor) {
+            return Err(ImportError::DuplicateMember);
+        }
+
+        let mut importer = Importer::new(self, data, from, from_data)?;
+        let mut method = method.clone();
+        let (name_index, descriptor_index) = importer.member(
+            method.name_index,
+            method.descriptor_index,
+            &mut method.attributes,
+        )?;
+        method.name_index = name_index;
+        method.descriptor_index = descriptor_index;
+        importer.finish(self, data)?;
+
+        self.methods.push(method);
+        self.methods_count = self.methods.len() as u16;
+        Ok(self.methods.len() - 1)
+    }
+
+    /// Copy the field of `from` into this class, along with its attributes and every constant
+    /// that they refer to, returning the index of the new field.
+    /// See [`ClassFile::import_method`].
+    pub fn import_field(
+        &mut self,
+        data: &mut Vec<u8>,
+        from: &ClassFile,
+        from_data: &[u8],
+        field: &FieldInfo,
+    ) -> Result<usize, ImportError> {
+        if self.fields.len() >= usize::from(u16::MAX) {
+            return Err(ImportError::TooManyMembers);
+        }
+        let text = |index: ConstantPoolIndexRaw<Utf8Constant>| {
+            from.const_pool
+                .get_t(index)
+                .map(|text| text.as_bytes(from_data))
+                .ok_or(RemapError::InvalidIndex(index.0))
+        };
+        let members = self
+            .fields
+            .iter()
+            .map(|field| (field.name_index, field.descriptor_index));
+        let name = text(field.name_index)?;
+        let descriptor = text(field.descriptor_index)?;
+        if has_member(&self.const_pool, data, members, name, descriptor) {
+            return Err(ImportError::DuplicateMember);
+        }
+
+        let mut importer = Importer::new(self, data, from, from_data)?;
+        let mut field = field.clone();
+        let (name_index, descriptor_index) = importer.member(
+            field.name_index,
+            field.descriptor_index,
+            &mut field.attributes,
+        )?;
+        field.name_index = name_index;
+        field.descriptor_index = descriptor_index;
+        importer.finish(self, data)?;
+
+        self.fields.push(field);
+        self.fields_count = self.fields.len() as u16;
+        Ok(self.fields.len() - 1)
+    }
+}
@@ -0,0 +1,446 @@
+//! A lightweight index of class files, which can be saved to disk and loaded again.
+//!
+//! Building a [`ClassIndex`] parses the constant pool and the headers of the members, and uses
+//! [`crate::scan`] to find where everything is, without parsing any attributes. A
+//! [`ClassPathIndex`] keeps the indexes of the classes from each archive along with a stamp
+//! chosen by the caller, such as the archive's size and modification time, so an archive whose
+//! stamp hasn't changed doesn't need to be read again.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::constant_info::{ClassConstant, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::error::{Malformation, ParseError};
+use crate::scan::{self, ClassLayout, Table};
+use crate::{ClassFileOpt, ClassFileVersion, ParseOptions};
+
+const MAGIC: &[u8; 4] = b"CPIX";
+const FORMAT_VERSION: u16 = 1;
+
+/// A field or method, and where it is in the class file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberIndex {
+    /// The raw access flags
+    pub access_flags: u16,
+    pub name: String,
+    pub descriptor: String,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassIndex {
+    /// The internal name of the class
+    pub name: String,
+    /// The internal name of the superclass, which is None for `java/lang/Object` and modules
+    pub super_class: Option<String>,
+    pub interfaces: Vec<String>,
+    /// The raw access flags
+    pub access_flags: u16,
+    pub version: ClassFileVersion,
+    pub layout: ClassLayout,
+    pub fields: Vec<MemberIndex>,
+    pub methods: Vec<MemberIndex>,
+}
+
+fn malformed(data: &[u8], offset: usize) -> ParseError {
+    ParseError::Malformed {
+        offset,
+        item: None,
+        reason: Malformation::Invalid,
+    }
+    .with_item(data)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, ParseError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| malformed(data, offset))
+}
+
+fn utf8_at(
+    pool: &ConstantPool,
+    data: &[u8],
+    offset: usize,
+    index: ConstantPoolIndexRaw<Utf8Constant>,
+) -> Result<String, ParseError> {
+    pool.get_t(index)
+        .map(|text| text.as_text(data).into_owned())
+        .ok_or_else(|| malformed(data, offset))
+}
+
+fn class_name_at(
+    pool: &ConstantPool,
+    data: &[u8],
+    offset: usize,
+    index: ConstantPoolIndexRaw<ClassConstant>,
+) -> Result<String, ParseError> {
+    let class = pool.get_t(index).ok_or_else(|| malformed(data, offset))?;
+    utf8_at(pool, data, offset, class.name_index)
+}
+
+/// Read the headers of the fields or methods in the table
+fn members(
+    pool: &ConstantPool,
+    data: &[u8],
+    table: Table,
+    skip: fn(&[u8], usize) -> Result<usize, ParseError>,
+) -> Result<Vec<MemberIndex>, ParseError> {
+    let mut members = Vec::with_capacity(usize::from(table.count));
+    let mut offset = table.start;
+    for _ in 0..table.count {
+        let name_index = ConstantPoolIndexRaw::new(u16_at(data, offset + 2)?);
+        let descriptor_index = ConstantPoolIndexRaw::new(u16_at(data, offset + 4)?);
+        members.push(MemberIndex {
+            access_flags: u16_at(data, offset)?,
+            name: utf8_at(pool, data, offset + 2, name_index)?,
+            descriptor: utf8_at(pool, data, offset + 4, descriptor_index)?,
+            offset,
+        });
+        offset = skip(data, offset)?;
+    }
+    Ok(members)
+}
+
+impl ClassIndex {
+    /// Index the class file.
+    /// Errors if it can't be parsed, or if a name it refers to isn't in the constant pool.
+    pub fn build(data: &[u8]) -> Result<ClassIndex, ParseError> {
+        let layout = scan::class_layout(data)?;
+        let class = ClassFileOpt::parse(data, &ParseOptions::default())?;
+        let pool = &class.const_pool;
+
+        // The this class follows the access flags, and the super class follows it
+        let this_offset = layout.access_flags + 2;
+        let super_offset = layout.access_flags + 4;
+        let super_class = if class.super_class.is_zero() {
+            None
+        } else {
+            Some(class_name_at(pool, data, super_offset, class.super_class)?)
+        };
+        let interfaces = class
+            .interfaces
+            .iter()
+            .enumerate()
+            .map(|(i, &interface)| {
+                class_name_at(pool, data, layout.interfaces.start + 2 * i, interface)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(ClassIndex {
+            name: class_name_at(pool, data, this_offset, class.this_class)?,
+            super_class,
+            interfaces,
+            access_flags: class.raw_flags(),
+            version: class.version,
+            layout,
+            fields: members(pool, data, layout.fields, scan::skip_field)?,
+            methods: members(pool, data, layout.methods, scan::skip_method)?,
+        })
+    }
+}
+
+/// The classes that were indexed from one archive or directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveIndex {
+    /// The archive or directory, as named by the caller
+    pub source: String,
+    /// A value which changes whenever the source does, such as its size and modification time
+    /// hashed together, for telling whether the index is still up to date
+    pub stamp: u64,
+    pub classes: Vec<ClassIndex>,
+}
+
+/// An error from reading a saved index
+#[derive(Debug)]
+pub enum IndexReadError {
+    Io(io::Error),
+    /// The data is not a saved index
+    BadMagic,
+    /// The index was saved in a format version that this doesn't know how to read
+    UnsupportedVersion(u16),
+    /// The data ended early or contains text which isn't UTF-8
+    Malformed,
+}
+impl From<io::Error> for IndexReadError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => IndexReadError::Malformed,
+            _ => IndexReadError::Io(err),
+        }
+    }
+}
+impl std::fmt::Display for IndexReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexReadError::Io(err) => write!(f, "io error: {}", err),
+            IndexReadError::BadMagic => f.write_str("not a class index"),
+            IndexReadError::UnsupportedVersion(version) => {
+                write!(f, "unsupported class index version {}", version)
+            }
+            IndexReadError::Malformed => f.write_str("malformed class index"),
+        }
+    }
+}
+impl std::error::Error for IndexReadError {}
+
+/// The indexes of the classes from every archive on a class path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassPathIndex {
+    pub archives: Vec<ArchiveIndex>,
+}
+impl ClassPathIndex {
+    pub fn new() -> ClassPathIndex {
+        ClassPathIndex::default()
+    }
+
+    pub fn get(&self, source: &str) -> Option<&ArchiveIndex> {
+        self.archives
+            .iter()
+            .find(|archive| archive.source == source)
+    }
+
+    /// Get the index of the source if its stamp is still the same, in which case it doesn't need
+    /// to be indexed again
+    pub fn get_fresh(&self, source: &str, stamp: u64) -> Option<&ArchiveIndex> {
+        self.get(source).filter(|archive| archive.stamp == stamp)
+    }
+
+    /// Add the index of an archive, replacing any earlier one from the same source
+    pub fn insert(&mut self, archive: ArchiveIndex) {
+        match self
+            .archives
+            .iter_mut()
+            .find(|existing| existing.source == archive.source)
+        {
+            Some(existing) => *existing = archive,
+            None => self.archives.push(archive),
+        }
+    }
+
+    /// Find the class with the internal name in any of the archives, in the order they were added
+    pub fn find_class(&self, name: &str) -> Option<(&ArchiveIndex, &ClassIndex)> {
+        self.archives.iter().find_map(|archive| {
+            archive
+                .classes
+                .iter()
+                .find(|class| class.name == name)
+                .map(|class| (archive, class))
+        })
+    }
+
+    pub fn write_to(&self, writer: impl Write) -> io::Result<()> {
+        let mut w = IndexWriter(writer);
+        w.0.write_all(MAGIC)?;
+        w.u16(FORMAT_VERSION)?;
+        w.len(self.archives.len())?;
+        for archive in self.archives.iter() {
+            w.string(&archive.source)?;
+            w.u64(archive.stamp)?;
+            w.len(archive.classes.len())?;
+            for class in archive.classes.iter() {
+                w.class(class)?;
+            }
+        }
+        w.0.flush()
+    }
+
+    pub fn read_from(reader: impl Read) -> Result<ClassPathIndex, IndexReadError> {
+        let mut r = IndexReader(reader);
+        let mut magic = [0; 4];
+        r.0.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(IndexReadError::BadMagic);
+        }
+        let version = r.u16()?;
+        if version != FORMAT_VERSION {
+            return Err(IndexReadError::UnsupportedVersion(version));
+        }
+
+        let mut archives = Vec::new();
+        for _ in 0..r.u64()? {
+            let source = r.string()?;
+            let stamp = r.u64()?;
+            let mut classes = Vec::new();
+            for _ in 0..r.u64()? {
+                classes.push(r.class()?);
+            }
+            archives.push(ArchiveIndex {
+                source,
+                stamp,
+                classes,
+            });
+        }
+        Ok(ClassPathIndex { archives })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<ClassPathIndex, IndexReadError> {
+        ClassPathIndex::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Writes numbers big endian, and lengths and offsets as 64-bit numbers
+struct IndexWriter<W: Write>(W);
+impl<W: Write> IndexWriter<W> {
+    fn u16(&mut self, value: u16) -> io::Result<()> {
+        self.0.write_all(&value.to_be_bytes())
+    }
+
+    fn u64(&mut self, value: u64) -> io::Result<()> {
+        self.0.write_all(&value.to_be_bytes())
+    }
+
+    fn len(&mut self, len: usize) -> io::Result<()> {
+        self.u64(len as u64)
+    }
+
+    fn string(&mut self, text: &str) -> io::Result<()> {
+        self.len(text.len())?;
+        self.0.write_all(text.as_bytes())
+    }
+
+    fn table(&mut self, table: Table) -> io::Result<()> {
+        self.u16(table.count)?;
+        self.len(table.start)?;
+        self.len(table.end)
+    }
+
+    fn members(&mut self, members: &[MemberIndex]) -> io::Result<()> {
+        self.len(members.len())?;
+        for member in members {
+            self.u16(member.access_flags)?;
+            self.string(&member.name)?;
+            self.string(&member.descriptor)?;
+            self.len(member.offset)?;
+        }
+        Ok(())
+    }
+
+    fn class(&mut self, class: &ClassIndex) -> io::Result<()> {
+        self.string(&class.name)?;
+        match &class.super_class {
+            Some(super_class) => {
+                self.0.write_all(&[1])?;
+                self.string(super_class)?;
+            }
+            None => self.0.write_all(&[0])?,
+        }
+        self.len(class.interfaces.len())?;
+        for interface in class.interfaces.iter() {
+            self.string(interface)?;
+        }
+        self.u16(class.access_flags)?;
+        self.u16(class.version.major)?;
+        self.u16(class.version.minor)?;
+
+        let layout = &class.layout;
+        self.table(layout.constant_pool)?;
+        self.len(layout.access_flags)?;
+        self.table(layout.interfaces)?;
+        self.table(layout.fields)?;
+        self.table(layout.methods)?;
+        self.table(layout.attributes)?;
+
+        self.members(&class.fields)?;
+        self.members(&class.methods)
+    }
+}
+
+struct IndexReader<R: Read>(R);
+impl<R: Read> IndexReader<R> {
+    fn u8(&mut self) -> Result<u8, IndexReadError> {
+        let mut bytes = [0; 1];
+        self.0.read_exact(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, IndexReadError> {
+        let mut bytes = [0; 2];
+        self.0.read_exact(&mut bytes)?;
+        Ok(u16::from_be_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, IndexReadError> {
+        let mut bytes = [0; 8];
+        self.0.read_exact(&mut bytes)?;
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn len(&mut self) -> Result<usize, IndexReadError> {
+        usize::try_from(self.u64()?).map_err(|_| IndexReadError::Malformed)
+    }
+
+    fn string(&mut self) -> Result<String, IndexReadError> {
+        let len = self.len()?;
+        // Read through `take` so that a corrupt length can't allocate more than the data has
+        let mut bytes = Vec::new();
+        (&mut self.0).take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(IndexReadError::Malformed);
+        }
+        String::from_utf8(bytes).map_err(|_| IndexReadError::Malformed)
+    }
+
+    fn table(&mut self) -> Result<Table, IndexReadError> {
+        Ok(Table {
+            count: self.u16()?,
+            start: self.len()?,
+            end: self.len()?,
+        })
+    }
+
+    fn members(&mut self) -> Result<Vec<MemberIndex>, IndexReadError> {
+        let mut members = Vec::new();
+        for _ in 0..self.u64()? {
+            members.push(MemberIndex {
+                access_flags: self.u16()?,
+                name: self.string()?,
+                descriptor: self.string()?,
+                offset: self.len()?,
+            });
+        }
+        Ok(members)
+    }
+
+    fn class(&mut self) -> Result<ClassIndex, IndexReadError> {
+        let name = self.string()?;
+        let super_class = match self.u8()? {
+            0 => None,
+            1 => Some(self.string()?),
+            _ => return Err(IndexReadError::Malformed),
+        };
+        let mut interfaces = Vec::new();
+        for _ in 0..self.u64()? {
+            interfaces.push(self.string()?);
+        }
+        let access_flags = self.u16()?;
+        let version = ClassFileVersion {
+            major: self.u16()?,
+            minor: self.u16()?,
+        };
+        let layout = ClassLayout {
+            constant_pool: self.table()?,
+            access_flags: self.len()?,
+            interfaces: self.table()?,
+            fields: self.table()?,
+            methods: self.table()?,
+            attributes: self.table()?,
+        };
+
+        Ok(ClassIndex {
+            name,
+            super_class,
+            interfaces,
+            access_flags,
+            version,
+            layout,
+            fields: self.members()?,
+            methods: self.members()?,
+        })
+    }
+}
@@ -0,0 +1,609 @@
+//! Inlining `jsr`/`ret` subroutines.
+//!
+//! Before Java 6, javac compiled `finally` blocks into subroutines which are called with `jsr` and
+//! return with `ret`. Class files from version 51 can't use these instructions, so upgrading old
+//! classes means replacing every call with its own copy of the subroutine, which is what
+//! [`inline_subroutines`] does.
+//!
+//! The inlined code has no StackMapTable. A class that is upgraded to version 51 or later needs
+//! frames to pass verification, so [`ClassFile::inline_subroutines_with_frames`] computes them for
+//! the inlined code.
+
+use std::collections::HashMap;
+
+use crate::assemble::{decode, encode, layout, Insn, Item, Op, ACONST_NULL, GOTO};
+#[cfg(feature = "stackmap")]
+use crate::attribute_info::code_attribute_parser;
+use crate::attribute_info::{names, CodeAttribute, HasAttributes};
+#[cfg(feature = "stackmap")]
+use crate::builder::ConstantPoolBuilder;
+use crate::constant_pool::ConstantPool;
+#[cfg(feature = "stackmap")]
+use crate::frames::{compute_frames, FrameError};
+#[cfg(feature = "stackmap")]
+use crate::parser::ParseData;
+#[cfg(feature = "stackmap")]
+use crate::provider::ClassProvider;
+use crate::util::Reader;
+use crate::ClassFile;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InlineError {
+    /// The code or one of its attributes could not be read
+    Malformed,
+    /// A branch or exception handler refers to the offset, which isn't the start of an instruction
+    InvalidTarget(usize),
+    /// The subroutine at the offset calls itself, directly or through other subroutines
+    RecursiveSubroutine(usize),
+    /// The `ret` at the offset is not inside any subroutine
+    UnmatchedRet(usize),
+    /// The inlined code is longer than the 65535 bytes that the JVM allows
+    CodeTooLong,
+    /// The frames of the inlined code of the method with the index couldn't be computed
+    #[cfg(feature = "stackmap")]
+    Frames { method: usize, error: FrameError },
+}
+
+/// An exception handler, with its offsets turned into instruction indices
+#[derive(Debug, Clone)]
+struct Handler {
+    start: usize,
+    end: usize,
+    handler: usize,
+    catch_type: u16,
+}
+
+/// A copy of the main code or of a subroutine
+#[derive(Debug, Clone)]
+struct Instance {
+    /// The index of the set of instructions that it owns, where zero is the main code
+    set: usize,
+    parent: Option<usize>,
+    /// The label that `ret` jumps to, which is just after the `jsr` in the parent
+    ret: Option<usize>,
+    /// The label of an instruction in this instance is this plus the instruction's index.
+    /// The end of the code has a label too.
+    labels: usize,
+}
+
+struct Inliner {
+    insns: Vec<Insn>,
+    /// The index of the instruction at each offset
+    starts: HashMap<usize, usize>,
+    handlers: Vec<Handler>,
+    /// The instructions reachable in the main code and each subroutine
+    sets: Vec<Vec<bool>>,
+    /// The index of the set for the subroutine at each instruction index
+    subroutines: HashMap<usize, usize>,
+    instances: Vec<Instance>,
+    next_label: usize,
+}
+impl Inliner {
+    fn index(&self, offset: usize) -> Result<usize, InlineError> {
+        self.starts
+            .get(&offset)
+            .copied()
+            .ok_or(InlineError::InvalidTarget(offset))
+    }
+
+    fn successors(&self, i: usize) -> Result<Vec<usize>, InlineError> {
+        let insn = &self.insns[i];
+        let mut next = Vec::new();
+        match &insn.op {
+            Op::Branch { target, .. } => next.push(self.index(*target)?),
+            Op::TableSwitch {
+                default, targets, ..
+            } => {
+                next.push(self.index(*default)?);
+                for &target in targets {
+                    next.push(self.index(target)?);
+                }
+            }
+            Op::LookupSwitch { default, pairs } => {
+                next.push(self.index(*default)?);
+                for &(_, target) in pairs {
+                    next.push(self.index(target)?);
+                }
+            }
+            _ => {}
+        }
+        if insn.instruction.falls_through() {
+            if i + 1 >= self.insns.len() {
+                // Execution would run off the end of the code
+                return Err(InlineError::Malformed);
+            }
+            next.push(i + 1);
+        }
+        Ok(next)
+    }
+
+    /// The instructions reachable from the entry without following calls or returns, along with
+    /// the handlers for any of them
+    fn reachable(&self, entry: usize) -> Result<Vec<bool>, InlineError> {
+        let mut set = vec![false; self.insns.len()];
+        let mut stack = vec![entry];
+        loop {
+            while let Some(i) = stack.pop() {
+                if !set[i] {
+                    set[i] = true;
+                    stack.extend(self.successors(i)?);
+                }
+            }
+
+            for handler in self.handlers.iter() {
+                if !set[handler.handler] && set[handler.start..handler.end].contains(&true) {
+                    stack.push(handler.handler);
+                }
+            }
+            if stack.is_empty() {
+                return Ok(set);
+            }
+        }
+    }
+
+    fn new_instance(&mut self, set: usize, parent: Option<usize>, ret: Option<usize>) -> usize {
+        self.instances.push(Instance {
+            set,
+            parent,
+            ret,
+            labels: self.next_label,
+        });
+        self.next_label += self.insns.len() + 1;
+        self.instances.len() - 1
+    }
+
+    /// The instances from the main code down to this one
+    fn chain(&self, instance: usize) -> Vec<usize> {
+        let mut chain = vec![instance];
+        while let Some(parent) = self.instances[*chain.last().unwrap()].parent {
+            chain.push(parent);
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// The instance that emits the instruction, which is the outermost one containing it.
+    /// Code shared between a subroutine and its caller belongs to the caller.
+    fn owner(&self, instance: usize, i: usize) -> Option<usize> {
+        self.chain(instance)
+            .into_iter()
+            .find(|&owner| self.sets[self.instances[owner].set][i])
+    }
+
+    /// The label to jump to for the instruction from inside the instance
+    fn goto_label(&self, instance: usize, i: usize) -> Result<usize, InlineError> {
+        let owner = self
+            .owner(instance, i)
+            .ok_or(InlineError::InvalidTarget(self.insns[i].offset))?;
+        Ok(self.instances[owner].labels + i)
+    }
+
+    fn subroutine(&mut self, instance: usize, target: usize) -> Result<usize, InlineError> {
+        let entry = self.index(target)?;
+        let set = match self.subroutines.get(&entry) {
+            Some(&set) => set,
+            None => {
+                let set = self.reachable(entry)?;
+                self.sets.push(set);
+                self.subroutines.insert(entry, self.sets.len() - 1);
+                self.sets.len() - 1
+            }
+        };
+        let recursive = self
+            .chain(instance)
+            .into_iter()
+            .any(|i| self.instances[i].set == set);
+        if recursive {
+            return Err(InlineError::RecursiveSubroutine(target));
+        }
+        Ok(set)
+    }
+
+    fn emit(&mut self, instance: usize, out: &mut Vec<Item>) -> Result<(), InlineError> {
+        let labels = self.instances[instance].labels;
+        for i in 0..self.insns.len() {
+            out.push(Item::Bind(labels + i));
+            if self.owner(instance, i) != Some(instance) {
+                continue;
+            }
+
+            let insn = self.insns[i].clone();
+            out.push(Item::Origin(insn.offset));
+            match &insn.op {
+                Op::Other => out.push(Item::Copy(i)),
+                &Op::Branch { opcode, target } => out.push(Item::Jump {
+                    opcode,
+                    target: self.goto_label(instance, self.index(target)?)?,
+                    wide: false,
+                }),
+                &Op::Jsr { target } => {
+                    if i + 1 >= self.insns.len() {
+                        return Err(InlineError::Malformed);
+                    }
+                    let set = self.subroutine(instance, target)?;
+                    let ret = self.goto_label(instance, i + 1)?;
+                    let child = self.new_instance(set, Some(instance), Some(ret));
+                    // The subroutine stores the return address, so give it something to store
+                    out.push(Item::Byte(ACONST_NULL));
+                    out.push(Item::Jump {
+                        opcode: GOTO,
+                        target: self.instances[child].labels + self.index(target)?,
+                        wide: false,
+                    });
+                }
+                Op::Ret => {
+                    // Return from the outermost subroutine containing the ret, which is the one
+                    // whose return address it uses
+                    let ret = self
+                        .chain(instance)
+                        .into_iter()
+                        .filter(|&owner| self.instances[owner].ret.is_some())
+                        .find(|&owner| self.sets[self.instances[owner].set][i])
+                        .and_then(|owner| self.instances[owner].ret)
+                        .ok_or(InlineError::UnmatchedRet(insn.offset))?;
+                    out.push(Item::Jump {
+                        opcode: GOTO,
+                        target: ret,
+                        wide: false,
+                    });
+                }
+                Op::TableSwitch {
+                    default,
+                    low,
+                    targets,
+                } => {
+                    let targets = targets
+                        .iter()
+                        .map(|&target| self.goto_label(instance, self.index(target)?))
+                        .collect::<Result<_, _>>()?;
+                    out.push(Item::TableSwitch {
+                        default: self.goto_label(instance, self.index(*default)?)?,
+                        low: *low,
+                        targets,
+                    });
+                }
+                Op::LookupSwitch { default, pairs } => {
+                    let pairs = pairs
+                        .iter()
+                        .map(|&(key, target)| {
+                            Ok((key, self.goto_label(instance, self.index(target)?)?))
+                        })
+                        .collect::<Result<_, _>>()?;
+                    out.push(Item::LookupSwitch {
+                        default: self.goto_label(instance, self.index(*default)?)?,
+                        pairs,
+                    });
+                }
+            }
+
+            // The next instruction may be emitted by another instance
+            let jsr = matches!(insn.op, Op::Jsr { .. });
+            if !jsr
+                && insn.instruction.falls_through()
+                && self.owner(instance, i + 1) != Some(instance)
+            {
+                out.push(Item::Jump {
+                    opcode: GOTO,
+                    target: self.goto_label(instance, i + 1)?,
+                    wide: false,
+                });
+            }
+        }
+        out.push(Item::Bind(labels + self.insns.len()));
+        Ok(())
+    }
+}
+
+/// Inline the subroutines of the code, returning the info of the new Code attribute, or None if
+/// the code doesn't call any subroutines.
+///
+/// Every `jsr` is replaced by `aconst_null` and a `goto` to its own copy of the subroutine, which
+/// the subroutine stores in place of the return address, and every `ret` by a `goto` back to the
+/// instruction after the call. Exception handlers are copied for each copy of the code they cover.
+/// Code which can't be reached is dropped.
+///
+/// The LineNumberTable is rewritten to match. The LocalVariableTable, LocalVariableTypeTable,
+/// StackMapTable, and type annotations of the code are dropped, since they describe offsets which
+/// may now be duplicated or gone. Other nested attributes are kept unchanged.
+pub fn inline_subroutines(
+    code: &CodeAttribute,
+    pool: &ConstantPool,
+    class_file_data: &[u8],
+) -> Result<Option<Vec<u8>>, InlineError> {
+    let bytes = class_file_data
+        .get(code.code.clone())
+        .ok_or(InlineError::Malformed)?;
+    let insns = decode(bytes).ok_or(InlineError::Malformed)?;
+    if !insns.iter().any(|insn| matches!(insn.op, Op::Jsr { .. })) {
+        return Ok(None);
+    }
+
+    let starts: HashMap<usize, usize> = insns
+        .iter()
+        .enumerate()
+        .map(|(i, insn)| (insn.offset, i))
+        .collect();
+    // The end of the code is a valid end for a handler's range
+    let index_or_end = |offset: usize| match starts.get(&offset) {
+        Some(&i) => Ok(i),
+        None if offset == bytes.len() => Ok(insns.len()),
+        None => Err(InlineError::InvalidTarget(offset)),
+    };
+    let handlers = code
+        .exception_table
+        .iter()
+        .map(|entry| {
+            let start = index_or_end(usize::from(entry.start_pc.0))?;
+            let end = index_or_end(usize::from(entry.end_pc.0))?;
+            let handler = index_or_end(usize::from(entry.handler_pc.0))?;
+            if start > end || handler >= insns.len() {
+                return Err(InlineError::Malformed);
+            }
+            Ok(Handler {
+                start,
+                end,
+                handler,
+                catch_type: entry.catch_type.0,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut inliner = Inliner {
+        insns,
+        starts,
+        handlers,
+        sets: Vec::new(),
+        subroutines: HashMap::new(),
+        instances: Vec::new(),
+        next_label: 0,
+    };
+    let main = inliner.reachable(0)?;
+    inliner.sets.push(main);
+    inliner.new_instance(0, None, None);
+
+    // New instances are added for each call while emitting, and are placed after the others
+    let mut items = Vec::new();
+    let mut instance = 0;
+    let mut copied = 0;
+    while instance < inliner.instances.len() {
+        let start = items.len();
+        inliner.emit(instance, &mut items)?;
+        // Stop early if the copies are already too long, since nested subroutines can make the
+        // number of copies grow exponentially
+        copied += items[start..]
+            .iter()
+            .map(|item| match item {
+                Item::Copy(i) => inliner.insns[*i].len,
+                _ => 0,
+            })
+            .sum::<usize>();
+        if copied > usize::from(u16::MAX) {
+            return Err(InlineError::CodeTooLong);
+        }
+        instance += 1;
+    }
+
+    let insns = &inliner.insns;
+    let (labels, positions) = layout(&mut items, insns, inliner.next_label);
+    let (out, origins) = encode(&items, insns, bytes, &labels, &positions);
+    let code_length = u16::try_from(out.len()).map_err(|_| InlineError::CodeTooLong)?;
+
+    let mut table = Vec::new();
+    for (i, instance) in inliner.instances.iter().enumerate() {
+        for handler in inliner.handlers.iter() {
+            let start = labels[instance.labels + handler.start];
+            let end = labels[instance.labels + handler.end];
+            if start == end {
+                continue;
+            }
+            let target = labels[inliner.goto_label(i, handler.handler)?];
+            table.push((start, end, target, handler.catch_type));
+        }
+    }
+    let table_length = u16::try_from(table.len()).map_err(|_| InlineError::CodeTooLong)?;
+
+    let mut attributes = Vec::new();
+    for attr in code.attributes.iter() {
+        let name = pool
+            .get_t(attr.attribute_name_index)
+            .map(|name| name.as_text(class_file_data))
+            .ok_or(InlineError::Malformed)?;
+        let info = class_file_data
+            .get(attr.info.clone())
+            .ok_or(InlineError::Malformed)?;
+        match name.as_ref() {
+            names::LINE_NUMBER_TABLE => {
+                let info = line_numbers(info, &origins)?;
+                attributes.push((attr, info));
+            }
+            names::LOCAL_VARIABLE_TABLE
+            | names::LOCAL_VARIABLE_TYPE_TABLE
+            | names::STACK_MAP_TABLE
+            | names::RUNTIME_VISIBLE_TYPE_ANNOTATIONS
+            | names::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {}
+            _ => attributes.push((attr, info.to_vec())),
+        }
+    }
+
+    let mut info = Vec::new();
+    info.extend_from_slice(&code.max_stack.to_be_bytes());
+    info.extend_from_slice(&code.max_locals.to_be_bytes());
+    info.extend_from_slice(&u32::from(code_length).to_be_bytes());
+    info.extend_from_slice(&out);
+    info.extend_from_slice(&table_length.to_be_bytes());
+    for (start, end, handler, catch_type) in table {
+        for value in [start as u16, end as u16, handler as u16, catch_type] {
+            info.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+    info.extend_from_slice(&(attributes.len() as u16).to_be_bytes());
+    for (attr, attr_info) in attributes {
+        info.extend_from_slice(&attr.attribute_name_index.0.to_be_bytes());
+        let len = u32::try_from(attr_info.len()).map_err(|_| InlineError::Malformed)?;
+        info.extend_from_slice(&len.to_be_bytes());
+        info.extend_from_slice(&attr_info);
+    }
+
+    Ok(Some(info))
+}
+
+/// Rewrite a LineNumberTable so that each line starts at every copy of its instruction
+fn line_numbers(info: &[u8], origins: &[(usize, usize)]) -> Result<Vec<u8>, InlineError> {
+    let mut r = Reader {
+        bytes: info,
+        pos: 0,
+    };
+    let count = r.u16().ok_or(InlineError::Malformed)?;
+    let mut lines: HashMap<usize, Vec<u16>> = HashMap::new();
+    for _ in 0..count {
+        let start_pc = r.u16().ok_or(InlineError::Malformed)?;
+        let line = r.u16().ok_or(InlineError::Malformed)?;
+        lines.entry(usize::from(start_pc)).or_default().push(line);
+    }
+
+    let mut entries = Vec::new();
+    for &(pos, offset) in origins {
+        for &line in lines.get(&offset).into_iter().flatten() {
+            entries.push((pos as u16, line));
+        }
+    }
+    let count = u16::try_from(entries.len()).map_err(|_| InlineError::CodeTooLong)?;
+
+    let mut out = count.to_be_bytes().to_vec();
+    for (pos, line) in entries {
+        out.extend_from_slice(&pos.to_be_bytes());
+        out.extend_from_slice(&line.to_be_bytes());
+    }
+    Ok(out)
+}
+
+/// Compute the frames of inlined code, see [`compute_frames`], and add its StackMapTable to the
+/// end of its attributes
+#[cfg(feature = "stackmap")]
+fn add_frames(
+    class_file: &ClassFile,
+    method: usize,
+    info: &mut Vec<u8>,
+    pool: &mut ConstantPool,
+    data: &mut Vec<u8>,
+    provider: &dyn ClassProvider,
+) -> Result<(), InlineError> {
+    let (_, code) =
+        code_attribute_parser(ParseData::new(info)).map_err(|_| InlineError::Malformed)?;
+    let frames = compute_frames(
+        class_file,
+        &class_file.methods[method],
+        &info[code.code.clone()],
+        &code.exception_table,
+        code.max_locals,
+        data,
+        provider,
+    )
+    .and_then(|frames| frames.to_stack_map_table(pool, data))
+    .map_err(|error| InlineError::Frames { method, error })?;
+    if frames.entries.is_empty() {
+        return Ok(());
+    }
+
+    let name = ConstantPoolBuilder::with_pool(pool, data, |constants| {
+        constants.utf8(names::STACK_MAP_TABLE)
+    })
+    .map_err(|_| InlineError::Frames {
+        method,
+        error: FrameError::PoolFull,
+    })?;
+    let table = frames.to_bytes();
+    let len = u32::try_from(table.len()).map_err(|_| InlineError::CodeTooLong)?;
+    let at = code.code.end + 2 + 8 * usize::from(code.exception_table_length);
+    let count = code.attributes_count + 1;
+    info[at..at + 2].copy_from_slice(&count.to_be_bytes());
+    info.extend_from_slice(&name.0.to_be_bytes());
+    info.extend_from_slice(&len.to_be_bytes());
+    info.extend_from_slice(&table);
+    Ok(())
+}
+
+impl ClassFile {
+    /// Inline the subroutines of each method, returning the index and new Code attribute info of
+    /// each method that changed
+    fn inline_methods(&self, data: &[u8]) -> Result<Vec<(usize, Vec<u8>)>, InlineError> {
+        let pool = &self.const_pool;
+        let mut rewritten = Vec::new();
+        for (i, method) in self.methods.iter().enumerate() {
+            let code = method
+                .find_attribute::<CodeAttribute>(pool, data)
+                .map_err(|_| InlineError::Malformed)?;
+            if let Some(code) = code {
+                if let Some(info) = inline_subroutines(&code, pool, data)? {
+                    rewritten.push((i, info));
+                }
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// Write the new Code attributes of the methods to the end of `data`
+    fn replace_code(&mut self, data: &mut Vec<u8>, rewritten: Vec<(usize, Vec<u8>)>) {
+        if !rewritten.is_empty() {
+            self.typed_attributes = None;
+        }
+        let pool = &self.const_pool;
+        for (i, info) in rewritten {
+            let start = data.len();
+            data.extend_from_slice(&info);
+            let method = &mut self.methods[i];
+            let code = method
+                .attributes
+                .iter_mut()
+                .find(|attr| {
+                    pool.get_t(attr.attribute_name_index)
+                        .map(|name| name.as_text(data) == names::CODE)
+                        .unwrap_or(false)
+                })
+                .expect("the method's Code attribute was just found");
+            let old = std::mem::replace(&mut code.info, start..data.len());
+            self.stale_ranges.mark(old);
+            code.attribute_length = info.len() as u32;
+        }
+    }
+
+    /// Inline the subroutines of every method, see [`inline_subroutines`], returning how many
+    /// methods were changed.
+    /// The new Code attributes are written to the end of `data`. On error, neither the class file
+    /// nor `data` is modified.
+    pub fn inline_subroutines(&mut self, data: &mut Vec<u8>) -> Result<usize, InlineError> {
+        let rewritten = self.inline_methods(data)?;
+        let count = rewritten.len();
+        self.replace_code(data, rewritten);
+        Ok(count)
+    }
+
+    /// Inline the subroutines of every method like [`ClassFile::inline_subroutines`], and give
+    /// the inlined code a StackMapTable, see [`compute_frames`]. The classes that the frames merge
+    /// are loaded from the provider.
+    /// Class entries for the frames are added to the constant pool. The version of the class is
+    /// left as it is.
+    /// On error, neither the class file nor `data` is modified.
+    #[cfg(feature = "stackmap")]
+    pub fn inline_subroutines_with_frames(
+        &mut self,
+        data: &mut Vec<u8>,
+        provider: &dyn ClassProvider,
+    ) -> Result<usize, InlineError> {
+        let mut rewritten = self.inline_methods(data)?;
+        let len = data.len();
+        let mut pool = self.const_pool.clone();
+        for (method, info) in rewritten.iter_mut() {
+            if let Err(err) = add_frames(self, *method, info, &mut pool, data, provider) {
+                data.truncate(len);
+                return Err(err);
+            }
+        }
+
+        let count = rewritten.len();
+        self.const_pool = pool;
+        self.const_pool_size = self.const_pool.len() + 1;
+        self.replace_code(data, rewritten);
+        Ok(count)
+    }
+}
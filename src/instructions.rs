@@ -0,0 +1,1177 @@
+//! Decoding the bytecode of a Code attribute into instructions.
+//!
+//! Branch and switch targets are kept as they are encoded, relative to the offset of the
+//! instruction, and constant pool operands as raw indices.
+//! [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-6.html)
+
+use crate::attribute_info::{CodeAttribute, CodeAttributeOpt};
+use crate::constant_info::*;
+use crate::constant_pool::ConstantPoolIndexRaw;
+use crate::util::Reader;
+
+/// A JVM instruction with its operands.
+/// Branches hold the offset of their target relative to the start of the instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Nop,
+    AconstNull,
+    IconstM1,
+    Iconst0,
+    Iconst1,
+    Iconst2,
+    Iconst3,
+    Iconst4,
+    Iconst5,
+    Lconst0,
+    Lconst1,
+    Fconst0,
+    Fconst1,
+    Fconst2,
+    Dconst0,
+    Dconst1,
+    /// Push the byte, sign extended to an int
+    Bipush(i8),
+    /// Push the short, sign extended to an int
+    Sipush(i16),
+    /// Push the constant with the index, which only has one byte
+    Ldc(u8),
+    LdcW(ConstantPoolIndexRaw<ConstantInfo>),
+    Ldc2W(ConstantPoolIndexRaw<ConstantInfo>),
+    Iload(u8),
+    Lload(u8),
+    Fload(u8),
+    Dload(u8),
+    Aload(u8),
+    Iload0,
+    Iload1,
+    Iload2,
+    Iload3,
+    Lload0,
+    Lload1,
+    Lload2,
+    Lload3,
+    Fload0,
+    Fload1,
+    Fload2,
+    Fload3,
+    Dload0,
+    Dload1,
+    Dload2,
+    Dload3,
+    Aload0,
+    Aload1,
+    Aload2,
+    Aload3,
+    Iaload,
+    Laload,
+    Faload,
+    Daload,
+    Aaload,
+    Baload,
+    Caload,
+    Saload,
+    Istore(u8),
+    Lstore(u8),
+    Fstore(u8),
+    Dstore(u8),
+    Astore(u8),
+    Istore0,
+    Istore1,
+    Istore2,
+    Istore3,
+    Lstore0,
+    Lstore1,
+    Lstore2,
+    Lstore3,
+    Fstore0,
+    Fstore1,
+    Fstore2,
+    Fstore3,
+    Dstore0,
+    Dstore1,
+    Dstore2,
+    Dstore3,
+    Astore0,
+    Astore1,
+    Astore2,
+    Astore3,
+    Iastore,
+    Lastore,
+    Fastore,
+    Dastore,
+    Aastore,
+    Bastore,
+    Castore,
+    Sastore,
+    Pop,
+    Pop2,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    Swap,
+    Iadd,
+    Ladd,
+    Fadd,
+    Dadd,
+    Isub,
+    Lsub,
+    Fsub,
+    Dsub,
+    Imul,
+    Lmul,
+    Fmul,
+    Dmul,
+    Idiv,
+    Ldiv,
+    Fdiv,
+    Ddiv,
+    Irem,
+    Lrem,
+    Frem,
+    Drem,
+    Ineg,
+    Lneg,
+    Fneg,
+    Dneg,
+    Ishl,
+    Lshl,
+    Ishr,
+    Lshr,
+    Iushr,
+    Lushr,
+    Iand,
+    Land,
+    Ior,
+    Lor,
+    Ixor,
+    Lxor,
+    Iinc {
+        index: u8,
+        value: i8,
+    },
+    I2l,
+    I2f,
+    I2d,
+    L2i,
+    L2f,
+    L2d,
+    F2i,
+    F2l,
+    F2d,
+    D2i,
+    D2l,
+    D2f,
+    I2b,
+    I2c,
+    I2s,
+    Lcmp,
+    Fcmpl,
+    Fcmpg,
+    Dcmpl,
+    Dcmpg,
+    Ifeq(i16),
+    Ifne(i16),
+    Iflt(i16),
+    Ifge(i16),
+    Ifgt(i16),
+    Ifle(i16),
+    IfIcmpeq(i16),
+    IfIcmpne(i16),
+    IfIcmplt(i16),
+    IfIcmpge(i16),
+    IfIcmpgt(i16),
+    IfIcmple(i16),
+    IfAcmpeq(i16),
+    IfAcmpne(i16),
+    Goto(i16),
+    Jsr(i16),
+    /// Return from a subroutine to the address in the local
+    Ret(u8),
+    Ireturn,
+    Lreturn,
+    Freturn,
+    Dreturn,
+    Areturn,
+    Return,
+    Getstatic(ConstantPoolIndexRaw<FieldRefConstant>),
+    Putstatic(ConstantPoolIndexRaw<FieldRefConstant>),
+    Getfield(ConstantPoolIndexRaw<FieldRefConstant>),
+    Putfield(ConstantPoolIndexRaw<FieldRefConstant>),
+    Invokevirtual(ConstantPoolIndexRaw<MethodRefConstant>),
+    Invokespecial(ConstantPoolIndexRaw<ConstantInfo>),
+    Invokestatic(ConstantPoolIndexRaw<ConstantInfo>),
+    New(ConstantPoolIndexRaw<ClassConstant>),
+    /// Create an array of the primitive type, from 4 for boolean to 11 for long
+    Newarray(u8),
+    Anewarray(ConstantPoolIndexRaw<ClassConstant>),
+    Arraylength,
+    Athrow,
+    Checkcast(ConstantPoolIndexRaw<ClassConstant>),
+    Instanceof(ConstantPoolIndexRaw<ClassConstant>),
+    Monitorenter,
+    Monitorexit,
+    Ifnull(i16),
+    Ifnonnull(i16),
+    GotoW(i32),
+    JsrW(i32),
+    /// Jump to the offset at `low + value`, or the default if the value isn't between `low` and
+    /// `high`
+    Tableswitch {
+        default: i32,
+        low: i32,
+        high: i32,
+        offsets: Vec<i32>,
+    },
+    /// Jump to the offset paired with the value, or the default if there is none.
+    /// The pairs are sorted by their value.
+    Lookupswitch {
+        default: i32,
+        pairs: Vec<(i32, i32)>,
+    },
+    Invokeinterface {
+        index: ConstantPoolIndexRaw<InterfaceMethodRefConstant>,
+        /// The number of stack slots that the arguments and receiver take up
+        count: u8,
+    },
+    Invokedynamic(ConstantPoolIndexRaw<InvokeDynamicConstant>),
+    /// A load, store, `ret`, or `iinc` with a two byte local index
+    Wide(WideInstruction),
+    Multianewarray {
+        index: ConstantPoolIndexRaw<ClassConstant>,
+        dimensions: u8,
+    },
+}
+impl Instruction {
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Instruction::Nop => 0x00,
+            Instruction::AconstNull => 0x01,
+            Instruction::IconstM1 => 0x02,
+            Instruction::Iconst0 => 0x03,
+            Instruction::Iconst1 => 0x04,
+            Instruction::Iconst2 => 0x05,
+            Instruction::Iconst3 => 0x06,
+            Instruction::Iconst4 => 0x07,
+            Instruction::Iconst5 => 0x08,
+            Instruction::Lconst0 => 0x09,
+            Instruction::Lconst1 => 0x0a,
+            Instruction::Fconst0 => 0x0b,
+            Instruction::Fconst1 => 0x0c,
+            Instruction::Fconst2 => 0x0d,
+            Instruction::Dconst0 => 0x0e,
+            Instruction::Dconst1 => 0x0f,
+            Instruction::Bipush(_) => 0x10,
+            Instruction::Sipush(_) => 0x11,
+            Instruction::Ldc(_) => 0x12,
+            Instruction::LdcW(_) => 0x13,
+            Instruction::Ldc2W(_) => 0x14,
+            Instruction::Iload(_) => 0x15,
+            Instruction::Lload(_) => 0x16,
+            Instruction::Fload(_) => 0x17,
+            Instruction::Dload(_) => 0x18,
+            Instruction::Aload(_) => 0x19,
+            Instruction::Iload0 => 0x1a,
+            Instruction::Iload1 => 0x1b,
+            Instruction::Iload2 => 0x1c,
+            Instruction::Iload3 => 0x1d,
+            Instruction::Lload0 => 0x1e,
+            Instruction::Lload1 => 0x1f,
+            Instruction::Lload2 => 0x20,
+            Instruction::Lload3 => 0x21,
+            Instruction::Fload0 => 0x22,
+            Instruction::Fload1 => 0x23,
+            Instruction::Fload2 => 0x24,
+            Instruction::Fload3 => 0x25,
+            Instruction::Dload0 => 0x26,
+            Instruction::Dload1 => 0x27,
+            Instruction::Dload2 => 0x28,
+            Instruction::Dload3 => 0x29,
+            Instruction::Aload0 => 0x2a,
+            Instruction::Aload1 => 0x2b,
+            Instruction::Aload2 => 0x2c,
+            Instruction::Aload3 => 0x2d,
+            Instruction::Iaload => 0x2e,
+            Instruction::Laload => 0x2f,
+            Instruction::Faload => 0x30,
+            Instruction::Daload => 0x31,
+            Instruction::Aaload => 0x32,
+            Instruction::Baload => 0x33,
+            Instruction::Caload => 0x34,
+            Instruction::Saload => 0x35,
+            Instruction::Istore(_) => 0x36,
+            Instruction::Lstore(_) => 0x37,
+            Instruction::Fstore(_) => 0x38,
+            Instruction::Dstore(_) => 0x39,
+            Instruction::Astore(_) => 0x3a,
+            Instruction::Istore0 => 0x3b,
+            Instruction::Istore1 => 0x3c,
+            Instruction::Istore2 => 0x3d,
+            Instruction::Istore3 => 0x3e,
+            Instruction::Lstore0 => 0x3f,
+            Instruction::Lstore1 => 0x40,
+            Instruction::Lstore2 => 0x41,
+            Instruction::Lstore3 => 0x42,
+            Instruction::Fstore0 => 0x43,
+            Instruction::Fstore1 => 0x44,
+            Instruction::Fstore2 => 0x45,
+            Instruction::Fstore3 => 0x46,
+            Instruction::Dstore0 => 0x47,
+            Instruction::Dstore1 => 0x48,
+            Instruction::Dstore2 => 0x49,
+            Instruction::Dstore3 => 0x4a,
+            Instruction::Astore0 => 0x4b,
+            Instruction::Astore1 => 0x4c,
+            Instruction::Astore2 => 0x4d,
+            Instruction::Astore3 => 0x4e,
+            Instruction::Iastore => 0x4f,
+            Instruction::Lastore => 0x50,
+            Instruction::Fastore => 0x51,
+            Instruction::Dastore => 0x52,
+            Instruction::Aastore => 0x53,
+            Instruction::Bastore => 0x54,
+            Instruction::Castore => 0x55,
+            Instruction::Sastore => 0x56,
+            Instruction::Pop => 0x57,
+            Instruction::Pop2 => 0x58,
+            Instruction::Dup => 0x59,
+            Instruction::DupX1 => 0x5a,
+            Instruction::DupX2 => 0x5b,
+            Instruction::Dup2 => 0x5c,
+            Instruction::Dup2X1 => 0x5d,
+            Instruction::Dup2X2 => 0x5e,
+            Instruction::Swap => 0x5f,
+            Instruction::Iadd => 0x60,
+            Instruction::Ladd => 0x61,
+            Instruction::Fadd => 0x62,
+            Instruction::Dadd => 0x63,
+            Instruction::Isub => 0x64,
+            Instruction::Lsub => 0x65,
+            Instruction::Fsub => 0x66,
+            Instruction::Dsub => 0x67,
+            Instruction::Imul => 0x68,
+            Instruction::Lmul => 0x69,
+            Instruction::Fmul => 0x6a,
+            Instruction::Dmul => 0x6b,
+            Instruction::Idiv => 0x6c,
+            Instruction::Ldiv => 0x6d,
+            Instruction::Fdiv => 0x6e,
+            Instruction::Ddiv => 0x6f,
+            Instruction::Irem => 0x70,
+            Instruction::Lrem => 0x71,
+            Instruction::Frem => 0x72,
+            Instruction::Drem => 0x73,
+            Instruction::Ineg => 0x74,
+            Instruction::Lneg => 0x75,
+            Instruction::Fneg => 0x76,
+            Instruction::Dneg => 0x77,
+            Instruction::Ishl => 0x78,
+            Instruction::Lshl => 0x79,
+            Instruction::Ishr => 0x7a,
+            Instruction::Lshr => 0x7b,
+            Instruction::Iushr => 0x7c,
+            Instruction::Lushr => 0x7d,
+            Instruction::Iand => 0x7e,
+            Instruction::Land => 0x7f,
+            Instruction::Ior => 0x80,
+            Instruction::Lor => 0x81,
+            Instruction::Ixor => 0x82,
+            Instruction::Lxor => 0x83,
+            Instruction::Iinc { .. } => 0x84,
+            Instruction::I2l => 0x85,
+            Instruction::I2f => 0x86,
+            Instruction::I2d => 0x87,
+            Instruction::L2i => 0x88,
+            Instruction::L2f => 0x89,
+            Instruction::L2d => 0x8a,
+            Instruction::F2i => 0x8b,
+            Instruction::F2l => 0x8c,
+            Instruction::F2d => 0x8d,
+            Instruction::D2i => 0x8e,
+            Instruction::D2l => 0x8f,
+            Instruction::D2f => 0x90,
+            Instruction::I2b => 0x91,
+            Instruction::I2c => 0x92,
+            Instruction::I2s => 0x93,
+            Instruction::Lcmp => 0x94,
+            Instruction::Fcmpl => 0x95,
+            Instruction::Fcmpg => 0x96,
+            Instruction::Dcmpl => 0x97,
+            Instruction::Dcmpg => 0x98,
+            Instruction::Ifeq(_) => 0x99,
+            Instruction::Ifne(_) => 0x9a,
+            Instruction::Iflt(_) => 0x9b,
+            Instruction::Ifge(_) => 0x9c,
+            Instruction::Ifgt(_) => 0x9d,
+            Instruction::Ifle(_) => 0x9e,
+            Instruction::IfIcmpeq(_) => 0x9f,
+            Instruction::IfIcmpne(_) => 0xa0,
+            Instruction::IfIcmplt(_) => 0xa1,
+            Instruction::IfIcmpge(_) => 0xa2,
+            Instruction::IfIcmpgt(_) => 0xa3,
+            Instruction::IfIcmple(_) => 0xa4,
+            Instruction::IfAcmpeq(_) => 0xa5,
+            Instruction::IfAcmpne(_) => 0xa6,
+            Instruction::Goto(_) => 0xa7,
+            Instruction::Jsr(_) => 0xa8,
+            Instruction::Ret(_) => 0xa9,
+            Instruction::Ireturn => 0xac,
+            Instruction::Lreturn => 0xad,
+            Instruction::Freturn => 0xae,
+            Instruction::Dreturn => 0xaf,
+            Instruction::Areturn => 0xb0,
+            Instruction::Return => 0xb1,
+            Instruction::Getstatic(_) => 0xb2,
+            Instruction::Putstatic(_) => 0xb3,
+            Instruction::Getfield(_) => 0xb4,
+            Instruction::Putfield(_) => 0xb5,
+            Instruction::Invokevirtual(_) => 0xb6,
+            Instruction::Invokespecial(_) => 0xb7,
+            Instruction::Invokestatic(_) => 0xb8,
+            Instruction::New(_) => 0xbb,
+            Instruction::Newarray(_) => 0xbc,
+            Instruction::Anewarray(_) => 0xbd,
+            Instruction::Arraylength => 0xbe,
+            Instruction::Athrow => 0xbf,
+            Instruction::Checkcast(_) => 0xc0,
+            Instruction::Instanceof(_) => 0xc1,
+            Instruction::Monitorenter => 0xc2,
+            Instruction::Monitorexit => 0xc3,
+            Instruction::Ifnull(_) => 0xc6,
+            Instruction::Ifnonnull(_) => 0xc7,
+            Instruction::GotoW(_) => 0xc8,
+            Instruction::JsrW(_) => 0xc9,
+            Instruction::Tableswitch { .. } => 0xaa,
+            Instruction::Lookupswitch { .. } => 0xab,
+            Instruction::Invokeinterface { .. } => 0xb9,
+            Instruction::Invokedynamic(_) => 0xba,
+            Instruction::Wide(_) => 0xc4,
+            Instruction::Multianewarray { .. } => 0xc5,
+        }
+    }
+
+    /// The name of the instruction as the JVM specification writes it, such as `invokevirtual`
+    pub fn mnemonic(&self) -> &'static str {
+        MNEMONICS[usize::from(self.opcode())]
+    }
+
+    /// The offsets that the instruction can jump to, relative to its own offset.
+    /// A `jsr` jumps to the start of its subroutine.
+    pub fn branch_targets(&self) -> Vec<i32> {
+        use Instruction::*;
+        match self {
+            Ifeq(offset) | Ifne(offset) | Iflt(offset) | Ifge(offset) | Ifgt(offset)
+            | Ifle(offset) | IfIcmpeq(offset) | IfIcmpne(offset) | IfIcmplt(offset)
+            | IfIcmpge(offset) | IfIcmpgt(offset) | IfIcmple(offset) | IfAcmpeq(offset)
+            | IfAcmpne(offset) | Ifnull(offset) | Ifnonnull(offset) | Goto(offset)
+            | Jsr(offset) => vec![i32::from(*offset)],
+            GotoW(offset) | JsrW(offset) => vec![*offset],
+            Tableswitch {
+                default, offsets, ..
+            } => std::iter::once(*default)
+                .chain(offsets.iter().copied())
+                .collect(),
+            Lookupswitch { default, pairs } => std::iter::once(*default)
+                .chain(pairs.iter().map(|&(_, offset)| offset))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The index of the constant that the instruction refers to, which is always the operand
+    /// just after the opcode. An `ldc` has a one byte index.
+    pub fn constant_index(&self) -> Option<u16> {
+        use Instruction::*;
+        Some(match self {
+            Ldc(index) => u16::from(*index),
+            LdcW(index) | Ldc2W(index) | Invokespecial(index) | Invokestatic(index) => index.0,
+            Getstatic(index) | Putstatic(index) | Getfield(index) | Putfield(index) => index.0,
+            Invokevirtual(index) => index.0,
+            New(index) | Anewarray(index) | Checkcast(index) | Instanceof(index) => index.0,
+            Multianewarray { index, .. } => index.0,
+            Invokeinterface { index, .. } => index.0,
+            Invokedynamic(index) => index.0,
+            _ => return None,
+        })
+    }
+
+    /// Whether execution can continue to the next instruction.
+    /// A `jsr` does once its subroutine returns, while a `ret` goes back to the instruction after
+    /// some `jsr` instead.
+    pub fn falls_through(&self) -> bool {
+        use Instruction::*;
+        !matches!(
+            self,
+            Goto(_)
+                | GotoW(_)
+                | Tableswitch { .. }
+                | Lookupswitch { .. }
+                | Ret(_)
+                | Wide(WideInstruction::Ret(_))
+                | Ireturn
+                | Lreturn
+                | Freturn
+                | Dreturn
+                | Areturn
+                | Return
+                | Athrow
+        )
+    }
+}
+
+/// An instruction which is modified by `wide`, with a two byte local index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WideInstruction {
+    Iload(u16),
+    Lload(u16),
+    Fload(u16),
+    Dload(u16),
+    Aload(u16),
+    Istore(u16),
+    Lstore(u16),
+    Fstore(u16),
+    Dstore(u16),
+    Astore(u16),
+    Ret(u16),
+    Iinc { index: u16, value: i16 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionError {
+    /// The instruction at the offset runs past the end of the code
+    Truncated {
+        offset: usize,
+    },
+    InvalidOpcode {
+        offset: usize,
+        opcode: u8,
+    },
+    /// The `wide` at the offset modifies an opcode that can't be widened
+    InvalidWide {
+        offset: usize,
+        opcode: u8,
+    },
+    /// A tableswitch whose high is below its low, or a lookupswitch with a negative count or
+    /// unsorted keys
+    InvalidSwitch {
+        offset: usize,
+    },
+    /// The instruction starts past the 65535 bytes that code can be
+    CodeTooLong {
+        offset: usize,
+    },
+}
+
+const MNEMONICS: [&str; 0xca] = [
+    "nop",
+    "aconst_null",
+    "iconst_m1",
+    "iconst_0",
+    "iconst_1",
+    "iconst_2",
+    "iconst_3",
+    "iconst_4",
+    "iconst_5",
+    "lconst_0",
+    "lconst_1",
+    "fconst_0",
+    "fconst_1",
+    "fconst_2",
+    "dconst_0",
+    "dconst_1",
+    "bipush",
+    "sipush",
+    "ldc",
+    "ldc_w",
+    "ldc2_w",
+    "iload",
+    "lload",
+    "fload",
+    "dload",
+    "aload",
+    "iload_0",
+    "iload_1",
+    "iload_2",
+    "iload_3",
+    "lload_0",
+    "lload_1",
+    "lload_2",
+    "lload_3",
+    "fload_0",
+    "fload_1",
+    "fload_2",
+    "fload_3",
+    "dload_0",
+    "dload_1",
+    "dload_2",
+    "dload_3",
+    "aload_0",
+    "aload_1",
+    "aload_2",
+    "aload_3",
+    "iaload",
+    "laload",
+    "faload",
+    "daload",
+    "aaload",
+    "baload",
+    "caload",
+    "saload",
+    "istore",
+    "lstore",
+    "fstore",
+    "dstore",
+    "astore",
+    "istore_0",
+    "istore_1",
+    "istore_2",
+    "istore_3",
+    "lstore_0",
+    "lstore_1",
+    "lstore_2",
+    "lstore_3",
+    "fstore_0",
+    "fstore_1",
+    "fstore_2",
+    "fstore_3",
+    "dstore_0",
+    "dstore_1",
+    "dstore_2",
+    "dstore_3",
+    "astore_0",
+    "astore_1",
+    "astore_2",
+    "astore_3",
+    "iastore",
+    "lastore",
+    "fastore",
+    "dastore",
+    "aastore",
+    "bastore",
+    "castore",
+    "sastore",
+    "pop",
+    "pop2",
+    "dup",
+    "dup_x1",
+    "dup_x2",
+    "dup2",
+    "dup2_x1",
+    "dup2_x2",
+    "swap",
+    "iadd",
+    "ladd",
+    "fadd",
+    "dadd",
+    "isub",
+    "lsub",
+    "fsub",
+    "dsub",
+    "imul",
+    "lmul",
+    "fmul",
+    "dmul",
+    "idiv",
+    "ldiv",
+    "fdiv",
+    "ddiv",
+    "irem",
+    "lrem",
+    "frem",
+    "drem",
+    "ineg",
+    "lneg",
+    "fneg",
+    "dneg",
+    "ishl",
+    "lshl",
+    "ishr",
+    "lshr",
+    "iushr",
+    "lushr",
+    "iand",
+    "land",
+    "ior",
+    "lor",
+    "ixor",
+    "lxor",
+    "iinc",
+    "i2l",
+    "i2f",
+    "i2d",
+    "l2i",
+    "l2f",
+    "l2d",
+    "f2i",
+    "f2l",
+    "f2d",
+    "d2i",
+    "d2l",
+    "d2f",
+    "i2b",
+    "i2c",
+    "i2s",
+    "lcmp",
+    "fcmpl",
+    "fcmpg",
+    "dcmpl",
+    "dcmpg",
+    "ifeq",
+    "ifne",
+    "iflt",
+    "ifge",
+    "ifgt",
+    "ifle",
+    "if_icmpeq",
+    "if_icmpne",
+    "if_icmplt",
+    "if_icmpge",
+    "if_icmpgt",
+    "if_icmple",
+    "if_acmpeq",
+    "if_acmpne",
+    "goto",
+    "jsr",
+    "ret",
+    "tableswitch",
+    "lookupswitch",
+    "ireturn",
+    "lreturn",
+    "freturn",
+    "dreturn",
+    "areturn",
+    "return",
+    "getstatic",
+    "putstatic",
+    "getfield",
+    "putfield",
+    "invokevirtual",
+    "invokespecial",
+    "invokestatic",
+    "invokeinterface",
+    "invokedynamic",
+    "new",
+    "newarray",
+    "anewarray",
+    "arraylength",
+    "athrow",
+    "checkcast",
+    "instanceof",
+    "monitorenter",
+    "monitorexit",
+    "wide",
+    "multianewarray",
+    "ifnull",
+    "ifnonnull",
+    "goto_w",
+    "jsr_w",
+];
+
+/// Get the name of the opcode as the JVM specification writes it, such as `invokevirtual`
+pub fn opcode_mnemonic(opcode: u8) -> Option<&'static str> {
+    MNEMONICS.get(usize::from(opcode)).copied()
+}
+
+/// Reads the operands of the instruction at `offset`
+struct Operands<'a> {
+    r: Reader<'a>,
+    offset: usize,
+}
+impl<'a> Operands<'a> {
+    fn truncated(&self) -> InstructionError {
+        InstructionError::Truncated {
+            offset: self.offset,
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8, InstructionError> {
+        self.r.u8().ok_or_else(|| self.truncated())
+    }
+
+    fn i8(&mut self) -> Result<i8, InstructionError> {
+        self.u8().map(|v| v as i8)
+    }
+
+    fn u16(&mut self) -> Result<u16, InstructionError> {
+        self.r.u16().ok_or_else(|| self.truncated())
+    }
+
+    fn i16(&mut self) -> Result<i16, InstructionError> {
+        self.r.i16().ok_or_else(|| self.truncated())
+    }
+
+    fn i32(&mut self) -> Result<i32, InstructionError> {
+        self.r.i32().ok_or_else(|| self.truncated())
+    }
+
+    fn index<T>(&mut self) -> Result<ConstantPoolIndexRaw<T>, InstructionError> {
+        self.u16().map(ConstantPoolIndexRaw::new)
+    }
+
+    /// Skip the padding that aligns the operands of a switch to a multiple of four bytes from
+    /// the start of the code
+    fn align(&mut self) -> Result<(), InstructionError> {
+        let padding = (4 - self.r.pos % 4) % 4;
+        self.r.take(padding).ok_or_else(|| self.truncated())?;
+        Ok(())
+    }
+}
+
+fn decode_wide(r: &mut Operands) -> Result<WideInstruction, InstructionError> {
+    let opcode = r.u8()?;
+    Ok(match opcode {
+        0x15 => WideInstruction::Iload(r.u16()?),
+        0x16 => WideInstruction::Lload(r.u16()?),
+        0x17 => WideInstruction::Fload(r.u16()?),
+        0x18 => WideInstruction::Dload(r.u16()?),
+        0x19 => WideInstruction::Aload(r.u16()?),
+        0x36 => WideInstruction::Istore(r.u16()?),
+        0x37 => WideInstruction::Lstore(r.u16()?),
+        0x38 => WideInstruction::Fstore(r.u16()?),
+        0x39 => WideInstruction::Dstore(r.u16()?),
+        0x3a => WideInstruction::Astore(r.u16()?),
+        0xa9 => WideInstruction::Ret(r.u16()?),
+        0x84 => WideInstruction::Iinc {
+            index: r.u16()?,
+            value: r.i16()?,
+        },
+        _ => {
+            return Err(InstructionError::InvalidWide {
+                offset: r.offset,
+                opcode,
+            })
+        }
+    })
+}
+
+/// Decode the instruction at the offset, which is from the start of the code so that switches are
+/// aligned correctly, returning it along with its length
+pub fn decode_instruction(
+    code: &[u8],
+    offset: usize,
+) -> Result<(Instruction, usize), InstructionError> {
+    let mut r = Operands {
+        r: Reader {
+            bytes: code,
+            pos: offset,
+        },
+        offset,
+    };
+    let opcode = r.u8()?;
+    let instruction = match opcode {
+        0x00 => Instruction::Nop,
+        0x01 => Instruction::AconstNull,
+        0x02 => Instruction::IconstM1,
+        0x03 => Instruction::Iconst0,
+        0x04 => Instruction::Iconst1,
+        0x05 => Instruction::Iconst2,
+        0x06 => Instruction::Iconst3,
+        0x07 => Instruction::Iconst4,
+        0x08 => Instruction::Iconst5,
+        0x09 => Instruction::Lconst0,
+        0x0a => Instruction::Lconst1,
+        0x0b => Instruction::Fconst0,
+        0x0c => Instruction::Fconst1,
+        0x0d => Instruction::Fconst2,
+        0x0e => Instruction::Dconst0,
+        0x0f => Instruction::Dconst1,
+        0x10 => Instruction::Bipush(r.i8()?),
+        0x11 => Instruction::Sipush(r.i16()?),
+        0x12 => Instruction::Ldc(r.u8()?),
+        0x13 => Instruction::LdcW(r.index()?),
+        0x14 => Instruction::Ldc2W(r.index()?),
+        0x15 => Instruction::Iload(r.u8()?),
+        0x16 => Instruction::Lload(r.u8()?),
+        0x17 => Instruction::Fload(r.u8()?),
+        0x18 => Instruction::Dload(r.u8()?),
+        0x19 => Instruction::Aload(r.u8()?),
+        0x1a => Instruction::Iload0,
+        0x1b => Instruction::Iload1,
+        0x1c => Instruction::Iload2,
+        0x1d => Instruction::Iload3,
+        0x1e => Instruction::Lload0,
+        0x1f => Instruction::Lload1,
+        0x20 => Instruction::Lload2,
+        0x21 => Instruction::Lload3,
+        0x22 => Instruction::Fload0,
+        0x23 => Instruction::Fload1,
+        0x24 => Instruction::Fload2,
+        0x25 => Instruction::Fload3,
+        0x26 => Instruction::Dload0,
+        0x27 => Instruction::Dload1,
+        0x28 => Instruction::Dload2,
+        0x29 => Instruction::Dload3,
+        0x2a => Instruction::Aload0,
+        0x2b => Instruction::Aload1,
+        0x2c => Instruction::Aload2,
+        0x2d => Instruction::Aload3,
+        0x2e => Instruction::Iaload,
+        0x2f => Instruction::Laload,
+        0x30 => Instruction::Faload,
+        0x31 => Instruction::Daload,
+        0x32 => Instruction::Aaload,
+        0x33 => Instruction::Baload,
+        0x34 => Instruction::Caload,
+        0x35 => Instruction::Saload,
+        0x36 => Instruction::Istore(r.u8()?),
+        0x37 => Instruction::Lstore(r.u8()?),
+        0x38 => Instruction::Fstore(r.u8()?),
+        0x39 => Instruction::Dstore(r.u8()?),
+        0x3a => Instruction::Astore(r.u8()?),
+        0x3b => Instruction::Istore0,
+        0x3c => Instruction::Istore1,
+        0x3d => Instruction::Istore2,
+        0x3e => Instruction::Istore3,
+        0x3f => Instruction::Lstore0,
+        0x40 => Instruction::Lstore1,
+        0x41 => Instruction::Lstore2,
+        0x42 => Instruction::Lstore3,
+        0x43 => Instruction::Fstore0,
+        0x44 => Instruction::Fstore1,
+        0x45 => Instruction::Fstore2,
+        0x46 => Instruction::Fstore3,
+        0x47 => Instruction::Dstore0,
+        0x48 => Instruction::Dstore1,
+        0x49 => Instruction::Dstore2,
+        0x4a => Instruction::Dstore3,
+        0x4b => Instruction::Astore0,
+        0x4c => Instruction::Astore1,
+        0x4d => Instruction::Astore2,
+        0x4e => Instruction::Astore3,
+        0x4f => Instruction::Iastore,
+        0x50 => Instruction::Lastore,
+        0x51 => Instruction::Fastore,
+        0x52 => Instruction::Dastore,
+        0x53 => Instruction::Aastore,
+        0x54 => Instruction::Bastore,
+        0x55 => Instruction::Castore,
+        0x56 => Instruction::Sastore,
+        0x57 => Instruction::Pop,
+        0x58 => Instruction::Pop2,
+        0x59 => Instruction::Dup,
+        0x5a => Instruction::DupX1,
+        0x5b => Instruction::DupX2,
+        0x5c => Instruction::Dup2,
+        0x5d => Instruction::Dup2X1,
+        0x5e => Instruction::Dup2X2,
+        0x5f => Instruction::Swap,
+        0x60 => Instruction::Iadd,
+        0x61 => Instruction::Ladd,
+        0x62 => Instruction::Fadd,
+        0x63 => Instruction::Dadd,
+        0x64 => Instruction::Isub,
+        0x65 => Instruction::Lsub,
+        0x66 => Instruction::Fsub,
+        0x67 => Instruction::Dsub,
+        0x68 => Instruction::Imul,
+        0x69 => Instruction::Lmul,
+        0x6a => Instruction::Fmul,
+        0x6b => Instruction::Dmul,
+        0x6c => Instruction::Idiv,
+        0x6d => Instruction::Ldiv,
+        0x6e => Instruction::Fdiv,
+        0x6f => Instruction::Ddiv,
+        0x70 => Instruction::Irem,
+        0x71 => Instruction::Lrem,
+        0x72 => Instruction::Frem,
+        0x73 => Instruction::Drem,
+        0x74 => Instruction::Ineg,
+        0x75 => Instruction::Lneg,
+        0x76 => Instruction::Fneg,
+        0x77 => Instruction::Dneg,
+        0x78 => Instruction::Ishl,
+        0x79 => Instruction::Lshl,
+        0x7a => Instruction::Ishr,
+        0x7b => Instruction::Lshr,
+        0x7c => Instruction::Iushr,
+        0x7d => Instruction::Lushr,
+        0x7e => Instruction::Iand,
+        0x7f => Instruction::Land,
+        0x80 => Instruction::Ior,
+        0x81 => Instruction::Lor,
+        0x82 => Instruction::Ixor,
+        0x83 => Instruction::Lxor,
+        0x84 => Instruction::Iinc {
+            index: r.u8()?,
+            value: r.i8()?,
+        },
+        0x85 => Instruction::I2l,
+        0x86 => Instruction::I2f,
+        0x87 => Instruction::I2d,
+        0x88 => Instruction::L2i,
+        0x89 => Instruction::L2f,
+        0x8a => Instruction::L2d,
+        0x8b => Instruction::F2i,
+        0x8c => Instruction::F2l,
+        0x8d => Instruction::F2d,
+        0x8e => Instruction::D2i,
+        0x8f => Instruction::D2l,
+        0x90 => Instruction::D2f,
+        0x91 => Instruction::I2b,
+        0x92 => Instruction::I2c,
+        0x93 => Instruction::I2s,
+        0x94 => Instruction::Lcmp,
+        0x95 => Instruction::Fcmpl,
+        0x96 => Instruction::Fcmpg,
+        0x97 => Instruction::Dcmpl,
+        0x98 => Instruction::Dcmpg,
+        0x99 => Instruction::Ifeq(r.i16()?),
+        0x9a => Instruction::Ifne(r.i16()?),
+        0x9b => Instruction::Iflt(r.i16()?),
+        0x9c => Instruction::Ifge(r.i16()?),
+        0x9d => Instruction::Ifgt(r.i16()?),
+        0x9e => Instruction::Ifle(r.i16()?),
+        0x9f => Instruction::IfIcmpeq(r.i16()?),
+        0xa0 => Instruction::IfIcmpne(r.i16()?),
+        0xa1 => Instruction::IfIcmplt(r.i16()?),
+        0xa2 => Instruction::IfIcmpge(r.i16()?),
+        0xa3 => Instruction::IfIcmpgt(r.i16()?),
+        0xa4 => Instruction::IfIcmple(r.i16()?),
+        0xa5 => Instruction::IfAcmpeq(r.i16()?),
+        0xa6 => Instruction::IfAcmpne(r.i16()?),
+        0xa7 => Instruction::Goto(r.i16()?),
+        0xa8 => Instruction::Jsr(r.i16()?),
+        0xa9 => Instruction::Ret(r.u8()?),
+        0xac => Instruction::Ireturn,
+        0xad => Instruction::Lreturn,
+        0xae => Instruction::Freturn,
+        0xaf => Instruction::Dreturn,
+        0xb0 => Instruction::Areturn,
+        0xb1 => Instruction::Return,
+        0xb2 => Instruction::Getstatic(r.index()?),
+        0xb3 => Instruction::Putstatic(r.index()?),
+        0xb4 => Instruction::Getfield(r.index()?),
+        0xb5 => Instruction::Putfield(r.index()?),
+        0xb6 => Instruction::Invokevirtual(r.index()?),
+        0xb7 => Instruction::Invokespecial(r.index()?),
+        0xb8 => Instruction::Invokestatic(r.index()?),
+        0xbb => Instruction::New(r.index()?),
+        0xbc => Instruction::Newarray(r.u8()?),
+        0xbd => Instruction::Anewarray(r.index()?),
+        0xbe => Instruction::Arraylength,
+        0xbf => Instruction::Athrow,
+        0xc0 => Instruction::Checkcast(r.index()?),
+        0xc1 => Instruction::Instanceof(r.index()?),
+        0xc2 => Instruction::Monitorenter,
+        0xc3 => Instruction::Monitorexit,
+        0xc6 => Instruction::Ifnull(r.i16()?),
+        0xc7 => Instruction::Ifnonnull(r.i16()?),
+        0xc8 => Instruction::GotoW(r.i32()?),
+        0xc9 => Instruction::JsrW(r.i32()?),
+        0xaa => {
+            r.align()?;
+            let default = r.i32()?;
+            let low = r.i32()?;
+            let high = r.i32()?;
+            if high < low {
+                return Err(InstructionError::InvalidSwitch { offset });
+            }
+            // Check that the offsets are there before allocating room for them
+            let count = (i64::from(high) - i64::from(low) + 1) as usize;
+            if count > (code.len() - r.r.pos) / 4 {
+                return Err(r.truncated());
+            }
+            let offsets = (0..count).map(|_| r.i32()).collect::<Result<_, _>>()?;
+            Instruction::Tableswitch {
+                default,
+                low,
+                high,
+                offsets,
+            }
+        }
+        0xab => {
+            r.align()?;
+            let default = r.i32()?;
+            let count = r.i32()?;
+            let count =
+                usize::try_from(count).map_err(|_| InstructionError::InvalidSwitch { offset })?;
+            if count > (code.len() - r.r.pos) / 8 {
+                return Err(r.truncated());
+            }
+            let mut pairs: Vec<(i32, i32)> = Vec::with_capacity(count);
+            for _ in 0..count {
+                let key = r.i32()?;
+                if pairs.last().is_some_and(|&(last, _)| last >= key) {
+                    return Err(InstructionError::InvalidSwitch { offset });
+                }
+                pairs.push((key, r.i32()?));
+            }
+            Instruction::Lookupswitch { default, pairs }
+        }
+        0xb9 => {
+            let index = r.index()?;
+            let count = r.u8()?;
+            // The last operand is always zero
+            r.u8()?;
+            Instruction::Invokeinterface { index, count }
+        }
+        0xba => {
+            let index = r.index()?;
+            // Followed by two zero bytes
+            r.u16()?;
+            Instruction::Invokedynamic(index)
+        }
+        0xc4 => Instruction::Wide(decode_wide(&mut r)?),
+        0xc5 => Instruction::Multianewarray {
+            index: r.index()?,
+            dimensions: r.u8()?,
+        },
+        _ => return Err(InstructionError::InvalidOpcode { offset, opcode }),
+    };
+    Ok((instruction, r.r.pos - offset))
+}
+
+/// Iterates over the instructions of code along with their offsets, see [`code_iter`]
+#[derive(Debug, Clone)]
+pub struct InstructionIter<'a> {
+    code: &'a [u8],
+    pos: usize,
+    failed: bool,
+}
+impl<'a> Iterator for InstructionIter<'a> {
+    type Item = Result<(u16, Instruction), InstructionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos >= self.code.len() {
+            return None;
+        }
+
+        let offset = self.pos;
+        let result = u16::try_from(offset)
+            .map_err(|_| InstructionError::CodeTooLong { offset })
+            .and_then(|short| {
+                let (instruction, len) = decode_instruction(self.code, offset)?;
+                self.pos += len;
+                Ok((short, instruction))
+            });
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+/// Iterate over the instructions of the code with their offsets.
+/// The code must be the whole of the code, since switches are aligned from its start. Iteration
+/// stops after the first error.
+pub fn code_iter(code: &[u8]) -> InstructionIter<'_> {
+    InstructionIter {
+        code,
+        pos: 0,
+        failed: false,
+    }
+}
+
+impl CodeAttribute {
+    /// Iterate over the instructions of the code, see [`code_iter`]
+    pub fn instructions<'a>(&self, class_file_data: &'a [u8]) -> InstructionIter<'a> {
+        code_iter(&class_file_data[self.code.clone()])
+    }
+}
+
+impl CodeAttributeOpt {
+    /// Iterate over the instructions of the code, see [`code_iter`]
+    pub fn instructions<'a>(&self, class_file_data: &'a [u8]) -> InstructionIter<'a> {
+        code_iter(&class_file_data[self.code_range.clone()])
+    }
+}
@@ -0,0 +1,90 @@
+//! Generation of the symbol names that the JVM looks up for `native` methods.
+//! [see more](https://docs.oracle.com/javase/8/docs/technotes/guides/jni/spec/design.html#resolving_native_method_names)
+
+use crate::constant_info::to_text;
+use crate::descriptor::method::MethodDescriptor;
+
+/// Escape a name as JNI requires for symbol names.
+/// `/` becomes `_`, while `_`, `;`, and `[` are escaped as `_1`, `_2`, and `_3` respectively.
+/// Any other character that is not alphanumeric ASCII is escaped as `_0xxxx`, with its UTF-16
+/// code units in lowercase hex.
+pub fn mangle(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '/' => out.push('_'),
+            '_' => out.push_str("_1"),
+            ';' => out.push_str("_2"),
+            '[' => out.push_str("_3"),
+            c if c.is_ascii_alphanumeric() => out.push(c),
+            c => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("_0{:04x}", unit));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// The mangled form of the parameter types of the descriptor, which is the part of the long
+/// symbol name after the `__`.
+pub fn mangle_arguments(descriptor: &MethodDescriptor) -> String {
+    let mut params = Vec::new();
+    for param in descriptor.parameter_types.iter() {
+        param.write_descriptor(&mut params);
+    }
+    mangle(&to_text(&params))
+}
+
+/// The short symbol name of a native method, such as `Java_pkg_Class_method`
+/// `class_name` is the internal name of the class, such as `pkg/Class`.
+pub fn short_symbol_name(class_name: &str, method_name: &str) -> String {
+    format!("Java_{}_{}", mangle(class_name), mangle(method_name))
+}
+
+/// The long symbol name of a native method, such as `Java_pkg_Class_method__ILjava_lang_String_2`
+/// which is used to distinguish overloaded native methods.
+/// `class_name` is the internal name of the class, such as `pkg/Class`.
+pub fn long_symbol_name(
+    class_name: &str,
+    method_name: &str,
+    descriptor: &MethodDescriptor,
+) -> String {
+    format!(
+        "{}__{}",
+        short_symbol_name(class_name, method_name),
+        mangle_arguments(descriptor)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::descriptor::method::MethodDescriptor;
+
+    use super::{long_symbol_name, mangle, short_symbol_name};
+
+    #[test]
+    fn mangling() {
+        assert_eq!(mangle("pkg/Cls"), "pkg_Cls");
+        assert_eq!(mangle("my_method"), "my_1method");
+        assert_eq!(mangle("[Ljava/lang/String;"), "_3Ljava_lang_String_2");
+        assert_eq!(mangle("Outer$Inner"), "Outer_00024Inner");
+        assert_eq!(mangle("π"), "_003c0");
+        assert_eq!(mangle("𠜎"), "_0d841_0df0e");
+    }
+
+    #[test]
+    fn symbols() {
+        let desc = MethodDescriptor::parse(b"(ILjava/lang/String;[J)V").unwrap();
+        assert_eq!(short_symbol_name("pkg/Cls", "f"), "Java_pkg_Cls_f");
+        assert_eq!(
+            long_symbol_name("pkg/Cls", "f", &desc),
+            "Java_pkg_Cls_f__ILjava_lang_String_2_3J"
+        );
+
+        let desc = MethodDescriptor::parse(b"()V").unwrap();
+        assert_eq!(long_symbol_name("Cls", "f", &desc), "Java_Cls_f__");
+    }
+}
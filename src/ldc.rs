@@ -0,0 +1,352 @@
+//! Choosing between `ldc` and `ldc_w`.
+//!
+//! `ldc` has a single byte operand, so it can only load the first 255 entries of the constant
+//! pool. When edits move a constant past that, each `ldc` of it has to become an `ldc_w`, and when
+//! they move it back the `ldc_w` can become an `ldc` again. Either way the code changes length, so
+//! its branches, exception table, and the offsets in its nested attributes are all moved to match.
+
+use std::collections::HashMap;
+
+use crate::assemble::{decode, encode, layout, Item, Op, JSR, LDC, LDC_W};
+#[cfg(feature = "stackmap")]
+use crate::attribute_info::stack_map_table_attribute_parser;
+use crate::attribute_info::{names, type_annotation_targets, CodeAttribute, HasAttributes};
+use crate::constant_info::ConstantInfo;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::instructions::Instruction;
+#[cfg(feature = "stackmap")]
+use crate::parser::ParseData;
+use crate::remap::{IndexRemap, RemapError};
+use crate::util::Reader;
+use crate::ClassFile;
+
+/// Whether the constant at the old index should be loaded with `ldc_w`, or None if either form
+/// works for the instruction as it is
+fn wants_wide(remap: &IndexRemap, index: u16) -> Result<Option<bool>, RemapError> {
+    let old = ConstantPoolIndexRaw::<ConstantInfo>::new(index);
+    let new = remap.get(old).ok_or(RemapError::Unmapped(index))?;
+    Ok(if new.0 > 255 {
+        Some(true)
+    } else if index <= 255 {
+        Some(false)
+    } else {
+        // The old index doesn't fit in an `ldc` yet, so narrowing has to wait until the remapping
+        // has been applied
+        None
+    })
+}
+
+/// Rewrite the code so that every `ldc` and `ldc_w` uses the narrowest form that will fit the
+/// new index of its constant under the remapping, returning the info of the new Code attribute,
+/// or None if nothing has to change.
+///
+/// The instructions keep their old indices, so the remapping can be applied afterwards. An
+/// `ldc_w` whose old index doesn't fit in a byte is left alone, so applying the identity
+/// remapping afterwards with [`ClassFile::fit_ldc`] narrows the rest.
+///
+/// Branches which no longer reach their targets are widened. The exception table, the
+/// LineNumberTable, LocalVariableTable, LocalVariableTypeTable, StackMapTable, and the type
+/// annotations of the code are moved to the new offsets. Other nested attributes are kept
+/// unchanged.
+/// Without the `stackmap` feature, a StackMapTable can't be moved, and is a
+/// [`RemapError::UnknownAttribute`].
+pub fn fit_ldc(
+    code: &CodeAttribute,
+    pool: &ConstantPool,
+    class_file_data: &[u8],
+    remap: &IndexRemap,
+) -> Result<Option<Vec<u8>>, RemapError> {
+    let bytes = class_file_data
+        .get(code.code.clone())
+        .ok_or(RemapError::Malformed)?;
+    let insns = decode(bytes).ok_or(RemapError::Malformed)?;
+
+    let mut resized = HashMap::new();
+    for (i, insn) in insns.iter().enumerate() {
+        let (wide, index) = match insn.instruction {
+            Instruction::Ldc(index) => (false, u16::from(index)),
+            Instruction::LdcW(index) => (true, index.0),
+            _ => continue,
+        };
+        if let Some(wants) = wants_wide(remap, index)? {
+            if wants != wide {
+                resized.insert(i, index);
+            }
+        }
+    }
+    if resized.is_empty() {
+        return Ok(None);
+    }
+
+    let starts: HashMap<usize, usize> = insns
+        .iter()
+        .enumerate()
+        .map(|(i, insn)| (insn.offset, i))
+        .collect();
+    let label = |offset: usize| starts.get(&offset).copied().ok_or(RemapError::Malformed);
+
+    // Each instruction's label is its index, and the end of the code has a label too
+    let mut items = Vec::new();
+    for (i, insn) in insns.iter().enumerate() {
+        items.push(Item::Bind(i));
+        items.push(Item::Origin(insn.offset));
+        match &insn.op {
+            Op::Other | Op::Ret => match resized.get(&i) {
+                Some(&index) if matches!(insn.instruction, Instruction::Ldc(_)) => {
+                    let [high, low] = index.to_be_bytes();
+                    items.extend([Item::Byte(LDC_W), Item::Byte(high), Item::Byte(low)]);
+                }
+                Some(&index) => items.extend([Item::Byte(LDC), Item::Byte(index as u8)]),
+                None => items.push(Item::Copy(i)),
+            },
+            &Op::Branch { opcode, target } => items.push(Item::Jump {
+                opcode,
+                target: label(target)?,
+                wide: insn.is_wide_jump(),
+            }),
+            &Op::Jsr { target } => items.push(Item::Jump {
+                opcode: JSR,
+                target: label(target)?,
+                wide: insn.is_wide_jump(),
+            }),
+            Op::TableSwitch {
+                default,
+                low,
+                targets,
+            } => items.push(Item::TableSwitch {
+                default: label(*default)?,
+                low: *low,
+                targets: targets
+                    .iter()
+                    .map(|&target| label(target))
+                    .collect::<Result<_, _>>()?,
+            }),
+            Op::LookupSwitch { default, pairs } => items.push(Item::LookupSwitch {
+                default: label(*default)?,
+                pairs: pairs
+                    .iter()
+                    .map(|&(key, target)| Ok((key, label(target)?)))
+                    .collect::<Result<_, _>>()?,
+            }),
+        }
+    }
+    items.push(Item::Bind(insns.len()));
+
+    let (labels, positions) = layout(&mut items, &insns, insns.len() + 1);
+    let (out, _) = encode(&items, &insns, bytes, &labels, &positions);
+    let code_length = u16::try_from(out.len()).map_err(|_| RemapError::CodeTooLong)?;
+    let position = |offset: usize| match starts.get(&offset) {
+        Some(&i) => Some(labels[i]),
+        None if offset == bytes.len() => Some(out.len()),
+        None => None,
+    };
+
+    let mut info = Vec::new();
+    info.extend_from_slice(&code.max_stack.to_be_bytes());
+    info.extend_from_slice(&code.max_locals.to_be_bytes());
+    info.extend_from_slice(&u32::from(code_length).to_be_bytes());
+    info.extend_from_slice(&out);
+    info.extend_from_slice(&code.exception_table_length.to_be_bytes());
+    for entry in code.exception_table.iter() {
+        for pc in [entry.start_pc.0, entry.end_pc.0, entry.handler_pc.0] {
+            let pc = position(usize::from(pc)).ok_or(RemapError::Malformed)?;
+            info.extend_from_slice(&(pc as u16).to_be_bytes());
+        }
+        info.extend_from_slice(&entry.catch_type.0.to_be_bytes());
+    }
+
+    info.extend_from_slice(&code.attributes_count.to_be_bytes());
+    for attr in code.attributes.iter() {
+        let name = pool
+            .get_t(attr.attribute_name_index)
+            .map(|name| name.as_text(class_file_data))
+            .ok_or(RemapError::Malformed)?;
+        let attr_info = class_file_data
+            .get(attr.info.clone())
+            .ok_or(RemapError::Malformed)?;
+        let attr_info = match name.as_ref() {
+            names::LINE_NUMBER_TABLE => line_number_table(attr_info, &position),
+            names::LOCAL_VARIABLE_TABLE | names::LOCAL_VARIABLE_TYPE_TABLE => {
+                local_variable_table(attr_info, &position)
+            }
+            names::STACK_MAP_TABLE => Some(stack_map_table(attr_info, &position)?),
+            names::RUNTIME_VISIBLE_TYPE_ANNOTATIONS | names::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
+                type_annotations(attr_info, &position)
+            }
+            _ => Some(attr_info.to_vec()),
+        }
+        .ok_or(RemapError::Malformed)?;
+
+        info.extend_from_slice(&attr.attribute_name_index.0.to_be_bytes());
+        let len = u32::try_from(attr_info.len()).map_err(|_| RemapError::Malformed)?;
+        info.extend_from_slice(&len.to_be_bytes());
+        info.extend_from_slice(&attr_info);
+    }
+
+    Ok(Some(info))
+}
+
+/// Overwrite the two byte code offset at `at` with its new position
+fn move_offset(
+    out: &mut [u8],
+    at: usize,
+    position: &impl Fn(usize) -> Option<usize>,
+) -> Option<usize> {
+    let offset = usize::from(u16::from_be_bytes([out[at], out[at + 1]]));
+    let new = position(offset)?;
+    out[at..at + 2].copy_from_slice(&(new as u16).to_be_bytes());
+    Some(new)
+}
+
+fn line_number_table(info: &[u8], position: &impl Fn(usize) -> Option<usize>) -> Option<Vec<u8>> {
+    let mut out = info.to_vec();
+    let mut r = Reader {
+        bytes: info,
+        pos: 0,
+    };
+    for _ in 0..r.u16()? {
+        let at = r.pos;
+        r.take(4)?;
+        move_offset(&mut out, at, position)?;
+    }
+    Some(out)
+}
+
+/// Move a LocalVariableTable or LocalVariableTypeTable, which have the same layout
+fn local_variable_table(
+    info: &[u8],
+    position: &impl Fn(usize) -> Option<usize>,
+) -> Option<Vec<u8>> {
+    let mut out = info.to_vec();
+    let mut r = Reader {
+        bytes: info,
+        pos: 0,
+    };
+    for _ in 0..r.u16()? {
+        let at = r.pos;
+        let start = usize::from(r.u16()?);
+        let length = usize::from(r.u16()?);
+        r.take(6)?;
+        let new_start = move_offset(&mut out, at, position)?;
+        let new_end = position(start + length)?;
+        let new_length = new_end.checked_sub(new_start)? as u16;
+        out[at + 2..at + 4].copy_from_slice(&new_length.to_be_bytes());
+    }
+    Some(out)
+}
+
+/// Move a StackMapTable, whose frames keep their kinds unless their offset delta no longer fits
+#[cfg(feature = "stackmap")]
+fn stack_map_table(
+    info: &[u8],
+    position: &impl Fn(usize) -> Option<usize>,
+) -> Result<Vec<u8>, RemapError> {
+    let (_, table) = stack_map_table_attribute_parser(ParseData::new(info))
+        .map_err(|_| RemapError::Malformed)?;
+    let table = table.moved(position).ok_or(RemapError::Malformed)?;
+    Ok(table.to_bytes())
+}
+
+/// Without the frame parser the StackMapTable can't be moved
+#[cfg(not(feature = "stackmap"))]
+fn stack_map_table(
+    _info: &[u8],
+    _position: &impl Fn(usize) -> Option<usize>,
+) -> Result<Vec<u8>, RemapError> {
+    Err(RemapError::UnknownAttribute(
+        names::STACK_MAP_TABLE.to_owned(),
+    ))
+}
+
+/// Move the type annotations of a Code attribute, which are all on local variables or on
+/// instructions
+fn type_annotations(info: &[u8], position: &impl Fn(usize) -> Option<usize>) -> Option<Vec<u8>> {
+    let mut out = info.to_vec();
+    for (target_type, at) in type_annotation_targets(info)? {
+        match target_type {
+            // localvar_target, like a LocalVariableTable without the names
+            0x40 | 0x41 => {
+                let mut r = Reader {
+                    bytes: info,
+                    pos: at,
+                };
+                for _ in 0..r.u16()? {
+                    let at = r.pos;
+                    let start = usize::from(r.u16()?);
+                    let length = usize::from(r.u16()?);
+                    r.take(2)?;
+                    let new_start = move_offset(&mut out, at, position)?;
+                    let new_end = position(start + length)?;
+                    let new_length = new_end.checked_sub(new_start)? as u16;
+                    out[at + 2..at + 4].copy_from_slice(&new_length.to_be_bytes());
+                }
+            }
+            // catch_target, which is an index into the exception table
+            0x42 => {}
+            // offset_target and type_argument_target
+            0x43..=0x4b => {
+                move_offset(&mut out, at, position)?;
+            }
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+impl IndexRemap {
+    /// Rewrite the code of every method with [`fit_ldc`], so that the remapping can be applied
+    /// without any `ldc` failing with [`RemapError::LdcIndexTooLarge`], returning how many
+    /// methods were changed.
+    /// The new Code attributes are written to the end of `data`. On error, neither the class file
+    /// nor `data` is modified.
+    pub fn fit_ldc(
+        &self,
+        class_file: &mut ClassFile,
+        data: &mut Vec<u8>,
+    ) -> Result<usize, RemapError> {
+        let pool = &class_file.const_pool;
+        let mut rewritten = Vec::new();
+        for (i, method) in class_file.methods.iter().enumerate() {
+            let code = method
+                .find_attribute::<CodeAttribute>(pool, data)
+                .map_err(|_| RemapError::Malformed)?;
+            if let Some(code) = code {
+                if let Some(info) = fit_ldc(&code, pool, data, self)? {
+                    rewritten.push((i, info));
+                }
+            }
+        }
+
+        let count = rewritten.len();
+        if count > 0 {
+            class_file.typed_attributes = None;
+        }
+        for (i, info) in rewritten {
+            let start = data.len();
+            data.extend_from_slice(&info);
+            let method = &mut class_file.methods[i];
+            let code = method
+                .attributes
+                .iter_mut()
+                .find(|attr| {
+                    pool.get_t(attr.attribute_name_index)
+                        .map(|name| name.as_text(data) == names::CODE)
+                        .unwrap_or(false)
+                })
+                .expect("the method's Code attribute was just found");
+            let old = std::mem::replace(&mut code.info, start..data.len());
+            class_file.stale_ranges.mark(old);
+            code.attribute_length = info.len() as u32;
+        }
+
+        Ok(count)
+    }
+}
+
+impl ClassFile {
+    /// Rewrite every `ldc` and `ldc_w` to the narrowest form that fits the current index of its
+    /// constant, see [`fit_ldc`], returning how many methods were changed.
+    pub fn fit_ldc(&mut self, data: &mut Vec<u8>) -> Result<usize, RemapError> {
+        IndexRemap::identity(&self.const_pool).fit_ldc(self, data)
+    }
+}
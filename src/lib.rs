@@ -18,14 +18,54 @@ pub mod method_info;
 pub mod parser;
 pub mod types;
 
+pub mod api;
+pub mod builder;
+pub mod classify;
+pub mod compat;
 pub mod constant_pool;
+pub mod control_flow;
 pub mod descriptor;
+pub mod error;
+pub mod histogram;
+pub mod import;
+pub mod index;
+pub mod inline;
+pub mod instructions;
+pub mod jni;
+pub mod ldc;
+pub mod modifiers;
+pub mod names;
+pub mod nest;
+pub mod provider;
+pub mod record;
+pub mod recover;
+pub mod remap;
+pub mod rename;
+pub mod resolved;
+pub mod scan;
+pub mod stack_depth;
+pub mod stale;
+pub mod unused;
+pub mod validate;
+pub mod writer;
 
+#[cfg(feature = "jar")]
+pub mod archive;
+#[cfg(feature = "digest")]
+pub mod digest;
+#[cfg(feature = "stackmap")]
+pub mod frames;
+#[cfg(feature = "threading")]
+pub mod prefetch;
+
+pub use error::ParseError;
 pub use parser::class_parser;
 pub use parser::class_parser_opt;
 use parser::ParseData;
+pub use parser::ParseOptions;
 pub use types::*;
 
+mod assemble;
 mod util;
 
 /// Attempt to parse a class file given a class file given a path to a class file
@@ -39,26 +79,52 @@ mod util;
 ///     Err(ex) => panic!("Failed to parse: {}", ex),
 /// };
 /// ```
-pub fn parse_class(class_name: &str) -> Result<ClassFile, String> {
+pub fn parse_class(class_name: &str) -> Result<ClassFile, ParseError> {
+    let class_bytes = read_class(class_name)?;
+    parser::check_header(&class_bytes)?;
+    class_parser(ParseData::new(&class_bytes))
+        .map(|(_, c)| c)
+        .map_err(|err| ParseError::from(err).with_item(&class_bytes))
+}
+
+/// Attempt to lazily parse a class file given a path to a class file (without .class extension).
+/// The bytes of the class file are returned alongside it, since the parts which are loaded later
+/// are read from them.
+///
+/// ```rust
+/// let (class_file, data) =
+///     classfile_parser::parse_class_opt("./java-assets/compiled-classes/BasicClass").unwrap();
+/// let method = class_file.load_method_at(&data, 0).unwrap();
+/// println!("first method has {} attributes", method.attributes_count);
+/// ```
+pub fn parse_class_opt(class_name: &str) -> Result<(ClassFileOpt, Vec<u8>), ParseError> {
+    let class_bytes = read_class(class_name)?;
+    let (class_file, _) = parse_class_opt_from_bytes(&class_bytes)?;
+    Ok((class_file, class_bytes))
+}
+
+/// Attempt to lazily parse a class file from its bytes, returning any bytes after the end of the
+/// class file.
+/// The bytes must be kept around, as the parts of the class file that are loaded later are read
+/// from them.
+pub fn parse_class_opt_from_bytes(data: &[u8]) -> Result<(ClassFileOpt, &[u8]), ParseError> {
+    parser::check_header(data)?;
+    class_parser_opt(ParseData::new(data))
+        .map(|(rest, c)| (c, rest.data()))
+        .map_err(|err| ParseError::from(err).with_item(data))
+}
+
+fn read_class(class_name: &str) -> Result<Vec<u8>, ParseError> {
     let class_file_name = &format!("{}.class", class_name);
     let path = Path::new(class_file_name);
-    let display = path.display();
-
-    let mut file = match File::open(&path) {
-        Err(why) => {
-            return Err(format!("Unable to open {}: {}", display, &why.to_string()));
-        }
-        Ok(file) => file,
+    let io_error = |err: std::io::Error| ParseError::Io {
+        kind: err.kind(),
+        message: format!("{}: {}", path.display(), err),
     };
 
+    let mut file = File::open(path).map_err(io_error)?;
     let mut class_bytes = Vec::new();
-    if let Err(why) = file.read_to_end(&mut class_bytes) {
-        return Err(format!("Unable to read {}: {}", display, &why.to_string()));
-    }
+    file.read_to_end(&mut class_bytes).map_err(io_error)?;
 
-    let parsed_class = class_parser(ParseData::new(&class_bytes));
-    match parsed_class {
-        Ok((_, c)) => Ok(c),
-        _ => Err("Failed to parse class?".to_string()),
-    }
+    Ok(class_bytes)
 }
@@ -0,0 +1,164 @@
+use smallvec::SmallVec;
+
+use crate::attribute_info::{names, AttributeInfo, CodeAttributeBuilder, CodeBuilderError};
+use crate::builder::{ClassBuilderError, ConstantPoolBuilder};
+use crate::constant_info::Utf8Constant;
+use crate::constant_pool::ConstantPoolIndexRaw;
+use crate::method_info::{MethodAccessFlags, MethodInfo};
+use crate::ClassFile;
+
+/// Why a method couldn't be added to a class
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodBuilderError {
+    /// The class already has a method with the same name and descriptor
+    DuplicateMethod,
+    /// The class already has as many methods as a class file can hold
+    TooManyMethods,
+    /// The constant pool has no room for the constants that the method needs
+    PoolTooLarge,
+    /// A name or descriptor is longer than a Utf8 constant can hold
+    Utf8TooLong,
+    /// There are more exceptions in the throws clause than an Exceptions attribute can hold
+    TooManyExceptions,
+    /// The code couldn't be built
+    Code(CodeBuilderError),
+}
+impl From<CodeBuilderError> for MethodBuilderError {
+    fn from(err: CodeBuilderError) -> Self {
+        MethodBuilderError::Code(err)
+    }
+}
+
+/// Builds a method and adds it to a class, adding the constants that it needs to the class's
+/// constant pool.
+///
+/// Names are internal names, such as `java/io/IOException`, and are written to the pool as is.
+#[derive(Debug, Clone)]
+pub struct MethodBuilder {
+    access_flags: MethodAccessFlags,
+    name: String,
+    descriptor: String,
+    throws: Vec<String>,
+    code: Option<CodeAttributeBuilder>,
+}
+impl MethodBuilder {
+    pub fn new(access_flags: MethodAccessFlags, name: &str, descriptor: &str) -> MethodBuilder {
+        MethodBuilder {
+            access_flags,
+            name: name.to_owned(),
+            descriptor: descriptor.to_owned(),
+            throws: Vec::new(),
+            code: None,
+        }
+    }
+
+    /// Add checked exceptions to the throws clause, which is written as an Exceptions attribute
+    pub fn throws(&mut self, exceptions: &[&str]) -> &mut Self {
+        self.throws
+            .extend(exceptions.iter().map(|&name| name.to_owned()));
+        self
+    }
+
+    /// Set the code of the method, which is written as a Code attribute.
+    /// Abstract and native methods have no code.
+    pub fn code(&mut self, code: CodeAttributeBuilder) -> &mut Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Add the method to the end of the class's methods, returning its index.
+    /// New constants and the attributes are written to the end of `data`.
+    /// On error, neither the class file nor `data` is modified.
+    pub fn build(
+        &self,
+        class_file: &mut ClassFile,
+        data: &mut Vec<u8>,
+    ) -> Result<usize, MethodBuilderError> {
+        if class_file.methods.len() >= usize::from(u16::MAX) {
+            return Err(MethodBuilderError::TooManyMethods);
+        }
+        let pool = &class_file.const_pool;
+        let text = |index: ConstantPoolIndexRaw<Utf8Constant>| {
+            pool.get_t(index).map(|text| text.as_text(data))
+        };
+        let duplicate = class_file.methods.iter().any(|method| {
+            text(method.name_index).as_deref() == Some(self.name.as_str())
+                && text(method.descriptor_index).as_deref() == Some(self.descriptor.as_str())
+        });
+        if duplicate {
+            return Err(MethodBuilderError::DuplicateMethod);
+        }
+
+        let start = data.len();
+        let mut pool = class_file.const_pool.clone();
+        let method = match ConstantPoolBuilder::with_pool(&mut pool, data, |constants| {
+            self.write(constants)
+        }) {
+            Ok(method) => method,
+            Err(err) => {
+                data.truncate(start);
+                return Err(err);
+            }
+        };
+
+        class_file.const_pool_size = pool.len() + 1;
+        class_file.const_pool = pool;
+        class_file.methods.push(method);
+        class_file.methods_count = class_file.methods.len() as u16;
+        Ok(class_file.methods.len() - 1)
+    }
+
+    /// Write the method's constants and attributes through the pool builder
+    pub(crate) fn write(
+        &self,
+        constants: &mut ConstantPoolBuilder,
+    ) -> Result<MethodInfo, MethodBuilderError> {
+        let name_index = constants.utf8(&self.name).map_err(pool_error)?;
+        let descriptor_index = constants.utf8(&self.descriptor).map_err(pool_error)?;
+
+        let mut attributes = SmallVec::new();
+        if let Some(code) = &self.code {
+            let attribute_name_index = constants.utf8(names::CODE).map_err(pool_error)?;
+            let (info, _) = code.build(constants.data_mut())?;
+            attributes.push(AttributeInfo {
+                attribute_name_index,
+                attribute_length: info.len() as u32,
+                info,
+            });
+        }
+        if !self.throws.is_empty() {
+            let count = u16::try_from(self.throws.len())
+                .map_err(|_| MethodBuilderError::TooManyExceptions)?;
+            let attribute_name_index = constants.utf8(names::EXCEPTIONS).map_err(pool_error)?;
+            let mut info = count.to_be_bytes().to_vec();
+            for name in self.throws.iter() {
+                let index = constants.class(name).map_err(pool_error)?;
+                info.extend_from_slice(&index.0.to_be_bytes());
+            }
+            let data = constants.data_mut();
+            let start = data.len();
+            data.extend_from_slice(&info);
+            attributes.push(AttributeInfo {
+                attribute_name_index,
+                attribute_length: info.len() as u32,
+                info: start..data.len(),
+            });
+        }
+
+        Ok(MethodInfo {
+            access_flags: self.access_flags,
+            raw_access_flags: self.access_flags.bits(),
+            name_index,
+            descriptor_index,
+            attributes_count: attributes.len() as u16,
+            attributes,
+        })
+    }
+}
+
+fn pool_error(err: ClassBuilderError) -> MethodBuilderError {
+    match err {
+        ClassBuilderError::Utf8TooLong => MethodBuilderError::Utf8TooLong,
+        _ => MethodBuilderError::PoolTooLarge,
+    }
+}
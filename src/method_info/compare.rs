@@ -0,0 +1,473 @@
+//! Deciding whether two methods, possibly from different class files, are copies of each other.
+//!
+//! Constants are compared by what they refer to rather than by their index, so the same method
+//! compiled into classes with different constant pools still compares equal. Branches are
+//! compared by the instruction that they jump to, which keeps `ldc` and `ldc_w` interchangeable
+//! even though they differ in length.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::attribute_info::{
+    BootstrapMethod, BootstrapMethodsAttribute, CodeAttribute, ExceptionsAttribute, HasAttributes,
+    SignatureAttribute,
+};
+use crate::constant_info::{ClassConstant, ConstantInfo, NameAndTypeConstant, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::instructions::{code_iter, Instruction};
+use crate::method_info::MethodInfo;
+use crate::{ClassFile, LoadError};
+
+/// How deeply dynamic constants may nest in the arguments of bootstrap methods before the
+/// comparison gives up, since a malformed class file could make them refer to themselves
+const MAX_CONSTANT_DEPTH: usize = 16;
+
+/// A method along with the class file that it is in
+struct Side<'a> {
+    pool: &'a ConstantPool,
+    data: &'a [u8],
+    bootstrap_methods: Option<BootstrapMethodsAttribute>,
+    /// The offset of each instruction of the code
+    offsets: Vec<u16>,
+    code_length: usize,
+}
+impl<'a> Side<'a> {
+    fn new(class_file: &'a ClassFile, data: &'a [u8]) -> Result<Side<'a>, LoadError> {
+        let pool = &class_file.const_pool;
+        Ok(Side {
+            pool,
+            data,
+            bootstrap_methods: class_file.find_attribute(pool, data)?,
+            offsets: Vec::new(),
+            code_length: 0,
+        })
+    }
+
+    fn constant(
+        &self,
+        index: ConstantPoolIndexRaw<ConstantInfo>,
+    ) -> Result<&'a ConstantInfo, LoadError> {
+        self.pool.get(index).ok_or(LoadError::Unknown)
+    }
+
+    fn utf8(&self, index: ConstantPoolIndexRaw<Utf8Constant>) -> Result<&'a [u8], LoadError> {
+        let text = self.pool.get_t(index).ok_or(LoadError::Unknown)?;
+        Ok(text.as_bytes(self.data))
+    }
+
+    fn bootstrap_method(&self, index: u16) -> Result<&BootstrapMethod, LoadError> {
+        self.bootstrap_methods
+            .as_ref()
+            .and_then(|attr| attr.bootstrap_methods.get(usize::from(index)))
+            .ok_or(LoadError::Unknown)
+    }
+
+    /// The index of the instruction that starts at the offset, where the end of the code counts
+    /// as the instruction after the last
+    fn instruction_at(&self, offset: i64) -> Result<usize, LoadError> {
+        if offset == self.code_length as i64 {
+            return Ok(self.offsets.len());
+        }
+        let offset = u16::try_from(offset).map_err(|_| LoadError::Unknown)?;
+        self.offsets
+            .binary_search(&offset)
+            .map_err(|_| LoadError::Unknown)
+    }
+}
+
+struct Comparison<'a> {
+    a: Side<'a>,
+    b: Side<'a>,
+    /// Whether each pair of constants compared so far was equal, by their raw indices, so that
+    /// dynamic constants shared between many bootstrap arguments are only compared once
+    compared: RefCell<HashMap<(u16, u16), bool>>,
+}
+impl<'a> Comparison<'a> {
+    fn utf8_eq(
+        &self,
+        a: ConstantPoolIndexRaw<Utf8Constant>,
+        b: ConstantPoolIndexRaw<Utf8Constant>,
+    ) -> Result<bool, LoadError> {
+        Ok(self.a.utf8(a)? == self.b.utf8(b)?)
+    }
+
+    /// Whether the classes have the same name, where a zero index only equals another zero index
+    fn class_eq(
+        &self,
+        a: ConstantPoolIndexRaw<ClassConstant>,
+        b: ConstantPoolIndexRaw<ClassConstant>,
+    ) -> Result<bool, LoadError> {
+        if a.is_zero() || b.is_zero() {
+            return Ok(a.is_zero() && b.is_zero());
+        }
+        let a: &ClassConstant = self.a.pool.get_t(a).ok_or(LoadError::Unknown)?;
+        let b: &ClassConstant = self.b.pool.get_t(b).ok_or(LoadError::Unknown)?;
+        self.utf8_eq(a.name_index, b.name_index)
+    }
+
+    fn name_and_type_eq(
+        &self,
+        a: ConstantPoolIndexRaw<NameAndTypeConstant>,
+        b: ConstantPoolIndexRaw<NameAndTypeConstant>,
+    ) -> Result<bool, LoadError> {
+        let a: &NameAndTypeConstant = self.a.pool.get_t(a).ok_or(LoadError::Unknown)?;
+        let b: &NameAndTypeConstant = self.b.pool.get_t(b).ok_or(LoadError::Unknown)?;
+        Ok(self.utf8_eq(a.name_index, b.name_index)?
+            && self.utf8_eq(a.descriptor_index, b.descriptor_index)?)
+    }
+
+    fn bootstrap_method_eq(&self, a: u16, b: u16, depth: usize) -> Result<bool, LoadError> {
+        let (a, b) = (self.a.bootstrap_method(a)?, self.b.bootstrap_method(b)?);
+        if a.bootstrap_arguments.len() != b.bootstrap_arguments.len()
+            || !self.constant_eq(
+                a.bootstrap_method_ref.into_generic(),
+                b.bootstrap_method_ref.into_generic(),
+                depth,
+            )?
+        {
+            return Ok(false);
+        }
+        for (&a, &b) in a
+            .bootstrap_arguments
+            .iter()
+            .zip(b.bootstrap_arguments.iter())
+        {
+            if !self.constant_eq(a, b, depth)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Whether the constants have the same kind and refer to the same values
+    fn constant_eq(
+        &self,
+        a: ConstantPoolIndexRaw<ConstantInfo>,
+        b: ConstantPoolIndexRaw<ConstantInfo>,
+        depth: usize,
+    ) -> Result<bool, LoadError> {
+        if let Some(&eq) = self.compared.borrow().get(&(a.0, b.0)) {
+            return Ok(eq);
+        }
+        let depth = depth + 1;
+        if depth > MAX_CONSTANT_DEPTH {
+            return Err(LoadError::Unknown);
+        }
+
+        let eq = match (self.a.constant(a)?, self.b.constant(b)?) {
+            (ConstantInfo::Utf8(a), ConstantInfo::Utf8(b)) => {
+                a.as_bytes(self.a.data) == b.as_bytes(self.b.data)
+            }
+            (ConstantInfo::Integer(a), ConstantInfo::Integer(b)) => a.value == b.value,
+            (ConstantInfo::Float(a), ConstantInfo::Float(b)) => {
+                a.value.to_bits() == b.value.to_bits()
+            }
+            (ConstantInfo::Long(a), ConstantInfo::Long(b)) => a.value == b.value,
+            (ConstantInfo::Double(a), ConstantInfo::Double(b)) => {
+                a.value.to_bits() == b.value.to_bits()
+            }
+            (ConstantInfo::Class(a), ConstantInfo::Class(b)) => {
+                self.utf8_eq(a.name_index, b.name_index)?
+            }
+            (ConstantInfo::String(a), ConstantInfo::String(b)) => {
+                self.utf8_eq(a.string_index, b.string_index)?
+            }
+            (ConstantInfo::FieldRef(a), ConstantInfo::FieldRef(b)) => {
+                self.class_eq(a.class_index, b.class_index)?
+                    && self.name_and_type_eq(a.name_and_type_index, b.name_and_type_index)?
+            }
+            (ConstantInfo::MethodRef(a), ConstantInfo::MethodRef(b)) => {
+                self.class_eq(a.class_index, b.class_index)?
+                    && self.name_and_type_eq(a.name_and_type_index, b.name_and_type_index)?
+            }
+            (ConstantInfo::InterfaceMethodRef(a), ConstantInfo::InterfaceMethodRef(b)) => {
+                self.class_eq(a.class_index, b.class_index)?
+                    && self.name_and_type_eq(a.name_and_type_index, b.name_and_type_index)?
+            }
+            (ConstantInfo::NameAndType(a), ConstantInfo::NameAndType(b)) => {
+                self.utf8_eq(a.name_index, b.name_index)?
+                    && self.utf8_eq(a.descriptor_index, b.descriptor_index)?
+            }
+            (ConstantInfo::MethodHandle(a), ConstantInfo::MethodHandle(b)) => {
+                a.reference_kind == b.reference_kind
+                    && self.constant_eq(a.reference_index, b.reference_index, depth)?
+            }
+            (ConstantInfo::MethodType(a), ConstantInfo::MethodType(b)) => {
+                self.utf8_eq(a.descriptor_index, b.descriptor_index)?
+            }
+            (ConstantInfo::Module(a), ConstantInfo::Module(b)) => {
+                self.utf8_eq(a.name_index, b.name_index)?
+            }
+            (ConstantInfo::Package(a), ConstantInfo::Package(b)) => {
+                self.utf8_eq(a.name_index, b.name_index)?
+            }
+            (ConstantInfo::InvokeDynamic(a), ConstantInfo::InvokeDynamic(b)) => {
+                self.name_and_type_eq(a.name_and_type_index, b.name_and_type_index)?
+                    && self.bootstrap_method_eq(
+                        a.bootstrap_method_attr_index,
+                        b.bootstrap_method_attr_index,
+                        depth,
+                    )?
+            }
+            (ConstantInfo::Dynamic(a), ConstantInfo::Dynamic(b)) => {
+                self.name_and_type_eq(a.name_and_type_index, b.name_and_type_index)?
+                    && self.bootstrap_method_eq(
+                        a.bootstrap_method_attr_index,
+                        b.bootstrap_method_attr_index,
+                        depth,
+                    )?
+            }
+            (ConstantInfo::Unusable, _) | (_, ConstantInfo::Unusable) => {
+                return Err(LoadError::Unknown)
+            }
+            _ => false,
+        };
+        self.compared.borrow_mut().insert((a.0, b.0), eq);
+        Ok(eq)
+    }
+
+    /// Whether the code does the same thing, instruction by instruction
+    fn code_eq(&mut self, a: &CodeAttribute, b: &CodeAttribute) -> Result<bool, LoadError> {
+        if a.max_stack != b.max_stack || a.max_locals != b.max_locals {
+            return Ok(false);
+        }
+
+        let decode = |side: &Side, code: &CodeAttribute| {
+            let bytes = side.data.get(code.code.clone()).ok_or(LoadError::Unknown)?;
+            code_iter(bytes)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| LoadError::Unknown)
+        };
+        let a_instructions = decode(&self.a, a)?;
+        let b_instructions = decode(&self.b, b)?;
+        if a_instructions.len() != b_instructions.len()
+            || a.exception_table.len() != b.exception_table.len()
+        {
+            return Ok(false);
+        }
+        self.a.offsets = a_instructions.iter().map(|&(offset, _)| offset).collect();
+        self.a.code_length = a.code.len();
+        self.b.offsets = b_instructions.iter().map(|&(offset, _)| offset).collect();
+        self.b.code_length = b.code.len();
+
+        for ((a_offset, a), (b_offset, b)) in a_instructions.iter().zip(b_instructions.iter()) {
+            if strip(a) != strip(b) {
+                return Ok(false);
+            }
+            match (constant_operand(a), constant_operand(b)) {
+                (Some(a), Some(b)) => {
+                    if !self.constant_eq(a, b, 0)? {
+                        return Ok(false);
+                    }
+                }
+                (None, None) => {}
+                _ => return Ok(false),
+            }
+            let a_targets = branch_targets(*a_offset, a);
+            let b_targets = branch_targets(*b_offset, b);
+            for (&a, &b) in a_targets.iter().zip(b_targets.iter()) {
+                if self.a.instruction_at(a)? != self.b.instruction_at(b)? {
+                    return Ok(false);
+                }
+            }
+        }
+
+        for (a, b) in a.exception_table.iter().zip(b.exception_table.iter()) {
+            let a_pcs = [a.start_pc.0, a.end_pc.0, a.handler_pc.0];
+            let b_pcs = [b.start_pc.0, b.end_pc.0, b.handler_pc.0];
+            for (&a, &b) in a_pcs.iter().zip(b_pcs.iter()) {
+                if self.a.instruction_at(a.into())? != self.b.instruction_at(b.into())? {
+                    return Ok(false);
+                }
+            }
+            if !self.class_eq(a.catch_type, b.catch_type)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// The instruction with its constant pool index and branch offsets set to zero, so that what is
+/// left can be compared directly.
+/// `ldc` becomes `ldc_w`, since which one is used only depends on the index.
+fn strip(instruction: &Instruction) -> Instruction {
+    let mut stripped = instruction.clone();
+    match &mut stripped {
+        Instruction::Ldc(_) => return Instruction::LdcW(ConstantPoolIndexRaw::new(0)),
+        Instruction::LdcW(index)
+        | Instruction::Ldc2W(index)
+        | Instruction::Invokespecial(index)
+        | Instruction::Invokestatic(index) => *index = ConstantPoolIndexRaw::new(0),
+        Instruction::Getstatic(index)
+        | Instruction::Putstatic(index)
+        | Instruction::Getfield(index)
+        | Instruction::Putfield(index) => *index = ConstantPoolIndexRaw::new(0),
+        Instruction::Invokevirtual(index) => *index = ConstantPoolIndexRaw::new(0),
+        Instruction::Invokeinterface { index, .. } => *index = ConstantPoolIndexRaw::new(0),
+        Instruction::Invokedynamic(index) => *index = ConstantPoolIndexRaw::new(0),
+        Instruction::New(index)
+        | Instruction::Anewarray(index)
+        | Instruction::Checkcast(index)
+        | Instruction::Instanceof(index)
+        | Instruction::Multianewarray { index, .. } => *index = ConstantPoolIndexRaw::new(0),
+        Instruction::Ifeq(offset)
+        | Instruction::Ifne(offset)
+        | Instruction::Iflt(offset)
+        | Instruction::Ifge(offset)
+        | Instruction::Ifgt(offset)
+        | Instruction::Ifle(offset)
+        | Instruction::IfIcmpeq(offset)
+        | Instruction::IfIcmpne(offset)
+        | Instruction::IfIcmplt(offset)
+        | Instruction::IfIcmpge(offset)
+        | Instruction::IfIcmpgt(offset)
+        | Instruction::IfIcmple(offset)
+        | Instruction::IfAcmpeq(offset)
+        | Instruction::IfAcmpne(offset)
+        | Instruction::Goto(offset)
+        | Instruction::Jsr(offset)
+        | Instruction::Ifnull(offset)
+        | Instruction::Ifnonnull(offset) => *offset = 0,
+        Instruction::GotoW(offset) | Instruction::JsrW(offset) => *offset = 0,
+        Instruction::Tableswitch {
+            default, offsets, ..
+        } => {
+            *default = 0;
+            offsets.iter_mut().for_each(|offset| *offset = 0);
+        }
+        Instruction::Lookupswitch { default, pairs } => {
+            *default = 0;
+            pairs.iter_mut().for_each(|(_, offset)| *offset = 0);
+        }
+        _ => {}
+    }
+    stripped
+}
+
+/// The constant that the instruction refers to, if any
+fn constant_operand(instruction: &Instruction) -> Option<ConstantPoolIndexRaw<ConstantInfo>> {
+    Some(match *instruction {
+        Instruction::Ldc(index) => ConstantPoolIndexRaw::new(index.into()),
+        Instruction::LdcW(index)
+        | Instruction::Ldc2W(index)
+        | Instruction::Invokespecial(index)
+        | Instruction::Invokestatic(index) => index,
+        Instruction::Getstatic(index)
+        | Instruction::Putstatic(index)
+        | Instruction::Getfield(index)
+        | Instruction::Putfield(index) => index.into_generic(),
+        Instruction::Invokevirtual(index) => index.into_generic(),
+        Instruction::Invokeinterface { index, .. } => index.into_generic(),
+        Instruction::Invokedynamic(index) => index.into_generic(),
+        Instruction::New(index)
+        | Instruction::Anewarray(index)
+        | Instruction::Checkcast(index)
+        | Instruction::Instanceof(index)
+        | Instruction::Multianewarray { index, .. } => index.into_generic(),
+        _ => return None,
+    })
+}
+
+/// The offsets that the instruction at the offset can jump to
+fn branch_targets(offset: u16, instruction: &Instruction) -> Vec<i64> {
+    let target = |relative: i32| i64::from(offset) + i64::from(relative);
+    match instruction {
+        Instruction::Ifeq(relative)
+        | Instruction::Ifne(relative)
+        | Instruction::Iflt(relative)
+        | Instruction::Ifge(relative)
+        | Instruction::Ifgt(relative)
+        | Instruction::Ifle(relative)
+        | Instruction::IfIcmpeq(relative)
+        | Instruction::IfIcmpne(relative)
+        | Instruction::IfIcmplt(relative)
+        | Instruction::IfIcmpge(relative)
+        | Instruction::IfIcmpgt(relative)
+        | Instruction::IfIcmple(relative)
+        | Instruction::IfAcmpeq(relative)
+        | Instruction::IfAcmpne(relative)
+        | Instruction::Goto(relative)
+        | Instruction::Jsr(relative)
+        | Instruction::Ifnull(relative)
+        | Instruction::Ifnonnull(relative) => vec![target((*relative).into())],
+        Instruction::GotoW(relative) | Instruction::JsrW(relative) => vec![target(*relative)],
+        Instruction::Tableswitch {
+            default, offsets, ..
+        } => std::iter::once(default)
+            .chain(offsets.iter())
+            .map(|&relative| target(relative))
+            .collect(),
+        Instruction::Lookupswitch { default, pairs } => std::iter::once(*default)
+            .chain(pairs.iter().map(|&(_, relative)| relative))
+            .map(target)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl MethodInfo {
+    /// Whether this method, in `class_file`, is a copy of `other`, in `other_class_file`.
+    /// The access flags, name, descriptor, generic signature and thrown exceptions must be the
+    /// same, as must the code and its exception handlers, where constants are compared by value
+    /// rather than by index.
+    /// Attributes of the code are not compared, which skips debug information such as line
+    /// numbers and local variable names along with stack map frames, which follow from the code.
+    /// Other attributes of the method, such as annotations, are not compared either.
+    /// Errors if either method refers to constants or attributes that can't be read.
+    pub fn structurally_eq(
+        &self,
+        class_file: &ClassFile,
+        class_file_data: &[u8],
+        other: &MethodInfo,
+        other_class_file: &ClassFile,
+        other_class_file_data: &[u8],
+    ) -> Result<bool, LoadError> {
+        let mut comparison = Comparison {
+            a: Side::new(class_file, class_file_data)?,
+            b: Side::new(other_class_file, other_class_file_data)?,
+            compared: RefCell::new(HashMap::new()),
+        };
+        let (a_pool, b_pool) = (comparison.a.pool, comparison.b.pool);
+
+        if self.raw_flags() != other.raw_flags()
+            || !comparison.utf8_eq(self.name_index, other.name_index)?
+            || !comparison.utf8_eq(self.descriptor_index, other.descriptor_index)?
+        {
+            return Ok(false);
+        }
+
+        let a = self.find_attribute::<SignatureAttribute>(a_pool, class_file_data)?;
+        let b = other.find_attribute::<SignatureAttribute>(b_pool, other_class_file_data)?;
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                if !comparison.utf8_eq(a.signature_index, b.signature_index)? {
+                    return Ok(false);
+                }
+            }
+            (None, None) => {}
+            _ => return Ok(false),
+        }
+
+        let a = self.find_attribute::<ExceptionsAttribute>(a_pool, class_file_data)?;
+        let b = other.find_attribute::<ExceptionsAttribute>(b_pool, other_class_file_data)?;
+        let a = a.map_or_else(Vec::new, |attr| attr.exception_table);
+        let b = b.map_or_else(Vec::new, |attr| attr.exception_table);
+        if a.len() != b.len() {
+            return Ok(false);
+        }
+        for (&a, &b) in a.iter().zip(b.iter()) {
+            if !comparison.class_eq(a, b)? {
+                return Ok(false);
+            }
+        }
+
+        let a = self.find_attribute::<CodeAttribute>(a_pool, class_file_data)?;
+        let b = other.find_attribute::<CodeAttribute>(b_pool, other_class_file_data)?;
+        match (a, b) {
+            (Some(a), Some(b)) => comparison.code_eq(&a, &b),
+            (None, None) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::attribute_info::{CodeAttributeOpt, HasAttributes};
+use crate::constant_pool::ConstantPool;
+use crate::descriptor::method::MethodDescriptor;
+use crate::method_info::{MethodAccessFlags, MethodInfo};
+use crate::LoadError;
+
+/// What a local variable slot holds when the method is entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalSlot {
+    /// The receiver of an instance method
+    This,
+    /// The parameter with the index, counting from zero and not counting `this`
+    Parameter(usize),
+    /// The second slot of the long or double parameter with the index
+    ParameterUpper(usize),
+    /// A slot after the parameters, which the code uses for its own locals
+    Local,
+}
+
+/// The layout of the local variables when a method is entered: `this` in slot 0 for instance
+/// methods, then each parameter in order, with longs and doubles taking two slots.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-2.html#jvms-2.6.1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalSlots {
+    has_this: bool,
+    /// The first slot of each parameter
+    parameter_slots: Vec<u16>,
+    /// Whether each parameter takes two slots
+    wide: Vec<bool>,
+    /// The number of slots that `this` and the parameters take up
+    parameters_size: u16,
+    max_locals: u16,
+}
+impl LocalSlots {
+    pub fn new(
+        descriptor: &MethodDescriptor,
+        access_flags: MethodAccessFlags,
+        max_locals: u16,
+    ) -> LocalSlots {
+        let has_this = !access_flags.contains(MethodAccessFlags::STATIC);
+        let mut slot = u16::from(has_this);
+        let mut parameter_slots = Vec::with_capacity(descriptor.parameter_types.len());
+        let mut wide = Vec::with_capacity(descriptor.parameter_types.len());
+        for parameter in descriptor.parameter_types.iter() {
+            parameter_slots.push(slot);
+            wide.push(parameter.is_wide());
+            // Descriptors over 255 slots are invalid, so this only saturates for bad class files
+            slot = slot.saturating_add(if parameter.is_wide() { 2 } else { 1 });
+        }
+
+        LocalSlots {
+            has_this,
+            parameter_slots,
+            wide,
+            parameters_size: slot,
+            max_locals,
+        }
+    }
+
+    /// Get the layout of the method's locals, using the `max_locals` of its Code attribute.
+    /// Returns None if the method has no code, such as abstract and native methods.
+    /// Errors if the descriptor or the Code attribute can't be parsed.
+    pub fn for_method(
+        method: &MethodInfo,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+    ) -> Result<Option<LocalSlots>, LoadError> {
+        let code = match method.find_attribute::<CodeAttributeOpt>(pool, class_file_data)? {
+            Some(code) => code,
+            None => return Ok(None),
+        };
+        let descriptor = pool
+            .get_t(method.descriptor_index)
+            .ok_or(LoadError::Unknown)?;
+        let descriptor = MethodDescriptor::parse(descriptor.as_bytes(class_file_data))
+            .map_err(|_| LoadError::Unknown)?;
+        Ok(Some(LocalSlots::new(
+            &descriptor,
+            method.access_flags,
+            code.max_locals,
+        )))
+    }
+
+    /// Whether slot 0 holds `this`, which it does for every method that isn't static
+    pub fn has_this(&self) -> bool {
+        self.has_this
+    }
+
+    pub fn parameter_count(&self) -> usize {
+        self.parameter_slots.len()
+    }
+
+    /// The number of slots that `this` and the parameters take up, which is also the first slot
+    /// that the code can use for its own locals
+    pub fn parameters_size(&self) -> u16 {
+        self.parameters_size
+    }
+
+    pub fn max_locals(&self) -> u16 {
+        self.max_locals
+    }
+
+    /// Whether `max_locals` leaves room for `this` and every parameter, which the JVM requires
+    pub fn fits(&self) -> bool {
+        self.parameters_size <= self.max_locals
+    }
+
+    /// The first slot of the parameter
+    pub fn parameter_slot(&self, parameter: usize) -> Option<u16> {
+        self.parameter_slots.get(parameter).copied()
+    }
+
+    /// Whether the parameter is a long or double, and so takes up two slots
+    pub fn is_wide(&self, parameter: usize) -> Option<bool> {
+        self.wide.get(parameter).copied()
+    }
+
+    /// What the slot holds when the method is entered.
+    /// Returns None if the slot isn't below `max_locals`.
+    pub fn slot(&self, slot: u16) -> Option<LocalSlot> {
+        if slot >= self.max_locals {
+            return None;
+        }
+        if self.has_this && slot == 0 {
+            return Some(LocalSlot::This);
+        }
+        if slot >= self.parameters_size {
+            return Some(LocalSlot::Local);
+        }
+
+        // The slot is within the parameters, so the last one starting at or before it holds it
+        let parameter = self.parameter_slots.partition_point(|&start| start <= slot) - 1;
+        if self.parameter_slots[parameter] == slot {
+            Some(LocalSlot::Parameter(parameter))
+        } else {
+            Some(LocalSlot::ParameterUpper(parameter))
+        }
+    }
+
+    /// The parameter whose value starts in the slot
+    pub fn parameter_at(&self, slot: u16) -> Option<usize> {
+        match self.slot(slot)? {
+            LocalSlot::Parameter(parameter) => Some(parameter),
+            _ => None,
+        }
+    }
+}
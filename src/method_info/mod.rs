@@ -1,8 +1,14 @@
+mod builder;
+mod compare;
+mod locals;
 mod parser;
 mod types;
 
+pub use self::builder::{MethodBuilder, MethodBuilderError};
+pub use self::locals::{LocalSlot, LocalSlots};
+pub(crate) use self::parser::attributes_search_by;
 pub use self::parser::{
-    attributes_search_parser, method_opt_parser, method_parser, skip_method_attributes_parser,
-    skip_method_parser,
+    attributes_search_all_parser, attributes_search_parser, method_opt_parser, method_parser,
+    skip_method_attributes_parser, skip_method_parser,
 };
 pub use self::types::*;
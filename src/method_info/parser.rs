@@ -1,4 +1,3 @@
-use std::borrow::Cow;
 use std::ops::Range;
 
 use nom::bytes::complete::take;
@@ -9,14 +8,16 @@ use nom::IResult;
 use crate::attribute_info::{attribute_parser, skip_attribute_parser};
 
 use crate::constant_info::{ConstantInfo, Utf8Constant};
-use crate::constant_pool::ConstantPool;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw, PoolRef};
 use crate::method_info::{MethodAccessFlags, MethodInfo};
 
+use crate::parser::combinators::{count_sv, skip_count};
 use crate::parser::ParseData;
-use crate::util::{constant_pool_index_raw, count_sv, skip_count};
+use crate::util::constant_pool_index_raw;
 
 use super::MethodInfoOpt;
 
+/// Skip over a method and its attributes
 pub fn skip_method_parser(i: ParseData) -> IResult<ParseData, ()> {
     let (i, _) = be_u16(i)?;
     let (i, _) = constant_pool_index_raw::<ConstantInfo>(i)?;
@@ -26,6 +27,7 @@ pub fn skip_method_parser(i: ParseData) -> IResult<ParseData, ()> {
     Ok((i, ()))
 }
 
+/// Skip over the given number of attributes, such as those that follow a [`MethodInfoOpt`]
 pub fn skip_method_attributes_parser(
     i: ParseData,
     attributes_count: u16,
@@ -44,6 +46,7 @@ pub fn method_parser(i: ParseData) -> IResult<ParseData, MethodInfo> {
         i,
         MethodInfo {
             access_flags: MethodAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
             name_index,
             descriptor_index,
             attributes_count,
@@ -62,6 +65,7 @@ pub fn method_opt_parser(i: ParseData) -> IResult<ParseData, MethodInfoOpt> {
         i,
         MethodInfoOpt {
             access_flags: MethodAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
             name_index,
             descriptor_index,
             attributes_count,
@@ -78,17 +82,26 @@ pub fn attributes_search_parser<'a>(
     constant_pool: &ConstantPool,
     name: &str,
     attributes_count: u16,
+) -> IResult<ParseData<'a>, Option<(u16, Range<usize>)>> {
+    attributes_search_by(input, attributes_count, |name_index| {
+        PoolRef::Loaded(constant_pool).is_utf8(name_index, class_file_data, name)
+    })
+}
+
+/// Like [`attributes_search_parser`], but with a function deciding whether the index of an
+/// attribute's name is the name being searched for, so that it works with lazy constant pools
+pub(crate) fn attributes_search_by<'a>(
+    input: ParseData<'a>,
+    attributes_count: u16,
+    mut is_named: impl FnMut(ConstantPoolIndexRaw<Utf8Constant>) -> bool,
 ) -> IResult<ParseData<'a>, Option<(u16, Range<usize>)>> {
     let mut input = input;
     for cur in 0..attributes_count {
         let (i, name_index) = constant_pool_index_raw::<Utf8Constant>(input)?;
-        if let Some(attr_name) = constant_pool.get_t(name_index) {
-            let attr_name = attr_name.as_text(class_file_data);
-            if Cow::Borrowed(name) == attr_name {
-                let (i, attribute_length) = be_u32(i)?;
-                let (i, info) = take(attribute_length)(i)?;
-                return Ok((i, Some((cur, info.as_range()))));
-            }
+        if is_named(name_index) {
+            let (i, attribute_length) = be_u32(i)?;
+            let (i, info) = take(attribute_length)(i)?;
+            return Ok((i, Some((cur, info.as_range()))));
         }
         let (i, attribute_length) = be_u32(i)?;
         let (i, _) = take(attribute_length)(i)?;
@@ -97,3 +110,30 @@ pub fn attributes_search_parser<'a>(
 
     Ok((input, None))
 }
+
+/// Like [`attributes_search_parser`], but finds every attribute with the name rather than
+/// stopping at the first, returning the index and range of each.
+/// Some producers write the same attribute more than once, which this lets tools notice.
+pub fn attributes_search_all_parser<'a>(
+    input: ParseData<'a>,
+    class_file_data: &[u8],
+    constant_pool: &ConstantPool,
+    name: &str,
+    attributes_count: u16,
+) -> IResult<ParseData<'a>, Vec<(u16, Range<usize>)>> {
+    let mut input = input;
+    let mut found = Vec::new();
+    for cur in 0..attributes_count {
+        let (i, name_index) = constant_pool_index_raw::<Utf8Constant>(input)?;
+        let (i, attribute_length) = be_u32(i)?;
+        let (i, info) = take(attribute_length)(i)?;
+        if let Some(attr_name) = constant_pool.get_t(name_index) {
+            if attr_name.as_text(class_file_data) == name {
+                found.push((cur, info.as_range()));
+            }
+        }
+        input = i;
+    }
+
+    Ok((input, found))
+}
@@ -1,30 +1,121 @@
 use smallvec::SmallVec;
 
-use crate::attribute_info::AttributeInfo;
+use crate::attribute_info::{AttributeInfo, CodeAttributeOpt, HasAttributes, KnownAttribute};
+use crate::util::AccessFlags;
 
-use crate::{constant_info::Utf8Constant, constant_pool::ConstantPoolIndexRaw};
+use crate::{
+    constant_info::Utf8Constant,
+    constant_pool::{ConstantPool, ConstantPoolIndexRaw},
+    LoadError,
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct MethodInfo {
     pub access_flags: MethodAccessFlags,
+    /// The access flags as they were in the class file, including bits which have no defined meaning
+    pub raw_access_flags: u16,
     pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
     pub descriptor_index: ConstantPoolIndexRaw<Utf8Constant>,
     pub attributes_count: u16,
     pub attributes: SmallVec<[AttributeInfo; 4]>,
 }
 
+impl MethodInfo {
+    /// The access flags as a raw value, including any bits that have no defined meaning
+    pub fn raw_flags(&self) -> u16 {
+        self.access_flags.with_undefined(self.raw_access_flags)
+    }
+
+    /// The number of bytes the method takes up in the class file, including its attributes
+    pub fn encoded_size(&self) -> usize {
+        // access flags, name index, descriptor index, and attributes count
+        let header = 8;
+        header
+            + self
+                .attributes
+                .iter()
+                .map(|attr| 6 + attr.info.len())
+                .sum::<usize>()
+    }
+
+    /// The size of the method's Code attribute, if it has one
+    pub fn code_size(
+        &self,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+    ) -> Result<Option<CodeSize>, LoadError> {
+        let info = match self.find_attribute_info(pool, class_file_data, CodeAttributeOpt::NAME) {
+            Some(info) => info,
+            None => return Ok(None),
+        };
+        let code = CodeAttributeOpt::parse_info(info, class_file_data)?;
+        Ok(Some(CodeSize {
+            attribute: 6 + info.info.len(),
+            code_length: code.code_range.len(),
+        }))
+    }
+
+    /// The sizes of the method and its Code attribute
+    pub fn size(
+        &self,
+        pool: &ConstantPool,
+        class_file_data: &[u8],
+    ) -> Result<MethodSize, LoadError> {
+        Ok(MethodSize {
+            name_index: self.name_index,
+            descriptor_index: self.descriptor_index,
+            total: self.encoded_size(),
+            code: self.code_size(pool, class_file_data)?,
+        })
+    }
+}
+
+impl HasAttributes for MethodInfo {
+    fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+}
+
+/// The encoded size of a Code attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeSize {
+    /// The size of the whole attribute, including its header
+    pub attribute: usize,
+    /// The size of the bytecode alone, which is what JIT inlining thresholds are measured in
+    pub code_length: usize,
+}
+
+/// The encoded size of a method, see [`MethodInfo::size`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodSize {
+    pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    pub descriptor_index: ConstantPoolIndexRaw<Utf8Constant>,
+    /// The size of the method including all of its attributes
+    pub total: usize,
+    /// The size of the Code attribute, if the method has one
+    pub code: Option<CodeSize>,
+}
+
 // TODO: Make MethodInfoOpt a field of MethodInfo?
 #[derive(Clone, Debug)]
 pub struct MethodInfoOpt {
     pub access_flags: MethodAccessFlags,
+    /// The access flags as they were in the class file, including bits which have no defined meaning
+    pub raw_access_flags: u16,
     pub name_index: ConstantPoolIndexRaw<Utf8Constant>,
     pub descriptor_index: ConstantPoolIndexRaw<Utf8Constant>,
     pub attributes_count: u16,
 }
 impl MethodInfoOpt {
+    /// The access flags as a raw value, including any bits that have no defined meaning
+    pub fn raw_flags(&self) -> u16 {
+        self.access_flags.with_undefined(self.raw_access_flags)
+    }
+
     pub fn from_method_info(m: &MethodInfo) -> MethodInfoOpt {
         MethodInfoOpt {
             access_flags: m.access_flags,
+            raw_access_flags: m.raw_access_flags,
             name_index: m.name_index,
             descriptor_index: m.descriptor_index,
             attributes_count: m.attributes_count,
@@ -48,3 +139,10 @@ bitflags! {
         const SYNTHETIC = 0x1000;    // 	Declared synthetic; not present in the source code.
     }
 }
+impl AccessFlags for MethodAccessFlags {
+    const DEFINED: u16 = MethodAccessFlags::all().bits();
+
+    fn defined_bits(self) -> u16 {
+        self.bits()
+    }
+}
@@ -0,0 +1,151 @@
+//! Reconstructing the modifiers that classes and members were declared with in the source.
+//!
+//! Access flags include modifiers that the source leaves implicit, such as `public abstract` on
+//! the methods of an interface, and some of them aren't modifiers at all, such as the synthetic,
+//! bridge, and varargs flags of methods. These functions give the modifiers as they would be
+//! written in the source, in the order that the JLS suggests, such as `public static final`.
+//! Annotations, `sealed`, and `non-sealed` are not included, since they are not in the flags.
+
+use crate::attribute_info::{HasAttributes, InnerClassAccessFlags, InnerClassesAttribute};
+use crate::field_info::FieldAccessFlags;
+use crate::method_info::MethodAccessFlags;
+use crate::names::INIT;
+use crate::{ClassFile, ClassKind};
+
+/// The modifiers of a class of the kind with the flags, such as `public abstract`.
+/// Enums, records, interfaces, and annotations are never written as static, since only nested
+/// ones can be and those are implicitly static, and neither are the flags that are implied by
+/// their kind written.
+/// Modifiers that are implied by the class that a nested class is in, such as `public static`
+/// for classes in interfaces, are kept, since the flags don't say what that class is.
+pub fn class_modifiers(kind: ClassKind, flags: InnerClassAccessFlags) -> String {
+    if kind == ClassKind::Module {
+        return String::new();
+    }
+    let mut modifiers = access_modifiers(
+        flags.contains(InnerClassAccessFlags::PUBLIC),
+        flags.contains(InnerClassAccessFlags::PROTECTED),
+        flags.contains(InnerClassAccessFlags::PRIVATE),
+    );
+    let plain_class = kind == ClassKind::Class;
+    if plain_class && flags.contains(InnerClassAccessFlags::ABSTRACT) {
+        modifiers.push("abstract");
+    }
+    if plain_class && flags.contains(InnerClassAccessFlags::STATIC) {
+        modifiers.push("static");
+    }
+    if plain_class && flags.contains(InnerClassAccessFlags::FINAL) {
+        modifiers.push("final");
+    }
+    modifiers.join(" ")
+}
+
+/// The modifiers of a field with the flags in a class of the kind.
+/// Enum constants and the fields of interfaces have none, since they are implicitly
+/// `public static final`, and the fields of records' components are implicitly `private final`.
+pub fn field_modifiers(owner: ClassKind, flags: FieldAccessFlags) -> String {
+    let implicit = if flags.contains(FieldAccessFlags::ENUM)
+        || matches!(owner, ClassKind::Interface | ClassKind::Annotation)
+    {
+        FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL
+    } else if owner == ClassKind::Record && !flags.contains(FieldAccessFlags::STATIC) {
+        FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL
+    } else {
+        FieldAccessFlags::empty()
+    };
+    let flags = flags - implicit;
+
+    let mut modifiers = access_modifiers(
+        flags.contains(FieldAccessFlags::PUBLIC),
+        flags.contains(FieldAccessFlags::PROTECTED),
+        flags.contains(FieldAccessFlags::PRIVATE),
+    );
+    for (flag, modifier) in [
+        (FieldAccessFlags::STATIC, "static"),
+        (FieldAccessFlags::FINAL, "final"),
+        (FieldAccessFlags::TRANSIENT, "transient"),
+        (FieldAccessFlags::VOLATILE, "volatile"),
+    ] {
+        if flags.contains(flag) {
+            modifiers.push(modifier);
+        }
+    }
+    modifiers.join(" ")
+}
+
+/// The modifiers of a method with the name and flags in a class of the kind.
+/// The methods of interfaces are implicitly public, and abstract unless they have a body, in
+/// which case instance methods are written as `default`. The constructors of enums are implicitly
+/// private.
+pub fn method_modifiers(owner: ClassKind, name: &str, flags: MethodAccessFlags) -> String {
+    let in_interface = matches!(owner, ClassKind::Interface | ClassKind::Annotation);
+    let implicit = if in_interface {
+        MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT
+    } else if owner == ClassKind::Enum && name == INIT {
+        MethodAccessFlags::PRIVATE
+    } else {
+        MethodAccessFlags::empty()
+    };
+    let default = in_interface
+        && !flags.intersects(
+            MethodAccessFlags::ABSTRACT | MethodAccessFlags::STATIC | MethodAccessFlags::PRIVATE,
+        );
+    let flags = flags - implicit;
+
+    let mut modifiers = access_modifiers(
+        flags.contains(MethodAccessFlags::PUBLIC),
+        flags.contains(MethodAccessFlags::PROTECTED),
+        flags.contains(MethodAccessFlags::PRIVATE),
+    );
+    if default {
+        modifiers.push("default");
+    }
+    for (flag, modifier) in [
+        (MethodAccessFlags::ABSTRACT, "abstract"),
+        (MethodAccessFlags::STATIC, "static"),
+        (MethodAccessFlags::FINAL, "final"),
+        (MethodAccessFlags::SYNCHRONIZED, "synchronized"),
+        (MethodAccessFlags::NATIVE, "native"),
+        (MethodAccessFlags::STRICT, "strictfp"),
+    ] {
+        if flags.contains(flag) {
+            modifiers.push(modifier);
+        }
+    }
+    modifiers.join(" ")
+}
+
+fn access_modifiers(public: bool, protected: bool, private: bool) -> Vec<&'static str> {
+    let mut modifiers = Vec::new();
+    if public {
+        modifiers.push("public");
+    }
+    if protected {
+        modifiers.push("protected");
+    }
+    if private {
+        modifiers.push("private");
+    }
+    modifiers
+}
+
+impl ClassFile {
+    /// The modifiers that the class was declared with, see [`class_modifiers`].
+    /// Nested classes use the flags of their own entry in the InnerClasses attribute, which unlike
+    /// those of the class file can be private, protected, and static.
+    pub fn source_modifiers(&self, data: &[u8]) -> String {
+        let inner_flags = self
+            .find_attribute::<InnerClassesAttribute>(&self.const_pool, data)
+            .ok()
+            .flatten()
+            .and_then(|attr| {
+                attr.classes
+                    .iter()
+                    .find(|entry| entry.inner_class_info_index == self.this_class)
+                    .map(|entry| entry.access_flags())
+            });
+        let flags = inner_flags
+            .unwrap_or_else(|| InnerClassAccessFlags::from_bits_truncate(self.access_flags.bits()));
+        class_modifiers(self.kind(data), flags)
+    }
+}
@@ -0,0 +1,217 @@
+//! Conversions between the different forms that class names take.
+//!
+//! - Internal names use `/` as the package separator: `java/util/Map$Entry`
+//! - Binary names use `.` as the package separator: `java.util.Map$Entry`
+//! - Descriptors wrap the internal name: `Ljava/util/Map$Entry;`
+//!
+//! Array classes are the exception, as their internal name is their descriptor: `[Ljava/lang/String;`
+//! [see more](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.2.1)
+//!
+//! There are also constants for the names and descriptors that class files refer to most often.
+
+use std::borrow::Cow;
+
+/// The root of the class hierarchy, which is the only class without a superclass
+pub const OBJECT: &str = "java/lang/Object";
+pub const STRING: &str = "java/lang/String";
+pub const CLASS: &str = "java/lang/Class";
+pub const THROWABLE: &str = "java/lang/Throwable";
+pub const OBJECT_DESCRIPTOR: &str = "Ljava/lang/Object;";
+pub const STRING_DESCRIPTOR: &str = "Ljava/lang/String;";
+
+/// The name of the instance initialization methods, which are the constructors
+pub const INIT: &str = "<init>";
+/// The name of the class initialization method, which is the static initializer
+pub const CLINIT: &str = "<clinit>";
+/// The descriptor of a method that takes nothing and returns void, such as a default constructor
+pub const VOID_METHOD_DESCRIPTOR: &str = "()V";
+
+/// Whether the internal name is `java/lang/Object`
+pub fn is_object(internal: &str) -> bool {
+    internal == OBJECT
+}
+
+/// Whether the method name is `<init>`
+pub fn is_constructor(name: &str) -> bool {
+    name == INIT
+}
+
+/// Whether the method name is `<clinit>`
+pub fn is_static_initializer(name: &str) -> bool {
+    name == CLINIT
+}
+
+/// Whether the method name is `<init>` or `<clinit>`, which are the only method names that may
+/// contain `<` or `>`, and which can't be invoked like other methods
+pub fn is_initializer(name: &str) -> bool {
+    is_constructor(name) || is_static_initializer(name)
+}
+
+/// Whether the name refers to an array class, which is named by its descriptor
+pub fn is_array_name(name: &str) -> bool {
+    name.starts_with('[')
+}
+
+/// `java/lang/String` -> `java.lang.String`
+pub fn internal_to_binary(internal: &str) -> Cow<'_, str> {
+    if internal.contains('/') {
+        Cow::Owned(internal.replace('/', "."))
+    } else {
+        Cow::Borrowed(internal)
+    }
+}
+
+/// `java.lang.String` -> `java/lang/String`
+pub fn binary_to_internal(binary: &str) -> Cow<'_, str> {
+    if binary.contains('.') {
+        Cow::Owned(binary.replace('.', "/"))
+    } else {
+        Cow::Borrowed(binary)
+    }
+}
+
+/// `java/lang/String` -> `Ljava/lang/String;`
+/// Array classes are already descriptors, and so are returned as is.
+pub fn internal_to_descriptor(internal: &str) -> Cow<'_, str> {
+    if is_array_name(internal) {
+        Cow::Borrowed(internal)
+    } else {
+        Cow::Owned(format!("L{};", internal))
+    }
+}
+
+/// `Ljava/lang/String;` -> `java/lang/String`
+/// Array descriptors are returned as is, since that is their internal name.
+/// Returns None for primitive descriptors or malformed input.
+pub fn descriptor_to_internal(descriptor: &str) -> Option<&str> {
+    if is_array_name(descriptor) {
+        return Some(descriptor);
+    }
+
+    let name = descriptor.strip_prefix('L')?.strip_suffix(';')?;
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// `java/util/Map$Entry` -> `java/util`
+/// Returns None for classes in the unnamed package and for array classes.
+pub fn package_of(internal: &str) -> Option<&str> {
+    if is_array_name(internal) {
+        return None;
+    }
+
+    internal.rfind('/').map(|i| &internal[..i])
+}
+
+/// `java/util/Map$Entry` -> `Map$Entry`
+pub fn class_name_of(internal: &str) -> &str {
+    match internal.rfind('/') {
+        Some(i) => &internal[i + 1..],
+        None => internal,
+    }
+}
+
+/// `java/util/Map$Entry` -> `Entry`
+/// This follows the naming scheme that javac uses for nested classes: anonymous classes
+/// (`Outer$1`) have an empty simple name, and local classes (`Outer$1Local`) lose their numeric
+/// prefix.
+/// Note that `$` is a legal character in any class name, so this can be fooled. The InnerClasses
+/// attribute is the authoritative source when it is available.
+pub fn simple_name_of(internal: &str) -> &str {
+    let name = class_name_of(internal);
+    let name = match name.rfind('$') {
+        Some(i) => &name[i + 1..],
+        None => return name,
+    };
+
+    name.trim_start_matches(|c: char| c.is_ascii_digit())
+}
+
+/// Check that the name is a valid unqualified name, as used for members and the parts of an
+/// internal name.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.2.2)
+pub fn is_valid_unqualified_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['.', ';', '[', '/'])
+}
+
+/// Check that the name is a valid internal name for a (non-array) class or interface
+pub fn is_valid_internal_name(internal: &str) -> bool {
+    internal.split('/').all(is_valid_unqualified_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(
+            internal_to_binary("java/util/Map$Entry"),
+            "java.util.Map$Entry"
+        );
+        assert_eq!(
+            binary_to_internal("java.util.Map$Entry"),
+            "java/util/Map$Entry"
+        );
+        assert_eq!(binary_to_internal("Foo"), "Foo");
+
+        assert_eq!(
+            internal_to_descriptor("java/lang/String"),
+            "Ljava/lang/String;"
+        );
+        assert_eq!(internal_to_descriptor("[I"), "[I");
+        assert_eq!(
+            descriptor_to_internal("Ljava/lang/String;"),
+            Some("java/lang/String")
+        );
+        assert_eq!(
+            descriptor_to_internal("[Ljava/lang/String;"),
+            Some("[Ljava/lang/String;")
+        );
+        assert_eq!(descriptor_to_internal("I"), None);
+        assert_eq!(descriptor_to_internal("L;"), None);
+        assert_eq!(descriptor_to_internal("Ljava/lang/String"), None);
+    }
+
+    #[test]
+    fn parts() {
+        assert_eq!(package_of("java/util/Map$Entry"), Some("java/util"));
+        assert_eq!(package_of("Foo"), None);
+        assert_eq!(package_of("[Ljava/lang/String;"), None);
+
+        assert_eq!(class_name_of("java/util/Map$Entry"), "Map$Entry");
+        assert_eq!(simple_name_of("java/util/Map$Entry"), "Entry");
+        assert_eq!(simple_name_of("java/lang/String"), "String");
+        assert_eq!(simple_name_of("a/Outer$1"), "");
+        assert_eq!(simple_name_of("a/Outer$1Local"), "Local");
+        assert_eq!(simple_name_of("Foo"), "Foo");
+    }
+
+    #[test]
+    fn well_known() {
+        assert!(is_object(OBJECT));
+        assert!(!is_object("java/lang/Objects"));
+        assert_eq!(internal_to_descriptor(OBJECT), OBJECT_DESCRIPTOR);
+        assert_eq!(descriptor_to_internal(STRING_DESCRIPTOR), Some(STRING));
+        assert!(is_constructor("<init>"));
+        assert!(!is_constructor(CLINIT));
+        assert!(is_static_initializer("<clinit>"));
+        assert!(is_initializer(INIT) && is_initializer(CLINIT));
+        assert!(!is_initializer("init"));
+    }
+
+    #[test]
+    fn validity() {
+        assert!(is_valid_internal_name("java/lang/String"));
+        assert!(is_valid_internal_name("Foo$Bar"));
+        assert!(!is_valid_internal_name(""));
+        assert!(!is_valid_internal_name("java//String"));
+        assert!(!is_valid_internal_name("java/lang/"));
+        assert!(!is_valid_internal_name("java.lang.String"));
+        assert!(!is_valid_internal_name("[I"));
+        assert!(!is_valid_unqualified_name("a;b"));
+    }
+}
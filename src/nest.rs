@@ -0,0 +1,309 @@
+//! Reconstructing how classes are nested inside each other.
+//!
+//! The compiler turns nested classes into separate class files, and records the nesting in the
+//! InnerClasses, EnclosingMethod, NestHost, and NestMembers attributes spread across them.
+//! [`nest_tree`] gathers these back together into a tree rooted at the top-level class.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::attribute_info::{
+    EnclosingMethodAttribute, HasAttributes, InnerClassesAttribute, NestHostAttribute,
+    NestMembersAttribute,
+};
+use crate::constant_info::{ClassConstant, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::names;
+use crate::provider::{ClassProvider, LoadedClass};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NestError {
+    /// The class could not be loaded from the provider
+    MissingClass(String),
+    /// The nesting attributes of the class could not be read
+    Malformed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestedKind {
+    TopLevel,
+    /// Declared directly in the body of another class
+    Member,
+    /// Declared with a name inside a method or initializer
+    Local,
+    Anonymous,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NestNode {
+    /// The internal name of the class
+    pub name: String,
+    pub kind: NestedKind,
+    /// The name in the source, which is None for anonymous classes
+    pub simple_name: Option<String>,
+    /// The fully qualified name as written in source, like `java.util.Map.Entry`.
+    /// Local and anonymous classes can't be named from outside, so they don't have one.
+    pub qualified_name: Option<String>,
+    /// The name and descriptor of the method that a local or anonymous class is declared in, if
+    /// it is declared in a method rather than an initializer
+    pub enclosing_method: Option<(String, String)>,
+    /// The classes declared inside this one, sorted by name
+    pub children: Vec<NestNode>,
+}
+impl NestNode {
+    /// Find the node for the class with the internal name in this tree
+    pub fn find(&self, name: &str) -> Option<&NestNode> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(name))
+    }
+}
+
+/// An entry of an InnerClasses attribute, with the names resolved
+#[derive(Debug, Clone)]
+struct InnerClassEntry {
+    inner: String,
+    outer: Option<String>,
+    simple_name: Option<String>,
+}
+
+/// The enclosing class, and the name and descriptor of the method if there is one
+type EnclosingMethod = (String, Option<(String, String)>);
+
+/// The nesting attributes of one class
+#[derive(Debug, Default)]
+struct NestAttributes {
+    inner_classes: Vec<InnerClassEntry>,
+    enclosing_method: Option<EnclosingMethod>,
+    nest_host: Option<String>,
+    nest_members: Option<Vec<String>>,
+}
+
+fn class_name(
+    pool: &ConstantPool,
+    data: &[u8],
+    index: ConstantPoolIndexRaw<ClassConstant>,
+) -> Option<String> {
+    let class = pool.get_t(index)?;
+    utf8(pool, data, class.name_index)
+}
+
+fn utf8(
+    pool: &ConstantPool,
+    data: &[u8],
+    index: ConstantPoolIndexRaw<Utf8Constant>,
+) -> Option<String> {
+    Some(pool.get_t(index)?.as_text(data).into_owned())
+}
+
+fn read_attributes(class: &LoadedClass) -> Option<NestAttributes> {
+    let class_file = &class.class_file;
+    let pool = &class_file.const_pool;
+    let data = class.data.as_slice();
+    let optional_class = |index: ConstantPoolIndexRaw<ClassConstant>| {
+        if index.is_zero() {
+            Some(None)
+        } else {
+            class_name(pool, data, index).map(Some)
+        }
+    };
+
+    let mut attrs = NestAttributes::default();
+    if let Some(attr) = class_file
+        .find_attribute::<InnerClassesAttribute>(pool, data)
+        .ok()?
+    {
+        for entry in attr.classes {
+            attrs.inner_classes.push(InnerClassEntry {
+                inner: class_name(pool, data, entry.inner_class_info_index)?,
+                outer: optional_class(entry.outer_class_info_index)?,
+                simple_name: if entry.inner_name_index.is_zero() {
+                    None
+                } else {
+                    Some(utf8(pool, data, entry.inner_name_index)?)
+                },
+            });
+        }
+    }
+    if let Some(attr) = class_file
+        .find_attribute::<EnclosingMethodAttribute>(pool, data)
+        .ok()?
+    {
+        let method = if attr.method_index.is_zero() {
+            None
+        } else {
+            let nat = pool.get_t(attr.method_index)?;
+            Some((
+                utf8(pool, data, nat.name_index)?,
+                utf8(pool, data, nat.descriptor_index)?,
+            ))
+        };
+        attrs.enclosing_method = Some((class_name(pool, data, attr.class_index)?, method));
+    }
+    if let Some(attr) = class_file
+        .find_attribute::<NestHostAttribute>(pool, data)
+        .ok()?
+    {
+        attrs.nest_host = Some(class_name(pool, data, attr.host_class_index)?);
+    }
+    if let Some(attr) = class_file
+        .find_attribute::<NestMembersAttribute>(pool, data)
+        .ok()?
+    {
+        let members = attr
+            .classes
+            .into_iter()
+            .map(|index| class_name(pool, data, index))
+            .collect::<Option<Vec<_>>>()?;
+        attrs.nest_members = Some(members);
+    }
+
+    Some(attrs)
+}
+
+fn load(provider: &dyn ClassProvider, name: &str) -> Result<Option<NestAttributes>, NestError> {
+    match provider.load_class(name) {
+        Some(class) => read_attributes(&class)
+            .map(Some)
+            .ok_or_else(|| NestError::Malformed(name.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Find the top-level class that the class is nested in, which is the class itself if it is not
+/// nested
+pub fn top_level_class(provider: &dyn ClassProvider, name: &str) -> Result<String, NestError> {
+    let mut current = name.to_string();
+    let mut seen = HashSet::new();
+    loop {
+        let attrs =
+            load(provider, &current)?.ok_or_else(|| NestError::MissingClass(current.clone()))?;
+        if let Some(host) = attrs.nest_host {
+            return Ok(host);
+        }
+
+        let outer = attrs
+            .inner_classes
+            .iter()
+            .find(|entry| entry.inner == current)
+            .and_then(|entry| entry.outer.clone())
+            .or_else(|| attrs.enclosing_method.map(|(class, _)| class));
+        match outer {
+            // A cycle is malformed, but the JVM doesn't check for them, so stop at the repeat
+            Some(outer) if seen.insert(current.clone()) => current = outer,
+            _ => return Ok(current),
+        }
+    }
+}
+
+/// Build the tree of classes nested in the top-level class that the named class is nested in.
+///
+/// Classes which can't be loaded from the provider are still included if another class records
+/// them, but they may be placed directly under the top-level class if their enclosing class isn't
+/// known.
+/// Before Java 11, class files do not record the members of a nest, so classes whose names start
+/// with the top-level class's name followed by `$` are assumed to be members.
+pub fn nest_tree(provider: &dyn ClassProvider, name: &str) -> Result<NestNode, NestError> {
+    let host = top_level_class(provider, name)?;
+    let host_attrs = load(provider, &host)?.ok_or_else(|| NestError::MissingClass(host.clone()))?;
+
+    let prefix = format!("{}$", host);
+    let declared_members: Option<HashSet<String>> = host_attrs
+        .nest_members
+        .as_ref()
+        .map(|members| members.iter().cloned().collect());
+    let in_nest = |name: &str| match &declared_members {
+        Some(members) => members.contains(name),
+        None => name.starts_with(&prefix),
+    };
+
+    let mut entries: HashMap<String, InnerClassEntry> = HashMap::new();
+    let mut enclosing: HashMap<String, EnclosingMethod> = HashMap::new();
+    let mut members = BTreeSet::new();
+    let mut queue = VecDeque::new();
+    let mut record = |name: String,
+                      attrs: NestAttributes,
+                      members: &mut BTreeSet<String>,
+                      queue: &mut VecDeque<String>| {
+        for entry in attrs.inner_classes {
+            if in_nest(&entry.inner) && members.insert(entry.inner.clone()) {
+                queue.push_back(entry.inner.clone());
+            }
+            entries.entry(entry.inner.clone()).or_insert(entry);
+        }
+        if let Some(method) = attrs.enclosing_method {
+            enclosing.insert(name, method);
+        }
+    };
+
+    for member in host_attrs.nest_members.iter().flatten() {
+        if members.insert(member.clone()) {
+            queue.push_back(member.clone());
+        }
+    }
+    record(host.clone(), host_attrs, &mut members, &mut queue);
+    while let Some(member) = queue.pop_front() {
+        if let Some(attrs) = load(provider, &member)? {
+            record(member, attrs, &mut members, &mut queue);
+        }
+    }
+
+    // Group every member under the class it is declared in
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    for member in members.iter() {
+        let entry = entries.get(member);
+        let parent = entry
+            .and_then(|entry| entry.outer.clone())
+            .or_else(|| enclosing.get(member).map(|(class, _)| class.clone()))
+            .filter(|parent| parent == &host || members.contains(parent))
+            .unwrap_or_else(|| host.clone());
+        children.entry(parent).or_default().push(member.clone());
+    }
+
+    let root = NestNode {
+        name: host.clone(),
+        kind: NestedKind::TopLevel,
+        simple_name: Some(names::class_name_of(&host).to_string()),
+        qualified_name: Some(names::internal_to_binary(&host).into_owned()),
+        enclosing_method: None,
+        children: Vec::new(),
+    };
+    Ok(build(root, &mut children, &entries, &enclosing))
+}
+
+fn build(
+    mut node: NestNode,
+    children: &mut HashMap<String, Vec<String>>,
+    entries: &HashMap<String, InnerClassEntry>,
+    enclosing: &HashMap<String, EnclosingMethod>,
+) -> NestNode {
+    // Removing the children means each class is only visited once, even with cycles
+    let names = children.remove(&node.name).unwrap_or_default();
+    for name in names {
+        let entry = entries.get(&name);
+        let simple_name = entry.and_then(|entry| entry.simple_name.clone());
+        let kind = match entry {
+            Some(entry) if entry.outer.is_some() => NestedKind::Member,
+            _ if simple_name.is_none() => NestedKind::Anonymous,
+            _ => NestedKind::Local,
+        };
+        let qualified_name = match (&node.qualified_name, &simple_name, kind) {
+            (Some(parent), Some(simple_name), NestedKind::Member) => {
+                Some(format!("{}.{}", parent, simple_name))
+            }
+            _ => None,
+        };
+        let child = NestNode {
+            enclosing_method: enclosing.get(&name).and_then(|(_, method)| method.clone()),
+            name,
+            kind,
+            simple_name,
+            qualified_name,
+            children: Vec::new(),
+        };
+        node.children
+            .push(build(child, children, entries, enclosing));
+    }
+
+    node
+}
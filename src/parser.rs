@@ -1,32 +1,62 @@
+use std::convert::TryFrom;
 use std::iter::{Copied, Enumerate};
-use std::ops::{Range, RangeFrom};
+use std::ops::{Range, RangeFrom, RangeTo};
 use std::slice::Iter;
 
 use nom::bytes::complete::tag;
+use nom::combinator::verify;
 use nom::number::complete::be_u16;
 use nom::{
     AsBytes, ExtendInto, FindSubstring, FindToken, IResult, InputIter, InputLength, InputTake,
     Needed, Slice, UnspecializedInput,
 };
 
-use crate::attribute_info::{attribute_parser, skip_attribute_parser};
-use crate::constant_info::constant_parser;
+use crate::attribute_info::{
+    attribute_parser, names, nesting_too_deep, skip_attribute_parser, AttributeInfo, CodeAttribute,
+    HasAttributes, KnownAttribute, RecordAttribute, SignatureAttribute,
+};
+use crate::constant_info::{constant_offsets_parser, constant_parser};
 use crate::field_info::{field_parser, skip_field_parser};
 use crate::method_info::{method_parser, skip_method_parser};
+use crate::recover::{recovering_fields_parser, recovering_methods_parser};
+use crate::stale::StaleRanges;
 use crate::types::{ClassAccessFlags, ClassFile};
 use crate::{ClassFileOpt, ClassFileVersion, OptSmallVec};
 
-use crate::constant_pool::ConstantPool;
-use crate::util::{constant_pool_index_raw, count_sv, skip_count};
+use crate::constant_pool::{ConstantPool, ConstantPoolOpt};
+use crate::error::ParseError;
+use crate::util::constant_pool_index_raw;
+use combinators::{count_sv, skip_count};
+
+pub mod combinators;
 
 // named!(magic_parser, tag!(&[0xCA, 0xFE, 0xBA, 0xBE]));
 
+pub(crate) const MAGIC: &[u8] = &[0xCA, 0xFE, 0xBA, 0xBE];
+
 fn magic_parser(i: ParseData) -> IResult<ParseData, ()> {
-    let magic: &[u8] = &[0xCA, 0xFE, 0xBA, 0xBE];
-    let (i, _) = tag(magic)(i)?;
+    let (i, _) = tag(MAGIC)(i)?;
     Ok((i, ()))
 }
 
+/// The `constant_pool_count`, which must be at least 1 as it is one more than the number of slots
+pub(crate) fn constant_pool_count_parser(i: ParseData) -> IResult<ParseData, u16> {
+    verify(be_u16, |&count| count != 0)(i)
+}
+
+/// Check the parts of the header which have their own errors, before parsing the rest
+pub(crate) fn check_header(data: &[u8]) -> Result<(), ParseError> {
+    if !data.starts_with(MAGIC) {
+        return Err(ParseError::BadMagic);
+    }
+    // The magic is followed by the minor and major version
+    if data.get(8..10) == Some(&[0, 0]) {
+        return Err(ParseError::EmptyConstantPool);
+    }
+
+    Ok(())
+}
+
 /// Parse a byte array into a ClassFile. This will probably be deprecated in 0.4.0 in as it returns
 /// a nom IResult type, which exposes the internal parsing library and not a good idea.
 ///
@@ -45,12 +75,21 @@ fn magic_parser(i: ParseData) -> IResult<ParseData, ()> {
 /// };
 /// ```
 pub fn class_parser(i: ParseData) -> IResult<ParseData, ClassFile> {
+    class_parser_with(i, false)
+}
+
+/// Parse the class file, recovering from wrong attribute counts if asked to, see
+/// [`ParseOptions::recover_attribute_counts`]
+fn class_parser_with(
+    i: ParseData,
+    recover_attribute_counts: bool,
+) -> IResult<ParseData, ClassFile> {
     let (i, _) = magic_parser(i)?;
 
     let (i, minor_version) = be_u16(i)?;
     let (i, major_version) = be_u16(i)?;
 
-    let (i, const_pool_size) = be_u16(i)?;
+    let (i, const_pool_size) = constant_pool_count_parser(i)?;
     let (i, const_pool) = constant_parser(i, (const_pool_size - 1).into())?;
 
     let (i, access_flags) = be_u16(i)?;
@@ -61,11 +100,21 @@ pub fn class_parser(i: ParseData) -> IResult<ParseData, ClassFile> {
     let (i, interfaces_count) = be_u16(i)?;
     let (i, interfaces) = count_sv(constant_pool_index_raw, interfaces_count.into())(i)?;
 
+    let const_pool = ConstantPool::new(const_pool);
+
     let (i, fields_count) = be_u16(i)?;
-    let (i, fields) = count_sv(field_parser, fields_count.into())(i)?;
+    let (i, fields) = if recover_attribute_counts {
+        recovering_fields_parser(i, fields_count, &const_pool)?
+    } else {
+        count_sv(field_parser, fields_count.into())(i)?
+    };
 
     let (i, methods_count) = be_u16(i)?;
-    let (i, methods) = count_sv(method_parser, methods_count.into())(i)?;
+    let (i, methods) = if recover_attribute_counts {
+        recovering_methods_parser(i, methods_count, &const_pool)?
+    } else {
+        count_sv(method_parser, methods_count.into())(i)?
+    };
 
     let (i, attributes_count) = be_u16(i)?;
     let (i, attributes) = count_sv(attribute_parser, attributes_count.into())(i)?;
@@ -78,8 +127,9 @@ pub fn class_parser(i: ParseData) -> IResult<ParseData, ClassFile> {
                 minor: minor_version,
             },
             const_pool_size,
-            const_pool: ConstantPool::new(const_pool),
+            const_pool,
             access_flags: ClassAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
             this_class,
             super_class,
             interfaces_count,
@@ -90,18 +140,40 @@ pub fn class_parser(i: ParseData) -> IResult<ParseData, ClassFile> {
             methods,
             attributes_count,
             attributes,
+            descriptor_cache: None,
+            typed_attributes: None,
+            stale_ranges: StaleRanges::new(),
         },
     ))
 }
 
 pub fn class_parser_opt(i: ParseData) -> IResult<ParseData, ClassFileOpt> {
+    class_parser_opt_with(i, false)
+}
+
+/// Parse the lazy class file, only recording where the constants are if asked to, see
+/// [`ParseOptions::lazy_constant_pool`]
+fn class_parser_opt_with(
+    i: ParseData,
+    lazy_constant_pool: bool,
+) -> IResult<ParseData, ClassFileOpt> {
     let (i, _) = magic_parser(i)?;
 
     let (i, minor_version) = be_u16(i)?;
     let (i, major_version) = be_u16(i)?;
 
-    let (i, const_pool_size) = be_u16(i)?;
-    let (i, const_pool) = constant_parser(i, (const_pool_size - 1).into())?;
+    let (i, const_pool_size) = constant_pool_count_parser(i)?;
+    let (i, const_pool, lazy_const_pool) = if lazy_constant_pool {
+        let (i, offsets) = constant_offsets_parser(i, (const_pool_size - 1).into())?;
+        (
+            i,
+            ConstantPool::default(),
+            Some(ConstantPoolOpt::new(offsets)),
+        )
+    } else {
+        let (i, const_pool) = constant_parser(i, (const_pool_size - 1).into())?;
+        (i, ConstantPool::new(const_pool), None)
+    };
 
     let (i, access_flags) = be_u16(i)?;
 
@@ -134,8 +206,10 @@ pub fn class_parser_opt(i: ParseData) -> IResult<ParseData, ClassFileOpt> {
                 minor: minor_version,
             },
             const_pool_size,
-            const_pool: ConstantPool::new(const_pool),
+            const_pool,
+            lazy_const_pool,
             access_flags: ClassAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
             this_class,
             super_class,
             interfaces_count,
@@ -147,6 +221,198 @@ pub fn class_parser_opt(i: ParseData) -> IResult<ParseData, ClassFileOpt> {
     ))
 }
 
+/// The nesting depth that is allowed by default when walking recursive structures, such as
+/// element values in annotations.
+/// This is above the 255 dimensions that an array type may have.
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 256;
+
+/// Options for parsing a class file through [`ClassFile::parse`] and [`ClassFileOpt::parse`]
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Reject class files which have data after the end of the attributes table.
+    /// The JVM ignores such data, which makes it a place to smuggle payloads.
+    pub reject_trailing_bytes: bool,
+    /// Reject class files which contain predefined attributes that the JVM does not recognize
+    /// until a later class file version than the one declared, such as a StackMapTable in a
+    /// version 49 class.
+    /// Only applies to [`ClassFile::parse`], since the lazy class file does not load attributes.
+    pub reject_attributes_before_version: bool,
+    /// Parse every attribute which has a type as the class file is parsed, see
+    /// [`ClassFile::parse_typed_attributes`].
+    /// Only applies to [`ClassFile::parse`].
+    pub parse_typed_attributes: bool,
+    /// Reject class files with annotations or signatures which are nested deeper than this,
+    /// counting each array and nested annotation in an element value, and each array dimension
+    /// and type argument list in a signature.
+    /// Only applies to [`ClassFile::parse`], which then checks the attributes of the class, its
+    /// fields, methods, record components, and code.
+    pub max_nesting_depth: Option<usize>,
+    /// Recover from fields and methods whose `attributes_count` is wrong, by guessing where
+    /// their attributes end from what the data looks like, see [`crate::recover`].
+    /// The mismatches are kept, and can be found with [`ClassFile::attribute_count_mismatches`].
+    /// Only applies to [`ClassFile::parse`].
+    pub recover_attribute_counts: bool,
+    /// Only record where each constant pool entry starts, rather than parsing the entries, see
+    /// [`ClassFileOpt::lazy_const_pool`].
+    /// Only applies to [`ClassFileOpt::parse`].
+    pub lazy_constant_pool: bool,
+}
+impl ParseOptions {
+    /// Options which reject anything suspicious, even if the JVM would accept it
+    pub fn strict() -> ParseOptions {
+        ParseOptions {
+            reject_trailing_bytes: true,
+            reject_attributes_before_version: true,
+            parse_typed_attributes: false,
+            max_nesting_depth: Some(DEFAULT_MAX_NESTING_DEPTH),
+            recover_attribute_counts: false,
+            lazy_constant_pool: false,
+        }
+    }
+}
+
+fn check_trailing(rest: &ParseData, options: &ParseOptions) -> Result<(), ParseError> {
+    if options.reject_trailing_bytes && !rest.is_empty() {
+        return Err(ParseError::TrailingBytes {
+            offset: rest.pos(),
+            len: rest.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Whether the signature nests deeper than `max_depth`, where each array dimension and each list
+/// of type arguments is a level
+fn signature_too_deep(signature: &[u8], max_depth: usize) -> bool {
+    let mut arguments = 0usize;
+    let mut dimensions = 0usize;
+    for &byte in signature {
+        match byte {
+            b'<' => arguments += 1,
+            b'>' => arguments = arguments.saturating_sub(1),
+            b'[' => dimensions += 1,
+            _ => dimensions = 0,
+        }
+        if arguments + dimensions > max_depth {
+            return true;
+        }
+    }
+    false
+}
+
+/// Check the attributes with annotations or signatures, see [`ParseOptions::max_nesting_depth`]
+fn check_nesting(
+    pool: &ConstantPool,
+    attributes: &[AttributeInfo],
+    data: &[u8],
+    max_depth: usize,
+) -> Result<(), ParseError> {
+    for attr in attributes {
+        let name = match pool.get_t(attr.attribute_name_index) {
+            Some(name) => name.as_text(data),
+            None => continue,
+        };
+        let offset = match name.as_ref() {
+            names::SIGNATURE => SignatureAttribute::parse_info(attr, data)
+                .ok()
+                .and_then(|signature| pool.get_t(signature.signature_index))
+                .filter(|signature| signature_too_deep(signature.as_bytes(data), max_depth))
+                .map(|_| attr.info.start),
+            name => nesting_too_deep(name, attr, data, max_depth),
+        };
+        if let Some(offset) = offset {
+            return Err(ParseError::TooDeeplyNested { offset, max_depth });
+        }
+    }
+
+    Ok(())
+}
+
+impl ClassFile {
+    /// Check every attribute which can nest, including those inside Code and Record attributes
+    fn check_nesting_depth(&self, data: &[u8], max_depth: usize) -> Result<(), ParseError> {
+        let pool = &self.const_pool;
+        check_nesting(pool, &self.attributes, data, max_depth)?;
+        if let Ok(Some(record)) = self.find_attribute::<RecordAttribute>(pool, data) {
+            for component in record.components.iter() {
+                check_nesting(pool, &component.attributes, data, max_depth)?;
+            }
+        }
+        for field in self.fields.iter() {
+            check_nesting(pool, &field.attributes, data, max_depth)?;
+        }
+        for method in self.methods.iter() {
+            check_nesting(pool, &method.attributes, data, max_depth)?;
+            if let Ok(Some(code)) = method.find_attribute::<CodeAttribute>(pool, data) {
+                check_nesting(pool, &code.attributes, data, max_depth)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn parse(data: &[u8], options: &ParseOptions) -> Result<ClassFile, ParseError> {
+        check_header(data)?;
+
+        let (rest, mut class_file) =
+            class_parser_with(ParseData::new(data), options.recover_attribute_counts)
+                .map_err(|err| ParseError::from(err).with_item(data))?;
+        check_trailing(&rest, options)?;
+
+        if options.reject_attributes_before_version {
+            let violation = class_file
+                .attribute_version_violations(data)
+                .into_iter()
+                .next();
+            if let Some(violation) = violation {
+                return Err(ParseError::AttributeBeforeVersion {
+                    name: violation.name,
+                    offset: violation.offset,
+                    min_major: violation.min_major,
+                });
+            }
+        }
+
+        if let Some(max_depth) = options.max_nesting_depth {
+            class_file.check_nesting_depth(data, max_depth)?;
+        }
+
+        if options.parse_typed_attributes {
+            class_file.parse_typed_attributes(data)?;
+        }
+
+        Ok(class_file)
+    }
+}
+
+impl ClassFileOpt {
+    pub fn parse(data: &[u8], options: &ParseOptions) -> Result<ClassFileOpt, ParseError> {
+        check_header(data)?;
+
+        let (rest, class_file) =
+            class_parser_opt_with(ParseData::new(data), options.lazy_constant_pool)
+                .map_err(|err| ParseError::from(err).with_item(data))?;
+        check_trailing(&rest, options)?;
+        Ok(class_file)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ClassFile {
+    type Error = ParseError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        ClassFile::parse(data, &ParseOptions::default())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ClassFileOpt {
+    type Error = ParseError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        ClassFileOpt::parse(data, &ParseOptions::default())
+    }
+}
+
 /// Keeps track of the current slice and the position within the actual data that we're at
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseData<'a> {
@@ -236,6 +502,22 @@ impl<'a> Slice<RangeFrom<usize>> for ParseData<'a> {
         }
     }
 }
+impl<'a> Slice<Range<usize>> for ParseData<'a> {
+    fn slice(&self, range: Range<usize>) -> Self {
+        ParseData {
+            data: &self.data[range.clone()],
+            pos: self.pos + range.start,
+        }
+    }
+}
+impl<'a> Slice<RangeTo<usize>> for ParseData<'a> {
+    fn slice(&self, range: RangeTo<usize>) -> Self {
+        ParseData {
+            data: &self.data[range],
+            pos: self.pos,
+        }
+    }
+}
 impl<'a> InputLength for ParseData<'a> {
     fn input_len(&self) -> usize {
         self.data.len()
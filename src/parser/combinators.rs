@@ -0,0 +1,101 @@
+//! Combinators used by the parsers of this crate, for parsers of custom attributes and other
+//! extensions of the format to reuse.
+
+use nom::IResult;
+use smallvec::SmallVec;
+
+use crate::parser::ParseData;
+
+/// Like nom's `count`, but collecting into a SmallVec, which avoids allocating when there are
+/// only a few items
+pub fn count_sv<I, O, E, F, const N: usize>(
+    mut f: F,
+    count: usize,
+) -> impl FnMut(I) -> IResult<I, SmallVec<[O; N]>, E>
+where
+    I: Clone + PartialEq,
+    F: nom::Parser<I, O, E>,
+    E: nom::error::ParseError<I>,
+{
+    move |i: I| {
+        let mut input = i.clone();
+        let mut res = SmallVec::with_capacity(count);
+
+        for _ in 0..count {
+            let input_ = input.clone();
+            match f.parse(input_) {
+                Ok((i, o)) => {
+                    res.push(o);
+                    input = i;
+                }
+                Err(nom::Err::Error(e)) => {
+                    return Err(nom::Err::Error(E::append(
+                        i,
+                        nom::error::ErrorKind::Count,
+                        e,
+                    )));
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok((input, res))
+    }
+}
+
+/// Like [`count_sv`], but drops the parsed values.
+/// Note that you should probably be using custom versions of any complex functions since they might
+/// not get optimized out properly
+pub fn skip_count<I, O, E, F>(mut f: F, count: usize) -> impl FnMut(I) -> IResult<I, (), E>
+where
+    I: Clone + PartialEq,
+    F: nom::Parser<I, O, E>,
+    E: nom::error::ParseError<I>,
+{
+    move |i: I| {
+        let mut input = i.clone();
+
+        for _ in 0..count {
+            let input_ = input.clone();
+            match f.parse(input_) {
+                Ok((i, _)) => {
+                    input = i;
+                }
+                Err(nom::Err::Error(e)) => {
+                    return Err(nom::Err::Error(E::append(
+                        i,
+                        nom::error::ErrorKind::Count,
+                        e,
+                    )));
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok((input, ()))
+    }
+}
+
+/// Like [`count_sv`], but pairs each value with the offset in the class file data that it was
+/// parsed from
+#[allow(clippy::type_complexity)]
+pub fn count_sv_offsets<'a, O, E, F, const N: usize>(
+    mut f: F,
+    count: usize,
+) -> impl FnMut(ParseData<'a>) -> IResult<ParseData<'a>, SmallVec<[(usize, O); N]>, E>
+where
+    F: nom::Parser<ParseData<'a>, O, E>,
+    E: nom::error::ParseError<ParseData<'a>>,
+{
+    move |i: ParseData<'a>| {
+        let offset_parser = |input: ParseData<'a>| {
+            let offset = input.pos();
+            f.parse(input).map(|(rest, o)| (rest, (offset, o)))
+        };
+        count_sv(offset_parser, count)(i)
+    }
+}
@@ -0,0 +1,106 @@
+//! Parsing the lazily loaded parts of a [`ClassFileOpt`] on a background thread.
+//!
+//! This is useful when most of the class will be needed soon, such as an interpreter that has just
+//! loaded a class, since the parsing can happen while the caller continues with other work.
+
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use smallvec::SmallVec;
+
+use crate::attribute_info::{attribute_parser, AttributeInfo};
+use crate::field_info::{field_parser, FieldInfo};
+use crate::method_info::{method_parser, MethodInfo};
+use crate::parser::combinators::count_sv;
+use crate::parser::ParseData;
+use crate::{ClassFileOpt, LoadError, OptSmallVec};
+
+/// Where a section starts and how many entries it has, or None if it is already loaded
+type Section = Option<(usize, u16)>;
+
+fn section<T, const N: usize>(v: &OptSmallVec<T, N>) -> Section {
+    if v.has_data() {
+        None
+    } else {
+        Some((v.start_pos(), v.len()))
+    }
+}
+
+fn parse_section<'a, T, const N: usize>(
+    data: &'a [u8],
+    section: Section,
+    parser: impl FnMut(ParseData<'a>) -> nom::IResult<ParseData<'a>, T>,
+) -> Result<Option<SmallVec<[T; N]>>, LoadError> {
+    let (start_pos, count) = match section {
+        Some(section) => section,
+        None => return Ok(None),
+    };
+
+    let input = ParseData::from_pos(data, start_pos);
+    let (_, values) =
+        count_sv(parser, usize::from(count))(input).map_err(|_| LoadError::Unknown)?;
+    Ok(Some(values))
+}
+
+struct Parsed {
+    fields: Option<SmallVec<[FieldInfo; 6]>>,
+    methods: Option<SmallVec<[MethodInfo; 6]>>,
+    attributes: Option<SmallVec<[AttributeInfo; 4]>>,
+}
+
+/// A handle to the background parsing of a class file's fields, methods, and attributes.
+/// See [`prefetch`]
+pub struct Prefetch {
+    handle: JoinHandle<Result<Parsed, LoadError>>,
+}
+impl Prefetch {
+    /// Whether the background parsing has finished, so that [`Prefetch::finish`] won't block
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Wait for the background parsing to finish and fill the caches of the class file with the
+    /// results.
+    /// This should be the same class file that was prefetched, and sections which were filled in
+    /// the meantime are kept as they are.
+    pub fn finish(self, class_file: &mut ClassFileOpt) -> Result<(), LoadError> {
+        let parsed = self.handle.join().map_err(|_| LoadError::Unknown)??;
+
+        if let Some(fields) = parsed.fields {
+            if !class_file.fields.has_data() {
+                class_file.fields.fill(fields);
+            }
+        }
+        if let Some(methods) = parsed.methods {
+            if !class_file.methods.has_data() {
+                class_file.methods.fill(methods);
+            }
+        }
+        if let Some(attributes) = parsed.attributes {
+            if !class_file.attributes.has_data() {
+                class_file.attributes.fill(attributes);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Start parsing the fields, methods, and attributes of the class file on a background thread.
+/// Sections which are already loaded are skipped.
+pub fn prefetch(class_file: &ClassFileOpt, data: Arc<[u8]>) -> Prefetch {
+    let fields = section(&class_file.fields);
+    let methods = section(&class_file.methods);
+    let attributes = section(&class_file.attributes);
+
+    let handle = thread::spawn(move || {
+        let data: &[u8] = &data;
+        Ok(Parsed {
+            fields: parse_section(data, fields, field_parser)?,
+            methods: parse_section(data, methods, method_parser)?,
+            attributes: parse_section(data, attributes, attribute_parser)?,
+        })
+    });
+
+    Prefetch { handle }
+}
@@ -0,0 +1,84 @@
+//! Looking up other classes by name, for analyses that span more than one class file.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::ParseError;
+use crate::parser::ParseOptions;
+use crate::{ClassFile, LoadError};
+
+/// A parsed class file along with the data that its ranges refer into
+#[derive(Debug, Clone)]
+pub struct LoadedClass {
+    pub class_file: ClassFile,
+    pub data: Vec<u8>,
+}
+impl LoadedClass {
+    pub fn parse(data: Vec<u8>, options: &ParseOptions) -> Result<LoadedClass, ParseError> {
+        let class_file = ClassFile::parse(&data, options)?;
+        Ok(LoadedClass { class_file, data })
+    }
+
+    /// The internal name of the class, such as `java/lang/String`
+    pub fn name(&self) -> Option<String> {
+        let pool = &self.class_file.const_pool;
+        let class = pool.get_t(self.class_file.this_class)?;
+        let name = pool.get_t(class.name_index)?;
+        Some(name.as_text(&self.data).into_owned())
+    }
+}
+
+/// A source of classes, looked up by their internal name such as `java/lang/String`
+pub trait ClassProvider {
+    /// Get the class with the name, or None if it is not available
+    fn load_class(&self, name: &str) -> Option<Rc<LoadedClass>>;
+}
+
+/// Provides classes that have already been loaded into memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryClassProvider {
+    classes: HashMap<String, Rc<LoadedClass>>,
+}
+impl MemoryClassProvider {
+    pub fn new() -> MemoryClassProvider {
+        MemoryClassProvider::default()
+    }
+
+    /// Add the class under its own name, replacing any class with the same name.
+    /// Classes without a valid name are not added.
+    pub fn insert(&mut self, class: LoadedClass) -> Option<Rc<LoadedClass>> {
+        let name = class.name()?;
+        let class = Rc::new(class);
+        self.classes.insert(name, class.clone());
+        Some(class)
+    }
+
+    /// Parse the class file and add it under its own name.
+    /// Errors with [`LoadError::InvalidIndex`] if `this_class` isn't a Class entry with a name.
+    pub fn insert_data(&mut self, data: Vec<u8>) -> Result<(), LoadError> {
+        let class = LoadedClass::parse(data, &ParseOptions::default())?;
+        let this_class = class.class_file.this_class;
+        match self.insert(class) {
+            Some(_) => Ok(()),
+            None => Err(LoadError::InvalidIndex(this_class.0)),
+        }
+    }
+
+    /// The names of the classes, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.classes.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+}
+impl ClassProvider for MemoryClassProvider {
+    fn load_class(&self, name: &str) -> Option<Rc<LoadedClass>> {
+        self.classes.get(name).cloned()
+    }
+}
@@ -0,0 +1,96 @@
+//! Reading the components of a record class, along with the attributes they carry.
+//!
+//! Annotations on a component are kept in the component's own attributes, separately from the
+//! copies that javac places on the field and accessor method. A [`RecordComponent`] keeps the raw
+//! annotation attributes, which can be walked with [`visit_annotations`] and
+//! [`visit_type_annotations`].
+//!
+//! [`visit_annotations`]: crate::attribute_info::visit_annotations
+//! [`visit_type_annotations`]: crate::attribute_info::visit_type_annotations
+
+use smallvec::SmallVec;
+
+use crate::attribute_info::{
+    names, AttributeInfo, HasAttributes, RecordAttribute, RecordComponentInfo, SignatureAttribute,
+};
+use crate::constant_info::Utf8Constant;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::{ClassFile, LoadError};
+
+#[derive(Debug, Clone)]
+pub struct RecordComponent {
+    pub name: String,
+    pub descriptor: String,
+    /// The generic signature, if the component's type is generic
+    pub signature: Option<String>,
+    pub visible_annotations: Option<AttributeInfo>,
+    pub invisible_annotations: Option<AttributeInfo>,
+    /// Type annotations, such as those on a type argument or a `TYPE_USE` annotation on the
+    /// component's type
+    pub visible_type_annotations: Option<AttributeInfo>,
+    pub invisible_type_annotations: Option<AttributeInfo>,
+    /// Every attribute of the component, including the ones above
+    pub attributes: SmallVec<[AttributeInfo; 2]>,
+}
+
+impl HasAttributes for RecordComponent {
+    fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+}
+
+fn utf8(
+    pool: &ConstantPool,
+    data: &[u8],
+    index: ConstantPoolIndexRaw<Utf8Constant>,
+) -> Result<String, LoadError> {
+    pool.get_t(index)
+        .map(|text| text.as_text(data).into_owned())
+        .ok_or(LoadError::Unknown)
+}
+
+impl RecordComponent {
+    fn new(
+        info: &RecordComponentInfo,
+        pool: &ConstantPool,
+        data: &[u8],
+    ) -> Result<RecordComponent, LoadError> {
+        let signature = match info.find_attribute::<SignatureAttribute>(pool, data)? {
+            Some(attr) => Some(utf8(pool, data, attr.signature_index)?),
+            None => None,
+        };
+        let find = |name| info.find_attribute_info(pool, data, name).cloned();
+        Ok(RecordComponent {
+            name: utf8(pool, data, info.name_index)?,
+            descriptor: utf8(pool, data, info.descriptor_index)?,
+            signature,
+            visible_annotations: find(names::RUNTIME_VISIBLE_ANNOTATIONS),
+            invisible_annotations: find(names::RUNTIME_INVISIBLE_ANNOTATIONS),
+            visible_type_annotations: find(names::RUNTIME_VISIBLE_TYPE_ANNOTATIONS),
+            invisible_type_annotations: find(names::RUNTIME_INVISIBLE_TYPE_ANNOTATIONS),
+            attributes: info.attributes.clone(),
+        })
+    }
+}
+
+impl ClassFile {
+    /// The components of the record, in the order they are declared, or None if the class isn't
+    /// a record.
+    /// Errors if the Record attribute or the names it refers to are malformed.
+    pub fn record_components(
+        &self,
+        data: &[u8],
+    ) -> Result<Option<Vec<RecordComponent>>, LoadError> {
+        let pool = &self.const_pool;
+        let record = match self.find_attribute::<RecordAttribute>(pool, data)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        record
+            .components
+            .iter()
+            .map(|info| RecordComponent::new(info, pool, data))
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+}
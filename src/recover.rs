@@ -0,0 +1,188 @@
+//! Recovering from fields and methods whose `attributes_count` does not match their attributes,
+//! see [`crate::ParseOptions::recover_attribute_counts`].
+//!
+//! Some bytecode protectors write wrong counts on purpose, to break tools which trust them.
+//! The attributes are found by looking at what follows each one: an attribute has to be named by
+//! a Utf8 constant and fit in the data, while a member has to have a Utf8 name and descriptor.
+//! The high half of an attribute's length is usually zero, which is never a valid name index, so
+//! the two are rarely confused.
+
+use nom::number::complete::{be_u16, be_u32};
+use nom::IResult;
+use smallvec::SmallVec;
+
+use crate::attribute_info::{attribute_parser, AttributeInfo, AttributeOwner};
+use crate::constant_info::Utf8Constant;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::field_info::{FieldAccessFlags, FieldInfo};
+use crate::method_info::{MethodAccessFlags, MethodInfo};
+use crate::parser::ParseData;
+use crate::util::constant_pool_index_raw;
+use crate::ClassFile;
+
+/// A field or method whose `attributes_count` did not match the attributes that were found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeCountMismatch {
+    /// The field or method
+    pub owner: AttributeOwner,
+    /// The `attributes_count` in the class file
+    pub declared: u16,
+    /// The number of attributes that were found
+    pub found: usize,
+}
+
+impl ClassFile {
+    /// Find the fields and methods whose `attributes_count` does not match their attributes.
+    /// This is only ever non-empty for class files parsed with
+    /// [`crate::ParseOptions::recover_attribute_counts`], since otherwise the count is trusted.
+    pub fn attribute_count_mismatches(&self) -> Vec<AttributeCountMismatch> {
+        let fields = self.fields.iter().enumerate().map(|(i, field)| {
+            (
+                AttributeOwner::Field(i),
+                field.attributes_count,
+                field.attributes.len(),
+            )
+        });
+        let methods = self.methods.iter().enumerate().map(|(i, method)| {
+            (
+                AttributeOwner::Method(i),
+                method.attributes_count,
+                method.attributes.len(),
+            )
+        });
+        fields
+            .chain(methods)
+            .filter(|&(_, declared, found)| usize::from(declared) != found)
+            .map(|(owner, declared, found)| AttributeCountMismatch {
+                owner,
+                declared,
+                found,
+            })
+            .collect()
+    }
+}
+
+fn is_utf8(pool: &ConstantPool, index: u16) -> bool {
+    pool.get_t(ConstantPoolIndexRaw::<Utf8Constant>::new(index))
+        .is_some()
+}
+
+/// Whether the data starts with an attribute named by a Utf8 constant, that fits in the data
+fn plausible_attribute(i: &ParseData, pool: &ConstantPool) -> bool {
+    let header: IResult<ParseData, (u16, u32)> = (|i| {
+        let (i, name) = be_u16(i)?;
+        let (i, length) = be_u32(i)?;
+        Ok((i, (name, length)))
+    })(i.clone());
+    match header {
+        Ok((rest, (name, length))) => {
+            is_utf8(pool, name) && usize::try_from(length).is_ok_and(|len| len <= rest.len())
+        }
+        Err(_) => false,
+    }
+}
+
+/// Whether the data starts with a field or method with a Utf8 name and descriptor
+fn plausible_member(i: &ParseData, pool: &ConstantPool) -> bool {
+    let header: IResult<ParseData, (u16, u16)> = (|i| {
+        let (i, _) = be_u16(i)?;
+        let (i, name) = be_u16(i)?;
+        let (i, descriptor) = be_u16(i)?;
+        let (i, _) = be_u16(i)?;
+        Ok((i, (name, descriptor)))
+    })(i.clone());
+    match header {
+        Ok((_, (name, descriptor))) => is_utf8(pool, name) && is_utf8(pool, descriptor),
+        Err(_) => false,
+    }
+}
+
+/// Parse the attributes of a member, taking fewer than declared if the data stops looking like
+/// attributes, and more than declared if it keeps looking like attributes rather than the next
+/// member.
+/// The last member is never given more attributes than declared, since the table that follows it
+/// can't be told apart from attributes.
+fn recovering_attributes_parser<'a, const N: usize>(
+    mut i: ParseData<'a>,
+    declared: u16,
+    pool: &ConstantPool,
+    last: bool,
+) -> IResult<ParseData<'a>, SmallVec<[AttributeInfo; N]>> {
+    let mut attributes = SmallVec::new();
+    while plausible_attribute(&i, pool) {
+        let extra = attributes.len() >= usize::from(declared);
+        if extra && (last || plausible_member(&i, pool)) {
+            break;
+        }
+        let (rest, attribute) = attribute_parser(i)?;
+        attributes.push(attribute);
+        i = rest;
+    }
+    Ok((i, attributes))
+}
+
+type MemberHeader = (
+    u16,
+    ConstantPoolIndexRaw<Utf8Constant>,
+    ConstantPoolIndexRaw<Utf8Constant>,
+    u16,
+);
+
+fn member_header_parser(i: ParseData) -> IResult<ParseData, MemberHeader> {
+    let (i, access_flags) = be_u16(i)?;
+    let (i, name_index) = constant_pool_index_raw(i)?;
+    let (i, descriptor_index) = constant_pool_index_raw(i)?;
+    let (i, attributes_count) = be_u16(i)?;
+    Ok((
+        i,
+        (access_flags, name_index, descriptor_index, attributes_count),
+    ))
+}
+
+pub(crate) fn recovering_fields_parser<'a>(
+    mut i: ParseData<'a>,
+    count: u16,
+    pool: &ConstantPool,
+) -> IResult<ParseData<'a>, SmallVec<[FieldInfo; 6]>> {
+    let mut fields = SmallVec::with_capacity(count.into());
+    for index in 0..count {
+        let (rest, (access_flags, name_index, descriptor_index, attributes_count)) =
+            member_header_parser(i)?;
+        let (rest, attributes) =
+            recovering_attributes_parser(rest, attributes_count, pool, index + 1 == count)?;
+        fields.push(FieldInfo {
+            access_flags: FieldAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
+            name_index,
+            descriptor_index,
+            attributes_count,
+            attributes,
+        });
+        i = rest;
+    }
+    Ok((i, fields))
+}
+
+pub(crate) fn recovering_methods_parser<'a>(
+    mut i: ParseData<'a>,
+    count: u16,
+    pool: &ConstantPool,
+) -> IResult<ParseData<'a>, SmallVec<[MethodInfo; 6]>> {
+    let mut methods = SmallVec::with_capacity(count.into());
+    for index in 0..count {
+        let (rest, (access_flags, name_index, descriptor_index, attributes_count)) =
+            member_header_parser(i)?;
+        let (rest, attributes) =
+            recovering_attributes_parser(rest, attributes_count, pool, index + 1 == count)?;
+        methods.push(MethodInfo {
+            access_flags: MethodAccessFlags::from_bits_truncate(access_flags),
+            raw_access_flags: access_flags,
+            name_index,
+            descriptor_index,
+            attributes_count,
+            attributes,
+        });
+        i = rest;
+    }
+    Ok((i, methods))
+}
@@ -0,0 +1,727 @@
+//! Renumbering the constant pool of a class file.
+//!
+//! An [`IndexRemap`] maps the indices of an old constant pool to the indices of a rebuilt one.
+//! Applying it rewrites every reference to the pool: in the pool entries themselves, the class,
+//! fields, methods, the payloads of attributes, and the operands of instructions.
+//! Since indices are always two bytes (except for `ldc`), the rewritten attributes are the same
+//! length as the originals.
+
+use crate::attribute_info::names::AttributeName;
+use crate::attribute_info::{AttributeInfo, TargetInfoKind};
+use crate::constant_info::*;
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::field_info::FieldInfo;
+use crate::instructions::{code_iter, Instruction};
+use crate::method_info::MethodInfo;
+use crate::parser::DEFAULT_MAX_NESTING_DEPTH;
+use crate::stale::StaleRanges;
+use crate::ClassFile;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemapError {
+    /// The index was not a usable entry of the old pool
+    InvalidIndex(u16),
+    /// The index appeared more than once in the new order
+    DuplicateIndex(u16),
+    /// The entry at the old index is referenced, but was not given a place in the new pool
+    Unmapped(u16),
+    /// The new pool has more entries than a class file can hold
+    PoolTooLarge,
+    /// The `ldc` instruction at the code offset refers to an entry whose new index does not fit in
+    /// its single byte operand, which [`IndexRemap::fit_ldc`] avoids
+    LdcIndexTooLarge { offset: usize },
+    /// Code rewritten to fit the new indices is longer than the 65535 bytes that the JVM allows
+    CodeTooLong,
+    /// The attribute's layout is not known, so the indices in it can't be rewritten
+    UnknownAttribute(String),
+    /// An attribute or its code could not be read
+    Malformed,
+}
+
+/// A mapping from the raw indices of an old constant pool to those of a new one.
+/// Long and Double entries always take up two slots in the new pool, just like in the old one.
+#[derive(Debug, Clone)]
+pub struct IndexRemap {
+    /// The new raw index of each old raw index, where zero means it has no new index
+    map: Vec<u16>,
+    /// The old raw indices in their new order
+    order: Vec<u16>,
+    /// The length of the new pool, including the unusable slots
+    new_len: u16,
+}
+impl IndexRemap {
+    /// Create a mapping which places the entries of the old pool in the given order.
+    /// Entries which aren't in the order are dropped from the new pool, and are an error to
+    /// reference when applying the mapping.
+    pub fn from_order(
+        pool: &ConstantPool,
+        order: impl IntoIterator<Item = ConstantPoolIndexRaw<ConstantInfo>>,
+    ) -> Result<IndexRemap, RemapError> {
+        let mut map = vec![0u16; usize::from(pool.len()) + 1];
+        let mut new_order = Vec::new();
+        // The largest pool has a constant_pool_count of u16::MAX, and so that many - 1 slots
+        let mut next: u32 = 1;
+        for index in order {
+            let width = match pool.get(index) {
+                Some(ConstantInfo::Long(_)) | Some(ConstantInfo::Double(_)) => 2,
+                Some(ConstantInfo::Unusable) | None => {
+                    return Err(RemapError::InvalidIndex(index.0))
+                }
+                Some(_) => 1,
+            };
+            let slot = &mut map[usize::from(index.0)];
+            if *slot != 0 {
+                return Err(RemapError::DuplicateIndex(index.0));
+            }
+            if next + width > u32::from(u16::MAX) {
+                return Err(RemapError::PoolTooLarge);
+            }
+
+            *slot = next as u16;
+            new_order.push(index.0);
+            next += width;
+        }
+
+        Ok(IndexRemap {
+            map,
+            order: new_order,
+            new_len: (next - 1) as u16,
+        })
+    }
+
+    /// Create a mapping to the new indices, where zero means that the old index has no new one.
+    /// The new pool is built some other way, so [`IndexRemap::remap_pool`] can't be used with it.
+    pub(crate) fn from_map(map: Vec<u16>, new_len: u16) -> IndexRemap {
+        IndexRemap {
+            map,
+            order: Vec::new(),
+            new_len,
+        }
+    }
+
+    /// Create a mapping which keeps every entry where it is
+    pub fn identity(pool: &ConstantPool) -> IndexRemap {
+        IndexRemap::from_order(pool, pool.indices())
+            .expect("the indices of a pool are always a valid order")
+    }
+
+    /// The number of slots in the new pool, including the unusable slots after Long and Double
+    /// entries
+    pub fn new_len(&self) -> u16 {
+        self.new_len
+    }
+
+    /// Get the new index of the entry at the old index.
+    /// The zero index, which is used as 'none' in some places, always maps to itself.
+    pub fn get<T>(&self, index: ConstantPoolIndexRaw<T>) -> Option<ConstantPoolIndexRaw<T>> {
+        if index.is_zero() {
+            return Some(index);
+        }
+
+        match self.map.get(usize::from(index.0)) {
+            Some(0) | None => None,
+            Some(&new) => Some(ConstantPoolIndexRaw::new(new)),
+        }
+    }
+
+    fn index<T>(
+        &self,
+        index: ConstantPoolIndexRaw<T>,
+    ) -> Result<ConstantPoolIndexRaw<T>, RemapError> {
+        self.get(index).ok_or(RemapError::Unmapped(index.0))
+    }
+
+    fn raw(&self, index: u16) -> Result<u16, RemapError> {
+        self.index(ConstantPoolIndexRaw::<ConstantInfo>::new(index))
+            .map(|i| i.0)
+    }
+
+    /// Build the new pool from the old one, with the references between entries rewritten
+    pub fn remap_pool(&self, pool: &ConstantPool) -> Result<ConstantPool, RemapError> {
+        let mut entries = Vec::with_capacity(usize::from(self.new_len));
+        for &old in self.order.iter() {
+            let entry = pool
+                .get(ConstantPoolIndexRaw::<ConstantInfo>::new(old))
+                .ok_or(RemapError::InvalidIndex(old))?;
+            let wide = matches!(entry, ConstantInfo::Long(_) | ConstantInfo::Double(_));
+            entries.push(self.remap_entry(entry)?);
+            if wide {
+                entries.push(ConstantInfo::Unusable);
+            }
+        }
+
+        Ok(ConstantPool::new(entries))
+    }
+
+    fn remap_entry(&self, entry: &ConstantInfo) -> Result<ConstantInfo, RemapError> {
+        Ok(match entry {
+            ConstantInfo::Utf8(_)
+            | ConstantInfo::Integer(_)
+            | ConstantInfo::Float(_)
+            | ConstantInfo::Long(_)
+            | ConstantInfo::Double(_)
+            | ConstantInfo::Unusable => entry.clone(),
+            ConstantInfo::Class(c) => ConstantInfo::Class(ClassConstant {
+                name_index: self.index(c.name_index)?,
+            }),
+            ConstantInfo::String(c) => ConstantInfo::String(StringConstant {
+                string_index: self.index(c.string_index)?,
+            }),
+            ConstantInfo::FieldRef(c) => ConstantInfo::FieldRef(FieldRefConstant {
+                class_index: self.index(c.class_index)?,
+                name_and_type_index: self.index(c.name_and_type_index)?,
+            }),
+            ConstantInfo::MethodRef(c) => ConstantInfo::MethodRef(MethodRefConstant {
+                class_index: self.index(c.class_index)?,
+                name_and_type_index: self.index(c.name_and_type_index)?,
+            }),
+            ConstantInfo::InterfaceMethodRef(c) => {
+                ConstantInfo::InterfaceMethodRef(InterfaceMethodRefConstant {
+                    class_index: self.index(c.class_index)?,
+                    name_and_type_index: self.index(c.name_and_type_index)?,
+                })
+            }
+            ConstantInfo::NameAndType(c) => ConstantInfo::NameAndType(NameAndTypeConstant {
+                name_index: self.index(c.name_index)?,
+                descriptor_index: self.index(c.descriptor_index)?,
+            }),
+            ConstantInfo::MethodHandle(c) => ConstantInfo::MethodHandle(MethodHandleConstant {
+                reference_kind: c.reference_kind,
+                reference_index: self.index(c.reference_index)?,
+            }),
+            ConstantInfo::MethodType(c) => ConstantInfo::MethodType(MethodTypeConstant {
+                descriptor_index: self.index(c.descriptor_index)?,
+            }),
+            ConstantInfo::InvokeDynamic(c) => ConstantInfo::InvokeDynamic(InvokeDynamicConstant {
+                bootstrap_method_attr_index: c.bootstrap_method_attr_index,
+                name_and_type_index: self.index(c.name_and_type_index)?,
+            }),
+            ConstantInfo::Dynamic(c) => ConstantInfo::Dynamic(DynamicConstant {
+                bootstrap_method_attr_index: c.bootstrap_method_attr_index,
+                name_and_type_index: self.index(c.name_and_type_index)?,
+            }),
+            ConstantInfo::Module(c) => ConstantInfo::Module(ModuleConstant {
+                name_index: self.index(c.name_index)?,
+            }),
+            ConstantInfo::Package(c) => ConstantInfo::Package(PackageConstant {
+                name_index: self.index(c.name_index)?,
+            }),
+        })
+    }
+
+    /// Rewrite the class file to use the new pool.
+    /// Attributes whose indices change are rewritten into new bytes appended to `data`, with their
+    /// ranges updated to point at them.
+    /// On error, neither the class file nor `data` is modified.
+    pub fn apply(&self, class_file: &mut ClassFile, data: &mut Vec<u8>) -> Result<(), RemapError> {
+        let pool = self.remap_pool(&class_file.const_pool)?;
+
+        let mut fields = class_file.fields.clone();
+        for field in fields.iter_mut() {
+            field.name_index = self.index(field.name_index)?;
+            field.descriptor_index = self.index(field.descriptor_index)?;
+        }
+        let mut methods = class_file.methods.clone();
+        for method in methods.iter_mut() {
+            method.name_index = self.index(method.name_index)?;
+            method.descriptor_index = self.index(method.descriptor_index)?;
+        }
+        let this_class = self.index(class_file.this_class)?;
+        let super_class = self.index(class_file.super_class)?;
+        let interfaces = class_file
+            .interfaces
+            .iter()
+            .map(|&i| self.index(i))
+            .collect::<Result<_, _>>()?;
+
+        let mut class_attributes = class_file.attributes.clone();
+        let mut stale_ranges = class_file.stale_ranges.clone();
+        rewrite_attributes(
+            self,
+            &class_file.const_pool,
+            data,
+            &mut class_attributes,
+            &mut fields,
+            &mut methods,
+            &mut stale_ranges,
+        )?;
+
+        class_file.const_pool_size = pool.len() + 1;
+        class_file.const_pool = pool;
+        class_file.this_class = this_class;
+        class_file.super_class = super_class;
+        class_file.interfaces = interfaces;
+        class_file.fields = fields;
+        class_file.methods = methods;
+        class_file.attributes = class_attributes;
+        class_file.stale_ranges = stale_ranges;
+        // The cache is keyed by the old indices
+        if class_file.descriptor_cache.is_some() {
+            class_file.descriptor_cache = Some(Default::default());
+        }
+        class_file.typed_attributes = None;
+
+        Ok(())
+    }
+}
+
+/// Gives the new index of each old index while rewriting attributes
+pub(crate) trait IndexMapping {
+    fn map_index(&self, index: u16) -> Result<u16, RemapError>;
+}
+impl IndexMapping for IndexRemap {
+    fn map_index(&self, index: u16) -> Result<u16, RemapError> {
+        self.raw(index)
+    }
+}
+
+/// Rewrite the attributes of a class and of its fields and methods with the mapping.
+/// Attributes whose info changes are pointed at new bytes appended to `data`, and their old info is
+/// marked as stale.
+/// On error, nothing is modified.
+pub(crate) fn rewrite_attributes(
+    mapping: &dyn IndexMapping,
+    pool: &ConstantPool,
+    data: &mut Vec<u8>,
+    class_attributes: &mut [AttributeInfo],
+    fields: &mut [FieldInfo],
+    methods: &mut [MethodInfo],
+    stale_ranges: &mut StaleRanges,
+) -> Result<(), RemapError> {
+    let rewriter = Rewriter {
+        remap: mapping,
+        pool,
+        data,
+    };
+    let mut out = Vec::new();
+    let old_class_attributes = class_attributes.to_vec();
+    rewriter.attributes(class_attributes, &mut out)?;
+    let old_fields = fields.to_vec();
+    for field in fields.iter_mut() {
+        rewriter.attributes(&mut field.attributes, &mut out)?;
+    }
+    let old_methods = methods.to_vec();
+    for method in methods.iter_mut() {
+        rewriter.attributes(&mut method.attributes, &mut out)?;
+    }
+
+    // The rewritten attributes refer to `out`, so shift them to where it is placed in `data`
+    let base = data.len();
+    data.extend_from_slice(&out);
+    let mut shift = |attributes: &mut [AttributeInfo], old: &[AttributeInfo]| {
+        for (attr, old) in attributes.iter_mut().zip(old) {
+            if attr.info != old.info {
+                attr.info = (attr.info.start + base)..(attr.info.end + base);
+                stale_ranges.mark(old.info.clone());
+            }
+        }
+    };
+    shift(class_attributes, &old_class_attributes);
+    for (field, old) in fields.iter_mut().zip(old_fields.iter()) {
+        shift(&mut field.attributes, &old.attributes);
+    }
+    for (method, old) in methods.iter_mut().zip(old_methods.iter()) {
+        shift(&mut method.attributes, &old.attributes);
+    }
+
+    Ok(())
+}
+
+/// Rewrite the attributes with the mapping, writing the info of every one of them to the end of
+/// `out`, whether it changed or not, and pointing their ranges at it
+pub(crate) fn copy_attributes(
+    mapping: &dyn IndexMapping,
+    pool: &ConstantPool,
+    data: &[u8],
+    attributes: &mut [AttributeInfo],
+    out: &mut Vec<u8>,
+) -> Result<(), RemapError> {
+    let rewriter = Rewriter {
+        remap: mapping,
+        pool,
+        data,
+    };
+    for attr in attributes.iter_mut() {
+        let name = rewriter.name(attr.attribute_name_index.0)?;
+        let info = data.get(attr.info.clone()).ok_or(RemapError::Malformed)?;
+        let start = out.len();
+        out.extend_from_slice(info);
+        rewriter.info(&name, &mut Cursor::new(&mut out[start..]))?;
+
+        attr.attribute_name_index =
+            ConstantPoolIndexRaw::new(mapping.map_index(attr.attribute_name_index.0)?);
+        attr.info = start..out.len();
+    }
+
+    Ok(())
+}
+
+impl ClassFile {
+    /// Remove the entry from the pool if nothing refers to it, returning whether it was removed.
+    /// The entries after it move down to fill the gap, and every reference to them is rewritten
+    /// as [`IndexRemap::apply`] does, so any indices held from before are no longer valid.
+    /// Errors if the pool can't be renumbered, such as when there are attributes whose layout is
+    /// not known.
+    pub fn remove_unused_constant(
+        &mut self,
+        data: &mut Vec<u8>,
+        index: ConstantPoolIndexRaw<ConstantInfo>,
+    ) -> Result<bool, RemapError> {
+        if matches!(
+            self.const_pool.get(index),
+            None | Some(ConstantInfo::Unusable)
+        ) {
+            return Err(RemapError::InvalidIndex(index.0));
+        }
+
+        let order = self.const_pool.indices().filter(|&i| i != index);
+        let remap = IndexRemap::from_order(&self.const_pool, order)?;
+        match remap.apply(self, data) {
+            Ok(()) => Ok(true),
+            // Something still refers to it
+            Err(RemapError::Unmapped(i)) if i == index.0 => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Reads and overwrites big endian values in an attribute's bytes
+struct Cursor<'a> {
+    bytes: &'a mut [u8],
+    pos: usize,
+}
+impl<'a> Cursor<'a> {
+    fn new(bytes: &'a mut [u8]) -> Cursor<'a> {
+        Cursor { bytes, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&mut [u8], RemapError> {
+        let end = self.pos.checked_add(len).ok_or(RemapError::Malformed)?;
+        let bytes = self
+            .bytes
+            .get_mut(self.pos..end)
+            .ok_or(RemapError::Malformed)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), RemapError> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, RemapError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, RemapError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, RemapError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Rewrite the constant pool index at the current position, returning the old index
+    fn index(&mut self, remap: &dyn IndexMapping) -> Result<u16, RemapError> {
+        let old = self.u16()?;
+        let new = remap.map_index(old)?;
+        self.bytes[self.pos - 2..self.pos].copy_from_slice(&new.to_be_bytes());
+        Ok(old)
+    }
+
+    /// A cursor over the next `len` bytes, which are skipped in this cursor
+    fn sub(&mut self, len: usize) -> Result<Cursor<'_>, RemapError> {
+        Ok(Cursor::new(self.take(len)?))
+    }
+}
+
+struct Rewriter<'a> {
+    remap: &'a dyn IndexMapping,
+    /// The old pool, which the names of attributes are looked up in
+    pool: &'a ConstantPool,
+    data: &'a [u8],
+}
+impl<'a> Rewriter<'a> {
+    /// Rewrite the attributes, appending any changed info to `out` and pointing the range at it
+    fn attributes(
+        &self,
+        attributes: &mut [AttributeInfo],
+        out: &mut Vec<u8>,
+    ) -> Result<(), RemapError> {
+        for attr in attributes.iter_mut() {
+            let name = self.name(attr.attribute_name_index.0)?;
+            let mut info = self
+                .data
+                .get(attr.info.clone())
+                .ok_or(RemapError::Malformed)?
+                .to_vec();
+            self.info(&name, &mut Cursor::new(&mut info))?;
+
+            attr.attribute_name_index =
+                ConstantPoolIndexRaw::new(self.remap.map_index(attr.attribute_name_index.0)?);
+            if info[..] != self.data[attr.info.clone()] {
+                let start = out.len();
+                out.extend_from_slice(&info);
+                attr.info = start..out.len();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn name(&self, index: u16) -> Result<String, RemapError> {
+        self.pool
+            .get_t(ConstantPoolIndexRaw::<Utf8Constant>::new(index))
+            .map(|name| name.as_text(self.data).into_owned())
+            .ok_or(RemapError::InvalidIndex(index))
+    }
+
+    fn indices(&self, c: &mut Cursor, count: usize) -> Result<(), RemapError> {
+        for _ in 0..count {
+            c.index(self.remap)?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite the info of an attribute with the given name
+    fn info(&self, name: &str, c: &mut Cursor) -> Result<(), RemapError> {
+        use AttributeName::*;
+        let remap = self.remap;
+        let name = AttributeName::from_name(name)
+            .ok_or_else(|| RemapError::UnknownAttribute(name.to_string()))?;
+        match name {
+            Code => self.code(c)?,
+            ConstantValue | SourceFile | Signature | NestHost | ModuleMainClass => {
+                c.index(remap)?;
+            }
+            Exceptions | NestMembers | PermittedSubclasses | ModulePackages => {
+                let count = c.u16()?;
+                self.indices(c, usize::from(count))?;
+            }
+            EnclosingMethod => self.indices(c, 2)?,
+            InnerClasses => {
+                for _ in 0..c.u16()? {
+                    self.indices(c, 3)?;
+                    c.skip(2)?;
+                }
+            }
+            BootstrapMethods => {
+                for _ in 0..c.u16()? {
+                    c.index(remap)?;
+                    let count = c.u16()?;
+                    self.indices(c, usize::from(count))?;
+                }
+            }
+            MethodParameters => {
+                for _ in 0..c.u8()? {
+                    c.index(remap)?;
+                    c.skip(2)?;
+                }
+            }
+            LocalVariableTable | LocalVariableTypeTable => {
+                for _ in 0..c.u16()? {
+                    c.skip(4)?;
+                    self.indices(c, 2)?;
+                    c.skip(2)?;
+                }
+            }
+            StackMapTable => self.stack_map_table(c)?,
+            RuntimeVisibleAnnotations | RuntimeInvisibleAnnotations => {
+                for _ in 0..c.u16()? {
+                    self.annotation(c, 0)?;
+                }
+            }
+            RuntimeVisibleParameterAnnotations | RuntimeInvisibleParameterAnnotations => {
+                for _ in 0..c.u8()? {
+                    for _ in 0..c.u16()? {
+                        self.annotation(c, 0)?;
+                    }
+                }
+            }
+            RuntimeVisibleTypeAnnotations | RuntimeInvisibleTypeAnnotations => {
+                for _ in 0..c.u16()? {
+                    self.type_annotation(c)?;
+                }
+            }
+            AnnotationDefault => self.element_value(c, 0)?,
+            Record => {
+                for _ in 0..c.u16()? {
+                    self.indices(c, 2)?;
+                    self.nested_attributes(c)?;
+                }
+            }
+            Module => self.module(c)?,
+            LineNumberTable | SourceDebugExtension | Deprecated | Synthetic => {}
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite a list of attributes that are stored inside another attribute
+    fn nested_attributes(&self, c: &mut Cursor) -> Result<(), RemapError> {
+        for _ in 0..c.u16()? {
+            let name = self.name(c.index(self.remap)?)?;
+            let len = c.u32()? as usize;
+            self.info(&name, &mut c.sub(len)?)?;
+        }
+        Ok(())
+    }
+
+    fn code(&self, c: &mut Cursor) -> Result<(), RemapError> {
+        c.skip(4)?;
+        let code_length = c.u32()? as usize;
+        self.instructions(&mut c.sub(code_length)?)?;
+        for _ in 0..c.u16()? {
+            c.skip(6)?;
+            c.index(self.remap)?;
+        }
+        self.nested_attributes(c)
+    }
+
+    fn instructions(&self, c: &mut Cursor) -> Result<(), RemapError> {
+        let mut indices = Vec::new();
+        for result in code_iter(c.bytes) {
+            let (offset, instruction) = result.map_err(|_| RemapError::Malformed)?;
+            if let Some(index) = instruction.constant_index() {
+                let ldc = matches!(instruction, Instruction::Ldc(_));
+                indices.push((usize::from(offset), ldc, index));
+            }
+        }
+
+        // The index is always just after the opcode
+        for (offset, ldc, index) in indices {
+            let new = self.remap.map_index(index)?;
+            if ldc {
+                c.bytes[offset + 1] =
+                    u8::try_from(new).map_err(|_| RemapError::LdcIndexTooLarge { offset })?;
+            } else {
+                c.bytes[offset + 1..offset + 3].copy_from_slice(&new.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    fn verification_type(&self, c: &mut Cursor) -> Result<(), RemapError> {
+        match c.u8()? {
+            // Object
+            7 => {
+                c.index(self.remap)?;
+            }
+            // Uninitialized
+            8 => c.skip(2)?,
+            0..=6 => {}
+            _ => return Err(RemapError::Malformed),
+        }
+        Ok(())
+    }
+
+    fn stack_map_table(&self, c: &mut Cursor) -> Result<(), RemapError> {
+        for _ in 0..c.u16()? {
+            match c.u8()? {
+                0..=63 => {}
+                64..=127 => self.verification_type(c)?,
+                247 => {
+                    c.skip(2)?;
+                    self.verification_type(c)?;
+                }
+                248..=251 => c.skip(2)?,
+                frame_type @ 252..=254 => {
+                    c.skip(2)?;
+                    for _ in 0..(frame_type - 251) {
+                        self.verification_type(c)?;
+                    }
+                }
+                255 => {
+                    c.skip(2)?;
+                    for _ in 0..c.u16()? {
+                        self.verification_type(c)?;
+                    }
+                    for _ in 0..c.u16()? {
+                        self.verification_type(c)?;
+                    }
+                }
+                _ => return Err(RemapError::Malformed),
+            }
+        }
+        Ok(())
+    }
+
+    /// `depth` is how many element values the annotation is nested in
+    fn annotation(&self, c: &mut Cursor, depth: usize) -> Result<(), RemapError> {
+        c.index(self.remap)?;
+        for _ in 0..c.u16()? {
+            c.index(self.remap)?;
+            self.element_value(c, depth)?;
+        }
+        Ok(())
+    }
+
+    fn element_value(&self, c: &mut Cursor, depth: usize) -> Result<(), RemapError> {
+        if depth >= DEFAULT_MAX_NESTING_DEPTH {
+            return Err(RemapError::Malformed);
+        }
+        match c.u8()? {
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' | b'c' => {
+                c.index(self.remap)?;
+            }
+            b'e' => self.indices(c, 2)?,
+            b'@' => self.annotation(c, depth + 1)?,
+            b'[' => {
+                for _ in 0..c.u16()? {
+                    self.element_value(c, depth + 1)?;
+                }
+            }
+            _ => return Err(RemapError::Malformed),
+        }
+        Ok(())
+    }
+
+    fn type_annotation(&self, c: &mut Cursor) -> Result<(), RemapError> {
+        let kind = TargetInfoKind::from_target_type(c.u8()?).ok_or(RemapError::Malformed)?;
+        match kind.fixed_len() {
+            Some(len) => c.skip(len)?,
+            None => {
+                let count = c.u16()?;
+                c.skip(usize::from(count) * 6)?;
+            }
+        }
+        let path_length = c.u8()?;
+        c.skip(usize::from(path_length) * 2)?;
+        self.annotation(c, 0)
+    }
+
+    fn module(&self, c: &mut Cursor) -> Result<(), RemapError> {
+        let remap = self.remap;
+        // name, flags, version
+        c.index(remap)?;
+        c.skip(2)?;
+        c.index(remap)?;
+        // requires
+        for _ in 0..c.u16()? {
+            c.index(remap)?;
+            c.skip(2)?;
+            c.index(remap)?;
+        }
+        // exports, then opens
+        for _ in 0..2 {
+            for _ in 0..c.u16()? {
+                c.index(remap)?;
+                c.skip(2)?;
+                let count = c.u16()?;
+                self.indices(c, usize::from(count))?;
+            }
+        }
+        // uses
+        let count = c.u16()?;
+        self.indices(c, usize::from(count))?;
+        // provides
+        for _ in 0..c.u16()? {
+            c.index(remap)?;
+            let count = c.u16()?;
+            self.indices(c, usize::from(count))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,433 @@
+//! Renaming a class throughout a class file, such as for shading a dependency.
+//!
+//! The class is renamed wherever the class file refers to it as a class: in Class entries, in
+//! the descriptors of members and NameAndType and MethodType entries, and in the descriptors and
+//! signatures inside attributes, which are rewritten as [`crate::remap::IndexRemap::apply`] does.
+//! The renamed text is added as new Utf8 entries rather than changing the old ones, so string
+//! constants which happen to hold the old name keep it.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::attribute_info::names;
+use crate::builder::{ClassBuilderError, ConstantPoolBuilder};
+use crate::constant_info::{ClassConstant, ConstantInfo, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::descriptor::signature::{
+    ClassSignature, ClassTypeSignature, MethodSignature, ReferenceTypeSignature, TypeArgument,
+    TypeParameter, TypeSignature,
+};
+use crate::remap::{rewrite_attributes, IndexMapping, RemapError};
+use crate::ClassFile;
+
+/// Maps the Utf8 entries of descriptors and signatures to the renamed ones, leaving every other
+/// index unchanged
+struct RenamedUtf8 {
+    renamed: HashMap<u16, u16>,
+}
+impl IndexMapping for RenamedUtf8 {
+    fn map_index(&self, index: u16) -> Result<u16, RemapError> {
+        Ok(self.renamed.get(&index).copied().unwrap_or(index))
+    }
+}
+
+/// Renames a class, and the classes nested in it, in internal names and signatures
+struct Rename<'a> {
+    old: &'a [u8],
+    new: &'a [u8],
+}
+impl<'a> Rename<'a> {
+    /// Rename the internal name if it is the class or a class nested in it, whose name starts with
+    /// that of the class followed by `$`
+    fn name(&self, name: &[u8]) -> Option<Vec<u8>> {
+        let rest = name.strip_prefix(self.old)?;
+        if !rest.is_empty() && rest[0] != b'$' {
+            return None;
+        }
+        let mut renamed = self.new.to_vec();
+        renamed.extend_from_slice(rest);
+        Some(renamed)
+    }
+
+    /// Rename the name of a Class entry, which is a descriptor for array classes
+    fn class_name(&self, name: &[u8]) -> Option<Vec<u8>> {
+        if name.first() == Some(&b'[') {
+            self.signature(name)
+        } else {
+            self.name(name)
+        }
+    }
+
+    /// Rename the classes in a descriptor or signature of any kind.
+    /// Returns None if nothing was renamed, or if the text is not a descriptor or signature.
+    fn signature(&self, text: &[u8]) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        let renamed = match text.first()? {
+            b'(' | b'<' if text.contains(&b'(') => {
+                let mut signature = MethodSignature::parse(text).ok()?;
+                let renamed = self.method_signature(&mut signature);
+                signature.write_signature(&mut out);
+                renamed
+            }
+            b'<' | b'L' => {
+                let mut signature = ClassSignature::parse(text).ok()?;
+                let mut renamed = self.type_parameters(&mut signature.type_parameters);
+                renamed |= self.class_type(&mut signature.super_class);
+                for interface in signature.interfaces.iter_mut() {
+                    renamed |= self.class_type(interface);
+                }
+                signature.write_signature(&mut out);
+                renamed
+            }
+            _ => {
+                let mut signature = TypeSignature::parse(text).ok()?;
+                let renamed = self.java_type(&mut signature);
+                signature.write_signature(&mut out);
+                renamed
+            }
+        };
+        renamed.then_some(out)
+    }
+
+    fn method_signature(&self, signature: &mut MethodSignature) -> bool {
+        let mut renamed = self.type_parameters(&mut signature.type_parameters);
+        for parameter in signature.parameter_types.iter_mut() {
+            renamed |= self.java_type(parameter);
+        }
+        if let Some(return_type) = &mut signature.return_type {
+            renamed |= self.java_type(return_type);
+        }
+        for throws in signature.throws.iter_mut() {
+            renamed |= self.reference_type(throws);
+        }
+        renamed
+    }
+
+    fn type_parameters(&self, parameters: &mut [TypeParameter]) -> bool {
+        let mut renamed = false;
+        for parameter in parameters {
+            if let Some(bound) = &mut parameter.class_bound {
+                renamed |= self.reference_type(bound);
+            }
+            for bound in parameter.interface_bounds.iter_mut() {
+                renamed |= self.reference_type(bound);
+            }
+        }
+        renamed
+    }
+
+    fn java_type(&self, typ: &mut TypeSignature) -> bool {
+        match typ {
+            TypeSignature::Base(_) => false,
+            TypeSignature::Reference(reference) => self.reference_type(reference),
+        }
+    }
+
+    fn reference_type(&self, typ: &mut ReferenceTypeSignature) -> bool {
+        match typ {
+            ReferenceTypeSignature::Class(class) => self.class_type(class),
+            ReferenceTypeSignature::TypeVariable(_) => false,
+            ReferenceTypeSignature::Array(component) => self.java_type(component),
+        }
+    }
+
+    fn class_type(&self, class: &mut ClassTypeSignature) -> bool {
+        let mut renamed = false;
+        // Only the outermost class has a package, and the inner classes after it are simple names
+        if let Some(outer) = class.classes.first_mut() {
+            if let Some(name) = self.name(&outer.name) {
+                outer.name = Cow::Owned(name);
+                renamed = true;
+            }
+        }
+        for class in class.classes.iter_mut() {
+            for argument in class.type_arguments.iter_mut() {
+                renamed |= match argument {
+                    TypeArgument::Any => false,
+                    TypeArgument::Exact(typ)
+                    | TypeArgument::Extends(typ)
+                    | TypeArgument::Super(typ) => self.reference_type(typ),
+                };
+            }
+        }
+        renamed
+    }
+}
+
+/// The simple name of the outermost class of an internal name, which source files are named after
+fn top_level_simple_name(name: &[u8]) -> &[u8] {
+    let simple = match name.iter().rposition(|&b| b == b'/') {
+        Some(slash) => &name[slash + 1..],
+        None => name,
+    };
+    match simple.iter().position(|&b| b == b'$') {
+        Some(dollar) => &simple[..dollar],
+        None => simple,
+    }
+}
+
+fn add_utf8(
+    pool: &mut ConstantPool,
+    data: &mut Vec<u8>,
+    bytes: &[u8],
+) -> Result<ConstantPoolIndexRaw<Utf8Constant>, RemapError> {
+    ConstantPoolBuilder::with_pool(pool, data, |constants| constants.utf8_bytes(bytes))
+        .map_err(|_| RemapError::PoolTooLarge)
+}
+
+fn utf8_bytes<'d>(
+    pool: &ConstantPool,
+    data: &'d [u8],
+    index: ConstantPoolIndexRaw<Utf8Constant>,
+) -> Result<&'d [u8], RemapError> {
+    pool.get_t(index)
+        .map(|text| text.as_bytes(data))
+        .ok_or(RemapError::InvalidIndex(index.0))
+}
+
+impl ClassFile {
+    /// Rename the class with the internal name `old` to `new`, such as `com/example/Util` to
+    /// `shaded/com/example/Util`, everywhere that the class file refers to it.
+    /// This is usually the class itself, but any class can be renamed, so that the classes which
+    /// refer to a renamed class can be updated to match.
+    ///
+    /// Classes nested in the renamed class, whose names start with `old$`, are renamed along with
+    /// it, since signatures refer to them through their outer class. The entries of the
+    /// InnerClasses and EnclosingMethod attributes refer to Class entries, so they follow, and the
+    /// simple names in InnerClasses are renamed when a nested class itself is. If the class that
+    /// `this_class` names is renamed, its SourceFile is too when it is named after the class, such
+    /// as `Util.java` becoming `Renamed.java`.
+    ///
+    /// Annotation string values which hold a descriptor of the class are renamed too, since they
+    /// can share a Utf8 entry with the descriptors.
+    /// Errors if the class file has attributes whose layout is not known, as
+    /// [`crate::remap::IndexRemap::apply`] does, or if the pool has no room for the new names.
+    /// On error, neither the class file nor `data` is modified.
+    pub fn rename_class(
+        &mut self,
+        data: &mut Vec<u8>,
+        old: &str,
+        new: &str,
+    ) -> Result<(), RemapError> {
+        let len = data.len();
+        let mut class_file = self.clone();
+        match class_file.rename_class_in_place(data, old.as_bytes(), new.as_bytes()) {
+            Ok(()) => {
+                *self = class_file;
+                Ok(())
+            }
+            Err(err) => {
+                data.truncate(len);
+                Err(err)
+            }
+        }
+    }
+
+    fn rename_class_in_place(
+        &mut self,
+        data: &mut Vec<u8>,
+        old: &[u8],
+        new: &[u8],
+    ) -> Result<(), RemapError> {
+        let rename = Rename { old, new };
+        // Taken before any renamed text is added to the pool
+        let utf8_entries: Vec<_> = self
+            .const_pool
+            .iter_indexed()
+            .filter_map(|(index, entry)| match entry {
+                ConstantInfo::Utf8(text) => Some((index.0, text.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let this_class = self
+            .const_pool
+            .try_get_t(self.this_class)
+            .map_err(|_| RemapError::InvalidIndex(self.this_class.0))?;
+        let this_name = utf8_bytes(&self.const_pool, data, this_class.name_index)?;
+        if rename.name(this_name).is_some() {
+            self.rename_source_file(data, old, new)?;
+        }
+        self.rename_inner_class_names(data, &rename)?;
+
+        // The Utf8 entries which are renamed as a class name, and as a descriptor or signature.
+        // The text of an array class is a descriptor, so it is renamed the same either way.
+        let mut class_names = HashMap::new();
+        let mut signatures = HashMap::new();
+        ConstantPoolBuilder::with_pool(&mut self.const_pool, data, |constants| {
+            for (index, text) in utf8_entries {
+                let bytes = text.as_bytes(constants.data()).to_vec();
+                if let Some(renamed) = rename.class_name(&bytes) {
+                    let new_index = constants.utf8_bytes(&renamed)?;
+                    class_names.insert(index, new_index.0);
+                }
+                if let Some(renamed) = rename.signature(&bytes) {
+                    let new_index = constants.utf8_bytes(&renamed)?;
+                    signatures.insert(index, new_index.0);
+                }
+            }
+            Ok(())
+        })
+        .map_err(|_: ClassBuilderError| RemapError::PoolTooLarge)?;
+        let signatures = RenamedUtf8 {
+            renamed: signatures,
+        };
+
+        for entry in self.const_pool.make_mut() {
+            match entry {
+                ConstantInfo::Class(class) => {
+                    if let Some(&index) = class_names.get(&class.name_index.0) {
+                        class.name_index = ConstantPoolIndexRaw::new(index);
+                    }
+                }
+                ConstantInfo::NameAndType(nat) => {
+                    let index = signatures.map_index(nat.descriptor_index.0)?;
+                    nat.descriptor_index = ConstantPoolIndexRaw::new(index);
+                }
+                ConstantInfo::MethodType(method_type) => {
+                    let index = signatures.map_index(method_type.descriptor_index.0)?;
+                    method_type.descriptor_index = ConstantPoolIndexRaw::new(index);
+                }
+                _ => {}
+            }
+        }
+        for field in self.fields.iter_mut() {
+            let index = signatures.map_index(field.descriptor_index.0)?;
+            field.descriptor_index = ConstantPoolIndexRaw::new(index);
+        }
+        for method in self.methods.iter_mut() {
+            let index = signatures.map_index(method.descriptor_index.0)?;
+            method.descriptor_index = ConstantPoolIndexRaw::new(index);
+        }
+
+        rewrite_attributes(
+            &signatures,
+            &self.const_pool,
+            data,
+            &mut self.attributes,
+            &mut self.fields,
+            &mut self.methods,
+            &mut self.stale_ranges,
+        )?;
+
+        self.const_pool_size = self.const_pool.len() + 1;
+        self.typed_attributes = None;
+        Ok(())
+    }
+
+    /// Replace the info of the class attribute at the index, marking the old info as stale
+    fn set_class_attribute_info(
+        &mut self,
+        data: &mut Vec<u8>,
+        index: usize,
+        info: &[u8],
+    ) -> Result<(), RemapError> {
+        self.attributes[index]
+            .set_info(data, info, &mut self.stale_ranges)
+            .map_err(|_| RemapError::Malformed)
+    }
+
+    /// Find the class attribute with the name, returning its index and a copy of its info
+    fn class_attribute_info(
+        &self,
+        data: &[u8],
+        name: &str,
+    ) -> Result<Option<(usize, Vec<u8>)>, RemapError> {
+        for (i, attr) in self.attributes.iter().enumerate() {
+            if utf8_bytes(&self.const_pool, data, attr.attribute_name_index)? == name.as_bytes() {
+                let info = self.read_range(data, attr.info.clone());
+                return Ok(Some((i, info.map_err(|_| RemapError::Malformed)?.to_vec())));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Rename the simple names in the InnerClasses attribute of the classes that are renamed,
+    /// which only changes them when a nested class is renamed.
+    /// This is done before the Class entries are renamed.
+    fn rename_inner_class_names(
+        &mut self,
+        data: &mut Vec<u8>,
+        rename: &Rename,
+    ) -> Result<(), RemapError> {
+        let (attr_index, mut info) = match self.class_attribute_info(data, names::INNER_CLASSES)? {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+        let count = info
+            .get(..2)
+            .map(|count| u16::from_be_bytes([count[0], count[1]]))
+            .ok_or(RemapError::Malformed)?;
+        let mut changed = false;
+        for i in 0..usize::from(count) {
+            let start = 2 + i * 8;
+            let entry = info.get(start..start + 8).ok_or(RemapError::Malformed)?;
+            let inner_class = ConstantPoolIndexRaw::<ClassConstant>::new(u16::from_be_bytes([
+                entry[0], entry[1],
+            ]));
+            let inner_name = ConstantPoolIndexRaw::new(u16::from_be_bytes([entry[4], entry[5]]));
+            if inner_name.is_zero() {
+                continue;
+            }
+            let class = self
+                .const_pool
+                .try_get_t(inner_class)
+                .map_err(|_| RemapError::InvalidIndex(inner_class.0))?;
+            let class_name = utf8_bytes(&self.const_pool, data, class.name_index)?;
+            let simple_name = utf8_bytes(&self.const_pool, data, inner_name)?;
+            let renamed = match rename.name(class_name) {
+                Some(renamed) => renamed,
+                None => continue,
+            };
+            let named_after_class = class_name
+                .strip_suffix(simple_name)
+                .is_some_and(|outer| outer.ends_with(b"$"));
+            let new_simple_name = match renamed.iter().rposition(|&b| b == b'$') {
+                Some(dollar) if named_after_class => &renamed[dollar + 1..],
+                _ => continue,
+            };
+            if new_simple_name != simple_name {
+                let index = add_utf8(&mut self.const_pool, data, new_simple_name)?;
+                info[start + 4..start + 6].copy_from_slice(&index.0.to_be_bytes());
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.set_class_attribute_info(data, attr_index, &info)?;
+        }
+        Ok(())
+    }
+
+    /// Rename the SourceFile if it is named after the class, keeping its extension
+    fn rename_source_file(
+        &mut self,
+        data: &mut Vec<u8>,
+        old: &[u8],
+        new: &[u8],
+    ) -> Result<(), RemapError> {
+        let (old_top, new_top) = (top_level_simple_name(old), top_level_simple_name(new));
+        if old_top == new_top {
+            return Ok(());
+        }
+        let (attr_index, info) = match self.class_attribute_info(data, names::SOURCE_FILE)? {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+        let source_file = match info.as_slice() {
+            [high, low] => {
+                ConstantPoolIndexRaw::<Utf8Constant>::new(u16::from_be_bytes([*high, *low]))
+            }
+            _ => return Err(RemapError::Malformed),
+        };
+        let text = utf8_bytes(&self.const_pool, data, source_file)?;
+        let extension = match text.strip_prefix(old_top) {
+            Some(extension) if extension.first() == Some(&b'.') => extension.to_vec(),
+            _ => return Ok(()),
+        };
+
+        let renamed = [new_top, &extension].concat();
+        let index = add_utf8(&mut self.const_pool, data, &renamed)?;
+        self.set_class_attribute_info(data, attr_index, &index.0.to_be_bytes())
+    }
+}
@@ -0,0 +1,423 @@
+//! A model of a class, with everything that refers to the constant pool already looked up.
+//!
+//! By default names, descriptors, and constant values are copied out of the class file data when
+//! the [`ResolvedClass`] is built, so it can be kept after the data is dropped. Building it with
+//! [`Borrowed`] text instead keeps the text that is valid UTF-8 borrowed from the data, which
+//! avoids an allocation per name when the model doesn't outlive the data, see [`TextMode`].
+//! Unlike [`crate::api::ClassApi`], every member is kept, in the order of the class file.
+//! The bytecode and the info of attributes are copied as they are, so any constant pool indices
+//! inside them are not resolved.
+
+use std::borrow::Cow;
+
+use crate::attribute_info::{
+    AttributeInfo, CodeAttribute, ConstantValueAttribute, ExceptionsAttribute, HasAttributes,
+    InstructionIndex, SignatureAttribute, SourceFileAttribute,
+};
+use crate::constant_info::{ClassConstant, ConstantValue, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::descriptor::method::MethodDescriptor;
+use crate::descriptor::{DescriptorError, DescriptorType, ParsedDescriptor};
+use crate::field_info::{FieldAccessFlags, FieldInfo};
+use crate::method_info::{MethodAccessFlags, MethodInfo};
+use crate::{ClassAccessFlags, ClassFile, ClassFileVersion, LoadError};
+
+#[derive(Debug, Clone)]
+pub enum ResolveError {
+    /// An index, or an attribute that is resolved, is malformed
+    Load(LoadError),
+    /// The descriptor of the named member could not be parsed
+    Descriptor(String, DescriptorError),
+    /// The named field has a method descriptor, or the named method has a field descriptor
+    WrongDescriptor(String),
+}
+impl From<LoadError> for ResolveError {
+    fn from(err: LoadError) -> ResolveError {
+        ResolveError::Load(err)
+    }
+}
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveError::Load(err) => err.fmt(f),
+            ResolveError::Descriptor(name, err) => {
+                write!(f, "invalid descriptor for {}: {:?}", name, err)
+            }
+            ResolveError::WrongDescriptor(name) => {
+                write!(f, "wrong kind of descriptor for {}", name)
+            }
+        }
+    }
+}
+impl std::error::Error for ResolveError {}
+
+/// How a resolved view holds the text that it looks up in the class file data, which is chosen
+/// by passing [`Borrowed`] or [`Owned`] to the `_with` constructors, such as
+/// [`ResolvedClass::from_class_file_with`] and [`crate::constant_info::resolve_member_ref_with`]
+pub trait TextMode<'a>: Copy {
+    type Text: AsRef<str>;
+
+    fn text(self, text: Cow<'a, str>) -> Self::Text;
+}
+
+/// Borrow text from the class file data where it is valid UTF-8, so the view can't outlive the
+/// data
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Borrowed;
+impl<'a> TextMode<'a> for Borrowed {
+    type Text = Cow<'a, str>;
+
+    fn text(self, text: Cow<'a, str>) -> Cow<'a, str> {
+        text
+    }
+}
+
+/// Copy all text out of the class file data, so the view can be kept after the data is dropped
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Owned;
+impl<'a> TextMode<'a> for Owned {
+    type Text = String;
+
+    fn text(self, text: Cow<'a, str>) -> String {
+        text.into_owned()
+    }
+}
+
+/// A resolved class, whose text is a `String` unless it is built with [`Borrowed`] text
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedClass<T = String> {
+    pub version: ClassFileVersion,
+    pub access_flags: ClassAccessFlags,
+    /// The internal name of the class
+    pub name: T,
+    /// The internal name of the superclass, which is None for `java/lang/Object` and modules
+    pub super_class: Option<T>,
+    /// The internal names of the directly implemented interfaces
+    pub interfaces: Vec<T>,
+    pub signature: Option<T>,
+    pub source_file: Option<T>,
+    pub fields: Vec<ResolvedField<T>>,
+    pub methods: Vec<ResolvedMethod<T>>,
+    pub attributes: Vec<ResolvedAttribute<T>>,
+}
+impl ResolvedClass {
+    /// Resolve the class and all of its members, copying their text out of the data.
+    /// Errors if a name, a descriptor, or one of the attributes it reads is malformed.
+    pub fn from_class_file(
+        class_file: &ClassFile,
+        data: &[u8],
+    ) -> Result<ResolvedClass, ResolveError> {
+        ResolvedClass::from_class_file_with(class_file, data, Owned)
+    }
+}
+impl<T: AsRef<str>> ResolvedClass<T> {
+    /// Resolve the class and all of its members, holding their text as the mode decides, see
+    /// [`ResolvedClass::from_class_file`]
+    pub fn from_class_file_with<'a, M: TextMode<'a, Text = T>>(
+        class_file: &ClassFile,
+        data: &'a [u8],
+        mode: M,
+    ) -> Result<ResolvedClass<T>, ResolveError> {
+        let pool = &class_file.const_pool;
+
+        let super_class = if class_file.super_class.is_zero() {
+            None
+        } else {
+            Some(class_name(pool, data, class_file.super_class, mode)?)
+        };
+        let interfaces = class_file
+            .interfaces
+            .iter()
+            .map(|&index| class_name(pool, data, index, mode))
+            .collect::<Result<Vec<_>, _>>()?;
+        let source_file = class_file
+            .find_attribute::<SourceFileAttribute>(pool, data)?
+            .map(|attr| utf8(pool, data, attr.sourcefile_index, mode))
+            .transpose()?;
+
+        let fields = class_file
+            .fields
+            .iter()
+            .map(|field| ResolvedField::resolve(class_file, field, data, mode))
+            .collect::<Result<Vec<_>, _>>()?;
+        let methods = class_file
+            .methods
+            .iter()
+            .map(|method| ResolvedMethod::resolve(class_file, method, data, mode))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ResolvedClass {
+            version: class_file.version,
+            access_flags: class_file.access_flags,
+            name: class_name(pool, data, class_file.this_class, mode)?,
+            super_class,
+            interfaces,
+            signature: signature(class_file, pool, data, mode)?,
+            source_file,
+            fields,
+            methods,
+            attributes: attributes(class_file, &class_file.attributes, data, mode)?,
+        })
+    }
+
+    pub fn field(&self, name: &str, descriptor: &str) -> Option<&ResolvedField<T>> {
+        self.fields
+            .iter()
+            .find(|field| field.name.as_ref() == name && field.descriptor.as_ref() == descriptor)
+    }
+
+    pub fn method(&self, name: &str, descriptor: &str) -> Option<&ResolvedMethod<T>> {
+        self.methods
+            .iter()
+            .find(|method| method.name.as_ref() == name && method.descriptor.as_ref() == descriptor)
+    }
+
+    /// Iterate over the methods with the name, in any of their overloads
+    pub fn methods_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a ResolvedMethod<T>> + 'a {
+        self.methods
+            .iter()
+            .filter(move |method| method.name.as_ref() == name)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&ResolvedAttribute<T>> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name.as_ref() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedField<T = String> {
+    pub access_flags: FieldAccessFlags,
+    pub name: T,
+    pub descriptor: T,
+    pub parsed_descriptor: DescriptorType<'static>,
+    pub signature: Option<T>,
+    /// The value of the ConstantValue attribute, if it has one
+    pub constant_value: Option<ConstantValue>,
+    pub attributes: Vec<ResolvedAttribute<T>>,
+}
+impl<T: AsRef<str>> ResolvedField<T> {
+    fn resolve<'a, M: TextMode<'a, Text = T>>(
+        class_file: &ClassFile,
+        field: &FieldInfo,
+        data: &'a [u8],
+        mode: M,
+    ) -> Result<ResolvedField<T>, ResolveError> {
+        let pool = &class_file.const_pool;
+        let name = utf8(pool, data, field.name_index, mode)?;
+        let descriptor = utf8(pool, data, field.descriptor_index, mode)?;
+        let parsed_descriptor = match ParsedDescriptor::parse(descriptor.as_ref().as_bytes()) {
+            Ok(ParsedDescriptor::Field(desc)) => desc,
+            Ok(ParsedDescriptor::Method(_)) => {
+                return Err(ResolveError::WrongDescriptor(name.as_ref().to_owned()))
+            }
+            Err(err) => return Err(ResolveError::Descriptor(name.as_ref().to_owned(), err)),
+        };
+
+        let constant_value = match field.find_attribute::<ConstantValueAttribute>(pool, data)? {
+            Some(attr) => {
+                let index = attr.constant_value_index;
+                let value = ConstantValue::resolve(pool, index, data)
+                    .ok_or(LoadError::InvalidIndex(index.0))?;
+                Some(value)
+            }
+            None => None,
+        };
+
+        Ok(ResolvedField {
+            access_flags: field.access_flags,
+            signature: signature(field, pool, data, mode)?,
+            attributes: attributes(class_file, &field.attributes, data, mode)?,
+            name,
+            descriptor,
+            parsed_descriptor,
+            constant_value,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedMethod<T = String> {
+    pub access_flags: MethodAccessFlags,
+    pub name: T,
+    pub descriptor: T,
+    pub parsed_descriptor: MethodDescriptor<'static>,
+    pub signature: Option<T>,
+    /// The internal names of the checked exceptions in the throws clause
+    pub exceptions: Vec<T>,
+    /// None for abstract and native methods
+    pub code: Option<ResolvedCode<T>>,
+    pub attributes: Vec<ResolvedAttribute<T>>,
+}
+impl<T: AsRef<str>> ResolvedMethod<T> {
+    fn resolve<'a, M: TextMode<'a, Text = T>>(
+        class_file: &ClassFile,
+        method: &MethodInfo,
+        data: &'a [u8],
+        mode: M,
+    ) -> Result<ResolvedMethod<T>, ResolveError> {
+        let pool = &class_file.const_pool;
+        let name = utf8(pool, data, method.name_index, mode)?;
+        let descriptor = utf8(pool, data, method.descriptor_index, mode)?;
+        let parsed_descriptor = match ParsedDescriptor::parse(descriptor.as_ref().as_bytes()) {
+            Ok(ParsedDescriptor::Method(desc)) => desc,
+            Ok(ParsedDescriptor::Field(_)) => {
+                return Err(ResolveError::WrongDescriptor(name.as_ref().to_owned()))
+            }
+            Err(err) => return Err(ResolveError::Descriptor(name.as_ref().to_owned(), err)),
+        };
+
+        let exceptions = match method.find_attribute::<ExceptionsAttribute>(pool, data)? {
+            Some(attr) => attr
+                .exception_table
+                .iter()
+                .map(|&index| class_name(pool, data, index, mode))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+        let code = method
+            .find_attribute::<CodeAttribute>(pool, data)?
+            .map(|code| ResolvedCode::resolve(class_file, code, data, mode))
+            .transpose()?;
+
+        Ok(ResolvedMethod {
+            access_flags: method.access_flags,
+            signature: signature(method, pool, data, mode)?,
+            attributes: attributes(class_file, &method.attributes, data, mode)?,
+            name,
+            descriptor,
+            parsed_descriptor,
+            exceptions,
+            code,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedCode<T = String> {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    /// The bytecode, which still refers to the constant pool by index
+    pub code: Vec<u8>,
+    pub exception_table: Vec<ResolvedExceptionHandler<T>>,
+    pub attributes: Vec<ResolvedAttribute<T>>,
+}
+impl<T: AsRef<str>> ResolvedCode<T> {
+    fn resolve<'a, M: TextMode<'a, Text = T>>(
+        class_file: &ClassFile,
+        code: CodeAttribute,
+        data: &'a [u8],
+        mode: M,
+    ) -> Result<ResolvedCode<T>, ResolveError> {
+        let pool = &class_file.const_pool;
+        let exception_table = code
+            .exception_table
+            .iter()
+            .map(|entry| {
+                let catch_type = if entry.catch_type.is_zero() {
+                    None
+                } else {
+                    Some(class_name(pool, data, entry.catch_type, mode)?)
+                };
+                Ok(ResolvedExceptionHandler {
+                    start_pc: entry.start_pc,
+                    end_pc: entry.end_pc,
+                    handler_pc: entry.handler_pc,
+                    catch_type,
+                })
+            })
+            .collect::<Result<Vec<_>, LoadError>>()?;
+
+        Ok(ResolvedCode {
+            max_stack: code.max_stack,
+            max_locals: code.max_locals,
+            code: class_file.read_range(data, code.code.clone())?.to_vec(),
+            exception_table,
+            attributes: attributes(class_file, &code.attributes, data, mode)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedExceptionHandler<T = String> {
+    pub start_pc: InstructionIndex,
+    pub end_pc: InstructionIndex,
+    pub handler_pc: InstructionIndex,
+    /// The internal name of the exception class that is caught, which is None for handlers that
+    /// catch everything, such as for `finally`
+    pub catch_type: Option<T>,
+}
+
+/// An attribute with its name looked up and its info copied out of the data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedAttribute<T = String> {
+    pub name: T,
+    pub info: Vec<u8>,
+}
+
+/// Hold the text that a lookup in the pool found as the mode decides, or fail with the index that
+/// it was for
+fn text<'a, M: TextMode<'a>>(
+    text: Option<Cow<'a, str>>,
+    index: u16,
+    mode: M,
+) -> Result<M::Text, LoadError> {
+    text.map(|text| mode.text(text))
+        .ok_or(LoadError::InvalidIndex(index))
+}
+
+fn utf8<'a, M: TextMode<'a>>(
+    pool: &ConstantPool,
+    data: &'a [u8],
+    index: ConstantPoolIndexRaw<Utf8Constant>,
+    mode: M,
+) -> Result<M::Text, LoadError> {
+    text(pool.get_utf8_text(index, data), index.0, mode)
+}
+
+fn class_name<'a, M: TextMode<'a>>(
+    pool: &ConstantPool,
+    data: &'a [u8],
+    index: ConstantPoolIndexRaw<ClassConstant>,
+    mode: M,
+) -> Result<M::Text, LoadError> {
+    text(pool.get_class_name(index, data), index.0, mode)
+}
+
+fn signature<'a, M: TextMode<'a>>(
+    owner: &impl HasAttributes,
+    pool: &ConstantPool,
+    data: &'a [u8],
+    mode: M,
+) -> Result<Option<M::Text>, LoadError> {
+    owner
+        .find_attribute::<SignatureAttribute>(pool, data)?
+        .map(|attr| utf8(pool, data, attr.signature_index, mode))
+        .transpose()
+}
+
+fn attributes<'a, M: TextMode<'a>>(
+    class_file: &ClassFile,
+    attributes: &[AttributeInfo],
+    data: &'a [u8],
+    mode: M,
+) -> Result<Vec<ResolvedAttribute<M::Text>>, LoadError> {
+    attributes
+        .iter()
+        .map(|attr| {
+            Ok(ResolvedAttribute {
+                name: utf8(
+                    &class_file.const_pool,
+                    data,
+                    attr.attribute_name_index,
+                    mode,
+                )?,
+                info: class_file.read_range(data, attr.info.clone())?.to_vec(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,254 @@
+//! Skipping over the parts of a class file without parsing them, for tools which build their own
+//! indexes of class files and only need to know where things are.
+//!
+//! The functions here take the class file data and an offset into it, and return the offset after
+//! the skipped item. The nom parsers they are built on are also re-exported, for use in custom
+//! parsers.
+
+use nom::bytes::complete::take;
+use nom::number::complete::{be_u16, be_u8};
+use nom::IResult;
+
+pub use crate::attribute_info::skip_attribute_parser;
+pub use crate::constant_info::skip_constant_pool_parser;
+pub use crate::field_info::skip_field_parser;
+pub use crate::method_info::{skip_method_attributes_parser, skip_method_parser};
+
+use crate::error::{ItemKind, ItemLocation, Malformation, ParseError};
+use crate::parser::combinators::skip_count;
+use crate::parser::{check_header, ParseData, MAGIC};
+
+fn skip_at<'a>(
+    data: &'a [u8],
+    offset: usize,
+    mut parser: impl FnMut(ParseData<'a>) -> IResult<ParseData<'a>, ()>,
+) -> Result<usize, ParseError> {
+    if offset > data.len() {
+        return Err(ParseError::Malformed {
+            offset,
+            item: None,
+            reason: Malformation::Truncated,
+        });
+    }
+
+    let (i, _) = parser(ParseData::from_pos(data, offset))?;
+    Ok(i.pos())
+}
+
+/// Skip the field at the offset, returning the offset after it
+pub fn skip_field(data: &[u8], offset: usize) -> Result<usize, ParseError> {
+    skip_at(data, offset, skip_field_parser)
+}
+
+/// Skip the method at the offset, returning the offset after it
+pub fn skip_method(data: &[u8], offset: usize) -> Result<usize, ParseError> {
+    skip_at(data, offset, skip_method_parser)
+}
+
+/// Skip the attribute at the offset, returning the offset after it
+pub fn skip_attribute(data: &[u8], offset: usize) -> Result<usize, ParseError> {
+    skip_at(data, offset, skip_attribute_parser)
+}
+
+/// Skip `count` fields starting at the offset, returning the offset after the last
+pub fn skip_fields(data: &[u8], offset: usize, count: u16) -> Result<usize, ParseError> {
+    skip_at(data, offset, skip_count(skip_field_parser, count.into()))
+}
+
+/// Skip `count` methods starting at the offset, returning the offset after the last
+pub fn skip_methods(data: &[u8], offset: usize, count: u16) -> Result<usize, ParseError> {
+    skip_at(data, offset, skip_count(skip_method_parser, count.into()))
+}
+
+/// Skip `count` attributes starting at the offset, returning the offset after the last
+pub fn skip_attributes(data: &[u8], offset: usize, count: u16) -> Result<usize, ParseError> {
+    skip_at(
+        data,
+        offset,
+        skip_count(skip_attribute_parser, count.into()),
+    )
+}
+
+/// A table in the class file, which is preceded by a count of its entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Table {
+    pub count: u16,
+    /// The offset of the first entry, directly after the count
+    pub start: usize,
+    /// The offset directly after the last entry
+    pub end: usize,
+}
+
+/// Where each part of a class file is.
+/// The constant pool's count is the `constant_pool_count` of the class file, which is one more
+/// than the number of slots in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassLayout {
+    pub constant_pool: Table,
+    /// The offset of the access flags, which are followed by the this and super class indices
+    pub access_flags: usize,
+    pub interfaces: Table,
+    pub fields: Table,
+    pub methods: Table,
+    pub attributes: Table,
+}
+impl ClassLayout {
+    /// The offset directly after the class file, where any trailing bytes would start
+    pub fn end(&self) -> usize {
+        self.attributes.end
+    }
+}
+
+fn table<'a>(
+    i: ParseData<'a>,
+    skip: impl FnOnce(ParseData<'a>, u16) -> IResult<ParseData<'a>, ()>,
+) -> IResult<ParseData<'a>, Table> {
+    let (i, count) = be_u16(i)?;
+    let start = i.pos();
+    let (i, _) = skip(i, count)?;
+    let end = i.pos();
+    Ok((i, Table { count, start, end }))
+}
+
+/// Find where each part of the class file is, without parsing any of it.
+/// This does not even parse the constant pool, unlike [`crate::ClassFileOpt`].
+pub fn class_layout(data: &[u8]) -> Result<ClassLayout, ParseError> {
+    check_header(data)?;
+    layout(data).map_err(|err| err.with_item(data))
+}
+
+fn layout(data: &[u8]) -> Result<ClassLayout, ParseError> {
+    // The magic is followed by the minor and major version
+    let (i, _) = take(MAGIC.len() + 4)(ParseData::new(data))?;
+    let (i, constant_pool) = table(i, |i, count| {
+        skip_constant_pool_parser(i, usize::from(count.saturating_sub(1)))
+    })?;
+    let access_flags = i.pos();
+    // The access flags, this class, and super class
+    let (i, _) = take(6usize)(i)?;
+    let (i, interfaces) = table(i, |i, count| skip_count(be_u16, usize::from(count))(i))?;
+    let (i, fields) = table(i, |i, count| skip_count(skip_field_parser, count.into())(i))?;
+    let (i, methods) = table(i, |i, count| {
+        skip_count(skip_method_parser, count.into())(i)
+    })?;
+    let (_, attributes) = table(i, |i, count| {
+        skip_count(skip_attribute_parser, count.into())(i)
+    })?;
+
+    Ok(ClassLayout {
+        constant_pool,
+        access_flags,
+        interfaces,
+        fields,
+        methods,
+        attributes,
+    })
+}
+
+/// Skip one entry, returning how many slots it takes up in its table
+type SkipEntry = for<'a> fn(ParseData<'a>) -> IResult<ParseData<'a>, usize>;
+
+fn skip_constant_entry(i: ParseData) -> IResult<ParseData, usize> {
+    let (_, tag) = be_u8(i.clone())?;
+    let (i, _) = skip_constant_pool_parser(i, 1)?;
+    // Longs and doubles take up two slots
+    Ok((i, if matches!(tag, 5 | 6) { 2 } else { 1 }))
+}
+
+fn skip_interface_entry(i: ParseData) -> IResult<ParseData, usize> {
+    let (i, _) = be_u16(i)?;
+    Ok((i, 1))
+}
+
+fn skip_field_entry(i: ParseData) -> IResult<ParseData, usize> {
+    let (i, _) = skip_field_parser(i)?;
+    Ok((i, 1))
+}
+
+fn skip_method_entry(i: ParseData) -> IResult<ParseData, usize> {
+    let (i, _) = skip_method_parser(i)?;
+    Ok((i, 1))
+}
+
+fn skip_attribute_entry(i: ParseData) -> IResult<ParseData, usize> {
+    let (i, _) = skip_attribute_parser(i)?;
+    Ok((i, 1))
+}
+
+fn take_u16(i: &mut ParseData) -> Option<u16> {
+    let (rest, value) = be_u16::<_, nom::error::Error<_>>(i.clone()).ok()?;
+    *i = rest;
+    Some(value)
+}
+
+fn skip_bytes(i: &mut ParseData, count: usize) -> Option<()> {
+    let (rest, _) = take::<_, _, nom::error::Error<_>>(count)(i.clone()).ok()?;
+    *i = rest;
+    Some(())
+}
+
+/// Skip over the entries of a table from `first` up to `end`, stopping at the one that the offset
+/// is inside
+fn find_entry(
+    i: &mut ParseData,
+    kind: ItemKind,
+    first: usize,
+    end: usize,
+    offset: usize,
+    skip: SkipEntry,
+) -> Option<ItemLocation> {
+    let mut index = first;
+    while index < end {
+        let location = ItemLocation {
+            kind,
+            index,
+            start: i.pos(),
+        };
+        match skip(i.clone()) {
+            Ok((rest, slots)) if offset >= rest.pos() => {
+                *i = rest;
+                index += slots;
+            }
+            _ => return Some(location),
+        }
+    }
+    None
+}
+
+/// Find the constant, interface, field, method, or class attribute that the offset is inside.
+/// Items are found by skipping over the ones before them, so an offset in or after an item that
+/// can't be skipped over is treated as being inside that item.
+pub fn item_at(data: &[u8], offset: usize) -> Option<ItemLocation> {
+    if !data.starts_with(MAGIC) {
+        return None;
+    }
+
+    let tables: [(ItemKind, SkipEntry); 5] = [
+        (ItemKind::Constant, skip_constant_entry),
+        (ItemKind::Interface, skip_interface_entry),
+        (ItemKind::Field, skip_field_entry),
+        (ItemKind::Method, skip_method_entry),
+        (ItemKind::Attribute, skip_attribute_entry),
+    ];
+    let mut i = ParseData::new(data);
+    // The magic is followed by the minor and major version
+    skip_bytes(&mut i, MAGIC.len() + 4)?;
+    for (kind, skip) in tables {
+        if kind == ItemKind::Interface {
+            // The access flags, this class, and super class
+            skip_bytes(&mut i, 6)?;
+        }
+
+        let count = usize::from(take_u16(&mut i)?);
+        if offset < i.pos() {
+            return None;
+        }
+        // The constant pool starts at 1, and its count is one more than the number of slots
+        let first = if kind == ItemKind::Constant { 1 } else { 0 };
+        if let Some(location) = find_entry(&mut i, kind, first, count, offset, skip) {
+            return Some(location);
+        }
+    }
+
+    None
+}
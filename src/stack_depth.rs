@@ -0,0 +1,341 @@
+//! Simulating the depth of the operand stack through the code of methods, to check the
+//! `max_stack` that each one declares.
+//!
+//! Only the number of slots on the stack is tracked, not their types, so this is a cheap check
+//! rather than the full verifier. Subroutines are assumed to return to the instruction after their
+//! `jsr` with the stack as it was before the `jsr`, which is how compilers use them.
+
+use std::collections::HashMap;
+
+use crate::attribute_info::{CodeAttribute, ExceptionEntry, HasAttributes};
+use crate::constant_info::{ConstantInfo, FieldRefConstant, NameAndTypeConstant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::descriptor::method::MethodDescriptor;
+use crate::descriptor::DescriptorType;
+use crate::instructions::{code_iter, Instruction, InstructionError, WideInstruction};
+use crate::{ClassFile, LoadError};
+
+/// Why the stack depth of some code couldn't be simulated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackDepthError {
+    Instruction(InstructionError),
+    /// The instruction at the offset refers to a constant of the wrong kind, or to a member with
+    /// a descriptor that can't be parsed
+    InvalidConstant {
+        offset: usize,
+    },
+    /// The instruction at the offset pops more than is on the stack
+    Underflow {
+        offset: usize,
+    },
+    /// The instruction at the offset is reached with different depths
+    InconsistentDepth {
+        offset: usize,
+        first: u32,
+        second: u32,
+    },
+    /// A branch or switch target which isn't the start of an instruction in the code
+    BadTarget {
+        offset: usize,
+        target: i64,
+    },
+    /// An exception handler which isn't the start of an instruction in the code
+    BadHandler {
+        handler_pc: u16,
+    },
+    /// The instruction at the offset is the last, and execution can continue past it
+    FallsOffEnd {
+        offset: usize,
+    },
+}
+
+/// The simulated stack depth of a method's code, next to the `max_stack` that it declares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackDepthReport {
+    /// The index of the method
+    pub method: usize,
+    /// The length of the code in bytes
+    pub code_length: usize,
+    /// The `max_stack` of the Code attribute
+    pub max_stack: u16,
+    /// The deepest that the stack gets, or why the code couldn't be simulated
+    pub simulated: Result<u32, StackDepthError>,
+}
+impl StackDepthReport {
+    /// Whether the simulated depth is exactly the declared `max_stack`
+    pub fn matches(&self) -> bool {
+        self.simulated == Ok(u32::from(self.max_stack))
+    }
+
+    /// Whether the stack gets deeper than the declared `max_stack`, which the verifier rejects.
+    /// A `max_stack` which is larger than needed is allowed, but wastes space in every frame.
+    pub fn exceeds_max_stack(&self) -> bool {
+        matches!(self.simulated, Ok(depth) if depth > u32::from(self.max_stack))
+    }
+}
+
+fn slots(ty: &DescriptorType) -> u32 {
+    if ty.is_wide() {
+        2
+    } else {
+        1
+    }
+}
+
+fn member_descriptor<'a>(
+    pool: &ConstantPool,
+    data: &'a [u8],
+    index: ConstantPoolIndexRaw<NameAndTypeConstant>,
+) -> Option<&'a [u8]> {
+    let (_, descriptor) = pool.get_name_and_type(index)?;
+    Some(descriptor.as_bytes(data))
+}
+
+/// The number of slots that the field takes up
+fn field_slots(
+    pool: &ConstantPool,
+    data: &[u8],
+    index: ConstantPoolIndexRaw<FieldRefConstant>,
+) -> Option<u32> {
+    let field = pool.get_t(index)?;
+    let descriptor = member_descriptor(pool, data, field.name_and_type_index)?;
+    match DescriptorType::parse(descriptor) {
+        Ok((ty, [])) => Some(slots(&ty)),
+        _ => None,
+    }
+}
+
+/// The slots that a call pops and pushes, counting the receiver if there is one
+fn call_effect(
+    pool: &ConstantPool,
+    data: &[u8],
+    index: ConstantPoolIndexRaw<NameAndTypeConstant>,
+    receiver: bool,
+) -> Option<(u32, u32)> {
+    let descriptor = MethodDescriptor::parse(member_descriptor(pool, data, index)?).ok()?;
+    let parameters: u32 = descriptor.parameter_types.iter().map(slots).sum();
+    let ret = descriptor.return_type.as_ref().map_or(0, slots);
+    Some((parameters + u32::from(receiver), ret))
+}
+
+/// The name and type of a Methodref or InterfaceMethodref
+fn method_name_and_type(
+    pool: &ConstantPool,
+    index: ConstantPoolIndexRaw<ConstantInfo>,
+) -> Option<ConstantPoolIndexRaw<NameAndTypeConstant>> {
+    match pool.get(index)? {
+        ConstantInfo::MethodRef(method) => Some(method.name_and_type_index),
+        ConstantInfo::InterfaceMethodRef(method) => Some(method.name_and_type_index),
+        _ => None,
+    }
+}
+
+/// The number of slots that the instruction pops and then pushes.
+/// Returns None if a constant that it refers to can't be resolved.
+fn stack_effect(instruction: &Instruction, pool: &ConstantPool, data: &[u8]) -> Option<(u32, u32)> {
+    use Instruction::*;
+    Some(match instruction {
+        Nop | Iinc { .. } | Goto(_) | GotoW(_) | Ret(_) | Return => (0, 0),
+        AconstNull | IconstM1 | Iconst0 | Iconst1 | Iconst2 | Iconst3 | Iconst4 | Iconst5
+        | Fconst0 | Fconst1 | Fconst2 | Bipush(_) | Sipush(_) | Ldc(_) | LdcW(_) => (0, 1),
+        Lconst0 | Lconst1 | Dconst0 | Dconst1 | Ldc2W(_) => (0, 2),
+        Iload(_) | Fload(_) | Aload(_) | Iload0 | Iload1 | Iload2 | Iload3 | Fload0 | Fload1
+        | Fload2 | Fload3 | Aload0 | Aload1 | Aload2 | Aload3 => (0, 1),
+        Lload(_) | Dload(_) | Lload0 | Lload1 | Lload2 | Lload3 | Dload0 | Dload1 | Dload2
+        | Dload3 => (0, 2),
+        Iaload | Faload | Aaload | Baload | Caload | Saload => (2, 1),
+        Laload | Daload => (2, 2),
+        Istore(_) | Fstore(_) | Astore(_) | Istore0 | Istore1 | Istore2 | Istore3 | Fstore0
+        | Fstore1 | Fstore2 | Fstore3 | Astore0 | Astore1 | Astore2 | Astore3 => (1, 0),
+        Lstore(_) | Dstore(_) | Lstore0 | Lstore1 | Lstore2 | Lstore3 | Dstore0 | Dstore1
+        | Dstore2 | Dstore3 => (2, 0),
+        Iastore | Fastore | Aastore | Bastore | Castore | Sastore => (3, 0),
+        Lastore | Dastore => (4, 0),
+        Pop => (1, 0),
+        Pop2 => (2, 0),
+        Dup => (1, 2),
+        DupX1 => (2, 3),
+        DupX2 => (3, 4),
+        Dup2 => (2, 4),
+        Dup2X1 => (3, 5),
+        Dup2X2 => (4, 6),
+        Swap => (2, 2),
+        Iadd | Fadd | Isub | Fsub | Imul | Fmul | Idiv | Fdiv | Irem | Frem | Ishl | Ishr
+        | Iushr | Iand | Ior | Ixor => (2, 1),
+        Ladd | Dadd | Lsub | Dsub | Lmul | Dmul | Ldiv | Ddiv | Lrem | Drem | Land | Lor | Lxor => {
+            (4, 2)
+        }
+        Lshl | Lshr | Lushr => (3, 2),
+        Ineg | Fneg | I2f | F2i | I2b | I2c | I2s => (1, 1),
+        Lneg | Dneg | L2d | D2l => (2, 2),
+        I2l | I2d | F2l | F2d => (1, 2),
+        L2i | L2f | D2i | D2f => (2, 1),
+        Lcmp | Dcmpl | Dcmpg => (4, 1),
+        Fcmpl | Fcmpg => (2, 1),
+        Ifeq(_) | Ifne(_) | Iflt(_) | Ifge(_) | Ifgt(_) | Ifle(_) | Ifnull(_) | Ifnonnull(_) => {
+            (1, 0)
+        }
+        IfIcmpeq(_) | IfIcmpne(_) | IfIcmplt(_) | IfIcmpge(_) | IfIcmpgt(_) | IfIcmple(_)
+        | IfAcmpeq(_) | IfAcmpne(_) => (2, 0),
+        Jsr(_) | JsrW(_) => (0, 1),
+        Tableswitch { .. } | Lookupswitch { .. } => (1, 0),
+        Ireturn | Freturn | Areturn | Athrow | Monitorenter | Monitorexit => (1, 0),
+        Lreturn | Dreturn => (2, 0),
+        Getstatic(index) => (0, field_slots(pool, data, *index)?),
+        Putstatic(index) => (field_slots(pool, data, *index)?, 0),
+        Getfield(index) => (1, field_slots(pool, data, *index)?),
+        Putfield(index) => (1 + field_slots(pool, data, *index)?, 0),
+        Invokevirtual(index) => {
+            let method = pool.get_t(*index)?;
+            call_effect(pool, data, method.name_and_type_index, true)?
+        }
+        Invokespecial(index) => call_effect(pool, data, method_name_and_type(pool, *index)?, true)?,
+        Invokestatic(index) => call_effect(pool, data, method_name_and_type(pool, *index)?, false)?,
+        Invokeinterface { index, .. } => {
+            let method = pool.get_t(*index)?;
+            call_effect(pool, data, method.name_and_type_index, true)?
+        }
+        Invokedynamic(index) => {
+            let call_site = pool.get_t(*index)?;
+            call_effect(pool, data, call_site.name_and_type_index, false)?
+        }
+        New(_) => (0, 1),
+        Newarray(_) | Anewarray(_) | Arraylength | Checkcast(_) | Instanceof(_) => (1, 1),
+        Multianewarray { dimensions, .. } => (u32::from(*dimensions), 1),
+        Wide(wide) => match wide {
+            WideInstruction::Iload(_) | WideInstruction::Fload(_) | WideInstruction::Aload(_) => {
+                (0, 1)
+            }
+            WideInstruction::Lload(_) | WideInstruction::Dload(_) => (0, 2),
+            WideInstruction::Istore(_)
+            | WideInstruction::Fstore(_)
+            | WideInstruction::Astore(_) => (1, 0),
+            WideInstruction::Lstore(_) | WideInstruction::Dstore(_) => (2, 0),
+            WideInstruction::Ret(_) | WideInstruction::Iinc { .. } => (0, 0),
+        },
+    })
+}
+
+/// Record the depth that the instruction is reached with, queueing it if it hadn't been reached
+fn enter(
+    depths: &mut [Option<u32>],
+    pending: &mut Vec<usize>,
+    index: usize,
+    offset: usize,
+    depth: u32,
+) -> Result<(), StackDepthError> {
+    match depths[index] {
+        None => {
+            depths[index] = Some(depth);
+            pending.push(index);
+            Ok(())
+        }
+        Some(first) if first != depth => Err(StackDepthError::InconsistentDepth {
+            offset,
+            first,
+            second: depth,
+        }),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Simulate the depth of the operand stack through every path of the code, returning the deepest
+/// that it gets.
+/// The code must be the whole of the code of a method, and the exception table the one that goes
+/// with it: each handler starts with just the exception on the stack.
+pub fn simulate_max_stack(
+    code: &[u8],
+    exception_table: &[ExceptionEntry],
+    pool: &ConstantPool,
+    class_file_data: &[u8],
+) -> Result<u32, StackDepthError> {
+    let mut instructions = Vec::new();
+    for result in code_iter(code) {
+        let (offset, instruction) = result.map_err(StackDepthError::Instruction)?;
+        instructions.push((usize::from(offset), instruction));
+    }
+    if instructions.is_empty() {
+        return Ok(0);
+    }
+    let indices: HashMap<usize, usize> = instructions
+        .iter()
+        .enumerate()
+        .map(|(index, &(offset, _))| (offset, index))
+        .collect();
+
+    let mut depths = vec![None; instructions.len()];
+    let mut pending = Vec::new();
+    let mut max = 0;
+    enter(&mut depths, &mut pending, 0, 0, 0)?;
+    for entry in exception_table {
+        let handler_pc = entry.handler_pc.0;
+        let handler = usize::from(handler_pc);
+        let index = *indices
+            .get(&handler)
+            .ok_or(StackDepthError::BadHandler { handler_pc })?;
+        enter(&mut depths, &mut pending, index, handler, 1)?;
+        max = 1;
+    }
+
+    while let Some(index) = pending.pop() {
+        let (offset, instruction) = &instructions[index];
+        let offset = *offset;
+        let depth = depths[index].unwrap_or(0);
+        let (pops, pushes) = stack_effect(instruction, pool, class_file_data)
+            .ok_or(StackDepthError::InvalidConstant { offset })?;
+        let after = depth
+            .checked_sub(pops)
+            .ok_or(StackDepthError::Underflow { offset })?
+            + pushes;
+        max = max.max(after);
+
+        for relative in instruction.branch_targets() {
+            let target = offset as i64 + i64::from(relative);
+            let index = usize::try_from(target)
+                .ok()
+                .and_then(|target| indices.get(&target))
+                .ok_or(StackDepthError::BadTarget { offset, target })?;
+            enter(&mut depths, &mut pending, *index, target as usize, after)?;
+        }
+        if instruction.falls_through() {
+            let next = index + 1;
+            let (next_offset, _) = instructions
+                .get(next)
+                .ok_or(StackDepthError::FallsOffEnd { offset })?;
+            // A subroutine returns to the next instruction without its return address
+            let is_jsr = matches!(instruction, Instruction::Jsr(_) | Instruction::JsrW(_));
+            let depth = if is_jsr { depth } else { after };
+            enter(&mut depths, &mut pending, next, *next_offset, depth)?;
+        }
+    }
+
+    Ok(max)
+}
+
+impl ClassFile {
+    /// Simulate the stack depth of the code of each method, see [`simulate_max_stack`], and
+    /// compare it to the `max_stack` that the method declares.
+    /// Methods without code are skipped.
+    /// Errors if a Code attribute can't be parsed.
+    pub fn stack_depth_reports(&self, data: &[u8]) -> Result<Vec<StackDepthReport>, LoadError> {
+        let pool = &self.const_pool;
+        let mut reports = Vec::new();
+        for (method, info) in self.methods.iter().enumerate() {
+            let code = match info.find_attribute::<CodeAttribute>(pool, data)? {
+                Some(code) => code,
+                None => continue,
+            };
+            let bytes = data.get(code.code.clone()).ok_or(LoadError::OutOfRange {
+                index: code.code.end,
+                len: data.len(),
+            })?;
+            reports.push(StackDepthReport {
+                method,
+                code_length: bytes.len(),
+                max_stack: code.max_stack,
+                simulated: simulate_max_stack(bytes, &code.exception_table, pool, data),
+            });
+        }
+        Ok(reports)
+    }
+}
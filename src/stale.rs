@@ -0,0 +1,92 @@
+//! Tracking which parts of the class file data have been superseded by edits.
+//!
+//! Edits never change the bytes that are already in the data: new attributes and constants are
+//! written to the end, and the class file is pointed at them. The old bytes are left in place, so
+//! a range that was read from the class file before the edit, such as the code of a parsed
+//! [`crate::attribute_info::CodeAttribute`], still reads without error but no longer describes
+//! the class. [`ClassFile::stale_ranges`] records those old ranges so that they can be caught.
+
+use std::ops::Range;
+
+use crate::{ClassFile, LoadError};
+
+/// Sorted, non-overlapping ranges of data which edits have replaced or removed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StaleRanges {
+    ranges: Vec<Range<usize>>,
+}
+impl StaleRanges {
+    pub fn new() -> StaleRanges {
+        StaleRanges::default()
+    }
+
+    /// Record the range as stale, merging it with any stale ranges that it overlaps or touches
+    pub fn mark(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let first = self.ranges.partition_point(|stale| stale.end < range.start);
+        let last = self
+            .ranges
+            .partition_point(|stale| stale.start <= range.end);
+        let merged = if first < last {
+            self.ranges[first].start.min(range.start)..self.ranges[last - 1].end.max(range.end)
+        } else {
+            range
+        };
+        self.ranges.splice(first..last, std::iter::once(merged));
+    }
+
+    /// Whether any of the range is stale
+    pub fn is_stale(&self, range: &Range<usize>) -> bool {
+        let i = self
+            .ranges
+            .partition_point(|stale| stale.end <= range.start);
+        self.ranges
+            .get(i)
+            .is_some_and(|stale| stale.start < range.end)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Iterate over the stale ranges, in order
+    pub fn iter(&self) -> impl Iterator<Item = &Range<usize>> {
+        self.ranges.iter()
+    }
+
+    /// Forget every stale range, such as after the class file has been written out and parsed
+    /// again
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+}
+
+impl ClassFile {
+    /// Whether any of the range of the data has been replaced or removed by an edit to the class,
+    /// see [`StaleRanges`]
+    pub fn is_stale(&self, range: &Range<usize>) -> bool {
+        self.stale_ranges.is_stale(range)
+    }
+
+    /// Get the bytes of the range of the data.
+    /// Errors if the range is out of bounds, or if an edit has replaced or removed any of it.
+    pub fn read_range<'a>(
+        &self,
+        data: &'a [u8],
+        range: Range<usize>,
+    ) -> Result<&'a [u8], LoadError> {
+        if self.is_stale(&range) {
+            return Err(LoadError::Stale {
+                start: range.start,
+                end: range.end,
+            });
+        }
+        let len = data.len();
+        data.get(range.clone()).ok_or(LoadError::OutOfRange {
+            index: range.end,
+            len,
+        })
+    }
+}
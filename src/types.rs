@@ -1,22 +1,42 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::ops::Range;
+use std::rc::Rc;
 
-use nom::number::complete::be_u16;
+use nom::IResult;
 use smallvec::SmallVec;
 
-use crate::attribute_info::AttributeInfo;
-use crate::constant_info::ConstantInfo;
-use crate::field_info::{field_opt_value_parser, FieldInfo, FieldInfoOpt};
+use crate::attribute_info::{
+    attribute_parser, names, AttributeInfo, BootstrapMethodsAttribute, ConstantValueAttribute,
+    HasAttributes, InnerClassesAttribute, KnownAttribute, SignatureAttribute, SourceFileAttribute,
+    TypedAttributes,
+};
+use crate::constant_info::{
+    self, ConstantInfo, ConstantValue, LdcError, LdcKind, LoadableConstant, MemberRef,
+    MemberRefError, MethodHandleConstant, MethodHandleError, ResolvedMethodHandle, Utf8Constant,
+};
+use crate::descriptor::method::MethodDescriptor;
+use crate::descriptor::{self, DescriptorCache, DescriptorError, DescriptorType, ParsedDescriptor};
+use crate::field_info::{
+    field_opt_parser, field_opt_value_parser_with, field_parser, skip_field_parser,
+    FieldAccessFlags, FieldInfo, FieldInfoOpt,
+};
 use crate::method_info::{
-    attributes_search_parser, method_opt_parser, method_parser, skip_method_attributes_parser,
-    skip_method_parser, MethodInfo, MethodInfoOpt,
+    attributes_search_by, method_opt_parser, method_parser, skip_method_attributes_parser,
+    skip_method_parser, MethodAccessFlags, MethodInfo, MethodInfoOpt, MethodSize,
 };
 
+use crate::error::ParseError;
+use crate::parser::combinators::{count_sv, skip_count};
 use crate::parser::ParseData;
-use crate::util::{constant_pool_index_raw, count_sv, skip_count};
+use crate::resolved::{Borrowed, TextMode};
+use crate::stale::StaleRanges;
+use crate::util::{AccessFlags, Shared};
 use crate::{
     constant_info::ClassConstant,
-    constant_pool::{ConstantPool, ConstantPoolIndexRaw},
+    constant_pool::{
+        ConstantPool, ConstantPoolIndex, ConstantPoolIndexRaw, ConstantPoolOpt, PoolRef,
+    },
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -67,6 +87,12 @@ impl ClassFileVersion {
     pub fn into_java_version(self) -> Option<ClassFileJavaVersion> {
         ClassFileJavaVersion::from_version(self.major, self.minor)
     }
+
+    /// Whether a JVM which supports class files up to this version treats every class as having
+    /// `ACC_SUPER`, whatever its flags and version. This is true from Java 8.
+    pub fn implies_acc_super(self) -> bool {
+        self.major >= 52
+    }
 }
 
 bitflags! {
@@ -79,15 +105,81 @@ bitflags! {
         const SYNTHETIC = 0x1000;  //	Declared synthetic; not present in the source code.
         const ANNOTATION = 0x2000; //	Declared as an annotation type.
         const ENUM = 0x4000;       //	Declared as an enum type.
+        const MODULE = 0x8000;     //	Is a module, not a class or interface.
     }
 }
+impl ClassAccessFlags {
+    /// Whether `invokespecial` in a class with these flags has the semantics from before
+    /// `ACC_SUPER`, calling the resolved superclass method directly rather than looking it up again
+    /// from the direct superclass.
+    /// `jvm_version` is the newest class file version supported by the JVM which runs the class,
+    /// since only JVMs older than Java 8 honor a missing `ACC_SUPER`, see
+    /// [`ClassFileVersion::implies_acc_super`].
+    pub fn uses_legacy_invokespecial(self, jvm_version: ClassFileVersion) -> bool {
+        !self.contains(ClassAccessFlags::SUPER) && !jvm_version.implies_acc_super()
+    }
+}
+impl AccessFlags for ClassAccessFlags {
+    const DEFINED: u16 = ClassAccessFlags::all().bits();
+
+    fn defined_bits(self) -> u16 {
+        self.bits()
+    }
+}
+
+/// What kind of type a class file declares, from its flags and attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClassKind {
+    Class,
+    Interface,
+    /// An interface declared with `@interface`
+    Annotation,
+    Enum,
+    Record,
+    /// A `module-info` class
+    Module,
+}
 
 /// An error in loading data
 #[derive(Debug, Clone)]
 pub enum LoadError {
     /// Some unknown error
     Unknown,
+    /// The data could not be parsed
+    Parse(ParseError),
+    /// A constant pool index was zero, out of range, or for the wrong kind of constant
+    InvalidIndex(u16),
+    /// An index into one of the tables of the class file, such as its methods, was out of range
+    OutOfRange { index: usize, len: usize },
+    /// The range of the data has been replaced or removed by an edit, see [`crate::stale`]
+    Stale { start: usize, end: usize },
+}
+impl From<ParseError> for LoadError {
+    fn from(err: ParseError) -> Self {
+        LoadError::Parse(err)
+    }
+}
+impl<'a> From<nom::Err<nom::error::Error<ParseData<'a>>>> for LoadError {
+    fn from(err: nom::Err<nom::error::Error<ParseData<'a>>>) -> Self {
+        LoadError::Parse(ParseError::from(err))
+    }
 }
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Unknown => f.write_str("failed to load data"),
+            LoadError::Parse(err) => err.fmt(f),
+            LoadError::InvalidIndex(index) => write!(f, "invalid constant pool index {}", index),
+            LoadError::OutOfRange { index, len } => {
+                write!(f, "index {} is out of range of {} entries", index, len)
+            }
+            LoadError::Stale { start, end } => {
+                write!(f, "the data at {}..{} was replaced by an edit", start, end)
+            }
+        }
+    }
+}
+impl std::error::Error for LoadError {}
 
 #[derive(Clone, Debug)]
 pub struct ClassFile {
@@ -95,6 +187,8 @@ pub struct ClassFile {
     pub const_pool_size: u16,
     pub const_pool: ConstantPool,
     pub access_flags: ClassAccessFlags,
+    /// The access flags as they were in the class file, including bits which have no defined meaning
+    pub raw_access_flags: u16,
     pub this_class: ConstantPoolIndexRaw<ClassConstant>,
     pub super_class: ConstantPoolIndexRaw<ClassConstant>,
     pub interfaces_count: u16,
@@ -105,14 +199,429 @@ pub struct ClassFile {
     pub methods: SmallVec<[MethodInfo; 6]>,
     pub attributes_count: u16,
     pub attributes: SmallVec<[AttributeInfo; 4]>,
+    /// Only exists if enabled with [`ClassFile::enable_descriptor_cache`]
+    pub descriptor_cache: Option<DescriptorCache>,
+    /// Only exists if parsed with [`ClassFile::parse_typed_attributes`], or
+    /// [`crate::ParseOptions::parse_typed_attributes`]
+    pub typed_attributes: Option<TypedAttributes>,
+    /// The ranges of the data which edits to the class have replaced or removed
+    pub stale_ranges: StaleRanges,
+}
+impl ClassFile {
+    /// The access flags as a raw value, including any bits that have no defined meaning
+    pub fn raw_flags(&self) -> u16 {
+        self.access_flags.with_undefined(self.raw_access_flags)
+    }
+
+    pub fn is_module(&self) -> bool {
+        self.access_flags.contains(ClassAccessFlags::MODULE)
+    }
+
+    /// Whether `invokespecial` in this class has the legacy semantics when run by a JVM which
+    /// supports up to `jvm_version`, see [`ClassAccessFlags::uses_legacy_invokespecial`]
+    pub fn uses_legacy_invokespecial(&self, jvm_version: ClassFileVersion) -> bool {
+        self.access_flags.uses_legacy_invokespecial(jvm_version)
+    }
+
+    pub fn is_annotation(&self) -> bool {
+        self.access_flags.contains(ClassAccessFlags::ANNOTATION)
+    }
+
+    /// Whether the class has a Record attribute
+    pub fn is_record(&self, data: &[u8]) -> bool {
+        self.find_attribute_info(&self.const_pool, data, names::RECORD)
+            .is_some()
+    }
+
+    /// The kind of type that the class declares.
+    /// Annotations are also interfaces, but are reported as annotations.
+    pub fn kind(&self, data: &[u8]) -> ClassKind {
+        if self.is_module() {
+            ClassKind::Module
+        } else if self.is_annotation() {
+            ClassKind::Annotation
+        } else if self.access_flags.contains(ClassAccessFlags::INTERFACE) {
+            ClassKind::Interface
+        } else if self.access_flags.contains(ClassAccessFlags::ENUM) {
+            ClassKind::Enum
+        } else if self.is_record(data) {
+            ClassKind::Record
+        } else {
+            ClassKind::Class
+        }
+    }
+
+    /// Cache parsed descriptors for [`ClassFile::parsed_descriptor_cached`]
+    pub fn enable_descriptor_cache(&mut self) {
+        if self.descriptor_cache.is_none() {
+            self.descriptor_cache = Some(DescriptorCache::new());
+        }
+    }
+
+    /// Parse the field or method descriptor held by the utf8 constant at the index.
+    /// If the descriptor cache is enabled then each descriptor is only parsed once, otherwise this
+    /// parses it every time.
+    pub fn parsed_descriptor_cached(
+        &self,
+        index: ConstantPoolIndexRaw<Utf8Constant>,
+        data: &[u8],
+    ) -> Result<Rc<ParsedDescriptor>, DescriptorError> {
+        match &self.descriptor_cache {
+            Some(cache) => cache.get_or_parse(&self.const_pool, index, data),
+            None => descriptor::parse_at(&self.const_pool, index, data).map(Rc::new),
+        }
+    }
+
+    /// Resolve the operand of an `ldc`, `ldc_w`, or `ldc2_w` instruction in this class, see
+    /// [`constant_info::resolve_ldc`]
+    pub fn resolve_ldc(
+        &self,
+        kind: LdcKind,
+        index: u16,
+        data: &[u8],
+    ) -> Result<LoadableConstant<'_>, LdcError> {
+        constant_info::resolve_ldc(&self.const_pool, kind, index, self.version, data)
+    }
+
+    /// Resolve the member that a method handle in this class refers to, see
+    /// [`constant_info::resolve_method_handle`]
+    pub fn resolve_method_handle<'a>(
+        &self,
+        handle: &MethodHandleConstant,
+        data: &'a [u8],
+    ) -> Result<ResolvedMethodHandle<Cow<'a, str>>, MethodHandleError> {
+        self.resolve_method_handle_with(handle, data, Borrowed)
+    }
+
+    /// Resolve the member that a method handle in this class refers to, holding its names as the
+    /// mode decides, see [`constant_info::resolve_method_handle_with`]
+    pub fn resolve_method_handle_with<'a, M: TextMode<'a>>(
+        &self,
+        handle: &MethodHandleConstant,
+        data: &'a [u8],
+        mode: M,
+    ) -> Result<ResolvedMethodHandle<M::Text>, MethodHandleError> {
+        constant_info::resolve_method_handle_with(
+            &self.const_pool,
+            handle,
+            self.version,
+            data,
+            mode,
+        )
+    }
+
+    /// Resolve every field and method reference in the constant pool, see
+    /// [`constant_info::referenced_members`]
+    pub fn referenced_members<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> Result<Vec<MemberRef<Cow<'a, str>>>, MemberRefError> {
+        self.referenced_members_with(data, Borrowed)
+    }
+
+    /// Resolve every field and method reference in the constant pool, holding their text as the
+    /// mode decides, see [`constant_info::referenced_members_with`]
+    pub fn referenced_members_with<'a, M: TextMode<'a>>(
+        &self,
+        data: &'a [u8],
+        mode: M,
+    ) -> Result<Vec<MemberRef<M::Text>>, MemberRefError> {
+        constant_info::referenced_members_with(&self.const_pool, data, mode)
+    }
+
+    /// Get the encoded size of each method, in order
+    pub fn method_sizes(&self, data: &[u8]) -> Result<Vec<MethodSize>, LoadError> {
+        self.methods
+            .iter()
+            .map(|method| method.size(&self.const_pool, data))
+            .collect()
+    }
+
+    fn method_descriptor<'a>(
+        &self,
+        method: &MethodInfo,
+        data: &'a [u8],
+    ) -> Result<MethodDescriptor<'a>, DescriptorError> {
+        let text = self
+            .const_pool
+            .get_t(method.descriptor_index)
+            .ok_or(DescriptorError::InvalidIndex)?;
+        MethodDescriptor::parse(text.as_bytes(data)).map_err(DescriptorError::Method)
+    }
+
+    /// Iterate over the name and parsed descriptor of each method, in order.
+    /// Methods can be overloaded, so collecting these into a map keyed by name keeps only the last
+    /// overload; `collect::<Result<Vec<_>, _>>()` keeps them all.
+    pub fn method_signatures<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = Result<(Cow<'a, str>, MethodDescriptor<'a>), DescriptorError>> + 'a
+    {
+        self.methods.iter().map(move |method| {
+            let name = member_name(&self.const_pool, data, method.name_index)?;
+            Ok((name, self.method_descriptor(method, data)?))
+        })
+    }
+
+    /// Iterate over the name and parsed descriptor of each field, in order, such as to collect
+    /// them into a map with `collect::<Result<HashMap<_, _>, _>>()`
+    pub fn field_signatures<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = Result<(Cow<'a, str>, DescriptorType<'a>), DescriptorError>> + 'a
+    {
+        self.fields.iter().map(move |field| {
+            let pool = &self.const_pool;
+            let name = member_name(pool, data, field.name_index)?;
+            let text = pool
+                .get_t(field.descriptor_index)
+                .ok_or(DescriptorError::InvalidIndex)?;
+            let (descriptor, rest) =
+                DescriptorType::parse(text.as_bytes(data)).map_err(DescriptorError::Field)?;
+            if !rest.is_empty() {
+                return Err(DescriptorError::RemainingData);
+            }
+            Ok((name, descriptor))
+        })
+    }
+
+    fn is_named(&self, method: &MethodInfo, data: &[u8], name: &str) -> bool {
+        self.const_pool
+            .get_t(method.name_index)
+            .map(|text| text.as_bytes(data) == name.as_bytes())
+            .unwrap_or(false)
+    }
+
+    /// Get the constructors of the class, which are its `<init>` methods, with their parsed
+    /// descriptors
+    pub fn constructors<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> Result<Vec<(&MethodInfo, MethodDescriptor<'a>)>, DescriptorError> {
+        self.methods
+            .iter()
+            .filter(|method| self.is_named(method, data, crate::names::INIT))
+            .map(|method| Ok((method, self.method_descriptor(method, data)?)))
+            .collect()
+    }
+
+    /// Get the static initializer of the class with its parsed descriptor, if it has one.
+    /// Like the JVM, this ignores `<clinit>` methods which don't return void, or from version 51,
+    /// which aren't static or which take arguments.
+    pub fn static_initializer<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> Result<Option<(&MethodInfo, MethodDescriptor<'a>)>, DescriptorError> {
+        let initializers = self
+            .methods
+            .iter()
+            .filter(|method| self.is_named(method, data, crate::names::CLINIT));
+        for method in initializers {
+            let descriptor = self.method_descriptor(method, data)?;
+            if descriptor.return_type.is_some() {
+                continue;
+            }
+            if self.version.major >= 51
+                && (!method.access_flags.contains(MethodAccessFlags::STATIC)
+                    || !descriptor.parameter_types.is_empty())
+            {
+                continue;
+            }
+
+            return Ok(Some((method, descriptor)));
+        }
+
+        Ok(None)
+    }
+
+    /// Get the compile-time constants of the class: every static final field that has a
+    /// ConstantValue attribute, keyed by the field's name
+    pub fn static_final_values(
+        &self,
+        data: &[u8],
+    ) -> Result<HashMap<String, ConstantValue>, LoadError> {
+        let mut values = HashMap::new();
+        for field in self.fields.iter() {
+            if !field.access_flags.contains(STATIC_FINAL) {
+                continue;
+            }
+
+            let attr: Option<ConstantValueAttribute> =
+                field.find_attribute(&self.const_pool, data)?;
+            if let Some(attr) = attr {
+                let (name, value) = resolve_field_value(
+                    PoolRef::Loaded(&self.const_pool),
+                    data,
+                    field.name_index,
+                    attr.constant_value_index,
+                )?;
+                values.insert(name, value);
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Get the internal name of the class, such as `java/lang/String`.
+    /// Returns None if `this_class` isn't a Class entry with a name.
+    pub fn this_class_name<'a>(&self, data: &'a [u8]) -> Option<Cow<'a, str>> {
+        self.const_pool.get_class_name(self.this_class, data)
+    }
+
+    /// Get the internal name of the superclass.
+    /// Returns None if there is no superclass, as for `java/lang/Object` and modules, or if
+    /// `super_class` isn't a Class entry with a name.
+    pub fn super_class_name<'a>(&self, data: &'a [u8]) -> Option<Cow<'a, str>> {
+        self.const_pool.get_class_name(self.super_class, data)
+    }
+
+    /// Get the internal names of the interfaces that the class directly implements, such as
+    /// `java/io/Serializable`, in the order they are declared.
+    /// Errors if any of them isn't a Class entry with a name.
+    pub fn interface_names<'a>(&self, data: &'a [u8]) -> Result<Vec<Cow<'a, str>>, LoadError> {
+        interface_names(PoolRef::Loaded(&self.const_pool), &self.interfaces, data)
+    }
+
+    /// Whether the class directly implements the interface with the internal name.
+    /// Superinterfaces, and interfaces implemented by superclasses, are not checked.
+    /// Errors if any of the interfaces isn't a Class entry with a name.
+    pub fn implements(&self, data: &[u8], name: &str) -> Result<bool, LoadError> {
+        implements(
+            PoolRef::Loaded(&self.const_pool),
+            &self.interfaces,
+            data,
+            name,
+        )
+    }
+
+    /// Find the method with the name and descriptor, such as `toString` and
+    /// `()Ljava/lang/String;`
+    pub fn find_method(&self, data: &[u8], name: &str, descriptor: &str) -> Option<&MethodInfo> {
+        let pool = PoolRef::Loaded(&self.const_pool);
+        self.methods.iter().find(|method| {
+            is_member(
+                pool,
+                data,
+                method.name_index,
+                method.descriptor_index,
+                name,
+                descriptor,
+            )
+        })
+    }
+
+    /// Find the field with the name and descriptor, such as `count` and `I`
+    pub fn find_field(&self, data: &[u8], name: &str, descriptor: &str) -> Option<&FieldInfo> {
+        let pool = PoolRef::Loaded(&self.const_pool);
+        self.fields.iter().find(|field| {
+            is_member(
+                pool,
+                data,
+                field.name_index,
+                field.descriptor_index,
+                name,
+                descriptor,
+            )
+        })
+    }
+}
+
+/// Whether the member's name and descriptor are the given ones
+fn is_member(
+    pool: PoolRef,
+    data: &[u8],
+    name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    descriptor_index: ConstantPoolIndexRaw<Utf8Constant>,
+    name: &str,
+    descriptor: &str,
+) -> bool {
+    pool.is_utf8(name_index, data, name) && pool.is_utf8(descriptor_index, data, descriptor)
+}
+
+fn member_name<'a>(
+    pool: &ConstantPool,
+    data: &'a [u8],
+    index: ConstantPoolIndexRaw<Utf8Constant>,
+) -> Result<Cow<'a, str>, DescriptorError> {
+    let text = pool.get_t(index).ok_or(DescriptorError::InvalidIndex)?;
+    Ok(text.as_text(data))
+}
+
+fn interface_name<'a>(
+    pool: PoolRef,
+    data: &'a [u8],
+    index: ConstantPoolIndexRaw<ClassConstant>,
+) -> Result<Cow<'a, str>, LoadError> {
+    pool.get_class_name(index, data)
+        .ok_or(LoadError::InvalidIndex(index.0))
+}
+
+fn interface_names<'a>(
+    pool: PoolRef,
+    interfaces: &[ConstantPoolIndexRaw<ClassConstant>],
+    data: &'a [u8],
+) -> Result<Vec<Cow<'a, str>>, LoadError> {
+    interfaces
+        .iter()
+        .map(|&index| interface_name(pool, data, index))
+        .collect()
+}
+
+fn implements(
+    pool: PoolRef,
+    interfaces: &[ConstantPoolIndexRaw<ClassConstant>],
+    data: &[u8],
+    name: &str,
+) -> Result<bool, LoadError> {
+    for &index in interfaces {
+        if interface_name(pool, data, index)? == name {
+            return Ok(true);
+        }
+    }
+    Ok(false)
 }
 
+const STATIC_FINAL: FieldAccessFlags = FieldAccessFlags::from_bits_truncate(
+    FieldAccessFlags::STATIC.bits() | FieldAccessFlags::FINAL.bits(),
+);
+
+fn resolve_field_value(
+    pool: PoolRef,
+    data: &[u8],
+    name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    value_index: ConstantPoolIndexRaw<ConstantInfo>,
+) -> Result<(String, ConstantValue), LoadError> {
+    let name = pool
+        .get_utf8_text(name_index, data)
+        .ok_or(LoadError::InvalidIndex(name_index.0))?;
+    let value = ConstantValue::resolve_in(pool, value_index, data)
+        .ok_or(LoadError::InvalidIndex(value_index.0))?;
+    Ok((name.into_owned(), value))
+}
+
+impl HasAttributes for ClassFile {
+    fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+}
+
+/// A class file whose fields, methods, and attributes are only parsed when asked for.
+/// Cloning is cheap, since the constant pool and any loaded fields, methods, and attributes are
+/// shared between the clones. With the `threading` feature, the clones can be sent to other
+/// threads.
 #[derive(Clone, Debug)]
 pub struct ClassFileOpt {
     pub version: ClassFileVersion,
     pub const_pool_size: u16,
+    /// Empty while the pool is lazy, until [`ClassFileOpt::load_constant_pool`] is called.
+    /// The methods of `ClassFileOpt` use whichever pool the class has.
     pub const_pool: ConstantPool,
+    /// The pool, if it was parsed with [`crate::ParseOptions::lazy_constant_pool`] and has not
+    /// been loaded yet
+    pub lazy_const_pool: Option<ConstantPoolOpt>,
     pub access_flags: ClassAccessFlags,
+    /// The access flags as they were in the class file, including bits which have no defined meaning
+    pub raw_access_flags: u16,
     pub this_class: ConstantPoolIndexRaw<ClassConstant>,
     pub super_class: ConstantPoolIndexRaw<ClassConstant>,
     pub interfaces_count: u16,
@@ -122,7 +631,104 @@ pub struct ClassFileOpt {
     pub attributes: OptSmallVec<AttributeInfo, 4>,
 }
 impl ClassFileOpt {
-    // TODO: Return more useful errors
+    /// The access flags as a raw value, including any bits that have no defined meaning
+    pub fn raw_flags(&self) -> u16 {
+        self.access_flags.with_undefined(self.raw_access_flags)
+    }
+
+    /// See [`ClassFile::uses_legacy_invokespecial`]
+    pub fn uses_legacy_invokespecial(&self, jvm_version: ClassFileVersion) -> bool {
+        self.access_flags.uses_legacy_invokespecial(jvm_version)
+    }
+
+    /// Get the constant pool entry at the index, parsing it from the data if the pool is lazy
+    pub fn constant<T>(
+        &self,
+        data: &[u8],
+        i: impl TryInto<ConstantPoolIndex<T>>,
+    ) -> Option<ConstantInfo> {
+        match &self.lazy_const_pool {
+            Some(pool) => pool.get(i, data),
+            None => self.const_pool.get(i).cloned(),
+        }
+    }
+
+    /// Parse every entry of a lazy constant pool into [`Self::const_pool`], so that it can be used
+    /// like that of a class file which was not parsed lazily.
+    /// Does nothing if the pool has already been loaded.
+    pub fn load_constant_pool(&mut self, data: &[u8]) -> Result<(), LoadError> {
+        if let Some(pool) = &self.lazy_const_pool {
+            self.const_pool = pool.to_pool(data)?;
+            self.lazy_const_pool = None;
+        }
+        Ok(())
+    }
+
+    /// The constant pool, whether it is lazy or loaded
+    fn pool(&self) -> PoolRef<'_> {
+        match &self.lazy_const_pool {
+            Some(pool) => PoolRef::Lazy(pool),
+            None => PoolRef::Loaded(&self.const_pool),
+        }
+    }
+
+    /// The constant pool with every entry parsed, for the helpers which need all of it.
+    /// A lazy pool is parsed in full each time this is called.
+    fn full_pool(&self, data: &[u8]) -> Result<Cow<'_, ConstantPool>, LoadError> {
+        match &self.lazy_const_pool {
+            Some(pool) => pool.to_pool(data).map(Cow::Owned),
+            None => Ok(Cow::Borrowed(&self.const_pool)),
+        }
+    }
+
+    /// Get the internal name of the class, see [`ClassFile::this_class_name`]
+    pub fn this_class_name<'a>(&self, data: &'a [u8]) -> Option<Cow<'a, str>> {
+        self.pool().get_class_name(self.this_class, data)
+    }
+
+    /// Get the internal name of the superclass, see [`ClassFile::super_class_name`]
+    pub fn super_class_name<'a>(&self, data: &'a [u8]) -> Option<Cow<'a, str>> {
+        self.pool().get_class_name(self.super_class, data)
+    }
+
+    /// Get the internal names of the interfaces that the class directly implements, see
+    /// [`ClassFile::interface_names`]
+    pub fn interface_names<'a>(&self, data: &'a [u8]) -> Result<Vec<Cow<'a, str>>, LoadError> {
+        interface_names(self.pool(), &self.interfaces, data)
+    }
+
+    /// Whether the class directly implements the interface with the internal name, see
+    /// [`ClassFile::implements`]
+    pub fn implements(&self, data: &[u8], name: &str) -> Result<bool, LoadError> {
+        implements(self.pool(), &self.interfaces, data, name)
+    }
+
+    /// Resolve every field and method reference in the constant pool, see
+    /// [`ClassFile::referenced_members`]
+    pub fn referenced_members<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> Result<Vec<MemberRef<Cow<'a, str>>>, MemberRefError> {
+        self.referenced_members_with(data, Borrowed)
+    }
+
+    /// Resolve every field and method reference in the constant pool, holding their text as the
+    /// mode decides, see [`ClassFile::referenced_members_with`]
+    pub fn referenced_members_with<'a, M: TextMode<'a>>(
+        &self,
+        data: &'a [u8],
+        mode: M,
+    ) -> Result<Vec<MemberRef<M::Text>>, MemberRefError> {
+        let pool = self.full_pool(data).map_err(|err| {
+            // Loading only fails on a malformed entry, see `ConstantPoolOpt::to_pool`
+            let index = match err {
+                LoadError::InvalidIndex(index) => index,
+                _ => 0,
+            };
+            MemberRefError::InvalidIndex(ConstantPoolIndexRaw::new(index))
+        })?;
+        constant_info::referenced_members_with(&pool, data, mode)
+    }
 
     pub fn load_attribute_with_name(
         &self,
@@ -130,35 +736,72 @@ impl ClassFileOpt {
         name: &str,
     ) -> Result<Option<Range<usize>>, LoadError> {
         let input = ParseData::from_pos(data, self.attributes.start_pos);
-        let (_, info) =
-            attributes_search_parser(input, data, &self.const_pool, name, self.attributes.count)
-                .map_err(|_| LoadError::Unknown)?;
+        let pool = self.pool();
+        let (_, info) = attributes_search_by(input, self.attributes.count, |name_index| {
+            pool.is_utf8(name_index, data, name)
+        })?;
 
         let info = info.map(|x| x.1);
 
         Ok(info)
     }
 
-    /// Loads a method at a given index
-    /// Returns the value in cache if there was one
-    /// Returns an owned value if there wasn't, and does not insert into cache
-    pub fn load_method_at(&self, data: &[u8], index: u16) -> Result<Cow<MethodInfo>, LoadError> {
-        if !self.methods.contains_index(index) {
-            return Err(LoadError::Unknown);
-        }
+    /// Find the first class attribute named [`KnownAttribute::NAME`] and parse it.
+    /// This uses the loaded attributes if there are any, and otherwise searches the data without
+    /// loading them.
+    pub fn load_class_attribute<T: KnownAttribute>(
+        &self,
+        data: &[u8],
+    ) -> Result<Option<T>, LoadError> {
+        let pool = self.pool();
+        let is_named =
+            |attr: &AttributeInfo| pool.is_utf8(attr.attribute_name_index, data, T::NAME);
 
-        if let Some(method) = self.methods.get_opt(index) {
-            return Ok(Cow::Borrowed(method));
-        }
+        let found = match self.attributes.data() {
+            Some(attributes) => attributes.iter().find(|attr| is_named(attr)).cloned(),
+            // The info follows the six bytes of the attribute's name index and length
+            None => match self.load_attribute_with_name(data, T::NAME)? {
+                Some(info) => Some(attribute_parser(ParseData::from_pos(data, info.start - 6))?.1),
+                None => None,
+            },
+        };
 
-        let start_pos = self.methods.start_pos();
-        let input = ParseData::from_pos(data, start_pos);
-        let (input, _) = skip_count(skip_method_parser, usize::from(index))(input)
-            .map_err(|_| LoadError::Unknown)?;
+        found.map(|info| T::parse_info(&info, data)).transpose()
+    }
+
+    /// Find the SourceFile attribute, see [`Self::load_class_attribute`]
+    pub fn source_file(&self, data: &[u8]) -> Result<Option<SourceFileAttribute>, LoadError> {
+        self.load_class_attribute(data)
+    }
+
+    /// Find the InnerClasses attribute, see [`Self::load_class_attribute`]
+    pub fn inner_classes(&self, data: &[u8]) -> Result<Option<InnerClassesAttribute>, LoadError> {
+        self.load_class_attribute(data)
+    }
+
+    /// Find the BootstrapMethods attribute, see [`Self::load_class_attribute`]
+    pub fn bootstrap_methods(
+        &self,
+        data: &[u8],
+    ) -> Result<Option<BootstrapMethodsAttribute>, LoadError> {
+        self.load_class_attribute(data)
+    }
 
-        method_parser(input)
-            .map_err(|_| LoadError::Unknown)
-            .map(|x| Cow::Owned(x.1))
+    /// Find the class's generic Signature attribute, see [`Self::load_class_attribute`]
+    pub fn signature(&self, data: &[u8]) -> Result<Option<SignatureAttribute>, LoadError> {
+        self.load_class_attribute(data)
+    }
+
+    /// Loads a method at a given index
+    /// Returns the value in cache if there was one
+    /// Returns an owned value if there wasn't, and does not insert into cache
+    pub fn load_method_at(
+        &self,
+        data: &[u8],
+        index: u16,
+    ) -> Result<Cow<'_, MethodInfo>, LoadError> {
+        self.methods
+            .get_or_parse(index, data, method_parser, skip_method_parser)
     }
 
     /// Loads a method at a given index
@@ -167,22 +810,12 @@ impl ClassFileOpt {
     /// Returns and owned value if there wasn't, and does not insert into cache
     /// It also returns the index of the data directly after it, aka the attributes count
     pub fn load_method_opt_at(&self, data: &[u8], index: u16) -> Result<MethodInfoOpt, LoadError> {
-        if !self.methods.contains_index(index) {
-            return Err(LoadError::Unknown);
-        }
-
         if let Some(method) = self.methods.get_opt(index) {
             return Ok(MethodInfoOpt::from_method_info(method));
         }
 
-        let start_pos = self.methods.start_pos();
-        let input = ParseData::from_pos(data, start_pos);
-        let (input, _) = skip_count(skip_method_parser, usize::from(index))(input)
-            .map_err(|_| LoadError::Unknown)?;
-
-        method_opt_parser(input)
-            .map_err(|_| LoadError::Unknown)
-            .map(|(_, method)| method)
+        self.methods
+            .parse_at(index, data, method_opt_parser, skip_method_parser)
     }
 
     /// This is guaranteed to be in order
@@ -199,6 +832,55 @@ impl ClassFileOpt {
         MethodOptIter::Parse(input)
     }
 
+    /// Find the method with the name and descriptor, see [`ClassFile::find_method`].
+    /// Returns the loaded method if the methods are loaded. Otherwise this skips over the methods
+    /// until it finds a match, which is the only one that gets fully parsed, and isn't cached.
+    pub fn find_method(
+        &self,
+        data: &[u8],
+        name: &str,
+        descriptor: &str,
+    ) -> Result<Option<Cow<'_, MethodInfo>>, LoadError> {
+        let pool = self.pool();
+        if let Some(methods) = self.methods.data() {
+            let method = methods.iter().find(|method| {
+                is_member(
+                    pool,
+                    data,
+                    method.name_index,
+                    method.descriptor_index,
+                    name,
+                    descriptor,
+                )
+            });
+            return Ok(method.map(Cow::Borrowed));
+        }
+
+        // Data that ends before the table can't hold the member
+        if self.methods.start_pos() > data.len() {
+            return Ok(None);
+        }
+        let mut input = ParseData::from_pos(data, self.methods.start_pos());
+        for _ in 0..self.methods.len() {
+            let (rest, method) = method_opt_parser(input.clone())?;
+            if is_member(
+                pool,
+                data,
+                method.name_index,
+                method.descriptor_index,
+                name,
+                descriptor,
+            ) {
+                let (_, method) = method_parser(input)?;
+                return Ok(Some(Cow::Owned(method)));
+            }
+            let (rest, _) = skip_method_attributes_parser(rest, method.attributes_count)?;
+            input = rest;
+        }
+
+        Ok(None)
+    }
+
     /// Does not load all methods if they're already loaded
     pub fn load_all_methods_mut(&mut self, data: &[u8]) -> Result<(), LoadError> {
         if self.methods.has_data() {
@@ -207,45 +889,72 @@ impl ClassFileOpt {
 
         let start_pos = self.methods.start_pos();
         let input = ParseData::from_pos(data, start_pos);
-        let (_, methods) = count_sv(method_parser, usize::from(self.methods.len()))(input)
-            .map_err(|_| LoadError::Unknown)?;
+        let (_, methods) = count_sv(method_parser, usize::from(self.methods.len()))(input)?;
 
         self.methods.fill(methods);
 
         Ok(())
     }
 
+    /// Get the encoded size of each method, in order.
+    /// This uses the loaded methods if there are any, but does not load them otherwise.
+    pub fn method_sizes(&self, data: &[u8]) -> Result<Vec<MethodSize>, LoadError> {
+        let pool = self.full_pool(data)?;
+        if let Some(methods) = self.methods.data() {
+            return methods
+                .iter()
+                .map(|method| method.size(&pool, data))
+                .collect();
+        }
+
+        if self.methods.start_pos() > data.len() {
+            return Err(LoadError::OutOfRange {
+                index: self.methods.start_pos(),
+                len: data.len(),
+            });
+        }
+        let mut input = ParseData::from_pos(data, self.methods.start_pos());
+        let mut sizes = Vec::with_capacity(usize::from(self.methods.len()));
+        for _ in 0..self.methods.len() {
+            let (i, method) = method_parser(input)?;
+            sizes.push(method.size(&pool, data)?);
+            input = i;
+        }
+
+        Ok(sizes)
+    }
+
     /// Loads the method at the given index and tries to find an attribute, if it exists, with the
     /// given name
-    pub fn load_method_attribute_info_at_with_name<'a>(
+    pub fn load_method_attribute_info_at_with_name(
         &self,
-        data: &'a [u8],
+        data: &[u8],
         index: u16,
         name: &str,
     ) -> Result<Option<Range<usize>>, LoadError> {
-        let (attr_info_start, method) = {
-            // TODO: This could do slightly better
-            let start_pos = self.methods.start_pos();
-            let input = ParseData::from_pos(data, start_pos);
-            let (input, _) = skip_count(skip_method_parser, usize::from(index))(input)
-                .map_err(|_| LoadError::Unknown)?;
-
-            method_opt_parser(input)
-                .ok()
-                .map(|(i, method)| (i.pos(), method))
-        }
-        .ok_or(LoadError::Unknown)?;
+        let (attr_info_start, method) = self.methods.parse_at(
+            index,
+            data,
+            |i| method_opt_parser(i).map(|(i, method)| (i.clone(), (i.pos(), method))),
+            skip_method_parser,
+        )?;
         // TODO: make this for more general usage
         let input = ParseData::from_pos(data, attr_info_start);
-        let (_, info) =
-            attributes_search_parser(input, data, &self.const_pool, name, method.attributes_count)
-                .map_err(|_| LoadError::Unknown)?;
+        let pool = self.pool();
+        let (_, info) = attributes_search_by(input, method.attributes_count, |name_index| {
+            pool.is_utf8(name_index, data, name)
+        })?;
         let info = info.map(|x| x.1);
 
         Ok(info)
     }
 
-    // TODO: provide actual error type
+    /// Loads a field at a given index, see [`ClassFileOpt::load_method_at`]
+    pub fn load_field_at(&self, data: &[u8], index: u16) -> Result<Cow<'_, FieldInfo>, LoadError> {
+        self.fields
+            .get_or_parse(index, data, field_parser, skip_field_parser)
+    }
+
     pub fn load_fields_values_iter<'a>(
         &'a self,
         data: &'a [u8],
@@ -256,6 +965,7 @@ impl ClassFileOpt {
         let count = self.fields.count;
         // TODO: use cached data if it exists
 
+        let pool = self.pool();
         let mut p_input = ParseData::from_pos(data, start_pos);
         let mut done = false;
         let mut processed = 0;
@@ -269,12 +979,10 @@ impl ClassFileOpt {
 
             let i = p_input.clone();
 
-            let (i, (field, value_index)) =
-                if let Ok((i, f)) = field_opt_value_parser(i, data, &self.const_pool) {
-                    (i, f)
-                } else {
-                    return Some(Err(LoadError::Unknown));
-                };
+            let (i, (field, value_index)) = match field_opt_value_parser_with(i, data, pool) {
+                Ok((i, f)) => (i, f),
+                Err(err) => return Some(Err(err.into())),
+            };
 
             p_input = i;
             processed += 1;
@@ -282,6 +990,78 @@ impl ClassFileOpt {
             Some(Ok((field, value_index)))
         })
     }
+
+    /// Find the field with the name and descriptor, see [`ClassFile::find_field`].
+    /// Like [`ClassFileOpt::find_method`], this only parses the matching field if the fields
+    /// aren't loaded.
+    pub fn find_field(
+        &self,
+        data: &[u8],
+        name: &str,
+        descriptor: &str,
+    ) -> Result<Option<Cow<'_, FieldInfo>>, LoadError> {
+        let pool = self.pool();
+        if let Some(fields) = self.fields.data() {
+            let field = fields.iter().find(|field| {
+                is_member(
+                    pool,
+                    data,
+                    field.name_index,
+                    field.descriptor_index,
+                    name,
+                    descriptor,
+                )
+            });
+            return Ok(field.map(Cow::Borrowed));
+        }
+
+        // Data that ends before the table can't hold the member
+        if self.fields.start_pos() > data.len() {
+            return Ok(None);
+        }
+        let mut input = ParseData::from_pos(data, self.fields.start_pos());
+        for _ in 0..self.fields.len() {
+            let (rest, field) = field_opt_parser(input.clone())?;
+            if is_member(
+                pool,
+                data,
+                field.name_index,
+                field.descriptor_index,
+                name,
+                descriptor,
+            ) {
+                let (_, field) = field_parser(input)?;
+                return Ok(Some(Cow::Owned(field)));
+            }
+            input = rest;
+        }
+
+        Ok(None)
+    }
+
+    /// Get the compile-time constants of the class: every static final field that has a
+    /// ConstantValue attribute, keyed by the field's name.
+    /// This does not load the fields.
+    pub fn static_final_values(
+        &self,
+        data: &[u8],
+    ) -> Result<HashMap<String, ConstantValue>, LoadError> {
+        let mut values = HashMap::new();
+        for field in self.load_fields_values_iter(data) {
+            let (field, value_index) = field?;
+            if !field.access_flags.contains(STATIC_FINAL) {
+                continue;
+            }
+
+            if let Some(value_index) = value_index {
+                let (name, value) =
+                    resolve_field_value(self.pool(), data, field.name_index, value_index)?;
+                values.insert(name, value);
+            }
+        }
+
+        Ok(values)
+    }
 }
 
 enum MethodOptIter<'a, 'c> {
@@ -325,13 +1105,14 @@ impl<'a, 'c> Iterator for MethodOptIter<'a, 'c> {
     }
 }
 
-/// A small vec that has content that may or may not exist, but includes the position it starts at
+/// A small vec that has content that may or may not exist, but includes the position it starts at.
+/// The content is shared between clones, and is never changed once filled.
 #[derive(Debug, Clone)]
 pub struct OptSmallVec<T, const N: usize> {
     start_pos: usize,
     /// The number of elements that are expected, since most data has this already.
     count: u16,
-    data: Option<SmallVec<[T; N]>>,
+    data: Option<Shared<SmallVec<[T; N]>>>,
 }
 impl<T, const N: usize> OptSmallVec<T, N> {
     pub(crate) fn empty(start_pos: usize, count: u16) -> OptSmallVec<T, N> {
@@ -350,8 +1131,9 @@ impl<T, const N: usize> OptSmallVec<T, N> {
         self.data.as_ref().map(|x| x.as_slice())
     }
 
+    /// Replace the content. Clones made before this keep the old content.
     pub fn fill(&mut self, data: SmallVec<[T; N]>) {
-        self.data = Some(data);
+        self.data = Some(Shared::new(data));
     }
 
     /// The position that the date starts in the file
@@ -365,6 +1147,55 @@ impl<T, const N: usize> OptSmallVec<T, N> {
         self.data.as_ref().and_then(|x| x.get(usize::from(index)))
     }
 
+    /// Get the element at the index, borrowing it if the content is loaded, and otherwise parsing
+    /// it with `parser` after skipping over the elements before it with `skipper`.
+    /// A parsed element is not cached.
+    pub fn get_or_parse<'d, O>(
+        &self,
+        index: u16,
+        data: &'d [u8],
+        parser: impl FnOnce(ParseData<'d>) -> IResult<ParseData<'d>, T>,
+        skipper: impl FnMut(ParseData<'d>) -> IResult<ParseData<'d>, O>,
+    ) -> Result<Cow<'_, T>, LoadError>
+    where
+        T: Clone,
+    {
+        if let Some(item) = self.get_opt(index) {
+            return Ok(Cow::Borrowed(item));
+        }
+
+        self.parse_at(index, data, parser, skipper).map(Cow::Owned)
+    }
+
+    /// Parse the element at the index with `parser`, after skipping over the elements before it
+    /// with `skipper`. This ignores the content even if it is loaded, so the parser can produce a
+    /// different type, such as the Opt version of the element.
+    pub fn parse_at<'d, U, O>(
+        &self,
+        index: u16,
+        data: &'d [u8],
+        parser: impl FnOnce(ParseData<'d>) -> IResult<ParseData<'d>, U>,
+        skipper: impl FnMut(ParseData<'d>) -> IResult<ParseData<'d>, O>,
+    ) -> Result<U, LoadError> {
+        if !self.contains_index(index) {
+            return Err(LoadError::OutOfRange {
+                index: usize::from(index),
+                len: usize::from(self.count),
+            });
+        }
+        if self.start_pos > data.len() {
+            return Err(LoadError::OutOfRange {
+                index: self.start_pos,
+                len: data.len(),
+            });
+        }
+
+        let input = ParseData::from_pos(data, self.start_pos);
+        let (input, _) = skip_count(skipper, usize::from(index))(input)?;
+        let (_, item) = parser(input)?;
+        Ok(item)
+    }
+
     /// Note that this only tells you if it _would_ contain that index
     pub fn contains_index(&self, index: u16) -> bool {
         index < self.count
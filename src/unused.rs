@@ -0,0 +1,186 @@
+//! Finding the private members of a class which nothing in the class refers to.
+//!
+//! Private members can only be used from their own class, or from its nestmates since Java 11,
+//! so a shrinker working one class at a time can remove them once nothing refers to them.
+//! Checking the nestmates, and uses through reflection, are left to the caller.
+
+use std::collections::HashSet;
+
+use crate::attribute_info::{BootstrapMethodsAttribute, CodeAttribute, HasAttributes};
+use crate::constant_info::{ClassConstant, ConstantInfo, NameAndTypeConstant, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::field_info::FieldAccessFlags;
+use crate::instructions::code_iter;
+use crate::method_info::MethodAccessFlags;
+use crate::names::{CLINIT, INIT};
+use crate::{ClassFile, LoadError};
+
+/// The private members which serialization finds by name, and so are used even when nothing
+/// refers to them
+const SERIALIZATION_MEMBERS: &[&str] = &[
+    "serialVersionUID",
+    "serialPersistentFields",
+    "writeObject",
+    "readObject",
+    "readObjectNoData",
+    "writeReplace",
+    "readResolve",
+    "$deserializeLambda$",
+];
+
+/// The private members of a class which nothing in the class refers to, as indices into its
+/// fields and methods
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnusedMembers {
+    pub fields: Vec<usize>,
+    pub methods: Vec<usize>,
+}
+
+/// Collects the name and type of every field and method of this class that is referred to
+struct References<'a> {
+    pool: &'a ConstantPool,
+    data: &'a [u8],
+    this_class: &'a [u8],
+    members: HashSet<(&'a [u8], &'a [u8])>,
+}
+impl<'a> References<'a> {
+    fn text(&self, index: ConstantPoolIndexRaw<Utf8Constant>) -> Option<&'a [u8]> {
+        Some(self.pool.get_t(index)?.as_bytes(self.data))
+    }
+
+    fn add_member(
+        &mut self,
+        class_index: ConstantPoolIndexRaw<ClassConstant>,
+        name_and_type_index: ConstantPoolIndexRaw<NameAndTypeConstant>,
+    ) -> Result<(), LoadError> {
+        let class = self.pool.get_t(class_index).ok_or(LoadError::Unknown)?;
+        if self.text(class.name_index).ok_or(LoadError::Unknown)? != self.this_class {
+            return Ok(());
+        }
+
+        let name_and_type = self
+            .pool
+            .get_t(name_and_type_index)
+            .ok_or(LoadError::Unknown)?;
+        let name = self
+            .text(name_and_type.name_index)
+            .ok_or(LoadError::Unknown)?;
+        let descriptor = self
+            .text(name_and_type.descriptor_index)
+            .ok_or(LoadError::Unknown)?;
+        self.members.insert((name, descriptor));
+        Ok(())
+    }
+
+    /// Add the member that the constant refers to, if any
+    fn add(&mut self, index: ConstantPoolIndexRaw<ConstantInfo>) -> Result<(), LoadError> {
+        match self.pool.get(index).ok_or(LoadError::Unknown)? {
+            // A method handle can only refer to a member reference, so it isn't followed further
+            ConstantInfo::MethodHandle(handle) => {
+                let reference = self
+                    .pool
+                    .get(handle.reference_index)
+                    .ok_or(LoadError::Unknown)?;
+                self.add_member_ref(reference)
+            }
+            entry => self.add_member_ref(entry),
+        }
+    }
+
+    /// Add the member that the constant refers to, if it is a member reference
+    fn add_member_ref(&mut self, entry: &ConstantInfo) -> Result<(), LoadError> {
+        match entry {
+            ConstantInfo::FieldRef(field) => {
+                self.add_member(field.class_index, field.name_and_type_index)
+            }
+            ConstantInfo::MethodRef(method) => {
+                self.add_member(method.class_index, method.name_and_type_index)
+            }
+            ConstantInfo::InterfaceMethodRef(method) => {
+                self.add_member(method.class_index, method.name_and_type_index)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn add_code(&mut self, code: &CodeAttribute) -> Result<(), LoadError> {
+        let code = self.data.get(code.code.clone()).ok_or(LoadError::Unknown)?;
+        for result in code_iter(code) {
+            let (_, instruction) = result.map_err(|_| LoadError::Unknown)?;
+            if let Some(index) = instruction.constant_index() {
+                self.add(ConstantPoolIndexRaw::new(index))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_serialization_member(name: &[u8]) -> bool {
+    SERIALIZATION_MEMBERS
+        .iter()
+        .any(|member| member.as_bytes() == name)
+}
+
+impl ClassFile {
+    /// Find the private fields and methods which aren't referred to by any code in the class or
+    /// by its bootstrap methods, such as those of lambdas.
+    /// The members used by serialization, and constructors, are never reported.
+    /// Errors if the constant pool, the Code attributes, or the BootstrapMethods attribute are
+    /// malformed.
+    pub fn unused_private_members(&self, data: &[u8]) -> Result<UnusedMembers, LoadError> {
+        let pool = &self.const_pool;
+        let this_class = pool.get_t(self.this_class).ok_or(LoadError::Unknown)?;
+        let mut references = References {
+            pool,
+            data,
+            this_class: &[],
+            members: HashSet::new(),
+        };
+        references.this_class = references
+            .text(this_class.name_index)
+            .ok_or(LoadError::Unknown)?;
+
+        for method in self.methods.iter() {
+            if let Some(code) = method.find_attribute::<CodeAttribute>(pool, data)? {
+                references.add_code(&code)?;
+            }
+        }
+        if let Some(attr) = self.find_attribute::<BootstrapMethodsAttribute>(pool, data)? {
+            for method in attr.bootstrap_methods.iter() {
+                references.add(method.bootstrap_method_ref.into_generic())?;
+                for &argument in method.bootstrap_arguments.iter() {
+                    references.add(argument)?;
+                }
+            }
+        }
+
+        let is_unused = |name_index, descriptor_index| -> Result<bool, LoadError> {
+            let name = references.text(name_index).ok_or(LoadError::Unknown)?;
+            let descriptor = references
+                .text(descriptor_index)
+                .ok_or(LoadError::Unknown)?;
+            Ok(!is_serialization_member(name) && !references.members.contains(&(name, descriptor)))
+        };
+
+        let mut unused = UnusedMembers::default();
+        for (i, field) in self.fields.iter().enumerate() {
+            if field.access_flags.contains(FieldAccessFlags::PRIVATE)
+                && is_unused(field.name_index, field.descriptor_index)?
+            {
+                unused.fields.push(i);
+            }
+        }
+        for (i, method) in self.methods.iter().enumerate() {
+            // Private constructors are often only there to stop instantiation
+            let name = references.text(method.name_index);
+            let is_initializer = name == Some(INIT.as_bytes()) || name == Some(CLINIT.as_bytes());
+            if method.access_flags.contains(MethodAccessFlags::PRIVATE)
+                && !is_initializer
+                && is_unused(method.name_index, method.descriptor_index)?
+            {
+                unused.methods.push(i);
+            }
+        }
+        Ok(unused)
+    }
+}
@@ -1,5 +1,4 @@
 use nom::{number::complete::be_u16, IResult};
-use smallvec::SmallVec;
 
 use crate::{constant_pool::ConstantPoolIndexRaw, parser::ParseData};
 
@@ -65,75 +64,54 @@ pub(crate) fn constant_pool_index_raw<T>(
     Ok((i, ConstantPoolIndexRaw::new(v)))
 }
 
-/// Count but for small vec
-pub(crate) fn count_sv<I, O, E, F, const N: usize>(
-    mut f: F,
-    count: usize,
-) -> impl FnMut(I) -> IResult<I, SmallVec<[O; N]>, E>
-where
-    I: Clone + PartialEq,
-    F: nom::Parser<I, O, E>,
-    E: nom::error::ParseError<I>,
-{
-    move |i: I| {
-        let mut input = i.clone();
-        let mut res = SmallVec::with_capacity(count);
-
-        for _ in 0..count {
-            let input_ = input.clone();
-            match f.parse(input_) {
-                Ok((i, o)) => {
-                    res.push(o);
-                    input = i;
-                }
-                Err(nom::Err::Error(e)) => {
-                    return Err(nom::Err::Error(E::append(
-                        i,
-                        nom::error::ErrorKind::Count,
-                        e,
-                    )));
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
+/// Access flags which are stored next to the raw value they were parsed from, so that the bits
+/// which have no defined meaning are kept
+pub(crate) trait AccessFlags: Copy {
+    /// Every flag with a defined meaning
+    const DEFINED: u16;
 
-        Ok((input, res))
+    fn defined_bits(self) -> u16;
+
+    /// Combine the defined flags with the undefined bits of the raw value
+    fn with_undefined(self, raw: u16) -> u16 {
+        self.defined_bits() | (raw & !Self::DEFINED)
     }
 }
 
-/// Drops any parsed values
-/// Note that you should probably be using custom versions of any complex functions since they might
-/// not get optimized out properly
-pub(crate) fn skip_count<I, O, E, F>(mut f: F, count: usize) -> impl FnMut(I) -> IResult<I, (), E>
-where
-    I: Clone + PartialEq,
-    F: nom::Parser<I, O, E>,
-    E: nom::error::ParseError<I>,
-{
-    move |i: I| {
-        let mut input = i.clone();
-
-        for _ in 0..count {
-            let input_ = input.clone();
-            match f.parse(input_) {
-                Ok((i, _)) => {
-                    input = i;
-                }
-                Err(nom::Err::Error(e)) => {
-                    return Err(nom::Err::Error(E::append(
-                        i,
-                        nom::error::ErrorKind::Count,
-                        e,
-                    )));
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
-        }
+/// The pointer that parsed content is shared between clones behind.
+/// With the `threading` feature this is an `Arc`, so that the clones can be sent to other threads.
+#[cfg(feature = "threading")]
+pub(crate) type Shared<T> = std::sync::Arc<T>;
+#[cfg(not(feature = "threading"))]
+pub(crate) type Shared<T> = std::rc::Rc<T>;
+
+/// Reads big endian values, returning None when there aren't enough bytes left
+pub(crate) struct Reader<'a> {
+    pub bytes: &'a [u8],
+    pub pos: usize,
+}
+impl<'a> Reader<'a> {
+    pub fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        let bytes = self.take(2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn i16(&mut self) -> Option<i16> {
+        self.u16().map(|v| v as i16)
+    }
 
-        Ok((input, ()))
+    pub fn i32(&mut self) -> Option<i32> {
+        let bytes = self.take(4)?;
+        Some(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
     }
 }
@@ -0,0 +1,603 @@
+//! Checks for inconsistencies between the parts of a class file, which parse fine but which the
+//! JVM rejects when it loads or links the class.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::attribute_info::{
+    AttributeOwner, BootstrapMethodsAttribute, CodeAttribute, HasAttributes,
+};
+use crate::constant_info::{ClassConstant, ConstantInfo};
+use crate::constant_pool::ConstantPoolIndexRaw;
+use crate::field_info::FieldAccessFlags;
+use crate::instructions::{code_iter, InstructionError};
+use crate::method_info::MethodAccessFlags;
+use crate::names;
+use crate::provider::ClassProvider;
+use crate::{ClassAccessFlags, ClassFile, LoadError};
+
+/// An InvokeDynamic or Dynamic constant whose bootstrap method does not exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BootstrapError {
+    /// The class has no BootstrapMethods attribute for the constant to refer to
+    MissingAttribute {
+        constant: ConstantPoolIndexRaw<ConstantInfo>,
+    },
+    /// The index is past the end of the BootstrapMethods attribute, which has `count` methods
+    IndexOutOfRange {
+        constant: ConstantPoolIndexRaw<ConstantInfo>,
+        bootstrap_method_attr_index: u16,
+        count: u16,
+    },
+}
+
+impl ClassFile {
+    /// Find the InvokeDynamic and Dynamic constants which refer to a bootstrap method that isn't
+    /// in the class's BootstrapMethods attribute.
+    /// Errors if the BootstrapMethods attribute can't be parsed.
+    pub fn bootstrap_errors(&self, data: &[u8]) -> Result<Vec<BootstrapError>, LoadError> {
+        let pool = &self.const_pool;
+        let count = self
+            .find_attribute::<BootstrapMethodsAttribute>(pool, data)?
+            .map(|attr| attr.bootstrap_methods.len());
+
+        let mut errors = Vec::new();
+        for (constant, entry) in pool.iter_indexed() {
+            let index = match entry {
+                ConstantInfo::InvokeDynamic(c) => c.bootstrap_method_attr_index,
+                ConstantInfo::Dynamic(c) => c.bootstrap_method_attr_index,
+                _ => continue,
+            };
+            match count {
+                None => errors.push(BootstrapError::MissingAttribute { constant }),
+                Some(count) if usize::from(index) >= count => {
+                    errors.push(BootstrapError::IndexOutOfRange {
+                        constant,
+                        bootstrap_method_attr_index: index,
+                        count: count as u16,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        Ok(errors)
+    }
+}
+
+/// A rule about which access flags can be combined, which depends on the kind of the owner.
+/// [see more](https://docs.oracle.com/javase/specs/jvms/se17/html/jvms-4.html#jvms-4.1-200-E.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessFlagRule {
+    /// An interface must be abstract, and can't be final, an enum, or a module
+    InterfaceClass,
+    /// Only interfaces can be annotation types
+    AnnotationNotInterface,
+    /// A class can't be both final and abstract
+    FinalAbstractClass,
+    /// A member can have at most one of public, private, and protected
+    MultipleVisibility,
+    /// A field can't be both final and volatile
+    FinalVolatileField,
+    /// An interface field must be public, static, and final, and nothing else but synthetic
+    InterfaceField,
+    /// Before Java 8 an interface method must be public and abstract. From Java 8 it must be
+    /// exactly one of public or private, and can't be protected, final, synchronized, or native.
+    InterfaceMethod,
+    /// An abstract method can't be private, static, final, synchronized, native, or strict
+    AbstractMethod,
+    /// A constructor can only be public, private, protected, varargs, strict, or synthetic
+    Constructor,
+}
+
+/// Access flags which break one of the rules for their owner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessFlagViolation {
+    /// The class, or the index of the field or method
+    pub owner: AttributeOwner,
+    pub rule: AccessFlagRule,
+    /// The raw access flags of the owner
+    pub flags: u16,
+}
+
+/// Whether more than one of the flags are set
+fn multiple(flags: u16, exclusive: u16) -> bool {
+    (flags & exclusive).count_ones() > 1
+}
+
+impl ClassFile {
+    /// Check the access flags of the class, its fields, and its methods against the rules for
+    /// their kind of owner, such as interface members needing to be public.
+    /// The `<clinit>` method is skipped, since the JVM ignores most of its flags.
+    pub fn access_flag_violations(&self, data: &[u8]) -> Vec<AccessFlagViolation> {
+        let mut violations = Vec::new();
+        let mut check = |owner: AttributeOwner, flags: u16, rule: AccessFlagRule, broken: bool| {
+            if broken {
+                violations.push(AccessFlagViolation { owner, rule, flags });
+            }
+        };
+        let major = self.version.major;
+
+        let class = self.access_flags;
+        let is_interface = class.contains(ClassAccessFlags::INTERFACE);
+        let flags = self.raw_flags();
+        if is_interface {
+            let disallowed = ClassAccessFlags::FINAL
+                | ClassAccessFlags::SUPER
+                | ClassAccessFlags::ENUM
+                | ClassAccessFlags::MODULE;
+            let broken =
+                !class.contains(ClassAccessFlags::ABSTRACT) || class.intersects(disallowed);
+            check(
+                AttributeOwner::Class,
+                flags,
+                AccessFlagRule::InterfaceClass,
+                broken,
+            );
+        } else {
+            check(
+                AttributeOwner::Class,
+                flags,
+                AccessFlagRule::AnnotationNotInterface,
+                class.contains(ClassAccessFlags::ANNOTATION),
+            );
+            check(
+                AttributeOwner::Class,
+                flags,
+                AccessFlagRule::FinalAbstractClass,
+                class.contains(ClassAccessFlags::FINAL | ClassAccessFlags::ABSTRACT),
+            );
+        }
+
+        let visibility =
+            FieldAccessFlags::PUBLIC | FieldAccessFlags::PRIVATE | FieldAccessFlags::PROTECTED;
+        for (i, field) in self.fields.iter().enumerate() {
+            let owner = AttributeOwner::Field(i);
+            let access = field.access_flags;
+            let flags = field.raw_flags();
+            if is_interface {
+                let required =
+                    FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL;
+                let broken = !access.contains(required)
+                    || !(required | FieldAccessFlags::SYNTHETIC).contains(access);
+                check(owner, flags, AccessFlagRule::InterfaceField, broken);
+            } else {
+                check(
+                    owner,
+                    flags,
+                    AccessFlagRule::MultipleVisibility,
+                    multiple(access.bits(), visibility.bits()),
+                );
+                check(
+                    owner,
+                    flags,
+                    AccessFlagRule::FinalVolatileField,
+                    access.contains(FieldAccessFlags::FINAL | FieldAccessFlags::VOLATILE),
+                );
+            }
+        }
+
+        let visibility =
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::PRIVATE | MethodAccessFlags::PROTECTED;
+        for (i, method) in self.methods.iter().enumerate() {
+            let name = self
+                .const_pool
+                .get_t(method.name_index)
+                .map(|name| name.as_text(data));
+            let name = name.as_deref();
+            if name.is_some_and(names::is_static_initializer) {
+                continue;
+            }
+
+            let owner = AttributeOwner::Method(i);
+            let access = method.access_flags;
+            let flags = method.raw_flags();
+            if name.is_some_and(names::is_constructor) {
+                let allowed = visibility
+                    | MethodAccessFlags::VARARGS
+                    | MethodAccessFlags::STRICT
+                    | MethodAccessFlags::SYNTHETIC;
+                check(
+                    owner,
+                    flags,
+                    AccessFlagRule::Constructor,
+                    !allowed.contains(access),
+                );
+            }
+
+            if is_interface {
+                let broken = if major < 52 {
+                    let allowed = MethodAccessFlags::PUBLIC
+                        | MethodAccessFlags::ABSTRACT
+                        | MethodAccessFlags::VARARGS
+                        | MethodAccessFlags::BRIDGE
+                        | MethodAccessFlags::SYNTHETIC;
+                    !access.contains(MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT)
+                        || !allowed.contains(access)
+                } else {
+                    let disallowed = MethodAccessFlags::PROTECTED
+                        | MethodAccessFlags::FINAL
+                        | MethodAccessFlags::SYNCHRONIZED
+                        | MethodAccessFlags::NATIVE;
+                    let public_or_private = MethodAccessFlags::PUBLIC | MethodAccessFlags::PRIVATE;
+                    (access & public_or_private).bits().count_ones() != 1
+                        || access.intersects(disallowed)
+                };
+                check(owner, flags, AccessFlagRule::InterfaceMethod, broken);
+            } else {
+                check(
+                    owner,
+                    flags,
+                    AccessFlagRule::MultipleVisibility,
+                    multiple(access.bits(), visibility.bits()),
+                );
+            }
+
+            if access.contains(MethodAccessFlags::ABSTRACT) {
+                let mut disallowed = MethodAccessFlags::PRIVATE
+                    | MethodAccessFlags::STATIC
+                    | MethodAccessFlags::FINAL
+                    | MethodAccessFlags::SYNCHRONIZED
+                    | MethodAccessFlags::NATIVE;
+                // ACC_STRICT only has a meaning from Java 1.2 until Java 17 made it the default
+                if (46..61).contains(&major) {
+                    disallowed |= MethodAccessFlags::STRICT;
+                }
+                check(
+                    owner,
+                    flags,
+                    AccessFlagRule::AbstractMethod,
+                    access.intersects(disallowed),
+                );
+            }
+        }
+
+        violations
+    }
+}
+
+/// A problem with the structure of a method's code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeErrorKind {
+    /// The code is empty
+    Empty,
+    /// The code is longer than the 65535 bytes allowed
+    TooLong,
+    InvalidOpcode(u8),
+    /// The instruction runs past the end of the code
+    Truncated,
+    /// A tableswitch whose high is below its low, or a lookupswitch with a negative count or
+    /// unsorted keys
+    InvalidSwitch,
+    /// A branch or switch target which isn't the start of an instruction in the code
+    BadTarget(i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeError {
+    /// The index of the method
+    pub method: usize,
+    /// The offset of the instruction in the code, which is zero for errors about the whole code
+    pub offset: usize,
+    pub kind: CodeErrorKind,
+}
+
+impl ClassFile {
+    /// Check that the code of each method decodes into instructions which end exactly at the end
+    /// of the code, and that every branch and switch lands on the start of one of them.
+    /// This is a cheap structural check, not the full verifier.
+    /// Decoding a method's code stops at the first instruction which can't be decoded.
+    /// Errors if a Code attribute can't be parsed.
+    pub fn code_errors(&self, data: &[u8]) -> Result<Vec<CodeError>, LoadError> {
+        let mut errors = Vec::new();
+        for (method, info) in self.methods.iter().enumerate() {
+            let code = match info.find_attribute::<CodeAttribute>(&self.const_pool, data)? {
+                Some(code) => code,
+                None => continue,
+            };
+            let mut error = |offset, kind| {
+                errors.push(CodeError {
+                    method,
+                    offset,
+                    kind,
+                })
+            };
+            let code = data.get(code.code).ok_or(LoadError::Unknown)?;
+            if code.is_empty() {
+                error(0, CodeErrorKind::Empty);
+            } else if code.len() > usize::from(u16::MAX) {
+                error(0, CodeErrorKind::TooLong);
+            }
+
+            let mut instructions = Vec::new();
+            for result in code_iter(code) {
+                match result {
+                    Ok((offset, instruction)) => instructions.push((offset, instruction)),
+                    Err(err) => {
+                        let (offset, kind) = match err {
+                            InstructionError::Truncated { offset } => {
+                                (offset, CodeErrorKind::Truncated)
+                            }
+                            InstructionError::InvalidOpcode { offset, opcode }
+                            | InstructionError::InvalidWide { offset, opcode } => {
+                                (offset, CodeErrorKind::InvalidOpcode(opcode))
+                            }
+                            InstructionError::InvalidSwitch { offset } => {
+                                (offset, CodeErrorKind::InvalidSwitch)
+                            }
+                            // Already reported as the code being too long
+                            InstructionError::CodeTooLong { .. } => break,
+                        };
+                        error(offset, kind);
+                    }
+                }
+            }
+
+            let starts: HashSet<i64> = instructions
+                .iter()
+                .map(|&(offset, _)| i64::from(offset))
+                .collect();
+            for (offset, instruction) in instructions.iter() {
+                for relative in instruction.branch_targets() {
+                    let target = i64::from(*offset) + i64::from(relative);
+                    if !starts.contains(&target) {
+                        error(usize::from(*offset), CodeErrorKind::BadTarget(target));
+                    }
+                }
+            }
+        }
+        Ok(errors)
+    }
+}
+
+/// A problem with an entry in the exception table of a method's code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceptionTableErrorKind {
+    /// The start_pc isn't before the end_pc, so the handler covers nothing
+    EmptyRange,
+    /// The end_pc is past the end of the code
+    EndPastCode,
+    /// The handler_pc isn't inside the code
+    HandlerPastCode,
+    /// The catch_type is neither zero nor a Class constant
+    CatchTypeNotClass,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceptionTableError {
+    /// The index of the method
+    pub method: usize,
+    /// The index of the entry in the exception table
+    pub entry: usize,
+    pub kind: ExceptionTableErrorKind,
+}
+
+impl ClassFile {
+    /// Check the ranges, handlers, and catch types of every exception table entry, reporting
+    /// each problem with each entry.
+    /// This doesn't check that the offsets are the starts of instructions.
+    /// Errors if a Code attribute can't be parsed.
+    pub fn exception_table_errors(
+        &self,
+        data: &[u8],
+    ) -> Result<Vec<ExceptionTableError>, LoadError> {
+        let pool = &self.const_pool;
+        let mut errors = Vec::new();
+        for (method, info) in self.methods.iter().enumerate() {
+            let code = match info.find_attribute::<CodeAttribute>(pool, data)? {
+                Some(code) => code,
+                None => continue,
+            };
+            let code_length = code.code.len();
+            for (entry, exception) in code.exception_table.iter().enumerate() {
+                let mut error = |kind| {
+                    errors.push(ExceptionTableError {
+                        method,
+                        entry,
+                        kind,
+                    })
+                };
+                let start = usize::from(exception.start_pc.0);
+                let end = usize::from(exception.end_pc.0);
+                if start >= end {
+                    error(ExceptionTableErrorKind::EmptyRange);
+                }
+                if end > code_length {
+                    error(ExceptionTableErrorKind::EndPastCode);
+                }
+                if usize::from(exception.handler_pc.0) >= code_length {
+                    error(ExceptionTableErrorKind::HandlerPastCode);
+                }
+                let catch_type = exception.catch_type;
+                if !catch_type.is_zero() && pool.get_t(catch_type).is_none() {
+                    error(ExceptionTableErrorKind::CatchTypeNotClass);
+                }
+            }
+        }
+        Ok(errors)
+    }
+}
+
+/// Why the catch_type of an exception handler isn't known to be a Throwable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatchTypeErrorKind {
+    /// The class isn't `java/lang/Throwable` or a subclass of it
+    NotThrowable,
+    /// The named class, which is the catch type or one of its superclasses, isn't in the provider,
+    /// so whether the catch type is a Throwable is unknown
+    MissingClass(String),
+    /// The super_class of the named class is neither zero nor a Class constant with a name
+    Malformed(String),
+    /// The superclasses loop back around to a class that was already seen
+    CircularHierarchy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchTypeError {
+    /// The index of the method
+    pub method: usize,
+    /// The index of the entry in the exception table
+    pub entry: usize,
+    /// The internal name of the class that the handler catches
+    pub catch_type: String,
+    pub kind: CatchTypeErrorKind,
+}
+
+/// The internal name of the superclass, which is None if the class has none.
+/// Returns None if the super_class isn't a Class constant with a name.
+fn super_class_name(class_file: &ClassFile, data: &[u8]) -> Option<Option<String>> {
+    if class_file.super_class.is_zero() {
+        return Some(None);
+    }
+    let name = class_file
+        .const_pool
+        .get_class_name(class_file.super_class, data)?;
+    Some(Some(name.into_owned()))
+}
+
+impl ClassFile {
+    /// Check that the catch_type of every exception handler is `java/lang/Throwable` or a
+    /// subclass of it, as the verifier requires, by walking up its superclasses.
+    /// The superclasses are loaded from the provider, apart from this class itself.
+    /// Catch types which aren't Class constants are left to
+    /// [`ClassFile::exception_table_errors`].
+    /// Errors if a Code attribute can't be parsed.
+    pub fn catch_type_errors(
+        &self,
+        data: &[u8],
+        provider: &dyn ClassProvider,
+    ) -> Result<Vec<CatchTypeError>, LoadError> {
+        let pool = &self.const_pool;
+        let this_name = pool.get_class_name(self.this_class, data);
+        let mut checked = HashMap::new();
+        let mut errors = Vec::new();
+        for (method, info) in self.methods.iter().enumerate() {
+            let code = match info.find_attribute::<CodeAttribute>(pool, data)? {
+                Some(code) => code,
+                None => continue,
+            };
+            for (entry, exception) in code.exception_table.iter().enumerate() {
+                if exception.catch_type.is_zero() {
+                    continue;
+                }
+                let catch_type = match pool.get_class_name(exception.catch_type, data) {
+                    Some(name) => name.into_owned(),
+                    None => continue,
+                };
+                let kind = checked
+                    .entry(catch_type.clone())
+                    .or_insert_with(|| {
+                        self.throwable_error(data, this_name.as_deref(), provider, &catch_type)
+                    })
+                    .clone();
+                if let Some(kind) = kind {
+                    errors.push(CatchTypeError {
+                        method,
+                        entry,
+                        catch_type,
+                        kind,
+                    });
+                }
+            }
+        }
+        Ok(errors)
+    }
+
+    /// Why the class with the name isn't known to be a Throwable, or None if it is one
+    fn throwable_error(
+        &self,
+        data: &[u8],
+        this_name: Option<&str>,
+        provider: &dyn ClassProvider,
+        name: &str,
+    ) -> Option<CatchTypeErrorKind> {
+        let mut seen = HashSet::new();
+        let mut current = name.to_string();
+        loop {
+            if current == names::THROWABLE {
+                return None;
+            }
+            // Array classes are never Throwables
+            if names::is_object(&current) || current.starts_with('[') {
+                return Some(CatchTypeErrorKind::NotThrowable);
+            }
+            if !seen.insert(current.clone()) {
+                return Some(CatchTypeErrorKind::CircularHierarchy);
+            }
+
+            let super_name = if this_name == Some(current.as_str()) {
+                super_class_name(self, data)
+            } else {
+                match provider.load_class(&current) {
+                    Some(class) => super_class_name(&class.class_file, &class.data),
+                    None => return Some(CatchTypeErrorKind::MissingClass(current)),
+                }
+            };
+            match super_name {
+                Some(Some(super_name)) => current = super_name,
+                Some(None) => return Some(CatchTypeErrorKind::NotThrowable),
+                None => return Some(CatchTypeErrorKind::Malformed(current)),
+            }
+        }
+    }
+}
+
+/// A problem with the this_class or super_class of a class
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassNameError {
+    /// The this_class isn't a Class constant with a name
+    ThisClassNotClass,
+    /// The name of the class isn't the one it was expected to have, such as the one implied by
+    /// its path in an archive
+    NameMismatch { expected: String, found: String },
+    /// The super_class is neither zero nor a Class constant with a name
+    SuperClassNotClass,
+    /// The super_class is zero, which only `java/lang/Object` and modules may have
+    MissingSuperClass,
+    /// `java/lang/Object` or a module has a superclass
+    UnexpectedSuperClass,
+}
+
+impl ClassFile {
+    /// Check that this_class and super_class are Class constants, that the name of the class is
+    /// `expected_name` if there is one, and that only `java/lang/Object` and modules have no
+    /// superclass.
+    /// The expected name is an internal name, like `java/lang/String`.
+    pub fn class_name_errors(
+        &self,
+        data: &[u8],
+        expected_name: Option<&str>,
+    ) -> Vec<ClassNameError> {
+        let pool = &self.const_pool;
+        let name_of = |index| {
+            pool.get_t(index)
+                .and_then(|class: &ClassConstant| pool.get_t(class.name_index))
+                .map(|name| name.as_text(data))
+        };
+
+        let mut errors = Vec::new();
+        let name = name_of(self.this_class);
+        match (&name, expected_name) {
+            (None, _) => errors.push(ClassNameError::ThisClassNotClass),
+            (Some(name), Some(expected)) if name != expected => {
+                errors.push(ClassNameError::NameMismatch {
+                    expected: expected.to_string(),
+                    found: name.to_string(),
+                })
+            }
+            _ => {}
+        }
+
+        let may_lack_super = self.is_module() || name.as_deref().is_some_and(names::is_object);
+        if self.super_class.is_zero() {
+            if !may_lack_super {
+                errors.push(ClassNameError::MissingSuperClass);
+            }
+        } else if name_of(self.super_class).is_none() {
+            errors.push(ClassNameError::SuperClassNotClass);
+        } else if may_lack_super {
+            errors.push(ClassNameError::UnexpectedSuperClass);
+        }
+
+        errors
+    }
+}
@@ -0,0 +1,418 @@
+//! Writing a class file back out as bytes.
+//!
+//! The constant pool's text and the info of attributes are ranges of the class file data, so
+//! writing needs the data that the class file was parsed from, including anything that edits
+//! appended to it. Attributes are written as their raw info, so a class file which was parsed and
+//! not changed is written out exactly as it was read.
+//!
+//! [`WriteOptions`] can sort the constant pool, members, and attributes, so that classes built
+//! from unordered data are written as the same bytes every time.
+
+use std::io::{self, Write};
+
+use crate::attribute_info::AttributeInfo;
+use crate::constant_info::{ConstantInfo, Utf8Constant};
+use crate::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use crate::remap::{IndexRemap, RemapError};
+use crate::ClassFile;
+
+#[derive(Debug)]
+pub enum WriteError {
+    Io(io::Error),
+    /// The pool has more entries than a class file can hold
+    PoolTooLarge,
+    /// The text of the Utf8 entry at the raw index is longer than 65535 bytes
+    Utf8TooLong(u16),
+    /// There are more interfaces, fields, methods, or attributes than fit in their count
+    TooManyItems,
+    /// The info of an attribute is longer than its u32 length allows
+    AttributeTooLong,
+    /// The info of an attribute is not within the class file data
+    OutOfBounds,
+    /// The constant pool could not be sorted, see [`WriteOptions::sort_constants`]
+    Remap(RemapError),
+}
+impl From<io::Error> for WriteError {
+    fn from(err: io::Error) -> Self {
+        WriteError::Io(err)
+    }
+}
+impl From<RemapError> for WriteError {
+    fn from(err: RemapError) -> Self {
+        WriteError::Remap(err)
+    }
+}
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::Io(err) => write!(f, "io error: {}", err),
+            WriteError::PoolTooLarge => f.write_str("the constant pool has too many entries"),
+            WriteError::Utf8TooLong(index) => {
+                write!(f, "the text of constant pool entry #{} is too long", index)
+            }
+            WriteError::TooManyItems => f.write_str("a table has too many entries for its count"),
+            WriteError::AttributeTooLong => f.write_str("an attribute is too long"),
+            WriteError::OutOfBounds => f.write_str("an attribute is outside of the class data"),
+            WriteError::Remap(err) => write!(f, "failed to sort the constant pool: {:?}", err),
+        }
+    }
+}
+impl std::error::Error for WriteError {}
+
+/// How the class file is arranged as it is written
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Sort the entries of the constant pool by their contents, rewriting every reference to them
+    /// as [`IndexRemap::apply`] does.
+    /// Fails with [`WriteError::Remap`] if there are attributes whose layout is not known.
+    pub sort_constants: bool,
+    /// Sort the fields and methods by their name and then their descriptor
+    pub sort_members: bool,
+    /// Sort the attributes of the class, fields, and methods by their name.
+    /// Attributes with the same name keep their order, as do the attributes nested in Code.
+    pub sort_attributes: bool,
+}
+impl WriteOptions {
+    /// Options which sort everything, so that the same class is always written as the same bytes
+    /// no matter the order it was built in
+    pub fn deterministic() -> WriteOptions {
+        WriteOptions {
+            sort_constants: true,
+            sort_members: true,
+            sort_attributes: true,
+        }
+    }
+}
+
+pub(crate) fn count(len: usize) -> Result<[u8; 2], WriteError> {
+    u16::try_from(len)
+        .map(u16::to_be_bytes)
+        .map_err(|_| WriteError::TooManyItems)
+}
+
+/// Write the entry in its class file form, starting with its tag.
+/// Unusable entries take up no bytes, since they only stand for the second slot of the Long or
+/// Double before them.
+pub fn write_constant(
+    entry: &ConstantInfo,
+    raw_index: u16,
+    class_file_data: &[u8],
+    out: &mut Vec<u8>,
+) -> Result<(), WriteError> {
+    match entry {
+        ConstantInfo::Utf8(text) => {
+            let bytes = text.as_bytes(class_file_data);
+            let len = u16::try_from(bytes.len()).map_err(|_| WriteError::Utf8TooLong(raw_index))?;
+            out.push(1);
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(bytes);
+        }
+        ConstantInfo::Integer(c) => {
+            out.push(3);
+            out.extend_from_slice(&c.value.to_be_bytes());
+        }
+        ConstantInfo::Float(c) => {
+            out.push(4);
+            out.extend_from_slice(&c.value.to_bits().to_be_bytes());
+        }
+        ConstantInfo::Long(c) => {
+            out.push(5);
+            out.extend_from_slice(&c.value.to_be_bytes());
+        }
+        ConstantInfo::Double(c) => {
+            out.push(6);
+            out.extend_from_slice(&c.value.to_bits().to_be_bytes());
+        }
+        ConstantInfo::Class(c) => {
+            out.push(7);
+            out.extend_from_slice(&c.name_index.0.to_be_bytes());
+        }
+        ConstantInfo::String(c) => {
+            out.push(8);
+            out.extend_from_slice(&c.string_index.0.to_be_bytes());
+        }
+        ConstantInfo::FieldRef(c) => {
+            out.push(9);
+            out.extend_from_slice(&c.class_index.0.to_be_bytes());
+            out.extend_from_slice(&c.name_and_type_index.0.to_be_bytes());
+        }
+        ConstantInfo::MethodRef(c) => {
+            out.push(10);
+            out.extend_from_slice(&c.class_index.0.to_be_bytes());
+            out.extend_from_slice(&c.name_and_type_index.0.to_be_bytes());
+        }
+        ConstantInfo::InterfaceMethodRef(c) => {
+            out.push(11);
+            out.extend_from_slice(&c.class_index.0.to_be_bytes());
+            out.extend_from_slice(&c.name_and_type_index.0.to_be_bytes());
+        }
+        ConstantInfo::NameAndType(c) => {
+            out.push(12);
+            out.extend_from_slice(&c.name_index.0.to_be_bytes());
+            out.extend_from_slice(&c.descriptor_index.0.to_be_bytes());
+        }
+        ConstantInfo::MethodHandle(c) => {
+            out.push(15);
+            out.push(c.reference_kind);
+            out.extend_from_slice(&c.reference_index.0.to_be_bytes());
+        }
+        ConstantInfo::MethodType(c) => {
+            out.push(16);
+            out.extend_from_slice(&c.descriptor_index.0.to_be_bytes());
+        }
+        ConstantInfo::Dynamic(c) => {
+            out.push(17);
+            out.extend_from_slice(&c.bootstrap_method_attr_index.to_be_bytes());
+            out.extend_from_slice(&c.name_and_type_index.0.to_be_bytes());
+        }
+        ConstantInfo::InvokeDynamic(c) => {
+            out.push(18);
+            out.extend_from_slice(&c.bootstrap_method_attr_index.to_be_bytes());
+            out.extend_from_slice(&c.name_and_type_index.0.to_be_bytes());
+        }
+        ConstantInfo::Module(c) => {
+            out.push(19);
+            out.extend_from_slice(&c.name_index.0.to_be_bytes());
+        }
+        ConstantInfo::Package(c) => {
+            out.push(20);
+            out.extend_from_slice(&c.name_index.0.to_be_bytes());
+        }
+        ConstantInfo::Unusable => {}
+    }
+    Ok(())
+}
+
+/// Write the pool's `constant_pool_count` followed by its entries
+pub fn write_constant_pool(
+    pool: &ConstantPool,
+    class_file_data: &[u8],
+    out: &mut Vec<u8>,
+) -> Result<(), WriteError> {
+    let size = pool.len().checked_add(1).ok_or(WriteError::PoolTooLarge)?;
+    out.extend_from_slice(&size.to_be_bytes());
+    for (i, entry) in pool.iter().enumerate() {
+        write_constant(entry, i as u16 + 1, class_file_data, out)?;
+    }
+    Ok(())
+}
+
+/// Write the attributes preceded by their count
+pub fn write_attributes(
+    attributes: &[AttributeInfo],
+    class_file_data: &[u8],
+    out: &mut Vec<u8>,
+) -> Result<(), WriteError> {
+    out.extend_from_slice(&count(attributes.len())?);
+    for attr in attributes {
+        let info = class_file_data
+            .get(attr.info.clone())
+            .ok_or(WriteError::OutOfBounds)?;
+        let len = u32::try_from(info.len()).map_err(|_| WriteError::AttributeTooLong)?;
+        out.extend_from_slice(&attr.attribute_name_index.0.to_be_bytes());
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(info);
+    }
+    Ok(())
+}
+
+impl ClassFile {
+    /// Encode the class file, reading the text of the pool and the info of attributes from the
+    /// data.
+    /// The counts are taken from the tables themselves rather than the `*_count` fields, and the
+    /// length of each attribute from its info.
+    pub fn to_bytes(&self, data: &[u8]) -> Result<Vec<u8>, WriteError> {
+        let mut out = Vec::with_capacity(data.len());
+        out.extend_from_slice(&[0xCA, 0xFE, 0xBA, 0xBE]);
+        out.extend_from_slice(&self.version.minor.to_be_bytes());
+        out.extend_from_slice(&self.version.major.to_be_bytes());
+        write_constant_pool(&self.const_pool, data, &mut out)?;
+
+        out.extend_from_slice(&self.raw_flags().to_be_bytes());
+        out.extend_from_slice(&self.this_class.0.to_be_bytes());
+        out.extend_from_slice(&self.super_class.0.to_be_bytes());
+        out.extend_from_slice(&count(self.interfaces.len())?);
+        for interface in self.interfaces.iter() {
+            out.extend_from_slice(&interface.0.to_be_bytes());
+        }
+
+        out.extend_from_slice(&count(self.fields.len())?);
+        for field in self.fields.iter() {
+            out.extend_from_slice(&field.raw_flags().to_be_bytes());
+            out.extend_from_slice(&field.name_index.0.to_be_bytes());
+            out.extend_from_slice(&field.descriptor_index.0.to_be_bytes());
+            write_attributes(&field.attributes, data, &mut out)?;
+        }
+
+        out.extend_from_slice(&count(self.methods.len())?);
+        for method in self.methods.iter() {
+            out.extend_from_slice(&method.raw_flags().to_be_bytes());
+            out.extend_from_slice(&method.name_index.0.to_be_bytes());
+            out.extend_from_slice(&method.descriptor_index.0.to_be_bytes());
+            write_attributes(&method.attributes, data, &mut out)?;
+        }
+
+        write_attributes(&self.attributes, data, &mut out)?;
+        Ok(out)
+    }
+
+    /// Encode the class file, arranged as the options ask, see [`ClassFile::to_bytes`].
+    /// The class file itself is left unchanged.
+    pub fn to_bytes_with(
+        &self,
+        data: &[u8],
+        options: &WriteOptions,
+    ) -> Result<Vec<u8>, WriteError> {
+        if !options.sort_constants && !options.sort_members && !options.sort_attributes {
+            return self.to_bytes(data);
+        }
+
+        let mut class_file = self.clone();
+        let mut data = data.to_vec();
+        if options.sort_constants {
+            sort_constants(&mut class_file, &mut data)?;
+        }
+
+        let pool = &class_file.const_pool;
+        // Entries that are missing or not text sort first
+        let text = |index: ConstantPoolIndexRaw<Utf8Constant>| {
+            pool.get_t(index)
+                .map_or(&[][..], |text| text.as_bytes(&data))
+        };
+        if options.sort_members {
+            class_file
+                .fields
+                .sort_by_key(|field| (text(field.name_index), text(field.descriptor_index)));
+            class_file
+                .methods
+                .sort_by_key(|method| (text(method.name_index), text(method.descriptor_index)));
+        }
+        if options.sort_attributes {
+            let sort = |attributes: &mut [AttributeInfo]| {
+                attributes.sort_by_key(|attr| text(attr.attribute_name_index));
+            };
+            sort(&mut class_file.attributes[..]);
+            for field in class_file.fields.iter_mut() {
+                sort(&mut field.attributes[..]);
+            }
+            for method in class_file.methods.iter_mut() {
+                sort(&mut method.attributes[..]);
+            }
+        }
+
+        class_file.to_bytes(&data)
+    }
+
+    /// Encode the class file and write it out, see [`ClassFile::to_bytes`].
+    /// Nothing is written if the class file can't be encoded.
+    pub fn write_to<W: Write>(&self, data: &[u8], mut writer: W) -> Result<(), WriteError> {
+        let bytes = self.to_bytes(data)?;
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+/// How deeply the key of an entry follows references to other entries, which is more than any
+/// well formed pool needs and stops cycles in malformed ones
+const MAX_KEY_DEPTH: usize = 8;
+
+/// Write a key for the entry which depends only on its contents and those of the entries it
+/// refers to, and not on where any of them are in the pool
+fn constant_key(pool: &ConstantPool, data: &[u8], index: u16, depth: usize, out: &mut Vec<u8>) {
+    let entry = match pool.get(ConstantPoolIndexRaw::<ConstantInfo>::new(index)) {
+        Some(entry) if depth < MAX_KEY_DEPTH => entry,
+        // Keep bad references apart from good ones, ordered by where they point
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&index.to_be_bytes());
+            return;
+        }
+    };
+
+    let refer = |index: u16, out: &mut Vec<u8>| constant_key(pool, data, index, depth + 1, out);
+    match entry {
+        ConstantInfo::Class(c) => {
+            out.push(7);
+            refer(c.name_index.0, out);
+        }
+        ConstantInfo::String(c) => {
+            out.push(8);
+            refer(c.string_index.0, out);
+        }
+        ConstantInfo::FieldRef(c) => {
+            out.push(9);
+            refer(c.class_index.0, out);
+            refer(c.name_and_type_index.0, out);
+        }
+        ConstantInfo::MethodRef(c) => {
+            out.push(10);
+            refer(c.class_index.0, out);
+            refer(c.name_and_type_index.0, out);
+        }
+        ConstantInfo::InterfaceMethodRef(c) => {
+            out.push(11);
+            refer(c.class_index.0, out);
+            refer(c.name_and_type_index.0, out);
+        }
+        ConstantInfo::NameAndType(c) => {
+            out.push(12);
+            refer(c.name_index.0, out);
+            refer(c.descriptor_index.0, out);
+        }
+        ConstantInfo::MethodHandle(c) => {
+            out.push(15);
+            out.push(c.reference_kind);
+            refer(c.reference_index.0, out);
+        }
+        ConstantInfo::MethodType(c) => {
+            out.push(16);
+            refer(c.descriptor_index.0, out);
+        }
+        ConstantInfo::Dynamic(c) => {
+            out.push(17);
+            out.extend_from_slice(&c.bootstrap_method_attr_index.to_be_bytes());
+            refer(c.name_and_type_index.0, out);
+        }
+        ConstantInfo::InvokeDynamic(c) => {
+            out.push(18);
+            out.extend_from_slice(&c.bootstrap_method_attr_index.to_be_bytes());
+            refer(c.name_and_type_index.0, out);
+        }
+        ConstantInfo::Module(c) => {
+            out.push(19);
+            refer(c.name_index.0, out);
+        }
+        ConstantInfo::Package(c) => {
+            out.push(20);
+            refer(c.name_index.0, out);
+        }
+        // The encoding of the rest is already independent of the pool, and the Utf8 length
+        // keeps keys which follow each other apart
+        _ => {
+            let _ = write_constant(entry, index, data, out);
+        }
+    }
+}
+
+/// Renumber the pool so that its entries are in the order of their keys.
+/// Any `ldc` whose constant moves past 255 is widened first, and those which can then be
+/// narrowed are narrowed afterwards.
+fn sort_constants(class_file: &mut ClassFile, data: &mut Vec<u8>) -> Result<(), WriteError> {
+    let pool = &class_file.const_pool;
+    let mut keyed: Vec<_> = pool
+        .indices()
+        .map(|index| {
+            let mut key = Vec::new();
+            constant_key(pool, data, index.0, 0, &mut key);
+            (key, index)
+        })
+        .collect();
+    // Identical entries keep their relative order
+    keyed.sort_by(|a, b| a.0.cmp(&b.0).then(a.1 .0.cmp(&b.1 .0)));
+
+    let remap = IndexRemap::from_order(pool, keyed.into_iter().map(|(_, index)| index))?;
+    remap.fit_ldc(class_file, data)?;
+    remap.apply(class_file, data)?;
+    class_file.fit_ldc(data)?;
+    Ok(())
+}
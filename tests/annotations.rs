@@ -0,0 +1,623 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{
+    visit_annotations, visit_parameter_annotations, visit_type_annotations, AnnotationVisitor,
+    HasAttributes,
+};
+use classfile_parser::constant_info::{ConstantInfo, Utf8Constant};
+use classfile_parser::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use classfile_parser::{ClassFile, ParseOptions};
+
+const INFO: &str = "Luk/co/palmr/classfileparser/Annotated$Info;";
+
+/// Records the elements of the Info annotations, as `name=value` strings
+struct InfoFinder<'a> {
+    pool: &'a ConstantPool,
+    data: &'a [u8],
+    found: Vec<String>,
+    /// The depth of the Info annotation being visited
+    inside: Option<usize>,
+    name: String,
+    parameters: Vec<u8>,
+}
+impl<'a> InfoFinder<'a> {
+    fn new(pool: &'a ConstantPool, data: &'a [u8]) -> Self {
+        InfoFinder {
+            pool,
+            data,
+            found: Vec::new(),
+            inside: None,
+            name: String::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    fn text(&self, index: ConstantPoolIndexRaw<Utf8Constant>) -> String {
+        self.pool
+            .get_t(index)
+            .unwrap()
+            .as_text(self.data)
+            .into_owned()
+    }
+
+    fn push(&mut self, value: String) {
+        let entry = format!("{}={}", self.name, value);
+        self.found.push(entry);
+    }
+}
+impl<'a> AnnotationVisitor for InfoFinder<'a> {
+    fn annotation_start(
+        &mut self,
+        type_index: ConstantPoolIndexRaw<Utf8Constant>,
+        depth: usize,
+    ) -> bool {
+        if self.inside.is_some() {
+            let name = self.text(type_index);
+            self.push(format!("@{}", name));
+            return false;
+        }
+        if self.text(type_index) == INFO {
+            self.inside = Some(depth);
+            return true;
+        }
+        false
+    }
+
+    fn annotation_end(&mut self, depth: usize) {
+        assert_eq!(self.inside, Some(depth));
+        self.inside = None;
+        self.found.push("end".to_string());
+    }
+
+    fn element_name(&mut self, name_index: ConstantPoolIndexRaw<Utf8Constant>) {
+        self.name = self.text(name_index);
+    }
+
+    fn const_value(&mut self, tag: u8, value_index: ConstantPoolIndexRaw<ConstantInfo>) {
+        let value = match self.pool.get(value_index).unwrap() {
+            ConstantInfo::Integer(i) => i.value.to_string(),
+            ConstantInfo::Utf8(s) => s.as_text(self.data).into_owned(),
+            _ => panic!("unexpected constant"),
+        };
+        self.push(format!("{}{}", tag as char, value));
+    }
+
+    fn enum_value(
+        &mut self,
+        _type_name_index: ConstantPoolIndexRaw<Utf8Constant>,
+        const_name_index: ConstantPoolIndexRaw<Utf8Constant>,
+    ) {
+        let value = self.text(const_name_index);
+        self.push(value);
+    }
+
+    fn class_value(&mut self, class_info_index: ConstantPoolIndexRaw<Utf8Constant>) {
+        let value = self.text(class_info_index);
+        self.push(value);
+    }
+
+    fn array_start(&mut self, len: u16) {
+        self.push(format!("[{}", len));
+    }
+
+    fn parameter_start(&mut self, index: u8) {
+        self.parameters.push(index);
+    }
+}
+
+#[test]
+fn test_visit_annotations() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Annotated.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+
+    let attr = c
+        .find_attribute_info(pool, data, "RuntimeVisibleAnnotations")
+        .unwrap();
+    let mut finder = InfoFinder::new(pool, data);
+    visit_annotations(attr, data, &mut finder).unwrap();
+    assert_eq!(
+        finder.found,
+        [
+            "id=I1",
+            "tags=[2",
+            "tags=sa",
+            "tags=sb",
+            "type=Ljava/lang/String;",
+            "kind=TYPE",
+            "end"
+        ]
+    );
+
+    let method = &c.methods[1];
+    let attr = method
+        .find_attribute_info(pool, data, "RuntimeVisibleAnnotations")
+        .unwrap();
+    let mut finder = InfoFinder::new(pool, data);
+    visit_annotations(attr, data, &mut finder).unwrap();
+    // The nested annotation is reported, but its elements are skipped
+    assert_eq!(
+        finder.found,
+        ["id=I2", "nested=@Ljava/lang/annotation/Retention;", "end"]
+    );
+
+    let attr = method
+        .find_attribute_info(pool, data, "RuntimeVisibleParameterAnnotations")
+        .unwrap();
+    let mut finder = InfoFinder::new(pool, data);
+    visit_parameter_annotations(attr, data, &mut finder).unwrap();
+    assert_eq!(finder.parameters, [0, 1]);
+    assert_eq!(finder.found, ["id=I3", "end"]);
+}
+
+/// The target type and type path of a type annotation
+type Target = (u8, Vec<u8>);
+
+/// Records the type of each top level annotation, along with the target of type annotations
+#[derive(Default)]
+struct TargetFinder {
+    found: Vec<(u16, Option<Target>)>,
+    target: Option<Target>,
+}
+impl AnnotationVisitor for TargetFinder {
+    fn annotation_start(
+        &mut self,
+        type_index: ConstantPoolIndexRaw<Utf8Constant>,
+        depth: usize,
+    ) -> bool {
+        if depth == 0 {
+            self.found.push((type_index.0, self.target.take()));
+        }
+        false
+    }
+
+    fn type_annotation_target(&mut self, target_type: u8, target_info: &[u8], type_path: &[u8]) {
+        assert!(target_info.is_empty());
+        self.target = Some((target_type, type_path.to_vec()));
+    }
+}
+
+#[test]
+fn test_record_component_annotations() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/RecordExample.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let text = |index: u16| {
+        pool.get_t(ConstantPoolIndexRaw::<Utf8Constant>::new(index))
+            .unwrap()
+            .as_text(data)
+            .into_owned()
+    };
+
+    let components = c.record_components(data).unwrap().unwrap();
+    let names: Vec<_> = components
+        .iter()
+        .map(|component| (component.name.as_str(), component.descriptor.as_str()))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("first", "I"),
+            ("names", "Ljava/util/List;"),
+            ("plain", "J")
+        ]
+    );
+
+    let first = &components[0];
+    let mut finder = TargetFinder::default();
+    visit_annotations(
+        first.visible_annotations.as_ref().unwrap(),
+        data,
+        &mut finder,
+    )
+    .unwrap();
+    assert_eq!(finder.found.len(), 1);
+    assert_eq!(
+        text(finder.found[0].0),
+        "Luk/co/palmr/classfileparser/RecordExample$Named;"
+    );
+    assert!(first.visible_type_annotations.is_none());
+
+    let names = &components[1];
+    assert_eq!(
+        names.signature.as_deref(),
+        Some("Ljava/util/List<Ljava/lang/String;>;")
+    );
+    assert!(names.visible_annotations.is_none());
+    let mut finder = TargetFinder::default();
+    visit_type_annotations(
+        names.visible_type_annotations.as_ref().unwrap(),
+        data,
+        &mut finder,
+    )
+    .unwrap();
+    assert_eq!(finder.found.len(), 1);
+    assert_eq!(
+        text(finder.found[0].0),
+        "Luk/co/palmr/classfileparser/RecordExample$Checked;"
+    );
+    // A field target, on the first type argument
+    assert_eq!(finder.found[0].1, Some((0x13, vec![1, 3, 0])));
+
+    let plain = &components[2];
+    assert!(plain.attributes.is_empty());
+    assert!(plain.signature.is_none());
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Annotated.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    assert!(c.record_components(data).unwrap().is_none());
+}
+
+#[test]
+#[cfg(feature = "annotations")]
+fn test_write_annotations() {
+    use classfile_parser::attribute_info::{
+        Annotation, AnnotationDefaultAttribute, AnnotationsAttribute, ElementValue,
+        ElementValuePair, ParameterAnnotations, ParameterAnnotationsAttribute, TargetInfo,
+        TypeAnnotation, TypeAnnotationsAttribute, TypePath, TypePathEntry,
+    };
+    use classfile_parser::writer::WriteError;
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Annotated.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let utf8 = |pool: &ConstantPool, data: &[u8], text: &str| {
+        pool.utf8_iter(data)
+            .find(|(_, found)| found == text)
+            .unwrap()
+            .0
+    };
+    let int = |value: i32| {
+        let (index, _) = pool
+            .iter_indexed()
+            .find(|(_, entry)| matches!(entry, ConstantInfo::Integer(i) if i.value == value))
+            .unwrap();
+        ElementValue::Const {
+            tag: b'I',
+            const_value_index: index,
+        }
+    };
+    let string = |text: &str| ElementValue::Const {
+        tag: b's',
+        const_value_index: ConstantPoolIndexRaw::new(utf8(pool, data, text).0),
+    };
+    let annotation = |type_name: &str, pairs: Vec<(&str, ElementValue)>| Annotation {
+        type_index: utf8(pool, data, type_name),
+        num_element_value_pairs: pairs.len() as u16,
+        element_value_pairs: pairs
+            .into_iter()
+            .map(|(name, value)| ElementValuePair {
+                element_name_index: utf8(pool, data, name),
+                value,
+            })
+            .collect(),
+    };
+
+    let info = annotation(
+        INFO,
+        vec![
+            ("id", int(1)),
+            (
+                "tags",
+                ElementValue::Array {
+                    num_values: 2,
+                    values: vec![string("a"), string("b")],
+                },
+            ),
+            (
+                "type",
+                ElementValue::Class {
+                    class_info_index: utf8(pool, data, "Ljava/lang/String;"),
+                },
+            ),
+            (
+                "kind",
+                ElementValue::Enum {
+                    type_name_index: utf8(pool, data, "Ljava/lang/annotation/ElementType;"),
+                    const_name_index: utf8(pool, data, "TYPE"),
+                },
+            ),
+        ],
+    );
+    let deprecated = annotation("Ljava/lang/Deprecated;", Vec::new());
+    let attr = AnnotationsAttribute {
+        num_annotations: 2,
+        annotations: vec![info, deprecated],
+    };
+    let expected = c
+        .find_attribute_info(pool, data, "RuntimeVisibleAnnotations")
+        .unwrap();
+    assert_eq!(attr.to_bytes().unwrap(), &data[expected.info.clone()]);
+
+    // The count fields are ignored, so an annotation can be removed without updating them
+    let mut removed = attr.clone();
+    removed.annotations.pop();
+    assert_eq!(&removed.to_bytes().unwrap()[..2], [0, 1]);
+
+    let parameters = ParameterAnnotationsAttribute {
+        num_parameters: 2,
+        parameter_annotations: vec![
+            ParameterAnnotations {
+                num_annotations: 1,
+                annotations: vec![annotation(INFO, vec![("id", int(3))])],
+            },
+            ParameterAnnotations {
+                num_annotations: 0,
+                annotations: Vec::new(),
+            },
+        ],
+    };
+    let expected = c.methods[1]
+        .find_attribute_info(pool, data, "RuntimeVisibleParameterAnnotations")
+        .unwrap();
+    assert_eq!(parameters.to_bytes().unwrap(), &data[expected.info.clone()]);
+
+    // The parameter count is a single byte
+    let mut too_many = parameters.clone();
+    too_many.parameter_annotations = vec![parameters.parameter_annotations[1].clone(); 256];
+    assert!(matches!(too_many.to_bytes(), Err(WriteError::TooManyItems)));
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Annotated$Info.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let kind = c
+        .methods
+        .iter()
+        .find(|method| pool.get_t(method.name_index).unwrap().as_text(data) == "kind")
+        .unwrap();
+    let default = AnnotationDefaultAttribute {
+        default_value: ElementValue::Enum {
+            type_name_index: utf8(pool, data, "Ljava/lang/annotation/ElementType;"),
+            const_name_index: utf8(pool, data, "METHOD"),
+        },
+    };
+    let expected = kind
+        .find_attribute_info(pool, data, "AnnotationDefault")
+        .unwrap();
+    assert_eq!(default.to_bytes().unwrap(), &data[expected.info.clone()]);
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/RecordExample.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let names = &c.record_components(data).unwrap().unwrap()[1];
+    let checked = TypeAnnotationsAttribute {
+        num_annotations: 1,
+        annotations: vec![TypeAnnotation {
+            target_type: 0x13,
+            target_info: TargetInfo::Empty,
+            target_path: TypePath {
+                path_length: 1,
+                path: vec![TypePathEntry {
+                    type_path_kind: 3,
+                    type_argument_index: 0,
+                }],
+            },
+            annotation: Annotation {
+                type_index: utf8(
+                    pool,
+                    data,
+                    "Luk/co/palmr/classfileparser/RecordExample$Checked;",
+                ),
+                num_element_value_pairs: 0,
+                element_value_pairs: Vec::new(),
+            },
+        }],
+    };
+    let expected = names.visible_type_annotations.as_ref().unwrap();
+    assert_eq!(checked.to_bytes().unwrap(), &data[expected.info.clone()]);
+}
+
+#[test]
+#[cfg(feature = "annotations")]
+fn test_parse_annotations() {
+    use classfile_parser::attribute_info::{
+        element_value_parser, AnnotationsAttribute, AttributeData, ElementValue,
+        ParameterAnnotationsAttribute,
+    };
+    use classfile_parser::parser::ParseData;
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Annotated.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let text = |index: ConstantPoolIndexRaw<Utf8Constant>| pool.get_t(index).unwrap().as_text(data);
+
+    let info = c
+        .find_attribute_info(pool, data, "RuntimeVisibleAnnotations")
+        .unwrap();
+    let attr = AnnotationsAttribute::parse_info(info, data).unwrap();
+    assert_eq!(attr.to_bytes().unwrap(), &data[info.info.clone()]);
+    let types: Vec<_> = attr
+        .annotations
+        .iter()
+        .map(|annotation| text(annotation.type_index))
+        .collect();
+    assert_eq!(types, [INFO, "Ljava/lang/Deprecated;"]);
+    let elements: Vec<_> = attr.annotations[0]
+        .element_value_pairs
+        .iter()
+        .map(|pair| text(pair.element_name_index))
+        .collect();
+    assert_eq!(elements, ["id", "tags", "type", "kind"]);
+    match &attr.annotations[0].element_value_pairs[1].value {
+        ElementValue::Array { values, .. } => assert_eq!(values.len(), 2),
+        value => panic!("tags is {:?}", value),
+    }
+
+    let method = &c.methods[1];
+    let info = method
+        .find_attribute_info(pool, data, "RuntimeVisibleAnnotations")
+        .unwrap();
+    let attr = AnnotationsAttribute::parse_info(info, data).unwrap();
+    let nested = match &attr.annotations[0].element_value_pairs[1].value {
+        ElementValue::Annotation(nested) => nested,
+        value => panic!("nested is {:?}", value),
+    };
+    assert_eq!(text(nested.type_index), "Ljava/lang/annotation/Retention;");
+
+    let info = method
+        .find_attribute_info(pool, data, "RuntimeVisibleParameterAnnotations")
+        .unwrap();
+    let parameters = ParameterAnnotationsAttribute::parse_info(info, data).unwrap();
+    assert_eq!(parameters.num_parameters, 2);
+    assert_eq!(parameters.parameter_annotations[0].annotations.len(), 1);
+    assert!(parameters.parameter_annotations[1].annotations.is_empty());
+    assert_eq!(parameters.to_bytes().unwrap(), &data[info.info.clone()]);
+
+    c.parse_typed_attributes(data).unwrap();
+    let typed = c.typed_attributes.as_ref().unwrap();
+    let annotations = typed
+        .iter()
+        .filter(|(_, _, attr)| matches!(attr, AttributeData::RuntimeVisibleAnnotations(_)))
+        .count();
+    assert_eq!(annotations, 2);
+
+    // Arrays nested past the limit fail rather than overflowing the stack
+    let mut deep = Vec::new();
+    for _ in 0..1000 {
+        deep.extend_from_slice(&[b'[', 0, 1]);
+    }
+    deep.extend_from_slice(&[b'I', 0, 1]);
+    assert!(element_value_parser(ParseData::new(&deep)).is_err());
+    let shallow = &deep[deep.len() - 3 * 10..];
+    let (_, value) = element_value_parser(ParseData::new(shallow)).unwrap();
+    assert!(matches!(value, ElementValue::Array { num_values: 1, .. }));
+
+    // A count larger than the rest of the input fails once the input runs out
+    let short = [b'[', 0xFF, 0xFF, b'I', 0, 1];
+    assert!(element_value_parser(ParseData::new(&short)).is_err());
+}
+
+#[test]
+#[cfg(feature = "annotations")]
+fn test_parse_type_annotations() {
+    use classfile_parser::attribute_info::{
+        type_annotation_parser, AnnotationDefaultAttribute, AttributeData, ElementValue,
+        KnownAttribute, LocalVarTargetEntry, TargetInfo, TypeAnnotationsAttribute, TypePath,
+    };
+    use classfile_parser::parser::ParseData;
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/RecordExample.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let init = c
+        .find_method(data, "<init>", "(ILjava/util/List;J)V")
+        .unwrap();
+    let info = init
+        .find_attribute_info(pool, data, "RuntimeVisibleTypeAnnotations")
+        .unwrap();
+    let attr = TypeAnnotationsAttribute::parse_info(info, data).unwrap();
+    assert_eq!(attr.to_bytes().unwrap(), &data[info.info.clone()]);
+    assert_eq!(attr.annotations.len(), 1);
+    let checked = &attr.annotations[0];
+    assert_eq!(checked.target_type, 0x16);
+    assert_eq!(
+        checked.target_info,
+        TargetInfo::FormalParameter {
+            formal_parameter_index: 1
+        }
+    );
+    assert_eq!(checked.target_path.path.len(), 1);
+    assert_eq!(checked.target_path.path[0].type_path_kind, 3);
+    assert_eq!(
+        pool.get_t(checked.annotation.type_index)
+            .unwrap()
+            .as_text(data),
+        "Luk/co/palmr/classfileparser/RecordExample$Checked;"
+    );
+
+    let names = c.find_field(data, "names", "Ljava/util/List;").unwrap();
+    let info = names
+        .find_attribute_info(pool, data, "RuntimeVisibleTypeAnnotations")
+        .unwrap();
+    let attr = TypeAnnotationsAttribute::parse_info(info, data).unwrap();
+    assert_eq!(attr.annotations[0].target_type, 0x13);
+    assert_eq!(attr.annotations[0].target_info, TargetInfo::Empty);
+
+    // Targets which javac didn't produce for the examples
+    let mut local = attr.annotations[0].clone();
+    local.target_type = 0x40;
+    local.target_info = TargetInfo::Localvar {
+        table_length: 2,
+        table: vec![
+            LocalVarTargetEntry {
+                start_pc: 0,
+                length: 5,
+                index: 1,
+            },
+            LocalVarTargetEntry {
+                start_pc: 7,
+                length: 3,
+                index: 2,
+            },
+        ],
+    };
+    local.target_path = TypePath {
+        path_length: 0,
+        path: Vec::new(),
+    };
+    let mut cast = local.clone();
+    cast.target_type = 0x47;
+    cast.target_info = TargetInfo::TypeArgument {
+        offset: 12,
+        type_argument_index: 1,
+    };
+    for annotation in [local, cast] {
+        let mut bytes = Vec::new();
+        annotation.write(&mut bytes).unwrap();
+        let (rest, parsed) = type_annotation_parser(ParseData::new(&bytes)).unwrap();
+        assert!(rest.data().is_empty());
+        assert_eq!(parsed, annotation);
+    }
+    let unknown = [0x20, 0, 0, 0, 0, 0];
+    assert!(type_annotation_parser(ParseData::new(&unknown)).is_err());
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Annotated$Info.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let default = |name: &str, descriptor: &str| {
+        let method = c.find_method(data, name, descriptor).unwrap();
+        let info = method
+            .find_attribute_info(pool, data, AnnotationDefaultAttribute::NAME)
+            .unwrap();
+        let attr = AnnotationDefaultAttribute::parse_info(info, data).unwrap();
+        assert_eq!(attr.to_bytes().unwrap(), &data[info.info.clone()]);
+        attr.default_value
+    };
+    assert!(matches!(
+        default("tags", "()[Ljava/lang/String;"),
+        ElementValue::Array { num_values: 0, .. }
+    ));
+    match default("type", "()Ljava/lang/Class;") {
+        ElementValue::Class { class_info_index } => assert_eq!(
+            pool.get_t(class_info_index).unwrap().as_text(data),
+            "Ljava/lang/Object;"
+        ),
+        value => panic!("type is {:?}", value),
+    }
+    match default("kind", "()Ljava/lang/annotation/ElementType;") {
+        ElementValue::Enum {
+            const_name_index, ..
+        } => assert_eq!(
+            pool.get_t(const_name_index).unwrap().as_text(data),
+            "METHOD"
+        ),
+        value => panic!("kind is {:?}", value),
+    }
+    assert!(matches!(
+        default("nested", "()Ljava/lang/annotation/Retention;"),
+        ElementValue::Annotation(_)
+    ));
+    let id = c.find_method(data, "id", "()I").unwrap();
+    assert!(id
+        .find_attribute::<AnnotationDefaultAttribute>(pool, data)
+        .unwrap()
+        .is_none());
+
+    c.parse_typed_attributes(data).unwrap();
+    let typed = c.typed_attributes.as_ref().unwrap();
+    let defaults = typed
+        .iter()
+        .filter(|(_, _, attr)| matches!(attr, AttributeData::AnnotationDefault(_)))
+        .count();
+    assert_eq!(defaults, 4);
+}
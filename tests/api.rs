@@ -0,0 +1,60 @@
+extern crate classfile_parser;
+
+use classfile_parser::method_info::MethodAccessFlags;
+use classfile_parser::{class_parser, parser::ParseData};
+
+#[test]
+fn test_class_api() {
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("not a class file");
+    let api = c.api(class_data).expect("failed to extract api");
+
+    assert_eq!(api.name, "uk/co/palmr/classfileparser/Generics");
+    assert_eq!(api.super_class.as_deref(), Some("java/lang/Object"));
+    assert!(api.interfaces.is_empty());
+    assert_eq!(
+        api.signature.as_deref(),
+        Some("<T:Ljava/lang/Number;U:Ljava/lang/Object;>Ljava/lang/Object;")
+    );
+
+    let names: Vec<_> = api.fields.iter().map(|field| field.name.as_str()).collect();
+    assert_eq!(names, ["names", "nested", "others", "plain", "value"]);
+    let value = api.field("value", "Ljava/lang/Number;").unwrap();
+    assert_eq!(value.signature.as_deref(), Some("TT;"));
+    assert_eq!(api.field("plain", "I").unwrap().signature, None);
+
+    let entry = api.method("entry", "()Ljava/util/Map$Entry;").unwrap();
+    assert_eq!(entry.exceptions, ["java/lang/IllegalStateException"]);
+    assert_eq!(entry.access_flags, MethodAccessFlags::PUBLIC);
+    assert!(api.method("<init>", "(Ljava/lang/Number;)V").is_some());
+
+    // The api doesn't refer to the data, so it compares equal to itself when extracted again
+    let (_, again) = class_parser(ParseData::new(class_data)).unwrap();
+    assert_eq!(again.api(class_data).unwrap(), api);
+}
+
+#[test]
+fn test_class_api_skips_private_members() {
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("not a class file");
+    let api = c.api(class_data).expect("failed to extract api");
+
+    // The fields are private
+    assert!(api.fields.is_empty());
+    let names: Vec<_> = api
+        .methods
+        .iter()
+        .map(|method| method.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "<init>",
+            "getInteger",
+            "getLEETness",
+            "getName",
+            "getSize",
+            "getString"
+        ]
+    );
+}
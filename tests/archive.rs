@@ -0,0 +1,233 @@
+#![cfg(feature = "jar")]
+extern crate classfile_parser;
+
+use classfile_parser::archive::{
+    jar_entry_class_name, jmod_entry_class_name, ArchiveError, ClassEntry, DuplicateClassDetector,
+    JarClassReader, JmodClassReader,
+};
+#[cfg(feature = "modules")]
+use classfile_parser::attribute_info::{HasAttributes, ModuleAttribute, ModulePackagesAttribute};
+#[cfg(feature = "modules")]
+use classfile_parser::constant_info::ConstantInfo;
+use classfile_parser::{ParseError, ParseOptions};
+
+const BASIC_JMOD: &str = "./java-assets/archives/basic.jmod";
+const BASIC_JAR: &str = "./java-assets/archives/basic.jar";
+
+#[test]
+fn test_jmod_class_names() {
+    let reader = JmodClassReader::open(BASIC_JMOD).expect("failed to open jmod");
+    let mut names: Vec<&str> = reader.class_names().collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        vec![
+            "module-info",
+            "uk/co/palmr/classfileparser/BasicClass",
+            "uk/co/palmr/classfileparser/HelloWorld",
+        ]
+    );
+}
+
+#[test]
+fn test_jmod_classes() {
+    let mut reader = JmodClassReader::open(BASIC_JMOD).expect("failed to open jmod");
+
+    let mut parsed = 0;
+    for entry in reader.classes() {
+        let entry = entry.expect("failed to read entry");
+        let class_file = entry
+            .parse(&ParseOptions::default())
+            .expect("failed to parse class");
+        assert_eq!(class_file.methods.len(), class_file.methods_count as usize);
+        parsed += 1;
+    }
+    assert_eq!(parsed, 3);
+
+    let entry = reader
+        .read_class("uk/co/palmr/classfileparser/HelloWorld")
+        .expect("failed to read entry")
+        .expect("missing HelloWorld");
+    entry
+        .parse_opt(&ParseOptions::default())
+        .expect("failed to parse class");
+    assert!(reader
+        .read_class("uk/co/palmr/classfileparser/Missing")
+        .expect("failed to search for entry")
+        .is_none());
+}
+
+#[test]
+#[cfg(feature = "modules")]
+fn test_jmod_module_info() {
+    let mut reader = JmodClassReader::open(BASIC_JMOD).expect("failed to open jmod");
+    let entry = reader
+        .read_class("module-info")
+        .expect("failed to read entry")
+        .expect("missing module-info");
+    let class_file = entry
+        .parse(&ParseOptions::default())
+        .expect("failed to parse module-info");
+    let pool = &class_file.const_pool;
+    let data = &entry.data[..];
+    assert!(pool
+        .iter()
+        .any(|constant| matches!(constant, ConstantInfo::Module(_))));
+    assert!(pool
+        .iter()
+        .any(|constant| matches!(constant, ConstantInfo::Package(_))));
+
+    let module: ModuleAttribute = class_file.find_attribute(pool, data).unwrap().unwrap();
+    let name = pool.get_t(module.module_name_index).unwrap();
+    assert_eq!(
+        pool.get_t(name.name_index).unwrap().as_text(data),
+        "uk.co.palmr.classfileparser"
+    );
+    let requires: Vec<_> = module
+        .requires
+        .iter()
+        .map(|requires| {
+            let module = pool.get_t(requires.requires_index).unwrap();
+            pool.get_t(module.name_index).unwrap().as_text(data)
+        })
+        .collect();
+    assert!(requires.iter().any(|name| name == "java.base"));
+
+    let packages: ModulePackagesAttribute = class_file.find_attribute(pool, data).unwrap().unwrap();
+    let package = pool.get_t(packages.package_index[0]).unwrap();
+    assert_eq!(
+        pool.get_t(package.name_index).unwrap().as_text(data),
+        "uk/co/palmr/classfileparser"
+    );
+
+    assert_eq!(class_file.to_bytes(data).unwrap(), entry.data);
+}
+
+#[test]
+fn test_not_jmod() {
+    let data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let res = JmodClassReader::new(std::io::Cursor::new(&data[..]));
+    assert!(matches!(res, Err(ArchiveError::NotJmod)));
+
+    assert_eq!(jmod_entry_class_name("classes/a/B.class"), Some("a/B"));
+    assert_eq!(jmod_entry_class_name("lib/libfoo.so"), None);
+}
+
+#[test]
+fn test_entry_parse_error() {
+    use std::error::Error;
+
+    let entry = ClassEntry {
+        name: "a/B".to_owned(),
+        data: b"PK\x03\x04".to_vec(),
+    };
+    let options = ParseOptions::default();
+    match entry.parse(&options).unwrap_err() {
+        ArchiveError::Parse { entry, error } => {
+            assert_eq!(entry, "a/B");
+            assert_eq!(error, ParseError::BadMagic);
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+    let err = entry.parse_opt(&options).unwrap_err();
+    assert!(err.source().unwrap().is::<ParseError>());
+}
+
+#[test]
+fn test_jar_classes() {
+    let mut reader = JarClassReader::open(BASIC_JAR).expect("failed to open jar");
+    let mut names: Vec<&str> = reader.class_names().collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        vec![
+            "uk/co/palmr/classfileparser/BasicInterface",
+            "uk/co/palmr/classfileparser/Constants",
+            "uk/co/palmr/karl/examples/BasicClass",
+        ]
+    );
+
+    let mut parsed = 0;
+    for entry in reader.classes() {
+        let entry = entry.expect("failed to read entry");
+        let class_file = entry
+            .parse(&ParseOptions::default())
+            .expect("failed to parse class");
+        assert_eq!(class_file.this_class_name(&entry.data).unwrap(), entry.name);
+        let opt = entry
+            .parse_opt(&ParseOptions::default())
+            .expect("failed to parse class");
+        assert_eq!(opt.methods.len(), class_file.methods_count);
+        parsed += 1;
+    }
+    assert_eq!(parsed, 3);
+
+    let entry = reader
+        .read_class("uk/co/palmr/karl/examples/BasicClass")
+        .unwrap()
+        .expect("missing class");
+    assert_eq!(
+        entry.data,
+        include_bytes!("../java-assets/compiled-classes/BasicClass.class")
+    );
+    assert!(reader.read_class("Missing").unwrap().is_none());
+
+    assert_eq!(jar_entry_class_name("a/B.class"), Some("a/B"));
+    assert_eq!(jar_entry_class_name("META-INF/versions/9/a/B.class"), None);
+    assert_eq!(jar_entry_class_name("META-INF/MANIFEST.MF"), None);
+
+    let mut detector = DuplicateClassDetector::new();
+    detector.add_jar("a.jar", &mut reader).unwrap();
+    detector.add_jar("b.jar", &mut reader).unwrap();
+    let duplicates = detector.duplicates();
+    assert_eq!(duplicates.len(), 3);
+    assert!(duplicates.iter().all(|d| d.is_identical()));
+    assert_eq!(
+        duplicates[2].origins[1].entry,
+        "uk/co/palmr/karl/examples/BasicClass.class"
+    );
+}
+
+#[test]
+fn test_duplicate_classes() {
+    let mut detector = DuplicateClassDetector::new();
+    for source in ["a.jmod", "b.jmod"] {
+        let mut reader = JmodClassReader::open(BASIC_JMOD).expect("failed to open jmod");
+        detector.add_jmod(source, &mut reader).unwrap();
+    }
+    let mut data = include_bytes!("../java-assets/compiled-classes/BasicClass.class").to_vec();
+    // A different minor version, so that the bytes differ
+    data[5] = 1;
+    detector.add_class(
+        "uk/co/palmr/classfileparser/BasicClass",
+        "classes",
+        "uk/co/palmr/classfileparser/BasicClass.class",
+        &data,
+    );
+    detector.add_class("Unique", "classes", "Unique.class", &data);
+
+    let duplicates = detector.duplicates();
+    let names: Vec<&str> = duplicates.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "module-info",
+            "uk/co/palmr/classfileparser/BasicClass",
+            "uk/co/palmr/classfileparser/HelloWorld",
+        ]
+    );
+    assert!(duplicates[0].is_identical());
+    assert!(duplicates[2].is_identical());
+
+    let basic = &duplicates[1];
+    assert!(!basic.is_identical());
+    let sources: Vec<&str> = basic.origins.iter().map(|o| o.source.as_str()).collect();
+    assert_eq!(sources, vec!["a.jmod", "b.jmod", "classes"]);
+    assert_eq!(
+        basic.origins[0].entry,
+        "classes/uk/co/palmr/classfileparser/BasicClass.class"
+    );
+    let version = basic.origins[2].version.unwrap();
+    assert_eq!((version.major, version.minor), (51, 1));
+    assert_eq!(basic.origins[0].version.unwrap().minor, 0);
+}
@@ -36,7 +36,7 @@ fn test_attribute_bootstrap_methods() {
                 bootstrap_method_const_index
             );
 
-            for (_, attribute_item) in c.attributes.iter().enumerate() {
+            for attribute_item in c.attributes.iter() {
                 if attribute_item.attribute_name_index.0 == bootstrap_method_const_index {
                     match bootstrap_methods_attribute_parser(ParseData::from_range(
                         class_file_data,
@@ -75,7 +75,7 @@ fn should_have_no_bootstrap_method_attr_if_no_invoke_dynamic() {
     let class_file_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
     match class_parser(ParseData::new(class_file_data)) {
         Result::Ok((_, c)) => {
-            for (_, const_item) in c.const_pool.iter().enumerate() {
+            for const_item in c.const_pool.iter() {
                 if let ConstantInfo::Utf8(ref c) = *const_item {
                     if c.as_text(class_file_data) == "BootstrapMethods" {
                         panic!("Should not have found a BootstrapMethods constant in a class not requiring it")
@@ -86,3 +86,49 @@ fn should_have_no_bootstrap_method_attr_if_no_invoke_dynamic() {
         _ => panic!("Not a valid class file"),
     }
 }
+
+#[test]
+fn test_bootstrap_errors() {
+    use classfile_parser::attribute_info::names;
+    use classfile_parser::scan::{class_layout, skip_constant_pool_parser};
+    use classfile_parser::validate::BootstrapError;
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let c = ClassFile::parse(original, &ParseOptions::default()).unwrap();
+    assert!(c.bootstrap_errors(original).unwrap().is_empty());
+
+    // #2 is the InvokeDynamic constant, which uses the first bootstrap method
+    let pool = ParseData::from_pos(
+        original,
+        class_layout(original).unwrap().constant_pool.start,
+    );
+    let (invoke_dynamic, _) = skip_constant_pool_parser(pool, 1).unwrap();
+    let index = invoke_dynamic.pos() + 1;
+    let mut data = original.to_vec();
+    data[index..index + 2].copy_from_slice(&[0, 5]);
+
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    assert_eq!(
+        c.bootstrap_errors(&data).unwrap(),
+        vec![BootstrapError::IndexOutOfRange {
+            constant: ConstantPoolIndexRaw::new(2),
+            bootstrap_method_attr_index: 5,
+            count: 1,
+        }]
+    );
+
+    let pool = c.const_pool.clone();
+    c.attributes.retain(|attr| {
+        pool.get_t(attr.attribute_name_index)
+            .unwrap()
+            .as_text(&data)
+            != names::BOOTSTRAP_METHODS
+    });
+    assert_eq!(
+        c.bootstrap_errors(&data).unwrap(),
+        vec![BootstrapError::MissingAttribute {
+            constant: ConstantPoolIndexRaw::new(2),
+        }]
+    );
+}
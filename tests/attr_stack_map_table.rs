@@ -4,6 +4,7 @@ extern crate nom;
 use classfile_parser::{class_parser, parser::ParseData};
 
 #[test]
+#[cfg(feature = "stackmap")]
 fn test_attribute_stack_map_table() {
     let stack_map_class: &[u8] = include_bytes!("../java-assets/compiled-classes/Factorial.class");
     let res = class_parser(ParseData::new(stack_map_class));
@@ -54,3 +55,188 @@ fn test_attribute_stack_map_table() {
         _ => panic!("not a class file"),
     };
 }
+
+#[test]
+#[cfg(feature = "stackmap")]
+fn test_find_attribute() {
+    use classfile_parser::attribute_info::{
+        CodeAttribute, CodeAttributeOpt, HasAttributes, SourceFileAttribute, StackMapTableAttribute,
+    };
+    use classfile_parser::LoadError;
+
+    let stack_map_class: &[u8] = include_bytes!("../java-assets/compiled-classes/Factorial.class");
+    let (_, c) = class_parser(ParseData::new(stack_map_class)).expect("not a class file");
+
+    // Factorial was compiled without debug info
+    assert!(c
+        .find_attribute::<SourceFileAttribute>(&c.const_pool, stack_map_class)
+        .expect("failed to search for SourceFile")
+        .is_none());
+
+    let code = c.methods[1]
+        .find_attribute::<CodeAttribute>(&c.const_pool, stack_map_class)
+        .expect("failed to parse Code")
+        .expect("no Code attribute");
+    let table = code
+        .find_attribute::<StackMapTableAttribute>(&c.const_pool, stack_map_class)
+        .expect("failed to parse StackMapTable")
+        .expect("no StackMapTable attribute");
+    assert_eq!(table.entries.len(), 2);
+
+    assert!(code
+        .find_attribute_info(&c.const_pool, stack_map_class, "Nonexistent")
+        .is_none());
+
+    // The lazily parsed code finds the same attribute
+    let code_opt = c.methods[1]
+        .find_attribute::<CodeAttributeOpt>(&c.const_pool, stack_map_class)
+        .expect("failed to parse Code")
+        .expect("no Code attribute");
+    let info = code
+        .find_attribute_info(&c.const_pool, stack_map_class, "StackMapTable")
+        .unwrap();
+    assert_eq!(
+        code_opt
+            .find_attribute(stack_map_class, &c.const_pool, "StackMapTable")
+            .expect("failed to search for StackMapTable"),
+        Some(info.info.clone())
+    );
+    assert_eq!(
+        code_opt
+            .find_attribute(stack_map_class, &c.const_pool, "Nonexistent")
+            .expect("failed to search for Nonexistent"),
+        None
+    );
+
+    // Searching data that the code wasn't parsed from doesn't read past its end
+    let short = &stack_map_class[..code_opt.attributes_start - 1];
+    assert!(matches!(
+        code_opt.find_attribute(short, &c.const_pool, "StackMapTable"),
+        Err(LoadError::OutOfRange { .. })
+    ));
+}
+
+#[test]
+fn test_find_source_file_attribute() {
+    use classfile_parser::attribute_info::{HasAttributes, SourceFileAttribute};
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("not a class file");
+
+    let source_file = c
+        .find_attribute::<SourceFileAttribute>(&c.const_pool, class_data)
+        .expect("failed to parse SourceFile")
+        .expect("no SourceFile attribute");
+    let source_file = c
+        .const_pool
+        .get_t(source_file.sourcefile_index)
+        .expect("SourceFile should reference a utf8 constant");
+    assert_eq!(source_file.as_text(class_data), "BasicClass.java");
+}
+
+#[test]
+#[cfg(feature = "stackmap")]
+fn test_stack_map_table_reencode() {
+    use classfile_parser::attribute_info::{
+        CodeAttribute, HasAttributes, StackMapTableAttribute, VerificationTypeInfo,
+    };
+    use classfile_parser::descriptor::method::MethodDescriptor;
+    use classfile_parser::method_info::MethodAccessFlags;
+
+    let classes: [&[u8]; 3] = [
+        include_bytes!("../java-assets/compiled-classes/Instructions.class"),
+        include_bytes!("../java-assets/compiled-classes/SwitchMap.class"),
+        include_bytes!("../java-assets/compiled-classes/Factorial.class"),
+    ];
+    let mut tables = 0;
+    for data in classes {
+        let (_, c) = class_parser(ParseData::new(data)).expect("not a class file");
+        for method in c.methods.iter() {
+            let pool = &c.const_pool;
+            let code: CodeAttribute = match method.find_attribute(pool, data).unwrap() {
+                Some(code) => code,
+                None => continue,
+            };
+            let info = match code.find_attribute_info(pool, data, "StackMapTable") {
+                Some(info) => info.info.clone(),
+                None => continue,
+            };
+            let table: StackMapTableAttribute = code.find_attribute(pool, data).unwrap().unwrap();
+
+            // Only the number of initial locals matters for re-encoding
+            let descriptor = pool.get_t(method.descriptor_index).unwrap().as_bytes(data);
+            let mut count = MethodDescriptor::parse(descriptor)
+                .unwrap()
+                .parameter_types
+                .len();
+            if !method.access_flags.contains(MethodAccessFlags::STATIC) {
+                count += 1;
+            }
+            let initial = vec![VerificationTypeInfo::Top; count];
+
+            let states = table.states(&initial).unwrap();
+            assert_eq!(states.len(), table.entries.len());
+            let encoded = StackMapTableAttribute::from_states(&initial, &states).unwrap();
+            assert_eq!(encoded.to_bytes(), &data[info]);
+            tables += 1;
+        }
+    }
+    assert!(tables >= 3);
+}
+
+#[test]
+#[cfg(feature = "stackmap")]
+fn test_stack_map_table_from_states() {
+    use classfile_parser::attribute_info::{
+        FrameState, StackMapError, StackMapFrame, StackMapTableAttribute,
+        VerificationTypeInfo::{Integer, Long, Null},
+    };
+
+    let state = |offset, locals: &[_], stack: &[_]| FrameState {
+        offset,
+        locals: locals.to_vec(),
+        stack: stack.to_vec(),
+    };
+    let states = [
+        state(3, &[Integer], &[]),
+        state(5, &[Integer, Long, Integer], &[]),
+        state(100, &[Integer, Long, Integer], &[Null]),
+        state(101, &[Integer], &[]),
+        state(102, &[Long], &[Integer, Integer]),
+    ];
+    let table = StackMapTableAttribute::from_states(&[Integer], &states).unwrap();
+    let kinds: Vec<_> = table
+        .entries
+        .iter()
+        .map(|frame| match frame {
+            StackMapFrame::SameFrame { .. } => "same",
+            StackMapFrame::AppendFrame { .. } => "append",
+            StackMapFrame::SameLocals1StackItemFrameExtended { .. } => "same_locals_1_extended",
+            StackMapFrame::ChopFrame { .. } => "chop",
+            StackMapFrame::FullFrame { .. } => "full",
+            _ => "other",
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        ["same", "append", "same_locals_1_extended", "chop", "full"]
+    );
+    assert_eq!(
+        table.to_bytes(),
+        [
+            0, 5, // number_of_entries
+            3, // same_frame
+            253, 0, 1, 4, 1, // append_frame of a long and an int
+            247, 0, 94, 5, // same_locals_1_stack_item_frame_extended of null
+            249, 0, 0, // chop_frame of two locals
+            255, 0, 0, 0, 1, 4, 0, 2, 1, 1, // full_frame
+        ]
+    );
+    assert_eq!(table.states(&[Integer]).unwrap(), states);
+
+    assert_eq!(
+        StackMapTableAttribute::from_states(&[], &[state(4, &[], &[]), state(4, &[], &[])])
+            .unwrap_err(),
+        StackMapError::UnorderedOffset(4)
+    );
+}
@@ -0,0 +1,137 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{CodeAttributeBuilder, HasAttributes, SourceFileAttribute};
+use classfile_parser::builder::{ClassBuilderError, ClassFileBuilder, ConstantPoolBuilder};
+use classfile_parser::constant_info::ConstantInfo;
+use classfile_parser::field_info::FieldAccessFlags;
+use classfile_parser::method_info::{MethodAccessFlags, MethodBuilder};
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[test]
+fn test_constant_pool_builder() {
+    let mut pool = ConstantPoolBuilder::new();
+    let text = pool.utf8("hello").unwrap();
+    let string = pool.string("hello").unwrap();
+    assert_eq!(pool.utf8("hello").unwrap(), text);
+    assert_eq!(pool.string("hello").unwrap(), string);
+    assert_eq!(pool.pool().len(), 2);
+
+    // Long and double constants take two slots
+    let long = pool.long(1 << 40).unwrap();
+    let int = pool.integer(7).unwrap();
+    assert_eq!(int.0, long.0 + 2);
+    assert_eq!(pool.long(1 << 40).unwrap(), long);
+    let nan = pool.double(f64::NAN).unwrap();
+    assert_eq!(pool.double(f64::NAN).unwrap(), nan);
+    assert_ne!(pool.float(0.0).unwrap().0, pool.float(-0.0).unwrap().0);
+
+    let method = pool
+        .method_ref("java/lang/Object", "<init>", "()V")
+        .unwrap();
+    let before = pool.pool().len();
+    let again = pool
+        .method_ref("java/lang/Object", "<init>", "()V")
+        .unwrap();
+    assert_eq!(again, method);
+    assert_eq!(pool.pool().len(), before);
+    let field = pool
+        .field_ref("java/lang/System", "out", "Ljava/io/PrintStream;")
+        .unwrap();
+    assert!(matches!(
+        pool.pool().get(field),
+        Some(ConstantInfo::FieldRef(_))
+    ));
+
+    // Nul and supplementary characters are written as modified UTF-8
+    let odd = pool.utf8("a\0\u{1F600}").unwrap();
+    assert_eq!(
+        pool.utf8_bytes(b"a\xC0\x80\xED\xA0\xBD\xED\xB8\x80")
+            .unwrap(),
+        odd
+    );
+
+    let (pool, data) = pool.finish();
+    assert_eq!(pool.get_t(text).unwrap().as_text(&data), "hello");
+    assert_eq!(pool.get_t(odd).unwrap().as_text(&data), "a\0\u{1F600}");
+    assert!(matches!(
+        ConstantPoolBuilder::new().utf8(&"a".repeat(70000)),
+        Err(ClassBuilderError::Utf8TooLong)
+    ));
+}
+
+#[test]
+fn test_class_file_builder() {
+    let mut class = ClassFileBuilder::new("com/example/Built", Some("java/lang/Object")).unwrap();
+    class.interface("java/lang/Runnable").unwrap();
+    class
+        .field(FieldAccessFlags::PRIVATE, "count", "I")
+        .unwrap();
+    assert_eq!(
+        class.field(FieldAccessFlags::PUBLIC, "count", "I").err(),
+        Some(ClassBuilderError::DuplicateField)
+    );
+
+    let init = class
+        .constants()
+        .method_ref("java/lang/Object", "<init>", "()V")
+        .unwrap();
+    let mut code = CodeAttributeBuilder::new(1, 1);
+    // aload_0, invokespecial, return
+    code.emit(&[0x2A, 0xB7]);
+    code.emit(&init.0.to_be_bytes());
+    code.emit(&[0xB1]);
+    let mut constructor = MethodBuilder::new(MethodAccessFlags::PUBLIC, "<init>", "()V");
+    constructor.code(code);
+    class.method(constructor);
+    let mut code = CodeAttributeBuilder::new(0, 1);
+    code.emit(&[0xB1]);
+    let mut run = MethodBuilder::new(MethodAccessFlags::PUBLIC, "run", "()V");
+    run.code(code);
+    class.method(run);
+
+    let source = class.constants().utf8("Built.java").unwrap();
+    class
+        .attribute("SourceFile", &source.0.to_be_bytes())
+        .unwrap();
+
+    let (built, data) = class.build().unwrap();
+    assert_eq!(built.methods.len(), 2);
+    assert_eq!(built.this_class_name(&data).unwrap(), "com/example/Built");
+    assert!(built.code_errors(&data).unwrap().is_empty());
+
+    // Writing it out gives a class file that parses to the same class
+    let bytes = built.to_bytes(&data).unwrap();
+    let c = ClassFile::parse(&bytes, &ParseOptions::strict()).unwrap();
+    assert_eq!(c.version.major, 52);
+    assert_eq!(c.raw_flags(), 0x0021);
+    assert_eq!(c.this_class_name(&bytes).unwrap(), "com/example/Built");
+    assert_eq!(c.super_class_name(&bytes).unwrap(), "java/lang/Object");
+    assert_eq!(c.interface_names(&bytes).unwrap(), ["java/lang/Runnable"]);
+    assert!(c.find_field(&bytes, "count", "I").is_some());
+    assert!(c.find_method(&bytes, "<init>", "()V").is_some());
+    assert!(c.find_method(&bytes, "run", "()V").is_some());
+    let source = c
+        .find_attribute::<SourceFileAttribute>(&c.const_pool, &bytes)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        c.const_pool
+            .get_t(source.sourcefile_index)
+            .unwrap()
+            .as_text(&bytes),
+        "Built.java"
+    );
+    assert!(c
+        .stack_depth_reports(&bytes)
+        .unwrap()
+        .iter()
+        .all(|report| report.matches()));
+    assert_eq!(c.to_bytes(&bytes).unwrap(), bytes);
+
+    let object = ClassFileBuilder::new("java/lang/Object", None)
+        .unwrap()
+        .build()
+        .unwrap()
+        .0;
+    assert!(object.super_class.is_zero());
+}
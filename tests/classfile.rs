@@ -2,7 +2,8 @@ extern crate classfile_parser;
 extern crate nom;
 
 use classfile_parser::class_parser;
-use classfile_parser::constant_info::ConstantInfo;
+use classfile_parser::constant_info::{ConstantInfo, NameAndTypeConstant, Utf8Constant};
+use classfile_parser::constant_pool::{ConstantPoolIndexRaw, ConstantPoolLookupError};
 use classfile_parser::parser::ParseData;
 
 #[test]
@@ -152,3 +153,2144 @@ fn test_malformed_class() {
 //         _ => panic!("Not a UTF type const?"),
 //     };
 // }
+
+#[test]
+fn test_constant_pool_indices_skip_wide_slots() {
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    assert!(c
+        .const_pool
+        .iter()
+        .any(|x| matches!(x, ConstantInfo::Unusable)));
+
+    let mut count = 0;
+    for index in c.const_pool.indices() {
+        assert!(!matches!(
+            c.const_pool.get(index),
+            Some(ConstantInfo::Unusable) | None
+        ));
+        count += 1;
+    }
+
+    let usable = c
+        .const_pool
+        .iter()
+        .filter(|x| !matches!(x, ConstantInfo::Unusable))
+        .count();
+    assert_eq!(count, usable);
+    assert_eq!(c.const_pool.iter_indexed().count(), usable);
+}
+
+#[test]
+fn test_utf8_iter() {
+    let class_data = include_bytes!("../java-assets/compiled-classes/UnicodeStrings.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    let pool = &c.const_pool;
+
+    let utf8: Vec<_> = pool.utf8_iter(class_data).collect();
+    let expected = pool
+        .iter()
+        .filter(|entry| matches!(entry, ConstantInfo::Utf8(_)))
+        .count();
+    assert_eq!(utf8.len(), expected);
+    for (index, text) in utf8.iter() {
+        assert_eq!(pool.get_t(*index).unwrap().as_text(class_data), *text);
+    }
+    assert!(utf8.iter().any(|(_, text)| text == "<init>"));
+}
+
+#[test]
+fn test_constant_pool_lookup_errors() {
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    let pool = &c.const_pool;
+    let (wide, _) = pool
+        .iter_indexed()
+        .find(|(_, entry)| matches!(entry, ConstantInfo::Long(_) | ConstantInfo::Double(_)))
+        .unwrap();
+    let unusable = ConstantPoolIndexRaw::<ConstantInfo>::new(wide.0 + 1);
+
+    assert!(pool.try_get(wide).is_ok());
+    assert_eq!(
+        pool.try_get(unusable).unwrap_err(),
+        ConstantPoolLookupError::UnusableSlot(wide.0 + 1)
+    );
+    assert_eq!(
+        pool.try_get(ConstantPoolIndexRaw::<ConstantInfo>::new(0))
+            .unwrap_err(),
+        ConstantPoolLookupError::ZeroIndex
+    );
+    let past_end = pool.len() + 1;
+    assert_eq!(
+        pool.try_get(ConstantPoolIndexRaw::<ConstantInfo>::new(past_end))
+            .unwrap_err(),
+        ConstantPoolLookupError::OutOfBounds(past_end)
+    );
+    assert_eq!(
+        pool.try_get_t(ConstantPoolIndexRaw::<Utf8Constant>::new(wide.0))
+            .unwrap_err(),
+        ConstantPoolLookupError::WrongType(wide.0)
+    );
+    assert_eq!(
+        pool.try_get_t(ConstantPoolIndexRaw::<Utf8Constant>::new(wide.0 + 1))
+            .unwrap_err(),
+        ConstantPoolLookupError::UnusableSlot(wide.0 + 1)
+    );
+    assert_eq!(
+        pool.try_get_t(c.this_class).map(|class| class.name_index),
+        Ok(pool.get_t(c.this_class).unwrap().name_index)
+    );
+}
+
+#[test]
+fn test_constant_pool_typed_getters() {
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    let pool = &c.const_pool;
+
+    assert_eq!(
+        pool.get_class_name(c.this_class, class_data).unwrap(),
+        "uk/co/palmr/karl/examples/BasicClass"
+    );
+    assert_eq!(
+        pool.get_class_name(c.super_class, class_data).unwrap(),
+        "java/lang/Object"
+    );
+    let this_name = pool.get_t(c.this_class).unwrap().name_index;
+    assert_eq!(
+        pool.get_utf8_text(this_name, class_data),
+        pool.get_class_name(c.this_class, class_data)
+    );
+    // The index of a Class entry doesn't refer to a Utf8 entry
+    let this_class = ConstantPoolIndexRaw::<Utf8Constant>::new(c.this_class.0);
+    assert_eq!(pool.get_utf8_text(this_class, class_data), None);
+
+    let (nat, _) = pool
+        .iter_indexed()
+        .find(|(_, entry)| matches!(entry, ConstantInfo::NameAndType(_)))
+        .unwrap();
+    let (name, descriptor) = pool
+        .get_name_and_type(ConstantPoolIndexRaw::<NameAndTypeConstant>::new(nat.0))
+        .unwrap();
+    assert_eq!(name.as_text(class_data), "<init>");
+    assert_eq!(descriptor.as_text(class_data), "()V");
+    assert!(pool
+        .get_name_and_type(ConstantPoolIndexRaw::<NameAndTypeConstant>::new(0))
+        .is_none());
+}
+
+#[test]
+fn test_lazy_constant_pool() {
+    use classfile_parser::{ClassFileOpt, ParseOptions};
+
+    // Has Long and Double entries, which are followed by unusable slots
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Constants.class");
+    let eager = ClassFileOpt::parse(data, &ParseOptions::default()).unwrap();
+    assert!(eager.lazy_const_pool.is_none());
+
+    let options = ParseOptions {
+        lazy_constant_pool: true,
+        ..ParseOptions::default()
+    };
+    let mut c = ClassFileOpt::parse(data, &options).unwrap();
+    assert!(c.const_pool.is_empty());
+    let lazy = c.lazy_const_pool.clone().unwrap();
+    assert_eq!(lazy.len(), eager.const_pool.len());
+    assert!(lazy.offset(0u16).is_some());
+    assert!(lazy.get(lazy.len(), data).is_none());
+
+    // ConstantInfo has no PartialEq, so the entries are compared by their debug output
+    for index in eager.const_pool.indices() {
+        let entry = format!("{:?}", eager.const_pool.get(index).unwrap());
+        assert_eq!(format!("{:?}", c.constant(data, index).unwrap()), entry);
+        assert_eq!(format!("{:?}", lazy.get(index, data).unwrap()), entry);
+    }
+    assert_eq!(
+        lazy.get_class_name(c.this_class, data),
+        eager.const_pool.get_class_name(eager.this_class, data)
+    );
+
+    c.load_constant_pool(data).unwrap();
+    assert!(c.lazy_const_pool.is_none());
+    assert_eq!(c.const_pool.dump(data), eager.const_pool.dump(data));
+    assert_eq!(c.this_class_name(data), eager.this_class_name(data));
+
+    // The entries are parsed from the data as it is when they are asked for, so changing the
+    // Class entry into a String entry of the same size is seen
+    let mut changed = data.to_vec();
+    changed[lazy.offset(c.this_class).unwrap()] = 8;
+    assert!(matches!(
+        lazy.get(c.this_class, &changed),
+        Some(ConstantInfo::String(_))
+    ));
+    assert_eq!(lazy.get_class_name(c.this_class, &changed), None);
+}
+
+#[test]
+fn test_lazy_constant_pool_helpers() {
+    use classfile_parser::{ClassFileOpt, ParseOptions};
+
+    let options = ParseOptions {
+        lazy_constant_pool: true,
+        ..ParseOptions::default()
+    };
+    for data in [
+        &include_bytes!("../java-assets/compiled-classes/BasicClass.class")[..],
+        &include_bytes!("../java-assets/compiled-classes/Constants.class")[..],
+        &include_bytes!("../java-assets/compiled-classes/PrivateMembers.class")[..],
+        &include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class")[..],
+    ] {
+        // The helpers find the same things whether or not the pool is lazy
+        let eager = ClassFileOpt::parse(data, &ParseOptions::default()).unwrap();
+        let lazy = ClassFileOpt::parse(data, &options).unwrap();
+        assert!(lazy.lazy_const_pool.is_some());
+
+        assert!(lazy.this_class_name(data).is_some());
+        assert_eq!(lazy.this_class_name(data), eager.this_class_name(data));
+        assert_eq!(lazy.super_class_name(data), eager.super_class_name(data));
+        assert_eq!(
+            lazy.interface_names(data).unwrap(),
+            eager.interface_names(data).unwrap()
+        );
+        assert_eq!(
+            lazy.implements(data, "java/io/Serializable").unwrap(),
+            eager.implements(data, "java/io/Serializable").unwrap()
+        );
+        assert_eq!(
+            lazy.referenced_members(data).unwrap(),
+            eager.referenced_members(data).unwrap()
+        );
+        assert_eq!(
+            lazy.load_attribute_with_name(data, "SourceFile").unwrap(),
+            eager.load_attribute_with_name(data, "SourceFile").unwrap()
+        );
+        assert!(lazy
+            .load_attribute_with_name(data, "SourceFile")
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            lazy.source_file(data).unwrap().unwrap().sourcefile_index,
+            eager.source_file(data).unwrap().unwrap().sourcefile_index
+        );
+        assert_eq!(
+            lazy.bootstrap_methods(data).unwrap().is_some(),
+            eager.bootstrap_methods(data).unwrap().is_some()
+        );
+        assert_eq!(
+            lazy.load_method_attribute_info_at_with_name(data, 0, "Code")
+                .unwrap(),
+            eager
+                .load_method_attribute_info_at_with_name(data, 0, "Code")
+                .unwrap()
+        );
+        assert_eq!(
+            lazy.method_sizes(data).unwrap(),
+            eager.method_sizes(data).unwrap()
+        );
+        assert_eq!(
+            lazy.static_final_values(data).unwrap(),
+            eager.static_final_values(data).unwrap()
+        );
+    }
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFileOpt::parse(data, &options).unwrap();
+    let init = c
+        .find_method(data, "<init>", "(Ljava/lang/String;Ljava/lang/Integer;)V")
+        .unwrap();
+    assert!(init.is_some());
+    let field = c.find_field(data, "mString", "Ljava/lang/String;").unwrap();
+    assert!(field.is_some());
+    assert!(c.find_field(data, "mString", "I").unwrap().is_none());
+}
+
+#[test]
+fn test_constant_pool_copy_on_write() {
+    use classfile_parser::constant_info::{IntegerConstant, LongConstant};
+    use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    let len = c.const_pool.len();
+
+    // Changing a clone leaves the original alone
+    let mut copy = c.clone();
+    let index = ConstantPoolIndexRaw::<ConstantInfo>::new(1);
+    *copy.const_pool.get_mut(index).unwrap() = ConstantInfo::Integer(IntegerConstant { value: 5 });
+    assert!(matches!(
+        copy.const_pool.get(index),
+        Some(ConstantInfo::Integer(IntegerConstant { value: 5 }))
+    ));
+    assert!(!matches!(
+        c.const_pool.get(index),
+        Some(ConstantInfo::Integer(_))
+    ));
+    assert!(copy
+        .const_pool
+        .get_mut(ConstantPoolIndexRaw::<ConstantInfo>::new(0))
+        .is_none());
+    assert!(copy
+        .const_pool
+        .get_mut(ConstantPoolIndexRaw::<ConstantInfo>::new(len + 1))
+        .is_none());
+
+    let pushed = copy
+        .const_pool
+        .push(ConstantInfo::Long(LongConstant { value: 7 }))
+        .unwrap();
+    assert_eq!(pushed.0, len + 1);
+    assert_eq!(copy.const_pool.len(), len + 2);
+    assert!(matches!(
+        copy.const_pool
+            .get(ConstantPoolIndexRaw::<ConstantInfo>::new(len + 2)),
+        Some(ConstantInfo::Unusable)
+    ));
+    assert_eq!(c.const_pool.len(), len);
+}
+
+#[test]
+fn test_try_from_bytes() {
+    use classfile_parser::{ClassFile, ClassFileOpt, ParseError};
+    use std::convert::TryFrom;
+
+    let valid_class: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::try_from(valid_class).expect("failed to parse class");
+    assert_eq!(c.methods.len(), c.methods_count as usize);
+    let c = ClassFileOpt::try_from(valid_class).expect("failed to parse class");
+    assert_eq!(c.methods.len(), 6);
+
+    let malformed_class: &[u8] = include_bytes!("../java-assets/compiled-classes/malformed.class");
+    assert_eq!(
+        ClassFile::try_from(malformed_class).unwrap_err(),
+        ParseError::BadMagic
+    );
+
+    let truncated = &valid_class[..valid_class.len() / 2];
+    assert!(matches!(
+        ClassFile::try_from(truncated),
+        Err(ParseError::Malformed { .. })
+    ));
+}
+
+#[test]
+fn test_empty_constant_pool() {
+    use classfile_parser::scan::class_layout;
+    use classfile_parser::{ClassFile, ClassFileOpt, ParseError};
+    use std::convert::TryFrom;
+
+    let valid_class: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut empty = valid_class.to_vec();
+    empty[8] = 0;
+    empty[9] = 0;
+    // Only the header, and a header followed by the rest of a real class
+    for data in [&empty[..10], &empty[..]] {
+        assert_eq!(
+            ClassFile::try_from(data).unwrap_err(),
+            ParseError::EmptyConstantPool
+        );
+        assert_eq!(
+            ClassFileOpt::try_from(data).unwrap_err(),
+            ParseError::EmptyConstantPool
+        );
+        assert_eq!(
+            class_layout(data).unwrap_err(),
+            ParseError::EmptyConstantPool
+        );
+        assert!(class_parser(ParseData::new(data)).is_err());
+    }
+
+    // The largest count, without any constants to back it
+    let mut huge = valid_class[..8].to_vec();
+    huge.extend_from_slice(&[0xFF, 0xFF]);
+    assert!(matches!(
+        ClassFile::try_from(&huge[..]),
+        Err(ParseError::Malformed { .. })
+    ));
+}
+
+#[test]
+fn test_structured_errors() {
+    use classfile_parser::error::{ItemKind, Malformation};
+    use classfile_parser::{parse_class, ClassFile, ClassFileOpt, LoadError, ParseError};
+    use std::convert::TryFrom;
+
+    assert!(matches!(
+        parse_class("./java-assets/compiled-classes/Missing"),
+        Err(ParseError::Io {
+            kind: std::io::ErrorKind::NotFound,
+            ..
+        })
+    ));
+
+    let valid_class: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let truncated = &valid_class[..valid_class.len() - 1];
+    assert!(matches!(
+        ClassFile::try_from(truncated),
+        Err(ParseError::Malformed {
+            reason: Malformation::Truncated,
+            ..
+        })
+    ));
+
+    // The tag of the first constant
+    let mut bad_tag = valid_class.to_vec();
+    bad_tag[10] = 13;
+    match ClassFile::try_from(&bad_tag[..]).unwrap_err() {
+        ParseError::Malformed {
+            offset,
+            item: Some(item),
+            reason,
+        } => {
+            assert_eq!(offset, 10);
+            assert_eq!((item.kind, item.index), (ItemKind::Constant, 1));
+            assert_eq!(reason, Malformation::InvalidConstantTag(13));
+        }
+        err => panic!("expected an invalid tag, got {:?}", err),
+    }
+
+    let c = ClassFileOpt::try_from(valid_class).unwrap();
+    assert!(matches!(
+        c.load_method_at(valid_class, 6),
+        Err(LoadError::OutOfRange { index: 6, len: 6 })
+    ));
+    let err = c.load_method_at(&valid_class[..c.methods.start_pos() + 4], 0);
+    assert!(matches!(
+        err,
+        Err(LoadError::Parse(ParseError::Malformed {
+            reason: Malformation::Truncated,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn test_utf8_constant_range() {
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(class_data, &ParseOptions::default()).unwrap();
+    let index = c
+        .const_pool
+        .iter_indexed()
+        .find_map(|(index, constant)| match constant {
+            ConstantInfo::Utf8(utf8) if utf8.as_text(class_data) == "BasicClass.java" => {
+                Some(index)
+            }
+            _ => None,
+        })
+        .unwrap();
+    let range = c
+        .const_pool
+        .get_t(ConstantPoolIndexRaw::<Utf8Constant>::new(index.0))
+        .unwrap()
+        .range();
+    assert_eq!(&class_data[range.clone()], b"BasicClass.java");
+    assert_eq!(
+        &class_data[range.start - 2..range.start],
+        &(range.len() as u16).to_be_bytes()
+    );
+
+    // Patch the bytes in place, keeping the length
+    let mut data = class_data.to_vec();
+    data[range.clone()].copy_from_slice(b"OtherClass.java");
+    let c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let utf8: &Utf8Constant = c
+        .const_pool
+        .get_t(ConstantPoolIndexRaw::<Utf8Constant>::new(index.0))
+        .unwrap();
+    assert_eq!(utf8.range(), range);
+    assert_eq!(utf8.as_text(&data), "OtherClass.java");
+}
+
+#[test]
+fn test_descriptor_cache() {
+    use std::rc::Rc;
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, mut c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+
+    // Without the cache every call parses anew
+    let method = &c.methods[0];
+    let first = c
+        .parsed_descriptor_cached(method.descriptor_index, class_data)
+        .unwrap();
+    let second = c
+        .parsed_descriptor_cached(method.descriptor_index, class_data)
+        .unwrap();
+    assert!(!Rc::ptr_eq(&first, &second));
+    assert_eq!(first, second);
+
+    c.enable_descriptor_cache();
+    for method in c.methods.iter() {
+        let desc = c
+            .parsed_descriptor_cached(method.descriptor_index, class_data)
+            .unwrap();
+        assert!(desc.as_method().is_some());
+        let again = c
+            .parsed_descriptor_cached(method.descriptor_index, class_data)
+            .unwrap();
+        assert!(Rc::ptr_eq(&desc, &again));
+    }
+    for field in c.fields.iter() {
+        let desc = c
+            .parsed_descriptor_cached(field.descriptor_index, class_data)
+            .unwrap();
+        assert!(desc.as_field().is_some());
+    }
+
+    let cache = c.descriptor_cache.as_ref().unwrap();
+    assert!(cache.len() < c.methods.len() + c.fields.len());
+}
+
+#[test]
+fn test_trailing_bytes() {
+    use classfile_parser::{ClassFile, ClassFileOpt, ParseError, ParseOptions};
+
+    let valid_class: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut padded = valid_class.to_vec();
+    padded.extend_from_slice(b"payload");
+
+    // The JVM ignores the extra data, so by default we do too
+    assert!(ClassFile::parse(&padded, &ParseOptions::default()).is_ok());
+    assert!(ClassFile::parse(valid_class, &ParseOptions::strict()).is_ok());
+
+    let expected = ParseError::TrailingBytes {
+        offset: valid_class.len(),
+        len: 7,
+    };
+    assert_eq!(
+        ClassFile::parse(&padded, &ParseOptions::strict()).unwrap_err(),
+        expected
+    );
+    assert_eq!(
+        ClassFileOpt::parse(&padded, &ParseOptions::strict()).unwrap_err(),
+        expected
+    );
+}
+
+#[test]
+fn test_attributes_before_version() {
+    use classfile_parser::attribute_info::AttributeOwner;
+    use classfile_parser::{ClassFile, ParseError, ParseOptions};
+
+    let valid_class: &[u8] = include_bytes!("../java-assets/compiled-classes/Factorial.class");
+    let c = ClassFile::parse(valid_class, &ParseOptions::strict()).unwrap();
+    assert!(c.attribute_version_violations(valid_class).is_empty());
+
+    // Claim to be a Java 5 class file, which predates the StackMapTable attribute
+    let mut old = valid_class.to_vec();
+    old[6..8].copy_from_slice(&49u16.to_be_bytes());
+
+    let c = ClassFile::parse(&old, &ParseOptions::default()).unwrap();
+    let violations = c.attribute_version_violations(&old);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].name, "StackMapTable");
+    assert_eq!(violations[0].min_major, 50);
+    assert!(matches!(violations[0].owner, AttributeOwner::Code(_)));
+
+    assert_eq!(
+        ClassFile::parse(&old, &ParseOptions::strict()).unwrap_err(),
+        ParseError::AttributeBeforeVersion {
+            name: "StackMapTable".to_string(),
+            offset: violations[0].offset,
+            min_major: 50,
+        }
+    );
+}
+
+#[test]
+fn test_max_nesting_depth() {
+    use classfile_parser::attribute_info::{visit_annotations, AnnotationVisitor, HasAttributes};
+    use classfile_parser::{ClassFile, ParseError, ParseOptions};
+
+    let valid_class: &[u8] = include_bytes!("../java-assets/compiled-classes/Annotated.class");
+    let c = ClassFile::parse(valid_class, &ParseOptions::strict()).unwrap();
+    let name_index = c
+        .find_attribute_info(&c.const_pool, valid_class, "RuntimeVisibleAnnotations")
+        .unwrap()
+        .attribute_name_index
+        .0
+        .to_be_bytes();
+
+    // Add a class annotation with a string nested inside arrays
+    let nested = |levels: usize| {
+        let mut info = vec![0, 1];
+        info.extend_from_slice(&name_index);
+        info.extend_from_slice(&[0, 1]);
+        info.extend_from_slice(&name_index);
+        for _ in 0..levels {
+            info.extend_from_slice(&[b'[', 0, 1]);
+        }
+        info.push(b's');
+        info.extend_from_slice(&name_index);
+
+        let mut data = valid_class.to_vec();
+        let count_offset = c.attributes[0].info.start - 8;
+        let count = u16::from_be_bytes([data[count_offset], data[count_offset + 1]]);
+        data[count_offset..count_offset + 2].copy_from_slice(&(count + 1).to_be_bytes());
+        data.extend_from_slice(&name_index);
+        data.extend_from_slice(&(info.len() as u32).to_be_bytes());
+        let start = data.len();
+        data.extend_from_slice(&info);
+        (data, start)
+    };
+
+    let options = ParseOptions {
+        max_nesting_depth: Some(8),
+        ..ParseOptions::default()
+    };
+    let (data, _) = nested(7);
+    assert!(ClassFile::parse(&data, &options).is_ok());
+    let (data, start) = nested(8);
+    assert_eq!(
+        ClassFile::parse(&data, &options).unwrap_err(),
+        ParseError::TooDeeplyNested {
+            offset: start + 8 + 3 * 8,
+            max_depth: 8,
+        }
+    );
+
+    // Nothing is checked by default, but visiting still stops at the limit
+    struct Nothing;
+    impl AnnotationVisitor for Nothing {}
+    let (data, _) = nested(1000);
+    let c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let attr = c.attributes.last().unwrap();
+    assert!(visit_annotations(attr, &data, &mut Nothing).is_err());
+    assert!(matches!(
+        ClassFile::parse(&data, &ParseOptions::strict()),
+        Err(ParseError::TooDeeplyNested { .. })
+    ));
+    let (data, _) = nested(200);
+    let c = ClassFile::parse(&data, &ParseOptions::strict()).unwrap();
+    let attr = c.attributes.last().unwrap();
+    assert!(visit_annotations(attr, &data, &mut Nothing).is_ok());
+}
+
+#[test]
+fn test_static_final_values() {
+    use classfile_parser::constant_info::ConstantValue;
+    use classfile_parser::{ClassFile, ClassFileOpt, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Constants.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let values = c.static_final_values(data).unwrap();
+
+    assert_eq!(values.len(), 6);
+    assert_eq!(values["ANSWER"], ConstantValue::Integer(42));
+    assert_eq!(values["ENABLED"], ConstantValue::Integer(1));
+    assert_eq!(values["BIG"], ConstantValue::Long(1 << 40));
+    assert_eq!(values["HALF"], ConstantValue::Float(0.5));
+    assert_eq!(values["RATE"], ConstantValue::Double(2.5));
+    assert_eq!(
+        values["GREETING"],
+        ConstantValue::String("Hello".to_string())
+    );
+    assert!(!values.contains_key("COMPUTED"));
+    assert!(!values.contains_key("instance"));
+
+    let c = ClassFileOpt::parse(data, &ParseOptions::default()).unwrap();
+    assert_eq!(c.static_final_values(data).unwrap(), values);
+}
+
+#[test]
+fn test_method_sizes() {
+    use classfile_parser::scan::class_layout;
+    use classfile_parser::{ClassFile, ClassFileOpt, LoadError, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Factorial.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let sizes = c.method_sizes(data).unwrap();
+    assert_eq!(sizes.len(), c.methods.len());
+
+    // The methods table is made up of exactly the methods
+    let layout = class_layout(data).unwrap();
+    let total: usize = sizes.iter().map(|size| size.total).sum();
+    assert_eq!(total, layout.methods.end - layout.methods.start);
+
+    for size in sizes.iter() {
+        let code = size.code.unwrap();
+        assert!(code.code_length > 0);
+        // max stack, max locals, and the code length come before the code
+        assert!(code.attribute >= 6 + 8 + code.code_length);
+        assert!(code.attribute < size.total);
+    }
+
+    let opt = ClassFileOpt::parse(data, &ParseOptions::default()).unwrap();
+    assert_eq!(opt.method_sizes(data).unwrap(), sizes);
+
+    // Data that ends before the fields and methods is an error rather than a panic
+    let short = &data[..opt.fields.start_pos() - 1];
+    assert!(matches!(
+        opt.method_sizes(short),
+        Err(LoadError::OutOfRange { len, .. }) if len == short.len()
+    ));
+    assert!(opt
+        .find_method(short, "main", "([Ljava/lang/String;)V")
+        .unwrap()
+        .is_none());
+    assert!(opt.find_field(short, "count", "I").unwrap().is_none());
+}
+
+#[test]
+fn test_resolve_ldc() {
+    use classfile_parser::constant_info::{ConstantInfo, LdcError, LdcKind, LoadableConstant};
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Constants.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let find = |c: &ClassFile, f: fn(&ConstantInfo) -> bool| {
+        c.const_pool
+            .iter_indexed()
+            .find(|(_, e)| f(e))
+            .unwrap()
+            .0
+             .0
+    };
+    let integer = find(&c, |e| matches!(e, ConstantInfo::Integer(_)));
+    let long = find(&c, |e| matches!(e, ConstantInfo::Long(_)));
+    let string = find(&c, |e| matches!(e, ConstantInfo::String(_)));
+    let class = find(&c, |e| matches!(e, ConstantInfo::Class(_)));
+    let utf8 = find(&c, |e| matches!(e, ConstantInfo::Utf8(_)));
+
+    assert!(matches!(
+        c.resolve_ldc(LdcKind::Ldc, integer, data),
+        Ok(LoadableConstant::Integer(42))
+    ));
+    assert!(matches!(
+        c.resolve_ldc(LdcKind::Ldc2W, long, data),
+        Ok(LoadableConstant::Long(v)) if v == 1 << 40
+    ));
+    assert!(matches!(
+        c.resolve_ldc(LdcKind::LdcW, string, data),
+        Ok(LoadableConstant::String(_))
+    ));
+    assert!(matches!(
+        c.resolve_ldc(LdcKind::LdcW, class, data),
+        Ok(LoadableConstant::Class(_))
+    ));
+
+    assert_eq!(
+        c.resolve_ldc(LdcKind::LdcW, long, data).unwrap_err(),
+        LdcError::WrongWidth
+    );
+    assert_eq!(
+        c.resolve_ldc(LdcKind::Ldc2W, integer, data).unwrap_err(),
+        LdcError::WrongWidth
+    );
+    assert_eq!(
+        c.resolve_ldc(LdcKind::Ldc, utf8, data).unwrap_err(),
+        LdcError::NotLoadable
+    );
+    assert_eq!(
+        c.resolve_ldc(LdcKind::Ldc, 0, data).unwrap_err(),
+        LdcError::InvalidIndex
+    );
+    // An ldc can't have an index that takes two bytes, whatever is there
+    assert_eq!(
+        c.resolve_ldc(LdcKind::Ldc, 256, data).unwrap_err(),
+        LdcError::IndexTooLarge
+    );
+    assert_eq!(
+        c.resolve_ldc(LdcKind::LdcW, 256, data).unwrap_err(),
+        LdcError::InvalidIndex
+    );
+
+    // Class constants could not be loaded before Java 5, and an owned constant can be kept while
+    // the class is changed
+    let owned = c
+        .resolve_ldc(LdcKind::LdcW, class, data)
+        .unwrap()
+        .to_owned();
+    c.version.major = 48;
+    assert_eq!(
+        c.resolve_ldc(LdcKind::LdcW, class, data).unwrap_err(),
+        LdcError::UnsupportedVersion { min_major: 49 }
+    );
+    assert!(matches!(owned, LoadableConstant::Class(_)));
+}
+
+#[test]
+fn test_signature_mismatches() {
+    use classfile_parser::attribute_info::AttributeOwner;
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    assert!(c.signature_mismatches(data).is_empty());
+
+    // The constructor of an inner class takes the outer instance, which its signature leaves out
+    let inner: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics$Inner.class");
+    let inner_class = ClassFile::parse(inner, &ParseOptions::default()).unwrap();
+    assert!(inner_class.signature_mismatches(inner).is_empty());
+
+    // Give `T value` the descriptor of `int plain`, as a careless rewriter might
+    let position = |c: &ClassFile, field_name: &str| {
+        c.fields.iter().position(|f| {
+            let name = c.const_pool.get_t(f.name_index).unwrap();
+            name.as_text(data) == field_name
+        })
+    };
+    let value = position(&c, "value");
+    let plain = position(&c, "plain");
+    let (value, plain) = (value.unwrap(), plain.unwrap());
+    c.fields[value].descriptor_index = c.fields[plain].descriptor_index;
+
+    let mismatches = c.signature_mismatches(data);
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].owner, AttributeOwner::Field(value));
+    assert_eq!(mismatches[0].signature, "TT;");
+    assert_eq!(mismatches[0].erased.as_deref(), Some("Ljava/lang/Number;"));
+    assert_eq!(mismatches[0].descriptor, "I");
+}
+
+#[test]
+fn test_generic_signatures() {
+    use classfile_parser::attribute_info::{HasAttributes, SignatureAttribute};
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+
+    let class = c
+        .find_attribute::<SignatureAttribute>(pool, data)
+        .unwrap()
+        .unwrap()
+        .class_signature(pool, data)
+        .unwrap();
+    let parameters: Vec<_> = class
+        .type_parameters
+        .iter()
+        .map(|parameter| parameter.to_string())
+        .collect();
+    assert_eq!(
+        parameters,
+        ["T extends java.lang.Number", "U extends java.lang.Object"]
+    );
+    assert_eq!(class.super_class.to_string(), "java.lang.Object");
+
+    let member_name =
+        |index: ConstantPoolIndexRaw<Utf8Constant>| pool.get_t(index).unwrap().as_text(data);
+    let field = c
+        .fields
+        .iter()
+        .find(|field| member_name(field.name_index) == "nested")
+        .unwrap();
+    let field = field
+        .find_attribute::<SignatureAttribute>(pool, data)
+        .unwrap()
+        .unwrap()
+        .field_signature(pool, data)
+        .unwrap();
+    assert_eq!(
+        field.to_string(),
+        "java.util.Map<java.lang.String, java.util.List<T>>"
+    );
+
+    let rendered: Vec<_> = ["max", "chained", "entry"]
+        .iter()
+        .map(|name| {
+            let method = c
+                .methods
+                .iter()
+                .find(|method| member_name(method.name_index) == *name)
+                .unwrap();
+            let method = method
+                .find_attribute::<SignatureAttribute>(pool, data)
+                .unwrap()
+                .unwrap()
+                .method_signature(pool, data)
+                .unwrap();
+            let parameters: Vec<_> = method
+                .parameter_types
+                .iter()
+                .map(|parameter| parameter.to_string())
+                .collect();
+            format!(
+                "{} ({}) {}",
+                method.type_parameters.len(),
+                parameters.join(", "),
+                method
+                    .return_type
+                    .map_or("void".to_string(), |ret| ret.to_string())
+            )
+        })
+        .collect();
+    assert_eq!(
+        rendered,
+        [
+            "1 (java.util.List<? extends E>) E",
+            "2 (A, B) void",
+            "0 () java.util.Map$Entry<T, U>",
+        ]
+    );
+}
+
+#[test]
+fn test_class_names() {
+    use classfile_parser::{parse_class_opt_from_bytes, ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let (mut opt, _) = parse_class_opt_from_bytes(data).unwrap();
+    let name = "uk/co/palmr/karl/examples/BasicClass";
+    assert_eq!(c.this_class_name(data).unwrap(), name);
+    assert_eq!(opt.this_class_name(data).unwrap(), name);
+    assert_eq!(c.super_class_name(data).unwrap(), "java/lang/Object");
+    assert_eq!(opt.super_class_name(data).unwrap(), "java/lang/Object");
+
+    // Like java/lang/Object itself
+    opt.super_class = ConstantPoolIndexRaw::new(0);
+    assert!(opt.super_class_name(data).is_none());
+    // Not a class entry
+    opt.this_class = ConstantPoolIndexRaw::new(opt.this_class.0 - 1);
+    assert!(opt.this_class_name(data).is_none());
+}
+
+#[test]
+fn test_find_members() {
+    use std::borrow::Cow;
+
+    use classfile_parser::attribute_info::{CodeAttribute, HasAttributes};
+    use classfile_parser::{parse_class_opt_from_bytes, ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let (mut opt, _) = parse_class_opt_from_bytes(data).unwrap();
+    let getter = ("getInteger", "()Ljava/lang/Integer;");
+
+    let method = c.find_method(data, getter.0, getter.1).unwrap();
+    let code = method
+        .find_attribute::<CodeAttribute>(&c.const_pool, data)
+        .unwrap();
+    assert!(code.is_some());
+    assert!(c.find_method(data, getter.0, "()I").is_none());
+    assert!(c.find_method(data, "getSize", "()J").is_some());
+    let field = c.find_field(data, "mString", "Ljava/lang/String;");
+    assert!(field.is_some());
+    assert!(c.find_field(data, "mString", "I").is_none());
+
+    // Without loading the members, only the match is parsed, and it has its attributes
+    let lazy = opt.find_method(data, getter.0, getter.1).unwrap().unwrap();
+    assert_eq!(lazy.name_index, method.name_index);
+    assert_eq!(lazy.attributes, method.attributes);
+    assert!(opt.find_method(data, "getSize", "()I").unwrap().is_none());
+    let lazy = opt
+        .find_field(data, "mInteger", "Ljava/lang/Integer;")
+        .unwrap()
+        .unwrap();
+    assert_eq!(lazy.attributes_count, 0);
+    assert!(opt.find_field(data, "count", "I").unwrap().is_none());
+
+    opt.load_all_methods_mut(data).unwrap();
+    let loaded = opt.find_method(data, getter.0, getter.1).unwrap().unwrap();
+    assert!(matches!(loaded, Cow::Borrowed(_)));
+}
+
+#[test]
+fn test_member_signatures() {
+    use std::collections::HashMap;
+
+    use classfile_parser::descriptor::{DescriptorType, DescriptorTypeBasic};
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+
+    let methods: HashMap<_, _> = c.method_signatures(data).collect::<Result<_, _>>().unwrap();
+    assert_eq!(methods.len(), c.methods.len());
+    assert_eq!(
+        methods["getSize"].return_type,
+        Some(DescriptorType::Basic(DescriptorTypeBasic::Long))
+    );
+    assert_eq!(methods["<init>"].parameter_types.len(), 2);
+    assert_eq!(methods["<init>"].return_type, None);
+
+    let fields: HashMap<_, _> = c.field_signatures(data).collect::<Result<_, _>>().unwrap();
+    let string = DescriptorTypeBasic::ClassName(b"java/lang/String".as_slice().into());
+    assert_eq!(fields["mString"], DescriptorType::Basic(string));
+    assert_eq!(fields.len(), 2);
+}
+
+#[test]
+fn test_legacy_invokespecial() {
+    use classfile_parser::{
+        parse_class_opt_from_bytes, ClassAccessFlags, ClassFile, ClassFileVersion, ParseOptions,
+    };
+
+    let java_7 = ClassFileVersion {
+        major: 51,
+        minor: 0,
+    };
+    let java_8 = ClassFileVersion {
+        major: 52,
+        minor: 0,
+    };
+    assert!(!java_7.implies_acc_super());
+    assert!(java_8.implies_acc_super());
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let (mut opt, _) = parse_class_opt_from_bytes(data).unwrap();
+    assert!(c.access_flags.contains(ClassAccessFlags::SUPER));
+    assert!(!c.uses_legacy_invokespecial(java_7));
+    assert!(!opt.uses_legacy_invokespecial(java_7));
+
+    // Only old JVMs honor a missing ACC_SUPER
+    c.access_flags.remove(ClassAccessFlags::SUPER);
+    opt.access_flags.remove(ClassAccessFlags::SUPER);
+    assert!(c.uses_legacy_invokespecial(java_7));
+    assert!(opt.uses_legacy_invokespecial(java_7));
+    assert!(!c.uses_legacy_invokespecial(java_8));
+}
+
+#[test]
+fn test_opt_class_attributes() {
+    use classfile_parser::attribute_info::SourceFileAttribute;
+    use classfile_parser::parse_class_opt_from_bytes;
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics.class");
+    let (opt, _) = parse_class_opt_from_bytes(data).unwrap();
+    let pool = &opt.const_pool;
+    let source = opt.source_file(data).unwrap().unwrap();
+    let name = pool.get_t(source.sourcefile_index).unwrap();
+    assert_eq!(name.as_text(data), "Generics.java");
+    let signature = opt.signature(data).unwrap().unwrap();
+    let signature = pool.get_t(signature.signature_index).unwrap();
+    assert!(signature.as_text(data).starts_with('<'));
+    let inner = opt.inner_classes(data).unwrap().unwrap();
+    assert!(!inner.classes.is_empty());
+    assert!(opt.bootstrap_methods(data).unwrap().is_none());
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let (opt, _) = parse_class_opt_from_bytes(data).unwrap();
+    let bootstrap = opt.bootstrap_methods(data).unwrap().unwrap();
+    assert!(!bootstrap.bootstrap_methods.is_empty());
+    assert!(opt.signature(data).unwrap().is_none());
+    assert!(opt
+        .load_class_attribute::<SourceFileAttribute>(data)
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+fn test_interface_names() {
+    use classfile_parser::{parse_class_opt_from_bytes, ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/PrivateMembers.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let (opt, _) = parse_class_opt_from_bytes(data).unwrap();
+    assert_eq!(c.interface_names(data).unwrap(), ["java/io/Serializable"]);
+    assert_eq!(opt.interface_names(data).unwrap(), ["java/io/Serializable"]);
+    assert!(c.implements(data, "java/io/Serializable").unwrap());
+    assert!(opt.implements(data, "java/io/Serializable").unwrap());
+    assert!(!opt.implements(data, "java/lang/Runnable").unwrap());
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (mut opt, _) = parse_class_opt_from_bytes(data).unwrap();
+    assert!(opt.interface_names(data).unwrap().is_empty());
+    assert!(!opt.implements(data, "java/io/Serializable").unwrap());
+
+    // An interface which isn't a class entry is an error rather than a mismatch
+    opt.interfaces
+        .push(ConstantPoolIndexRaw::new(opt.this_class.0 + 1000));
+    assert!(opt.interface_names(data).is_err());
+    assert!(opt.implements(data, "java/io/Serializable").is_err());
+}
+
+#[test]
+fn test_parse_class_opt() {
+    use classfile_parser::{parse_class_opt, parse_class_opt_from_bytes};
+
+    let (c, data) = parse_class_opt("./java-assets/compiled-classes/Factorial").unwrap();
+    assert_eq!(c.methods.len(), 2);
+    assert_eq!(c.load_method_opt_iter(&data).count(), 2);
+    assert!(parse_class_opt("./java-assets/compiled-classes/Missing").is_err());
+
+    let mut padded = data.clone();
+    padded.extend_from_slice(&[1, 2, 3]);
+    let (c, rest) = parse_class_opt_from_bytes(&padded).unwrap();
+    assert_eq!(c.methods.len(), 2);
+    assert_eq!(rest, &[1, 2, 3]);
+    assert!(parse_class_opt_from_bytes(&data[..20]).is_err());
+}
+
+#[test]
+fn test_class_opt_clone_shares_methods() {
+    use classfile_parser::parse_class_opt;
+
+    let (mut c, data) = parse_class_opt("./java-assets/compiled-classes/Factorial").unwrap();
+    c.load_all_methods_mut(&data).unwrap();
+    let clone = c.clone();
+    let methods = c.methods.data().unwrap();
+    assert_eq!(methods.as_ptr(), clone.methods.data().unwrap().as_ptr());
+
+    // Filling the methods again leaves the clone with the old ones
+    let old = methods.as_ptr();
+    let mut refilled = clone.clone();
+    refilled.methods.fill(methods.iter().cloned().collect());
+    assert_ne!(refilled.methods.data().unwrap().as_ptr(), old);
+    assert_eq!(clone.methods.data().unwrap().as_ptr(), old);
+}
+
+#[cfg(feature = "threading")]
+#[test]
+fn test_class_opt_clone_sent_to_thread() {
+    use classfile_parser::parse_class_opt;
+
+    let (mut c, data) = parse_class_opt("./java-assets/compiled-classes/Factorial").unwrap();
+    c.load_all_methods_mut(&data).unwrap();
+    let clone = c.clone();
+    let methods = std::thread::spawn(move || clone.methods.data().unwrap().as_ptr() as usize)
+        .join()
+        .unwrap();
+    assert_eq!(methods, c.methods.data().unwrap().as_ptr() as usize);
+}
+
+#[test]
+fn test_resolve_method_handle() {
+    use classfile_parser::constant_info::{MethodHandleConstant, MethodHandleError, ReferenceKind};
+    use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+    use classfile_parser::resolved::Owned;
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+
+    let handle: &MethodHandleConstant = c.const_pool.get_t(ConstantPoolIndexRaw::new(38)).unwrap();
+    let handle = handle.clone();
+    let resolved = c.resolve_method_handle(&handle, data).unwrap();
+    assert_eq!(resolved.kind, ReferenceKind::InvokeStatic);
+    assert_eq!(
+        resolved.class_name,
+        "uk/co/palmr/classfileparser/BootstrapMethods"
+    );
+    assert_eq!(resolved.name, "lambda$main$0");
+    assert_eq!(resolved.handle_type().unwrap(), "()Ljava/lang/String;");
+
+    // An owned copy doesn't borrow the data
+    let owned = {
+        let copy = data.to_vec();
+        c.resolve_method_handle_with(&handle, &copy, Owned).unwrap()
+    };
+    assert_eq!(owned, resolved.clone().to_owned());
+
+    // A handle built by hand can have a descriptor which isn't a method descriptor
+    for descriptor in ["", "V"] {
+        let mut malformed = owned.clone();
+        malformed.descriptor = descriptor.into();
+        assert_eq!(
+            malformed.handle_type().unwrap_err(),
+            MethodHandleError::InvalidDescriptor
+        );
+    }
+
+    // #1 is Object.<init>, #4 is System.out, and #5 is Supplier.get
+    let handle = |kind: u8, index: u16| MethodHandleConstant {
+        reference_kind: kind,
+        reference_index: ConstantPoolIndexRaw::new(index),
+    };
+    let handle_type = |c: &ClassFile, kind, index| {
+        c.resolve_method_handle(&handle(kind, index), data)
+            .and_then(|resolved| resolved.handle_type())
+    };
+    assert_eq!(handle_type(&c, 2, 4).unwrap(), "()Ljava/io/PrintStream;");
+    assert_eq!(
+        handle_type(&c, 3, 4).unwrap(),
+        "(Ljava/lang/System;Ljava/io/PrintStream;)V"
+    );
+    assert_eq!(handle_type(&c, 8, 1).unwrap(), "()Ljava/lang/Object;");
+    assert_eq!(
+        handle_type(&c, 9, 5).unwrap(),
+        "(Ljava/util/function/Supplier;)Ljava/lang/Object;"
+    );
+
+    assert_eq!(
+        handle_type(&c, 0, 1).unwrap_err(),
+        MethodHandleError::InvalidKind(0)
+    );
+    assert_eq!(
+        handle_type(&c, 1, 1).unwrap_err(),
+        MethodHandleError::WrongReferenceType
+    );
+    assert_eq!(
+        handle_type(&c, 5, 5).unwrap_err(),
+        MethodHandleError::WrongReferenceType
+    );
+    assert_eq!(
+        handle_type(&c, 5, 1).unwrap_err(),
+        MethodHandleError::InvalidMethodName
+    );
+    assert_eq!(
+        handle_type(&c, 6, 4000).unwrap_err(),
+        MethodHandleError::InvalidIndex
+    );
+
+    // Static handles could only refer to interface methods from Java 8
+    assert!(handle_type(&c, 6, 5).is_ok());
+    c.version.major = 51;
+    assert_eq!(
+        handle_type(&c, 6, 5).unwrap_err(),
+        MethodHandleError::WrongReferenceType
+    );
+}
+
+#[test]
+fn test_attribute_names() {
+    use classfile_parser::attribute_info::names::{self, AttributeName};
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    for name in AttributeName::ALL {
+        assert_eq!(AttributeName::from_name(name.as_str()), Some(name));
+    }
+    assert_eq!(
+        AttributeName::from_name(names::CODE),
+        Some(AttributeName::Code)
+    );
+    assert_eq!(AttributeName::from_name("code"), None);
+    assert_eq!(AttributeName::Record.min_major_version(), 60);
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    for attr in c.attributes.iter() {
+        let name = c.const_pool.get_t(attr.attribute_name_index).unwrap();
+        assert!(AttributeName::from_name(&name.as_text(data)).is_some());
+    }
+}
+
+#[test]
+fn test_raw_access_flags() {
+    use classfile_parser::scan::class_layout;
+    use classfile_parser::{ClassAccessFlags, ClassFile, ClassFileOpt, ParseOptions};
+
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Factorial.class");
+    let offset = class_layout(original).unwrap().access_flags;
+
+    // Set 0x0100, which has no meaning for classes, alongside public and super
+    let mut data = original.to_vec();
+    data[offset] |= 0x01;
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    assert_eq!(
+        c.access_flags,
+        ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER
+    );
+    assert_eq!(c.raw_access_flags, 0x0121);
+    assert_eq!(c.raw_flags(), 0x0121);
+
+    // Changing the defined flags keeps the unknown bits
+    c.access_flags.insert(ClassAccessFlags::FINAL);
+    assert_eq!(c.raw_flags(), 0x0131);
+
+    let c = ClassFileOpt::parse(&data, &ParseOptions::default()).unwrap();
+    assert_eq!(c.raw_flags(), 0x0121);
+
+    let c = ClassFile::parse(original, &ParseOptions::default()).unwrap();
+    assert_eq!(c.raw_flags(), c.access_flags.bits());
+    for method in c.methods.iter() {
+        assert_eq!(method.raw_flags(), method.access_flags.bits());
+    }
+}
+
+#[test]
+fn test_malformed_item_location() {
+    use classfile_parser::error::{ItemKind, ItemLocation};
+    use classfile_parser::scan::{class_layout, skip_methods};
+    use classfile_parser::{ClassFile, ClassFileOpt, ParseError, ParseOptions};
+
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let layout = class_layout(original).unwrap();
+    let start = skip_methods(original, layout.methods.start, 2).unwrap();
+
+    // Give the first attribute of the third method a length that runs past the end of the data
+    let mut data = original.to_vec();
+    let length = start + 10;
+    data[length..length + 4].copy_from_slice(&[0xff; 4]);
+
+    let item = Some(ItemLocation {
+        kind: ItemKind::Method,
+        index: 2,
+        start,
+    });
+    let err = ClassFile::parse(&data, &ParseOptions::default()).unwrap_err();
+    assert!(matches!(err, ParseError::Malformed { item: i, .. } if i == item));
+    assert!(err
+        .to_string()
+        .starts_with(&format!("malformed method 2 at {:#x}", start)));
+    let err = ClassFileOpt::parse(&data, &ParseOptions::default()).unwrap_err();
+    assert!(matches!(err, ParseError::Malformed { item: i, .. } if i == item));
+    let err = class_layout(&data).unwrap_err();
+    assert!(matches!(err, ParseError::Malformed { item: i, .. } if i == item));
+}
+
+#[test]
+fn test_recover_attribute_counts() {
+    use classfile_parser::attribute_info::AttributeOwner;
+    use classfile_parser::recover::AttributeCountMismatch;
+    use classfile_parser::scan::class_layout;
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let expected = ClassFile::parse(original, &ParseOptions::default()).unwrap();
+    assert!(expected.attribute_count_mismatches().is_empty());
+    let options = ParseOptions {
+        recover_attribute_counts: true,
+        ..ParseOptions::default()
+    };
+    let c = ClassFile::parse(original, &options).unwrap();
+    assert!(c.attribute_count_mismatches().is_empty());
+
+    // The attributes_count of the first method follows its flags, name, and descriptor
+    let count = class_layout(original).unwrap().methods.start + 6;
+    let declared = u16::from_be_bytes([original[count], original[count + 1]]);
+    assert_eq!(usize::from(declared), expected.methods[0].attributes.len());
+    for wrong in [declared - 1, declared + 1, 0x7fff] {
+        let mut data = original.to_vec();
+        data[count..count + 2].copy_from_slice(&wrong.to_be_bytes());
+        assert!(ClassFile::parse(&data, &ParseOptions::default()).is_err());
+
+        let c = ClassFile::parse(&data, &options).unwrap();
+        assert_eq!(
+            c.attribute_count_mismatches(),
+            vec![AttributeCountMismatch {
+                owner: AttributeOwner::Method(0),
+                declared: wrong,
+                found: usize::from(declared),
+            }]
+        );
+        for (method, expected) in c.methods.iter().zip(expected.methods.iter()) {
+            assert_eq!(method.attributes, expected.attributes);
+        }
+        assert_eq!(c.attributes, expected.attributes);
+        // Writing it out fixes the count
+        assert_eq!(c.to_bytes(&data).unwrap(), original);
+    }
+}
+
+#[test]
+fn test_constant_pool_dump() {
+    use classfile_parser::constant_info::MethodHandleConstant;
+    use classfile_parser::constant_pool::ConstantPool;
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let dump = c.const_pool.dump(data);
+    let lines: Vec<&str> = dump.lines().collect();
+
+    // This is the same as the output of javap
+    assert_eq!(
+        lines[0],
+        "   #1 = Methodref          #10.#34        // java/lang/Object.\"<init>\":()V"
+    );
+    assert_eq!(
+        lines[1],
+        "   #2 = InvokeDynamic      #0:#40         // #0:get:()Ljava/util/function/Supplier;"
+    );
+    assert_eq!(
+        lines[37],
+        "  #38 = MethodHandle       6:#54          // REF_invokeStatic \
+         uk/co/palmr/classfileparser/BootstrapMethods.lambda$main$0:()Ljava/lang/String;"
+    );
+    assert_eq!(
+        lines[74],
+        "  #75 = Utf8               java/lang/invoke/MethodHandles"
+    );
+    assert_eq!(lines.len(), usize::from(c.const_pool.len()));
+
+    // Longs and doubles take up two slots, but only have one line
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Constants.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let dump = c.const_pool.dump(data);
+    assert!(dump.contains("\n  #34 = Long               1099511627776l\n  #36 = "));
+    assert!(dump.contains("\n  #41 = Double             2.5d\n"));
+
+    // A method handle which refers to itself has no comment, rather than recursing forever
+    let mut pool = ConstantPool::default();
+    pool.push(ConstantInfo::MethodHandle(MethodHandleConstant {
+        reference_kind: 6,
+        reference_index: ConstantPoolIndexRaw::new(1),
+    }))
+    .unwrap();
+    assert_eq!(pool.dump(&[]), "   #1 = MethodHandle       6:#1\n");
+}
+
+#[test]
+fn test_access_flag_violations() {
+    use classfile_parser::attribute_info::AttributeOwner;
+    use classfile_parser::field_info::FieldAccessFlags;
+    use classfile_parser::method_info::MethodAccessFlags;
+    use classfile_parser::validate::AccessFlagRule;
+
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, mut c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    assert!(c.access_flag_violations(class_data).is_empty());
+
+    c.fields[0].access_flags |= FieldAccessFlags::PUBLIC | FieldAccessFlags::VOLATILE;
+    // Only the flags are checked, so an abstract method with code is fine
+    c.methods[1].access_flags |= MethodAccessFlags::ABSTRACT;
+    let rules: Vec<_> = c
+        .access_flag_violations(class_data)
+        .into_iter()
+        .map(|violation| (violation.owner, violation.rule))
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            (AttributeOwner::Field(0), AccessFlagRule::MultipleVisibility),
+            (AttributeOwner::Field(0), AccessFlagRule::FinalVolatileField),
+        ]
+    );
+
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicInterface.class");
+    let (_, mut c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    assert!(c.access_flag_violations(class_data).is_empty());
+
+    // Private interface methods are allowed from Java 8, but need a body
+    c.methods[0].access_flags = MethodAccessFlags::PRIVATE | MethodAccessFlags::ABSTRACT;
+    let violations = c.access_flag_violations(class_data);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].rule, AccessFlagRule::AbstractMethod);
+    assert_eq!(violations[0].flags, 0x0402);
+
+    c.version.major = 51;
+    let rules: Vec<_> = c
+        .access_flag_violations(class_data)
+        .into_iter()
+        .map(|violation| violation.rule)
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            AccessFlagRule::InterfaceMethod,
+            AccessFlagRule::AbstractMethod
+        ]
+    );
+}
+
+#[test]
+fn test_code_errors() {
+    use classfile_parser::attribute_info::CodeAttributeBuilder;
+    use classfile_parser::validate::{CodeError, CodeErrorKind};
+
+    let class_data = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let (_, mut c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    assert!(c.code_errors(class_data).unwrap().is_empty());
+
+    let mut data = class_data.to_vec();
+    let mut set_code = |c: &mut classfile_parser::ClassFile, code: &[u8]| {
+        let mut builder = CodeAttributeBuilder::new(1, 1);
+        builder.emit(code);
+        let (range, _) = builder.build(&mut data).unwrap();
+        let pool = &c.const_pool;
+        let attr = c.methods[0]
+            .attributes
+            .iter_mut()
+            .find(|attr| {
+                pool.get_t(attr.attribute_name_index)
+                    .unwrap()
+                    .as_text(&data)
+                    == "Code"
+            })
+            .unwrap();
+        attr.attribute_length = range.len() as u32;
+        attr.info = range;
+        c.code_errors(&data).unwrap()
+    };
+    let errors = |offset, kind| {
+        vec![CodeError {
+            method: 0,
+            offset,
+            kind,
+        }]
+    };
+
+    assert_eq!(
+        set_code(
+            &mut c,
+            &[
+                0x03, // iconst_0
+                0x99, 0x00, 0x02, // ifeq 3, in the middle of itself
+                0xa7, 0xff, 0xfc, // goto 0
+                0xb1, // return
+            ]
+        ),
+        errors(1, CodeErrorKind::BadTarget(3))
+    );
+    assert_eq!(
+        set_code(&mut c, &[0xa7, 0x00, 0x04, 0xb1]),
+        errors(0, CodeErrorKind::BadTarget(4))
+    );
+    assert_eq!(
+        set_code(&mut c, &[0xb1, 0xa7, 0xff, 0xfe]),
+        errors(1, CodeErrorKind::BadTarget(-1))
+    );
+    assert_eq!(
+        set_code(&mut c, &[0x00, 0x11, 0x00]),
+        errors(1, CodeErrorKind::Truncated)
+    );
+    assert_eq!(
+        set_code(&mut c, &[0x00, 0xfe]),
+        errors(1, CodeErrorKind::InvalidOpcode(0xfe))
+    );
+    assert_eq!(
+        set_code(
+            &mut c,
+            &[
+                0x03, // iconst_0
+                0xaa, 0x00, 0x00, // tableswitch
+                0x00, 0x00, 0x00, 0x0f, // default 16
+                0x00, 0x00, 0x00, 0x01, // low 1
+                0x00, 0x00, 0x00, 0x00, // high 0
+                0xb1, // return
+            ]
+        ),
+        errors(1, CodeErrorKind::InvalidSwitch)
+    );
+    assert_eq!(set_code(&mut c, &[]), errors(0, CodeErrorKind::Empty));
+}
+
+#[test]
+fn test_exception_table_errors() {
+    use classfile_parser::attribute_info::CodeAttributeBuilder;
+    use classfile_parser::validate::ExceptionTableErrorKind;
+
+    let class_data = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let (_, mut c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    assert!(c.exception_table_errors(class_data).unwrap().is_empty());
+
+    let mut data = class_data.to_vec();
+    let mut builder = CodeAttributeBuilder::new(1, 1);
+    let start = builder.label_here().unwrap();
+    builder.emit(&[0x00, 0x00]);
+    let end = builder.label_here().unwrap();
+    builder.emit(&[0xb1]);
+    for _ in 0..3 {
+        builder.exception_handler(start, end, end, c.this_class);
+    }
+    let (range, _) = builder.build(&mut data).unwrap();
+
+    // The builder won't make bad entries, so they are written over the good ones. The entries
+    // come after max_stack, max_locals, code_length, the code, and the table's length.
+    let table = range.start + 8 + 3 + 2;
+    let mut write = |offset: usize, value: u16| {
+        data[table + offset..table + offset + 2].copy_from_slice(&value.to_be_bytes())
+    };
+    // An end_pc equal to the start_pc
+    write(2, 0);
+    // A handler_pc at the end of the code, and the catch_type of the class's name
+    let name = c.const_pool.get_t(c.this_class).unwrap().name_index;
+    write(8 + 4, 3);
+    write(8 + 6, name.0);
+    // An end_pc past the end of the code
+    write(16 + 2, 100);
+
+    let pool = &c.const_pool;
+    let attr = c.methods[0]
+        .attributes
+        .iter_mut()
+        .find(|attr| {
+            pool.get_t(attr.attribute_name_index)
+                .unwrap()
+                .as_text(&data)
+                == "Code"
+        })
+        .unwrap();
+    attr.attribute_length = range.len() as u32;
+    attr.info = range;
+
+    let errors: Vec<_> = c
+        .exception_table_errors(&data)
+        .unwrap()
+        .into_iter()
+        .map(|error| {
+            assert_eq!(error.method, 0);
+            (error.entry, error.kind)
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (0, ExceptionTableErrorKind::EmptyRange),
+            (1, ExceptionTableErrorKind::HandlerPastCode),
+            (1, ExceptionTableErrorKind::CatchTypeNotClass),
+            (2, ExceptionTableErrorKind::EndPastCode),
+        ]
+    );
+}
+
+#[test]
+fn test_catch_type_errors() {
+    use classfile_parser::attribute_info::{CodeAttribute, HasAttributes};
+    use classfile_parser::builder::ClassFileBuilder;
+    use classfile_parser::provider::{LoadedClass, MemoryClassProvider};
+    use classfile_parser::validate::CatchTypeErrorKind;
+    use classfile_parser::ClassFile;
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Handlers.class");
+    let failure: &[u8] = include_bytes!("../java-assets/compiled-classes/Handlers$Failure.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    let failure_name = "uk/co/palmr/classfileparser/Handlers$Failure";
+    let errors = |c: &ClassFile, data: &[u8], provider: &MemoryClassProvider| {
+        c.catch_type_errors(data, provider)
+            .unwrap()
+            .into_iter()
+            .map(|error| (error.method, error.entry, error.catch_type, error.kind))
+            .collect::<Vec<_>>()
+    };
+
+    // Failure extends Exception, which isn't available yet
+    let mut provider = MemoryClassProvider::new();
+    provider.insert_data(failure.to_vec()).unwrap();
+    let missing = CatchTypeErrorKind::MissingClass("java/lang/Exception".to_string());
+    assert_eq!(
+        errors(&c, class_data, &provider),
+        [(2, 0, failure_name.to_string(), missing)]
+    );
+
+    // Stand-ins for the classes of the JDK, so that the hierarchy reaches Throwable
+    let class = |name, super_name| {
+        let (class_file, data) = ClassFileBuilder::new(name, Some(super_name))
+            .unwrap()
+            .build()
+            .unwrap();
+        LoadedClass { class_file, data }
+    };
+    provider.insert(class("java/lang/Exception", "java/lang/Throwable"));
+    provider.insert(class("java/lang/Throwable", "java/lang/Object"));
+    assert!(errors(&c, class_data, &provider).is_empty());
+
+    // Catching the class itself, which only extends Object and doesn't need to be in the
+    // provider. The catch_type is the last field of the first entry, which comes after the code
+    // and the table's length.
+    let code = c.methods[2]
+        .find_attribute::<CodeAttribute>(&c.const_pool, class_data)
+        .unwrap()
+        .unwrap();
+    let catch_type = code.code.end + 2 + 6;
+    let mut data = class_data.to_vec();
+    data[catch_type..catch_type + 2].copy_from_slice(&c.this_class.0.to_be_bytes());
+    assert_eq!(
+        errors(&c, &data, &provider),
+        [(
+            2,
+            0,
+            "uk/co/palmr/classfileparser/Handlers".to_string(),
+            CatchTypeErrorKind::NotThrowable
+        )]
+    );
+}
+
+#[test]
+fn test_class_name_errors() {
+    use classfile_parser::validate::ClassNameError;
+
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, mut c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    let name = "uk/co/palmr/karl/examples/BasicClass";
+    assert!(c.class_name_errors(class_data, None).is_empty());
+    assert!(c.class_name_errors(class_data, Some(name)).is_empty());
+    assert_eq!(
+        c.class_name_errors(class_data, Some("BasicClass")),
+        [ClassNameError::NameMismatch {
+            expected: "BasicClass".to_string(),
+            found: name.to_string(),
+        }]
+    );
+
+    let (this_class, super_class) = (c.this_class, c.super_class);
+    c.super_class = ConstantPoolIndexRaw::new(0);
+    assert_eq!(
+        c.class_name_errors(class_data, None),
+        [ClassNameError::MissingSuperClass]
+    );
+
+    // Claim to be java/lang/Object, which has no superclass
+    c.this_class = super_class;
+    assert!(c.class_name_errors(class_data, None).is_empty());
+    c.super_class = this_class;
+    assert_eq!(
+        c.class_name_errors(class_data, None),
+        [ClassNameError::UnexpectedSuperClass]
+    );
+
+    let (utf8, _) = c.const_pool.utf8_iter(class_data).next().unwrap();
+    c.this_class = ConstantPoolIndexRaw::new(utf8.0);
+    c.super_class = ConstantPoolIndexRaw::new(utf8.0);
+    assert_eq!(
+        c.class_name_errors(class_data, Some(name)),
+        [
+            ClassNameError::ThisClassNotClass,
+            ClassNameError::SuperClassNotClass
+        ]
+    );
+}
+
+#[test]
+fn test_class_kind() {
+    use classfile_parser::{ClassAccessFlags, ClassFile, ClassKind, ParseOptions};
+
+    let kind = |data: &[u8]| {
+        let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+        c.kind(data)
+    };
+    assert_eq!(
+        kind(include_bytes!(
+            "../java-assets/compiled-classes/BasicClass.class"
+        )),
+        ClassKind::Class
+    );
+    assert_eq!(
+        kind(include_bytes!(
+            "../java-assets/compiled-classes/BasicInterface.class"
+        )),
+        ClassKind::Interface
+    );
+    assert_eq!(
+        kind(include_bytes!(
+            "../java-assets/compiled-classes/Annotated$Info.class"
+        )),
+        ClassKind::Annotation
+    );
+    assert_eq!(
+        kind(include_bytes!(
+            "../java-assets/compiled-classes/RecordExample.class"
+        )),
+        ClassKind::Record
+    );
+
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut c = ClassFile::parse(class_data, &ParseOptions::default()).unwrap();
+    assert!(!c.is_record(class_data));
+    c.access_flags |= ClassAccessFlags::ENUM;
+    assert_eq!(c.kind(class_data), ClassKind::Enum);
+    c.access_flags = ClassAccessFlags::MODULE;
+    assert!(c.is_module());
+    assert_eq!(c.kind(class_data), ClassKind::Module);
+}
+
+#[test]
+fn test_attributes_search_all() {
+    use classfile_parser::method_info::{attributes_search_all_parser, attributes_search_parser};
+
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("Not a class file");
+    let name_index = |text: &str| {
+        c.const_pool
+            .iter_indexed()
+            .find(|(_, entry)| match entry {
+                ConstantInfo::Utf8(u) => u.as_text(class_data) == text,
+                _ => false,
+            })
+            .map(|(index, _)| index.0)
+            .unwrap()
+    };
+
+    // Two SourceFile attributes with a Code one between them
+    let mut data = class_data.to_vec();
+    let start = data.len();
+    for (name, info) in [
+        ("SourceFile", &[0, 1][..]),
+        ("Code", &[][..]),
+        ("SourceFile", &[0, 2][..]),
+    ] {
+        data.extend_from_slice(&name_index(name).to_be_bytes());
+        data.extend_from_slice(&(info.len() as u32).to_be_bytes());
+        data.extend_from_slice(info);
+    }
+
+    let input = ParseData::from_pos(&data, start);
+    let (rest, found) =
+        attributes_search_all_parser(input.clone(), &data, &c.const_pool, "SourceFile", 3).unwrap();
+    assert!(rest.is_empty());
+    let end = data.len();
+    assert_eq!(found, [(0, start + 6..start + 8), (2, end - 2..end)]);
+
+    // The first match is the same one that attributes_search_parser finds
+    let (_, first) =
+        attributes_search_parser(input.clone(), &data, &c.const_pool, "SourceFile", 3).unwrap();
+    assert_eq!(first, Some(found[0].clone()));
+    let (_, none) =
+        attributes_search_all_parser(input, &data, &c.const_pool, "Signature", 3).unwrap();
+    assert!(none.is_empty());
+}
+
+#[test]
+fn test_parse_data_slices() {
+    use nom::number::complete::be_u16;
+    use nom::{AsBytes, Slice};
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let input = ParseData::new(class_data).slice(4..);
+
+    // Bounding the input to the version keeps the positions relative to the whole class
+    let version = input.slice(..4);
+    assert_eq!(version.as_range(), 4..8);
+    let (rest, minor) = be_u16::<_, ()>(version).unwrap();
+    let (rest, major) = be_u16::<_, ()>(rest).unwrap();
+    assert_eq!((minor, major), (0, 51));
+    assert_eq!(rest.as_range(), 8..8);
+
+    let major = input.slice(2..4);
+    assert_eq!(major.pos(), 6);
+    assert_eq!(major.as_bytes(), &class_data[6..8]);
+}
+
+#[test]
+#[cfg(feature = "stackmap")]
+fn test_parse_typed_attributes() {
+    use classfile_parser::attribute_info::{AttributeData, AttributeOwner, StackMapFrame};
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let c = ClassFile::parse(class_data, &ParseOptions::default()).unwrap();
+    assert!(c.typed_attributes.is_none());
+
+    let options = ParseOptions {
+        parse_typed_attributes: true,
+        ..ParseOptions::default()
+    };
+    let c = ClassFile::parse(class_data, &options).unwrap();
+    let typed = c.typed_attributes.as_ref().unwrap();
+    // The SourceFile, two Code attributes, and the StackMapTable of the second
+    assert_eq!(typed.len(), 4);
+
+    match typed.get(AttributeOwner::Class, 0) {
+        Some(AttributeData::SourceFile(source)) => {
+            let name = c.const_pool.get_t(source.sourcefile_index).unwrap();
+            assert_eq!(name.as_text(class_data), "Instructions.java");
+        }
+        other => panic!("expected the source file, got {:?}", other),
+    }
+    match typed.get(AttributeOwner::Method(1), 0) {
+        Some(AttributeData::Code(code)) => assert_eq!(code.code_length, 146),
+        other => panic!("expected code, got {:?}", other),
+    }
+    // The LineNumberTable has no type, so only the StackMapTable is parsed
+    assert!(typed.get(AttributeOwner::Code(1), 0).is_none());
+    match typed.get(AttributeOwner::Code(1), 1) {
+        Some(AttributeData::StackMapTable(table)) => {
+            assert_eq!(table.entries.len(), 8);
+            assert!(matches!(table.entries[1], StackMapFrame::SameFrame { .. }));
+        }
+        other => panic!("expected a stack map table, got {:?}", other),
+    }
+
+    // The indices no longer match once attributes are removed
+    let mut c = c;
+    let mut data = class_data.to_vec();
+    c.remove_attribute(&mut data, AttributeOwner::Class, "SourceFile", false)
+        .unwrap();
+    assert!(c.typed_attributes.is_none());
+}
+
+#[test]
+#[cfg(not(feature = "stackmap"))]
+fn test_parse_typed_attributes_without_stackmap() {
+    use classfile_parser::attribute_info::{
+        parse_attribute, Attribute, AttributeData, AttributeOwner,
+    };
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let options = ParseOptions {
+        parse_typed_attributes: true,
+        ..ParseOptions::default()
+    };
+    let c = ClassFile::parse(class_data, &options).unwrap();
+    let typed = c.typed_attributes.as_ref().unwrap();
+    // The type of the StackMapTable is compiled out, so it is left unparsed
+    assert_eq!(typed.len(), 3);
+    assert!(typed.get(AttributeOwner::Code(1), 1).is_none());
+
+    let pool = &c.const_pool;
+    let code = match parse_attribute(&c.methods[1].attributes[0], class_data, pool).unwrap() {
+        Attribute::Typed(AttributeData::Code(code)) => code,
+        other => panic!("expected code, got {:?}", other),
+    };
+    let stack_map_table = &code.attributes[1];
+    match parse_attribute(stack_map_table, class_data, pool).unwrap() {
+        Attribute::Raw(range) => assert_eq!(range, stack_map_table.info),
+        other => panic!("expected a raw attribute, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_attribute() {
+    use classfile_parser::attribute_info::{parse_attribute, Attribute, AttributeData};
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let c = ClassFile::parse(class_data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+
+    let source = parse_attribute(&c.attributes[0], class_data, pool).unwrap();
+    match source.typed() {
+        Some(AttributeData::SourceFile(source)) => {
+            let name = pool.get_t(source.sourcefile_index).unwrap();
+            assert_eq!(name.as_text(class_data), "Instructions.java");
+        }
+        other => panic!("expected the source file, got {:?}", other),
+    }
+
+    let code = match parse_attribute(&c.methods[1].attributes[0], class_data, pool).unwrap() {
+        Attribute::Typed(AttributeData::Code(code)) => code,
+        other => panic!("expected code, got {:?}", other),
+    };
+    // The LineNumberTable has no type
+    let line_numbers = &code.attributes[0];
+    match parse_attribute(line_numbers, class_data, pool).unwrap() {
+        Attribute::Raw(range) => assert_eq!(range, line_numbers.info),
+        other => panic!("expected a raw attribute, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_constructors_and_static_initializer() {
+    use classfile_parser::descriptor::{DescriptorType, DescriptorTypeBasic};
+    use classfile_parser::method_info::MethodAccessFlags;
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics.class");
+    let c = ClassFile::parse(class_data, &ParseOptions::default()).unwrap();
+    let constructors = c.constructors(class_data).unwrap();
+    assert_eq!(constructors.len(), 1);
+    let (_, descriptor) = &constructors[0];
+    assert_eq!(descriptor.parameter_types.len(), 1);
+    assert_eq!(
+        descriptor.parameter_types[0],
+        DescriptorType::Basic(DescriptorTypeBasic::ClassName(
+            b"java/lang/Number"[..].into()
+        ))
+    );
+    assert!(c.static_initializer(class_data).unwrap().is_none());
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Constants.class");
+    let mut c = ClassFile::parse(class_data, &ParseOptions::default()).unwrap();
+    let (method, descriptor) = c.static_initializer(class_data).unwrap().unwrap();
+    assert!(method.access_flags.contains(MethodAccessFlags::STATIC));
+    assert!(descriptor.parameter_types.is_empty() && descriptor.return_type.is_none());
+
+    // From version 51 the initializer has to be static, but before that the flags are ignored
+    let index = c
+        .methods
+        .iter()
+        .position(|m| m.access_flags.contains(MethodAccessFlags::STATIC))
+        .unwrap();
+    c.methods[index].access_flags = MethodAccessFlags::empty();
+    assert!(c.static_initializer(class_data).unwrap().is_none());
+    c.version.major = 50;
+    assert!(c.static_initializer(class_data).unwrap().is_some());
+}
+
+#[test]
+fn test_local_slots() {
+    use classfile_parser::descriptor::method::MethodDescriptor;
+    use classfile_parser::method_info::{LocalSlot, LocalSlots, MethodAccessFlags};
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let descriptor = MethodDescriptor::parse(b"(IJLjava/lang/String;D)V").unwrap();
+    let slots = LocalSlots::new(&descriptor, MethodAccessFlags::PUBLIC, 8);
+    assert!(slots.has_this());
+    assert_eq!(slots.parameters_size(), 7);
+    assert!(slots.fits());
+    assert_eq!(slots.parameter_slot(1), Some(2));
+    assert_eq!(slots.parameter_slot(3), Some(5));
+    assert_eq!(slots.is_wide(3), Some(true));
+    let expected = [
+        LocalSlot::This,
+        LocalSlot::Parameter(0),
+        LocalSlot::Parameter(1),
+        LocalSlot::ParameterUpper(1),
+        LocalSlot::Parameter(2),
+        LocalSlot::Parameter(3),
+        LocalSlot::ParameterUpper(3),
+        LocalSlot::Local,
+    ];
+    for (slot, expected) in expected.iter().enumerate() {
+        assert_eq!(slots.slot(slot as u16), Some(*expected));
+    }
+    assert_eq!(slots.slot(8), None);
+    assert_eq!(slots.parameter_at(4), Some(2));
+    assert_eq!(slots.parameter_at(3), None);
+
+    let slots = LocalSlots::new(&descriptor, MethodAccessFlags::STATIC, 6);
+    assert_eq!(slots.slot(0), Some(LocalSlot::Parameter(0)));
+    assert_eq!(slots.parameters_size(), 6);
+
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(class_data, &ParseOptions::default()).unwrap();
+    for method in c.methods.iter() {
+        let slots = LocalSlots::for_method(method, &c.const_pool, class_data).unwrap();
+        assert_eq!(
+            slots.is_none(),
+            method.access_flags.contains(MethodAccessFlags::ABSTRACT)
+        );
+        if let Some(slots) = slots {
+            assert!(slots.fits());
+        }
+    }
+}
+
+#[test]
+fn test_method_parameters() {
+    use classfile_parser::attribute_info::{
+        HasAttributes, MethodParameterAccessFlags, MethodParametersAttribute,
+    };
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/RecordExample.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let init = c
+        .find_method(data, "<init>", "(ILjava/util/List;J)V")
+        .unwrap();
+    let attr = init
+        .find_attribute::<MethodParametersAttribute>(&c.const_pool, data)
+        .unwrap()
+        .unwrap();
+    assert_eq!(attr.parameters_count, 3);
+    let names: Vec<_> = attr
+        .parameters
+        .iter()
+        .map(|parameter| {
+            assert_eq!(parameter.flags(), MethodParameterAccessFlags::empty());
+            c.const_pool
+                .get_t(parameter.name_index)
+                .unwrap()
+                .as_text(data)
+                .into_owned()
+        })
+        .collect();
+    assert_eq!(names, ["first", "names", "plain"]);
+
+    let accessor = c.find_method(data, "first", "()I").unwrap();
+    assert!(accessor
+        .find_attribute::<MethodParametersAttribute>(&c.const_pool, data)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_referenced_members() {
+    use classfile_parser::constant_info::{
+        resolve_member_ref, MemberRef, MemberRefError, MemberRefKind,
+    };
+    use classfile_parser::descriptor::ParsedDescriptor;
+    use classfile_parser::resolved::Owned;
+    use classfile_parser::{ClassFile, ClassFileOpt, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let members = c.referenced_members(data).unwrap();
+    let found: Vec<_> = members
+        .iter()
+        .map(|member| {
+            (
+                member.index.0,
+                member.kind,
+                member.class_name.as_ref(),
+                member.name.as_ref(),
+            )
+        })
+        .collect();
+    let this = "uk/co/palmr/classfileparser/BootstrapMethods";
+    assert_eq!(
+        found,
+        [
+            (1, MemberRefKind::Method, "java/lang/Object", "<init>"),
+            (3, MemberRefKind::Method, this, "takesLambda"),
+            (4, MemberRefKind::Field, "java/lang/System", "out"),
+            (
+                5,
+                MemberRefKind::InterfaceMethod,
+                "java/util/function/Supplier",
+                "get"
+            ),
+            (7, MemberRefKind::Method, "java/io/PrintStream", "println"),
+            (
+                52,
+                MemberRefKind::Method,
+                "java/lang/invoke/LambdaMetafactory",
+                "metafactory"
+            ),
+            (54, MemberRefKind::Method, this, "lambda$main$0"),
+        ]
+    );
+
+    let out = &members[2];
+    assert_eq!(out.descriptor, "Ljava/io/PrintStream;");
+    assert!(matches!(out.parsed_descriptor, ParsedDescriptor::Field(_)));
+    let println = members[4].clone().to_owned();
+    let method = println.parsed_descriptor.as_method().unwrap();
+    assert_eq!(method.parameter_types.len(), 1);
+    assert!(method.return_type.is_none());
+
+    let owned: Vec<_> = members.iter().cloned().map(MemberRef::to_owned).collect();
+    assert_eq!(c.referenced_members_with(data, Owned).unwrap(), owned);
+
+    let c = ClassFileOpt::parse(data, &ParseOptions::default()).unwrap();
+    assert_eq!(c.referenced_members(data).unwrap(), members);
+    assert_eq!(c.referenced_members_with(data, Owned).unwrap(), owned);
+
+    // The class entry isn't a member reference
+    let index = ConstantPoolIndexRaw::new(c.this_class.0);
+    assert_eq!(
+        resolve_member_ref(&c.const_pool, index, data),
+        Err(MemberRefError::InvalidIndex(index))
+    );
+}
+
+#[test]
+fn test_get_or_parse() {
+    use classfile_parser::field_info::{field_parser, skip_field_parser};
+    use classfile_parser::method_info::{method_opt_parser, skip_method_parser};
+    use classfile_parser::{ClassFile, ClassFileOpt, LoadError, ParseOptions};
+    use std::borrow::Cow;
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let eager = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let mut c = ClassFileOpt::parse(data, &ParseOptions::default()).unwrap();
+    assert!(!c.fields.has_data());
+
+    let field = c.load_field_at(data, 1).unwrap();
+    assert!(matches!(field, Cow::Owned(_)));
+    assert_eq!(field.name_index, eager.fields[1].name_index);
+    assert_eq!(
+        c.fields
+            .get_or_parse(1, data, field_parser, skip_field_parser)
+            .unwrap()
+            .descriptor_index,
+        eager.fields[1].descriptor_index
+    );
+    assert!(matches!(
+        c.load_field_at(data, 2),
+        Err(LoadError::OutOfRange { index: 2, len: 2 })
+    ));
+
+    let opt = c.load_method_opt_at(data, 3).unwrap();
+    assert_eq!(opt.name_index, eager.methods[3].name_index);
+
+    // Once loaded, the loaded methods are used, but parse_at still parses the data
+    c.load_all_methods_mut(data).unwrap();
+    assert!(matches!(
+        c.load_method_at(data, 3).unwrap(),
+        Cow::Borrowed(_)
+    ));
+    let parsed = c
+        .methods
+        .parse_at(3, data, method_opt_parser, skip_method_parser)
+        .unwrap();
+    assert_eq!(parsed.name_index, opt.name_index);
+    assert!(matches!(
+        c.methods
+            .parse_at(6, data, method_opt_parser, skip_method_parser),
+        Err(LoadError::OutOfRange { index: 6, len: 6 })
+    ));
+
+    // Data that ends before the table starts is an error rather than a panic
+    assert!(matches!(
+        c.methods
+            .parse_at(0, &data[..4], method_opt_parser, skip_method_parser),
+        Err(LoadError::OutOfRange { len: 4, .. })
+    ));
+}
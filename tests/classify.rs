@@ -0,0 +1,30 @@
+extern crate classfile_parser;
+
+use classfile_parser::classify::{
+    classify_class, classify_method, SyntheticClassKind, SyntheticMethodKind,
+};
+use classfile_parser::{class_parser, parser::ParseData};
+
+#[test]
+fn test_classify_switch_map() {
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/SwitchMap$1.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("not a class file");
+    assert_eq!(
+        classify_class(&c, class_data),
+        Some(SyntheticClassKind::SwitchMap)
+    );
+}
+
+#[test]
+fn test_classify_lambda_body() {
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/SwitchMap.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("not a class file");
+    assert_eq!(classify_class(&c, class_data), None);
+
+    let kinds: Vec<_> = c
+        .methods
+        .iter()
+        .filter_map(|method| classify_method(method, &c.const_pool, class_data))
+        .collect();
+    assert_eq!(kinds, vec![SyntheticMethodKind::LambdaBody]);
+}
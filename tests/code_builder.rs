@@ -0,0 +1,199 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{
+    code_attribute_opt_parser, code_attribute_parser, CodeAttribute, CodeAttributeBuilder,
+    CodeBuilderError, ExceptionEntry, ExceptionsAttribute, HasAttributes,
+};
+use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+use classfile_parser::method_info::{MethodAccessFlags, MethodBuilder, MethodBuilderError};
+use classfile_parser::parser::ParseData;
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[test]
+fn test_code_builder_round_trip() {
+    let mut builder = CodeAttributeBuilder::new(2, 1);
+    let outer_start = builder.new_label();
+    let outer_end = builder.new_label();
+    let outer_handler = builder.new_label();
+    let inner_start = builder.new_label();
+    let inner_end = builder.new_label();
+    let inner_handler = builder.new_label();
+
+    // The inner handler is declared first, so that it is tried first
+    builder.exception_handler(
+        inner_start,
+        inner_end,
+        inner_handler,
+        ConstantPoolIndexRaw::new(7),
+    );
+    builder.exception_handler(
+        outer_start,
+        outer_end,
+        outer_handler,
+        ConstantPoolIndexRaw::new(0),
+    );
+
+    builder.bind(outer_start).unwrap();
+    // nop
+    builder.emit(&[0x00]);
+    builder.bind(inner_start).unwrap();
+    // iconst_0, pop
+    builder.emit(&[0x03, 0x57]);
+    builder.bind(inner_end).unwrap();
+    // goto +5
+    builder.emit(&[0xA7, 0x00, 0x05]);
+    builder.bind(outer_end).unwrap();
+    builder.bind(inner_handler).unwrap();
+    builder.bind(outer_handler).unwrap();
+    // athrow, return
+    builder.emit(&[0xBF, 0xB1]);
+    builder.attribute(ConstantPoolIndexRaw::new(3), vec![0, 0]);
+
+    let mut data = vec![0xAA; 3];
+    let (range, built) = builder.build(&mut data).expect("failed to build code");
+    assert_eq!(range, 3..data.len());
+
+    let (rest, parsed) = code_attribute_parser(ParseData::from_range(&data, range.clone()))
+        .expect("failed to parse");
+    assert!(rest.is_empty());
+    assert_eq!(parsed.code_length, 8);
+    assert_eq!(parsed.code, built.code);
+    assert_eq!(
+        &data[parsed.code.clone()],
+        &[0x00, 0x03, 0x57, 0xA7, 0x00, 0x05, 0xBF, 0xB1]
+    );
+    assert_eq!(parsed.max_stack, 2);
+    assert_eq!(parsed.max_locals, 1);
+
+    assert_eq!(parsed.exception_table_length, 2);
+    assert_eq!(parsed.exception_table[0].start_pc.0, 1);
+    assert_eq!(parsed.exception_table[0].end_pc.0, 3);
+    assert_eq!(parsed.exception_table[0].catch_type.0, 7);
+    assert_eq!(parsed.exception_table[1].start_pc.0, 0);
+    assert_eq!(parsed.exception_table[1].end_pc.0, 6);
+    assert_eq!(parsed.exception_table[1].handler_pc.0, 6);
+
+    assert_eq!(parsed.attributes_count, 1);
+    assert_eq!(parsed.attributes, built.attributes);
+
+    // The lazily parsed exception table has the same entries
+    let (_, opt) = code_attribute_opt_parser(ParseData::from_range(&data, range)).unwrap();
+    let pcs = |entry: &ExceptionEntry| {
+        (
+            entry.start_pc.0,
+            entry.end_pc.0,
+            entry.handler_pc.0,
+            entry.catch_type.0,
+        )
+    };
+    let entries: Vec<_> = opt.exception_entries_iter(&data).map(|e| pcs(&e)).collect();
+    let expected: Vec<_> = parsed.exception_table.iter().map(pcs).collect();
+    assert_eq!(entries, expected);
+}
+
+#[test]
+fn test_code_builder_errors() {
+    let mut builder = CodeAttributeBuilder::new(0, 0);
+    let start = builder.label_here().unwrap();
+    let end = builder.new_label();
+    builder.exception_handler(start, end, start, ConstantPoolIndexRaw::new(0));
+    builder.emit(&[0xB1]);
+
+    let mut data = Vec::new();
+    assert_eq!(
+        builder.build(&mut data).unwrap_err(),
+        CodeBuilderError::UnboundLabel(end)
+    );
+
+    let mut builder = CodeAttributeBuilder::new(0, 0);
+    let start = builder.label_here().unwrap();
+    builder.exception_handler(start, start, start, ConstantPoolIndexRaw::new(0));
+    assert_eq!(
+        builder.build(&mut data).unwrap_err(),
+        CodeBuilderError::EmptyHandlerRange {
+            start_pc: 0,
+            end_pc: 0
+        }
+    );
+
+    // Handlers aren't reordered, even when a later one is narrower
+    let mut builder = CodeAttributeBuilder::new(0, 0);
+    let start = builder.label_here().unwrap();
+    builder.emit(&[0x00, 0x00]);
+    let middle = builder.label_here().unwrap();
+    builder.emit(&[0xB1]);
+    let end = builder.label_here().unwrap();
+    builder.exception_handler(start, end, start, ConstantPoolIndexRaw::new(0));
+    builder.exception_handler(middle, end, start, ConstantPoolIndexRaw::new(0));
+    let (_, built) = builder.build(&mut data).unwrap();
+    let starts: Vec<_> = built
+        .exception_table
+        .iter()
+        .map(|entry| entry.start_pc.0)
+        .collect();
+    assert_eq!(starts, [0, 2]);
+
+    // A label from another builder is an error rather than a panic
+    let mut other = CodeAttributeBuilder::new(0, 0);
+    let foreign = (0..5).map(|_| other.new_label()).last().unwrap();
+    assert_eq!(
+        builder.bind(foreign).unwrap_err(),
+        CodeBuilderError::UnknownLabel(foreign)
+    );
+    builder.exception_handler(start, foreign, start, ConstantPoolIndexRaw::new(0));
+    assert_eq!(
+        builder.build(&mut data).unwrap_err(),
+        CodeBuilderError::UnknownLabel(foreign)
+    );
+}
+
+#[test]
+fn test_method_builder_throws() {
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut c = ClassFile::parse(class_data, &ParseOptions::default()).unwrap();
+    let mut data = class_data.to_vec();
+    let pool_size = c.const_pool_size;
+
+    let mut code = CodeAttributeBuilder::new(0, 1);
+    // return
+    code.emit(&[0xB1]);
+    let mut builder = MethodBuilder::new(MethodAccessFlags::PUBLIC, "close", "()V");
+    builder
+        .throws(&["java/io/IOException", "java/lang/Object"])
+        .code(code);
+    let index = builder.build(&mut c, &mut data).unwrap();
+    assert_eq!(index, c.methods.len() - 1);
+    // ()V, Code and java/lang/Object are reused, so only the name, the IOException class and
+    // its name, and Exceptions are added
+    assert_eq!(c.const_pool_size, pool_size + 4);
+
+    assert_eq!(
+        builder.build(&mut c, &mut data).unwrap_err(),
+        MethodBuilderError::DuplicateMethod
+    );
+
+    let written = c.to_bytes(&data).unwrap();
+    let c = ClassFile::parse(&written, &ParseOptions::default()).unwrap();
+    let method = &c.methods[index];
+    let exceptions = method
+        .find_attribute::<ExceptionsAttribute>(&c.const_pool, &written)
+        .unwrap()
+        .unwrap();
+    let names: Vec<_> = exceptions
+        .exception_table
+        .iter()
+        .map(|&class| {
+            let class = c.const_pool.get_t(class).unwrap();
+            c.const_pool
+                .get_t(class.name_index)
+                .unwrap()
+                .as_text(&written)
+        })
+        .collect();
+    assert_eq!(names, ["java/io/IOException", "java/lang/Object"]);
+    let code = method
+        .find_attribute::<CodeAttribute>(&c.const_pool, &written)
+        .unwrap()
+        .unwrap();
+    assert_eq!(&written[code.code], [0xB1]);
+}
@@ -0,0 +1,30 @@
+extern crate classfile_parser;
+
+use classfile_parser::parser::combinators::{count_sv, count_sv_offsets, skip_count};
+use classfile_parser::parser::ParseData;
+use nom::number::complete::{be_u16, be_u8};
+use smallvec::SmallVec;
+
+#[test]
+fn test_combinators() {
+    // A count followed by that many u16s, then a trailing byte
+    let data: &[u8] = &[0x00, 0x03, 0x00, 0x07, 0x00, 0x08, 0x01, 0x00, 0xff];
+
+    let input = ParseData::from_pos(data, 2);
+    let (rest, values): (_, SmallVec<[u16; 4]>) =
+        count_sv::<_, _, nom::error::Error<_>, _, 4>(be_u16, 3)(input.clone()).unwrap();
+    assert_eq!(values.as_slice(), [7, 8, 0x100]);
+    assert_eq!(rest.pos(), 8);
+
+    let (rest, ()) = skip_count::<_, _, nom::error::Error<_>, _>(be_u16, 3)(input.clone()).unwrap();
+    assert_eq!(rest.pos(), 8);
+
+    let (rest, values): (_, SmallVec<[(usize, u16); 4]>) =
+        count_sv_offsets::<_, nom::error::Error<_>, _, 4>(be_u16, 3)(input.clone()).unwrap();
+    assert_eq!(values.as_slice(), [(2, 7), (4, 8), (6, 0x100)]);
+    let (_, last) = be_u8::<_, nom::error::Error<_>>(rest).unwrap();
+    assert_eq!(last, 0xff);
+
+    // Running out of input is an error rather than a short result
+    assert!(count_sv::<_, _, nom::error::Error<_>, _, 4>(be_u16, 4)(input.clone()).is_err());
+}
@@ -0,0 +1,12 @@
+//! Helpers shared between the integration tests
+
+use classfile_parser::ClassFile;
+
+/// The raw index of the utf8 constant with the text, which must be in the pool
+pub fn utf8_index(c: &ClassFile, data: &[u8], text: &str) -> u16 {
+    c.const_pool
+        .utf8_iter(data)
+        .find(|(_, entry)| entry == text)
+        .map(|(index, _)| index.0)
+        .unwrap()
+}
@@ -0,0 +1,124 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::AttributeOwner;
+use classfile_parser::method_info::MethodAccessFlags;
+use classfile_parser::remap::IndexRemap;
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[test]
+fn test_method_structurally_eq() {
+    let classes: [&[u8]; 3] = [
+        include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class"),
+        include_bytes!("../java-assets/compiled-classes/Instructions.class"),
+        include_bytes!("../java-assets/compiled-classes/SwitchMap.class"),
+    ];
+    for data in classes {
+        let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+
+        // Renumbering the constants moves some `ldc`s to `ldc_w`, which shifts the code after them
+        let mut shuffled = c.clone();
+        let mut shuffled_data = data.to_vec();
+        let order: Vec<_> = c.const_pool.indices().collect();
+        let remap = IndexRemap::from_order(&c.const_pool, order.into_iter().rev()).unwrap();
+        remap.fit_ldc(&mut shuffled, &mut shuffled_data).unwrap();
+        remap.apply(&mut shuffled, &mut shuffled_data).unwrap();
+        let shuffled_data = shuffled.to_bytes(&shuffled_data).unwrap();
+        let shuffled = ClassFile::parse(&shuffled_data, &ParseOptions::default()).unwrap();
+
+        for (i, method) in c.methods.iter().enumerate() {
+            for (j, other) in shuffled.methods.iter().enumerate() {
+                let eq = method
+                    .structurally_eq(&c, data, other, &shuffled, &shuffled_data)
+                    .unwrap();
+                assert_eq!(eq, i == j);
+            }
+        }
+
+        // Debug attributes of the code don't matter, but the method's flags do
+        let mut stripped = c.clone();
+        let mut stripped_data = data.to_vec();
+        for i in 0..stripped.methods.len() {
+            for name in ["LineNumberTable", "LocalVariableTable"] {
+                stripped
+                    .remove_attribute(&mut stripped_data, AttributeOwner::Code(i), name, false)
+                    .unwrap();
+            }
+        }
+        assert_ne!(stripped.to_bytes(&stripped_data).unwrap(), data);
+        for (method, other) in c.methods.iter().zip(stripped.methods.iter()) {
+            assert!(method
+                .structurally_eq(&c, data, other, &stripped, &stripped_data)
+                .unwrap());
+        }
+        for method in stripped.methods.iter_mut() {
+            method.access_flags.toggle(MethodAccessFlags::SYNTHETIC);
+        }
+        for (method, other) in c.methods.iter().zip(stripped.methods.iter()) {
+            assert!(!method
+                .structurally_eq(&c, data, other, &stripped, &stripped_data)
+                .unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_structurally_eq_shared_dynamic_constants() {
+    use classfile_parser::attribute_info::CodeAttributeBuilder;
+    use classfile_parser::builder::ClassFileBuilder;
+    use classfile_parser::constant_info::{ConstantInfo, DynamicConstant, MethodHandleConstant};
+    use classfile_parser::method_info::MethodBuilder;
+
+    // Each dynamic constant's bootstrap arguments are eight copies of the one before it, so
+    // comparing the last one without remembering the pairs already compared takes 8^12 steps
+    let mut class = ClassFileBuilder::new("Chain", Some("java/lang/Object")).unwrap();
+    let constants = class.constants();
+    let bsm = constants
+        .method_ref("Chain", "bsm", "(Ljava/lang/invoke/MethodHandles$Lookup;)I")
+        .unwrap();
+    let handle = constants
+        .add(ConstantInfo::MethodHandle(MethodHandleConstant {
+            reference_kind: 6,
+            reference_index: bsm.into_generic(),
+        }))
+        .unwrap();
+    let name_and_type_index = constants.name_and_type("x", "I").unwrap();
+    let mut bootstrap_methods = Vec::new();
+    let mut previous = None;
+    for i in 0..12u16 {
+        bootstrap_methods.extend_from_slice(&handle.0.to_be_bytes());
+        match previous {
+            Some(previous) => {
+                bootstrap_methods.extend_from_slice(&8u16.to_be_bytes());
+                for _ in 0..8 {
+                    bootstrap_methods.extend_from_slice(&u16::to_be_bytes(previous));
+                }
+            }
+            None => bootstrap_methods.extend_from_slice(&0u16.to_be_bytes()),
+        }
+        let dynamic = constants
+            .add(ConstantInfo::Dynamic(DynamicConstant {
+                bootstrap_method_attr_index: i,
+                name_and_type_index,
+            }))
+            .unwrap();
+        previous = Some(dynamic.0);
+    }
+    let mut info = 12u16.to_be_bytes().to_vec();
+    info.extend_from_slice(&bootstrap_methods);
+    class.attribute("BootstrapMethods", &info).unwrap();
+
+    let mut code = CodeAttributeBuilder::new(1, 0);
+    // ldc_w, ireturn
+    code.emit(&[0x13]);
+    code.emit(&previous.unwrap().to_be_bytes());
+    code.emit(&[0xAC]);
+    let mut method = MethodBuilder::new(MethodAccessFlags::STATIC, "value", "()I");
+    method.code(code);
+    class.method(method);
+    let (c, data) = class.build().unwrap();
+
+    let method = &c.methods[0];
+    assert!(method
+        .structurally_eq(&c, &data, method, &c, &data)
+        .unwrap());
+}
@@ -0,0 +1,91 @@
+extern crate classfile_parser;
+
+use classfile_parser::compat::{compare_classes, ApiElement, ChangeKind, Compatibility};
+use classfile_parser::method_info::MethodAccessFlags;
+use classfile_parser::{class_parser, parser::ParseData, ClassAccessFlags};
+
+#[test]
+fn test_compare_class_api() {
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("not a class file");
+    let old = c.api(class_data).expect("failed to extract api");
+    assert!(old.compare(&old).is_empty());
+
+    let mut new = old.clone();
+    new.access_flags |= ClassAccessFlags::FINAL;
+    new.fields.retain(|field| field.name != "plain");
+    let entry = new
+        .methods
+        .iter_mut()
+        .find(|method| method.name == "entry")
+        .unwrap();
+    entry.access_flags |= MethodAccessFlags::FINAL;
+    entry.exceptions.clear();
+    let mut added = entry.clone();
+    added.name = "added".to_string();
+    added.access_flags = MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT;
+    new.methods.push(added);
+
+    let changes = old.compare(&new);
+    let summary: Vec<_> = changes
+        .iter()
+        .map(|change| {
+            let name = match &change.element {
+                ApiElement::Class => "",
+                ApiElement::Field { name, .. } | ApiElement::Method { name, .. } => name,
+            };
+            (name, &change.kind, change.compatibility)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("", &ChangeKind::MadeFinal, Compatibility::BinaryBreaking),
+            ("plain", &ChangeKind::Removed, Compatibility::BinaryBreaking),
+            ("added", &ChangeKind::Added, Compatibility::SourceBreaking),
+            // The class is final, so nothing could have overridden it
+            ("entry", &ChangeKind::MadeFinal, Compatibility::Compatible),
+            (
+                "entry",
+                &ChangeKind::ExceptionRemoved("java/lang/IllegalStateException".to_string()),
+                Compatibility::SourceBreaking
+            ),
+        ]
+    );
+    assert_eq!(
+        changes.iter().map(|change| change.compatibility).max(),
+        Some(Compatibility::BinaryBreaking)
+    );
+}
+
+#[test]
+fn test_compare_classes() {
+    let generics_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics.class");
+    let basic_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, generics) = class_parser(ParseData::new(generics_data)).unwrap();
+    let (_, basic) = class_parser(ParseData::new(basic_data)).unwrap();
+    let generics = generics.api(generics_data).unwrap();
+    let basic = basic.api(basic_data).unwrap();
+
+    let mut hidden = basic.clone();
+    hidden.name.push_str("Hidden");
+    hidden.access_flags.remove(ClassAccessFlags::PUBLIC);
+
+    let changes = compare_classes([&basic, &generics, &hidden], [&generics, &hidden]);
+    let summary: Vec<_> = changes
+        .iter()
+        .map(|change| (change.class.as_str(), &change.kind, change.compatibility))
+        .collect();
+    assert_eq!(
+        summary,
+        [(
+            basic.name.as_str(),
+            &ChangeKind::Removed,
+            Compatibility::BinaryBreaking
+        )]
+    );
+
+    let changes = compare_classes([&generics, &hidden], [&generics]);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].compatibility, Compatibility::Compatible);
+}
@@ -0,0 +1,157 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{
+    CodeAttribute, ExceptionEntry, HasAttributes, InstructionIndex,
+};
+use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+use classfile_parser::control_flow::{
+    control_flow_graph, ControlFlowError, Edge, EdgeKind, Subroutine,
+};
+use classfile_parser::{ClassFile, ParseOptions};
+
+fn handler(start_pc: u16, end_pc: u16, handler_pc: u16) -> ExceptionEntry {
+    ExceptionEntry {
+        start_pc: InstructionIndex(start_pc),
+        end_pc: InstructionIndex(end_pc),
+        handler_pc: InstructionIndex(handler_pc),
+        catch_type: ConstantPoolIndexRaw::new(0),
+    }
+}
+
+fn edges(edges: &[(EdgeKind, usize)]) -> Vec<Edge> {
+    edges
+        .iter()
+        .map(|&(kind, target)| Edge { kind, target })
+        .collect()
+}
+
+#[test]
+fn test_compiled_classes() {
+    let classes: [&[u8]; 3] = [
+        include_bytes!("../java-assets/compiled-classes/Handlers.class"),
+        include_bytes!("../java-assets/compiled-classes/Instructions.class"),
+        include_bytes!("../java-assets/compiled-classes/SwitchMap.class"),
+    ];
+    for data in classes {
+        let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+        for method in c.methods.iter() {
+            let code: CodeAttribute = match method.find_attribute(&c.const_pool, data).unwrap() {
+                Some(code) => code,
+                None => continue,
+            };
+            let graph = code.control_flow_graph(data).unwrap();
+            assert_eq!(graph.blocks[0].start, 0);
+            assert_eq!(graph.blocks.last().unwrap().end, code.code.len());
+            assert!(graph.subroutines.is_empty());
+        }
+    }
+}
+
+#[test]
+fn test_subroutine() {
+    // A subroutine called from two places, with a handler around the calls
+    let code = [
+        0xa8, 0x00, 0x08, // jsr 8
+        0xa8, 0x00, 0x05, // jsr 8
+        0x04, // iconst_1
+        0xac, // ireturn
+        0x4d, // astore_2
+        0xa9, 0x02, // ret 2
+    ];
+    let graph = control_flow_graph(&code, &[handler(0, 6, 6)]).unwrap();
+    let bounds: Vec<_> = graph
+        .blocks
+        .iter()
+        .map(|block| (block.start, block.end, block.last))
+        .collect();
+    assert_eq!(bounds, [(0, 3, 0), (3, 6, 3), (6, 8, 7), (8, 11, 9)]);
+
+    use EdgeKind::*;
+    assert_eq!(graph.blocks[0].edges, edges(&[(Call, 3), (Exception, 2)]));
+    assert_eq!(graph.blocks[1].edges, edges(&[(Call, 3), (Exception, 2)]));
+    assert_eq!(graph.blocks[2].edges, []);
+    // The `ret` goes back after both calls
+    assert_eq!(graph.blocks[3].edges, edges(&[(Return, 1), (Return, 2)]));
+
+    assert_eq!(
+        graph.subroutines,
+        [Subroutine {
+            entry: 3,
+            callers: vec![0, 1],
+            blocks: vec![3],
+            rets: vec![3],
+        }]
+    );
+    assert_eq!(graph.block_at(9), Some(3));
+    assert_eq!(graph.block_at(11), None);
+}
+
+#[test]
+fn test_nested_subroutines() {
+    let code = [
+        0xc9, 0x00, 0x00, 0x00, 0x06, // jsr_w 6
+        0xb1, // return
+        0x4c, // astore_1
+        0xa8, 0x00, 0x07, // jsr 14
+        0xc4, 0xa9, 0x00, 0x01, // wide ret 1
+        0x4d, // astore_2
+        0xa9, 0x02, // ret 2
+    ];
+    let graph = control_flow_graph(&code, &[]).unwrap();
+    let starts: Vec<_> = graph.blocks.iter().map(|block| block.start).collect();
+    assert_eq!(starts, [0, 5, 6, 10, 14]);
+
+    use EdgeKind::*;
+    assert_eq!(graph.blocks[0].edges, edges(&[(Call, 2)]));
+    assert_eq!(graph.blocks[2].edges, edges(&[(Call, 4)]));
+    assert_eq!(graph.blocks[3].edges, edges(&[(Return, 1)]));
+    assert_eq!(graph.blocks[4].edges, edges(&[(Return, 3)]));
+
+    // The outer subroutine continues after its nested call, without taking in the inner one
+    assert_eq!(
+        graph.subroutine(2),
+        Some(&Subroutine {
+            entry: 2,
+            callers: vec![0],
+            blocks: vec![2, 3],
+            rets: vec![3],
+        })
+    );
+    assert_eq!(
+        graph.subroutine(4),
+        Some(&Subroutine {
+            entry: 4,
+            callers: vec![2],
+            blocks: vec![4],
+            rets: vec![4],
+        })
+    );
+}
+
+#[test]
+fn test_control_flow_errors() {
+    assert_eq!(
+        control_flow_graph(&[0xa9, 0x01], &[]),
+        Err(ControlFlowError::UnmatchedRet { offset: 0 })
+    );
+    assert_eq!(
+        control_flow_graph(&[0xa7, 0x00, 0x05], &[]),
+        Err(ControlFlowError::BadTarget {
+            offset: 0,
+            target: 5
+        })
+    );
+    // The subroutine would return past the end of the code
+    assert_eq!(
+        control_flow_graph(&[0xa8, 0x00, 0x00], &[]),
+        Err(ControlFlowError::FallsOffEnd { offset: 0 })
+    );
+    assert_eq!(
+        control_flow_graph(&[0x04, 0xac], &[handler(0, 2, 2)]),
+        Err(ControlFlowError::BadHandler { handler_pc: 2 })
+    );
+    assert_eq!(
+        control_flow_graph(&[0x10, 0x01, 0xac], &[handler(1, 3, 0)]),
+        Err(ControlFlowError::BadHandler { handler_pc: 0 })
+    );
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "digest")]
+extern crate classfile_parser;
+
+use classfile_parser::digest::DigestAlgorithm;
+use classfile_parser::method_info::MethodAccessFlags;
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[test]
+fn test_class_digest() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+
+    let sha = c.digest(data, DigestAlgorithm::Sha256).unwrap();
+    assert_eq!(
+        sha.raw.to_string(),
+        "d57e361f72c5c2d8220673f8d5a1d4e66367c190d009901027ceb08814218391"
+    );
+    let md5 = c.digest(data, DigestAlgorithm::Md5).unwrap();
+    assert_eq!(md5.raw.to_string(), "47241a3a03788fb1165ff1105fe99379");
+    assert_eq!(md5.structural.0.len(), 16);
+
+    // Code and other attributes aren't part of the structure
+    for method in c.methods.iter_mut() {
+        method.attributes.clear();
+        method.attributes_count = 0;
+    }
+    assert_eq!(
+        c.digest(data, DigestAlgorithm::Sha256).unwrap().structural,
+        sha.structural
+    );
+
+    c.methods[0].access_flags |= MethodAccessFlags::FINAL;
+    assert_ne!(
+        c.digest(data, DigestAlgorithm::Sha256).unwrap().structural,
+        sha.structural
+    );
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "stackmap")]
+extern crate classfile_parser;
+
+use std::fs;
+
+use classfile_parser::attribute_info::{CodeAttribute, HasAttributes, StackMapTableAttribute};
+use classfile_parser::frames::compute_frames;
+use classfile_parser::provider::MemoryClassProvider;
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[test]
+fn test_frames_match_javac() {
+    let classes: Vec<Vec<u8>> = fs::read_dir("java-assets/compiled-classes")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "class"))
+        .map(|path| fs::read(path).unwrap())
+        .collect();
+    let mut provider = MemoryClassProvider::new();
+    for data in classes.iter() {
+        // The malformed class has no name to be found by
+        let _ = provider.insert_data(data.clone());
+    }
+
+    let mut checked = 0;
+    for data in classes.iter() {
+        let c = match ClassFile::parse(data, &ParseOptions::default()) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        for method in c.methods.iter() {
+            let code: CodeAttribute = match method.find_attribute(&c.const_pool, data).unwrap() {
+                Some(code) => code,
+                None => continue,
+            };
+            let frames = compute_frames(
+                &c,
+                method,
+                &data[code.code.clone()],
+                &code.exception_table,
+                code.max_locals,
+                data,
+                &provider,
+            )
+            .unwrap();
+
+            // Every class javac names in a frame is already in the pool
+            let mut pool = c.const_pool.clone();
+            let mut written = data.clone();
+            let table = frames.to_stack_map_table(&mut pool, &mut written).unwrap();
+            assert_eq!(written.len(), data.len());
+
+            let javac: Option<StackMapTableAttribute> =
+                code.find_attribute(&c.const_pool, data).unwrap();
+            match javac {
+                Some(javac) => {
+                    assert_eq!(table.to_bytes(), javac.to_bytes());
+                    checked += 1;
+                }
+                None => assert!(frames.frames.is_empty()),
+            }
+        }
+    }
+    assert!(checked > 0);
+}
@@ -0,0 +1,67 @@
+extern crate classfile_parser;
+
+use classfile_parser::import::ImportError;
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[test]
+fn test_import_members() {
+    let into_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let from_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let from = ClassFile::parse(from_data, &ParseOptions::default()).unwrap();
+    let mut c = ClassFile::parse(into_data, &ParseOptions::default()).unwrap();
+    let mut data = into_data.to_vec();
+
+    let mut imported = Vec::new();
+    for method in from.methods.iter() {
+        match c.import_method(&mut data, &from, from_data, method) {
+            Ok(index) => imported.push((index, method)),
+            Err(err) => assert_eq!(err, ImportError::DuplicateMember),
+        }
+    }
+    assert!(imported.len() > 1);
+    let constants_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Constants.class");
+    let constants = ClassFile::parse(constants_data, &ParseOptions::default()).unwrap();
+    let field_count = c.fields.len();
+    for field in constants.fields.iter() {
+        c.import_field(&mut data, &constants, constants_data, field)
+            .unwrap();
+    }
+
+    // The copies survive being written out and read back, and still do the same thing
+    let written = c.to_bytes(&data).unwrap();
+    let c = ClassFile::parse(&written, &ParseOptions::default()).unwrap();
+    assert_eq!(c.fields.len(), field_count + constants.fields.len());
+    let values = c.static_final_values(&written).unwrap();
+    assert!(!values.is_empty());
+    assert_eq!(
+        values,
+        constants.static_final_values(constants_data).unwrap()
+    );
+    assert!(c.bootstrap_errors(&written).unwrap().is_empty());
+    for (index, method) in imported {
+        assert!(method
+            .structurally_eq(&from, from_data, &c.methods[index], &c, &written)
+            .unwrap());
+    }
+}
+
+#[test]
+fn test_import_reuses_constants() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let from = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let mut c = from.clone();
+    let mut out = data.to_vec();
+    assert_eq!(
+        c.import_method(&mut out, &from, data, &from.methods[0]),
+        Err(ImportError::DuplicateMember)
+    );
+
+    // Putting a method back into its own class needs no new constants or bootstrap methods
+    let last = c.methods.pop().unwrap();
+    c.methods_count -= 1;
+    let index = c.import_method(&mut out, &from, data, &last).unwrap();
+    assert_eq!(index, c.methods.len() - 1);
+    assert_eq!(c.const_pool.len(), from.const_pool.len());
+    assert_eq!(c.attributes, from.attributes);
+    assert_eq!(c.to_bytes(&out).unwrap(), data);
+}
@@ -0,0 +1,69 @@
+extern crate classfile_parser;
+
+use classfile_parser::index::{ArchiveIndex, ClassIndex, ClassPathIndex, IndexReadError};
+use classfile_parser::{ClassFile, ClassFileOpt, ParseOptions};
+
+#[test]
+fn test_class_index() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let index = ClassIndex::build(data).unwrap();
+
+    assert_eq!(index.name, "uk/co/palmr/karl/examples/BasicClass");
+    assert_eq!(index.super_class.as_deref(), Some("java/lang/Object"));
+    assert_eq!(index.access_flags, c.raw_flags());
+    assert_eq!(index.version, c.version);
+    assert_eq!(index.methods.len(), c.methods.len());
+    for (member, method) in index.methods.iter().zip(c.methods.iter()) {
+        let name = c.const_pool.get_t(method.name_index).unwrap();
+        assert_eq!(member.name, name.as_text(data));
+        assert_eq!(member.access_flags, method.raw_flags());
+    }
+
+    let opt = ClassFileOpt::parse(data, &ParseOptions::default()).unwrap();
+    assert_eq!(
+        index.fields.first().map(|f| f.offset),
+        Some(opt.fields.start_pos())
+    );
+    assert_eq!(index.methods[0].offset, opt.methods.start_pos());
+}
+
+#[test]
+fn test_class_path_index_round_trip() {
+    let classes = [
+        &include_bytes!("../java-assets/compiled-classes/BasicClass.class")[..],
+        &include_bytes!("../java-assets/compiled-classes/BasicInterface.class")[..],
+        &include_bytes!("../java-assets/compiled-classes/Generics.class")[..],
+    ];
+    let mut index = ClassPathIndex::new();
+    index.insert(ArchiveIndex {
+        source: "examples.jar".to_string(),
+        stamp: 42,
+        classes: classes
+            .iter()
+            .map(|data| ClassIndex::build(data).unwrap())
+            .collect(),
+    });
+
+    let mut saved = Vec::new();
+    index.write_to(&mut saved).unwrap();
+    let loaded = ClassPathIndex::read_from(&saved[..]).unwrap();
+    assert_eq!(loaded, index);
+
+    assert!(loaded.get_fresh("examples.jar", 42).is_some());
+    assert!(loaded.get_fresh("examples.jar", 43).is_none());
+    let (archive, class) = loaded
+        .find_class("uk/co/palmr/classfileparser/BasicInterface")
+        .unwrap();
+    assert_eq!(archive.source, "examples.jar");
+    assert!(class.super_class.is_some());
+
+    assert!(matches!(
+        ClassPathIndex::read_from(&saved[..saved.len() - 1]),
+        Err(IndexReadError::Malformed)
+    ));
+    assert!(matches!(
+        ClassPathIndex::read_from(&b"nope"[..]),
+        Err(IndexReadError::BadMagic)
+    ));
+}
@@ -0,0 +1,266 @@
+extern crate classfile_parser;
+
+mod common;
+
+use classfile_parser::attribute_info::{CodeAttribute, CodeAttributeBuilder, HasAttributes};
+#[cfg(feature = "stackmap")]
+use classfile_parser::constant_info::ConstantInfo;
+use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+use classfile_parser::inline::{inline_subroutines, InlineError};
+use classfile_parser::{ClassFile, ParseOptions};
+
+use common::utf8_index;
+
+/// Build a Code attribute with no exception handlers
+fn build(code: &[u8], data: &mut Vec<u8>) -> CodeAttribute {
+    let mut builder = CodeAttributeBuilder::new(1, 3);
+    builder.emit(code);
+    builder.build(data).unwrap().1
+}
+
+#[test]
+fn test_inline_subroutines() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let line_number_table = utf8_index(&c, &data, "LineNumberTable");
+
+    // A subroutine called from two places, with a handler around the calls
+    let mut builder = CodeAttributeBuilder::new(1, 3);
+    let start = builder.label_here().unwrap();
+    builder.emit(&[
+        0xa8, 0x00, 0x08, // jsr 8
+        0xa8, 0x00, 0x05, // jsr 8
+    ]);
+    let handler = builder.label_here().unwrap();
+    builder.emit(&[
+        0x04, // iconst_1
+        0xac, // ireturn
+        0x4d, // astore_2
+        0xa9, 0x02, // ret 2
+    ]);
+    builder.exception_handler(start, handler, handler, ConstantPoolIndexRaw::new(0));
+    let lines = [0, 3, 0, 0, 0, 10, 0, 6, 0, 11, 0, 8, 0, 20];
+    builder.attribute(ConstantPoolIndexRaw::new(line_number_table), lines.to_vec());
+    let (range, _) = builder.build(&mut data).unwrap();
+
+    let pool = &c.const_pool;
+    let code = c.methods[0]
+        .attributes
+        .iter_mut()
+        .find(|attr| {
+            pool.get_t(attr.attribute_name_index)
+                .unwrap()
+                .as_text(&data)
+                == "Code"
+        })
+        .unwrap();
+    code.attribute_length = range.len() as u32;
+    code.info = range;
+
+    assert_eq!(c.inline_subroutines(&mut data), Ok(1));
+    let code: CodeAttribute = c.methods[0]
+        .find_attribute(&c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        &data[code.code.clone()],
+        &[
+            0x01, 0xa7, 0x00, 0x09, // aconst_null, goto 10
+            0x01, 0xa7, 0x00, 0x09, // aconst_null, goto 14
+            0x04, 0xac, // iconst_1, ireturn
+            0x4d, 0xa7, 0xff, 0xf9, // astore_2, goto 4
+            0x4d, 0xa7, 0xff, 0xf9, // astore_2, goto 8
+        ]
+    );
+
+    // The handler only covers the main code, since the copies aren't in its range
+    assert_eq!(code.exception_table_length, 1);
+    let entry = &code.exception_table[0];
+    assert_eq!(
+        (entry.start_pc.0, entry.end_pc.0, entry.handler_pc.0),
+        (0, 8, 8)
+    );
+
+    // Each copy of the subroutine starts the line again
+    let table = code
+        .find_attribute_info(&c.const_pool, &data, "LineNumberTable")
+        .unwrap();
+    assert_eq!(
+        &data[table.info.clone()],
+        &[0, 4, 0, 0, 0, 10, 0, 8, 0, 11, 0, 10, 0, 20, 0, 14, 0, 20]
+    );
+
+    // There is nothing left to inline
+    assert_eq!(c.inline_subroutines(&mut data), Ok(0));
+}
+
+#[test]
+fn test_inline_wide_branches() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+
+    // The ifeq is as far as it can go, so the call growing pushes its target out of range
+    let mut code = vec![
+        0x99, 0x7f, 0xff, // ifeq 32767
+        0xa8, 0x7f, 0xfd, // jsr 32768
+    ];
+    code.resize(32767, 0x00);
+    code.extend_from_slice(&[
+        0xb1, // return
+        0x4c, // astore_1
+        0xa9, 0x01, // ret 1
+    ]);
+    let code = build(&code, &mut data);
+
+    let info = inline_subroutines(&code, &c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    // The code starts after max_stack, max_locals, and code_length
+    assert_eq!(&info[4..8], &32778u32.to_be_bytes());
+    let inlined = &info[8..8 + 32778];
+    assert_eq!(
+        &inlined[..12],
+        &[
+            0x9a, 0x00, 0x08, // ifne 8
+            0xc8, 0x00, 0x00, 0x80, 0x02, // goto_w 32773
+            0x01, // aconst_null
+            0xa7, 0x7f, 0xfd, // goto 32774
+        ]
+    );
+    assert_eq!(
+        &inlined[32773..],
+        &[
+            0xb1, // return
+            0x4c, // astore_1
+            0xa7, 0x80, 0x05, // goto 12
+        ]
+    );
+}
+
+#[test]
+fn test_inline_errors() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let mut inline = |code: &[u8]| {
+        let code = build(code, &mut data);
+        inline_subroutines(&code, &c.const_pool, &data)
+    };
+
+    // Code without subroutines is left alone
+    assert_eq!(inline(&[0x04, 0xac]), Ok(None));
+    assert_eq!(
+        inline(&[
+            0xa8, 0x00, 0x04, // jsr 4
+            0xb1, // return
+            0x4c, // astore_1
+            0xa8, 0xff, 0xff, // jsr 4
+            0xa9, 0x01, // ret 1
+        ]),
+        Err(InlineError::RecursiveSubroutine(4))
+    );
+    assert_eq!(
+        inline(&[
+            0xa8, 0x00, 0x05, // jsr 5
+            0xa9, 0x01, // ret 1
+            0x4c, // astore_1
+            0xa9, 0x01, // ret 1
+        ]),
+        Err(InlineError::UnmatchedRet(3))
+    );
+    assert_eq!(
+        inline(&[
+            0xa8, 0x00, 0x02, // jsr 2
+            0xb1, // return
+        ]),
+        Err(InlineError::InvalidTarget(2))
+    );
+}
+
+#[cfg(feature = "stackmap")]
+#[test]
+fn test_inline_subroutines_with_frames() {
+    use classfile_parser::attribute_info::StackMapTableAttribute;
+    use classfile_parser::provider::MemoryClassProvider;
+
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Factorial.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let pool_size = c.const_pool.len();
+
+    // `static int factorial(int)`, with a subroutine called from both sides of a branch
+    let mut builder = CodeAttributeBuilder::new(2, 3);
+    builder.emit(&[
+        0x1a, // iload_0
+        0x99, 0x00, 0x0a, // ifeq 11
+    ]);
+    let start = builder.label_here().unwrap();
+    builder.emit(&[0xa8, 0x00, 0x0c]); // jsr 16
+    let end = builder.label_here().unwrap();
+    builder.emit(&[
+        0x1a, // iload_0
+        0x04, // iconst_1
+        0x60, // iadd
+        0xac, // ireturn
+        0xa8, 0x00, 0x05, // jsr 16
+        0x03, // iconst_0
+        0xac, // ireturn
+        0x4d, // astore_2
+        0xa9, 0x02, // ret 2
+    ]);
+    let handler = builder.label_here().unwrap();
+    builder.emit(&[0xbf]); // athrow
+    builder.exception_handler(start, end, handler, ConstantPoolIndexRaw::new(0));
+    let (range, _) = builder.build(&mut data).unwrap();
+
+    let pool = &c.const_pool;
+    let code = c.methods[1]
+        .attributes
+        .iter_mut()
+        .find(|attr| {
+            pool.get_t(attr.attribute_name_index)
+                .unwrap()
+                .as_text(&data)
+                == "Code"
+        })
+        .unwrap();
+    code.attribute_length = range.len() as u32;
+    code.info = range;
+
+    let provider = MemoryClassProvider::new();
+    assert_eq!(
+        c.inline_subroutines_with_frames(&mut data, &provider),
+        Ok(1)
+    );
+    let code: CodeAttribute = c.methods[1]
+        .find_attribute(&c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    let table: StackMapTableAttribute = code.find_attribute(&c.const_pool, &data).unwrap().unwrap();
+    let throwable = utf8_index(&c, &data, "java/lang/Throwable");
+    let throwable = c
+        .const_pool
+        .iter_indexed()
+        .find(|(_, entry)| matches!(entry, ConstantInfo::Class(class) if class.name_index.0 == throwable))
+        .map(|(index, _)| index.0)
+        .unwrap();
+    let [high, low] = throwable.to_be_bytes();
+    assert_eq!(
+        table.to_bytes(),
+        &[
+            0, 6, // number_of_entries
+            253, 0, 8, 0, 5, // append top, null at 8
+            249, 0, 3, // chop at 12
+            253, 0, 3, 0, 5, // append top, null at 16
+            255, 0, 1, 0, 1, 1, 0, 1, 7, high, low, // full int, Throwable on the stack at 18
+            64, 5, // null on the stack at 19
+            67, 5, // null on the stack at 23
+        ]
+    );
+
+    // The handler's Throwable wasn't in the pool yet
+    assert_eq!(c.const_pool.len(), pool_size + 2);
+    assert_eq!(c.const_pool_size, c.const_pool.len() + 1);
+}
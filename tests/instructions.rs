@@ -0,0 +1,189 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{CodeAttribute, HasAttributes};
+use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+use classfile_parser::histogram::InstructionStats;
+use classfile_parser::instructions::{
+    code_iter, decode_instruction, opcode_mnemonic, Instruction, InstructionError, WideInstruction,
+};
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[test]
+fn test_decode_classes() {
+    let classes: [&[u8]; 4] = [
+        include_bytes!("../java-assets/compiled-classes/Instructions.class"),
+        include_bytes!("../java-assets/compiled-classes/SwitchMap.class"),
+        include_bytes!("../java-assets/compiled-classes/SwitchMap$1.class"),
+        include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class"),
+    ];
+    let mut mnemonics = Vec::new();
+    for data in classes {
+        let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+        for method in c.methods.iter() {
+            let code: CodeAttribute = match method.find_attribute(&c.const_pool, data).unwrap() {
+                Some(code) => code,
+                None => continue,
+            };
+            let bytes = &data[code.code.clone()];
+            let mut end = 0;
+            for result in code.instructions(data) {
+                let (offset, instruction) = result.unwrap();
+                assert_eq!(usize::from(offset), end);
+                assert_eq!(instruction.opcode(), bytes[end]);
+                let (decoded, len) = decode_instruction(bytes, end).unwrap();
+                assert_eq!(decoded, instruction);
+                end += len;
+                mnemonics.push(instruction.mnemonic());
+            }
+            assert_eq!(end, bytes.len());
+        }
+    }
+    for expected in ["lookupswitch", "tableswitch", "invokedynamic", "getstatic"] {
+        assert!(mnemonics.contains(&expected), "no {}", expected);
+    }
+}
+
+#[test]
+fn test_decode_bytes() {
+    let code = [
+        // nop
+        &[0x00][..],
+        // tableswitch, padded to offset 4, from 1 to 2
+        &[0xaa, 0, 0],
+        &[
+            0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 24, 0, 0, 0, 28,
+        ],
+        // wide iinc 300 by -1
+        &[0xc4, 0x84, 1, 44, 0xff, 0xff],
+        // goto back to the start
+        &[0xa7, 0xff, 0xe2],
+    ]
+    .concat();
+    let decoded: Vec<_> = code_iter(&code).map(Result::unwrap).collect();
+    assert_eq!(
+        decoded,
+        [
+            (0, Instruction::Nop),
+            (
+                1,
+                Instruction::Tableswitch {
+                    default: 20,
+                    low: 1,
+                    high: 2,
+                    offsets: vec![24, 28],
+                }
+            ),
+            (
+                24,
+                Instruction::Wide(WideInstruction::Iinc {
+                    index: 300,
+                    value: -1,
+                })
+            ),
+            (30, Instruction::Goto(-30)),
+        ]
+    );
+    assert_eq!(opcode_mnemonic(0xc4), Some("wide"));
+    assert_eq!(opcode_mnemonic(0xca), None);
+
+    // Errors end the iteration
+    let mut iter = code_iter(&[0x00, 0x10]);
+    assert!(iter.next().unwrap().is_ok());
+    assert_eq!(
+        iter.next(),
+        Some(Err(InstructionError::Truncated { offset: 1 }))
+    );
+    assert_eq!(iter.next(), None);
+    assert_eq!(
+        code_iter(&[0xc4, 0x00]).next(),
+        Some(Err(InstructionError::InvalidWide {
+            offset: 0,
+            opcode: 0
+        }))
+    );
+    assert_eq!(
+        code_iter(&[0xfe]).next(),
+        Some(Err(InstructionError::InvalidOpcode {
+            offset: 0,
+            opcode: 0xfe
+        }))
+    );
+    // A lookupswitch whose keys aren't in order
+    let unsorted = [
+        0xab, 0, 0, 0, // lookupswitch, padded to offset 4
+        0, 0, 0, 8, 0, 0, 0, 2, // default, 2 pairs
+        0, 0, 0, 5, 0, 0, 0, 8, // 5 => 8
+        0, 0, 0, 5, 0, 0, 0, 8, // 5 => 8
+    ];
+    assert_eq!(
+        code_iter(&unsorted).next(),
+        Some(Err(InstructionError::InvalidSwitch { offset: 0 }))
+    );
+}
+
+#[test]
+fn test_instruction_operands() {
+    assert_eq!(Instruction::Ldc(7).constant_index(), Some(7));
+    let invoke = Instruction::Invokeinterface {
+        index: ConstantPoolIndexRaw::new(300),
+        count: 2,
+    };
+    assert_eq!(invoke.constant_index(), Some(300));
+    assert_eq!(Instruction::Iload(1).constant_index(), None);
+
+    assert_eq!(Instruction::Goto(-3).branch_targets(), [-3]);
+    assert!(!Instruction::Goto(-3).falls_through());
+    // A jsr comes back once its subroutine returns
+    assert_eq!(Instruction::JsrW(70000).branch_targets(), [70000]);
+    assert!(Instruction::JsrW(70000).falls_through());
+    assert!(!Instruction::Wide(WideInstruction::Ret(1)).falls_through());
+    let switch = Instruction::Lookupswitch {
+        default: 20,
+        pairs: vec![(1, 12), (4, 16)],
+    };
+    assert_eq!(switch.branch_targets(), [20, 12, 16]);
+    assert!(!switch.falls_through());
+}
+
+#[test]
+fn test_instruction_stats() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let stats = c.instruction_stats(data).unwrap();
+    assert_eq!(stats.methods.len(), c.methods.len());
+
+    let mut total = 0;
+    for (method, method_stats) in c.methods.iter().zip(stats.methods.iter()) {
+        let code: CodeAttribute = method.find_attribute(&c.const_pool, data).unwrap().unwrap();
+        let method_stats = method_stats.as_ref().unwrap();
+        let count = code.instructions(data).count() as u64;
+        assert_eq!(method_stats.opcodes.total(), count);
+        total += count;
+    }
+    assert_eq!(stats.total.opcodes.total(), total);
+
+    // Only the constructor calls anything, which is the constructor of Object
+    let calls = &stats.total.call_sites;
+    assert_eq!(calls.invokespecial, 1);
+    assert_eq!(calls.total(), 1);
+    assert_eq!(calls.targets.values().sum::<u32>(), 1);
+    assert_eq!(stats.total.opcodes.count_mnemonic("invokespecial"), 1);
+    assert_eq!(stats.total.opcodes.count_mnemonic("putfield"), 2);
+
+    // aload_0, invokevirtual #2, aload_0, invokevirtual #2, invokestatic #3, pop, return
+    let code = [
+        0x2a, 0xb6, 0x00, 0x02, 0x2a, 0xb6, 0x00, 0x02, 0xb8, 0x00, 0x03, 0x57, 0xb1,
+    ];
+    let stats = InstructionStats::from_code(&code).unwrap();
+    assert_eq!(
+        stats.opcodes.most_common(),
+        [(0x2a, 2), (0xb6, 2), (0x57, 1), (0xb1, 1), (0xb8, 1)]
+    );
+    assert_eq!(stats.call_sites.invokevirtual, 2);
+    assert_eq!(stats.call_sites.invokestatic, 1);
+    assert_eq!(
+        stats.call_sites.targets.iter().collect::<Vec<_>>(),
+        [(&2, &2), (&3, &1)]
+    );
+    assert!(InstructionStats::from_code(&[0xb6, 0x00]).is_err());
+}
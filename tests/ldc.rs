@@ -0,0 +1,238 @@
+extern crate classfile_parser;
+
+#[cfg(feature = "stackmap")]
+mod common;
+
+use classfile_parser::attribute_info::CodeAttributeBuilder;
+#[cfg(feature = "stackmap")]
+use classfile_parser::attribute_info::{CodeAttribute, HasAttributes};
+use classfile_parser::builder::ConstantPoolBuilder;
+use classfile_parser::constant_info::ConstantInfo;
+#[cfg(feature = "stackmap")]
+use classfile_parser::constant_info::IntegerConstant;
+#[cfg(feature = "stackmap")]
+use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+#[cfg(feature = "stackmap")]
+use classfile_parser::remap::IndexRemap;
+use classfile_parser::remap::RemapError;
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[cfg(feature = "stackmap")]
+use common::utf8_index;
+
+#[cfg(feature = "stackmap")]
+fn method_code(c: &ClassFile, data: &[u8], name: &str) -> CodeAttribute {
+    let method = c
+        .methods
+        .iter()
+        .find(|method| c.const_pool.get_t(method.name_index).unwrap().as_text(data) == name)
+        .unwrap();
+    method.find_attribute(&c.const_pool, data).unwrap().unwrap()
+}
+
+#[cfg(feature = "stackmap")]
+#[test]
+fn test_fit_ldc_narrows() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let line_number_table = utf8_index(&c, &data, "LineNumberTable");
+    let stack_map_table = utf8_index(&c, &data, "StackMapTable");
+    let string = c
+        .const_pool
+        .iter_indexed()
+        .find(|(_, entry)| matches!(entry, ConstantInfo::String(_)))
+        .unwrap()
+        .0;
+    let [_, low] = string.0.to_be_bytes();
+
+    let mut builder = CodeAttributeBuilder::new(1, 1);
+    builder.emit(&[
+        0x03, // iconst_0
+        0x99, 0x00, 0x0d, // ifeq 14
+    ]);
+    let start = builder.label_here().unwrap();
+    builder.emit(&[0x13, 0x00, low]); // ldc_w
+    builder.emit(&[0x57]); // pop
+    let end = builder.label_here().unwrap();
+    builder.emit(&[0xc8, 0x00, 0x00, 0x00, 0x06]); // goto_w 14
+    let handler = builder.label_here().unwrap();
+    builder.emit(&[
+        0x00, // nop
+        0xb1, // return
+    ]);
+    builder.exception_handler(start, end, handler, ConstantPoolIndexRaw::new(0));
+    let lines = [0, 3, 0, 0, 0, 10, 0, 4, 0, 11, 0, 14, 0, 12];
+    builder.attribute(ConstantPoolIndexRaw::new(line_number_table), lines.to_vec());
+    // A same_locals_1_stack_item_frame at 13 with an Object, and a full_frame at 14 with an
+    // Uninitialized from 7
+    let frames = [0, 2, 64 + 13, 7, 0, 4, 255, 0, 0, 0, 1, 8, 0, 7, 0, 0];
+    builder.attribute(ConstantPoolIndexRaw::new(stack_map_table), frames.to_vec());
+    let (range, _) = builder.build(&mut data).unwrap();
+
+    let pool = &c.const_pool;
+    let code = c.methods[0]
+        .attributes
+        .iter_mut()
+        .find(|attr| {
+            pool.get_t(attr.attribute_name_index)
+                .unwrap()
+                .as_text(&data)
+                == "Code"
+        })
+        .unwrap();
+    code.attribute_length = range.len() as u32;
+    code.info = range;
+
+    assert_eq!(c.fit_ldc(&mut data), Ok(1));
+    let code: CodeAttribute = c.methods[0]
+        .find_attribute(&c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        &data[code.code.clone()],
+        &[
+            0x03, // iconst_0
+            0x99, 0x00, 0x0c, // ifeq 13
+            0x12, low,  // ldc
+            0x57, // pop
+            0xc8, 0x00, 0x00, 0x00, 0x06, // goto_w 13, which is left wide
+            0x00, // nop
+            0xb1, // return
+        ]
+    );
+    let entry = &code.exception_table[0];
+    assert_eq!(
+        (entry.start_pc.0, entry.end_pc.0, entry.handler_pc.0),
+        (4, 7, 12)
+    );
+    let table = code
+        .find_attribute_info(&c.const_pool, &data, "LineNumberTable")
+        .unwrap();
+    assert_eq!(
+        &data[table.info.clone()],
+        &[0, 3, 0, 0, 0, 10, 0, 4, 0, 11, 0, 13, 0, 12]
+    );
+    let table = code
+        .find_attribute_info(&c.const_pool, &data, "StackMapTable")
+        .unwrap();
+    assert_eq!(
+        &data[table.info.clone()],
+        &[0, 2, 64 + 12, 7, 0, 4, 255, 0, 0, 0, 1, 8, 0, 6, 0, 0]
+    );
+
+    // Everything is as narrow as it can be
+    assert_eq!(c.fit_ldc(&mut data), Ok(0));
+}
+
+#[cfg(feature = "stackmap")]
+#[test]
+fn test_fit_ldc_for_remap() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let before = method_code(&c, &data, "test");
+    let stack_map_table = before
+        .find_attribute_info(&c.const_pool, &data, "StackMapTable")
+        .unwrap();
+    let stack_map_table = data[stack_map_table.info.clone()].to_vec();
+
+    // Move the string loaded by `ldc #3` after enough new constants that it no longer fits
+    for value in 0..300 {
+        c.const_pool
+            .push(ConstantInfo::Integer(IntegerConstant { value }))
+            .unwrap();
+    }
+    c.const_pool_size = c.const_pool.len() + 1;
+    let string = c.const_pool.indices().nth(2).unwrap();
+    let mut order: Vec<_> = c.const_pool.indices().filter(|&i| i != string).collect();
+    order.push(string);
+    let remap = IndexRemap::from_order(&c.const_pool, order).unwrap();
+
+    let mut unfitted = c.clone();
+    assert_eq!(
+        remap.apply(&mut unfitted, &mut data.clone()),
+        Err(RemapError::LdcIndexTooLarge { offset: 95 })
+    );
+
+    assert_eq!(remap.fit_ldc(&mut c, &mut data), Ok(1));
+    remap.apply(&mut c, &mut data).unwrap();
+    let code = method_code(&c, &data, "test");
+    let new_index = remap.get(string).unwrap().0;
+    assert!(new_index > 255);
+    assert_eq!(code.code_length, before.code_length + 1);
+    assert_eq!(
+        &data[code.code.start + 95..][..3],
+        &[0x13, (new_index >> 8) as u8, new_index as u8]
+    );
+
+    // Everything after the ldc has moved along by one, and the frames are all before it
+    let lines = code
+        .find_attribute_info(&c.const_pool, &data, "LineNumberTable")
+        .unwrap();
+    assert_eq!(&data[lines.info.end - 4..lines.info.end], &[0, 129, 0, 18]);
+    let frames = code
+        .find_attribute_info(&c.const_pool, &data, "StackMapTable")
+        .unwrap();
+    assert_eq!(&data[frames.info.clone()], &stack_map_table[..]);
+    assert_eq!(c.fit_ldc(&mut data), Ok(0));
+
+    // Moving it back can't narrow the `ldc_w` until its old index fits, so that is left for after
+    let mut order: Vec<_> = c.const_pool.indices().collect();
+    let string = order.pop().unwrap();
+    order.insert(2, string);
+    let remap = IndexRemap::from_order(&c.const_pool, order).unwrap();
+    assert_eq!(remap.fit_ldc(&mut c, &mut data), Ok(0));
+    remap.apply(&mut c, &mut data).unwrap();
+    assert_eq!(c.fit_ldc(&mut data), Ok(1));
+    let code = method_code(&c, &data, "test");
+    assert_eq!(&data[code.code], &original[before.code]);
+}
+
+#[test]
+fn test_fit_ldc_deeply_nested_type_annotation() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let type_annotations = ConstantPoolBuilder::with_pool(&mut c.const_pool, &mut data, |pool| {
+        pool.utf8("RuntimeVisibleTypeAnnotations")
+    })
+    .unwrap();
+    c.const_pool_size = c.const_pool.len() + 1;
+    let [high, low] = type_annotations.0.to_be_bytes();
+    let string = c
+        .const_pool
+        .iter_indexed()
+        .find(|(_, entry)| matches!(entry, ConstantInfo::String(_)))
+        .unwrap()
+        .0;
+    let [_, string_low] = string.0.to_be_bytes();
+
+    let mut builder = CodeAttributeBuilder::new(1, 1);
+    builder.emit(&[0x13, 0x00, string_low]); // ldc_w
+    builder.emit(&[0x57, 0xb1]); // pop, return
+                                 // An offset_target on the ldc_w, whose only element is an array nested far too deeply
+    let mut annotations = vec![0, 1, 0x43, 0, 0, 0, high, low, 0, 1, high, low];
+    for _ in 0..100_000 {
+        annotations.extend_from_slice(&[b'[', 0, 1]);
+    }
+    annotations.extend_from_slice(&[b'I', high, low]);
+    builder.attribute(type_annotations, annotations);
+    let (range, _) = builder.build(&mut data).unwrap();
+
+    let code = c.methods[0]
+        .attributes
+        .iter_mut()
+        .find(|attr| {
+            c.const_pool
+                .get_t(attr.attribute_name_index)
+                .unwrap()
+                .as_text(&data)
+                == "Code"
+        })
+        .unwrap();
+    code.attribute_length = range.len() as u32;
+    code.info = range;
+
+    assert_eq!(c.fit_ldc(&mut data), Err(RemapError::Malformed));
+}
@@ -0,0 +1,108 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::InnerClassAccessFlags;
+use classfile_parser::field_info::FieldAccessFlags;
+use classfile_parser::method_info::MethodAccessFlags;
+use classfile_parser::modifiers::{class_modifiers, field_modifiers, method_modifiers};
+use classfile_parser::{ClassFile, ClassKind, ParseOptions};
+
+/// The name and source modifiers of each method that isn't synthetic
+fn method_modifiers_of(c: &ClassFile, data: &[u8]) -> Vec<(String, String)> {
+    let kind = c.kind(data);
+    c.methods
+        .iter()
+        .filter(|method| !method.access_flags.contains(MethodAccessFlags::SYNTHETIC))
+        .map(|method| {
+            let name = c
+                .const_pool
+                .get_utf8_text(method.name_index, data)
+                .unwrap()
+                .into_owned();
+            let modifiers = method_modifiers(kind, &name, method.access_flags);
+            (name, modifiers)
+        })
+        .collect()
+}
+
+#[test]
+fn test_interface_modifiers() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Modifiers.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    assert_eq!(c.source_modifiers(data), "public");
+    assert_eq!(field_modifiers(c.kind(data), c.fields[0].access_flags), "");
+
+    let methods = method_modifiers_of(&c, data);
+    let modifiers = |name: &str| {
+        methods
+            .iter()
+            .find(|(method, _)| method == name)
+            .map(|(_, modifiers)| modifiers.as_str())
+            .unwrap()
+    };
+    assert_eq!(modifiers("run"), "");
+    assert_eq!(modifiers("name"), "default");
+    assert_eq!(modifiers("create"), "static");
+    assert_eq!(modifiers("helper"), "private");
+}
+
+#[test]
+fn test_enum_modifiers() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Modifiers$Level.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    // Nested in an interface, so it is public, but never written as static or final
+    assert_eq!(c.kind(data), ClassKind::Enum);
+    assert_eq!(c.source_modifiers(data), "public");
+
+    let fields: Vec<_> = c
+        .fields
+        .iter()
+        .filter(|field| !field.access_flags.contains(FieldAccessFlags::SYNTHETIC))
+        .map(|field| field_modifiers(ClassKind::Enum, field.access_flags))
+        .collect();
+    assert_eq!(fields, vec!["", "", "private final"]);
+
+    let methods = method_modifiers_of(&c, data);
+    assert!(methods.contains(&("<init>".to_string(), String::new())));
+    assert!(methods.contains(&("weight".to_string(), "public synchronized".to_string())));
+    assert!(methods.contains(&("values".to_string(), "public static".to_string())));
+}
+
+#[test]
+fn test_class_modifiers() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Modifiers$Base.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    // The InnerClasses entry has the static flag that the class file's own flags can't
+    assert_eq!(c.source_modifiers(data), "public abstract static");
+    assert_eq!(
+        field_modifiers(ClassKind::Class, c.fields[0].access_flags),
+        "protected transient volatile"
+    );
+    // The default constructor has the access of its class
+    let methods = method_modifiers_of(&c, data);
+    assert!(methods.contains(&("<init>".to_string(), "public".to_string())));
+    assert!(methods.contains(&("step".to_string(), "protected abstract".to_string())));
+    assert!(methods.contains(&("poke".to_string(), "static native".to_string())));
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    assert_eq!(c.source_modifiers(data), "public");
+
+    // Bridge and varargs share their bits with volatile and transient, but aren't modifiers
+    let flags = MethodAccessFlags::PUBLIC
+        | MethodAccessFlags::BRIDGE
+        | MethodAccessFlags::VARARGS
+        | MethodAccessFlags::SYNTHETIC;
+    assert_eq!(
+        method_modifiers(ClassKind::Class, "compareTo", flags),
+        "public"
+    );
+    let flags = InnerClassAccessFlags::PRIVATE
+        | InnerClassAccessFlags::STATIC
+        | InnerClassAccessFlags::FINAL
+        | InnerClassAccessFlags::ENUM;
+    assert_eq!(class_modifiers(ClassKind::Enum, flags), "private");
+    assert_eq!(
+        class_modifiers(ClassKind::Module, InnerClassAccessFlags::empty()),
+        ""
+    );
+}
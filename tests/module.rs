@@ -0,0 +1,74 @@
+#![cfg(feature = "modules")]
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{
+    module_attribute_parser, module_main_class_attribute_parser, module_packages_attribute_parser,
+    ExportsFlags, ModuleFlags, RequiresFlags,
+};
+use classfile_parser::parser::ParseData;
+
+fn u16s(values: &[u16]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_be_bytes())
+        .collect()
+}
+
+#[test]
+fn test_module_attributes() {
+    let info = u16s(&[
+        // name, flags, version
+        1, 0x0020, 2, //
+        // requires java.base, mandated, without a version
+        1, 3, 0x8000, 0, //
+        // exports a package to one module
+        1, 4, 0, 1, 5, //
+        // opens a package to every module
+        1, 6, 0x1000, 0, //
+        // uses
+        1, 7, //
+        // provides a service with two implementations
+        1, 8, 2, 9, 10,
+    ]);
+    let (rest, module) = module_attribute_parser(ParseData::new(&info)).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(module.module_name_index.0, 1);
+    assert_eq!(module.flags(), ModuleFlags::OPEN);
+    assert_eq!(module.module_version_index.0, 2);
+
+    assert_eq!(module.requires.len(), 1);
+    assert_eq!(module.requires[0].requires_index.0, 3);
+    assert_eq!(module.requires[0].flags(), RequiresFlags::MANDATED);
+    assert!(module.requires[0].requires_version_index.is_zero());
+
+    assert_eq!(module.exports.len(), 1);
+    assert_eq!(module.exports[0].package_index.0, 4);
+    assert!(!module.exports[0].is_unqualified());
+    assert_eq!(module.exports[0].to_index[0].0, 5);
+    assert_eq!(module.opens.len(), 1);
+    assert_eq!(module.opens[0].flags(), ExportsFlags::SYNTHETIC);
+    assert!(module.opens[0].is_unqualified());
+
+    assert_eq!(module.uses_count, 1);
+    assert_eq!(module.uses_index[0].0, 7);
+    assert_eq!(module.provides.len(), 1);
+    assert_eq!(module.provides[0].provides_index.0, 8);
+    let with: Vec<_> = module.provides[0]
+        .provides_with_index
+        .iter()
+        .map(|index| index.0)
+        .collect();
+    assert_eq!(with, [9, 10]);
+
+    // The counts must be followed by that many entries
+    assert!(module_attribute_parser(ParseData::new(&info[..info.len() - 2])).is_err());
+
+    let info = u16s(&[2, 11, 12]);
+    let (_, packages) = module_packages_attribute_parser(ParseData::new(&info)).unwrap();
+    assert_eq!(packages.package_count, 2);
+    assert_eq!(packages.package_index[1].0, 12);
+
+    let info = u16s(&[13]);
+    let (_, main_class) = module_main_class_attribute_parser(ParseData::new(&info)).unwrap();
+    assert_eq!(main_class.main_class_index.0, 13);
+}
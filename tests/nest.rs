@@ -0,0 +1,175 @@
+extern crate classfile_parser;
+
+use classfile_parser::nest::{nest_tree, top_level_class, NestError, NestedKind};
+use classfile_parser::provider::MemoryClassProvider;
+
+const NESTED: &str = "uk/co/palmr/classfileparser/Nested";
+
+fn provider() -> MemoryClassProvider {
+    let mut provider = MemoryClassProvider::new();
+    let classes: [&[u8]; 8] = [
+        include_bytes!("../java-assets/compiled-classes/Nested.class"),
+        include_bytes!("../java-assets/compiled-classes/Nested$Inner.class"),
+        include_bytes!("../java-assets/compiled-classes/Nested$Inner$Deeper.class"),
+        include_bytes!("../java-assets/compiled-classes/Nested$StaticMember.class"),
+        include_bytes!("../java-assets/compiled-classes/Nested$1Local.class"),
+        include_bytes!("../java-assets/compiled-classes/Nested$1.class"),
+        include_bytes!("../java-assets/compiled-classes/SwitchMap.class"),
+        include_bytes!("../java-assets/compiled-classes/SwitchMap$1.class"),
+    ];
+    for data in classes.iter() {
+        provider.insert_data(data.to_vec()).unwrap();
+    }
+    provider
+}
+
+#[test]
+fn test_nest_tree() {
+    let provider = provider();
+    let deeper = format!("{}$Inner$Deeper", NESTED);
+    assert_eq!(top_level_class(&provider, &deeper).unwrap(), NESTED);
+
+    let tree = nest_tree(&provider, &deeper).unwrap();
+    assert_eq!(tree.name, NESTED);
+    assert_eq!(tree.kind, NestedKind::TopLevel);
+    assert_eq!(
+        tree.qualified_name.as_deref(),
+        Some("uk.co.palmr.classfileparser.Nested")
+    );
+
+    let children: Vec<_> = tree.children.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        children,
+        [
+            "uk/co/palmr/classfileparser/Nested$1",
+            "uk/co/palmr/classfileparser/Nested$1Local",
+            "uk/co/palmr/classfileparser/Nested$Inner",
+            "uk/co/palmr/classfileparser/Nested$StaticMember",
+        ]
+    );
+
+    let deeper = tree.find(&deeper).unwrap();
+    assert_eq!(deeper.kind, NestedKind::Member);
+    assert_eq!(deeper.simple_name.as_deref(), Some("Deeper"));
+    assert_eq!(
+        deeper.qualified_name.as_deref(),
+        Some("uk.co.palmr.classfileparser.Nested.Inner.Deeper")
+    );
+
+    let local = &tree.children[1];
+    assert_eq!(local.kind, NestedKind::Local);
+    assert_eq!(local.simple_name.as_deref(), Some("Local"));
+    assert_eq!(local.qualified_name, None);
+    assert_eq!(
+        local.enclosing_method,
+        Some(("make".to_string(), "()Ljava/lang/Runnable;".to_string()))
+    );
+
+    let anonymous = &tree.children[0];
+    assert_eq!(anonymous.kind, NestedKind::Anonymous);
+    assert_eq!(anonymous.simple_name, None);
+    assert_eq!(anonymous.enclosing_method.as_ref().unwrap().0, "anonymous");
+}
+
+#[test]
+fn test_nest_tree_without_nest_attributes() {
+    // Compiled for Java 8, so there is no NestHost or NestMembers
+    let provider = provider();
+    let tree = nest_tree(&provider, "uk/co/palmr/classfileparser/SwitchMap$1").unwrap();
+    assert_eq!(tree.name, "uk/co/palmr/classfileparser/SwitchMap");
+    assert_eq!(tree.children.len(), 1);
+    assert_eq!(tree.children[0].kind, NestedKind::Anonymous);
+
+    assert_eq!(
+        nest_tree(&provider, "does/not/Exist"),
+        Err(NestError::MissingClass("does/not/Exist".to_string()))
+    );
+}
+
+#[test]
+fn test_insert_class_without_name() {
+    use classfile_parser::builder::ClassFileBuilder;
+    use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+    use classfile_parser::LoadError;
+
+    let (mut c, data) = ClassFileBuilder::new("example/Named", Some("java/lang/Object"))
+        .unwrap()
+        .build()
+        .unwrap();
+    c.this_class = ConstantPoolIndexRaw::new(0);
+    let data = c.to_bytes(&data).unwrap();
+
+    let mut provider = MemoryClassProvider::new();
+    assert!(matches!(
+        provider.insert_data(data),
+        Err(LoadError::InvalidIndex(0))
+    ));
+    assert!(provider.is_empty());
+}
+
+#[test]
+fn test_nest_attributes() {
+    use classfile_parser::attribute_info::{
+        EnclosingMethodAttribute, HasAttributes, InnerClassAccessFlags, InnerClassesAttribute,
+        NestHostAttribute, NestMembersAttribute,
+    };
+    use classfile_parser::constant_info::ClassConstant;
+    use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+    use classfile_parser::{ClassFile, ParseOptions};
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Nested.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let class_name = |index: ConstantPoolIndexRaw<ClassConstant>| {
+        let class = pool.get_t(index).unwrap();
+        pool.get_t(class.name_index).unwrap().as_text(data)
+    };
+
+    let members: NestMembersAttribute = c.find_attribute(pool, data).unwrap().unwrap();
+    assert_eq!(members.number_of_classes as usize, members.classes.len());
+    let mut names: Vec<_> = members.classes.iter().map(|&i| class_name(i)).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "uk/co/palmr/classfileparser/Nested$1",
+            "uk/co/palmr/classfileparser/Nested$1Local",
+            "uk/co/palmr/classfileparser/Nested$Inner",
+            "uk/co/palmr/classfileparser/Nested$Inner$Deeper",
+            "uk/co/palmr/classfileparser/Nested$StaticMember",
+        ]
+    );
+    let host: Option<NestHostAttribute> = c.find_attribute(pool, data).unwrap();
+    assert!(host.is_none());
+
+    let inner: InnerClassesAttribute = c.find_attribute(pool, data).unwrap().unwrap();
+    let member = inner
+        .classes
+        .iter()
+        .find(|entry| class_name(entry.inner_class_info_index).ends_with("$StaticMember"))
+        .unwrap();
+    assert_eq!(class_name(member.outer_class_info_index), NESTED);
+    assert_eq!(
+        member.access_flags(),
+        InnerClassAccessFlags::PUBLIC | InnerClassAccessFlags::STATIC
+    );
+    let anonymous = inner
+        .classes
+        .iter()
+        .find(|entry| class_name(entry.inner_class_info_index).ends_with("$1"))
+        .unwrap();
+    assert!(anonymous.outer_class_info_index.is_zero());
+    assert!(anonymous.inner_name_index.is_zero());
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Nested$1Local.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let pool = &c.const_pool;
+    let enclosing: EnclosingMethodAttribute = c.find_attribute(pool, data).unwrap().unwrap();
+    let class = pool.get_t(enclosing.class_index).unwrap();
+    assert_eq!(pool.get_t(class.name_index).unwrap().as_text(data), NESTED);
+    let method = pool.get_t(enclosing.method_index).unwrap();
+    assert_eq!(pool.get_t(method.name_index).unwrap().as_text(data), "make");
+    let host: NestHostAttribute = c.find_attribute(pool, data).unwrap().unwrap();
+    let host = pool.get_t(host.host_class_index).unwrap();
+    assert_eq!(pool.get_t(host.name_index).unwrap().as_text(data), NESTED);
+}
@@ -0,0 +1,24 @@
+#![cfg(feature = "threading")]
+extern crate classfile_parser;
+
+use std::sync::Arc;
+
+use classfile_parser::prefetch::prefetch;
+use classfile_parser::{class_parser, class_parser_opt, parser::ParseData};
+
+#[test]
+fn test_prefetch() {
+    let class_data: Arc<[u8]> =
+        Arc::from(&include_bytes!("../java-assets/compiled-classes/BasicClass.class")[..]);
+    let (_, eager) = class_parser(ParseData::new(&class_data)).expect("not a class file");
+    let (_, mut c) = class_parser_opt(ParseData::new(&class_data)).expect("not a class file");
+    assert!(!c.methods.has_data());
+
+    let pending = prefetch(&c, class_data.clone());
+    pending.finish(&mut c).expect("failed to prefetch");
+
+    assert_eq!(c.methods.data().unwrap(), eager.methods.as_slice());
+    assert_eq!(c.fields.data().unwrap().len(), eager.fields.len());
+    assert_eq!(c.attributes.data().unwrap(), eager.attributes.as_slice());
+    assert!(c.load_method_at(&class_data, 1).is_ok());
+}
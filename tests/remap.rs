@@ -0,0 +1,289 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{
+    AttributeOwner, CodeAttribute, CodeAttributeBuilder, HasAttributes, SourceFileAttribute,
+};
+use classfile_parser::constant_info::{ConstantInfo, MethodRefConstant, Utf8Constant};
+use classfile_parser::constant_pool::{ConstantPool, ConstantPoolIndexRaw};
+use classfile_parser::remap::{IndexRemap, RemapError};
+use classfile_parser::{ClassFile, ParseOptions};
+
+fn utf8(pool: &ConstantPool, data: &[u8], index: ConstantPoolIndexRaw<Utf8Constant>) -> String {
+    pool.get_t(index).unwrap().as_text(data).into_owned()
+}
+
+/// The name of every method referenced by the code of each method, in order
+fn called_methods(c: &ClassFile, data: &[u8]) -> Vec<String> {
+    let mut names = Vec::new();
+    for method in c.methods.iter() {
+        let code: CodeAttribute = match method.find_attribute(&c.const_pool, data).unwrap() {
+            Some(code) => code,
+            None => continue,
+        };
+        let code = &data[code.code];
+        for window in code.windows(3) {
+            // invokevirtual, invokespecial, invokestatic
+            if (0xb6..=0xb8).contains(&window[0]) {
+                let index = u16::from_be_bytes([window[1], window[2]]);
+                let method_ref: Option<&MethodRefConstant> =
+                    c.const_pool.get_t(ConstantPoolIndexRaw::new(index));
+                if let Some(method_ref) = method_ref {
+                    if let Some(ConstantInfo::NameAndType(nat)) =
+                        c.const_pool.get(method_ref.name_and_type_index)
+                    {
+                        names.push(utf8(&c.const_pool, data, nat.name_index));
+                    }
+                }
+            }
+        }
+    }
+    names
+}
+
+#[test]
+fn test_reverse_pool() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Instructions.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let before = c.clone();
+
+    let order: Vec<_> = c.const_pool.indices().collect();
+    let remap = IndexRemap::from_order(&c.const_pool, order.into_iter().rev()).unwrap();
+    assert_eq!(remap.new_len(), c.const_pool.len());
+    remap.apply(&mut c, &mut data).unwrap();
+
+    // Long and Double entries are still followed by their unusable slot
+    let entries: Vec<_> = c.const_pool.iter().collect();
+    for (i, entry) in entries.iter().enumerate() {
+        if matches!(entry, ConstantInfo::Long(_) | ConstantInfo::Double(_)) {
+            assert!(matches!(entries[i + 1], ConstantInfo::Unusable));
+        }
+    }
+    assert_eq!(c.const_pool_size, before.const_pool_size);
+    assert_ne!(c.this_class, before.this_class);
+
+    // Everything resolves to the same values as before
+    let class_name = |c: &ClassFile, data: &[u8]| {
+        let class = c.const_pool.get_t(c.this_class).unwrap();
+        utf8(&c.const_pool, data, class.name_index)
+    };
+    assert_eq!(class_name(&c, &data), class_name(&before, original));
+    for (method, old) in c.methods.iter().zip(before.methods.iter()) {
+        assert_eq!(
+            utf8(&c.const_pool, &data, method.name_index),
+            utf8(&before.const_pool, original, old.name_index)
+        );
+        assert_eq!(
+            utf8(&c.const_pool, &data, method.descriptor_index),
+            utf8(&before.const_pool, original, old.descriptor_index)
+        );
+    }
+    assert!(!called_methods(&before, original).is_empty());
+    assert_eq!(called_methods(&c, &data), called_methods(&before, original));
+}
+
+#[test]
+fn test_identity_keeps_attributes() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let before = c.clone();
+
+    IndexRemap::identity(&c.const_pool)
+        .apply(&mut c, &mut data)
+        .unwrap();
+    assert_eq!(data.len(), original.len());
+    assert_eq!(c.methods, before.methods);
+}
+
+#[test]
+fn test_dropped_entry() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+
+    // Leave out the class's own name
+    let name_index = c.const_pool.get_t(c.this_class).unwrap().name_index;
+    let order: Vec<_> = c
+        .const_pool
+        .indices()
+        .filter(|i| i.0 != name_index.0)
+        .collect();
+    let remap = IndexRemap::from_order(&c.const_pool, order).unwrap();
+    assert_eq!(remap.get(name_index), None);
+
+    let before = c.clone();
+    let mut data = data.to_vec();
+    assert_eq!(
+        remap.apply(&mut c, &mut data),
+        Err(RemapError::Unmapped(name_index.0))
+    );
+    assert_eq!(c.this_class, before.this_class);
+}
+
+#[test]
+fn test_remove_attribute() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let has_utf8 = |c: &ClassFile, data: &[u8], text: &str| {
+        c.const_pool
+            .iter()
+            .any(|entry| matches!(entry, ConstantInfo::Utf8(u) if u.as_text(data) == text))
+    };
+    let pool_len = c.const_pool.len();
+
+    // Nothing else refers to the name, so it is dropped
+    let removed = c
+        .remove_attribute(&mut data, AttributeOwner::Class, "SourceFile", true)
+        .unwrap();
+    assert_eq!(removed, 1);
+    assert_eq!(c.attributes_count, 0);
+    assert_eq!(c.const_pool.len(), pool_len - 1);
+    assert!(!has_utf8(&c, &data, "SourceFile"));
+    assert_eq!(
+        called_methods(&c, &data),
+        called_methods(
+            &ClassFile::parse(original, &ParseOptions::default()).unwrap(),
+            original
+        )
+    );
+
+    // The other methods still have a LineNumberTable, so the name is kept
+    let code_len = |c: &ClassFile, data: &[u8]| {
+        let code: CodeAttribute = c.methods[0]
+            .find_attribute(&c.const_pool, data)
+            .unwrap()
+            .unwrap();
+        (code.attributes_count, code.code_length)
+    };
+    let (attributes_count, code_length) = code_len(&c, &data);
+    let removed = c
+        .remove_attribute(&mut data, AttributeOwner::Code(0), "LineNumberTable", true)
+        .unwrap();
+    assert_eq!(removed, 1);
+    assert_eq!(code_len(&c, &data), (attributes_count - 1, code_length));
+    assert!(has_utf8(&c, &data, "LineNumberTable"));
+
+    // Until it is removed from all of them
+    for i in 1..c.methods.len() {
+        c.remove_attribute(&mut data, AttributeOwner::Code(i), "LineNumberTable", true)
+            .unwrap();
+    }
+    assert!(!has_utf8(&c, &data, "LineNumberTable"));
+
+    assert_eq!(
+        c.remove_attribute(&mut data, AttributeOwner::Method(0), "Nonexistent", false)
+            .unwrap(),
+        0
+    );
+    assert!(c
+        .remove_attribute(&mut data, AttributeOwner::Field(100), "Signature", false)
+        .is_err());
+}
+
+#[test]
+fn test_replace_attribute() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let (init, _) = c
+        .const_pool
+        .utf8_iter(&data)
+        .find(|(_, text)| text == "<init>")
+        .unwrap();
+
+    // Point the SourceFile at another name
+    let replaced = c
+        .replace_attribute(
+            &mut data,
+            AttributeOwner::Class,
+            "SourceFile",
+            &init.0.to_be_bytes(),
+        )
+        .unwrap();
+    assert!(replaced);
+    let source: SourceFileAttribute = c.find_attribute(&c.const_pool, &data).unwrap().unwrap();
+    assert_eq!(source.sourcefile_index, init);
+
+    // Empty the line numbers of the first method, keeping the rest of its code
+    let code_of = |c: &ClassFile, data: &[u8]| -> CodeAttribute {
+        c.methods[0]
+            .find_attribute(&c.const_pool, data)
+            .unwrap()
+            .unwrap()
+    };
+    let before = code_of(&c, &data);
+    let replaced = c
+        .replace_attribute(
+            &mut data,
+            AttributeOwner::Code(0),
+            "LineNumberTable",
+            &[0, 0],
+        )
+        .unwrap();
+    assert!(replaced);
+    let after = code_of(&c, &data);
+    assert_eq!(after.attributes_count, before.attributes_count);
+    assert_eq!(data[after.code.clone()], original[before.code.clone()]);
+    let table = after
+        .find_attribute_info(&c.const_pool, &data, "LineNumberTable")
+        .unwrap();
+    assert_eq!(table.attribute_length, 2);
+    assert_eq!(data[table.info.clone()], [0, 0]);
+
+    assert!(!c
+        .replace_attribute(&mut data, AttributeOwner::Method(0), "Nonexistent", &[])
+        .unwrap());
+    assert!(c
+        .replace_attribute(&mut data, AttributeOwner::Field(100), "Signature", &[])
+        .is_err());
+}
+
+#[test]
+fn test_remap_subroutine_instructions() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    let string = c
+        .const_pool
+        .iter_indexed()
+        .find(|(_, entry)| matches!(entry, ConstantInfo::String(_)))
+        .unwrap()
+        .0;
+
+    // Legacy code with subroutines, followed by an index which is only rewritten if the
+    // instructions before it were decoded with the right lengths
+    let subroutines = [
+        0xc9, 0x00, 0x00, 0x00, 0x05, // jsr_w +5
+        0xa8, 0x00, 0x03, // jsr +3
+        0xa9, 0x01, // ret 1
+        0xc4, 0xa9, 0x00, 0x01, // wide ret 1
+    ];
+    let mut builder = CodeAttributeBuilder::new(1, 2);
+    builder.emit(&subroutines);
+    builder
+        .emit(&[0x13])
+        .emit(&string.0.to_be_bytes())
+        .emit(&[0xb0]);
+    let (range, _) = builder.build(&mut data).unwrap();
+    let pool = &c.const_pool;
+    let code = c.methods[0]
+        .attributes
+        .iter_mut()
+        .find(|attr| utf8(pool, &data, attr.attribute_name_index) == "Code")
+        .unwrap();
+    code.attribute_length = range.len() as u32;
+    code.info = range;
+
+    let order: Vec<_> = c.const_pool.indices().collect();
+    let remap = IndexRemap::from_order(&c.const_pool, order.into_iter().rev()).unwrap();
+    remap.apply(&mut c, &mut data).unwrap();
+
+    let code: CodeAttribute = c.methods[0]
+        .find_attribute(&c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    let code = &data[code.code];
+    assert_eq!(&code[..subroutines.len()], &subroutines);
+    assert_eq!(&code[15..17], &remap.get(string).unwrap().0.to_be_bytes());
+}
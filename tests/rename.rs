@@ -0,0 +1,173 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{
+    HasAttributes, InnerClassesAttribute, NestHostAttribute, SignatureAttribute,
+};
+use classfile_parser::builder::ClassFileBuilder;
+use classfile_parser::remap::RemapError;
+use classfile_parser::resolved::ResolvedClass;
+use classfile_parser::{ClassFile, ParseOptions};
+
+/// The class and outer class names, and the simple name, of each InnerClasses entry
+fn inner_classes(c: &ClassFile, data: &[u8]) -> Vec<(String, Option<String>, Option<String>)> {
+    let pool = &c.const_pool;
+    let attr: InnerClassesAttribute = c.find_attribute(pool, data).unwrap().unwrap();
+    attr.classes
+        .iter()
+        .map(|entry| {
+            (
+                pool.get_class_name(entry.inner_class_info_index, data)
+                    .unwrap()
+                    .into_owned(),
+                pool.get_class_name(entry.outer_class_info_index, data)
+                    .map(|name| name.into_owned()),
+                pool.get_utf8_text(entry.inner_name_index, data)
+                    .map(|name| name.into_owned()),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_rename_class() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Nested$Inner.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    c.rename_class(
+        &mut data,
+        "uk/co/palmr/classfileparser/Nested",
+        "shaded/Renamed",
+    )
+    .unwrap();
+
+    // Written out and parsed again, the class only refers to the new name
+    let bytes = c.to_bytes(&data).unwrap();
+    let parsed = ClassFile::parse(&bytes, &ParseOptions::default()).unwrap();
+    let resolved = ResolvedClass::from_class_file(&parsed, &bytes).unwrap();
+    assert_eq!(ResolvedClass::from_class_file(&c, &data).unwrap(), resolved);
+    assert!(!format!("{:?}", resolved).contains("classfileparser"));
+
+    // Nested classes are renamed with their outer class
+    assert_eq!(resolved.name, "shaded/Renamed$Inner");
+    assert_eq!(resolved.source_file.as_deref(), Some("Renamed.java"));
+    assert_eq!(resolved.fields[0].descriptor, "Lshaded/Renamed;");
+    assert_eq!(resolved.methods[0].descriptor, "(Lshaded/Renamed;)V");
+    assert_eq!(
+        inner_classes(&parsed, &bytes),
+        vec![
+            (
+                "shaded/Renamed$Inner".to_string(),
+                Some("shaded/Renamed".to_string()),
+                Some("Inner".to_string())
+            ),
+            (
+                "shaded/Renamed$Inner$Deeper".to_string(),
+                Some("shaded/Renamed$Inner".to_string()),
+                Some("Deeper".to_string())
+            ),
+        ]
+    );
+    let host: NestHostAttribute = parsed
+        .find_attribute(&parsed.const_pool, &bytes)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        parsed
+            .const_pool
+            .get_class_name(host.host_class_index, &bytes),
+        Some("shaded/Renamed".into())
+    );
+}
+
+#[test]
+fn test_rename_nested_class() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Nested$Inner.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    c.rename_class(
+        &mut data,
+        "uk/co/palmr/classfileparser/Nested$Inner",
+        "uk/co/palmr/classfileparser/Nested$Other",
+    )
+    .unwrap();
+
+    // The simple name follows the class, and the source file is still named after the outermost
+    // class
+    let entries = inner_classes(&c, &data);
+    assert_eq!(entries[0].0, "uk/co/palmr/classfileparser/Nested$Other");
+    assert_eq!(entries[0].2.as_deref(), Some("Other"));
+    assert_eq!(
+        entries[1].0,
+        "uk/co/palmr/classfileparser/Nested$Other$Deeper"
+    );
+    assert_eq!(entries[1].2.as_deref(), Some("Deeper"));
+    let resolved = ResolvedClass::from_class_file(&c, &data).unwrap();
+    assert_eq!(resolved.source_file.as_deref(), Some("Nested.java"));
+    assert_eq!(
+        resolved.fields[0].descriptor,
+        "Luk/co/palmr/classfileparser/Nested;"
+    );
+
+    // The outer class refers to the nested class in the same way
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Nested.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    c.rename_class(
+        &mut data,
+        "uk/co/palmr/classfileparser/Nested$Inner",
+        "uk/co/palmr/classfileparser/Nested$Other",
+    )
+    .unwrap();
+    let entries = inner_classes(&c, &data);
+    let other = entries
+        .iter()
+        .find(|entry| entry.0 == "uk/co/palmr/classfileparser/Nested$Other")
+        .unwrap();
+    assert_eq!(other.2.as_deref(), Some("Other"));
+    assert_eq!(
+        c.this_class_name(&data).unwrap(),
+        "uk/co/palmr/classfileparser/Nested"
+    );
+}
+
+#[test]
+fn test_rename_referenced_class() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/Generics$Inner.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    c.rename_class(&mut data, "java/util/List", "shaded/List")
+        .unwrap();
+
+    let method = &c.methods[0];
+    let signature: SignatureAttribute = method
+        .find_attribute(&c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        c.const_pool.get_utf8_text(signature.signature_index, &data),
+        Some("(Lshaded/List<TT;>;)V".into())
+    );
+    assert_eq!(
+        c.const_pool.get_utf8_text(method.descriptor_index, &data),
+        Some("(Luk/co/palmr/classfileparser/Generics;Lshaded/List;)V".into())
+    );
+    // The class itself is not renamed, so neither is its source file
+    let resolved = ResolvedClass::from_class_file(&c, &data).unwrap();
+    assert_eq!(resolved.name, "uk/co/palmr/classfileparser/Generics$Inner");
+    assert_eq!(resolved.source_file.as_deref(), Some("Generics.java"));
+}
+
+#[test]
+fn test_rename_class_unknown_attribute() {
+    let mut builder = ClassFileBuilder::new("example/Old", Some("java/lang/Object")).unwrap();
+    builder.attribute("Custom", &[0, 1]).unwrap();
+    let (mut c, mut data) = builder.build().unwrap();
+    let before = data.clone();
+
+    assert_eq!(
+        c.rename_class(&mut data, "example/Old", "example/New"),
+        Err(RemapError::UnknownAttribute("Custom".to_string()))
+    );
+    assert_eq!(data, before);
+    assert_eq!(c.this_class_name(&data).unwrap(), "example/Old");
+}
@@ -0,0 +1,108 @@
+extern crate classfile_parser;
+
+use std::borrow::Cow;
+
+use classfile_parser::constant_info::ConstantValue;
+use classfile_parser::descriptor::{DescriptorType, DescriptorTypeBasic};
+use classfile_parser::method_info::MethodAccessFlags;
+use classfile_parser::resolved::{Borrowed, ResolvedClass};
+use classfile_parser::{class_parser, parser::ParseData};
+
+fn resolve(class_data: &[u8]) -> ResolvedClass {
+    let (_, c) = class_parser(ParseData::new(class_data)).expect("not a class file");
+    ResolvedClass::from_class_file(&c, class_data).expect("failed to resolve class")
+}
+
+#[test]
+fn test_resolved_class() {
+    // The data is dropped after resolving, since nothing refers to it
+    let class_data = include_bytes!("../java-assets/compiled-classes/BasicClass.class").to_vec();
+    let class = resolve(&class_data);
+    drop(class_data);
+
+    assert_eq!(class.name, "uk/co/palmr/karl/examples/BasicClass");
+    assert_eq!(class.super_class.as_deref(), Some("java/lang/Object"));
+    assert_eq!(class.source_file.as_deref(), Some("BasicClass.java"));
+    assert_eq!(class.attribute("SourceFile").unwrap().info.len(), 2);
+
+    let names: Vec<_> = class
+        .fields
+        .iter()
+        .map(|field| field.name.as_str())
+        .collect();
+    assert_eq!(names, ["mString", "mInteger"]);
+    let names: Vec<_> = class.methods.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "<init>",
+            "getString",
+            "getInteger",
+            "getName",
+            "getSize",
+            "getLEETness"
+        ]
+    );
+
+    let get_string = class.method("getString", "()Ljava/lang/String;").unwrap();
+    assert_eq!(get_string.access_flags, MethodAccessFlags::PUBLIC);
+    assert!(get_string.parsed_descriptor.parameter_types.is_empty());
+    let code = get_string.code.as_ref().unwrap();
+    assert!(!code.code.is_empty());
+    assert!(code.exception_table.is_empty());
+    assert!(code
+        .attributes
+        .iter()
+        .any(|attr| attr.name == "LineNumberTable"));
+    assert_eq!(class.methods_named("<init>").count(), 1);
+}
+
+#[test]
+fn test_resolved_constant_values() {
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/Constants.class");
+    let class = resolve(class_data);
+
+    let value = |name: &str| {
+        class
+            .fields
+            .iter()
+            .find(|field| field.name == name)
+            .unwrap()
+            .constant_value
+            .clone()
+    };
+    assert_eq!(value("ANSWER"), Some(ConstantValue::Integer(42)));
+    assert_eq!(value("BIG"), Some(ConstantValue::Long(1 << 40)));
+    assert_eq!(value("RATE"), Some(ConstantValue::Double(2.5)));
+    assert_eq!(
+        value("GREETING"),
+        Some(ConstantValue::String("Hello".to_owned()))
+    );
+    assert_eq!(value("COMPUTED"), None);
+    assert_eq!(value("counter"), None);
+
+    let answer = class.field("ANSWER", "I").unwrap();
+    assert_eq!(
+        answer.parsed_descriptor,
+        DescriptorType::Basic(DescriptorTypeBasic::Int)
+    );
+}
+
+#[test]
+fn test_resolved_borrowed() {
+    let class_data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let (_, c) = class_parser(ParseData::new(class_data)).unwrap();
+    let class = ResolvedClass::from_class_file_with(&c, class_data, Borrowed).unwrap();
+
+    // The names are all plain ASCII, so none of them are copied
+    assert!(matches!(class.name, Cow::Borrowed(_)));
+    assert!(class
+        .methods
+        .iter()
+        .all(|method| matches!(method.name, Cow::Borrowed(_))));
+    assert!(class.method("getString", "()Ljava/lang/String;").is_some());
+
+    let owned = resolve(class_data);
+    assert_eq!(class.name, owned.name);
+    assert_eq!(class.methods.len(), owned.methods.len());
+}
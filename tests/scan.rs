@@ -0,0 +1,89 @@
+extern crate classfile_parser;
+
+use classfile_parser::scan::{self, class_layout};
+use classfile_parser::{ClassFileOpt, ParseError, ParseOptions};
+
+#[test]
+fn test_class_layout() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let c = ClassFileOpt::parse(data, &ParseOptions::default()).unwrap();
+    let layout = class_layout(data).unwrap();
+
+    assert_eq!(layout.constant_pool.start, 10);
+    assert_eq!(layout.constant_pool.count, c.const_pool_size);
+    assert_eq!(layout.interfaces.count, c.interfaces_count);
+    assert_eq!(layout.fields.start, c.fields.start_pos());
+    assert_eq!(layout.fields.count, c.fields.len());
+    assert_eq!(layout.methods.start, c.methods.start_pos());
+    assert_eq!(layout.methods.count, c.methods.len());
+    assert_eq!(layout.attributes.start, c.attributes.start_pos());
+    assert_eq!(layout.end(), data.len());
+
+    let flags = u16::from_be_bytes([data[layout.access_flags], data[layout.access_flags + 1]]);
+    assert_eq!(flags, c.access_flags.bits());
+}
+
+#[test]
+fn test_skip_one_at_a_time() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class");
+    let layout = class_layout(data).unwrap();
+
+    let mut offset = layout.methods.start;
+    for _ in 0..layout.methods.count {
+        offset = scan::skip_method(data, offset).unwrap();
+    }
+    assert_eq!(offset, layout.methods.end);
+    assert_eq!(
+        scan::skip_methods(data, layout.methods.start, layout.methods.count).unwrap(),
+        layout.methods.end
+    );
+
+    let mut offset = layout.attributes.start;
+    for _ in 0..layout.attributes.count {
+        offset = scan::skip_attribute(data, offset).unwrap();
+    }
+    assert_eq!(offset, data.len());
+}
+
+#[test]
+fn test_scan_errors() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    assert_eq!(class_layout(&data[4..]), Err(ParseError::BadMagic));
+    assert!(matches!(
+        class_layout(&data[..data.len() - 1]),
+        Err(ParseError::Malformed { .. })
+    ));
+    assert!(scan::skip_field(data, data.len() + 1).is_err());
+}
+
+#[test]
+fn test_item_at() {
+    use classfile_parser::error::{ItemKind, ItemLocation};
+    use classfile_parser::parser::ParseData;
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/Constants.class");
+    let layout = class_layout(data).unwrap();
+    let item = |kind, index, start| Some(ItemLocation { kind, index, start });
+
+    // #34 is a long, so it takes up the slot of #35 as well
+    let pool = ParseData::from_pos(data, layout.constant_pool.start);
+    let (rest, _) = scan::skip_constant_pool_parser(pool, 35).unwrap();
+    let start = rest.pos();
+    assert_eq!(
+        scan::item_at(data, start),
+        item(ItemKind::Constant, 36, start)
+    );
+    assert_eq!(
+        scan::item_at(data, start - 1),
+        item(ItemKind::Constant, 34, start - 9)
+    );
+
+    assert_eq!(
+        scan::item_at(data, layout.methods.start + 3),
+        item(ItemKind::Method, 0, layout.methods.start)
+    );
+    // The counts and the class file header are not inside any item
+    assert_eq!(scan::item_at(data, 9), None);
+    assert_eq!(scan::item_at(data, layout.access_flags), None);
+    assert_eq!(scan::item_at(data, data.len()), None);
+}
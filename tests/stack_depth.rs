@@ -0,0 +1,103 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{ExceptionEntry, InstructionIndex};
+use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+use classfile_parser::stack_depth::{simulate_max_stack, StackDepthError, StackDepthReport};
+use classfile_parser::{ClassFile, ParseOptions};
+
+#[test]
+fn test_compiled_classes_match() {
+    let classes: [&[u8]; 5] = [
+        include_bytes!("../java-assets/compiled-classes/BasicClass.class"),
+        include_bytes!("../java-assets/compiled-classes/Instructions.class"),
+        include_bytes!("../java-assets/compiled-classes/BootstrapMethods.class"),
+        include_bytes!("../java-assets/compiled-classes/Factorial.class"),
+        include_bytes!("../java-assets/compiled-classes/SwitchMap.class"),
+    ];
+    for data in classes {
+        let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+        let reports = c.stack_depth_reports(data).unwrap();
+        assert!(!reports.is_empty());
+        for report in reports {
+            assert!(report.matches(), "{:?}", report);
+            assert!(report.code_length > 0);
+        }
+    }
+}
+
+#[test]
+fn test_simulate_max_stack() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let simulate = |code: &[u8], handlers: &[u16]| {
+        let table: Vec<_> = handlers
+            .iter()
+            .map(|&handler_pc| ExceptionEntry {
+                start_pc: InstructionIndex(0),
+                end_pc: InstructionIndex(1),
+                handler_pc: InstructionIndex(handler_pc),
+                catch_type: ConstantPoolIndexRaw::new(0),
+            })
+            .collect();
+        simulate_max_stack(code, &table, &c.const_pool, data)
+    };
+
+    // lconst_0, iconst_1, pop, pop2, return
+    assert_eq!(simulate(&[0x09, 0x04, 0x57, 0x58, 0xB1], &[]), Ok(3));
+    // nop, return, athrow, where the athrow is a handler
+    assert_eq!(simulate(&[0x00, 0xB1, 0xBF], &[2]), Ok(1));
+    // The return is reached from the nop, and as a handler with the exception
+    assert_eq!(
+        simulate(&[0x00, 0xB1, 0xBF], &[1]),
+        Err(StackDepthError::InconsistentDepth {
+            offset: 1,
+            first: 1,
+            second: 0
+        })
+    );
+    assert_eq!(
+        simulate(&[0x00, 0xB1], &[5]),
+        Err(StackDepthError::BadHandler { handler_pc: 5 })
+    );
+    // pop, return
+    assert_eq!(
+        simulate(&[0x57, 0xB1], &[]),
+        Err(StackDepthError::Underflow { offset: 0 })
+    );
+    // iconst_0
+    assert_eq!(
+        simulate(&[0x03], &[]),
+        Err(StackDepthError::FallsOffEnd { offset: 0 })
+    );
+    // iconst_0, ifeq +4, iconst_0, return: the return is reached with both 0 and 1
+    assert_eq!(
+        simulate(&[0x03, 0x99, 0x00, 0x04, 0x03, 0xB1], &[]),
+        Err(StackDepthError::InconsistentDepth {
+            offset: 5,
+            first: 0,
+            second: 1
+        })
+    );
+    // goto +2, in the middle of itself
+    assert_eq!(
+        simulate(&[0xA7, 0x00, 0x02], &[]),
+        Err(StackDepthError::BadTarget {
+            offset: 0,
+            target: 2
+        })
+    );
+    // jsr +4, return, astore_1, ret 1: the subroutine returns without its return address
+    assert_eq!(
+        simulate(&[0xA8, 0x00, 0x04, 0xB1, 0x4C, 0xA9, 0x01], &[]),
+        Ok(1)
+    );
+
+    let report = StackDepthReport {
+        method: 0,
+        code_length: 1,
+        max_stack: 1,
+        simulated: Ok(2),
+    };
+    assert!(!report.matches());
+    assert!(report.exceeds_max_stack());
+}
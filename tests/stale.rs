@@ -0,0 +1,98 @@
+extern crate classfile_parser;
+
+use classfile_parser::attribute_info::{AttributeOwner, CodeAttribute, HasAttributes};
+use classfile_parser::stale::StaleRanges;
+use classfile_parser::{ClassFile, LoadError, ParseOptions};
+
+#[test]
+fn test_stale_ranges() {
+    let mut stale = StaleRanges::new();
+    assert!(stale.is_empty());
+    stale.mark(10..20);
+    stale.mark(30..40);
+    stale.mark(5..5);
+    assert_eq!(stale.iter().count(), 2);
+    assert!(stale.is_stale(&(15..16)));
+    assert!(stale.is_stale(&(0..11)));
+    assert!(stale.is_stale(&(19..31)));
+    assert!(!stale.is_stale(&(20..30)));
+    assert!(!stale.is_stale(&(40..50)));
+
+    // Touching and overlapping ranges are merged
+    stale.mark(20..25);
+    stale.mark(24..30);
+    assert_eq!(stale.iter().cloned().collect::<Vec<_>>(), vec![10..40]);
+    stale.mark(0..50);
+    assert_eq!(stale.iter().cloned().collect::<Vec<_>>(), vec![0..50]);
+
+    stale.clear();
+    assert!(!stale.is_stale(&(0..50)));
+}
+
+#[test]
+fn test_edits_mark_stale() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+    assert!(c.stale_ranges.is_empty());
+
+    let old = c.methods[0]
+        .find_attribute::<CodeAttribute>(&c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    assert!(c.read_range(&data, old.code.clone()).is_ok());
+    let source = c.attributes[0].info.clone();
+
+    let removed = c
+        .remove_attribute(&mut data, AttributeOwner::Code(0), "LineNumberTable", false)
+        .unwrap();
+    assert_eq!(removed, 1);
+    c.remove_attribute(&mut data, AttributeOwner::Class, "SourceFile", false)
+        .unwrap();
+
+    // The old code is still in the data, but is no longer the method's code
+    assert!(c.is_stale(&old.code));
+    assert!(c.is_stale(&source));
+    assert!(matches!(
+        c.read_range(&data, old.code.clone()),
+        Err(LoadError::Stale { .. })
+    ));
+    let new = c.methods[0]
+        .find_attribute::<CodeAttribute>(&c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    assert!(!c.is_stale(&new.code));
+    assert_eq!(c.read_range(&data, new.code).unwrap(), &original[old.code]);
+    // The other methods are untouched
+    let other = c.methods[1]
+        .find_attribute::<CodeAttribute>(&c.const_pool, &data)
+        .unwrap()
+        .unwrap();
+    assert!(!c.is_stale(&other.code));
+}
+
+#[test]
+fn test_member_edits_mark_stale() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+
+    // Editing a member directly records its old info in the class' stale ranges too
+    let code = c.methods[0].attributes[0].info.clone();
+    let removed = c.methods[0].remove_attribute(&c.const_pool, &data, "Code", &mut c.stale_ranges);
+    assert_eq!(removed, 1);
+    assert!(c.is_stale(&code));
+
+    let source = c.attributes[0].info.clone();
+    c.attributes[0]
+        .set_info(&mut data, &[0, 1], &mut c.stale_ranges)
+        .unwrap();
+    assert!(c.is_stale(&source));
+    assert!(!c.is_stale(&c.attributes[0].info));
+
+    let end = data.len() + 1;
+    assert!(matches!(
+        c.read_range(&data, data.len()..end),
+        Err(LoadError::OutOfRange { index, len }) if index == end && len == data.len()
+    ));
+}
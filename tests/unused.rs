@@ -0,0 +1,58 @@
+extern crate classfile_parser;
+
+use classfile_parser::builder::ClassFileBuilder;
+use classfile_parser::constant_info::{ConstantInfo, MethodHandleConstant};
+use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+use classfile_parser::unused::UnusedMembers;
+use classfile_parser::{ClassFile, ParseOptions};
+
+fn member_names(c: &ClassFile, data: &[u8], fields: &[usize], methods: &[usize]) -> Vec<String> {
+    let pool = &c.const_pool;
+    let fields = fields.iter().map(|&i| c.fields[i].name_index);
+    let methods = methods.iter().map(|&i| c.methods[i].name_index);
+    fields
+        .chain(methods)
+        .map(|index| pool.get_t(index).unwrap().as_text(data).into_owned())
+        .collect()
+}
+
+#[test]
+fn test_unused_private_members() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/PrivateMembers.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let unused = c.unused_private_members(data).unwrap();
+    // The lambda body is only referred to by its bootstrap method, and serialVersionUID is only
+    // used by serialization. The private constructor is never reported
+    assert_eq!(
+        member_names(&c, data, &unused.fields, &unused.methods),
+        ["unused", "neverCalled"]
+    );
+
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    let unused = c.unused_private_members(data).unwrap();
+    assert!(unused.fields.is_empty() && unused.methods.is_empty());
+}
+
+#[test]
+fn test_unused_private_members_method_handle_cycle() {
+    // A method handle which refers to itself is malformed, but must not be followed forever
+    let mut builder = ClassFileBuilder::new("example/Cycle", Some("java/lang/Object")).unwrap();
+    let index = builder.constants().pool().len() + 1;
+    builder
+        .constants()
+        .add(ConstantInfo::MethodHandle(MethodHandleConstant {
+            reference_kind: 6,
+            reference_index: ConstantPoolIndexRaw::new(index),
+        }))
+        .unwrap();
+    let [high, low] = index.to_be_bytes();
+    builder
+        .attribute("BootstrapMethods", &[0, 1, high, low, 0, 0])
+        .unwrap();
+    let (c, data) = builder.build().unwrap();
+    assert_eq!(
+        c.unused_private_members(&data).unwrap(),
+        UnusedMembers::default()
+    );
+}
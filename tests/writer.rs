@@ -0,0 +1,114 @@
+extern crate classfile_parser;
+
+use std::fs;
+
+use classfile_parser::attribute_info::{AttributeOwner, HasAttributes, SourceFileAttribute};
+use classfile_parser::constant_info::{ConstantInfo, IntegerConstant};
+use classfile_parser::constant_pool::ConstantPoolIndexRaw;
+use classfile_parser::method_info::MethodAccessFlags;
+use classfile_parser::remap::IndexRemap;
+use classfile_parser::writer::{WriteError, WriteOptions};
+use classfile_parser::{ClassFile, ParseOptions};
+
+/// Every class file that parses, along with its data
+fn classes() -> Vec<(ClassFile, Vec<u8>)> {
+    fs::read_dir("java-assets/compiled-classes")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "class"))
+        .map(|path| fs::read(path).unwrap())
+        .filter_map(|data| {
+            let c = ClassFile::parse(&data, &ParseOptions::default()).ok()?;
+            Some((c, data))
+        })
+        .collect()
+}
+
+#[test]
+fn test_write_round_trip() {
+    for (c, data) in classes() {
+        assert_eq!(c.to_bytes(&data).unwrap(), data);
+
+        let mut written = Vec::new();
+        c.write_to(&data, &mut written).unwrap();
+        assert_eq!(written, data);
+    }
+}
+
+#[test]
+fn test_write_edited() {
+    let original: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut data = original.to_vec();
+    let mut c = ClassFile::parse(&data, &ParseOptions::default()).unwrap();
+
+    // Edits which append to the data, change flags, and grow the pool are all written out
+    let removed = c
+        .remove_attribute(&mut data, AttributeOwner::Class, "SourceFile", false)
+        .unwrap();
+    assert_eq!(removed, 1);
+    c.methods[0].access_flags |= MethodAccessFlags::SYNTHETIC;
+    let index = c
+        .const_pool
+        .push(ConstantInfo::Integer(IntegerConstant { value: 42 }))
+        .unwrap();
+
+    let written = c.to_bytes(&data).unwrap();
+    assert!(written.len() < original.len());
+    let c = ClassFile::parse(&written, &ParseOptions::strict()).unwrap();
+    let source: Option<SourceFileAttribute> = c.find_attribute(&c.const_pool, &written).unwrap();
+    assert!(source.is_none());
+    assert!(c.methods[0]
+        .access_flags
+        .contains(MethodAccessFlags::SYNTHETIC));
+    let value: &IntegerConstant = c
+        .const_pool
+        .get_t(ConstantPoolIndexRaw::new(index.0))
+        .unwrap();
+    assert_eq!(value.value, 42);
+}
+
+#[test]
+fn test_write_out_of_bounds() {
+    let data: &[u8] = include_bytes!("../java-assets/compiled-classes/BasicClass.class");
+    let mut c = ClassFile::parse(data, &ParseOptions::default()).unwrap();
+    c.attributes[0].info = data.len()..data.len() + 4;
+    assert!(matches!(c.to_bytes(data), Err(WriteError::OutOfBounds)));
+}
+
+#[test]
+fn test_write_deterministic() {
+    let options = WriteOptions::deterministic();
+    for (c, data) in classes() {
+        let sorted = c.to_bytes_with(&data, &options).unwrap();
+        // Nothing is sorted without asking
+        assert_eq!(
+            c.to_bytes_with(&data, &WriteOptions::default()).unwrap(),
+            data
+        );
+
+        // The same class built in another order is written the same
+        let mut shuffled = c.clone();
+        let mut shuffled_data = data.clone();
+        let order: Vec<_> = c.const_pool.indices().collect();
+        let remap = IndexRemap::from_order(&c.const_pool, order.into_iter().rev()).unwrap();
+        remap.fit_ldc(&mut shuffled, &mut shuffled_data).unwrap();
+        remap.apply(&mut shuffled, &mut shuffled_data).unwrap();
+        shuffled.fields.reverse();
+        shuffled.methods.reverse();
+        shuffled.attributes.reverse();
+        for method in shuffled.methods.iter_mut() {
+            method.attributes.reverse();
+        }
+        assert_ne!(shuffled.to_bytes(&shuffled_data).unwrap(), data);
+        assert_eq!(
+            shuffled.to_bytes_with(&shuffled_data, &options).unwrap(),
+            sorted
+        );
+
+        // Sorting is stable once sorted
+        let again = ClassFile::parse(&sorted, &ParseOptions::strict()).unwrap();
+        assert_eq!(again.methods.len(), c.methods.len());
+        assert_eq!(again.to_bytes_with(&sorted, &options).unwrap(), sorted);
+        assert_eq!(again.to_bytes(&sorted).unwrap(), sorted);
+    }
+}